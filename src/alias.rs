@@ -0,0 +1,178 @@
+//! Canonical names of counterparties, read from an alias file, so that
+//! reports on exported entries are not split across the variants of a
+//! merchant's name such as `AMZN Mktp CH*2F4` and `AMAZON EU SARL`.
+//!
+//! Each line maps names to the canonical name before `=`. Names are strings
+//! compared ignoring case and runs of whitespace, where `*` stands for any
+//! text, or regular expressions after `matches`, see [`crate::regex`]. The
+//! first line with a matching name wins; names no line matches are kept.
+//! Canonical names are the payees of exports, see [`Transaction::payee`].
+//!
+//! ```text
+//! Amazon = "AMZN Mktp CH*", "AMAZON EU SARL"
+//! Coop = matches "(?i)^coop(-| |$)"
+//! ```
+
+use crate::model::{Statement, Transaction};
+use crate::regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alias {
+    canonical: String,
+    names: Vec<Regex>,
+}
+
+/// Mappings of an alias file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aliases {
+    aliases: Vec<Alias>,
+}
+
+/// An alias file that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasError(String);
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for AliasError {}
+
+impl FromStr for Aliases {
+    type Err = AliasError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut aliases = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let alias = parse_alias(line).map_err(|message| {
+                AliasError(format!("Line {} of aliases: {}", index + 1, message))
+            })?;
+            aliases.push(alias);
+        }
+        Ok(Aliases { aliases })
+    }
+}
+
+impl Aliases {
+    /// Whether there are no mappings
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Canonical name of a counterparty, if a mapping matches it
+    pub fn canonical(&self, name: &str) -> Option<&str> {
+        let normalized = normalize(name);
+        if normalized.is_empty() {
+            return None;
+        }
+        self.aliases
+            .iter()
+            .find(|alias| alias.names.iter().any(|regex| regex.is_match(&normalized)))
+            .map(|alias| alias.canonical.as_str())
+    }
+
+    /// Set the payee of an entry to the canonical name of its counterparty,
+    /// or of its payee where one was set, keeping the names of the bank
+    pub fn apply_transaction(&self, transaction: &mut Transaction) {
+        if let Some(canonical) = self.canonical(transaction.payee()) {
+            transaction.payee = canonical.to_string();
+        }
+    }
+
+    /// Set the payees of the entries of a statement to the canonical names of
+    /// their counterparties
+    pub fn apply(&self, statement: &mut Statement) {
+        for transaction in &mut statement.transactions {
+            self.apply_transaction(transaction);
+        }
+    }
+}
+
+// Runs of whitespace as one space, without any around the name
+fn normalize(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn parse_alias(line: &str) -> Result<Alias, String> {
+    let (canonical, mut rest) = line
+        .split_once('=')
+        .ok_or("expected a canonical name and = at the start")?;
+    let canonical = normalize(canonical);
+    if canonical.is_empty() {
+        return Err("expected a canonical name before =".to_string());
+    }
+    let mut names = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let regex = rest
+            .strip_prefix("matches")
+            .filter(|after| after.starts_with([' ', '\t', '"']));
+        let is_regex = regex.is_some();
+        if let Some(after) = regex {
+            rest = after.trim_start();
+        }
+        let (text, after) = string(rest)?;
+        names.push(if is_regex {
+            text.parse()?
+        } else {
+            glob(&normalize(&text))
+        });
+        rest = after.trim_start();
+        match rest.chars().next() {
+            None | Some('#') => break,
+            Some(',') => rest = &rest[1..],
+            Some(c) => return Err(format!("expected , instead of {:?}", c)),
+        }
+    }
+    Ok(Alias { canonical, names })
+}
+
+// A string in double quotes at the start of `text` and the text after it.
+// Only \" is an escape, for a quote, so that regular expressions keep their
+// backslashes.
+fn string(text: &str) -> Result<(String, &str), String> {
+    let Some(quoted) = text.strip_prefix('"') else {
+        return Err(match text.chars().next() {
+            Some(c) => format!("expected a name in double quotes instead of {:?}", c),
+            None => "expected a name in double quotes at the end".to_string(),
+        });
+    };
+    let mut value = String::new();
+    let mut chars = quoted.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &quoted[index + 1..])),
+            '\\' if chars.peek().is_some_and(|(_, c)| *c == '"') => {
+                chars.next();
+                value.push('"');
+            }
+            c => value.push(c),
+        }
+    }
+    Err("unclosed string".to_string())
+}
+
+// Regular expression matching a whole name ignoring case, with * for any text
+fn glob(name: &str) -> Regex {
+    let mut pattern = "(?i)^".to_string();
+    for c in name.chars() {
+        if c == '*' {
+            pattern.push_str(".*");
+        } else {
+            if !c.is_alphanumeric() && !c.is_whitespace() {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+    }
+    pattern.push('$');
+    pattern.parse().expect("escaped names are valid patterns")
+}
@@ -0,0 +1,86 @@
+//! Moving converted inputs to an archive folder, so that the pickup folder
+//! only holds files still to be converted, and inputs that could not be
+//! converted to a quarantine folder.
+
+use chrono::NaiveDateTime;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Format of the timestamp added to archived file names
+pub const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+/// Name of a file in the archive, with the timestamp if any added before
+/// the extension: `statement.xml` becomes `statement_20250620T101500.xml`
+pub fn archive_name(path: &Path, timestamp: Option<NaiveDateTime>) -> Option<OsString> {
+    let file_name = path.file_name()?;
+    let Some(timestamp) = timestamp else {
+        return Some(file_name.to_os_string());
+    };
+    let mut name = path.file_stem()?.to_os_string();
+    name.push(format!("_{}", timestamp.format(TIMESTAMP_FORMAT)));
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    Some(name)
+}
+
+/// Move a file into `dir`, creating the folder if needed, and return its new
+/// path. A file of the same name in the archive is never overwritten.
+pub fn archive_file(
+    path: &Path,
+    dir: &Path,
+    timestamp: Option<NaiveDateTime>,
+) -> io::Result<PathBuf> {
+    let name = archive_name(path, timestamp).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a file", path.display()),
+        )
+    })?;
+    fs::create_dir_all(dir)?;
+    let target = dir.join(name);
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    match fs::rename(path, &target) {
+        Ok(()) => Ok(target),
+        // The archive may be on another file system, such as a network share
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = fs::copy(path, &target) {
+                let _ = fs::remove_file(&target);
+                return Err(e);
+            }
+            fs::remove_file(path)?;
+            Ok(target)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Move a file that could not be converted into `dir` with the time added to
+/// its name, and write what went wrong next to it, e.g.
+/// `statement_20250620T101500.xml.error.txt`. Returns the new path of the
+/// file.
+pub fn quarantine_file(
+    path: &Path,
+    dir: &Path,
+    time: NaiveDateTime,
+    errors: &str,
+) -> io::Result<PathBuf> {
+    let quarantined = archive_file(path, dir, Some(time))?;
+    fs::write(error_path(&quarantined), errors)?;
+    Ok(quarantined)
+}
+
+/// Path of the file with the errors of a quarantined file
+pub fn error_path(path: &Path) -> PathBuf {
+    let mut error_path = path.as_os_str().to_owned();
+    error_path.push(".error.txt");
+    PathBuf::from(error_path)
+}
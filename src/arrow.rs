@@ -0,0 +1,437 @@
+//! Entries of a statement as an Apache Arrow IPC stream, so that analytics
+//! tools such as Polars, DataFusion or pandas load them into columns directly
+//! instead of parsing CSV.
+//!
+//! The stream holds a schema and one record batch with a row per entry. The
+//! columns are those of an entry as JSON, see
+//! [`entry_value`](crate::extract::entry_value): text as `Utf8`, dates as
+//! `Date32` and the signed amount as `Decimal128` with the largest number of
+//! decimal places of the statement. Optional values are null where missing.
+//!
+//! The IPC metadata are FlatBuffers, which are written here directly as
+//! Arrow's schema needs only a few tables.
+
+use crate::model::{DateOrDateTime, Statement};
+use crate::reference::{ReferenceStyle, generate_transaction_reference};
+use chrono::{Datelike, NaiveDate};
+use std::cmp::Reverse;
+use std::io::{self, Write};
+
+// MetadataVersion V5 of Schema.fbs
+const METADATA_VERSION: i16 = 4;
+
+// Days from 0001-01-01 to the Unix epoch, in which Date32 counts
+const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Write the entries of a statement as an Arrow IPC stream
+pub fn write_entries<W: Write>(
+    mut output: W,
+    statement: &Statement,
+    style: ReferenceStyle,
+) -> io::Result<()> {
+    let columns = columns(statement, style);
+
+    let fields = columns
+        .iter()
+        .map(|(name, column)| {
+            let (type_id, type_fields) = column.arrow_type();
+            vec![
+                Fb::Text(name.to_string()),
+                Fb::U8(1),
+                Fb::U8(type_id),
+                Fb::Table(type_fields),
+                Fb::Absent,
+                Fb::Tables(Vec::new()),
+            ]
+        })
+        .collect();
+    let schema = vec![Fb::Absent, Fb::Tables(fields)];
+    write_message(&mut output, 1, schema, &[])?;
+
+    let rows = statement.transactions.len();
+    let mut nodes = Vec::new();
+    let mut buffers = Vec::new();
+    let mut body = Vec::new();
+    for (_, column) in &columns {
+        nodes.push([rows as i64, column.null_count() as i64]);
+        column.write_buffers(&mut body, &mut buffers);
+    }
+    let batch = vec![
+        Fb::I64(rows as i64),
+        Fb::Structs(nodes),
+        Fb::Structs(buffers),
+    ];
+    write_message(&mut output, 3, batch, &body)?;
+
+    // End of stream
+    output.write_all(&[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0])?;
+    output.flush()
+}
+
+enum Column {
+    Text(Vec<Option<String>>),
+    Date(Vec<Option<NaiveDate>>),
+    // Mantissas of amounts with the same scale
+    Decimal(Vec<i128>, u32),
+}
+
+fn columns(statement: &Statement, style: ReferenceStyle) -> Vec<(&'static str, Column)> {
+    let transactions = &statement.transactions;
+    let text = |value: &dyn Fn(usize) -> String| {
+        Column::Text(
+            (0..transactions.len())
+                .map(|index| Some(value(index)).filter(|text| !text.is_empty()))
+                .collect(),
+        )
+    };
+    let date = |value: &dyn Fn(usize) -> Option<NaiveDate>| {
+        Column::Date((0..transactions.len()).map(value).collect())
+    };
+    let amounts: Vec<_> = transactions
+        .iter()
+        .map(|transaction| transaction.signed_amount())
+        .collect();
+    let scale = amounts
+        .iter()
+        .map(|amount| amount.scale())
+        .max()
+        .unwrap_or(2);
+
+    vec![
+        ("iban", text(&|_| statement.iban.clone())),
+        ("statement", text(&|_| statement.id.clone())),
+        (
+            "account_servicer_ref",
+            text(&|index| transactions[index].account_servicer_ref.clone()),
+        ),
+        (
+            "reference",
+            text(&|index| generate_transaction_reference(statement, &transactions[index], style)),
+        ),
+        (
+            "end_to_end_id",
+            text(&|index| transactions[index].end_to_end_id.clone()),
+        ),
+        (
+            "amount",
+            Column::Decimal(
+                amounts
+                    .iter()
+                    .map(|amount| {
+                        amount
+                            .with_scale(scale)
+                            .map_or(0, |amount| amount.mantissa())
+                    })
+                    .collect(),
+                scale,
+            ),
+        ),
+        (
+            "currency",
+            text(&|index| transactions[index].amount.currency.clone()),
+        ),
+        (
+            "status",
+            text(&|index| transactions[index].status.as_str().to_string()),
+        ),
+        (
+            "booking_date",
+            date(&|index| {
+                transactions[index]
+                    .booking_date
+                    .as_ref()
+                    .map(DateOrDateTime::date)
+            }),
+        ),
+        (
+            "value_date",
+            date(&|index| {
+                transactions[index]
+                    .value_date
+                    .as_ref()
+                    .map(DateOrDateTime::date)
+            }),
+        ),
+        (
+            "bank_transaction_code",
+            text(&|index| transactions[index].bank_tx_code.clone()),
+        ),
+        (
+            "creditor_reference",
+            text(&|index| {
+                transactions[index]
+                    .creditor_reference
+                    .as_ref()
+                    .map(|reference| reference.reference.clone())
+                    .unwrap_or_default()
+            }),
+        ),
+        (
+            "debtor",
+            text(&|index| transactions[index].debtor_name.clone()),
+        ),
+        (
+            "remittance_info",
+            text(&|index| transactions[index].remittance_info.join("\n")),
+        ),
+        (
+            "additional_info",
+            text(&|index| transactions[index].additional_info.clone()),
+        ),
+        (
+            "category",
+            text(&|index| transactions[index].category.clone()),
+        ),
+        (
+            "gl_account",
+            text(&|index| transactions[index].gl_account.clone()),
+        ),
+        (
+            "payee",
+            text(&|index| transactions[index].payee().to_string()),
+        ),
+    ]
+}
+
+impl Column {
+    // Member of the Type union and fields of its table
+    fn arrow_type(&self) -> (u8, Vec<Fb>) {
+        match self {
+            Column::Text(_) => (5, Vec::new()),
+            // Unit DAY, which is not the default
+            Column::Date(_) => (8, vec![Fb::I16(0)]),
+            Column::Decimal(_, scale) => {
+                (7, vec![Fb::I32(38), Fb::I32(*scale as i32), Fb::I32(128)])
+            }
+        }
+    }
+
+    fn null_count(&self) -> usize {
+        match self {
+            Column::Text(values) => values.iter().filter(|value| value.is_none()).count(),
+            Column::Date(values) => values.iter().filter(|value| value.is_none()).count(),
+            Column::Decimal(..) => 0,
+        }
+    }
+
+    // Append the buffers of the column to a record batch body
+    fn write_buffers(&self, body: &mut Vec<u8>, buffers: &mut Vec<[i64; 2]>) {
+        let mut add = |bytes: &[u8]| {
+            buffers.push([body.len() as i64, bytes.len() as i64]);
+            body.extend_from_slice(bytes);
+            body.resize(body.len().next_multiple_of(8), 0);
+        };
+        match self {
+            Column::Text(values) => {
+                add(&validity(values));
+                let mut offsets = vec![0u8; 4];
+                let mut data = Vec::new();
+                for value in values {
+                    data.extend_from_slice(value.as_deref().unwrap_or_default().as_bytes());
+                    offsets.extend_from_slice(&(data.len() as i32).to_le_bytes());
+                }
+                add(&offsets);
+                add(&data);
+            }
+            Column::Date(values) => {
+                add(&validity(values));
+                let days: Vec<u8> = values
+                    .iter()
+                    .flat_map(|date| {
+                        let days =
+                            date.map_or(0, |date| date.num_days_from_ce() - EPOCH_DAYS_FROM_CE);
+                        days.to_le_bytes()
+                    })
+                    .collect();
+                add(&days);
+            }
+            Column::Decimal(values, _) => {
+                add(&[]);
+                let values: Vec<u8> = values
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes())
+                    .collect();
+                add(&values);
+            }
+        }
+    }
+}
+
+// Bitmap of the values that are not null, empty if all are
+fn validity<T>(values: &[Option<T>]) -> Vec<u8> {
+    if values.iter().all(Option::is_some) {
+        return Vec::new();
+    }
+    let mut bitmap = vec![0u8; values.len().div_ceil(8)];
+    for (index, value) in values.iter().enumerate() {
+        if value.is_some() {
+            bitmap[index / 8] |= 1 << (index % 8);
+        }
+    }
+    bitmap
+}
+
+// An encapsulated IPC message with a Schema (1) or RecordBatch (3) header
+fn write_message(
+    output: &mut impl Write,
+    header_type: u8,
+    header: Vec<Fb>,
+    body: &[u8],
+) -> io::Result<()> {
+    let message = FlatBuffer::finish(vec![
+        Fb::I16(METADATA_VERSION),
+        Fb::U8(header_type),
+        Fb::Table(header),
+        Fb::I64(body.len() as i64),
+    ]);
+    output.write_all(&[0xFF, 0xFF, 0xFF, 0xFF])?;
+    output.write_all(&(message.len() as i32).to_le_bytes())?;
+    output.write_all(&message)?;
+    output.write_all(body)
+}
+
+// A field of a FlatBuffers table, by field id
+enum Fb {
+    Absent,
+    U8(u8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Table(Vec<Fb>),
+    Text(String),
+    Tables(Vec<Vec<Fb>>),
+    // Structs of two longs, such as FieldNode and Buffer
+    Structs(Vec<[i64; 2]>),
+}
+
+impl Fb {
+    // Size in the table, where everything but scalars is an offset
+    fn inline_size(&self) -> usize {
+        match self {
+            Fb::Absent => 0,
+            Fb::U8(_) => 1,
+            Fb::I16(_) => 2,
+            Fb::I64(_) => 8,
+            Fb::I32(_) | Fb::Table(_) | Fb::Text(_) | Fb::Tables(_) | Fb::Structs(_) => 4,
+        }
+    }
+}
+
+// FlatBuffers written front to back: each table follows its vtable, and
+// what a table refers to follows the table, so that offsets point forward
+// as the format requires
+struct FlatBuffer {
+    bytes: Vec<u8>,
+}
+
+impl FlatBuffer {
+    // A buffer with `root` as root table, padded to 8 bytes
+    fn finish(root: Vec<Fb>) -> Vec<u8> {
+        let mut buffer = FlatBuffer { bytes: vec![0; 4] };
+        let table = buffer.table(&root);
+        buffer.patch(0, table);
+        buffer.pad(8, 0);
+        buffer.bytes
+    }
+
+    // Pad until the position plus `offset` is a multiple of `alignment`
+    fn pad(&mut self, alignment: usize, offset: usize) {
+        while !(self.bytes.len() + offset).is_multiple_of(alignment) {
+            self.bytes.push(0);
+        }
+    }
+
+    fn patch(&mut self, at: usize, target: usize) {
+        let offset = (target - at) as u32;
+        self.bytes[at..at + 4].copy_from_slice(&offset.to_le_bytes());
+    }
+
+    // Write a table and what it refers to, returning its position
+    fn table(&mut self, fields: &[Fb]) -> usize {
+        // Larger fields first, so that each is aligned when the table starts
+        // 4 bytes before a multiple of 8
+        let mut order: Vec<usize> = (0..fields.len())
+            .filter(|&id| fields[id].inline_size() > 0)
+            .collect();
+        order.sort_by_key(|&id| Reverse(fields[id].inline_size()));
+        let mut offsets = vec![0u16; fields.len()];
+        let mut size = 4;
+        for &id in &order {
+            offsets[id] = size as u16;
+            size += fields[id].inline_size();
+        }
+
+        self.pad(2, 0);
+        let vtable = self.bytes.len();
+        let vtable_size = 4 + 2 * fields.len() as u16;
+        for value in [vtable_size, size as u16].into_iter().chain(offsets) {
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        self.pad(8, 4);
+        let table = self.bytes.len();
+        self.bytes
+            .extend_from_slice(&((table - vtable) as i32).to_le_bytes());
+
+        let mut references = Vec::new();
+        for &id in &order {
+            match &fields[id] {
+                Fb::U8(value) => self.bytes.push(*value),
+                Fb::I16(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+                Fb::I32(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+                Fb::I64(value) => self.bytes.extend_from_slice(&value.to_le_bytes()),
+                field => {
+                    references.push((self.bytes.len(), field));
+                    self.bytes.extend_from_slice(&[0; 4]);
+                }
+            }
+        }
+        for (at, field) in references {
+            let target = self.reference(field);
+            self.patch(at, target);
+        }
+        table
+    }
+
+    // Write a table, string or vector, returning its position
+    fn reference(&mut self, field: &Fb) -> usize {
+        match field {
+            Fb::Table(fields) => self.table(fields),
+            Fb::Text(text) => {
+                self.pad(4, 0);
+                let position = self.bytes.len();
+                self.bytes
+                    .extend_from_slice(&(text.len() as u32).to_le_bytes());
+                self.bytes.extend_from_slice(text.as_bytes());
+                self.bytes.push(0);
+                position
+            }
+            Fb::Tables(tables) => {
+                self.pad(4, 0);
+                let position = self.bytes.len();
+                self.bytes
+                    .extend_from_slice(&(tables.len() as u32).to_le_bytes());
+                let slots = self.bytes.len();
+                self.bytes.resize(slots + 4 * tables.len(), 0);
+                for (index, fields) in tables.iter().enumerate() {
+                    let table = self.table(fields);
+                    self.patch(slots + 4 * index, table);
+                }
+                position
+            }
+            Fb::Structs(structs) => {
+                // The structs are aligned to 8 after the length
+                self.pad(8, 4);
+                let position = self.bytes.len();
+                self.bytes
+                    .extend_from_slice(&(structs.len() as u32).to_le_bytes());
+                for value in structs.iter().flatten() {
+                    self.bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                position
+            }
+            Fb::Absent | Fb::U8(_) | Fb::I16(_) | Fb::I32(_) | Fb::I64(_) => {
+                unreachable!("scalars are written in their table")
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Detection of the bank that issued a statement, from the BIC of the
+//! account servicer, the clearing number (IID) in a Swiss or Liechtenstein
+//! IBAN, or the pattern of its proprietary bank transaction codes.
+//!
+//! The bank is reported with the conversion, so that statements of an
+//! unexpected bank stand out, and is the key for bank specific handling.
+
+use crate::model::Statement;
+use std::fmt;
+
+/// A bank that can be detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bank {
+    /// Short name, such as `postfinance`
+    pub id: &'static str,
+    pub name: &'static str,
+    // Institution codes, the first four characters of the BICs of the bank
+    institution_codes: &'static [&'static str],
+    // Ranges of clearing numbers of the bank, inclusive
+    clearing_numbers: &'static [(u32, u32)],
+}
+
+/// The banks that are detected
+pub const BANKS: &[Bank] = &[
+    Bank {
+        id: "postfinance",
+        name: "PostFinance",
+        institution_codes: &["POFI"],
+        clearing_numbers: &[(9000, 9000)],
+    },
+    Bank {
+        id: "ubs",
+        name: "UBS",
+        institution_codes: &["UBSW"],
+        clearing_numbers: &[(200, 299)],
+    },
+    Bank {
+        id: "credit-suisse",
+        name: "Credit Suisse",
+        institution_codes: &["CRES"],
+        clearing_numbers: &[(4800, 4899)],
+    },
+    Bank {
+        id: "zkb",
+        name: "Zürcher Kantonalbank",
+        institution_codes: &["ZKBK"],
+        clearing_numbers: &[(700, 700)],
+    },
+    Bank {
+        id: "raiffeisen",
+        name: "Raiffeisen",
+        institution_codes: &["RAIF"],
+        clearing_numbers: &[(80000, 81999)],
+    },
+    Bank {
+        id: "wise",
+        name: "Wise",
+        institution_codes: &["TRWI"],
+        clearing_numbers: &[],
+    },
+    Bank {
+        id: "revolut",
+        name: "Revolut",
+        institution_codes: &["REVO"],
+        clearing_numbers: &[],
+    },
+];
+
+// Proprietary codes of Wise entries, the kind of entry and its id, e.g.
+// `CARD-1234567`
+const WISE_CODE_KINDS: &[&str] = &["TRANSFER", "CARD", "BALANCE", "DIRECT_DEBIT"];
+
+/// What a bank was detected from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Evidence {
+    ServicerBic(String),
+    ClearingNumber(u32),
+    ProprietaryCodes,
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Evidence::ServicerBic(bic) => write!(f, "servicer BIC {}", bic),
+            Evidence::ClearingNumber(iid) => write!(f, "clearing number {}", iid),
+            Evidence::ProprietaryCodes => f.write_str("proprietary bank transaction codes"),
+        }
+    }
+}
+
+/// The bank of a statement and what it was detected from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    pub bank: &'static Bank,
+    pub evidence: Evidence,
+}
+
+/// Detect the bank of a statement. The servicer BIC is the surest sign,
+/// then the clearing number of the account; proprietary codes are only used
+/// if neither names a known bank, and only if all entries have them.
+pub fn detect(statement: &Statement) -> Option<Detection> {
+    let bic = statement.servicer_bic.trim();
+    if let Some(institution) = bic.get(..4)
+        && let Some(bank) = BANKS
+            .iter()
+            .find(|bank| bank.institution_codes.contains(&institution))
+    {
+        return Some(Detection {
+            bank,
+            evidence: Evidence::ServicerBic(bic.to_string()),
+        });
+    }
+
+    if let Some(iid) = clearing_number(&statement.iban)
+        && let Some(bank) = BANKS.iter().find(|bank| {
+            bank.clearing_numbers
+                .iter()
+                .any(|(first, last)| (*first..=*last).contains(&iid))
+        })
+    {
+        return Some(Detection {
+            bank,
+            evidence: Evidence::ClearingNumber(iid),
+        });
+    }
+
+    let wise_codes = !statement.transactions.is_empty()
+        && statement
+            .transactions
+            .iter()
+            .all(|transaction| is_wise_code(&transaction.bank_tx_code));
+    if wise_codes && let Some(bank) = BANKS.iter().find(|bank| bank.id == "wise") {
+        return Some(Detection {
+            bank,
+            evidence: Evidence::ProprietaryCodes,
+        });
+    }
+    None
+}
+
+/// Clearing number (IID) of a Swiss or Liechtenstein IBAN, the five digits
+/// after the check digits
+pub fn clearing_number(iban: &str) -> Option<u32> {
+    let iban: String = iban.chars().filter(|c| !c.is_whitespace()).collect();
+    let country = iban.get(..2)?.to_ascii_uppercase();
+    if country != "CH" && country != "LI" {
+        return None;
+    }
+    let iid = iban.get(4..9)?;
+    if !iid.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    iid.parse().ok()
+}
+
+fn is_wise_code(code: &str) -> bool {
+    code.split_once('-').is_some_and(|(kind, id)| {
+        WISE_CODE_KINDS.contains(&kind) && !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())
+    })
+}
@@ -0,0 +1,62 @@
+//! Format of Business Identifier Codes (ISO 9362), the BICs of banks.
+
+use std::fmt;
+
+// ISO 3166 country codes, and XK for Kosovo as used by SWIFT
+const COUNTRIES: &str = "AD AE AF AG AI AL AM AO AQ AR AS AT AU AW AX AZ BA BB BD BE BF BG BH BI \
+BJ BL BM BN BO BQ BR BS BT BV BW BY BZ CA CC CD CF CG CH CI CK CL CM CN CO CR CU CV CW CX CY CZ \
+DE DJ DK DM DO DZ EC EE EG EH ER ES ET FI FJ FK FM FO FR GA GB GD GE GF GG GH GI GL GM GN GP GQ \
+GR GS GT GU GW GY HK HM HN HR HT HU ID IE IL IM IN IO IQ IR IS IT JE JM JO JP KE KG KH KI KM KN \
+KP KR KW KY KZ LA LB LC LI LK LR LS LT LU LV LY MA MC MD ME MF MG MH MK ML MM MN MO MP MQ MR MS \
+MT MU MV MW MX MY MZ NA NC NE NF NG NI NL NO NP NR NU NZ OM PA PE PF PG PH PK PL PM PN PR PS PT \
+PW PY QA RE RO RS RU RW SA SB SC SD SE SG SH SI SJ SK SL SM SN SO SR SS ST SV SX SY SZ TC TD TF \
+TG TH TJ TK TL TM TN TO TR TT TV TW TZ UA UG UM US UY UZ VA VC VE VG VI VN VU WF WS XK YE YT ZA \
+ZM ZW";
+
+/// Why a BIC is invalid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BicError {
+    /// Not 8 or 11 characters long
+    Length(usize),
+    /// Not upper case letters and digits in the places of a BIC
+    Format,
+    /// The country code is not an ISO 3166 country
+    Country(String),
+}
+
+impl fmt::Display for BicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BicError::Length(length) => write!(f, "{} characters instead of 8 or 11", length),
+            BicError::Format => write!(f, "not a BIC"),
+            BicError::Country(country) => write!(f, "unknown country code {}", country),
+        }
+    }
+}
+
+impl std::error::Error for BicError {}
+
+/// Check that a BIC consists of a four character institution code, a
+/// country code, a two character location code and optionally a three
+/// character branch code
+pub fn validate_bic(bic: &str) -> Result<(), BicError> {
+    let bytes = bic.as_bytes();
+    if bytes.len() != 8 && bytes.len() != 11 {
+        return Err(BicError::Length(bic.chars().count()));
+    }
+    let is_code = |b: &u8| b.is_ascii_uppercase() || b.is_ascii_digit();
+    if !bytes[..4].iter().all(is_code)
+        || !bytes[4..6].iter().all(u8::is_ascii_uppercase)
+        || !bytes[6..].iter().all(is_code)
+    {
+        return Err(BicError::Format);
+    }
+    let country = &bic[4..6];
+    if !COUNTRIES
+        .split_ascii_whitespace()
+        .any(|code| code == country)
+    {
+        return Err(BicError::Country(country.to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+//! Exclusive XML Canonicalization without comments
+//! (<https://www.w3.org/TR/xml-exc-c14n/>) of written documents, so that
+//! digests and signatures over them are stable.
+//!
+//! The canonical form has no XML declaration and no comments. Elements are
+//! written with start and end tags, also where they are empty, and attributes
+//! in double quotes, sorted by namespace and name. A namespace is declared on
+//! each element that uses it in its name or attributes, unless an enclosing
+//! element of the output already declares it. Whitespace between elements is
+//! kept, so the layout of the document stays as it was written.
+
+use crate::error::{ConvertError, Result};
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::reader::NsReader;
+use std::io::Write;
+
+/// Write a document in canonical form
+pub fn canonicalize<W: Write>(document: &[u8], mut output: W) -> Result<()> {
+    let mut reader = NsReader::from_reader(document);
+    reader.config_mut().expand_empty_elements = true;
+    let mut buf = Vec::new();
+    // Namespace declarations written for each open element, as prefix and
+    // namespace, the default namespace with an empty prefix
+    let mut declared: Vec<Vec<(String, String)>> = Vec::new();
+    let mut after_root = false;
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                let declarations = start_tag(&reader, &e, &declared, &mut output)?;
+                declared.push(declarations);
+            }
+            Event::End(e) => {
+                let name = std::str::from_utf8(e.name().into_inner())?;
+                write!(output, "</{}>", name)?;
+                declared.pop();
+                after_root = declared.is_empty();
+            }
+            Event::Text(e) if !declared.is_empty() => {
+                let raw = std::str::from_utf8(&e)?;
+                let text = unescape(&normalize_line_breaks(raw))?.into_owned();
+                output.write_all(escape_text(&text).as_bytes())?;
+            }
+            Event::CData(e) if !declared.is_empty() => {
+                let text = normalize_line_breaks(std::str::from_utf8(&e)?);
+                output.write_all(escape_text(&text).as_bytes())?;
+            }
+            Event::PI(e) => {
+                let instruction = std::str::from_utf8(&e)?;
+                match (declared.is_empty(), after_root) {
+                    (false, _) => write!(output, "<?{}?>", instruction)?,
+                    (true, false) => writeln!(output, "<?{}?>", instruction)?,
+                    (true, true) => write!(output, "\n<?{}?>", instruction)?,
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    output.flush()?;
+    Ok(())
+}
+
+// Write the start tag of an element, returning the namespaces it declares
+fn start_tag<R>(
+    reader: &NsReader<R>,
+    e: &BytesStart,
+    declared: &[Vec<(String, String)>],
+    output: &mut impl Write,
+) -> Result<Vec<(String, String)>> {
+    let name = std::str::from_utf8(e.name().into_inner())?;
+
+    // Namespaces the element uses, and its attributes with their namespace
+    let mut used = vec![(
+        prefix(name).to_string(),
+        namespace_of(reader.resolve_element(e.name()).0)?,
+    )];
+    let mut attributes = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        if attr.key.as_namespace_binding().is_some() {
+            continue;
+        }
+        let qname = std::str::from_utf8(attr.key.into_inner())?;
+        let local_name = std::str::from_utf8(attr.key.local_name().into_inner())?;
+        let namespace = namespace_of(reader.resolve_attribute(attr.key).0)?;
+        let attr_prefix = prefix(qname);
+        if !attr_prefix.is_empty() && attr_prefix != "xml" {
+            used.push((attr_prefix.to_string(), namespace.clone()));
+        }
+        let raw = std::str::from_utf8(&attr.value)?;
+        let value = unescape(&normalize_attribute_whitespace(raw))?.into_owned();
+        attributes.push((namespace, local_name.to_string(), qname.to_string(), value));
+    }
+
+    // Declarations not already in effect in the output
+    let in_effect = |prefix: &str| {
+        declared
+            .iter()
+            .rev()
+            .flatten()
+            .find(|(declared_prefix, _)| declared_prefix == prefix)
+            .map_or("", |(_, namespace)| namespace.as_str())
+    };
+    let mut declarations: Vec<(String, String)> = used
+        .into_iter()
+        .filter(|(prefix, namespace)| in_effect(prefix) != namespace)
+        .collect();
+    declarations.sort();
+    declarations.dedup();
+    attributes.sort();
+
+    write!(output, "<{}", name)?;
+    for (prefix, namespace) in &declarations {
+        let value = escape_attribute(namespace);
+        match prefix.as_str() {
+            "" => write!(output, " xmlns=\"{}\"", value)?,
+            prefix => write!(output, " xmlns:{}=\"{}\"", prefix, value)?,
+        }
+    }
+    for (_, _, qname, value) in &attributes {
+        write!(output, " {}=\"{}\"", qname, escape_attribute(value))?;
+    }
+    write!(output, ">")?;
+    Ok(declarations)
+}
+
+fn prefix(qname: &str) -> &str {
+    qname.split_once(':').map_or("", |(prefix, _)| prefix)
+}
+
+fn namespace_of(resolved: ResolveResult) -> Result<String> {
+    match resolved {
+        ResolveResult::Bound(namespace) => Ok(std::str::from_utf8(namespace.as_ref())?.to_string()),
+        ResolveResult::Unbound => Ok(String::new()),
+        ResolveResult::Unknown(prefix) => Err(ConvertError::XmlSyntax(format!(
+            "Undeclared namespace prefix '{}'",
+            String::from_utf8_lossy(&prefix)
+        ))),
+    }
+}
+
+// Line breaks as an XML processor passes them on
+fn normalize_line_breaks(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+// Attribute values as an XML processor passes them on, with literal
+// whitespace as spaces
+fn normalize_attribute_whitespace(value: &str) -> String {
+    normalize_line_breaks(value).replace(['\t', '\n'], " ")
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\r' => escaped.push_str("&#xD;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' => escaped.push_str("&#x9;"),
+            '\n' => escaped.push_str("&#xA;"),
+            '\r' => escaped.push_str("&#xD;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
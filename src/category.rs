@@ -0,0 +1,419 @@
+//! Categories of entries, such as `groceries` or `bank-fees`, assigned by
+//! the rules of a categories file.
+//!
+//! Each line holds one rule: the category, a colon and conditions joined
+//! with `and`. The category of an entry is that of the first rule whose
+//! conditions all hold; a rule without conditions matches every entry, as a
+//! fallback at the end of the file.
+//!
+//! ```text
+//! groceries: counterparty matches "(?i)migros|coop|aldi" and amount < 0
+//! salary: remittance contains "SALARY" and amount > 0
+//! bank-fees: code starts_with "PMNT-CCRD-CHRG"
+//! rent: amount >= -2500 and amount <= -2000 and text matches "(?i)miete|rent"
+//! other:
+//! ```
+//!
+//! Text fields are `remittance` (the remittance information, one line per
+//! Ustrd), `counterparty` (the creditor of debits and the debtor of
+//! credits), `info` (the additional information of the entry and its
+//! transaction details), `code`, `currency` and `text`, all of them
+//! together. They are compared to a string with `matches` (a regular
+//! expression, see [`crate::regex`]), `==`, `!=`, `contains`, `starts_with`
+//! or `ends_with`. Backslashes in strings are kept for regular expressions,
+//! only `\"` stands for a quote. `amount` is the signed amount, compared to
+//! a number with `==`, `!=`, `<`, `<=`, `>` or `>=`.
+
+use crate::decimal::Decimal;
+use crate::model::{Statement, Transaction};
+use crate::reader::StatementHandler;
+use crate::regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+/// Text fields of conditions
+pub const TEXT_FIELDS: &[&str] = &[
+    "remittance",
+    "counterparty",
+    "info",
+    "code",
+    "currency",
+    "text",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Remittance,
+    Counterparty,
+    Info,
+    Code,
+    Currency,
+    Text,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        Some(match name {
+            "remittance" => Field::Remittance,
+            "counterparty" => Field::Counterparty,
+            "info" => Field::Info,
+            "code" => Field::Code,
+            "currency" => Field::Currency,
+            "text" => Field::Text,
+            _ => return None,
+        })
+    }
+
+    fn get(self, transaction: &Transaction) -> String {
+        match self {
+            Field::Remittance => transaction.remittance_info.join("\n"),
+            Field::Counterparty => transaction.counterparty().to_string(),
+            Field::Info => join([&transaction.additional_info, &transaction.transaction_info]),
+            Field::Code => transaction.bank_tx_code.clone(),
+            Field::Currency => transaction.amount.currency.clone(),
+            Field::Text => join([
+                &Field::Remittance.get(transaction),
+                &Field::Counterparty.get(transaction),
+                &Field::Info.get(transaction),
+            ]),
+        }
+    }
+}
+
+// The non-empty values, one per line
+fn join<const N: usize>(values: [&String; N]) -> String {
+    let values: Vec<&str> = values
+        .iter()
+        .map(|value| value.as_str())
+        .filter(|value| !value.is_empty())
+        .collect();
+    values.join("\n")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TextTest {
+    Matches(Regex),
+    Equals(String),
+    NotEquals(String),
+    Contains(String),
+    StartsWith(String),
+    EndsWith(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equals,
+    NotEquals,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Text(Field, TextTest),
+    Amount(Comparison, Decimal),
+}
+
+impl Condition {
+    fn holds(&self, transaction: &Transaction) -> bool {
+        match self {
+            Condition::Text(field, test) => {
+                let value = field.get(transaction);
+                match test {
+                    TextTest::Matches(regex) => regex.is_match(&value),
+                    TextTest::Equals(operand) => value == *operand,
+                    TextTest::NotEquals(operand) => value != *operand,
+                    TextTest::Contains(operand) => value.contains(operand.as_str()),
+                    TextTest::StartsWith(operand) => value.starts_with(operand.as_str()),
+                    TextTest::EndsWith(operand) => value.ends_with(operand.as_str()),
+                }
+            }
+            Condition::Amount(comparison, operand) => {
+                let amount = transaction.signed_amount();
+                match comparison {
+                    Comparison::Equals => amount == *operand,
+                    Comparison::NotEquals => amount != *operand,
+                    Comparison::Less => amount < *operand,
+                    Comparison::LessOrEqual => amount <= *operand,
+                    Comparison::Greater => amount > *operand,
+                    Comparison::GreaterOrEqual => amount >= *operand,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    category: String,
+    conditions: Vec<Condition>,
+}
+
+/// Rules of a categories file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Categories {
+    rules: Vec<Rule>,
+}
+
+/// A categories file that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryError(String);
+
+impl fmt::Display for CategoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CategoryError {}
+
+impl FromStr for Categories {
+    type Err = CategoryError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut rules = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule = parse_rule(line).map_err(|message| {
+                CategoryError(format!("Line {} of categories: {}", index + 1, message))
+            })?;
+            rules.push(rule);
+        }
+        Ok(Categories { rules })
+    }
+}
+
+impl Categories {
+    /// Whether there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Category of the first rule matching an entry, if any
+    pub fn categorize(&self, transaction: &Transaction) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.conditions
+                    .iter()
+                    .all(|condition| condition.holds(transaction))
+            })
+            .map(|rule| rule.category.as_str())
+    }
+
+    /// Set the category of an entry, and with `tag` also add it to the
+    /// additional information as `#category`, unless it is there already.
+    /// Entries no rule matches are left as they are.
+    pub fn apply(&self, transaction: &mut Transaction, tag: bool) {
+        let Some(category) = self.categorize(transaction) else {
+            return;
+        };
+        transaction.category = category.to_string();
+        let hashtag = format!("#{}", category);
+        if tag
+            && !transaction
+                .additional_info
+                .split_whitespace()
+                .any(|word| word == hashtag)
+        {
+            if !transaction.additional_info.is_empty() {
+                transaction.additional_info.push(' ');
+            }
+            transaction.additional_info.push_str(&hashtag);
+        }
+    }
+}
+
+/// Categorizes entries as they are read, before passing them on
+pub struct Categorized<'a, H> {
+    categories: &'a Categories,
+    tag: bool,
+    pub inner: H,
+}
+
+impl<'a, H> Categorized<'a, H> {
+    pub fn new(categories: &'a Categories, tag: bool, inner: H) -> Self {
+        Categorized {
+            categories,
+            tag,
+            inner,
+        }
+    }
+}
+
+impl<H: StatementHandler> StatementHandler for Categorized<'_, H> {
+    fn begin(&mut self, statement: &Statement) -> crate::Result<()> {
+        self.inner.begin(statement)
+    }
+
+    fn entry(&mut self, statement: &Statement, mut transaction: Transaction) -> crate::Result<()> {
+        self.categories.apply(&mut transaction, self.tag);
+        self.inner.entry(statement, transaction)
+    }
+
+    fn end(&mut self, statement: &Statement) -> crate::Result<()> {
+        self.inner.end(statement)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Text(String),
+    Number(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Name(name) | Token::Number(name) => f.write_str(name),
+            Token::Text(text) => write!(f, "{:?}", text),
+            Token::Symbol(symbol) => f.write_str(symbol),
+        }
+    }
+}
+
+// Longer symbols first, so that `<=` is not read as `<`
+const SYMBOLS: &[&str] = &["==", "!=", "<=", ">=", "<", ">"];
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '#' {
+            break;
+        } else if c == '"' {
+            let mut text = String::new();
+            let mut chars = rest[1..].char_indices().peekable();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) if chars.peek().is_some_and(|(_, c)| *c == '"') => {
+                        chars.next();
+                        text.push('"');
+                    }
+                    Some((_, c)) => text.push(c),
+                    None => return Err("unclosed string".to_string()),
+                }
+            };
+            tokens.push(Token::Text(text));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c.is_ascii_digit() || c == '-' || c == '+' {
+            let end = rest[1..]
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .map_or(rest.len(), |end| end + 1);
+            tokens.push(Token::Number(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            return Err(format!("unexpected character {:?}", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn parse_rule(line: &str) -> Result<Rule, String> {
+    let (category, conditions) = line
+        .split_once(':')
+        .ok_or("expected a category and : at the start")?;
+    let category = category.trim();
+    if category.is_empty() || category.contains(char::is_whitespace) || category.contains('"') {
+        return Err(format!("invalid category {:?}", category));
+    }
+    let mut tokens = tokenize(conditions)?.into_iter();
+    let mut conditions = Vec::new();
+    while let Some(token) = tokens.next() {
+        let field = if conditions.is_empty() {
+            token
+        } else if token == Token::Name("and".to_string()) {
+            tokens.next().ok_or("expected a condition after and")?
+        } else {
+            return Err(format!("expected and instead of {}", token));
+        };
+        conditions.push(parse_condition(field, &mut tokens)?);
+    }
+    Ok(Rule {
+        category: category.to_string(),
+        conditions,
+    })
+}
+
+fn parse_condition(
+    field: Token,
+    tokens: &mut impl Iterator<Item = Token>,
+) -> Result<Condition, String> {
+    let name = match field {
+        Token::Name(name) => name,
+        token => return Err(format!("expected a field instead of {}", token)),
+    };
+    let operator = tokens
+        .next()
+        .ok_or_else(|| format!("expected a comparison after {}", name))?;
+    let operand = tokens
+        .next()
+        .ok_or_else(|| format!("expected a value after {}", operator))?;
+
+    if name == "amount" {
+        let comparison = match operator {
+            Token::Symbol("==") => Comparison::Equals,
+            Token::Symbol("!=") => Comparison::NotEquals,
+            Token::Symbol("<") => Comparison::Less,
+            Token::Symbol("<=") => Comparison::LessOrEqual,
+            Token::Symbol(">") => Comparison::Greater,
+            Token::Symbol(">=") => Comparison::GreaterOrEqual,
+            token => {
+                return Err(format!(
+                    "expected ==, !=, <, <=, > or >= instead of {}",
+                    token
+                ));
+            }
+        };
+        let value = match &operand {
+            Token::Number(number) => number.trim_start_matches('+').parse::<Decimal>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| format!("expected an amount instead of {}", operand))?;
+        return Ok(Condition::Amount(comparison, value));
+    }
+
+    let field = Field::from_name(&name).ok_or_else(|| {
+        format!(
+            "unknown field {}, expected amount or one of: {}",
+            name,
+            TEXT_FIELDS.join(", ")
+        )
+    })?;
+    let Token::Text(operand) = operand else {
+        return Err(format!("expected a string instead of {}", operand));
+    };
+    let test = match operator {
+        Token::Name(name) if name == "matches" => TextTest::Matches(operand.parse()?),
+        Token::Symbol("==") => TextTest::Equals(operand),
+        Token::Symbol("!=") => TextTest::NotEquals(operand),
+        Token::Name(name) if name == "contains" => TextTest::Contains(operand),
+        Token::Name(name) if name == "starts_with" => TextTest::StartsWith(operand),
+        Token::Name(name) if name == "ends_with" => TextTest::EndsWith(operand),
+        token => {
+            return Err(format!(
+                "expected matches, ==, !=, contains, starts_with or ends_with instead of {}",
+                token
+            ));
+        }
+    };
+    Ok(Condition::Text(field, test))
+}
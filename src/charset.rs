@@ -0,0 +1,117 @@
+//! Transliteration of names and remittance text to the SWIFT X character
+//! set, for receiving banks that accept no other characters.
+//!
+//! SWIFT X has the letters and digits of basic Latin, space and
+//! `/ - ? : ( ) . , ' +`, the characters all SEPA banks must support.
+//! Umlauts are written out as in German (`ä` as `ae`), other letters with
+//! accents lose them, and punctuation is replaced by the closest character
+//! of the set or else by a full stop.
+
+use crate::rules::is_sepa_character;
+use std::borrow::Cow;
+
+/// Character set of names and remittance text in the output
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// Any character, as in the input
+    #[default]
+    Unicode,
+    /// Letters and digits of basic Latin, space and / - ? : ( ) . , ' +
+    SwiftX,
+}
+
+impl Charset {
+    /// Text in the character set
+    pub fn convert<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Charset::SwiftX if !text.chars().all(is_sepa_character) => Cow::Owned(to_swift_x(text)),
+            _ => Cow::Borrowed(text),
+        }
+    }
+}
+
+// Letters replaced by more than one letter
+const LIGATURES: &[(char, &str)] = &[
+    ('ä', "ae"),
+    ('ö', "oe"),
+    ('ü', "ue"),
+    ('Ä', "Ae"),
+    ('Ö', "Oe"),
+    ('Ü', "Ue"),
+    ('ß', "ss"),
+    ('æ', "ae"),
+    ('Æ', "AE"),
+    ('œ', "oe"),
+    ('Œ', "OE"),
+    ('þ', "th"),
+    ('Þ', "TH"),
+    ('&', "+"),
+];
+
+// Letters with accents and punctuation, by the character written for them
+const REPLACEMENTS: &[(&str, char)] = &[
+    ("àáâãåāăą", 'a'),
+    ("ÀÁÂÃÅĀĂĄ", 'A'),
+    ("çćĉċč", 'c'),
+    ("ÇĆĈĊČ", 'C'),
+    ("ďđð", 'd'),
+    ("ĎĐÐ", 'D'),
+    ("èéêëēĕėęě", 'e'),
+    ("ÈÉÊËĒĔĖĘĚ", 'E'),
+    ("ĝğġģ", 'g'),
+    ("ĜĞĠĢ", 'G'),
+    ("ĥħ", 'h'),
+    ("ĤĦ", 'H'),
+    ("ìíîïĩīĭįı", 'i'),
+    ("ÌÍÎÏĨĪĬĮİ", 'I'),
+    ("ĵ", 'j'),
+    ("Ĵ", 'J'),
+    ("ķ", 'k'),
+    ("Ķ", 'K'),
+    ("ĺļľŀł", 'l'),
+    ("ĹĻĽĿŁ", 'L'),
+    ("ñńņňŉ", 'n'),
+    ("ÑŃŅŇ", 'N'),
+    ("òóôõøōŏő", 'o'),
+    ("ÒÓÔÕØŌŎŐ", 'O'),
+    ("ŕŗř", 'r'),
+    ("ŔŖŘ", 'R'),
+    ("śŝşšș", 's'),
+    ("ŚŜŞŠȘ", 'S'),
+    ("ţťŧț", 't'),
+    ("ŢŤŦȚ", 'T'),
+    ("ùúûũūŭůűų", 'u'),
+    ("ÙÚÛŨŪŬŮŰŲ", 'U'),
+    ("ŵ", 'w'),
+    ("Ŵ", 'W'),
+    ("ýÿŷ", 'y'),
+    ("ÝŸŶ", 'Y'),
+    ("źżž", 'z'),
+    ("ŹŻŽ", 'Z'),
+    ("\"`´‘’‚“”„«»‹›", '\''),
+    ("_–—‐‑", '-'),
+    (";", ','),
+    ("[{<", '('),
+    ("]}>", ')'),
+];
+
+/// Text with every character outside SWIFT X replaced
+pub fn to_swift_x(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_sepa_character(c) {
+            converted.push(c);
+        } else if let Some((_, replacement)) = LIGATURES.iter().find(|(from, _)| *from == c) {
+            converted.push_str(replacement);
+        } else if c.is_whitespace() {
+            converted.push(' ');
+        } else {
+            let replacement = REPLACEMENTS
+                .iter()
+                .find(|(from, _)| from.contains(c))
+                .map_or('.', |(_, to)| *to);
+            converted.push(replacement);
+        }
+    }
+    converted
+}
@@ -0,0 +1,117 @@
+//! Checksum sidecar files, as required by archival policies for converted
+//! documents.
+//!
+//! A sidecar is written next to the file it belongs to, named after it with
+//! the algorithm as extra extension (`statement_08.xml.sha256`), in the format
+//! of `sha256sum` so it can be checked with `sha256sum -c`.
+
+use crate::sha256::Sha256;
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Hash algorithm of a checksum sidecar
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    #[default]
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    /// Extension of sidecar files of this algorithm
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+/// Path of the sidecar belonging to `path`
+pub fn sidecar_path(path: &Path, algorithm: ChecksumAlgorithm) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".");
+    sidecar.push(algorithm.extension());
+    PathBuf::from(sidecar)
+}
+
+/// Checksum of `data` as lower case hex digits
+pub fn checksum(data: &[u8], algorithm: ChecksumAlgorithm) -> String {
+    let digest = match algorithm {
+        ChecksumAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            hasher.update(data);
+            hasher.finish()
+        }
+    };
+    hex(&digest)
+}
+
+/// Checksum of a file's content as lower case hex digits
+#[cfg(feature = "fs")]
+pub fn file_checksum(path: &Path, algorithm: ChecksumAlgorithm) -> io::Result<String> {
+    reader_checksum(File::open(path)?, algorithm)
+}
+
+/// Checksum of everything read from `input` as lower case hex digits
+pub fn reader_checksum(mut input: impl Read, algorithm: ChecksumAlgorithm) -> io::Result<String> {
+    let digest = match algorithm {
+        ChecksumAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            let mut buf = vec![0u8; 64 * 1024];
+            loop {
+                let n = input.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+            }
+            hasher.finish()
+        }
+    };
+    Ok(hex(&digest))
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Write the sidecar of a file and return its path
+#[cfg(feature = "fs")]
+pub fn write_sidecar(path: &Path, algorithm: ChecksumAlgorithm) -> io::Result<PathBuf> {
+    let checksum = file_checksum(path, algorithm)?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let sidecar = sidecar_path(path, algorithm);
+    fs::write(&sidecar, format!("{}  {}\n", checksum, file_name))?;
+    Ok(sidecar)
+}
+
+/// Check a file against its sidecar.
+///
+/// Fails with [`io::ErrorKind::NotFound`] if there is no sidecar and with
+/// [`io::ErrorKind::InvalidData`] if the checksums differ.
+#[cfg(feature = "fs")]
+pub fn verify_sidecar(path: &Path, algorithm: ChecksumAlgorithm) -> io::Result<()> {
+    let sidecar = sidecar_path(path, algorithm);
+    let content = fs::read_to_string(&sidecar).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Cannot read checksum file {}: {}", sidecar.display(), e),
+        )
+    })?;
+    // The first word is the checksum, the file name after it is not checked
+    // so that renamed files can still be verified
+    let expected = content.split_whitespace().next().unwrap_or_default();
+    let actual = file_checksum(path, algorithm)?;
+    if !expected.eq_ignore_ascii_case(&actual) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Checksum of {} does not match {}",
+                path.display(),
+                sidecar.display()
+            ),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Conversion of a whole document held in memory, for callers without files
+//! such as the HTTP server and the WebAssembly build.
+
+use crate::error::Result;
+use crate::matrix::{READERS, VersionReader, VersionWriter, find_writer};
+use crate::reader::ParseOptions;
+use crate::stats::{ConversionStats, Stopwatch};
+use crate::totals::CodeTotals;
+use crate::writer::{WriteOptions, check_lengths, check_placeholders};
+
+/// A converted document
+#[derive(Debug, Clone)]
+pub struct Converted {
+    /// The document in the output version
+    pub output: Vec<u8>,
+    /// Problems that did not stop the conversion, including a mismatch of
+    /// balances and entries and placeholders in the output
+    pub warnings: Vec<String>,
+    pub stats: ConversionStats,
+}
+
+/// Convert a camt.053.001.10 document, plain or gzip-compressed, to the
+/// version of the write options
+pub fn convert_bytes(
+    input: &[u8],
+    parse_options: &ParseOptions,
+    write_options: &WriteOptions,
+) -> Result<Converted> {
+    let writer =
+        find_writer(write_options.version.number()).expect("every output version has a writer");
+    convert_versions(input, &READERS[0], writer, parse_options, write_options)
+}
+
+/// Convert a document between any two versions of the
+/// [`matrix`](crate::matrix)
+pub fn convert_versions(
+    mut input: &[u8],
+    reader: &VersionReader,
+    writer: &VersionWriter,
+    parse_options: &ParseOptions,
+    write_options: &WriteOptions,
+) -> Result<Converted> {
+    let stopwatch = Stopwatch::start();
+    let bytes_read = input.len() as u64;
+    let parsed = (reader.read)(&mut input, parse_options)?;
+    let statement = parsed.statement;
+    let mut warnings: Vec<String> = parsed.warnings.iter().map(ToString::to_string).collect();
+    warnings.extend(statement.check_balances());
+    let placeholders = check_placeholders(&statement, write_options)?;
+    warnings.extend(placeholders.iter().map(ToString::to_string));
+    let truncations = check_lengths(&statement, write_options)?;
+    warnings.extend(truncations.iter().map(ToString::to_string));
+
+    let mut output = Vec::new();
+    (writer.write)(&mut output, &statement, write_options)?;
+
+    let mut code_totals = CodeTotals::new();
+    for transaction in &statement.transactions {
+        code_totals.add(transaction)?;
+    }
+    let stats = ConversionStats {
+        balances: statement.balances.len(),
+        entries: statement.transactions.len(),
+        totals: code_totals.by_currency()?,
+        warnings: warnings.len(),
+        duration: stopwatch.elapsed(),
+        bytes_read,
+        bytes_written: output.len() as u64,
+    };
+    Ok(Converted {
+        output,
+        warnings,
+        stats,
+    })
+}
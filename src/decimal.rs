@@ -0,0 +1,205 @@
+// Exact decimal numbers for monetary amounts.
+//
+// Amounts are stored as an integer mantissa with a decimal scale, so "250.50"
+// is (25050, 2). The scale of the input is kept, which means formatting a
+// parsed value gives back the original text.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Neg;
+use std::str::FromStr;
+
+// ISO 20022 amounts have at most 18 total digits, so this leaves ample room
+const MAX_SCALE: u32 = 28;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError(String);
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid decimal amount: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+impl Decimal {
+    // Bring both values to the larger of the two scales
+    fn aligned(self, other: Decimal) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        let a = self
+            .mantissa
+            .checked_mul(10i128.checked_pow(scale - self.scale)?)?;
+        let b = other
+            .mantissa
+            .checked_mul(10i128.checked_pow(scale - other.scale)?)?;
+        Some((a, b, scale))
+    }
+
+    /// The value `mantissa` / 10^`scale`, e.g. 25050 with scale 2 for 250.50
+    ///
+    /// # Panics
+    ///
+    /// If `scale` is above 28.
+    pub fn new(mantissa: i128, scale: u32) -> Decimal {
+        assert!(scale <= MAX_SCALE, "scale {} is above {}", scale, MAX_SCALE);
+        Decimal { mantissa, scale }
+    }
+
+    pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Decimal {
+            mantissa: a.checked_add(b)?,
+            scale,
+        })
+    }
+
+    pub fn checked_sub(self, other: Decimal) -> Option<Decimal> {
+        self.checked_add(-other)
+    }
+
+    /// The value without its decimal point, e.g. 25050 for 250.50
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// Number of decimal places
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Number of significant digits as XML Schema's `totalDigits` counts
+    /// them: without leading zeros and trailing zeros after the point
+    pub fn total_digits(&self) -> u32 {
+        let mut mantissa = self.mantissa.unsigned_abs();
+        let mut scale = self.scale;
+        while scale > 0 && mantissa.is_multiple_of(10) {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        let digits = mantissa.checked_ilog10().map_or(0, |log| log + 1);
+        // Zeros right after the point still count, as in 0.05
+        digits.max(scale)
+    }
+
+    /// The same value with `scale` decimal places, or `None` if it has
+    /// non-zero digits beyond them
+    pub fn with_scale(self, scale: u32) -> Option<Decimal> {
+        let mantissa = if scale >= self.scale {
+            self.mantissa
+                .checked_mul(10i128.checked_pow(scale - self.scale)?)?
+        } else {
+            let divisor = 10i128.checked_pow(self.scale - scale)?;
+            if self.mantissa % divisor != 0 {
+                return None;
+            }
+            self.mantissa / divisor
+        };
+        Some(Decimal { mantissa, scale })
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseDecimalError(s.to_string());
+
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let (int_part, frac_part) = match digits.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, frac_part),
+            None => (digits, ""),
+        };
+
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(err());
+        }
+        if !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|b| b.is_ascii_digit())
+        {
+            return Err(err());
+        }
+
+        let scale = frac_part.len() as u32;
+        if scale > MAX_SCALE {
+            return Err(err());
+        }
+
+        let mut mantissa: i128 = 0;
+        for b in int_part.bytes().chain(frac_part.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(i128::from(b - b'0')))
+                .ok_or_else(err)?;
+        }
+
+        Ok(Decimal {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale,
+        })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        let scale = self.scale as usize;
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (int_part, frac_part) = padded.split_at(padded.len() - scale);
+        write!(f, "{}{}.{}", sign, int_part, frac_part)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.aligned(*other) {
+            Some((a, b, _)) => a.cmp(&b),
+            // Only reachable for values near i128::MAX; fall back to the sign
+            None => self.mantissa.signum().cmp(&other.mantissa.signum()),
+        }
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal {
+            mantissa: -self.mantissa,
+            scale: self.scale,
+        }
+    }
+}
@@ -0,0 +1,409 @@
+// Verification of enveloped XML signatures (XML-DSig) of inputs and signing
+// of outputs with the xmlsec1 command line tool.
+//
+// Some delivery channels sign the documents they deliver. The document is
+// given to xmlsec1 through a pipe, like decrypted input, so it never hits the
+// disk. Outputs are signed so that recipients can check they come unchanged
+// from the converter.
+
+use crate::error::{ConvertError, Result};
+use quick_xml::events::Event;
+use quick_xml::name::ResolveResult;
+use quick_xml::reader::NsReader;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+
+const DSIG_NAMESPACE: &[u8] = b"http://www.w3.org/2000/09/xmldsig#";
+const ENVELOPED_TRANSFORM: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+// Transforms allowed next to the enveloped signature transform
+const CANONICALIZATIONS: &[&[u8]] = &[
+    b"http://www.w3.org/TR/2001/REC-xml-c14n-20010315",
+    b"http://www.w3.org/TR/2001/REC-xml-c14n-20010315#WithComments",
+    b"http://www.w3.org/2006/12/xml-c14n11",
+    b"http://www.w3.org/2006/12/xml-c14n11#WithComments",
+    b"http://www.w3.org/2001/10/xml-exc-c14n#",
+    b"http://www.w3.org/2001/10/xml-exc-c14n#WithComments",
+];
+
+/// What a signature is verified against
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// Certificate (PEM) of the signer, whose key the signature must be made
+    /// with
+    pub certificate: Option<PathBuf>,
+    /// CA certificates (PEM) that must have issued the certificate the
+    /// signature carries in its `KeyInfo`
+    pub trusted: Option<PathBuf>,
+}
+
+impl VerifyOptions {
+    /// Whether signatures are verified at all
+    pub fn is_enabled(&self) -> bool {
+        self.certificate.is_some() || self.trusted.is_some()
+    }
+}
+
+/// Whether a document has an XML signature (`ds:Signature`). Documents that
+/// are not well-formed have none.
+pub fn is_signed(document: &[u8]) -> bool {
+    let mut reader = NsReader::from_reader(document);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_resolved_event_into(&mut buf) {
+            Ok((ResolveResult::Bound(namespace), Event::Start(e) | Event::Empty(e)))
+                if namespace.as_ref() == DSIG_NAMESPACE
+                    && e.local_name().as_ref() == b"Signature" =>
+            {
+                return true;
+            }
+            Ok((_, Event::Eof)) | Err(_) => return false,
+            Ok(_) => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Check that a document has one signature with one reference, over the
+/// whole document holding it: an empty URI with the enveloped signature
+/// transform and no transforms other than canonicalization. A signature over
+/// only a part of the document would verify with the rest changed.
+pub fn check_reference(document: &[u8]) -> std::result::Result<(), String> {
+    let mut reader = NsReader::from_reader(document);
+    let mut buf = Vec::new();
+    // Local names of the open XML-DSig elements, empty for other elements
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut signatures = 0;
+    let mut references = 0;
+    let mut enveloped = false;
+    loop {
+        let (namespace, event) = reader
+            .read_resolved_event_into(&mut buf)
+            .map_err(|e| format!("not well-formed: {}", e))?;
+        let is_dsig = matches!(namespace, ResolveResult::Bound(namespace)
+            if namespace.as_ref() == DSIG_NAMESPACE);
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let name = if is_dsig {
+                    e.local_name().as_ref().to_vec()
+                } else {
+                    Vec::new()
+                };
+                match name.as_slice() {
+                    b"Signature" if path.is_empty() => {
+                        return Err("the signature is not in a document".to_string());
+                    }
+                    b"Signature" => signatures += 1,
+                    b"Reference" if within(&path, &[b"Signature", b"SignedInfo"]) => {
+                        references += 1;
+                        let uri = e
+                            .try_get_attribute("URI")
+                            .map_err(|e| e.to_string())?
+                            .map(|uri| uri.value.into_owned());
+                        if uri.as_deref() != Some(b"") {
+                            return Err("the signature does not cover the whole document, \
+                                        its reference URI is not empty"
+                                .to_string());
+                        }
+                    }
+                    b"Transform"
+                        if within(&path, &[b"SignedInfo", b"Reference", b"Transforms"]) =>
+                    {
+                        let algorithm = e
+                            .try_get_attribute("Algorithm")
+                            .map_err(|e| e.to_string())?
+                            .map(|algorithm| algorithm.value.into_owned())
+                            .unwrap_or_default();
+                        if algorithm == ENVELOPED_TRANSFORM.as_bytes() {
+                            enveloped = true;
+                        } else if !CANONICALIZATIONS.contains(&algorithm.as_slice()) {
+                            return Err(format!(
+                                "the signature reference has the transform {}",
+                                String::from_utf8_lossy(&algorithm)
+                            ));
+                        }
+                    }
+                    _ => {}
+                }
+                if matches!(event, Event::Start(_)) {
+                    path.push(name);
+                }
+            }
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    match (signatures, references, enveloped) {
+        (0, _, _) => Err("no XML signature (ds:Signature)".to_string()),
+        (1, 1, true) => Ok(()),
+        (1, 1, false) => {
+            Err("the signature reference has no enveloped signature transform".to_string())
+        }
+        (1, _, _) => Err(format!(
+            "the signature has {} references instead of one",
+            references
+        )),
+        _ => Err(format!("{} XML signatures instead of one", signatures)),
+    }
+}
+
+// Whether the innermost open elements are the XML-DSig elements `names`
+fn within(path: &[Vec<u8>], names: &[&[u8]]) -> bool {
+    path.len() >= names.len()
+        && path[path.len() - names.len()..]
+            .iter()
+            .zip(names)
+            .all(|(open, name)| open == name)
+}
+
+/// Verify the signature of a document with xmlsec1. Fails if the document
+/// does not have exactly one signature over the whole of it (see
+/// [`check_reference`]), if the signature or the reference digest does not
+/// match, or if the key is not the one of the certificate or not in a
+/// certificate issued by a trusted CA. Keys the signature carries itself
+/// (`KeyValue`) are not used, and no other documents are fetched.
+pub fn verify(document: &[u8], options: &VerifyOptions) -> Result<()> {
+    check_reference(document).map_err(ConvertError::Rejected)?;
+
+    let mut command = Command::new("xmlsec1");
+    command
+        .arg("--verify")
+        .args(["--enabled-key-data", "x509"])
+        .args(["--enabled-reference-uris", "empty,same-doc"]);
+    if let Some(certificate) = &options.certificate {
+        command.arg("--pubkey-cert-pem").arg(certificate);
+    }
+    if let Some(trusted) = &options.trusted {
+        command.arg("--trusted-pem").arg(trusted);
+    }
+    // Read the document from standard input
+    command.arg("-");
+    let run = xmlsec1(&mut command, document)?;
+    if !run.status.success() {
+        return Err(ConvertError::Rejected(format!(
+            "invalid XML signature, xmlsec1 could not verify it: {}",
+            run.messages.trim()
+        )));
+    }
+    Ok(())
+}
+
+/// How outputs are signed
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignatureMode {
+    /// A `ds:Signature` in the supplementary data of the document
+    #[default]
+    Enveloped,
+    /// A signature file next to the document, named like it with `.sig`
+    Detached,
+}
+
+/// The key outputs are signed with
+#[derive(Debug, Clone)]
+pub struct SignOptions {
+    /// Private RSA key (PEM) to sign with. It must not be protected by a
+    /// passphrase.
+    pub key: PathBuf,
+    /// Certificate (PEM) of the key, written to the `KeyInfo` of signatures
+    /// so that recipients can check it against their truststore. Without
+    /// one, the public key is written.
+    pub certificate: Option<PathBuf>,
+    pub mode: SignatureMode,
+}
+
+/// Sign a written document with xmlsec1. An enveloped signature replaces the
+/// document with the signed one; a detached signature is written to the
+/// path returned.
+pub fn sign_file(path: &Path, options: &SignOptions) -> Result<Option<PathBuf>> {
+    let mut command = Command::new("xmlsec1");
+    command.arg("--sign").arg("--privkey-pem");
+    match &options.certificate {
+        Some(certificate) => {
+            let mut key = options.key.clone().into_os_string();
+            key.push(",");
+            key.push(certificate);
+            command.arg(key)
+        }
+        None => command.arg(&options.key),
+    };
+    let with_certificate = options.certificate.is_some();
+
+    match options.mode {
+        SignatureMode::Enveloped => {
+            let document = std::fs::read(path)?;
+            let template = envelop(&document, &signature_template("", with_certificate))?;
+            command.arg("-");
+            let signed = sign_with(&mut command, &template)?;
+            std::fs::write(path, signed)?;
+            Ok(None)
+        }
+        SignatureMode::Detached => {
+            // xmlsec1 resolves the reference from its working folder, so it
+            // runs in the folder of the document
+            let file_name = path.file_name().unwrap_or_default();
+            let signature = signature_path(path);
+            let template =
+                signature_template(&uri_escape(&file_name.to_string_lossy()), with_certificate);
+            std::fs::write(&signature, template)?;
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                command.current_dir(dir);
+            }
+            command.arg(signature.file_name().unwrap_or_default());
+            let signed = sign_with(&mut command, &[]);
+            match signed {
+                Ok(signed) => std::fs::write(&signature, signed)?,
+                Err(e) => {
+                    let _ = std::fs::remove_file(&signature);
+                    return Err(e);
+                }
+            }
+            Ok(Some(signature))
+        }
+    }
+}
+
+/// Path of the detached signature of a document, e.g. `statement_08.xml.sig`
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut signature = path.as_os_str().to_owned();
+    signature.push(".sig");
+    PathBuf::from(signature)
+}
+
+/// Template of an RSA-SHA256 signature over the document at `uri`, or with an
+/// empty URI and the enveloped signature transform over the document holding
+/// it, for xmlsec1 to fill in
+pub fn signature_template(uri: &str, with_certificate: bool) -> String {
+    let transforms = if uri.is_empty() {
+        "<ds:Transforms>\
+         <ds:Transform Algorithm=\"http://www.w3.org/2000/09/xmldsig#enveloped-signature\"/>\
+         <ds:Transform Algorithm=\"http://www.w3.org/2001/10/xml-exc-c14n#\"/>\
+         </ds:Transforms>"
+    } else {
+        ""
+    };
+    let key_info = if with_certificate {
+        "<ds:X509Data><ds:X509Certificate/></ds:X509Data>"
+    } else {
+        "<ds:KeyValue/>"
+    };
+    format!(
+        "<ds:Signature xmlns:ds=\"http://www.w3.org/2000/09/xmldsig#\">\
+         <ds:SignedInfo>\
+         <ds:CanonicalizationMethod Algorithm=\"http://www.w3.org/2001/10/xml-exc-c14n#\"/>\
+         <ds:SignatureMethod Algorithm=\"http://www.w3.org/2001/04/xmldsig-more#rsa-sha256\"/>\
+         <ds:Reference URI=\"{}\">{}\
+         <ds:DigestMethod Algorithm=\"http://www.w3.org/2001/04/xmlenc#sha256\"/>\
+         <ds:DigestValue/>\
+         </ds:Reference>\
+         </ds:SignedInfo>\
+         <ds:SignatureValue/>\
+         <ds:KeyInfo>{}</ds:KeyInfo>\
+         </ds:Signature>",
+        uri, transforms, key_info
+    )
+}
+
+/// Add a signature template to a statement document as supplementary data
+/// (`SplmtryData/Envlp`) after its last statement, where the schemas of all
+/// output versions allow it. It is indented like the statement.
+pub fn envelop(document: &[u8], template: &str) -> Result<Vec<u8>> {
+    const STMT_END: &[u8] = b"</Stmt>";
+    let end = document
+        .windows(STMT_END.len())
+        .rposition(|window| window == STMT_END)
+        .ok_or_else(|| ConvertError::MissingField("BkToCstmrStmt/Stmt".to_string()))?;
+    let line_start = document[..end]
+        .iter()
+        .rposition(|&b| !matches!(b, b' ' | b'\t'))
+        .map_or(0, |i| i + 1);
+    let indent = match document[..line_start].last() {
+        Some(b'\n') => &document[line_start - 1..end],
+        _ => &[][..],
+    };
+
+    let insert_at = end + STMT_END.len();
+    let mut enveloped = Vec::with_capacity(document.len() + template.len() + 64);
+    enveloped.extend_from_slice(&document[..insert_at]);
+    enveloped.extend_from_slice(indent);
+    enveloped.extend_from_slice(b"<SplmtryData><Envlp>");
+    enveloped.extend_from_slice(template.as_bytes());
+    enveloped.extend_from_slice(b"</Envlp></SplmtryData>");
+    enveloped.extend_from_slice(&document[insert_at..]);
+    Ok(enveloped)
+}
+
+// Run xmlsec1 to sign, returning the signed document
+fn sign_with(command: &mut Command, input: &[u8]) -> Result<Vec<u8>> {
+    let run = xmlsec1(command, input)?;
+    if !run.status.success() {
+        return Err(ConvertError::Io(io::Error::other(format!(
+            "xmlsec1 could not sign the output: {}",
+            run.messages.trim()
+        ))));
+    }
+    Ok(run.output)
+}
+
+// Characters of a file name that cannot be used in a URI as they are
+fn uri_escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for b in name.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            escaped.push(b as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", b));
+        }
+    }
+    escaped
+}
+
+struct Run {
+    status: ExitStatus,
+    output: Vec<u8>,
+    messages: String,
+}
+
+// Run xmlsec1 with `input` on its standard input
+fn xmlsec1(command: &mut Command, input: &[u8]) -> io::Result<Run> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not run xmlsec1: {}", e)))?;
+
+    // Write the input and collect messages while the output is read, so
+    // neither side can block on a full pipe
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let messages = std::thread::spawn(move || {
+        let mut messages = String::new();
+        let _ = stderr.read_to_string(&mut messages);
+        messages
+    });
+    let output = std::thread::scope(|scope| {
+        // xmlsec1 may stop reading early when the document is broken; its
+        // exit status tells what went wrong
+        scope.spawn(move || {
+            let _ = stdin.write_all(input);
+        });
+        let mut output = Vec::new();
+        child
+            .stdout
+            .take()
+            .expect("stdout is piped")
+            .read_to_end(&mut output)
+            .map(|_| output)
+    })?;
+    let status = child.wait()?;
+    let messages = messages.join().unwrap_or_default();
+    Ok(Run {
+        status,
+        output,
+        messages,
+    })
+}
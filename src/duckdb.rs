@@ -0,0 +1,125 @@
+//! Entries of statements written to a DuckDB database with the `duckdb`
+//! command line client, for querying statements with SQL.
+//!
+//! Entries go to the table `entries`, created on first use with the columns
+//! of an entry as JSON, see [`entry_value`](crate::extract::entry_value).
+//! Amounts are `DECIMAL(38, 5)`, enough for the five decimal places ISO 20022
+//! allows, and dates are `DATE`. Writing a statement replaces the entries
+//! written for it before, found by IBAN and statement id, so converting a
+//! file again does not add its entries twice.
+
+use crate::model::Statement;
+use crate::reference::{ReferenceStyle, generate_transaction_reference};
+use crate::sql::{literal, optional};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Statement creating the table of entries if it does not exist
+pub const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS entries (
+    iban VARCHAR NOT NULL,
+    statement VARCHAR NOT NULL,
+    account_servicer_ref VARCHAR,
+    reference VARCHAR NOT NULL,
+    end_to_end_id VARCHAR,
+    amount DECIMAL(38, 5) NOT NULL,
+    currency VARCHAR NOT NULL,
+    status VARCHAR NOT NULL,
+    booking_date DATE,
+    value_date DATE,
+    bank_transaction_code VARCHAR,
+    creditor_reference VARCHAR,
+    debtor VARCHAR,
+    remittance_info VARCHAR,
+    additional_info VARCHAR,
+    category VARCHAR,
+    gl_account VARCHAR,
+    payee VARCHAR
+);";
+
+/// SQL replacing the entries of a statement in one transaction
+pub fn entries_sql(statement: &Statement, style: ReferenceStyle) -> String {
+    let mut sql = String::new();
+    let _ = writeln!(sql, "BEGIN TRANSACTION;\n{}", CREATE_TABLE);
+    let _ = writeln!(
+        sql,
+        "DELETE FROM entries WHERE iban = {} AND statement = {};",
+        literal(&statement.iban),
+        literal(&statement.id)
+    );
+    for transaction in &statement.transactions {
+        let date = |date: Option<String>| date.map_or("NULL".to_string(), |date| literal(&date));
+        let values = [
+            literal(&statement.iban),
+            literal(&statement.id),
+            optional(&transaction.account_servicer_ref),
+            literal(&generate_transaction_reference(
+                statement,
+                transaction,
+                style,
+            )),
+            optional(&transaction.end_to_end_id),
+            transaction.signed_amount().to_string(),
+            literal(&transaction.amount.currency),
+            literal(transaction.status.as_str()),
+            date(
+                transaction
+                    .booking_date
+                    .as_ref()
+                    .map(|date| date.date().to_string()),
+            ),
+            date(
+                transaction
+                    .value_date
+                    .as_ref()
+                    .map(|date| date.date().to_string()),
+            ),
+            optional(&transaction.bank_tx_code),
+            transaction
+                .creditor_reference
+                .as_ref()
+                .map_or("NULL".to_string(), |reference| {
+                    literal(&reference.reference)
+                }),
+            optional(&transaction.debtor_name),
+            optional(&transaction.remittance_info.join("\n")),
+            optional(&transaction.additional_info),
+            optional(&transaction.category),
+            optional(&transaction.gl_account),
+            optional(transaction.payee()),
+        ];
+        let _ = writeln!(sql, "INSERT INTO entries VALUES ({});", values.join(", "));
+    }
+    sql.push_str("COMMIT;\n");
+    sql
+}
+
+/// Replace the entries of a statement in the database, creating it if needed
+pub fn write_entries(
+    database: &Path,
+    statement: &Statement,
+    style: ReferenceStyle,
+) -> io::Result<()> {
+    let mut child = Command::new("duckdb")
+        .arg("-bail")
+        .arg(database)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not run duckdb: {}", e)))?;
+    // Closed at the end of the block, so the input ends
+    {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(entries_sql(statement, style).as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "duckdb failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,187 @@
+// Transcoding of single-byte encoded input to UTF-8.
+//
+// Some banks still deliver CAMT files as ISO-8859-1 or windows-1252. The XML
+// reader only understands UTF-8, so such input is converted on the fly.
+
+use crate::error::{ConvertError, Result};
+use std::io::{BufRead, Read};
+
+/// Character encoding of an input document
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEncoding {
+    #[value(name = "utf-8", alias = "utf8")]
+    Utf8,
+    #[value(name = "iso-8859-1", alias = "latin1")]
+    Latin1,
+    #[value(name = "iso-8859-15", alias = "latin9")]
+    Latin9,
+    #[value(name = "windows-1252", alias = "cp1252")]
+    Windows1252,
+}
+
+impl InputEncoding {
+    /// Look up an encoding by the name used in XML declarations
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(InputEncoding::Utf8),
+            "iso-8859-1" | "iso8859-1" | "latin1" | "l1" => Some(InputEncoding::Latin1),
+            "iso-8859-15" | "iso8859-15" | "latin9" | "l9" => Some(InputEncoding::Latin9),
+            "windows-1252" | "cp1252" => Some(InputEncoding::Windows1252),
+            _ => None,
+        }
+    }
+
+    /// Name of the encoding as used in XML declarations
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InputEncoding::Utf8 => "utf-8",
+            InputEncoding::Latin1 => "iso-8859-1",
+            InputEncoding::Latin9 => "iso-8859-15",
+            InputEncoding::Windows1252 => "windows-1252",
+        }
+    }
+
+    fn decode_byte(&self, b: u8) -> char {
+        match self {
+            InputEncoding::Utf8 | InputEncoding::Latin1 => char::from(b),
+            InputEncoding::Latin9 => match b {
+                0xA4 => '€',
+                0xA6 => 'Š',
+                0xA8 => 'š',
+                0xB4 => 'Ž',
+                0xB8 => 'ž',
+                0xBC => 'Œ',
+                0xBD => 'œ',
+                0xBE => 'Ÿ',
+                _ => char::from(b),
+            },
+            InputEncoding::Windows1252 => match b {
+                0x80 => '€',
+                0x82 => '‚',
+                0x83 => 'ƒ',
+                0x84 => '„',
+                0x85 => '…',
+                0x86 => '†',
+                0x87 => '‡',
+                0x88 => 'ˆ',
+                0x89 => '‰',
+                0x8A => 'Š',
+                0x8B => '‹',
+                0x8C => 'Œ',
+                0x8E => 'Ž',
+                0x91 => '\u{2018}',
+                0x92 => '\u{2019}',
+                0x93 => '\u{201C}',
+                0x94 => '\u{201D}',
+                0x95 => '•',
+                0x96 => '–',
+                0x97 => '—',
+                0x98 => '˜',
+                0x99 => '™',
+                0x9A => 'š',
+                0x9B => '›',
+                0x9C => 'œ',
+                0x9E => 'ž',
+                0x9F => 'Ÿ',
+                // 0x81, 0x8D, 0x8F, 0x90 and 0x9D are unassigned
+                _ => char::from(b),
+            },
+        }
+    }
+}
+
+/// Determine the encoding of a document from its first bytes.
+///
+/// A byte order mark takes precedence, otherwise the encoding named in the XML
+/// declaration is used. Returns UTF-8 when there is no declaration or it names
+/// no encoding.
+pub fn detect_encoding(prefix: &[u8]) -> Result<InputEncoding> {
+    if prefix.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Ok(InputEncoding::Utf8);
+    }
+    if prefix.starts_with(&[0xFF, 0xFE]) || prefix.starts_with(&[0xFE, 0xFF]) {
+        return Err(ConvertError::UnsupportedEncoding("UTF-16".to_string()));
+    }
+
+    // Tolerate whitespace before the declaration, as written by some portals
+    let start = prefix
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(prefix.len());
+    let Some(rest) = prefix[start..].strip_prefix(b"<?xml") else {
+        return Ok(InputEncoding::Utf8);
+    };
+    let end = rest
+        .windows(2)
+        .position(|w| w == b"?>")
+        .unwrap_or(rest.len());
+    let declaration = String::from_utf8_lossy(&rest[..end]);
+
+    let Some((_, value)) = declaration.split_once("encoding") else {
+        return Ok(InputEncoding::Utf8);
+    };
+    let label = value
+        .trim_start()
+        .trim_start_matches('=')
+        .trim_start()
+        .trim_start_matches(['"', '\''])
+        .split(['"', '\''])
+        .next()
+        .unwrap_or_default();
+
+    InputEncoding::from_label(label)
+        .ok_or_else(|| ConvertError::UnsupportedEncoding(label.to_string()))
+}
+
+/// Reader that converts single-byte encoded input to UTF-8
+pub struct Transcoder<R> {
+    inner: R,
+    encoding: InputEncoding,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: BufRead> Transcoder<R> {
+    pub fn new(inner: R, encoding: InputEncoding) -> Self {
+        Transcoder {
+            inner,
+            encoding,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Read for Transcoder<R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Transcoder<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+
+            let input = self.inner.fill_buf()?;
+            let mut utf8 = [0u8; 4];
+            for b in input {
+                let c = self.encoding.decode_byte(*b);
+                self.buf
+                    .extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            }
+            let consumed = input.len();
+            self.inner.consume(consumed);
+        }
+        Ok(&self.buf[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.buf.len());
+    }
+}
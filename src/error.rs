@@ -0,0 +1,188 @@
+use std::fmt;
+
+/// Where in the input document a problem was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// 1-based line number
+    pub line: u64,
+    /// 1-based column, counted in bytes
+    pub column: u64,
+    /// Path of the enclosing element, e.g. `Document/BkToCstmrStmt/Stmt/Ntry`
+    pub path: String,
+}
+
+/// A problem that did not stop the conversion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub message: String,
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(location) = &self.location {
+            write!(f, " at line {}, column {}", location.line, location.column)?;
+        }
+        Ok(())
+    }
+}
+
+/// Errors returned by the conversion library.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConvertError {
+    /// Reading the input or writing the output failed
+    Io(std::io::Error),
+    /// The input is not well-formed XML
+    XmlSyntax(String),
+    /// The input is a CAMT document of a version this tool cannot read
+    UnsupportedVersion(String),
+    /// The input uses a character encoding this tool cannot read
+    UnsupportedEncoding(String),
+    /// A field required for the output is missing from the input
+    MissingField(String),
+    /// An amount is not a valid decimal number
+    InvalidAmount { path: String, value: String },
+    /// A code, date or other value does not have an allowed form
+    InvalidValue { path: String, value: String },
+    /// A value that was read failed a check, such as the check digits of an
+    /// IBAN. Only raised with the strict option, warned about otherwise.
+    FailedCheck { path: String, message: String },
+    /// The input has a DOCTYPE declaration or exceeds one of the
+    /// [`Limits`](crate::reader::Limits), which protect against documents
+    /// crafted to exhaust memory or time
+    Rejected(String),
+    /// A sum of amounts, such as the total of the entries of a statement, is
+    /// too large to be computed
+    Overflow(String),
+    /// The output cannot be split into pages within the given limits, as
+    /// one entry alone exceeds the size of a page
+    PageLimit(String),
+    /// The conversion stopped on a bug in this tool rather than a problem of
+    /// the input, with the message it panicked with
+    Internal(String),
+    /// Any of the above, with the position in the input where it occurred
+    Located(Box<ConvertError>, Location),
+}
+
+impl ConvertError {
+    /// Whether the problem is confined to the data of a single element.
+    ///
+    /// Recoverable errors leave the rest of the document readable, so a caller
+    /// may choose to skip the affected entry. I/O, syntax and version errors
+    /// are fatal for the whole document.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self.kind(),
+            ConvertError::MissingField(_)
+                | ConvertError::InvalidAmount { .. }
+                | ConvertError::InvalidValue { .. }
+                | ConvertError::FailedCheck { .. }
+        )
+    }
+
+    /// The underlying error, without location information
+    pub fn kind(&self) -> &ConvertError {
+        match self {
+            ConvertError::Located(error, _) => error.kind(),
+            error => error,
+        }
+    }
+
+    /// Position in the input document, if known
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            ConvertError::Located(_, location) => Some(location),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Io(e) => write!(f, "I/O error: {}", e),
+            ConvertError::XmlSyntax(msg) => write!(f, "Error parsing XML: {}", msg),
+            ConvertError::UnsupportedVersion(ns) => {
+                write!(f, "Unsupported document version: {}", ns)
+            }
+            ConvertError::UnsupportedEncoding(encoding) => {
+                write!(f, "Unsupported input encoding: {}", encoding)
+            }
+            ConvertError::MissingField(field) => write!(f, "Missing required field {}", field),
+            ConvertError::Rejected(reason) => write!(f, "Input rejected: {}", reason),
+            ConvertError::PageLimit(reason) => write!(f, "Cannot paginate output: {}", reason),
+            ConvertError::Overflow(sum) => write!(f, "The {} is too large to compute", sum),
+            ConvertError::Internal(message) => write!(f, "Internal error: {}", message),
+            ConvertError::InvalidAmount { path, value } => {
+                write!(f, "Invalid amount '{}' in {}", value, path)
+            }
+            ConvertError::InvalidValue { path, value } => {
+                write!(f, "Invalid value '{}' in {}", value, path)
+            }
+            ConvertError::FailedCheck { path, message } => write!(f, "{} in {}", message, path),
+            ConvertError::Located(error, location) => {
+                write!(f, "{}", error)?;
+                // Amount, value and check errors already name their element,
+                // and there is no element at the end of the input
+                if !location.path.is_empty()
+                    && !matches!(
+                        error.kind(),
+                        ConvertError::InvalidAmount { .. }
+                            | ConvertError::InvalidValue { .. }
+                            | ConvertError::FailedCheck { .. }
+                    )
+                {
+                    write!(f, " in {}", location.path)?;
+                }
+                write!(f, " at line {}, column {}", location.line, location.column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConvertError::Io(e) => Some(e),
+            ConvertError::Located(error, _) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ConvertError {
+    fn from(e: std::io::Error) -> Self {
+        ConvertError::Io(e)
+    }
+}
+
+impl From<quick_xml::Error> for ConvertError {
+    fn from(e: quick_xml::Error) -> Self {
+        match e {
+            quick_xml::Error::Io(e) => ConvertError::Io(std::io::Error::new(e.kind(), e)),
+            e => ConvertError::XmlSyntax(e.to_string()),
+        }
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for ConvertError {
+    fn from(e: quick_xml::events::attributes::AttrError) -> Self {
+        ConvertError::XmlSyntax(e.to_string())
+    }
+}
+
+impl From<quick_xml::escape::EscapeError> for ConvertError {
+    fn from(e: quick_xml::escape::EscapeError) -> Self {
+        ConvertError::XmlSyntax(e.to_string())
+    }
+}
+
+impl From<std::str::Utf8Error> for ConvertError {
+    fn from(e: std::str::Utf8Error) -> Self {
+        ConvertError::XmlSyntax(e.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ConvertError>;
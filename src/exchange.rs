@@ -0,0 +1,196 @@
+//! Currency exchanges of entries, for checking the rates a bank applied.
+//!
+//! An entry is listed if its amount details have a currency exchange
+//! (`CcyXchg`). Its original amount is the amount in the source currency of
+//! the exchange and its counter amount the one in the target currency, taken
+//! from the amount details or the amount of the entry. Where the exchange
+//! names no target currency, the currency of the account is taken. The
+//! volume of each pair of currencies adds up the amounts of its entries
+//! regardless of whether they were credited or debited.
+
+use crate::decimal::Decimal;
+use crate::error::{ConvertError, Result};
+use crate::model::{Amount, CurrencyExchange, Statement, Transaction};
+use crate::totals::csv_field;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone)]
+struct Row {
+    booking_date: String,
+    credit_debit: &'static str,
+    original: Amount,
+    /// Amount in the target currency, if the entry has one
+    counter: Option<Amount>,
+    exchange: CurrencyExchange,
+    account: String,
+    statement: String,
+}
+
+/// Number and amounts of the exchanges between two currencies
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Volume {
+    pub entries: usize,
+    /// Sum of the original amounts
+    pub original: Decimal,
+    /// Sum of the counter amounts, of the entries that have one
+    pub counter: Decimal,
+}
+
+/// Entries with a currency exchange, with their amounts and rate
+#[derive(Debug, Clone, Default)]
+pub struct ExchangeReport {
+    rows: Vec<Row>,
+}
+
+impl ExchangeReport {
+    pub fn new() -> Self {
+        ExchangeReport::default()
+    }
+
+    /// Add an entry of a statement, if its amount details have a currency
+    /// exchange
+    pub fn add(&mut self, statement: &Statement, transaction: &Transaction) {
+        let Some(exchange) = transaction
+            .amount_details
+            .details()
+            .find_map(|(_, detail)| detail.exchange.clone())
+        else {
+            return;
+        };
+        let target = if exchange.target_currency.is_empty() {
+            statement.account_currency()
+        } else {
+            exchange.target_currency.as_str()
+        };
+        let amount_in = |currency: &str| {
+            transaction
+                .amount_details
+                .in_currency(currency)
+                .or_else(|| Some(&transaction.amount).filter(|a| a.currency == currency))
+                .cloned()
+        };
+        let original = amount_in(&exchange.source_currency).unwrap_or_else(|| Amount {
+            value: Decimal::default(),
+            currency: exchange.source_currency.clone(),
+        });
+        let counter = amount_in(target);
+        self.rows.push(Row {
+            booking_date: transaction
+                .booking_date
+                .map(|date| date.date().to_string())
+                .unwrap_or_default(),
+            credit_debit: transaction
+                .credit_debit_ind
+                .map(|ind| ind.as_str())
+                .unwrap_or_default(),
+            original,
+            counter,
+            exchange: CurrencyExchange {
+                target_currency: target.to_string(),
+                ..exchange
+            },
+            account: statement.iban.clone(),
+            statement: statement.id.clone(),
+        });
+    }
+
+    /// Add the entries of other documents
+    pub fn merge(&mut self, other: &ExchangeReport) {
+        self.rows.extend(other.rows.iter().cloned());
+    }
+
+    /// Number of entries with a currency exchange
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Volume by source and target currency, or an error if a volume is too
+    /// large to compute
+    pub fn volumes(&self) -> Result<BTreeMap<(String, String), Volume>> {
+        let mut volumes: BTreeMap<(String, String), Volume> = BTreeMap::new();
+        for row in &self.rows {
+            let key = (
+                row.original.currency.clone(),
+                row.exchange.target_currency.clone(),
+            );
+            let volume = volumes.entry(key).or_default();
+            volume.entries += 1;
+            let overflow = || {
+                ConvertError::Overflow(format!(
+                    "volume of {} to {}",
+                    row.original.currency, row.exchange.target_currency
+                ))
+            };
+            volume.original = volume
+                .original
+                .checked_add(row.original.value)
+                .ok_or_else(overflow)?;
+            if let Some(counter) = &row.counter {
+                volume.counter = volume
+                    .counter
+                    .checked_add(counter.value)
+                    .ok_or_else(overflow)?;
+            }
+        }
+        Ok(volumes)
+    }
+
+    /// Write the entries as CSV, one `entry` line per entry in statement
+    /// order, followed by one `total` line with the volume of each pair of
+    /// currencies
+    pub fn write_csv(&self, mut output: impl Write) -> io::Result<()> {
+        writeln!(
+            output,
+            "line,entries,original_amount,original_currency,counter_amount,counter_currency,\
+             rate,unit_currency,credit_debit,booking_date,account,statement"
+        )?;
+        for row in &self.rows {
+            writeln!(
+                output,
+                "entry,1,{},{},{},{},{},{},{},{},{},{}",
+                row.original.normalized(),
+                csv_field(&row.original.currency),
+                row.counter
+                    .as_ref()
+                    .map(|counter| counter.normalized().to_string())
+                    .unwrap_or_default(),
+                csv_field(&row.exchange.target_currency),
+                row.exchange.rate,
+                csv_field(&row.exchange.unit_currency),
+                row.credit_debit,
+                row.booking_date,
+                csv_field(&row.account),
+                csv_field(&row.statement)
+            )?;
+        }
+        let volumes = self
+            .volumes()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        for ((source, target), volume) in volumes {
+            writeln!(
+                output,
+                "total,{},{},{},{},{},,,,,,",
+                volume.entries,
+                amount(volume.original, &source),
+                csv_field(&source),
+                amount(volume.counter, &target),
+                csv_field(&target)
+            )?;
+        }
+        output.flush()
+    }
+}
+
+// Sums with the decimal places of their currency
+fn amount(value: Decimal, currency: &str) -> Decimal {
+    Amount {
+        value,
+        currency: currency.to_string(),
+    }
+    .normalized()
+}
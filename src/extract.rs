@@ -0,0 +1,81 @@
+//! Single entries taken out of a statement, to forward one booking, such as a
+//! disputed one, without the rest of the statement.
+//!
+//! An entry is found by its reference: the reference of the bank
+//! (AcctSvcrRef) in the input, the reference generated for it in outputs, or
+//! its end-to-end reference. It is written as a camt.054 notification with
+//! [`write_notification`](crate::writer::write_notification) or as JSON.
+
+use crate::json::Value;
+use crate::model::{Statement, Transaction};
+use crate::reference::{ReferenceStyle, generate_transaction_reference};
+
+/// Entries of a statement with the reference, in statement order. References
+/// are compared ignoring case and surrounding whitespace, and generated
+/// references are those of `style`.
+pub fn find_entries<'a>(
+    statement: &'a Statement,
+    reference: &str,
+    style: ReferenceStyle,
+) -> Vec<&'a Transaction> {
+    let reference = reference.trim();
+    statement
+        .transactions
+        .iter()
+        .filter(|transaction| {
+            [
+                transaction.account_servicer_ref.clone(),
+                generate_transaction_reference(statement, transaction, style),
+                transaction.end_to_end_id.clone(),
+            ]
+            .iter()
+            .any(|candidate| !candidate.is_empty() && candidate.eq_ignore_ascii_case(reference))
+        })
+        .collect()
+}
+
+/// An entry with the account and statement it belongs to as JSON
+pub fn entry_value(
+    statement: &Statement,
+    transaction: &Transaction,
+    style: ReferenceStyle,
+) -> Value {
+    let text = |text: &str| (!text.is_empty()).then(|| text.to_string());
+    Value::object()
+        .with("iban", statement.iban.as_str())
+        .with("statement", statement.id.as_str())
+        .with(
+            "account_servicer_ref",
+            text(&transaction.account_servicer_ref),
+        )
+        .with(
+            "reference",
+            generate_transaction_reference(statement, transaction, style),
+        )
+        .with("end_to_end_id", text(&transaction.end_to_end_id))
+        .with("amount", transaction.signed_amount())
+        .with("currency", transaction.amount.currency.as_str())
+        .with("status", transaction.status.as_str())
+        .with(
+            "booking_date",
+            transaction.booking_date.map(|date| date.to_string()),
+        )
+        .with(
+            "value_date",
+            transaction.value_date.map(|date| date.to_string()),
+        )
+        .with("bank_transaction_code", text(&transaction.bank_tx_code))
+        .with(
+            "creditor_reference",
+            transaction
+                .creditor_reference
+                .as_ref()
+                .map(|reference| reference.reference.as_str()),
+        )
+        .with("debtor", text(&transaction.debtor_name))
+        .with("payee", text(transaction.payee()))
+        .with("remittance_info", transaction.remittance_info.clone())
+        .with("additional_info", text(&transaction.additional_info))
+        .with("category", text(&transaction.category))
+        .with("gl_account", text(&transaction.gl_account))
+}
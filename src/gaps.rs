@@ -0,0 +1,198 @@
+//! Detection of statements missing from an archive, so that a delivery the
+//! bank skipped is noticed.
+//!
+//! The statements of each account are put in order of their period. Within
+//! that order, electronic sequence numbers (ElctrncSeqNb) have to follow each
+//! other and each period has to start the day after the previous one ended.
+//! A sequence number lower than the one before starts a new count, as banks
+//! that count per year do in January, and is not reported.
+
+use crate::model::Statement;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// What is known about one statement of the archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    /// Where the statement was read from, such as its file
+    pub source: String,
+    pub iban: String,
+    /// Electronic sequence number, `None` if the statement has none that is
+    /// a number
+    pub sequence_number: Option<u64>,
+    /// First and last day of the statement period
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl Delivery {
+    pub fn of(source: &str, statement: &Statement) -> Delivery {
+        Delivery {
+            source: source.to_string(),
+            iban: statement.iban.clone(),
+            sequence_number: statement.sequence_number.trim().parse().ok(),
+            from: statement.from_datetime.map(|from| from.date_naive()),
+            to: statement.to_datetime.map(|to| to.date_naive()),
+        }
+    }
+}
+
+/// Something missing or twice between two statements of an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Gap {
+    /// Sequence numbers `first` to `last` are missing
+    Sequence {
+        iban: String,
+        first: u64,
+        last: u64,
+        after: String,
+        before: String,
+    },
+    /// Two statements have the same sequence number
+    Duplicate {
+        iban: String,
+        sequence_number: u64,
+        first: String,
+        second: String,
+    },
+    /// No statement covers the days `from` to `to`
+    Dates {
+        iban: String,
+        from: NaiveDate,
+        to: NaiveDate,
+        after: String,
+        before: String,
+    },
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Gap::Sequence {
+                iban,
+                first,
+                last,
+                after,
+                before,
+            } if first == last => write!(
+                f,
+                "{}: sequence number {} is missing between {} and {}",
+                iban, first, after, before
+            ),
+            Gap::Sequence {
+                iban,
+                first,
+                last,
+                after,
+                before,
+            } => write!(
+                f,
+                "{}: sequence numbers {} to {} are missing between {} and {}",
+                iban, first, last, after, before
+            ),
+            Gap::Duplicate {
+                iban,
+                sequence_number,
+                first,
+                second,
+            } => write!(
+                f,
+                "{}: sequence number {} is in both {} and {}",
+                iban, sequence_number, first, second
+            ),
+            Gap::Dates {
+                iban,
+                from,
+                to,
+                after,
+                before,
+            } if from == to => write!(
+                f,
+                "{}: no statement for {} between {} and {}",
+                iban, from, after, before
+            ),
+            Gap::Dates {
+                iban,
+                from,
+                to,
+                after,
+                before,
+            } => write!(
+                f,
+                "{}: no statement for {} to {} between {} and {}",
+                iban, from, to, after, before
+            ),
+        }
+    }
+}
+
+/// Gaps of the deliveries of each account, by account (IBAN, ignoring case
+/// and spaces) and in period order
+pub fn find_gaps(deliveries: &[Delivery]) -> Vec<Gap> {
+    let mut accounts: BTreeMap<String, Vec<&Delivery>> = BTreeMap::new();
+    for delivery in deliveries {
+        let iban: String = delivery
+            .iban
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        accounts
+            .entry(iban.to_ascii_uppercase())
+            .or_default()
+            .push(delivery);
+    }
+
+    let mut gaps = Vec::new();
+    for (iban, mut deliveries) in accounts {
+        deliveries.sort_by(|a, b| {
+            (a.from, a.sequence_number, &a.source).cmp(&(b.from, b.sequence_number, &b.source))
+        });
+
+        let numbered = deliveries
+            .iter()
+            .filter_map(|delivery| Some((delivery.sequence_number?, *delivery)));
+        for ((previous, after), (next, before)) in numbered.clone().zip(numbered.skip(1)) {
+            if next == previous {
+                gaps.push(Gap::Duplicate {
+                    iban: iban.clone(),
+                    sequence_number: next,
+                    first: after.source.clone(),
+                    second: before.source.clone(),
+                });
+            } else if next > previous + 1 {
+                gaps.push(Gap::Sequence {
+                    iban: iban.clone(),
+                    first: previous + 1,
+                    last: next - 1,
+                    after: after.source.clone(),
+                    before: before.source.clone(),
+                });
+            }
+        }
+
+        // Where periods overlap, the latest end so far counts
+        let mut covered: Option<(NaiveDate, &Delivery)> = None;
+        for delivery in &deliveries {
+            let (Some(from), Some(to)) = (delivery.from, delivery.to) else {
+                continue;
+            };
+            if let Some((end, after)) = covered
+                && let Some(missing_from) = end.succ_opt()
+                && from > missing_from
+            {
+                gaps.push(Gap::Dates {
+                    iban: iban.clone(),
+                    from: missing_from,
+                    to: from.pred_opt().unwrap_or(from),
+                    after: after.source.clone(),
+                    before: delivery.source.clone(),
+                });
+            }
+            if covered.is_none_or(|(end, _)| to > end) {
+                covered = Some((to, delivery));
+            }
+        }
+    }
+    gaps
+}
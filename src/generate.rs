@@ -0,0 +1,284 @@
+//! Random but realistic camt.053.001.10 statements, so that fixtures can be
+//! made without real bank data.
+//!
+//! Accounts have valid IBANs, amounts have the decimal places of their
+//! currency and the closing balance is the opening balance plus the entries.
+//! The same options and seed always give the same document.
+
+use crate::currency::minor_units;
+use crate::decimal::Decimal;
+use crate::error::Result;
+use crate::iban::iban_from_bban;
+use chrono::{Datelike, Days, NaiveDate};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::writer::Writer;
+use std::io::Write;
+
+const CAMT_10_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:camt.053.001.10";
+
+const OWNERS: &[&str] = &[
+    "Muster AG",
+    "Beispiel GmbH",
+    "Bäckerei Alpenblick",
+    "Nordlicht Consulting",
+    "Seeblick Immobilien AG",
+];
+
+const COUNTERPARTIES: &[&str] = &[
+    "ACME & Co",
+    "Coop Zürich",
+    "Migros",
+    "Swisscom",
+    "SBB CFF FFS",
+    "Müller Treuhand",
+    "Stadtwerke",
+    "Galaxus",
+];
+
+/// What to generate
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    pub entries: usize,
+    /// Currency of the account and all its entries
+    pub currency: String,
+    /// Last day of the statement period, which starts on the first of its
+    /// month
+    pub date: NaiveDate,
+    pub seed: u64,
+}
+
+/// Write a statement with random entries as a camt.053.001.10 document
+pub fn generate_camt_10<W: Write>(output: W, options: &GenerateOptions) -> Result<()> {
+    let mut random = Random(options.seed);
+    let units = minor_units(&options.currency).unwrap_or(2);
+    let currency = options.currency.as_str();
+    let to = options.date;
+    let from = to.with_day(1).expect("every month has a first day");
+    let created = format!(
+        "{}T06:00:00Z",
+        to.checked_add_days(Days::new(1)).unwrap_or(to)
+    );
+
+    // Accounts in euros are German, all others Swiss
+    let (iban, bic) = if currency == "EUR" {
+        (iban_from_bban("DE", &random.digits(18)), "TESTDEFFXXX")
+    } else {
+        (iban_from_bban("CH", &random.digits(17)), "TESTCHZZXXX")
+    };
+    let owner = random.pick(OWNERS);
+
+    let mut booking_days: Vec<u64> = (0..options.entries)
+        .map(|_| random.below((to - from).num_days() as u64 + 1))
+        .collect();
+    booking_days.sort_unstable();
+
+    let mut writer = Writer::new_with_indent(output, b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    let mut document = BytesStart::new("Document");
+    document.push_attribute(("xmlns", CAMT_10_NAMESPACE));
+    writer.write_event(Event::Start(document))?;
+    start(&mut writer, "BkToCstmrStmt")?;
+
+    start(&mut writer, "GrpHdr")?;
+    element(
+        &mut writer,
+        "MsgId",
+        &format!("MSG-{}-{:04}", to.format("%Y%m%d"), options.seed % 10_000),
+    )?;
+    element(&mut writer, "CreDtTm", &created)?;
+    end(&mut writer, "GrpHdr")?;
+
+    start(&mut writer, "Stmt")?;
+    element(
+        &mut writer,
+        "Id",
+        &format!("STMT-{}", to.format("%Y-%m-%d")),
+    )?;
+    element(&mut writer, "CreDtTm", &created)?;
+    start(&mut writer, "FrToDt")?;
+    element(&mut writer, "FrDtTm", &format!("{}T00:00:00Z", from))?;
+    element(&mut writer, "ToDtTm", &format!("{}T23:59:59Z", to))?;
+    end(&mut writer, "FrToDt")?;
+
+    start(&mut writer, "Acct")?;
+    start(&mut writer, "Id")?;
+    element(&mut writer, "IBAN", &iban)?;
+    end(&mut writer, "Id")?;
+    element(&mut writer, "Ccy", currency)?;
+    start(&mut writer, "Ownr")?;
+    element(&mut writer, "Nm", owner)?;
+    end(&mut writer, "Ownr")?;
+    start(&mut writer, "Svcr")?;
+    start(&mut writer, "FinInstnId")?;
+    element(&mut writer, "BICFI", bic)?;
+    end(&mut writer, "FinInstnId")?;
+    end(&mut writer, "Svcr")?;
+    end(&mut writer, "Acct")?;
+
+    // Entries are drawn first, as the closing balance depends on them
+    let entries: Vec<Entry> = booking_days
+        .iter()
+        .map(|day| Entry::random(&mut random, from + Days::new(*day), units, currency))
+        .collect();
+    let opening = random.amount(1_000, 50_000, units) as i128;
+    let closing = opening + entries.iter().map(|entry| entry.amount).sum::<i128>();
+    balance(&mut writer, "OPBD", opening, units, currency, from)?;
+    balance(&mut writer, "CLBD", closing, units, currency, to)?;
+
+    for entry in &entries {
+        entry.write(&mut writer, units, currency)?;
+    }
+
+    end(&mut writer, "Stmt")?;
+    end(&mut writer, "BkToCstmrStmt")?;
+    end(&mut writer, "Document")?;
+    let mut output = writer.into_inner();
+    output.write_all(b"\n")?;
+    output.flush()?;
+    Ok(())
+}
+
+struct Entry {
+    // Signed amount in minor units
+    amount: i128,
+    date: NaiveDate,
+    code: String,
+    info: String,
+}
+
+impl Entry {
+    fn random(random: &mut Random, date: NaiveDate, units: u32, currency: &str) -> Entry {
+        let counterparty = random.pick(COUNTERPARTIES);
+        let number = random.below(10_000);
+        let kind = random.below(100);
+        let (amount, code, info) = if kind < 30 {
+            let amount = random.amount(50, 5_000, units) as i128;
+            let info = format!("Payment from {}, invoice {}", counterparty, number);
+            (amount, format!("TRANSFER-{:04}", number), info)
+        } else if kind < 55 {
+            let amount = -(random.amount(20, 3_000, units) as i128);
+            let info = format!("Payment to {}", counterparty);
+            (amount, format!("TRANSFER-{:04}", number), info)
+        } else if kind < 95 {
+            let amount = random.amount(5, 300, units) as i128;
+            let info = format!(
+                "Card transaction of {} {} issued by {}",
+                Decimal::new(amount, units),
+                currency,
+                counterparty
+            );
+            (-amount, format!("CARD-{:04}", number), info)
+        } else {
+            let amount = -(random.amount(1, 20, units) as i128);
+            (amount, "FEE".to_string(), "Account fee".to_string())
+        };
+        Entry {
+            amount,
+            date,
+            code,
+            info,
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut Writer<W>, units: u32, currency: &str) -> Result<()> {
+        start(writer, "Ntry")?;
+        amount(writer, self.amount, units, currency)?;
+        start(writer, "Sts")?;
+        element(writer, "Cd", "BOOK")?;
+        end(writer, "Sts")?;
+        for name in ["BookgDt", "ValDt"] {
+            start(writer, name)?;
+            element(writer, "Dt", &self.date.to_string())?;
+            end(writer, name)?;
+        }
+        start(writer, "BkTxCd")?;
+        start(writer, "Prtry")?;
+        element(writer, "Cd", &self.code)?;
+        end(writer, "Prtry")?;
+        end(writer, "BkTxCd")?;
+        element(writer, "AddtlNtryInf", &self.info)?;
+        end(writer, "Ntry")
+    }
+}
+
+fn balance<W: Write>(
+    writer: &mut Writer<W>,
+    code: &str,
+    value: i128,
+    units: u32,
+    currency: &str,
+    date: NaiveDate,
+) -> Result<()> {
+    start(writer, "Bal")?;
+    start(writer, "Tp")?;
+    start(writer, "CdOrPrtry")?;
+    element(writer, "Cd", code)?;
+    end(writer, "CdOrPrtry")?;
+    end(writer, "Tp")?;
+    amount(writer, value, units, currency)?;
+    start(writer, "Dt")?;
+    element(writer, "Dt", &date.to_string())?;
+    end(writer, "Dt")?;
+    end(writer, "Bal")
+}
+
+// Amt and CdtDbtInd of a signed amount in minor units
+fn amount<W: Write>(writer: &mut Writer<W>, value: i128, units: u32, currency: &str) -> Result<()> {
+    let mut amount = BytesStart::new("Amt");
+    amount.push_attribute(("Ccy", currency));
+    writer.write_event(Event::Start(amount))?;
+    let text = Decimal::new(value.abs(), units).to_string();
+    writer.write_event(Event::Text(BytesText::new(&text)))?;
+    end(writer, "Amt")?;
+    element(writer, "CdtDbtInd", if value < 0 { "DBIT" } else { "CRDT" })
+}
+
+fn start<W: Write>(writer: &mut Writer<W>, name: &str) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    Ok(())
+}
+
+fn end<W: Write>(writer: &mut Writer<W>, name: &str) -> Result<()> {
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+fn element<W: Write>(writer: &mut Writer<W>, name: &str, value: &str) -> Result<()> {
+    start(writer, name)?;
+    writer.write_event(Event::Text(BytesText::new(value)))?;
+    end(writer, name)
+}
+
+// SplitMix64, small and good enough for test data
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // A number from 0 to `bound` - 1
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn pick<'a>(&mut self, values: &[&'a str]) -> &'a str {
+        values[self.below(values.len() as u64) as usize]
+    }
+
+    fn digits(&mut self, count: usize) -> String {
+        (0..count)
+            .map(|_| char::from(b'0' + self.below(10) as u8))
+            .collect()
+    }
+
+    // An amount from `min` to `max` in minor units
+    fn amount(&mut self, min: u64, max: u64, units: u32) -> u64 {
+        let scale = 10u64.pow(units);
+        self.below((max - min) * scale + 1) + min * scale
+    }
+}
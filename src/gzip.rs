@@ -0,0 +1,129 @@
+// Decompression of gzip input (RFC 1952).
+//
+// Archived statements are often stored gzip-compressed. The reader detects
+// them by their magic bytes and decompresses them on the fly, so they never
+// have to be unpacked to temporary files.
+
+use crate::inflate::{BitReader, Inflater};
+use std::io::{self, BufRead, Read};
+
+const MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+const FLAG_HEADER_CRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+/// Whether a document starts with the gzip magic bytes
+pub fn is_gzip(prefix: &[u8]) -> bool {
+    prefix.starts_with(&MAGIC)
+}
+
+/// Reader that decompresses gzip input.
+///
+/// Concatenated gzip members are read as one stream. Bytes after the last
+/// member that do not start another member are ignored, like `gunzip` does.
+pub struct GzDecoder<R> {
+    inflater: Inflater<R>,
+    // Whether the header of the current member has been read
+    in_member: bool,
+}
+
+impl<R: BufRead> GzDecoder<R> {
+    pub fn new(inner: R) -> Self {
+        GzDecoder {
+            inflater: Inflater::new(inner),
+            in_member: false,
+        }
+    }
+
+    // Check the trailer of a finished member. Returns whether another member
+    // follows.
+    fn finish_member(&mut self) -> io::Result<bool> {
+        let input = self.inflater.input();
+        let crc = input.u32_le()?;
+        let size = input.u32_le()?;
+        if crc != self.inflater.crc() {
+            return Err(invalid("checksum mismatch"));
+        }
+        if size != self.inflater.size() as u32 {
+            return Err(invalid("length mismatch"));
+        }
+        self.in_member = false;
+        Ok(is_gzip(self.inflater.input().inner().fill_buf()?))
+    }
+}
+
+impl<R: BufRead> Read for GzDecoder<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for GzDecoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        loop {
+            if !self.in_member {
+                if self.inflater.is_finished() {
+                    // Past the last member
+                    break;
+                }
+                read_header(self.inflater.input())?;
+                self.inflater.restart();
+                self.in_member = true;
+            }
+            if !self.inflater.fill_buf()?.is_empty() || !self.finish_member()? {
+                break;
+            }
+            self.inflater.restart();
+        }
+        self.inflater.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inflater.consume(amt);
+    }
+}
+
+fn read_header<R: BufRead>(input: &mut BitReader<R>) -> io::Result<()> {
+    let mut header = [0u8; 10];
+    for b in &mut header {
+        *b = input.byte()?;
+    }
+    if !is_gzip(&header) {
+        return Err(invalid("not a gzip stream"));
+    }
+    if header[2] != 8 {
+        return Err(invalid("unsupported compression method"));
+    }
+    let flags = header[3];
+    if flags & 0xE0 != 0 {
+        return Err(invalid("reserved header flags are set"));
+    }
+    if flags & FLAG_EXTRA != 0 {
+        let length = input.u16_le()?;
+        for _ in 0..length {
+            input.byte()?;
+        }
+    }
+    for flag in [FLAG_NAME, FLAG_COMMENT] {
+        if flags & flag != 0 {
+            while input.byte()? != 0 {}
+        }
+    }
+    if flags & FLAG_HEADER_CRC != 0 {
+        input.u16_le()?;
+    }
+    Ok(())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid gzip input: {}", message),
+    )
+}
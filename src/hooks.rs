@@ -0,0 +1,574 @@
+//! Rules changing field values of statements and entries before they are
+//! written, read from a hooks file so that values can be adjusted without
+//! recompiling.
+//!
+//! Each line holds one rule: the hook it belongs to, an optional condition
+//! and an assignment. Rules run in file order, each seeing the changes of
+//! those before it.
+//!
+//! ```text
+//! # Card payments all get the same code
+//! transaction: bank_tx_code starts_with "CARD" => bank_tx_code = "CARD"
+//! transaction: additional_info = replace(additional_info, "Zürich", "ZH")
+//! transaction: remittance == "" => remittance = additional_info
+//! statement: owner_name = upper(owner_name)
+//! ```
+//!
+//! Values are text. Expressions are string literals, field names, the
+//! functions `replace(value, "from", "to")`, `upper(value)`, `lower(value)`
+//! and `trim(value)`, joined with `+`. Conditions compare an expression with
+//! `==`, `!=`, `contains`, `starts_with` or `ends_with` to a string literal.
+
+use crate::model::{CreditDebit, Statement, Transaction};
+use crate::reader::StatementHandler;
+use std::fmt;
+use std::str::FromStr;
+
+/// Fields of the `statement` hook, the writable ones first
+pub const STATEMENT_FIELDS: &[&str] = &["id", "owner_name", "servicer_bic", "iban", "currency"];
+
+/// Fields of the `transaction` hook, the writable ones first
+pub const TRANSACTION_FIELDS: &[&str] = &[
+    "additional_info",
+    "transaction_info",
+    "remittance",
+    "bank_tx_code",
+    "amount",
+    "currency",
+    "credit_debit",
+    "status",
+    "booking_date",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Hook {
+    Statement,
+    Transaction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    OwnerName,
+    ServicerBic,
+    Iban,
+    Currency,
+    AdditionalInfo,
+    TransactionInfo,
+    Remittance,
+    BankTxCode,
+    Amount,
+    CreditDebit,
+    Status,
+    BookingDate,
+}
+
+impl Field {
+    fn from_name(hook: Hook, name: &str) -> Option<Field> {
+        Some(match (hook, name) {
+            (Hook::Statement, "id") => Field::Id,
+            (Hook::Statement, "owner_name") => Field::OwnerName,
+            (Hook::Statement, "servicer_bic") => Field::ServicerBic,
+            (Hook::Statement, "iban") => Field::Iban,
+            (_, "currency") => Field::Currency,
+            (Hook::Transaction, "additional_info") => Field::AdditionalInfo,
+            (Hook::Transaction, "transaction_info") => Field::TransactionInfo,
+            (Hook::Transaction, "remittance") => Field::Remittance,
+            (Hook::Transaction, "bank_tx_code") => Field::BankTxCode,
+            (Hook::Transaction, "amount") => Field::Amount,
+            (Hook::Transaction, "credit_debit") => Field::CreditDebit,
+            (Hook::Transaction, "status") => Field::Status,
+            (Hook::Transaction, "booking_date") => Field::BookingDate,
+            _ => return None,
+        })
+    }
+
+    fn is_writable(self) -> bool {
+        matches!(
+            self,
+            Field::Id
+                | Field::OwnerName
+                | Field::ServicerBic
+                | Field::AdditionalInfo
+                | Field::TransactionInfo
+                | Field::Remittance
+                | Field::BankTxCode
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Text(String),
+    Field(Field),
+    Replace(Box<Expr>, String, String),
+    Upper(Box<Expr>),
+    Lower(Box<Expr>),
+    Trim(Box<Expr>),
+    Concat(Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equals,
+    NotEquals,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Condition {
+    value: Expr,
+    operator: Operator,
+    operand: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    hook: Hook,
+    condition: Option<Condition>,
+    field: Field,
+    value: Expr,
+}
+
+/// Rules of a hooks file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hooks {
+    rules: Vec<Rule>,
+}
+
+/// A hooks file that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookError(String);
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for HookError {}
+
+impl FromStr for Hooks {
+    type Err = HookError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut rules = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule = parse_rule(line).map_err(|message| {
+                HookError(format!("Line {} of hooks: {}", index + 1, message))
+            })?;
+            rules.push(rule);
+        }
+        Ok(Hooks { rules })
+    }
+}
+
+impl Hooks {
+    /// Whether there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply the `statement` rules
+    pub fn apply_statement(&self, statement: &mut Statement) {
+        self.apply(Hook::Statement, statement);
+    }
+
+    /// Apply the `transaction` rules
+    pub fn apply_transaction(&self, transaction: &mut Transaction) {
+        self.apply(Hook::Transaction, transaction);
+    }
+
+    fn apply(&self, hook: Hook, record: &mut impl Fields) {
+        for rule in self.rules.iter().filter(|rule| rule.hook == hook) {
+            if rule
+                .condition
+                .as_ref()
+                .is_none_or(|condition| condition.holds(record))
+            {
+                let value = rule.value.eval(record);
+                record.set(rule.field, value);
+            }
+        }
+    }
+}
+
+/// Applies hooks to a statement and its entries as they are read, before
+/// passing them on
+pub struct Hooked<'a, H> {
+    hooks: &'a Hooks,
+    pub inner: H,
+    // The statement as changed by the hooks, passed along with the entries
+    statement: Statement,
+}
+
+impl<'a, H> Hooked<'a, H> {
+    pub fn new(hooks: &'a Hooks, inner: H) -> Self {
+        Hooked {
+            hooks,
+            inner,
+            statement: Statement::default(),
+        }
+    }
+}
+
+impl<H: StatementHandler> StatementHandler for Hooked<'_, H> {
+    fn begin(&mut self, statement: &Statement) -> crate::Result<()> {
+        if self.hooks.is_empty() {
+            return self.inner.begin(statement);
+        }
+        self.statement = statement.clone();
+        self.hooks.apply_statement(&mut self.statement);
+        self.inner.begin(&self.statement)
+    }
+
+    fn entry(&mut self, statement: &Statement, mut transaction: Transaction) -> crate::Result<()> {
+        if self.hooks.is_empty() {
+            return self.inner.entry(statement, transaction);
+        }
+        self.hooks.apply_transaction(&mut transaction);
+        self.inner.entry(&self.statement, transaction)
+    }
+
+    fn end(&mut self, statement: &Statement) -> crate::Result<()> {
+        if self.hooks.is_empty() {
+            return self.inner.end(statement);
+        }
+        self.statement = statement.clone();
+        self.hooks.apply_statement(&mut self.statement);
+        self.inner.end(&self.statement)
+    }
+}
+
+trait Fields {
+    fn get(&self, field: Field) -> String;
+    fn set(&mut self, field: Field, value: String);
+}
+
+impl Fields for Statement {
+    fn get(&self, field: Field) -> String {
+        match field {
+            Field::Id => self.id.clone(),
+            Field::OwnerName => self.owner_name.clone(),
+            Field::ServicerBic => self.servicer_bic.clone(),
+            Field::Iban => self.iban.clone(),
+            Field::Currency => self.currency.clone(),
+            _ => String::new(),
+        }
+    }
+
+    fn set(&mut self, field: Field, value: String) {
+        match field {
+            Field::Id => self.id = value,
+            Field::OwnerName => self.owner_name = value,
+            Field::ServicerBic => self.servicer_bic = value,
+            _ => {}
+        }
+    }
+}
+
+impl Fields for Transaction {
+    fn get(&self, field: Field) -> String {
+        match field {
+            Field::AdditionalInfo => self.additional_info.clone(),
+            Field::TransactionInfo => self.transaction_info.clone(),
+            Field::Remittance => self.remittance_info.join("\n"),
+            Field::BankTxCode => self.bank_tx_code.clone(),
+            Field::Amount => self.amount.value.to_string(),
+            Field::Currency => self.amount.currency.clone(),
+            Field::CreditDebit => match self.credit_debit_ind {
+                Some(CreditDebit::Credit) => "CRDT".to_string(),
+                Some(CreditDebit::Debit) => "DBIT".to_string(),
+                None => String::new(),
+            },
+            Field::Status => self.status.as_str().to_string(),
+            Field::BookingDate => self
+                .booking_date
+                .map_or_else(String::new, |date| date.date().to_string()),
+            _ => String::new(),
+        }
+    }
+
+    fn set(&mut self, field: Field, value: String) {
+        match field {
+            Field::AdditionalInfo => self.additional_info = value,
+            Field::TransactionInfo => self.transaction_info = value,
+            // One Ustrd per line, none for an empty value
+            Field::Remittance => {
+                self.remittance_info = value
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            Field::BankTxCode => self.bank_tx_code = value,
+            _ => {}
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, record: &impl Fields) -> String {
+        match self {
+            Expr::Text(text) => text.clone(),
+            Expr::Field(field) => record.get(*field),
+            Expr::Replace(value, from, to) => value.eval(record).replace(from.as_str(), to),
+            Expr::Upper(value) => value.eval(record).to_uppercase(),
+            Expr::Lower(value) => value.eval(record).to_lowercase(),
+            Expr::Trim(value) => value.eval(record).trim().to_string(),
+            Expr::Concat(values) => values.iter().map(|value| value.eval(record)).collect(),
+        }
+    }
+}
+
+impl Condition {
+    fn holds(&self, record: &impl Fields) -> bool {
+        let value = self.value.eval(record);
+        let operand = self.operand.as_str();
+        match self.operator {
+            Operator::Equals => value == operand,
+            Operator::NotEquals => value != operand,
+            Operator::Contains => value.contains(operand),
+            Operator::StartsWith => value.starts_with(operand),
+            Operator::EndsWith => value.ends_with(operand),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Text(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Name(name) => f.write_str(name),
+            Token::Text(text) => write!(f, "{:?}", text),
+            Token::Symbol(symbol) => f.write_str(symbol),
+        }
+    }
+}
+
+// Longer symbols first, so that `==` is not read as two `=`
+const SYMBOLS: &[&str] = &["==", "!=", "=>", "=", ":", "(", ")", ",", "+"];
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '#' {
+            break;
+        } else if c == '"' {
+            let mut text = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, escaped @ ('"' | '\\'))) => text.push(escaped),
+                        _ => return Err("only \\\" and \\\\ can be escaped".to_string()),
+                    },
+                    Some((_, c)) => text.push(c),
+                    None => return Err("unclosed string".to_string()),
+                }
+            };
+            tokens.push(Token::Text(text));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            return Err(format!("unexpected character {:?}", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    hook: Hook,
+}
+
+fn parse_rule(line: &str) -> Result<Rule, String> {
+    let tokens = tokenize(line)?;
+    let hook = match tokens.first() {
+        Some(Token::Name(name)) if name == "statement" => Hook::Statement,
+        Some(Token::Name(name)) if name == "transaction" => Hook::Transaction,
+        _ => return Err("expected statement: or transaction: at the start".to_string()),
+    };
+    let mut parser = Parser {
+        tokens,
+        position: 1,
+        hook,
+    };
+    parser.expect(":")?;
+
+    // A condition comes before =>, if there is one
+    let condition = if parser.tokens.contains(&Token::Symbol("=>")) {
+        let value = parser.expr()?;
+        let operator = parser.operator()?;
+        let operand = parser.text()?;
+        parser.expect("=>")?;
+        Some(Condition {
+            value,
+            operator,
+            operand,
+        })
+    } else {
+        None
+    };
+
+    let field = parser.field()?;
+    if !field.is_writable() {
+        return Err(format!(
+            "{} cannot be changed",
+            parser.tokens[parser.position - 1]
+        ));
+    }
+    parser.expect("=")?;
+    let value = parser.expr()?;
+    if let Some(token) = parser.tokens.get(parser.position) {
+        return Err(format!("unexpected {} after the value", token));
+    }
+    Ok(Rule {
+        hook,
+        condition,
+        field,
+        value,
+    })
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_symbol(&self, symbol: &str) -> bool {
+        matches!(self.tokens.get(self.position), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Symbol(s)) if s == symbol => Ok(()),
+            Some(token) => Err(format!("expected {} instead of {}", symbol, token)),
+            None => Err(format!("expected {} at the end", symbol)),
+        }
+    }
+
+    fn text(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Text(text)) => Ok(text),
+            Some(token) => Err(format!("expected a string instead of {}", token)),
+            None => Err("expected a string at the end".to_string()),
+        }
+    }
+
+    fn field(&mut self) -> Result<Field, String> {
+        match self.next() {
+            Some(Token::Name(name)) => self.field_named(&name),
+            Some(token) => Err(format!("expected a field instead of {}", token)),
+            None => Err("expected a field at the end".to_string()),
+        }
+    }
+
+    fn field_named(&self, name: &str) -> Result<Field, String> {
+        Field::from_name(self.hook, name).ok_or_else(|| {
+            let (hook, fields) = match self.hook {
+                Hook::Statement => ("statement", STATEMENT_FIELDS),
+                Hook::Transaction => ("transaction", TRANSACTION_FIELDS),
+            };
+            format!(
+                "unknown {} field {}, expected one of: {}",
+                hook,
+                name,
+                fields.join(", ")
+            )
+        })
+    }
+
+    fn operator(&mut self) -> Result<Operator, String> {
+        Ok(match self.next() {
+            Some(Token::Symbol("==")) => Operator::Equals,
+            Some(Token::Symbol("!=")) => Operator::NotEquals,
+            Some(Token::Name(name)) if name == "contains" => Operator::Contains,
+            Some(Token::Name(name)) if name == "starts_with" => Operator::StartsWith,
+            Some(Token::Name(name)) if name == "ends_with" => Operator::EndsWith,
+            Some(token) => {
+                return Err(format!(
+                    "expected ==, !=, contains, starts_with or ends_with instead of {}",
+                    token
+                ));
+            }
+            None => return Err("expected a comparison at the end".to_string()),
+        })
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut values = vec![self.term()?];
+        while self.peek_symbol("+") {
+            self.position += 1;
+            values.push(self.term()?);
+        }
+        Ok(if values.len() == 1 {
+            values.remove(0)
+        } else {
+            Expr::Concat(values)
+        })
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let name = match self.next() {
+            Some(Token::Text(text)) => return Ok(Expr::Text(text)),
+            Some(Token::Name(name)) => name,
+            Some(token) => return Err(format!("expected a value instead of {}", token)),
+            None => return Err("expected a value at the end".to_string()),
+        };
+        if !self.peek_symbol("(") {
+            return self.field_named(&name).map(Expr::Field);
+        }
+        self.position += 1;
+        let value = Box::new(self.expr()?);
+        let expr = match name.as_str() {
+            "replace" => {
+                self.expect(",")?;
+                let from = self.text()?;
+                self.expect(",")?;
+                let to = self.text()?;
+                if from.is_empty() {
+                    return Err("replace needs text to replace".to_string());
+                }
+                Expr::Replace(value, from, to)
+            }
+            "upper" => Expr::Upper(value),
+            "lower" => Expr::Lower(value),
+            "trim" => Expr::Trim(value),
+            _ => {
+                return Err(format!(
+                    "unknown function {}, expected replace, upper, lower or trim",
+                    name
+                ));
+            }
+        };
+        self.expect(")")?;
+        Ok(expr)
+    }
+}
@@ -0,0 +1,117 @@
+//! Structure and check digits of International Bank Account Numbers
+//! (ISO 13616), so that mistyped or corrupted account numbers are noticed.
+
+use std::fmt;
+
+/// Why an IBAN is invalid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IbanError {
+    /// Not a country code and check digits followed by letters and digits
+    Format,
+    /// Not the length of IBANs of its country
+    Length { expected: usize, actual: usize },
+    /// The check digits do not match the rest of the IBAN
+    Checksum,
+}
+
+impl fmt::Display for IbanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IbanError::Format => write!(f, "not an IBAN"),
+            IbanError::Length { expected, actual } => write!(
+                f,
+                "{} characters instead of {} for its country",
+                actual, expected
+            ),
+            IbanError::Checksum => write!(f, "check digits do not match"),
+        }
+    }
+}
+
+impl std::error::Error for IbanError {}
+
+/// Check the structure and check digits of an IBAN in electronic form,
+/// without spaces
+pub fn validate_iban(iban: &str) -> Result<(), IbanError> {
+    let bytes = iban.as_bytes();
+    let is_well_formed = (5..=34).contains(&bytes.len())
+        && bytes[..2].iter().all(u8::is_ascii_uppercase)
+        && bytes[2..4].iter().all(u8::is_ascii_digit)
+        && bytes[4..].iter().all(u8::is_ascii_alphanumeric);
+    if !is_well_formed {
+        return Err(IbanError::Format);
+    }
+    // Countries that are not in the IBAN registry are only checksummed
+    if let Some(expected) = country_length(&iban[..2])
+        && expected != bytes.len()
+    {
+        return Err(IbanError::Length {
+            expected,
+            actual: bytes.len(),
+        });
+    }
+
+    // Moved to the end, a valid IBAN has a remainder of 1 when divided by 97
+    if remainder_97(bytes[4..].iter().chain(&bytes[..4])) != 1 {
+        return Err(IbanError::Checksum);
+    }
+    Ok(())
+}
+
+/// The IBAN of an account with the national account number `bban` in
+/// `country`, e.g. `CH93` followed by `00762011623852957`
+pub fn iban_from_bban(country: &str, bban: &str) -> String {
+    let remainder = remainder_97(
+        bban.as_bytes()
+            .iter()
+            .chain(country.as_bytes())
+            .chain(b"00"),
+    );
+    format!("{}{:02}{}", country, 98 - remainder, bban)
+}
+
+/// Whether an IBAN is a Swiss or Liechtenstein QR-IBAN, whose institution
+/// identification is from 30000 to 31999. Payments to it carry a QR
+/// reference.
+pub fn is_qr_iban(iban: &str) -> bool {
+    let iban = iban.replace(' ', "");
+    (iban.starts_with("CH") || iban.starts_with("LI"))
+        && iban
+            .get(4..9)
+            .and_then(|iid| iid.parse::<u32>().ok())
+            .is_some_and(|iid| (30000..=31999).contains(&iid))
+}
+
+// Remainder of the number with letters as numbers from 10 divided by 97
+pub(crate) fn remainder_97<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    bytes.fold(0, |remainder, &b| match b {
+        b'0'..=b'9' => (remainder * 10 + (b - b'0') as u32) % 97,
+        _ => (remainder * 100 + (b.to_ascii_uppercase() - b'A' + 10) as u32) % 97,
+    })
+}
+
+// Length of IBANs by country, from the SWIFT IBAN registry
+fn country_length(country: &str) -> Option<usize> {
+    let length = match country {
+        "NO" => 15,
+        "BE" => 16,
+        "DK" | "FI" | "FK" | "FO" | "GL" | "NL" | "SD" => 18,
+        "MK" | "SI" => 19,
+        "AT" | "BA" | "EE" | "KZ" | "LT" | "LU" | "MN" | "XK" => 20,
+        "CH" | "HR" | "LI" | "LV" => 21,
+        "BG" | "BH" | "CR" | "DE" | "GB" | "GE" | "IE" | "ME" | "RS" | "VA" => 22,
+        "AE" | "GI" | "IL" | "IQ" | "OM" | "SO" | "TL" => 23,
+        "AD" | "CZ" | "ES" | "MD" | "PK" | "RO" | "SA" | "SE" | "SK" | "TN" | "VG" => 24,
+        "LY" | "PT" | "ST" => 25,
+        "IS" | "TR" => 26,
+        "BI" | "DJ" | "FR" | "GR" | "IT" | "MC" | "MR" | "SM" => 27,
+        "AL" | "AZ" | "BY" | "CY" | "DO" | "GT" | "HN" | "HU" | "LB" | "NI" | "PL" | "SV" => 28,
+        "BR" | "EG" | "PS" | "QA" | "UA" => 29,
+        "JO" | "KW" | "MU" | "YE" => 30,
+        "MT" | "SC" => 31,
+        "LC" => 32,
+        "RU" => 33,
+        _ => return None,
+    };
+    Some(length)
+}
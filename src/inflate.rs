@@ -0,0 +1,413 @@
+// Decompression of raw deflate data (RFC 1951), shared by the gzip and zip
+// readers.
+
+use std::io::{self, BufRead, Read};
+
+// Deflate keeps back references within the last 32 KiB of output
+const WINDOW_SIZE: usize = 32 * 1024;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// Order in which code length code lengths are stored in dynamic blocks
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+enum State {
+    BlockHeader,
+    Stored(usize),
+    Compressed(Huffman, Huffman),
+    Done,
+}
+
+/// Reader that decompresses one deflate stream and keeps the CRC-32 and
+/// length of its output. After the final block the input is left at the next
+/// byte boundary, see [`Inflater::input`].
+pub(crate) struct Inflater<R> {
+    input: BitReader<R>,
+    state: State,
+    last_block: bool,
+    // Decompressed output. Bytes before `pos` have been consumed and are only
+    // kept as the window for back references.
+    buf: Vec<u8>,
+    pos: usize,
+    crc: u32,
+    size: u64,
+}
+
+impl<R: BufRead> Inflater<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Inflater {
+            input: BitReader {
+                inner,
+                bits: 0,
+                count: 0,
+            },
+            state: State::BlockHeader,
+            last_block: false,
+            buf: Vec::new(),
+            pos: 0,
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    /// The compressed input, for reading the framing around the stream
+    pub(crate) fn input(&mut self) -> &mut BitReader<R> {
+        &mut self.input
+    }
+
+    /// Whether the final block has been read
+    pub(crate) fn is_finished(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// Start reading another deflate stream from the input
+    pub(crate) fn restart(&mut self) {
+        self.state = State::BlockHeader;
+        self.last_block = false;
+        self.buf.clear();
+        self.pos = 0;
+        self.crc = 0;
+        self.size = 0;
+    }
+
+    /// CRC-32 of the output produced so far
+    pub(crate) fn crc(&self) -> u32 {
+        self.crc
+    }
+
+    /// Number of bytes produced so far
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    // Decompress until at least a window of output is pending or the stream
+    // ends
+    fn decompress(&mut self) -> io::Result<()> {
+        if self.buf.len() > WINDOW_SIZE {
+            self.buf.drain(..self.buf.len() - WINDOW_SIZE);
+            self.pos = self.buf.len();
+        }
+        let start = self.buf.len();
+
+        while self.buf.len() - self.pos < WINDOW_SIZE {
+            match &mut self.state {
+                State::BlockHeader => {
+                    if self.last_block {
+                        self.input.align();
+                        self.state = State::Done;
+                        continue;
+                    }
+                    self.last_block = self.input.bits(1)? == 1;
+                    self.state = match self.input.bits(2)? {
+                        0 => {
+                            self.input.align();
+                            let length = self.input.u16_le()?;
+                            let complement = self.input.u16_le()?;
+                            if length != !complement {
+                                return Err(invalid("stored block length does not match"));
+                            }
+                            State::Stored(length as usize)
+                        }
+                        1 => {
+                            State::Compressed(Huffman::fixed_literals(), Huffman::fixed_distances())
+                        }
+                        2 => {
+                            let (literals, distances) = self.input.dynamic_tables()?;
+                            State::Compressed(literals, distances)
+                        }
+                        _ => return Err(invalid("invalid block type")),
+                    };
+                }
+                State::Stored(remaining) => {
+                    if *remaining == 0 {
+                        self.state = State::BlockHeader;
+                        continue;
+                    }
+                    *remaining -= 1;
+                    self.buf.push(self.input.byte()?);
+                }
+                State::Compressed(literals, distances) => {
+                    let symbol = literals.decode(&mut self.input)?;
+                    match symbol {
+                        0..=255 => self.buf.push(symbol as u8),
+                        256 => self.state = State::BlockHeader,
+                        _ => {
+                            let index = symbol as usize - 257;
+                            if index >= LENGTH_BASE.len() {
+                                return Err(invalid("invalid length code"));
+                            }
+                            let length = LENGTH_BASE[index] as usize
+                                + self.input.bits(LENGTH_EXTRA[index])? as usize;
+                            let index = distances.decode(&mut self.input)? as usize;
+                            if index >= DISTANCE_BASE.len() {
+                                return Err(invalid("invalid distance code"));
+                            }
+                            let distance = DISTANCE_BASE[index] as usize
+                                + self.input.bits(DISTANCE_EXTRA[index])? as usize;
+                            if distance > self.buf.len() {
+                                return Err(invalid("distance too far back"));
+                            }
+                            // Copies may overlap the bytes they produce
+                            let from = self.buf.len() - distance;
+                            for i in 0..length {
+                                self.buf.push(self.buf[from + i]);
+                            }
+                        }
+                    }
+                }
+                State::Done => break,
+            }
+        }
+
+        let produced = &self.buf[start..];
+        self.crc = crc32(self.crc, produced);
+        self.size += produced.len() as u64;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Inflater<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Inflater<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.buf.len() {
+            self.decompress()?;
+        }
+        Ok(&self.buf[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.buf.len());
+    }
+}
+
+/// Reads the input least significant bit first, as deflate stores it
+pub(crate) struct BitReader<R> {
+    inner: R,
+    bits: u32,
+    count: u8,
+}
+
+impl<R: BufRead> BitReader<R> {
+    fn bits(&mut self, n: u8) -> io::Result<u32> {
+        while self.count < n {
+            self.bits |= (self.next_byte()? as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u32 << n) - 1);
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    // Skip to the next byte boundary. Whole bytes are never buffered, so
+    // only the rest of the current byte is dropped.
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+
+    /// The input after the last byte read
+    pub(crate) fn inner(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub(crate) fn byte(&mut self) -> io::Result<u8> {
+        Ok(self.bits(8)? as u8)
+    }
+
+    pub(crate) fn u16_le(&mut self) -> io::Result<u16> {
+        Ok(self.bits(16)? as u16)
+    }
+
+    pub(crate) fn u32_le(&mut self) -> io::Result<u32> {
+        let low = self.bits(16)?;
+        let high = self.bits(16)?;
+        Ok(high << 16 | low)
+    }
+
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let Some(&b) = self.inner.fill_buf()?.first() else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "compressed input is truncated",
+            ));
+        };
+        self.inner.consume(1);
+        Ok(b)
+    }
+
+    fn dynamic_tables(&mut self) -> io::Result<(Huffman, Huffman)> {
+        let literal_count = self.bits(5)? as usize + 257;
+        let distance_count = self.bits(5)? as usize + 1;
+        let code_length_count = self.bits(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err(invalid("too many length or distance codes"));
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[index] = self.bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        let mut lengths = Vec::with_capacity(literal_count + distance_count);
+        while lengths.len() < literal_count + distance_count {
+            let symbol = code_length_code.decode(self)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let Some(&previous) = lengths.last() else {
+                        return Err(invalid("repeated code length without a previous one"));
+                    };
+                    (previous, 3 + self.bits(2)?)
+                }
+                17 => (0, 3 + self.bits(3)?),
+                _ => (0, 11 + self.bits(7)?),
+            };
+            if lengths.len() + repeat as usize > literal_count + distance_count {
+                return Err(invalid("too many code lengths"));
+            }
+            lengths.extend(std::iter::repeat_n(value, repeat as usize));
+        }
+        if lengths[256] == 0 {
+            return Err(invalid("missing end of block code"));
+        }
+
+        Ok((
+            Huffman::new(&lengths[..literal_count])?,
+            Huffman::new(&lengths[literal_count..])?,
+        ))
+    }
+}
+
+// Canonical Huffman code, decoded one bit at a time
+struct Huffman {
+    // Number of codes of each length
+    counts: [u16; 16],
+    // Symbols ordered by code
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Reject over-subscribed codes. Incomplete codes are allowed, as
+        // deflate uses them for blocks with a single distance.
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(invalid("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn fixed_literals() -> Huffman {
+        let mut lengths = [8u8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        Huffman::new(&lengths).expect("fixed literal code is valid")
+    }
+
+    fn fixed_distances() -> Huffman {
+        Huffman::new(&[5u8; 30]).expect("fixed distance code is valid")
+    }
+
+    fn decode<R: BufRead>(&self, input: &mut BitReader<R>) -> io::Result<u16> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for &count in &self.counts[1..] {
+            code |= input.bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid Huffman code"))
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid compressed input: {}", message),
+    )
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/// CRC-32 as used by gzip and zip
+pub(crate) fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut c = !crc;
+    for &b in data {
+        c = CRC_TABLE[((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8);
+    }
+    !c
+}
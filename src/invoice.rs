@@ -0,0 +1,225 @@
+//! Invoice references of entries, for matching payments against open
+//! invoices.
+//!
+//! The reference of an entry is taken from its structured remittance
+//! information, or else searched for in its unstructured remittance
+//! information and entry information, where payers often write it in groups
+//! separated by spaces. Only QR references and ISO 11649 creditor references
+//! with valid check digits are taken.
+//!
+//! Legacy ESR references have the format of QR references. Their participant
+//! numbers, such as `01-162-8`, are found the same way.
+
+use crate::model::{Amount, Statement, Transaction};
+use crate::rules::{check_digit_mod10, is_creditor_reference, is_qr_reference};
+use crate::totals::csv_field;
+use std::io::{self, Write};
+
+/// Kind of an invoice reference, named by its type code in CdtrRefInf
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// Swiss QR reference of 27 digits
+    Qrr,
+    /// ISO 11649 creditor reference such as `RF18539007547034`
+    Scor,
+}
+
+impl ReferenceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReferenceKind::Qrr => "QRR",
+            ReferenceKind::Scor => "SCOR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvoiceReference {
+    pub kind: ReferenceKind,
+    /// The reference without spaces, creditor references in upper case
+    pub reference: String,
+}
+
+/// The invoice reference of an entry, if it has one
+pub fn invoice_reference(transaction: &Transaction) -> Option<InvoiceReference> {
+    transaction
+        .creditor_reference
+        .iter()
+        .map(|reference| reference.reference.as_str())
+        .chain(transaction.remittance_info.iter().map(String::as_str))
+        .chain([transaction.additional_info.as_str()])
+        .find_map(find_reference)
+}
+
+/// The first QR or creditor reference in a text, which may be split into
+/// groups by spaces or other separators
+pub fn find_reference(text: &str) -> Option<InvoiceReference> {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    (0..words.len()).find_map(|start| {
+        let mut candidate = String::new();
+        // The longest creditor reference, as a shorter one may pass the
+        // check by chance
+        let mut found = None;
+        for word in &words[start..] {
+            candidate.push_str(&word.to_ascii_uppercase());
+            if candidate.len() > 27 {
+                break;
+            }
+            if is_qr_reference(&candidate) {
+                return Some(InvoiceReference {
+                    kind: ReferenceKind::Qrr,
+                    reference: candidate,
+                });
+            }
+            if is_creditor_reference(&candidate) {
+                found = Some(InvoiceReference {
+                    kind: ReferenceKind::Scor,
+                    reference: candidate.clone(),
+                });
+            }
+        }
+        found
+    })
+}
+
+/// An ESR reference and participant number found in the unstructured
+/// remittance information and entry information of an entry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EsrDetails {
+    /// The reference of 27 digits
+    pub reference: Option<String>,
+    /// The participant number of 9 digits
+    pub participant: Option<String>,
+}
+
+/// The ESR reference and participant number written in the text of an entry
+pub fn find_esr(transaction: &Transaction) -> EsrDetails {
+    let texts = || {
+        transaction
+            .remittance_info
+            .iter()
+            .map(String::as_str)
+            .chain([transaction.additional_info.as_str()])
+    };
+    EsrDetails {
+        reference: texts()
+            .filter_map(find_reference)
+            .find(|reference| reference.kind == ReferenceKind::Qrr)
+            .map(|reference| reference.reference),
+        participant: texts().find_map(find_esr_participant),
+    }
+}
+
+/// The first ESR participant number in a text written as `01-162-8`, as 9
+/// digits such as `010001628`
+pub fn find_esr_participant(text: &str) -> Option<String> {
+    text.split(|c: char| !c.is_ascii_digit() && c != '-')
+        .find_map(|word| {
+            let mut parts = word.split('-');
+            let (Some(prefix), Some(number), Some(check), None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return None;
+            };
+            if prefix.len() != 2 || !(1..=6).contains(&number.len()) || check.len() != 1 {
+                return None;
+            }
+            Some(format!("{}{:0>6}{}", prefix, number, check))
+                .filter(|participant| is_esr_participant(participant))
+        })
+}
+
+/// Whether a number is an ESR participant number: 9 digits, the last a check
+/// digit computed with the recursive modulo 10 method
+pub fn is_esr_participant(number: &str) -> bool {
+    let bytes = number.as_bytes();
+    bytes.len() == 9
+        && bytes.iter().all(u8::is_ascii_digit)
+        && check_digit_mod10(&bytes[..8]) == bytes[8] - b'0'
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    booking_date: String,
+    reference: InvoiceReference,
+    amount: Amount,
+    credit_debit: &'static str,
+    payer: String,
+    account: String,
+    statement: String,
+}
+
+/// Entries with an invoice reference, with their amount and payer
+#[derive(Debug, Clone, Default)]
+pub struct InvoiceReport {
+    rows: Vec<Row>,
+}
+
+impl InvoiceReport {
+    pub fn new() -> Self {
+        InvoiceReport::default()
+    }
+
+    /// Add an entry of a statement, if it has an invoice reference
+    pub fn add(&mut self, statement: &Statement, transaction: &Transaction) {
+        let Some(reference) = invoice_reference(transaction) else {
+            return;
+        };
+        self.rows.push(Row {
+            booking_date: transaction
+                .booking_date
+                .map(|date| date.date().to_string())
+                .unwrap_or_default(),
+            reference,
+            amount: transaction.amount.clone(),
+            credit_debit: transaction
+                .credit_debit_ind
+                .map(|ind| ind.as_str())
+                .unwrap_or_default(),
+            payer: transaction.debtor_name.clone(),
+            account: statement.iban.clone(),
+            statement: statement.id.clone(),
+        });
+    }
+
+    /// Add the entries of other documents
+    pub fn merge(&mut self, other: &InvoiceReport) {
+        self.rows.extend(other.rows.iter().cloned());
+    }
+
+    /// Number of entries with an invoice reference
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Write the entries as CSV, one line per entry in statement order
+    pub fn write_csv(&self, mut output: impl Write) -> io::Result<()> {
+        writeln!(
+            output,
+            "reference,type,amount,currency,credit_debit,payer,booking_date,account,statement"
+        )?;
+        for row in &self.rows {
+            writeln!(
+                output,
+                "{},{},{},{},{},{},{},{},{}",
+                row.reference.reference,
+                row.reference.kind.as_str(),
+                row.amount.normalized(),
+                csv_field(&row.amount.currency),
+                row.credit_debit,
+                csv_field(&row.payer),
+                row.booking_date,
+                csv_field(&row.account),
+                csv_field(&row.statement)
+            )?;
+        }
+        output.flush()
+    }
+}
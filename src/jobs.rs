@@ -0,0 +1,306 @@
+//! Queue of conversions for the asynchronous API of the server, so that
+//! clients submitting large documents need not hold a connection open while
+//! they are converted.
+//!
+//! A job is converted by one of a fixed number of worker threads, in the
+//! order the jobs were submitted. Its result is kept in memory until it is
+//! deleted, [`JOB_RETENTION`] after it was done, or until [`MAX_RESULTS`]
+//! newer results need the room.
+
+use crate::convert::{Converted, convert_bytes};
+use crate::error::ConvertError;
+use crate::metrics::Metrics;
+use crate::reference::random_uuid;
+use crate::server::ServerOptions;
+use crate::warn;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Most jobs queued or running at a time
+pub const MAX_JOBS: usize = 32;
+
+/// Most results of jobs done or failed kept at a time, the oldest being
+/// deleted to make room for newer ones
+pub const MAX_RESULTS: usize = 32;
+
+/// Time the result of a job is kept after it was done
+pub const JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Where a job is
+#[derive(Debug)]
+pub enum JobState {
+    Queued,
+    Running,
+    Done(Converted),
+    Failed(ConvertError),
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Done(_) => "done",
+            JobState::Failed(_) => "failed",
+        }
+    }
+}
+
+struct Job {
+    state: JobState,
+    // Document and options until a worker takes the job
+    input: Option<(Vec<u8>, ServerOptions)>,
+    // API key or tenant that submitted the job, the only one it is shown to
+    owner: Option<String>,
+    done_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Queue {
+    jobs: HashMap<String, Job>,
+    // Ids of the queued jobs, oldest first
+    pending: VecDeque<String>,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    submitted: Condvar,
+    metrics: Arc<Metrics>,
+    // Worker threads that have not stopped, which they only do by panicking
+    // outside of a conversion
+    workers: AtomicUsize,
+}
+
+/// How many workers there are and what they are doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// Workers started
+    pub workers: usize,
+    /// Workers still running
+    pub alive: usize,
+    pub queued: usize,
+    pub running: usize,
+    /// Jobs done or failed whose results are kept, at most [`MAX_RESULTS`]
+    pub done: usize,
+    /// All jobs kept, including those done
+    pub jobs: usize,
+}
+
+impl PoolStatus {
+    /// Whether jobs can be submitted and will be converted. Results kept do
+    /// not count, as they make room for new ones.
+    pub fn is_ready(&self) -> bool {
+        self.alive > 0 && self.queued + self.running < MAX_JOBS
+    }
+}
+
+/// Jobs of a server, shared by its connections and workers
+#[derive(Clone)]
+pub struct Jobs {
+    shared: Arc<Shared>,
+    workers: usize,
+}
+
+impl Jobs {
+    /// Start `workers` threads converting submitted jobs, counting the
+    /// conversions in `metrics`
+    pub fn new(workers: usize, metrics: Arc<Metrics>) -> Jobs {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue::default()),
+            submitted: Condvar::new(),
+            metrics,
+            workers: AtomicUsize::new(0),
+        });
+        let workers = workers.max(1);
+        for _ in 0..workers {
+            let shared = Arc::clone(&shared);
+            shared.workers.fetch_add(1, Ordering::SeqCst);
+            thread::spawn(move || {
+                let _alive = Alive(&shared.workers);
+                work(&shared)
+            });
+        }
+        Jobs { shared, workers }
+    }
+
+    /// Workers and jobs at the moment
+    pub fn status(&self) -> PoolStatus {
+        let queue = self.lock();
+        let count = |state: fn(&JobState) -> bool| {
+            queue.jobs.values().filter(|job| state(&job.state)).count()
+        };
+        PoolStatus {
+            workers: self.workers,
+            alive: self.shared.workers.load(Ordering::SeqCst),
+            queued: count(|state| matches!(state, JobState::Queued)),
+            running: count(|state| matches!(state, JobState::Running)),
+            done: count(|state| matches!(state, JobState::Done(_) | JobState::Failed(_))),
+            jobs: queue.jobs.len(),
+        }
+    }
+
+    /// Queue a document for conversion on behalf of `owner`, returning the
+    /// id of its job, or `None` if there are already [`MAX_JOBS`] jobs queued
+    /// or running
+    pub fn submit(
+        &self,
+        document: Vec<u8>,
+        options: ServerOptions,
+        owner: Option<&str>,
+    ) -> Option<String> {
+        let mut queue = self.lock();
+        let unfinished = queue
+            .jobs
+            .values()
+            .filter(|job| job.done_at.is_none())
+            .count();
+        if unfinished >= MAX_JOBS {
+            return None;
+        }
+        let id = random_uuid();
+        queue.jobs.insert(
+            id.clone(),
+            Job {
+                state: JobState::Queued,
+                input: Some((document, options)),
+                owner: owner.map(str::to_string),
+                done_at: None,
+            },
+        );
+        queue.pending.push_back(id.clone());
+        self.shared.submitted.notify_one();
+        Some(id)
+    }
+
+    /// Look at the state of a job of `owner`, `None` if it has no such job
+    pub fn inspect<R>(
+        &self,
+        id: &str,
+        owner: Option<&str>,
+        f: impl FnOnce(&JobState) -> R,
+    ) -> Option<R> {
+        self.lock()
+            .jobs
+            .get(id)
+            .filter(|job| job.owner.as_deref() == owner)
+            .map(|job| f(&job.state))
+    }
+
+    /// Delete a job of `owner` and its result. A queued job is not
+    /// converted, a running one is deleted once it is done. Returns whether
+    /// `owner` had such a job.
+    pub fn remove(&self, id: &str, owner: Option<&str>) -> bool {
+        let mut queue = self.lock();
+        if queue
+            .jobs
+            .get(id)
+            .is_none_or(|job| job.owner.as_deref() != owner)
+        {
+            return false;
+        }
+        queue.pending.retain(|pending| pending != id);
+        queue.jobs.remove(id).is_some()
+    }
+
+    // The queue without the results that expired
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        let mut queue = lock(&self.shared);
+        queue.jobs.retain(|_, job| {
+            job.done_at
+                .is_none_or(|done| done.elapsed() < JOB_RETENTION)
+        });
+        queue
+    }
+}
+
+// A worker converting jobs as they are submitted
+fn work(shared: &Shared) {
+    loop {
+        let (id, document, options) = {
+            let mut queue = lock(shared);
+            loop {
+                if let Some(id) = queue.pending.pop_front()
+                    && let Some(job) = queue.jobs.get_mut(&id)
+                    && let Some((document, options)) = job.input.take()
+                {
+                    job.state = JobState::Running;
+                    break (id, document, options);
+                }
+                if queue.pending.is_empty() {
+                    queue = shared
+                        .submitted
+                        .wait(queue)
+                        .unwrap_or_else(|e| e.into_inner());
+                }
+            }
+        };
+
+        let started = Instant::now();
+        // A document the conversion panics on fails its job, not the worker
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            convert_bytes(&document, &options.parse, &options.write)
+        }))
+        .unwrap_or_else(|payload| Err(ConvertError::Internal(panic_message(&*payload))));
+        shared.metrics.record(result.as_ref(), started.elapsed());
+        let state = match result {
+            Ok(converted) => {
+                for warning in &converted.warnings {
+                    warn!("Job {}: {}", id, warning);
+                }
+                JobState::Done(converted)
+            }
+            Err(e) => JobState::Failed(e),
+        };
+        let mut queue = lock(shared);
+        if let Some(job) = queue.jobs.get_mut(&id) {
+            job.state = state;
+            job.done_at = Some(Instant::now());
+        }
+        evict_results(&mut queue);
+    }
+}
+
+// Delete the oldest results beyond the most kept
+fn evict_results(queue: &mut Queue) {
+    let mut done: Vec<(Instant, String)> = queue
+        .jobs
+        .iter()
+        .filter_map(|(id, job)| Some((job.done_at?, id.clone())))
+        .collect();
+    if done.len() <= MAX_RESULTS {
+        return;
+    }
+    done.sort();
+    for (_, id) in &done[..done.len() - MAX_RESULTS] {
+        queue.jobs.remove(id);
+    }
+}
+
+// What a panic was raised with, if it was a message
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("the conversion panicked");
+    message.to_string()
+}
+
+// Counts a worker as running until it stops
+struct Alive<'a>(&'a AtomicUsize);
+
+impl Drop for Alive<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// A worker that panicked leaves the queue as it was
+fn lock(shared: &Shared) -> MutexGuard<'_, Queue> {
+    shared.queue.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,188 @@
+//! Append-only journal of conversions, so that operations can look up when a
+//! statement was converted and what came of it.
+//!
+//! The journal is a text file with one JSON object per line (JSON lines), one
+//! line for each input document of a run: when it was converted, the input
+//! and outputs, the account and period of the statement and the outcome.
+//! Lines are only ever added, so the file can be kept as an audit trail and
+//! read with any tool that reads JSON lines.
+//!
+//! Entries are added as each document is done rather than at the end of a
+//! run, so that a run that was interrupted can be resumed: the documents the
+//! journal has as converted, from the same input and with the same options,
+//! are not converted again.
+
+use crate::json::Value;
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// One conversion of an input document
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// When the run converting the document finished
+    pub time: DateTime<FixedOffset>,
+    pub input: String,
+    /// SHA-256 of the input as it was received
+    pub input_sha256: Option<String>,
+    /// Fingerprint of the options that affect outputs
+    pub options_fingerprint: Option<String>,
+    /// `ok`, `skipped` (unchanged since an earlier run) or `error`
+    pub status: String,
+    pub outputs: Vec<String>,
+    /// IBAN of the statement's account, empty if the input could not be read
+    pub account: String,
+    /// Statement Id
+    pub statement: String,
+    pub from: Option<DateTime<FixedOffset>>,
+    pub to: Option<DateTime<FixedOffset>>,
+    /// What went wrong, for a failed conversion
+    pub error: Option<String>,
+}
+
+impl JournalEntry {
+    pub fn to_json(&self) -> Value {
+        Value::object()
+            .with("time", self.time.to_rfc3339())
+            .with("input", self.input.as_str())
+            .with("input_sha256", self.input_sha256.clone())
+            .with("options_fingerprint", self.options_fingerprint.clone())
+            .with("status", self.status.as_str())
+            .with("outputs", self.outputs.clone())
+            .with("account", self.account.as_str())
+            .with("statement", self.statement.as_str())
+            .with("from", self.from.map(|from| from.to_rfc3339()))
+            .with("to", self.to.map(|to| to.to_rfc3339()))
+            .with("error", self.error.clone())
+    }
+
+    /// Read an entry from its JSON object, `None` if it is not one
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let text = |key: &str| value.get(key).and_then(Value::as_str);
+        let datetime = |key: &str| text(key).and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+        Some(JournalEntry {
+            time: datetime("time")?,
+            input: text("input")?.to_string(),
+            input_sha256: text("input_sha256").map(str::to_string),
+            options_fingerprint: text("options_fingerprint").map(str::to_string),
+            status: text("status")?.to_string(),
+            outputs: value
+                .get("outputs")
+                .and_then(Value::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|output| output.as_str().map(str::to_string))
+                .collect(),
+            account: text("account").unwrap_or_default().to_string(),
+            statement: text("statement").unwrap_or_default().to_string(),
+            from: datetime("from"),
+            to: datetime("to"),
+            error: text("error").map(str::to_string),
+        })
+    }
+}
+
+/// Add entries at the end of a journal, creating it if needed
+pub fn append(path: &Path, entries: &[JournalEntry]) -> io::Result<()> {
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&entry.to_json().to_string());
+        lines.push('\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    // One write, so that lines of runs at the same time do not mix
+    file.write_all(lines.as_bytes())
+}
+
+/// Read all entries of a journal, with the number of lines that could not be
+/// read. A missing journal has no entries.
+pub fn read(path: &Path) -> io::Result<(Vec<JournalEntry>, usize)> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    let mut unreadable = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match line.parse().ok().as_ref().and_then(JournalEntry::from_json) {
+            Some(entry) => entries.push(entry),
+            None => unreadable += 1,
+        }
+    }
+    Ok((entries, unreadable))
+}
+
+/// The last conversion of `input` if it converted the same input (by its
+/// SHA-256) with the same options and its outputs still exist, so that it
+/// need not be converted again
+pub fn converted<'a>(
+    entries: &'a [JournalEntry],
+    input: &str,
+    input_sha256: &str,
+    options_fingerprint: &str,
+) -> Option<&'a JournalEntry> {
+    let entry = entries.iter().rev().find(|entry| entry.input == input)?;
+    let converted = matches!(entry.status.as_str(), "ok" | "skipped")
+        && entry.input_sha256.as_deref() == Some(input_sha256)
+        && entry.options_fingerprint.as_deref() == Some(options_fingerprint)
+        && entry
+            .outputs
+            .iter()
+            .all(|output| Path::new(output).exists());
+    converted.then_some(entry)
+}
+
+/// Which journal entries to list
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// IBAN of the account, ignoring case and spaces
+    pub account: Option<String>,
+    /// A month (first day) the statement period must overlap
+    pub month: Option<NaiveDate>,
+    pub status: Option<String>,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        let normalize = |iban: &str| {
+            iban.chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .to_ascii_uppercase()
+        };
+        if let Some(account) = &self.account
+            && normalize(account) != normalize(&entry.account)
+        {
+            return false;
+        }
+        if let Some(status) = &self.status
+            && *status != entry.status
+        {
+            return false;
+        }
+        if let Some(month) = self.month {
+            // Statements without a period are taken to cover the day of
+            // their conversion
+            let from = entry
+                .from
+                .map_or(entry.time.date_naive(), |from| from.date_naive());
+            let to = entry.to.map_or(from, |to| to.date_naive());
+            let month_end = month
+                .checked_add_months(chrono::Months::new(1))
+                .and_then(|next| next.pred_opt())
+                .unwrap_or(NaiveDate::MAX);
+            if to < month || from > month_end {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Parse a month as `YYYY-MM`, giving its first day
+pub fn parse_month(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{}-01", text), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid month {}, expected YYYY-MM", text))
+}
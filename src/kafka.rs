@@ -0,0 +1,197 @@
+//! Consumption of statements from a Kafka topic and publication of the
+//! converted documents with the `kcat` command line client (formerly
+//! kafkacat).
+//!
+//! The consumer runs `kcat` as a member of a consumer group, so that several
+//! converters can share the partitions of a topic. `kcat` commits the offsets
+//! of messages as they are read and cannot commit them later, so delivery is
+//! at most once: a message whose result cannot be published is not consumed
+//! again. Each converted document or error is published by its own `kcat`
+//! run, which fails if the broker does not acknowledge it.
+
+use crate::process::ChildOutput;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Output format of the consumer: a line with the lengths of key and payload
+// (-1 for none), partition, offset and topic, then the key and the payload
+const CONSUME_FORMAT: &str = "%K %S %p %o %t\\n%k%s";
+
+/// A Kafka cluster, reached through some of its brokers
+#[derive(Debug, Clone)]
+pub struct Kafka {
+    /// Bootstrap brokers, `host:port` separated by commas
+    pub brokers: String,
+    /// Further client properties, `name=value` as for `kcat -X`, such as
+    /// `security.protocol=SSL`
+    pub properties: Vec<String>,
+}
+
+/// A message of a topic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub topic: String,
+    pub partition: u32,
+    pub offset: u64,
+    pub key: Option<Vec<u8>>,
+    pub payload: Option<Vec<u8>>,
+}
+
+impl Kafka {
+    /// Consume the messages of `topic` as a member of `group`. With
+    /// `exit_at_end`, consuming ends once the end of all partitions was
+    /// reached, otherwise it waits for new messages.
+    pub fn consume(&self, topic: &str, group: &str, exit_at_end: bool) -> io::Result<Consumer> {
+        let mut command = self.command();
+        command
+            .arg("-C")
+            .arg("-u")
+            .arg("-f")
+            .arg(CONSUME_FORMAT)
+            .arg("-G")
+            .arg(group);
+        if exit_at_end {
+            command.arg("-e");
+        }
+        command.arg(topic).stdin(Stdio::null());
+        let output = ChildOutput::spawn(&mut command, "kcat could not consume")
+            .map_err(|e| io::Error::new(e.kind(), format!("Could not run kcat: {}", e)))?;
+        Ok(Consumer {
+            output: BufReader::new(output),
+        })
+    }
+
+    /// Publish a message to `topic` with headers given as name and value
+    pub fn publish(
+        &self,
+        topic: &str,
+        key: Option<&[u8]>,
+        headers: &[(&str, String)],
+        payload: &[u8],
+    ) -> io::Result<()> {
+        // kcat publishes a file given as argument as one message, whereas it
+        // would split its input at line breaks
+        let file = PayloadFile::with(payload)?;
+        let mut command = self.command();
+        command.arg("-P").arg("-t").arg(topic);
+        if let Some(key) = key {
+            command.arg("-k").arg(String::from_utf8_lossy(key).as_ref());
+        }
+        for (name, value) in headers {
+            command.arg("-H").arg(format!("{}={}", name, value));
+        }
+        command.arg(file.path()).stdin(Stdio::null());
+        let mut messages = String::new();
+        ChildOutput::spawn(&mut command, format!("kcat could not publish to {}", topic))
+            .and_then(|mut output| output.read_to_string(&mut messages))
+            .map(|_| ())
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new("kcat");
+        command.arg("-q").arg("-b").arg(&self.brokers);
+        for property in &self.properties {
+            command.arg("-X").arg(property);
+        }
+        command
+    }
+}
+
+/// Messages read by a running consumer
+pub struct Consumer {
+    output: BufReader<ChildOutput>,
+}
+
+impl Iterator for Consumer {
+    type Item = io::Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        read_message(&mut self.output).transpose()
+    }
+}
+
+/// Read a message as written by the consumer, `None` at the end
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Message>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unexpected output of kcat: {}", line.trim_end()),
+        )
+    };
+    let mut fields = line.trim_end_matches(['\r', '\n']).splitn(5, ' ');
+    let mut field = || fields.next().ok_or_else(invalid);
+    let key_length: i64 = field()?.parse().map_err(|_| invalid())?;
+    let payload_length: i64 = field()?.parse().map_err(|_| invalid())?;
+    let partition = field()?.parse().map_err(|_| invalid())?;
+    let offset = field()?.parse().map_err(|_| invalid())?;
+    let topic = field()?.to_string();
+
+    let mut bytes = |length: i64| -> io::Result<Option<Vec<u8>>> {
+        let Ok(length) = usize::try_from(length) else {
+            return Ok(None);
+        };
+        let mut bytes = vec![0; length];
+        reader.read_exact(&mut bytes)?;
+        Ok(Some(bytes))
+    };
+    let key = bytes(key_length)?;
+    let payload = bytes(payload_length)?;
+    Ok(Some(Message {
+        topic,
+        partition,
+        offset,
+        key,
+        payload,
+    }))
+}
+
+// A payload to publish, in a folder of its own that only the user can read,
+// removed once published
+struct PayloadFile {
+    folder: PathBuf,
+}
+
+impl PayloadFile {
+    fn with(payload: &[u8]) -> io::Result<PayloadFile> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let folder = std::env::temp_dir().join(format!(
+            "camtconvert-kafka-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        let builder = {
+            let mut builder = builder;
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            builder
+        };
+        // Fails rather than writing into a folder or link someone else made
+        builder.create(&folder)?;
+        let file = PayloadFile { folder };
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(file.path())?.write_all(payload)?;
+        Ok(file)
+    }
+
+    fn path(&self) -> PathBuf {
+        self.folder.join("payload")
+    }
+}
+
+impl Drop for PayloadFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.folder);
+    }
+}
@@ -0,0 +1,129 @@
+//! General-ledger accounts of entries, mapped from their category or bank
+//! transaction code, so that exports can be imported into bookkeeping
+//! without coding each entry by hand.
+//!
+//! Each line of a GL accounts file maps a category, a code or, with `*`,
+//! any entry to an account. The first matching line wins. Codes are
+//! compared with the proprietary code of the entry; a `*` at the end of a
+//! code matches any rest.
+//!
+//! ```text
+//! category:groceries = 4400
+//! category:rent = 6000
+//! code:CARD-* = 6570
+//! * = 2999
+//! ```
+
+use crate::model::Transaction;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Key {
+    Category(String),
+    Code(String),
+    CodePrefix(String),
+    Any,
+}
+
+impl Key {
+    fn matches(&self, transaction: &Transaction) -> bool {
+        match self {
+            Key::Category(category) => transaction.category == *category,
+            Key::Code(code) => transaction.bank_tx_code == *code,
+            Key::CodePrefix(prefix) => transaction.bank_tx_code.starts_with(prefix.as_str()),
+            Key::Any => true,
+        }
+    }
+}
+
+/// Mappings of a GL accounts file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlAccounts {
+    mappings: Vec<(Key, String)>,
+}
+
+/// A GL accounts file that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlAccountError(String);
+
+impl fmt::Display for GlAccountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for GlAccountError {}
+
+impl FromStr for GlAccounts {
+    type Err = GlAccountError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut mappings = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mapping = parse_mapping(line).map_err(|message| {
+                GlAccountError(format!("Line {} of GL accounts: {}", index + 1, message))
+            })?;
+            mappings.push(mapping);
+        }
+        Ok(GlAccounts { mappings })
+    }
+}
+
+impl GlAccounts {
+    /// Whether there are no mappings
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    /// Account of the first mapping matching an entry, if any
+    pub fn account(&self, transaction: &Transaction) -> Option<&str> {
+        self.mappings
+            .iter()
+            .find(|(key, _)| key.matches(transaction))
+            .map(|(_, account)| account.as_str())
+    }
+
+    /// Set the GL account of an entry, if a mapping matches it
+    pub fn apply(&self, transaction: &mut Transaction) {
+        if let Some(account) = self.account(transaction) {
+            transaction.gl_account = account.to_string();
+        }
+    }
+}
+
+fn parse_mapping(line: &str) -> Result<(Key, String), String> {
+    let (key, account) = line
+        .split_once('=')
+        .ok_or("expected category:NAME, code:CODE or * and = before the account")?;
+    let account = account.trim();
+    if account.is_empty() || account.contains(char::is_whitespace) {
+        return Err(format!("invalid account {:?}", account));
+    }
+    let key = match key.trim() {
+        "*" => Key::Any,
+        key => match key.split_once(':') {
+            Some(("category", category)) if !category.trim().is_empty() => {
+                Key::Category(category.trim().to_string())
+            }
+            Some(("code", code)) if !code.trim().is_empty() => {
+                let code = code.trim();
+                match code.strip_suffix('*') {
+                    Some(prefix) => Key::CodePrefix(prefix.to_string()),
+                    None => Key::Code(code.to_string()),
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "expected category:NAME, code:CODE or * instead of {:?}",
+                    key
+                ));
+            }
+        },
+    };
+    Ok((key, account.to_string()))
+}
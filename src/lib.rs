@@ -0,0 +1,103 @@
+//! Conversion of CAMT 053 bank statements from version 053.001.10 to 053.001.08.
+//!
+//! The input is read into a version independent [`model::Statement`] by
+//! [`reader::parse_camt_10`] and written back out by [`writer::write_camt_08`].
+//! The readers and writers of all supported versions are registered in
+//! [`matrix`], so that any input version can be converted to any output
+//! version.
+//!
+//! Modules that need files, processes or the network are behind the default
+//! `fs` feature. Without it the library builds for `wasm32-unknown-unknown`,
+//! exporting [`convert::convert_bytes`] to JavaScript.
+
+pub mod alias;
+#[cfg(feature = "fs")]
+pub mod archive;
+pub mod arrow;
+pub mod bank;
+pub mod bic;
+pub mod c14n;
+pub mod category;
+pub mod charset;
+pub mod checksum;
+pub mod convert;
+pub mod currency;
+pub mod decimal;
+#[cfg(feature = "fs")]
+pub mod dsig;
+#[cfg(feature = "fs")]
+pub mod duckdb;
+pub mod encoding;
+pub mod error;
+pub mod exchange;
+pub mod extract;
+pub mod gaps;
+pub mod generate;
+pub mod gzip;
+pub mod hooks;
+pub mod iban;
+mod inflate;
+pub mod invoice;
+#[cfg(feature = "fs")]
+pub mod jobs;
+#[cfg(feature = "fs")]
+pub mod journal;
+pub mod json;
+#[cfg(feature = "fs")]
+pub mod kafka;
+pub mod ledger;
+pub mod log;
+pub mod matrix;
+pub mod merchant;
+#[cfg(feature = "fs")]
+pub mod metrics;
+pub mod model;
+pub mod normalize;
+#[cfg(feature = "fs")]
+pub mod notify;
+pub mod pagination;
+mod passthrough;
+pub mod pending;
+#[cfg(feature = "fs")]
+pub mod pgp;
+#[cfg(feature = "fs")]
+pub mod postgres;
+#[cfg(feature = "fs")]
+pub mod posthook;
+#[cfg(feature = "fs")]
+mod process;
+pub mod query;
+pub mod reader;
+pub mod reconcile;
+pub mod reference;
+pub mod regex;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod review;
+pub mod rules;
+#[cfg(feature = "schemas")]
+pub mod schemas;
+pub mod search;
+#[cfg(feature = "fs")]
+pub mod seen;
+#[cfg(feature = "fs")]
+pub mod server;
+#[cfg(feature = "fs")]
+pub mod sftp;
+mod sha1;
+mod sha256;
+mod siphash;
+#[cfg(feature = "fs")]
+mod sql;
+#[cfg(feature = "fs")]
+pub mod state;
+pub mod stats;
+pub mod template;
+pub mod timezone;
+pub mod totals;
+#[cfg(target_family = "wasm")]
+mod wasm;
+pub mod writer;
+pub mod zip;
+
+pub use error::{ConvertError, Result};
@@ -0,0 +1,185 @@
+//! Leveled logging to standard error.
+//!
+//! Messages below the configured level are dropped. The level and format are
+//! global and set once by the command line tool, see [`set_max_level`],
+//! [`set_format`] and [`set_color`].
+
+use crate::json::Value;
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// Importance of a log message, from most to least important
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    /// Lower case name of the level, as used in JSON logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+
+    fn from_u8(value: u8) -> Level {
+        match value {
+            0 => Level::Error,
+            1 => Level::Warn,
+            2 => Level::Info,
+            3 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+}
+
+/// How log messages are written
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text for people
+    #[default]
+    Text,
+    /// One JSON object per line with `level` and `message` fields
+    Json,
+}
+
+/// Colors and emphasis of text on a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Dim,
+    Red,
+    Green,
+    Yellow,
+}
+
+impl Style {
+    fn code(&self) -> &'static str {
+        match self {
+            Style::Bold => "\x1b[1m",
+            Style::Dim => "\x1b[2m",
+            Style::Red => "\x1b[31m",
+            Style::Green => "\x1b[32m",
+            Style::Yellow => "\x1b[33m",
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static JSON: AtomicBool = AtomicBool::new(false);
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Set the least important level that is still logged
+pub fn set_max_level(level: Level) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// The least important level that is still logged
+pub fn max_level() -> Level {
+    Level::from_u8(MAX_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Set how log messages are written
+pub fn set_format(format: Format) {
+    JSON.store(format == Format::Json, Ordering::Relaxed);
+}
+
+/// Set whether text messages are colored, for a terminal
+pub fn set_color(color: bool) {
+    COLOR.store(color, Ordering::Relaxed);
+}
+
+/// Whether text messages are colored
+pub fn color() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+/// Text in a style if messages are colored, otherwise as it is
+pub fn paint(text: &str, style: Style) -> String {
+    if color() {
+        format!("{}{}{}", style.code(), text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Whether messages of the given level are logged
+pub fn enabled(level: Level) -> bool {
+    level <= max_level()
+}
+
+/// Log a message. Use the [`error!`](crate::error!), [`warn!`](crate::warn!),
+/// [`info!`](crate::info!), [`debug!`](crate::debug!) and
+/// [`trace!`](crate::trace!) macros rather than calling this directly.
+pub fn log(level: Level, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+    let line = if JSON.load(Ordering::Relaxed) {
+        Value::object()
+            .with("level", level.as_str())
+            .with("message", args.to_string())
+            .to_string()
+    } else {
+        let (prefix, style) = match level {
+            Level::Error => ("Error: ", Some(Style::Red)),
+            Level::Warn => ("Warning: ", Some(Style::Yellow)),
+            Level::Info => ("", None),
+            Level::Debug => ("debug: ", Some(Style::Dim)),
+            Level::Trace => ("trace: ", Some(Style::Dim)),
+        };
+        let line = format!("{}{}", prefix, args);
+        match style {
+            Some(style) => paint(&line, style),
+            None => line,
+        }
+    };
+    // Logging must never fail the conversion
+    let _ = writeln!(std::io::stderr().lock(), "{}", line);
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::Level::Error, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::Level::Info, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::Level::Trace, format_args!($($arg)*))
+    };
+}
@@ -1,636 +1,3520 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use clap::Parser;
-use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
-use quick_xml::reader::Reader;
-use quick_xml::writer::Writer;
-use std::collections::hash_map::DefaultHasher;
+use camtconvert::alias::{AliasError, Aliases};
+use camtconvert::archive::{archive_file, quarantine_file};
+use camtconvert::arrow;
+use camtconvert::bank;
+use camtconvert::bic::{BicError, validate_bic};
+use camtconvert::c14n::canonicalize;
+use camtconvert::category::{Categories, Categorized, CategoryError};
+use camtconvert::charset::Charset;
+use camtconvert::checksum::{
+    ChecksumAlgorithm, checksum, file_checksum, reader_checksum, verify_sidecar, write_sidecar,
+};
+use camtconvert::convert::convert_bytes;
+use camtconvert::currency::minor_units;
+use camtconvert::decimal::Decimal;
+use camtconvert::dsig::{self, SignOptions, SignatureMode, VerifyOptions};
+use camtconvert::duckdb;
+use camtconvert::encoding::InputEncoding;
+use camtconvert::exchange::ExchangeReport;
+use camtconvert::extract::{entry_value, find_entries};
+use camtconvert::gaps::{Delivery, find_gaps};
+use camtconvert::generate::{GenerateOptions, generate_camt_10};
+use camtconvert::gzip::{GzDecoder, is_gzip};
+use camtconvert::hooks::{HookError, Hooked, Hooks};
+use camtconvert::invoice::InvoiceReport;
+use camtconvert::journal::{self, HistoryFilter, JournalEntry, parse_month};
+use camtconvert::json::Value;
+use camtconvert::kafka::Kafka;
+use camtconvert::ledger::{GlAccountError, GlAccounts};
+use camtconvert::log::{self, Format, Level, Style};
+use camtconvert::matrix::{READERS, find_reader, find_writer};
+use camtconvert::merchant::{MerchantCleanup, MerchantError};
+use camtconvert::model::{Balance, BalanceType, Statement, Transaction};
+use camtconvert::normalize::normalize_camt_10;
+use camtconvert::notify;
+use camtconvert::pagination::{PageLimits, write_pages};
+use camtconvert::pending::{PendingAction, interim_statement, is_pending, take_pending};
+use camtconvert::pgp::{DecryptOptions, decrypt, is_encrypted};
+use camtconvert::postgres;
+use camtconvert::posthook::PostHook;
+use camtconvert::reader::{
+    Limits, ParseOptions, StatementHandler, SupplementaryData, parse_camt_10, stream_camt_10,
+};
+use camtconvert::reconcile::{Reconciliation, Status, read_expected};
+use camtconvert::reference::ReferenceStyle;
+#[cfg(feature = "remote")]
+use camtconvert::remote;
+use camtconvert::review::{self, ask, confirm, write_review};
+use camtconvert::rules::{RuleSet, check_rules};
+#[cfg(feature = "schemas")]
+use camtconvert::schemas::{SCHEMAS, Schema, find_schema};
+use camtconvert::search::{self, EntryFilter};
+use camtconvert::seen::{DuplicateAction, DuplicateCheck, SeenEntries};
+use camtconvert::server::{self, ServerOptions};
+use camtconvert::sftp::{Sftp, SftpUrl};
+use camtconvert::state::ConversionState;
+use camtconvert::stats::{ConversionStats, CountingReader};
+use camtconvert::template::{NameTemplate, OutputLayout};
+use camtconvert::timezone::TimeZone;
+use camtconvert::totals::CodeTotals;
+use camtconvert::writer::{
+    DateFormat, Indent, MessageId, OutputFormat, OutputVersion, PLACEHOLDER_BIC, Page,
+    StreamWriter, Truncation, WriteOptions, XsiNamespace, check_lengths, check_placeholders,
+    entry_truncations, statement_truncations, write_notification, write_statement,
+};
+use camtconvert::zip::{ZipArchive, is_zip};
+use camtconvert::{ConvertError, debug, error, info, warn};
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate};
+use clap::{ArgAction, Parser, Subcommand};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::hash::{Hash, Hasher};
-use std::io::{BufReader, BufWriter};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Seek, Write};
+use std::net::TcpListener;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
-#[derive(Parser, Debug)]
-#[command(author, version, about = "Convert CAMT files from version 053.001.10 to 053.001.08", long_about = None)]
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about = "Convert CAMT files from version 053.001.10 to 053.001.08 or 053.001.04", long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
-    /// Path to the CAMT 053.001.10 file to convert
-    input: PathBuf,
+    /// Path to the CAMT 053.001.10 file to convert, or with the remote
+    /// feature an https:// or s3:// URL
+    #[arg(required = true)]
+    input: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Version of the output
+    #[arg(long, value_enum, value_name = "VERSION", default_value = "08")]
+    target_version: OutputVersion,
+
+    /// Format of the outputs. With arrow, the entries are written as an
+    /// Apache Arrow IPC stream named like statement.arrow for analytics
+    /// tools.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        conflicts_with_all = ["stream", "max_entries_per_file", "max_bytes", "canonicalize", "sign_key", "rules"]
+    )]
+    format: OutputFormat,
+
+    /// How AcctSvcrRef values are generated for entries
+    #[arg(long, value_enum, default_value_t = ReferenceStyle::Hash)]
+    reference: ReferenceStyle,
+
+    /// Skip entries that cannot be parsed instead of aborting, with a warning
+    #[arg(long)]
+    lenient: bool,
+
+    /// Fail on values that fail a check, such as IBANs with wrong check
+    /// digits, instead of warning about them, and instead of writing the
+    /// placeholder BIC or cutting values too long for the schema
+    #[arg(long)]
+    strict: bool,
+
+    /// Exit with code 6 when any warning was reported, keeping the outputs
+    #[arg(long)]
+    fail_on_warning: bool,
+
+    /// Check outputs against business rules beyond the schema, warning about
+    /// or with --strict failing on violations. Several sets are separated by
+    /// commas
+    #[arg(long, value_name = "RULES", value_delimiter = ',')]
+    rules: Vec<RuleSet>,
+
+    /// BIC of the account servicer, written instead of the one in the input
+    /// or the XXXXXXXX placeholder
+    #[arg(long, value_name = "BIC", value_parser = parse_bic)]
+    servicer_bic: Option<String>,
+
+    /// BIC of the message recipient, written instead of the XXXXXXXX
+    /// placeholder
+    #[arg(long, value_name = "BIC", value_parser = parse_bic)]
+    recipient_bic: Option<String>,
+
+    /// Write ESR references and participant numbers found in the text of
+    /// entries as structured remittance information and creditor account
+    #[arg(long)]
+    esr_references: bool,
+
+    /// Message identification written to the group header: the statement
+    /// Id, the input MsgId, a random UUID, or a template such as
+    /// "{iban}-{created_date}" with the placeholders of --name-template
+    #[arg(long, value_name = "STRATEGY", default_value = "statement")]
+    msg_id: MessageId,
+
+    /// Character set of names and remittance text in the output; swift-x
+    /// transliterates umlauts, accents and other punctuation
+    #[arg(long, value_enum, default_value_t = Charset::Unicode)]
+    charset: Charset,
+
+    /// Ask on the terminal for the recipient and servicer BICs when neither
+    /// the input nor an option gives them, instead of writing placeholders
+    #[arg(long)]
+    prompt: bool,
+
+    /// Character encoding of the input, overriding the XML declaration
+    #[arg(long, value_enum)]
+    input_encoding: Option<InputEncoding>,
+
+    /// Time zone of timestamps without a UTC offset, as a name such as
+    /// Europe/Zurich (default) or a fixed offset such as +01:00
+    #[arg(long, value_name = "ZONE")]
+    timezone: Option<TimeZone>,
+
+    /// Reject inputs with elements nested deeper than this
+    #[arg(long, value_name = "N", default_value_t = Limits::default().max_depth)]
+    max_depth: usize,
+
+    /// Reject inputs with more elements than this
+    #[arg(long, value_name = "N", default_value_t = Limits::default().max_elements)]
+    max_elements: u64,
+
+    /// Reject inputs with an element text longer than this, in bytes
+    #[arg(long, value_name = "BYTES", default_value_t = Limits::default().max_text_length)]
+    max_text_length: usize,
+
+    /// Copy elements the converter does not model (e.g. SplmtryData) to the output
+    #[arg(long)]
+    keep_unknown: bool,
+
+    /// What to do with supplementary data (SplmtryData) of the message and
+    /// of transaction details, instead of treating it like other elements not
+    /// modelled
+    #[arg(long, value_enum, value_name = "POLICY")]
+    supplementary_data: Option<SupplementaryData>,
+
+    /// Replace tabs, line breaks and other control characters in text by
+    /// spaces, for receivers that reject them
+    #[arg(long)]
+    strip_control_chars: bool,
+
+    /// Add xsi:schemaLocation to the output, optionally with the XSD
+    /// location; the XSD file name of the output version by default
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        value_name = "XSD"
+    )]
+    schema_location: Option<String>,
+
+    /// When to declare the xmlns:xsi namespace on the output Document
+    #[arg(long, value_enum, default_value_t = XsiNamespace::Always)]
+    xsi_namespace: XsiNamespace,
+
+    /// Only write optional elements that were present in the input
+    #[arg(long)]
+    fidelity: bool,
+
+    /// How booking and balance dates are written: as Dt, as DtTm, or as in
+    /// the input. Defaults to input with --fidelity, date otherwise.
+    #[arg(long, value_enum)]
+    date_format: Option<DateFormat>,
+
+    /// Number of spaces to indent nested elements by
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    indent: usize,
+
+    /// Write the output on a single line
+    #[arg(long, conflicts_with = "indent")]
+    compact: bool,
+
+    /// Write outputs in Exclusive XML Canonicalization form, for stable
+    /// digests and signatures
+    #[arg(long)]
+    canonicalize: bool,
+
+    /// Sign outputs with the private RSA key (PEM) in the file
+    #[arg(long, value_name = "FILE")]
+    sign_key: Option<PathBuf>,
+
+    /// Certificate (PEM) of the signing key, written to the signatures
+    #[arg(long, value_name = "FILE", requires = "sign_key")]
+    sign_cert: Option<PathBuf>,
+
+    /// Whether signatures are written into the outputs or next to them
+    #[arg(long, value_enum, default_value_t, requires = "sign_key")]
+    sign_mode: SignatureMode,
+
+    /// Write entries as they are read, for very large statements
+    #[arg(long)]
+    stream: bool,
+
+    /// Split outputs with more entries into pages of this many entries, each
+    /// a document numbered in its group header and named like
+    /// statement_08_p1.xml
+    #[arg(long, value_name = "N", value_parser = parse_page_size, conflicts_with = "stream")]
+    max_entries_per_file: Option<usize>,
+
+    /// Split outputs larger than this into pages of at most this many bytes,
+    /// as for --max-entries-per-file
+    #[arg(long, value_name = "BYTES", conflicts_with = "stream")]
+    max_bytes: Option<u64>,
+
+    /// Log more details: -v for timings and counts, -vv for every entry
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Only log warnings and errors
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Never color output, as when the NO_COLOR environment variable is set
+    #[arg(long)]
+    no_color: bool,
+
+    /// Name output files from the statement, e.g. "{iban}_{to_date}_{version}.xml".
+    /// Placeholders: iban, currency, id, owner, created_date, from_date,
+    /// to_date, version and input.
+    #[arg(long, value_name = "TEMPLATE")]
+    name_template: Option<NameTemplate>,
+
+    /// File outputs in subfolders of the input's folder by the start of the
+    /// statement period (YYYY/MM/) or by account (IBAN/YYYY/)
+    #[arg(long, value_enum, default_value_t = OutputLayout::Flat)]
+    layout: OutputLayout,
+
+    /// Write a checksum file next to every output file
+    #[arg(long, value_enum, value_name = "ALGORITHM")]
+    checksum: Option<ChecksumAlgorithm>,
+
+    /// Check the input against its checksum file (e.g. input.xml.sha256)
+    /// before converting it
+    #[arg(long)]
+    verify_checksum: bool,
+
+    /// Convert all documents of a zip archive again, including those that
+    /// did not change since they were last converted
+    #[arg(long)]
+    rerun: bool,
+
+    /// Secret key file to decrypt PGP-encrypted input with, instead of the
+    /// keys of the current gpg user
+    #[arg(long, value_name = "FILE")]
+    decryption_key: Option<PathBuf>,
+
+    /// File with the passphrase of the decryption key
+    #[arg(long, value_name = "FILE")]
+    passphrase_file: Option<PathBuf>,
+
+    /// Verify the XML signature of the input with the certificate (PEM) of
+    /// the signer before converting it, failing on unsigned input
+    #[arg(long, value_name = "FILE")]
+    signature_cert: Option<PathBuf>,
+
+    /// Verify the XML signature of the input with the certificate it carries,
+    /// which must be issued by one of the CA certificates (PEM) in the file
+    #[arg(long, value_name = "FILE")]
+    signature_trust: Option<PathBuf>,
+
+    /// Write the count and sums of entries by bank transaction code and
+    /// currency to a CSV file, or to standard output with -
+    #[arg(long, value_name = "FILE")]
+    code_totals: Option<PathBuf>,
+
+    /// Write the QR and creditor references of entries with their amount and
+    /// payer to a CSV file, or to standard output with -
+    #[arg(long, value_name = "FILE")]
+    qr_report: Option<PathBuf>,
+
+    /// Write the original and counter amounts and exchange rate of entries
+    /// with a currency exchange, and the volume by currency pair, to a CSV
+    /// file, or to standard output with -
+    #[arg(long, value_name = "FILE")]
+    fx_report: Option<PathBuf>,
+
+    /// Write the entries of converted statements to the table entries of a
+    /// DuckDB database, replacing those written for the statements before
+    #[arg(long, value_name = "FILE", conflicts_with = "stream")]
+    duckdb: Option<PathBuf>,
+
+    /// Write a JSON report of the run to a file: versions, inputs, outputs,
+    /// statistics, dropped fields and warnings of each document
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Write a JSON manifest of the run to a file for audits: the version and
+    /// options, and the SHA-256 of each input and of the outputs written
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// Add a line for each converted document to a journal of conversions
+    /// (JSON lines), see the history command
+    #[arg(long, value_name = "FILE")]
+    journal: Option<PathBuf>,
+
+    /// Skip documents the journal has as converted from the same input with
+    /// the same options, to resume an interrupted run
+    #[arg(long, requires = "journal")]
+    resume: bool,
+
+    /// File of rules changing values of statements and entries before they
+    /// are written, see the README
+    #[arg(long, value_name = "FILE", value_parser = read_hooks)]
+    hooks: Option<Hooks>,
+
+    /// File of rules assigning a category to each entry, after the hooks,
+    /// for the category column of exports, see the README
+    #[arg(long, value_name = "FILE", value_parser = read_categories)]
+    categories: Option<Categories>,
+
+    /// Also add the category of each entry to its additional information
+    /// (AddtlNtryInf) as `#category`
+    #[arg(long, requires = "categories")]
+    category_tags: bool,
+
+    /// File mapping variants of counterparty names to canonical names for
+    /// exports (--format arrow, --duckdb, load, extract --json), see the
+    /// README
+    #[arg(long, value_name = "FILE", value_parser = read_aliases)]
+    aliases: Option<Aliases>,
+
+    /// File mapping categories and bank transaction codes to general-ledger
+    /// accounts, for the gl_account column of exports, see the README
+    #[arg(long, value_name = "FILE", value_parser = read_gl_accounts)]
+    gl_accounts: Option<GlAccounts>,
+
+    /// Clean up the merchant texts of card entries into readable payee names
+    /// for exports, removing terminal ids, card numbers and the like
+    #[arg(long)]
+    clean_merchants: bool,
+
+    /// File of further patterns removed by --clean-merchants, one regular
+    /// expression per line
+    #[arg(long, value_name = "FILE", value_parser = read_merchant_patterns, requires = "clean_merchants")]
+    merchant_patterns: Option<MerchantCleanup>,
+
+    /// Index of the entries converted so far, kept across runs, to flag or
+    /// drop entries that a later delivery repeats
+    #[arg(long, value_name = "FILE")]
+    seen_entries: Option<PathBuf>,
+
+    /// What to do with entries found in the --seen-entries index
+    #[arg(long, value_enum, default_value_t, requires = "seen_entries")]
+    duplicates: DuplicateAction,
+
+    /// What to do with pending entries (status PDNG), which are not part of
+    /// the booked balances. separate-file writes them to an interim
+    /// statement named like the output with _pending added.
+    #[arg(long, value_enum, default_value_t)]
+    pending: PendingAction,
+
+    /// Move the input to this folder once all its documents are converted
+    #[arg(long, value_name = "DIR")]
+    archive_dir: Option<PathBuf>,
+
+    /// Add the time of archiving to the names of archived inputs, e.g.
+    /// statement_20250620T101500.xml
+    #[arg(long, requires = "archive_dir")]
+    archive_timestamp: bool,
+
+    /// Move the input to this folder if a document of it cannot be read or
+    /// is invalid, with its errors in a .error.txt file next to it
+    #[arg(long, value_name = "DIR")]
+    quarantine_dir: Option<PathBuf>,
+
+    /// Command to run after each output is written, e.g.
+    /// "import-statement --account {iban} {output}". Placeholders: output,
+    /// input, iban, currency, id, from_date, to_date and version
+    #[arg(long, value_name = "COMMAND")]
+    post_hook: Option<PostHook>,
+
+    /// POST a JSON summary of each converted document to this webhook
+    #[arg(long, value_name = "URL", value_parser = parse_notify_url)]
+    notify_url: Option<String>,
+
+    /// Format of log messages. With json, a result summary is also printed
+    /// on standard output.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    log_format: Format,
 }
 
-// Structure to hold transaction data during conversion
-#[derive(Debug, Default, Clone)]
-struct Transaction {
-    amount: String,
-    currency: String,
-    credit_debit_ind: String,
-    booking_date: String,
-    bank_tx_code: String,
-    additional_info: String,
-    charges: Option<String>,
-}
-
-// Structure to hold balance data
-#[derive(Debug, Default, Clone)]
-struct Balance {
-    balance_type: String,
-    amount: String,
-    currency: String,
-    credit_debit_ind: String,
-    date: String,
-}
-
-// Structure to hold statement data
-#[derive(Debug, Default)]
-struct Statement {
-    id: String,
-    creation_datetime: String,
-    from_datetime: String,
-    to_datetime: String,
-    iban: String,
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Convert documents posted to POST /convert over HTTP, with the
+    /// conversion options given before the command as defaults
+    Serve {
+        /// Address and port to listen on
+        #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1:8080")]
+        listen: String,
+        /// Only serve clients giving one of the keys of this file, one per
+        /// line as name:key
+        #[arg(long, value_name = "FILE")]
+        api_key_file: Option<PathBuf>,
+        /// Default options of tenants, one per line as name: query, such as
+        /// ubs: version=04&servicer-bic=UBSWCHZH80A
+        #[arg(long, value_name = "FILE")]
+        tenants: Option<PathBuf>,
+    },
+    /// Download new statement files from an SFTP folder and convert them,
+    /// with the conversion options given before the command
+    Fetch(Fetch),
+    /// Write a camt.053.001.10 statement with random entries, for tests and
+    /// demonstrations without real bank data
+    Generate(Generate),
+    /// Show the balances, entries and warnings of a statement and ask for
+    /// confirmation before writing its output
+    Review {
+        /// Path to the camt.053.001.10 XML file
+        input: PathBuf,
+    },
+    /// Show the balances, entries and warnings of a statement without
+    /// converting it
+    Inspect(Inspect),
+    /// Find entries by text or amount in the statements of folders and
+    /// list them with file, booking date, amount and reference
+    Search(Search),
+    /// Write one entry of a statement, found by its reference, as a
+    /// camt.054.001.08 notification or JSON
+    Extract(Extract),
+    /// Check that the statements of folders follow each other without
+    /// missing sequence numbers or days, and report the gaps
+    Gaps(Gaps),
+    /// Load statements, their balances and entries into PostgreSQL tables,
+    /// updating those loaded before
+    Load(Load),
+    /// Match the entries of statements against a CSV list of expected
+    /// payments and report matched, missing and unexpected items as CSV
+    Reconcile(Reconcile),
+    /// Write a camt.053.001.10 statement again in the same version, with
+    /// elements in schema order and whitespace and escaping normalized, for
+    /// deduplicating and diffing archived statements
+    Normalize(Normalize),
+    /// List conversions recorded in a journal written with --journal, such
+    /// as when the statement of an account for a month was converted
+    History(History),
+    /// Convert statements consumed from a Kafka topic and publish the
+    /// converted documents, or errors, to another topic, with the conversion
+    /// options given before the command
+    Consume(Consume),
+    /// List the bundled XSDs of the camt versions that can be read and
+    /// written, print one or export them to a folder for validating offline
+    #[cfg(feature = "schemas")]
+    Schemas(Schemas),
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Reconcile {
+    /// camt.053.001.10 files with the entries
+    #[arg(required = true, value_name = "STATEMENT")]
+    statements: Vec<PathBuf>,
+
+    /// CSV file of expected payments with reference, amount and currency
+    /// columns
+    #[arg(long, value_name = "FILE")]
+    expected: PathBuf,
+
+    /// File to write the report to, standard output by default
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Normalize {
+    /// Path to the camt.053.001.10 XML file
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+
+    /// File to write the normalized statement to, standard output by default
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Inspect {
+    /// Path to the camt.053.001.10 XML file
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+
+    /// Write the account, period, balances, entry counts and warnings as a
+    /// JSON object
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Search {
+    /// Folders searched with their subfolders, or statement files
+    #[arg(required = true, value_name = "PATH")]
+    paths: Vec<PathBuf>,
+
+    /// Only entries with this text in a reference, name, code or
+    /// information, ignoring case
+    #[arg(long)]
+    text: Option<String>,
+
+    /// Only entries of this amount; negative for debits only
+    #[arg(long, allow_negative_numbers = true)]
+    amount: Option<Decimal>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Extract {
+    /// Path to the camt.053.001.10 XML file
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+
+    /// Reference of the entry: AcctSvcrRef of the input or of outputs, or
+    /// EndToEndId
+    #[arg(long, value_name = "REFERENCE")]
+    reference: String,
+
+    /// Write the entry as a JSON object instead
+    #[arg(long)]
+    json: bool,
+
+    /// File to write the entry to, standard output by default
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Gaps {
+    /// Folders checked with their subfolders, or statement files
+    #[arg(required = true, value_name = "PATH")]
+    paths: Vec<PathBuf>,
+
+    /// Only check the statements of this account, instead of each account
+    /// separately
+    #[arg(long, value_name = "IBAN")]
+    iban: Option<String>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Load {
+    /// Folders loaded with their subfolders, or statement files
+    #[arg(required = true, value_name = "PATH")]
+    paths: Vec<PathBuf>,
+
+    /// Connection string or URI of the database, as taken by psql, e.g.
+    /// postgresql://loader@db.internal/finance
+    #[arg(long, value_name = "CONNECTION")]
+    pg: String,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct History {
+    /// Journal file written with --journal
+    #[arg(value_name = "FILE")]
+    journal: PathBuf,
+
+    /// Only conversions of statements of this account (IBAN)
+    #[arg(long, value_name = "IBAN")]
+    account: Option<String>,
+
+    /// Only conversions of statements whose period overlaps this month
+    /// (YYYY-MM)
+    #[arg(long, value_name = "MONTH", value_parser = parse_month)]
+    month: Option<NaiveDate>,
+
+    /// Only conversions with this outcome
+    #[arg(long, value_parser = ["ok", "skipped", "error"])]
+    status: Option<String>,
+
+    /// Write the matching journal lines as they are, as JSON lines
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Consume {
+    /// Kafka bootstrap brokers, host:port separated by commas
+    #[arg(long, value_name = "BROKERS")]
+    brokers: String,
+
+    /// Topic to consume camt.053.001.10 documents from
+    #[arg(long, value_name = "TOPIC")]
+    topic: String,
+
+    /// Topic to publish converted documents to
+    #[arg(long, value_name = "TOPIC")]
+    output_topic: String,
+
+    /// Topic to publish errors to, as JSON; the output topic by default
+    #[arg(long, value_name = "TOPIC")]
+    error_topic: Option<String>,
+
+    /// Consumer group sharing the partitions of the topic
+    #[arg(long, value_name = "GROUP", default_value = "camtconvert")]
+    group: String,
+
+    /// Kafka client property name=value, such as security.protocol=SSL; may
+    /// be given several times
+    #[arg(short = 'X', long = "property", value_name = "NAME=VALUE")]
+    properties: Vec<String>,
+
+    /// Stop once all messages of the topic were consumed instead of waiting
+    /// for new ones
+    #[arg(long)]
+    exit_at_end: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Fetch {
+    /// Remote folder, e.g. sftp://user@host/outgoing, or sftp://host/~/outgoing
+    /// in the home folder
+    #[arg(value_name = "URL")]
+    url: SftpUrl,
+
+    /// Only fetch files last modified on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    since: Option<NaiveDate>,
+
+    /// Folder to download files to and write the outputs in
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    download_dir: PathBuf,
+
+    /// Move remote files to this folder on the server once converted
+    #[arg(long, value_name = "DIR")]
+    archive_remote: Option<String>,
+
+    /// Delete remote files once converted
+    #[arg(long, conflicts_with = "archive_remote")]
+    delete_remote: bool,
+
+    /// Private key to log in with, instead of those of the SSH configuration
+    #[arg(long, value_name = "FILE")]
+    identity: Option<PathBuf>,
+}
+
+#[cfg(feature = "schemas")]
+#[derive(clap::Args, Debug, Clone)]
+struct Schemas {
+    /// Only this version, given as camt.053.001.08, 053.001.08 or 08; its
+    /// XSD is printed unless exported
+    #[arg(long, value_name = "VERSION")]
+    version: Option<String>,
+
+    /// Write the XSDs to this folder as camt.053.001.08.xsd and so on
+    #[arg(long, value_name = "DIR")]
+    export: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct Generate {
+    /// Number of entries
+    #[arg(long, default_value_t = 10)]
+    entries: usize,
+
+    /// Currency of the account and its entries
+    #[arg(long, default_value = "CHF", value_parser = parse_currency)]
     currency: String,
-    owner_name: String,
-    balances: Vec<Balance>,
-    transactions: Vec<Transaction>,
+
+    /// Last day of the statement period (YYYY-MM-DD), which starts on the
+    /// first of its month; yesterday by default
+    #[arg(long, value_name = "DATE")]
+    date: Option<NaiveDate>,
+
+    /// Seed of the random values, the same seed giving the same statement;
+    /// random by default
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// File to write, standard output by default
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+impl Args {
+    fn input(&self) -> &Path {
+        match &self.command {
+            Some(Command::Review { input }) => input,
+            _ => self
+                .input
+                .as_deref()
+                .expect("the input is required without a command"),
+        }
+    }
 
-    // Validate input file exists
-    if !args.input.exists() {
-        anyhow::bail!("Input file does not exist: {}", args.input.display());
+    // Whether output to a terminal or not is colored
+    fn color(&self, terminal: bool) -> bool {
+        terminal
+            && !self.no_color
+            && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
     }
 
-    // Create output filename
-    let output_path = create_output_path(&args.input)?;
+    // The first command or option given that runs other programs or uses
+    // the network, which a WASI runtime does not provide
+    fn host_only(&self) -> Option<&'static str> {
+        match self.command {
+            Some(Command::Serve { .. }) => return Some("serve"),
+            Some(Command::Fetch(_)) => return Some("fetch"),
+            Some(Command::Consume(_)) => return Some("consume"),
+            Some(Command::Load(_)) => return Some("load"),
+            _ => {}
+        }
+        [
+            (self.sign_key.is_some(), "--sign-key"),
+            (self.signature_cert.is_some(), "--signature-cert"),
+            (self.signature_trust.is_some(), "--signature-trust"),
+            (self.decryption_key.is_some(), "--decryption-key"),
+            (self.post_hook.is_some(), "--post-hook"),
+            (self.notify_url.is_some(), "--notify-url"),
+            (self.duckdb.is_some(), "--duckdb"),
+        ]
+        .into_iter()
+        .find_map(|(given, option)| given.then_some(option))
+    }
 
-    println!(
-        "Converting {} to {}",
-        args.input.display(),
-        output_path.display()
-    );
+    fn reviewing(&self) -> bool {
+        matches!(self.command, Some(Command::Review { .. }))
+    }
 
-    // Parse the input file
-    let statement = parse_camt_10(&args.input)?;
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            lenient: self.lenient,
+            encoding: self.input_encoding,
+            keep_unknown: self.keep_unknown,
+            supplementary_data: self.supplementary_data,
+            timezone: self.timezone.clone().unwrap_or_default(),
+            strict: self.strict,
+            strip_control_chars: self.strip_control_chars,
+            limits: Limits {
+                max_depth: self.max_depth,
+                max_elements: self.max_elements,
+                max_text_length: self.max_text_length,
+            },
+        }
+    }
 
-    // Write the converted output
-    write_camt_08(&output_path, &statement)?;
+    fn page_limits(&self) -> PageLimits {
+        PageLimits {
+            max_entries: self.max_entries_per_file,
+            max_bytes: self.max_bytes,
+        }
+    }
 
-    println!("Conversion completed successfully!");
+    fn write_options(&self) -> WriteOptions {
+        WriteOptions {
+            version: self.target_version,
+            reference: self.reference,
+            schema_location: self.schema_location.clone(),
+            xsi_namespace: self.xsi_namespace,
+            fidelity: self.fidelity,
+            date_format: self.date_format.unwrap_or(if self.fidelity {
+                DateFormat::Input
+            } else {
+                DateFormat::Date
+            }),
+            timezone: self.timezone.clone().unwrap_or_default(),
+            servicer_bic: self.servicer_bic.clone(),
+            recipient_bic: self.recipient_bic.clone(),
+            strict: self.strict,
+            esr_references: self.esr_references,
+            message_id: self.msg_id.clone(),
+            charset: self.charset,
+            page: Page::default(),
+            indent: if self.compact {
+                Indent::Compact
+            } else {
+                Indent::Spaces(self.indent)
+            },
+        }
+    }
 
-    Ok(())
-}
+    // Short digest of everything that affects the output, so that a change of
+    // options or version causes documents to be converted again
+    fn options_fingerprint(&self) -> String {
+        let options = format!(
+            "{} {:?} {:?} {:?} {:?} {:?} {} {:?} {} {:?} {:?} {:?} {} {:?}",
+            env!("CARGO_PKG_VERSION"),
+            self.parse_options(),
+            self.write_options(),
+            self.name_template.as_ref().map(ToString::to_string),
+            self.hooks,
+            self.categories,
+            self.category_tags,
+            self.aliases,
+            self.clean_merchants,
+            self.merchant_patterns,
+            self.gl_accounts,
+            self.pending,
+            self.canonicalize,
+            self.sign_options()
+        );
+        let mut fingerprint = checksum(options.as_bytes(), ChecksumAlgorithm::Sha256);
+        fingerprint.truncate(16);
+        fingerprint
+    }
 
-fn create_output_path(input_path: &Path) -> Result<PathBuf> {
-    let file_stem = input_path
-        .file_stem()
-        .context("Invalid input filename")?
-        .to_string_lossy();
+    // Categories of the entries of statements not converted, for exports
+    fn categorize(&self, statement: &mut Statement) {
+        if let Some(categories) = &self.categories {
+            for transaction in &mut statement.transactions {
+                categories.apply(transaction, self.category_tags);
+            }
+        }
+    }
+
+    // The statement as exported, with cleaned merchant texts, the canonical
+    // names of counterparties and GL accounts
+    fn exported<'a>(&self, statement: &'a Statement) -> Cow<'a, Statement> {
+        if !self.clean_merchants && self.aliases.is_none() && self.gl_accounts.is_none() {
+            return Cow::Borrowed(statement);
+        }
+        let mut statement = statement.clone();
+        for transaction in &mut statement.transactions {
+            self.export_transaction(transaction);
+        }
+        Cow::Owned(statement)
+    }
+
+    fn export_transaction(&self, transaction: &mut Transaction) {
+        if self.clean_merchants {
+            match &self.merchant_patterns {
+                Some(cleanup) => cleanup.apply_transaction(transaction),
+                None => MerchantCleanup::default().apply_transaction(transaction),
+            }
+        }
+        if let Some(aliases) = &self.aliases {
+            aliases.apply_transaction(transaction);
+        }
+        if let Some(gl_accounts) = &self.gl_accounts {
+            gl_accounts.apply(transaction);
+        }
+    }
 
-    let mut output_path = input_path.to_path_buf();
-    output_path.set_file_name(format!("{}_08.xml", file_stem));
+    fn decrypt_options(&self) -> DecryptOptions {
+        DecryptOptions {
+            key: self.decryption_key.clone(),
+            passphrase_file: self.passphrase_file.clone(),
+        }
+    }
+
+    fn sign_options(&self) -> Option<SignOptions> {
+        let key = self.sign_key.clone()?;
+        Some(SignOptions {
+            key,
+            certificate: self.sign_cert.clone(),
+            mode: self.sign_mode,
+        })
+    }
+
+    fn verify_options(&self) -> VerifyOptions {
+        VerifyOptions {
+            certificate: self.signature_cert.clone(),
+            trusted: self.signature_trust.clone(),
+        }
+    }
+}
 
-    Ok(output_path)
+/// Exit status of the process, documented in the README
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Exit {
+    Success = 0,
+    /// Invalid arguments or input path
+    Usage = 1,
+    /// The input is not a readable camt.053.001.10 document
+    Parse = 2,
+    /// The document is readable but its data is invalid or incomplete
+    Validation = 3,
+    /// Some documents of an archive were converted, others failed
+    PartialFailure = 4,
+    /// Reading or writing a file failed
+    Io = 5,
+    /// Documents were converted with warnings and --fail-on-warning was given
+    Warnings = 6,
 }
 
-fn parse_camt_10(path: &Path) -> Result<Statement> {
-    let file = File::open(path)?;
-    let file = BufReader::new(file);
+impl Exit {
+    // Exit status of a run. When some documents of an archive fail, the
+    // run is only a partial failure if others were converted.
+    fn of_files(files: &[FileResult]) -> Exit {
+        let mut errors = files.iter().filter_map(|file| file.result.as_ref().err());
+        match errors.next() {
+            None => Exit::Success,
+            Some(_) if files.iter().any(|file| file.result.is_ok()) => Exit::PartialFailure,
+            Some(e) => Exit::of(e),
+        }
+    }
 
-    let mut reader = Reader::from_reader(file);
-    reader.config_mut().trim_text(true);
+    fn of(error: &anyhow::Error) -> Exit {
+        for cause in error.chain() {
+            if let Some(e) = cause.downcast_ref::<ConvertError>() {
+                return match e.kind() {
+                    ConvertError::Io(_) => Exit::Io,
+                    ConvertError::MissingField(_)
+                    | ConvertError::InvalidAmount { .. }
+                    | ConvertError::InvalidValue { .. }
+                    | ConvertError::FailedCheck { .. }
+                    | ConvertError::Overflow(_)
+                    | ConvertError::PageLimit(_) => Exit::Validation,
+                    _ => Exit::Parse,
+                };
+            }
+            if cause.is::<std::io::Error>() {
+                return Exit::Io;
+            }
+        }
+        // Errors raised by main itself are about its arguments
+        Exit::Usage
+    }
+}
 
-    let mut buf = Vec::new();
-    let mut statement = Statement::default();
-    let mut current_balance = Balance::default();
-    let mut current_transaction = Transaction::default();
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
 
-    let mut current_path = Vec::new();
-    let mut in_balance = false;
-    let mut in_transaction = false;
-    let mut in_charges = false;
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(e) => {
+            // Help and version requests are not errors
+            let _ = e.print();
+            return if e.use_stderr() {
+                Exit::Usage.into()
+            } else {
+                Exit::Success.into()
+            };
+        }
+    };
+    log::set_max_level(match (args.quiet, args.verbose) {
+        (true, _) => Level::Warn,
+        (false, 0) => Level::Info,
+        (false, 1) => Level::Debug,
+        (false, _) => Level::Trace,
+    });
+    log::set_format(args.log_format);
+    log::set_color(args.log_format == Format::Text && args.color(io::stderr().is_terminal()));
+    if args.prompt && !io::stdin().is_terminal() {
+        error!("--prompt needs a terminal to ask for values");
+        return Exit::Usage.into();
+    }
+    if cfg!(target_os = "wasi")
+        && let Some(option) = args.host_only()
+    {
+        error!(
+            "{} is not available in the WASI build, which cannot run other programs or use the network",
+            option
+        );
+        return Exit::Usage.into();
+    }
 
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                let name = std::str::from_utf8(e.name().0)?;
-                current_path.push(name.to_string());
-
-                match name {
-                    "Bal" => {
-                        in_balance = true;
-                        current_balance = Balance::default();
-                    }
-                    "Ntry" => {
-                        in_transaction = true;
-                        current_transaction = Transaction::default();
-                    }
-                    "Chrgs" => {
-                        in_charges = true;
-                    }
-                    _ => {}
+    let started = Local::now();
+    let earlier = match (&args.journal, args.resume) {
+        (Some(path), true) => match journal::read(path) {
+            Ok((entries, _)) => entries,
+            Err(e) => {
+                error!("Cannot read journal {}: {}", path.display(), e);
+                return Exit::Io.into();
+            }
+        },
+        _ => Vec::new(),
+    };
+    let files = match &args.command {
+        Some(Command::Serve {
+            listen,
+            api_key_file,
+            tenants,
+        }) => return serve(&args, listen, api_key_file.as_deref(), tenants.as_deref()).into(),
+        Some(Command::Fetch(options)) => fetch(&args, options, &earlier),
+        Some(Command::Generate(options)) => return generate(options).into(),
+        Some(Command::Reconcile(options)) => return reconcile(&args, options).into(),
+        Some(Command::Normalize(options)) => return normalize(&args, options).into(),
+        Some(Command::Inspect(options)) => return inspect(&args, options).into(),
+        Some(Command::Search(options)) => return search(&args, options).into(),
+        Some(Command::Extract(options)) => return extract(&args, options).into(),
+        Some(Command::Gaps(options)) => return gaps(&args, options).into(),
+        Some(Command::Load(options)) => return load(&args, options).into(),
+        Some(Command::History(options)) => return history(options).into(),
+        Some(Command::Consume(options)) => return consume(&args, options).into(),
+        #[cfg(feature = "schemas")]
+        Some(Command::Schemas(options)) => return schemas(options).into(),
+        Some(Command::Review { .. }) | None => run(&args, &earlier),
+    };
+    let mut exit = Exit::of_files(&files);
+    if args.fail_on_warning && exit == Exit::Success {
+        let warnings: usize = files
+            .iter()
+            .filter_map(|file| file.result.as_ref().ok())
+            .map(|converted| converted.warnings.len())
+            .sum();
+        if warnings > 0 {
+            error!(
+                "Converted with {} warnings, failing for --fail-on-warning",
+                warnings
+            );
+            exit = Exit::Warnings;
+        }
+    }
+    if let Some(dir) = &args.archive_dir
+        && matches!(args.command, None | Some(Command::Review { .. }))
+        && let Err(e) = archive_input(&args, dir, &files)
+    {
+        error!("Cannot archive {}: {:#}", args.input().display(), e);
+        if exit == Exit::Success {
+            exit = Exit::Io;
+        }
+    }
+    if let Some(dir) = &args.quarantine_dir
+        && matches!(args.command, None | Some(Command::Review { .. }))
+        && let Err(e) = quarantine_input(&args, dir, &files)
+    {
+        error!("Cannot quarantine {}: {:#}", args.input().display(), e);
+    }
+    if let Some(path) = &args.code_totals
+        && let Err(e) = write_code_totals(path, &files)
+    {
+        error!("Cannot write code totals to {}: {}", path.display(), e);
+        if exit == Exit::Success {
+            exit = Exit::Io;
+        }
+    }
+    if let Some(path) = &args.qr_report
+        && let Err(e) = write_qr_report(path, &files)
+    {
+        error!("Cannot write QR report to {}: {}", path.display(), e);
+        if exit == Exit::Success {
+            exit = Exit::Io;
+        }
+    }
+    if let Some(path) = &args.fx_report
+        && let Err(e) = write_fx_report(path, &files)
+    {
+        error!("Cannot write FX report to {}: {}", path.display(), e);
+        if exit == Exit::Success {
+            exit = Exit::Io;
+        }
+    }
+    if let Some(path) = &args.report {
+        let report = report(&files, exit, started);
+        match std::fs::write(path, format!("{}\n", report)) {
+            Ok(()) => info!("Wrote report to {}", path.display()),
+            Err(e) => {
+                error!("Cannot write report to {}: {}", path.display(), e);
+                if exit == Exit::Success {
+                    exit = Exit::Io;
                 }
             }
-            Ok(Event::Text(ref e)) => {
-                let text = e.unescape()?.to_string();
-                let path = current_path.join("/");
-
-                // Parse statement header information
-                if path.ends_with("Stmt/Id") {
-                    statement.id = text.clone();
-                } else if path.ends_with("Stmt/CreDtTm") {
-                    statement.creation_datetime = text.clone();
-                } else if path.ends_with("FrToDt/FrDtTm") {
-                    statement.from_datetime = text.clone();
-                } else if path.ends_with("FrToDt/ToDtTm") {
-                    statement.to_datetime = text.clone();
-                } else if path.ends_with("Acct/Id/IBAN") {
-                    statement.iban = text.clone();
-                } else if path.ends_with("Acct/Ccy") {
-                    statement.currency = text.clone();
-                } else if path.ends_with("Acct/Ownr/Nm") {
-                    statement.owner_name = text.clone();
+        }
+    }
+    if let Some(path) = &args.manifest {
+        let written = manifest(&args, &files)
+            .and_then(|manifest| std::fs::write(path, format!("{}\n", manifest)));
+        match written {
+            Ok(()) => info!("Wrote manifest to {}", path.display()),
+            Err(e) => {
+                error!("Cannot write manifest to {}: {}", path.display(), e);
+                if exit == Exit::Success {
+                    exit = Exit::Io;
                 }
+            }
+        }
+    }
+    if JOURNAL_FAILED.load(Ordering::Relaxed) && exit == Exit::Success {
+        exit = Exit::Io;
+    }
+    if let Some(url) = &args.notify_url {
+        for payload in files.iter().filter_map(notification) {
+            if let Err(e) = notify::post_json(url, &payload) {
+                warn!("{}", e);
+            }
+        }
+    }
+    if args.log_format == Format::Json {
+        let summary = summary(&files).with("exit_code", exit as u64);
+        println!("{}", summary);
+    }
+    if files.len() > 1 && args.log_format == Format::Text && log::enabled(Level::Info) {
+        summary_table(&files);
+    }
+    for file in &files {
+        if let Err(e) = &file.result {
+            error!("{:#}", e);
+        }
+    }
 
-                // Parse balance information
-                if in_balance {
-                    if path.ends_with("Bal/Tp/CdOrPrtry/Cd") {
-                        current_balance.balance_type = text.clone();
-                    } else if path.ends_with("Bal/CdtDbtInd") {
-                        current_balance.credit_debit_ind = text.clone();
-                    } else if path.ends_with("Bal/Dt/DtTm") {
-                        current_balance.date = text.clone();
-                    }
+    exit.into()
+}
+
+fn serve(
+    args: &Args,
+    listen: &str,
+    api_key_file: Option<&Path>,
+    tenant_file: Option<&Path>,
+) -> Exit {
+    let api_keys = match api_key_file {
+        None => Vec::new(),
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(text) => match server::parse_api_keys(&text) {
+                Ok(keys) if !keys.is_empty() => keys,
+                Ok(_) => {
+                    error!("No API keys in {}", path.display());
+                    return Exit::Usage;
+                }
+                Err(e) => {
+                    error!("Invalid API key file {}: {}", path.display(), e);
+                    return Exit::Usage;
                 }
+            },
+            Err(e) => {
+                error!("Cannot read {}: {}", path.display(), e);
+                return Exit::Io;
+            }
+        },
+    };
+    if api_keys.is_empty() {
+        warn!("No --api-key-file given, serving anyone who can connect");
+    }
+    let listener = match TcpListener::bind(listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Cannot listen on {}: {}", listen, e);
+            return Exit::Io;
+        }
+    };
+    match listener.local_addr() {
+        Ok(address) => info!("Listening on http://{}/convert", address),
+        Err(_) => info!("Listening on {}", listen),
+    }
+    let mut options = ServerOptions {
+        parse: args.parse_options(),
+        write: args.write_options(),
+        api_keys,
+        tenants: Vec::new(),
+    };
+    if let Some(path) = tenant_file {
+        let tenants = std::fs::read_to_string(path)
+            .map_err(|e| (Exit::Io, e.to_string()))
+            .and_then(|text| server::parse_tenants(&text, &options).map_err(|e| (Exit::Usage, e)));
+        match tenants {
+            Ok(tenants) => options.tenants = tenants,
+            Err((exit, e)) => {
+                error!("Cannot read tenants from {}: {}", path.display(), e);
+                return exit;
+            }
+        }
+        let names: Vec<&str> = options
+            .tenants
+            .iter()
+            .map(|tenant| tenant.name.as_str())
+            .collect();
+        info!("Serving tenants {}", names.join(", "));
+    }
+    match server::serve(listener, options) {
+        Ok(()) => Exit::Success,
+        Err(e) => {
+            error!("Cannot accept connections: {}", e);
+            Exit::Io
+        }
+    }
+}
 
-                // Parse transaction information
-                if in_transaction {
-                    if path.ends_with("Ntry/CdtDbtInd") {
-                        current_transaction.credit_debit_ind = text.clone();
-                    } else if path.ends_with("Ntry/BookgDt/DtTm") {
-                        current_transaction.booking_date = text.clone();
-                    } else if path.ends_with("Ntry/BkTxCd/Prtry/Cd") {
-                        current_transaction.bank_tx_code = text.clone();
-                    } else if path.ends_with("Ntry/AddtlNtryInf") {
-                        current_transaction.additional_info = text.clone();
-                    }
+// Download the files of an SFTP folder that are new since the last fetch and
+// convert them like files given on the command line
+fn generate(options: &Generate) -> Exit {
+    let seed = options.seed.unwrap_or_else(|| {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+        now.map_or(0, |now| now.as_nanos() as u64)
+    });
+    let generate_options = GenerateOptions {
+        entries: options.entries,
+        currency: options.currency.clone(),
+        date: options
+            .date
+            .unwrap_or_else(|| Local::now().date_naive() - Days::new(1)),
+        seed,
+    };
+    let result = match &options.output {
+        Some(path) => File::create(path)
+            .map_err(ConvertError::from)
+            .and_then(|file| generate_camt_10(BufWriter::new(file), &generate_options)),
+        None => generate_camt_10(BufWriter::new(io::stdout().lock()), &generate_options),
+    };
+    match result {
+        Ok(()) => {
+            info!(
+                "Generated {} entries in {} with seed {}",
+                options.entries, options.currency, seed
+            );
+            Exit::Success
+        }
+        Err(e) => {
+            error!("Cannot write statement: {}", e);
+            Exit::of(&e.into())
+        }
+    }
+}
 
-                    if in_charges && path.ends_with("Chrgs/TtlChrgsAndTaxAmt") {
-                        current_transaction.charges = Some(text.clone());
-                    }
+// List the bundled XSDs with whether their version is read or written,
+// print the XSD of one version or write them to a folder
+#[cfg(feature = "schemas")]
+fn schemas(options: &Schemas) -> Exit {
+    let selected: Vec<&Schema> = match &options.version {
+        Some(version) => match find_schema(version) {
+            Some(schema) => vec![schema],
+            None => {
+                error!("No schema of version {}", version);
+                return Exit::Usage;
+            }
+        },
+        None => SCHEMAS.iter().collect(),
+    };
+    if let Some(dir) = &options.export {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            error!("Cannot create {}: {}", dir.display(), e);
+            return Exit::Io;
+        }
+        for schema in selected {
+            let path = dir.join(schema.file_name());
+            if let Err(e) = std::fs::write(&path, schema.xsd) {
+                error!("Cannot write {}: {}", path.display(), e);
+                return Exit::Io;
+            }
+            info!("Exported {}", path.display());
+        }
+        return Exit::Success;
+    }
+    let mut stdout = io::stdout().lock();
+    let written = match (&options.version, selected.as_slice()) {
+        (Some(_), [schema]) => stdout.write_all(schema.xsd.as_bytes()),
+        _ => selected.iter().try_for_each(|schema| {
+            let roles = [
+                find_reader(schema.version).map(|_| "input"),
+                find_writer(schema.version).map(|_| "output"),
+            ];
+            let roles: Vec<&str> = roles.into_iter().flatten().collect();
+            writeln!(
+                stdout,
+                "{}  {:<7}  {}",
+                schema.version,
+                roles.join(", "),
+                schema.file_name()
+            )
+        }),
+    };
+    if written.is_err() {
+        return Exit::Io;
+    }
+    Exit::Success
+}
+
+// Convert the messages of a Kafka topic until consuming ends, publishing
+// each converted document or error under the key of its message
+fn consume(args: &Args, options: &Consume) -> Exit {
+    let kafka = Kafka {
+        brokers: options.brokers.clone(),
+        properties: options.properties.clone(),
+    };
+    let consumer = match kafka.consume(&options.topic, &options.group, options.exit_at_end) {
+        Ok(consumer) => consumer,
+        Err(e) => {
+            error!("Cannot consume {}: {}", options.topic, e);
+            return Exit::Io;
+        }
+    };
+    info!(
+        "Consuming {} from {} as {}",
+        options.topic, options.brokers, options.group
+    );
+    let parse_options = args.parse_options();
+    let write_options = args.write_options();
+    let error_topic = options
+        .error_topic
+        .as_ref()
+        .unwrap_or(&options.output_topic);
+    let (mut converted, mut failed) = (0, 0);
+    for message in consumer {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => {
+                error!("Cannot consume {}: {}", options.topic, e);
+                return Exit::Io;
+            }
+        };
+        let name = format!(
+            "{} partition {} offset {}",
+            message.topic, message.partition, message.offset
+        );
+        let payload = message.payload.as_deref().unwrap_or_default();
+        let published = match convert_bytes(payload, &parse_options, &write_options) {
+            Ok(document) => {
+                for warning in &document.warnings {
+                    warn!("{}: {}", name, warning);
                 }
+                converted += 1;
+                let headers = [
+                    ("content-type", "application/xml".to_string()),
+                    ("camtconvert-warnings", document.warnings.len().to_string()),
+                ];
+                kafka
+                    .publish(
+                        &options.output_topic,
+                        message.key.as_deref(),
+                        &headers,
+                        &document.output,
+                    )
+                    .map(|()| debug!("Published {} to {}", name, options.output_topic))
             }
+            Err(e) => {
+                warn!("Cannot convert {}: {}", name, e);
+                failed += 1;
+                let error = Value::object()
+                    .with("topic", message.topic.as_str())
+                    .with("partition", u64::from(message.partition))
+                    .with("offset", message.offset)
+                    .with("error", e.to_string());
+                let headers = [("content-type", "application/json".to_string())];
+                kafka.publish(
+                    error_topic,
+                    message.key.as_deref(),
+                    &headers,
+                    error.to_string().as_bytes(),
+                )
+            }
+        };
+        // The offset is committed already and the message is not consumed
+        // again, so stopping leaves the lost result to be looked into
+        if let Err(e) = published {
+            error!("Cannot publish the result of {}: {}", name, e);
+            return Exit::Io;
+        }
+    }
+    info!("Converted {} messages, {} failed", converted, failed);
+    Exit::Success
+}
 
-            Ok(Event::End(ref e)) => {
-                let name = std::str::from_utf8(e.name().0)?;
+fn reconcile(args: &Args, options: &Reconcile) -> Exit {
+    let expected =
+        match File::open(&options.expected).and_then(|file| read_expected(BufReader::new(file))) {
+            Ok(expected) => expected,
+            Err(e) => {
+                error!("Cannot read {}: {}", options.expected.display(), e);
+                return Exit::of(&e.into());
+            }
+        };
+    let mut reconciliation = Reconciliation::new(expected);
+    let parse_options = args.parse_options();
+    for path in &options.statements {
+        let parsed = File::open(path)
+            .map_err(ConvertError::from)
+            .and_then(|file| parse_camt_10(BufReader::new(file), &parse_options));
+        match parsed {
+            Ok(parsed) => reconciliation.add(&parsed.statement),
+            Err(e) => {
+                error!("Cannot read {}: {}", path.display(), e);
+                return Exit::of(&e.into());
+            }
+        }
+    }
+    let result = match &options.output {
+        Some(path) => {
+            File::create(path).and_then(|file| reconciliation.write_csv(BufWriter::new(file)))
+        }
+        None => reconciliation.write_csv(io::stdout().lock()),
+    };
+    if let Err(e) = result {
+        error!("Cannot write reconciliation: {}", e);
+        return Exit::Io;
+    }
+    info!(
+        "{} expected payments matched, {} missing, {} unexpected entries",
+        reconciliation.count(Status::Matched),
+        reconciliation.count(Status::Missing),
+        reconciliation.count(Status::Unexpected)
+    );
+    Exit::Success
+}
 
-                match name {
-                    "Bal" => {
-                        in_balance = false;
-                        statement.balances.push(current_balance.clone());
-                    }
-                    "Ntry" => {
-                        in_transaction = false;
-                        in_charges = false;
-                        statement.transactions.push(current_transaction.clone());
-                    }
-                    "Chrgs" => {
-                        in_charges = false;
-                    }
-                    _ => {}
-                }
+fn inspect(args: &Args, options: &Inspect) -> Exit {
+    let parsed = File::open(&options.file)
+        .map_err(ConvertError::from)
+        .and_then(|file| parse_camt_10(BufReader::new(file), &args.parse_options()));
+    let parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            error!("Cannot read {}: {}", options.file.display(), e);
+            return Exit::of(&e.into());
+        }
+    };
+    let mut warnings: Vec<String> = parsed.warnings.iter().map(ToString::to_string).collect();
+    warnings.extend(parsed.statement.check_balances());
+    let mut stdout = io::stdout().lock();
+    let written = if options.json {
+        writeln!(stdout, "{}", review::summary(&parsed.statement, &warnings))
+    } else {
+        let color = args.color(io::stdout().is_terminal());
+        write_review(&mut stdout, &parsed.statement, &warnings, color)
+    };
+    match written {
+        Ok(()) => Exit::Success,
+        Err(_) => Exit::Io,
+    }
+}
 
-                current_path.pop();
+fn search(args: &Args, options: &Search) -> Exit {
+    let filter = EntryFilter {
+        text: options.text.clone(),
+        amount: options.amount,
+    };
+    let mut files = Vec::new();
+    for path in &options.paths {
+        if let Err(e) = statement_files(path, &mut files) {
+            error!("Cannot read {}: {}", path.display(), e);
+            return Exit::Io;
+        }
+    }
+    let parse_options = args.parse_options();
+    let mut stdout = io::stdout().lock();
+    let (mut found, mut unreadable) = (0, 0);
+    for path in &files {
+        // The first version that can read the file
+        let parsed = READERS.iter().find_map(|reader| {
+            let mut input = BufReader::new(File::open(path).ok()?);
+            (reader.read)(&mut input, &parse_options).ok()
+        });
+        let Some(parsed) = parsed else {
+            warn!(
+                "Skipped {}, not a statement that can be read",
+                path.display()
+            );
+            unreadable += 1;
+            continue;
+        };
+        for transaction in parsed
+            .statement
+            .transactions
+            .iter()
+            .filter(|transaction| filter.matches(transaction))
+        {
+            found += 1;
+            let line = format!(
+                "{}  {:<10}  {:>16} {}  {}  {}",
+                path.display(),
+                transaction
+                    .booking_date
+                    .map_or_else(String::new, |date| date.date().to_string()),
+                transaction.signed_amount().to_string(),
+                transaction.amount.currency,
+                search::reference(transaction),
+                transaction.additional_info
+            );
+            if writeln!(stdout, "{}", line.trim_end()).is_err() {
+                return Exit::Io;
             }
-            Ok(Event::Eof) => break,
-            Err(e) => return Err(anyhow::anyhow!("Error parsing XML: {}", e)),
-            _ => {}
         }
+    }
+    info!(
+        "{} entries found in {} files, {} skipped",
+        found,
+        files.len() - unreadable,
+        unreadable
+    );
+    Exit::Success
+}
 
-        buf.clear();
+fn extract(args: &Args, options: &Extract) -> Exit {
+    let parsed = File::open(&options.file)
+        .map_err(ConvertError::from)
+        .and_then(|file| parse_camt_10(BufReader::new(file), &args.parse_options()));
+    let mut statement = match parsed {
+        Ok(parsed) => parsed.statement,
+        Err(e) => {
+            error!("Cannot read {}: {}", options.file.display(), e);
+            return Exit::of(&e.into());
+        }
+    };
+    args.categorize(&mut statement);
+    let write_options = args.write_options();
+    let transaction = match find_entries(&statement, &options.reference, write_options.reference)[..]
+    {
+        [transaction] => transaction,
+        [] => {
+            error!(
+                "No entry of {} has the reference {}",
+                options.file.display(),
+                options.reference
+            );
+            return Exit::Usage;
+        }
+        ref entries => {
+            error!(
+                "{} entries of {} have the reference {}",
+                entries.len(),
+                options.file.display(),
+                options.reference
+            );
+            return Exit::Usage;
+        }
+    };
+    let mut document = Vec::new();
+    if options.json {
+        let mut transaction = transaction.clone();
+        args.export_transaction(&mut transaction);
+        let value = entry_value(&statement, &transaction, write_options.reference);
+        document = format!("{}\n", value).into_bytes();
+    } else if let Err(e) =
+        write_notification(&mut document, &statement, transaction, &write_options)
+    {
+        error!("Cannot write the entry: {}", e);
+        return Exit::of(&e.into());
     }
+    let written = match &options.output {
+        Some(path) => std::fs::write(path, &document),
+        None => io::stdout().lock().write_all(&document),
+    };
+    match written {
+        Ok(()) => Exit::Success,
+        Err(e) => {
+            error!("Cannot write the entry: {}", e);
+            Exit::Io
+        }
+    }
+}
 
-    // Special handling for Amt elements which contain both attribute and text
-    let file = File::open(path)?;
-    let file = BufReader::new(file);
-    let mut reader = Reader::from_reader(file);
-    reader.config_mut().trim_text(true);
+fn gaps(args: &Args, options: &Gaps) -> Exit {
+    let mut files = Vec::new();
+    for path in &options.paths {
+        if let Err(e) = statement_files(path, &mut files) {
+            error!("Cannot read {}: {}", path.display(), e);
+            return Exit::Io;
+        }
+    }
+    let normalize = |iban: &str| {
+        iban.chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase()
+    };
+    let parse_options = args.parse_options();
+    let mut deliveries = Vec::new();
+    for path in &files {
+        let parsed = READERS.iter().find_map(|reader| {
+            let mut input = BufReader::new(File::open(path).ok()?);
+            (reader.read)(&mut input, &parse_options).ok()
+        });
+        let Some(parsed) = parsed else {
+            warn!(
+                "Skipped {}, not a statement that can be read",
+                path.display()
+            );
+            continue;
+        };
+        let delivery = Delivery::of(&path.display().to_string(), &parsed.statement);
+        if delivery.iban.is_empty()
+            || options
+                .iban
+                .as_ref()
+                .is_some_and(|iban| normalize(iban) != normalize(&delivery.iban))
+        {
+            continue;
+        }
+        if delivery.sequence_number.is_none() {
+            warn!("{} has no sequence number", path.display());
+        }
+        deliveries.push(delivery);
+    }
 
-    let mut buf = Vec::new();
-    let mut current_path = Vec::new();
-    let mut in_balance = false;
-    let mut in_transaction = false;
-    let mut balance_idx = 0;
-    let mut tx_idx = 0;
+    let gaps = find_gaps(&deliveries);
+    let mut stdout = io::stdout().lock();
+    for gap in &gaps {
+        if writeln!(stdout, "{}", gap).is_err() {
+            return Exit::Io;
+        }
+    }
+    info!("{} gaps in {} statements", gaps.len(), deliveries.len());
+    if gaps.is_empty() {
+        Exit::Success
+    } else {
+        Exit::Validation
+    }
+}
 
-    loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                let name = std::str::from_utf8(e.name().0)?;
-                current_path.push(name.to_string());
-
-                match name {
-                    "Bal" => {
-                        in_balance = true;
-                    }
-                    "Ntry" => {
-                        in_transaction = true;
-                    }
-                    "Amt" => {
-                        // Handle Amt element
-                        let mut currency = String::new();
-                        for attr in e.attributes() {
-                            let attr = attr?;
-                            if attr.key.0 == b"Ccy" {
-                                currency = std::str::from_utf8(&attr.value)?.to_string();
-                            }
-                        }
-
-                        // Read the amount value
-                        if let Ok(Event::Text(ref t)) = reader.read_event_into(&mut buf) {
-                            let amount = t.unescape()?.to_string();
-
-                            let path = current_path.join("/");
-                            if in_balance
-                                && path.ends_with("Bal/Amt")
-                                && balance_idx < statement.balances.len()
-                            {
-                                statement.balances[balance_idx].amount = amount;
-                                statement.balances[balance_idx].currency = currency;
-                            } else if in_transaction
-                                && path.ends_with("Ntry/Amt")
-                                && tx_idx < statement.transactions.len()
-                            {
-                                statement.transactions[tx_idx].amount = amount;
-                                statement.transactions[tx_idx].currency = currency;
-                            }
-                        }
-                    }
-                    _ => {}
+// Load statements into PostgreSQL, each in its own transaction. Statements
+// that cannot be read are reported and left out; loading stops at the first
+// database error.
+fn load(args: &Args, options: &Load) -> Exit {
+    let mut files = Vec::new();
+    for path in &options.paths {
+        if let Err(e) = statement_files(path, &mut files) {
+            error!("Cannot read {}: {}", path.display(), e);
+            return Exit::Io;
+        }
+    }
+    let parse_options = args.parse_options();
+    let (mut loaded, mut unreadable) = (0, 0);
+    for path in &files {
+        let parsed = READERS.iter().find_map(|reader| {
+            let mut input = BufReader::new(File::open(path).ok()?);
+            (reader.read)(&mut input, &parse_options).ok()
+        });
+        let Some(parsed) = parsed else {
+            error!(
+                "Cannot load {}, not a statement that can be read",
+                path.display()
+            );
+            unreadable += 1;
+            continue;
+        };
+        let mut statement = parsed.statement;
+        args.categorize(&mut statement);
+        if let Err(e) = postgres::load(&options.pg, &args.exported(&statement), args.reference) {
+            error!("Cannot load {}: {}", path.display(), e);
+            return Exit::Io;
+        }
+        info!(
+            "Loaded {}: {} sequence {}, {} balances and {} entries",
+            path.display(),
+            statement.iban,
+            postgres::sequence_number(&statement),
+            statement.balances.len(),
+            statement.transactions.len()
+        );
+        loaded += 1;
+    }
+    info!("{} statements loaded, {} unreadable", loaded, unreadable);
+    if unreadable > 0 {
+        Exit::Parse
+    } else {
+        Exit::Success
+    }
+}
+
+// Statement files of a folder and its subfolders in name order, `.xml` or
+// gzip-compressed `.xml.gz`, or the path itself if it is a file
+fn statement_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if entry.is_dir() {
+            statement_files(&entry, files)?;
+        } else if name.ends_with(".xml") || name.ends_with(".xml.gz") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn normalize(args: &Args, options: &Normalize) -> Exit {
+    let indent = args.write_options().indent;
+    let input = match File::open(&options.file) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            error!("Cannot read {}: {}", options.file.display(), e);
+            return Exit::Io;
+        }
+    };
+    let result = match &options.output {
+        Some(path) => File::create(path)
+            .map_err(ConvertError::from)
+            .and_then(|file| {
+                normalize_camt_10(input, BufWriter::new(file), &args.parse_options(), indent)
+            }),
+        None => normalize_camt_10(input, io::stdout().lock(), &args.parse_options(), indent),
+    };
+    match result {
+        Ok(warnings) => {
+            for warning in &warnings {
+                warn!("{}", warning);
+            }
+            Exit::Success
+        }
+        Err(e) => {
+            error!("Cannot normalize {}: {}", options.file.display(), e);
+            Exit::of(&e.into())
+        }
+    }
+}
+
+fn fetch(args: &Args, options: &Fetch, earlier: &[JournalEntry]) -> Vec<FileResult> {
+    let failed = |input: String, error: anyhow::Error| {
+        let failed = vec![FileResult {
+            input,
+            input_checksum: None,
+            result: Err(error),
+        }];
+        add_to_journal(args, &failed);
+        failed
+    };
+    if let Err(e) = std::fs::create_dir_all(&options.download_dir) {
+        return failed(
+            options.download_dir.display().to_string(),
+            anyhow::Error::new(e).context("Cannot create download folder"),
+        );
+    }
+    let sftp = Sftp {
+        url: options.url.clone(),
+        identity: options.identity.clone(),
+    };
+    let remote_files = match sftp.list(Local::now().date_naive()) {
+        Ok(remote_files) => remote_files,
+        Err(e) => {
+            let context = format!("Cannot list {}", options.url);
+            return failed(
+                options.url.to_string(),
+                anyhow::Error::new(e).context(context),
+            );
+        }
+    };
+
+    let url = options.url.to_string();
+    let mut files = Vec::new();
+    for remote in remote_files {
+        let input_name = format!("{}/{}", url.trim_end_matches('/'), remote.name);
+        if options.since.is_some_and(|since| remote.modified < since) {
+            debug!("Skipping {}, modified on {}", input_name, remote.modified);
+            continue;
+        }
+        // A local file of the same name and size was fetched before. When
+        // resuming, it is converted unless the journal has it as converted.
+        let local_path = options.download_dir.join(&remote.name);
+        let downloaded =
+            std::fs::metadata(&local_path).is_ok_and(|metadata| metadata.len() == remote.size);
+        if downloaded && !args.resume {
+            debug!("Skipping {}, already downloaded", input_name);
+            continue;
+        }
+
+        let path = if downloaded {
+            local_path
+        } else {
+            info!("Downloading {}", input_name);
+            match sftp.download(&remote, &options.download_dir) {
+                Ok(path) => path,
+                Err(e) => {
+                    let context = format!("Cannot download {}", input_name);
+                    files.extend(failed(input_name, anyhow::Error::new(e).context(context)));
+                    continue;
                 }
             }
-            Ok(Event::End(ref e)) => {
-                let name = std::str::from_utf8(e.name().0)?;
+        };
+        let file_args = Args {
+            input: Some(path.clone()),
+            command: None,
+            ..args.clone()
+        };
+        let results = run(&file_args, earlier);
+
+        // Remote files are only touched once all their documents converted
+        if results.iter().all(|file| file.result.is_ok()) {
+            let cleaned_up = if let Some(folder) = &options.archive_remote {
+                sftp.archive(&remote.name, folder)
+                    .map(|()| info!("Moved {} to {}", input_name, folder))
+            } else if options.delete_remote {
+                sftp.delete(&remote.name)
+                    .map(|()| info!("Deleted {}", input_name))
+            } else {
+                Ok(())
+            };
+            if let Err(e) = cleaned_up {
+                warn!("Cannot clean up {}: {}", input_name, e);
+            }
+        } else {
+            let quarantined = match &args.quarantine_dir {
+                Some(dir) => quarantine_input(&file_args, dir, &results).unwrap_or_else(|e| {
+                    warn!("Cannot quarantine {}: {:#}", path.display(), e);
+                    false
+                }),
+                None => false,
+            };
+            // Otherwise it would be taken as fetched and not tried again
+            if !quarantined && let Err(e) = std::fs::remove_file(&path) {
+                warn!("Cannot remove {}: {}", path.display(), e);
+            }
+        }
+        files.extend(results);
+    }
+    if files.is_empty() {
+        info!("No new statement files in {}", url);
+    }
+    files
+}
 
-                match name {
-                    "Bal" => {
-                        in_balance = false;
-                        balance_idx += 1;
-                    }
-                    "Ntry" => {
-                        in_transaction = false;
-                        tx_idx += 1;
+// Outcome of a conversion, for the JSON summary
+struct Converted {
+    output_path: PathBuf,
+    stats: ConversionStats,
+    warnings: Vec<String>,
+    // Paths of elements not converted, with their count
+    dropped: BTreeMap<String, usize>,
+    // The output was left as it is because the input did not change
+    skipped: bool,
+    // All pages if the output was split, the first at output_path
+    pages: Vec<PathBuf>,
+    // Interim statement of the pending entries, with --pending separate-file
+    pending_output: Option<PathBuf>,
+    code_totals: CodeTotals,
+    // Entries with invoice references, for --qr-report
+    invoices: InvoiceReport,
+    // Entries with a currency exchange, for --fx-report
+    exchanges: ExchangeReport,
+    // Account, period and balances, for notifications
+    statement: Value,
+}
+
+impl Converted {
+    // Files written, the pages if the output was split
+    fn outputs(&self) -> &[PathBuf] {
+        if self.pages.is_empty() {
+            std::slice::from_ref(&self.output_path)
+        } else {
+            &self.pages
+        }
+    }
+}
+
+// Result of converting one input document
+struct FileResult {
+    input: String,
+    // SHA-256 of the input as it was received, for --manifest
+    input_checksum: Option<String>,
+    result: Result<Converted>,
+}
+
+enum Input {
+    Document(Box<dyn BufRead>),
+    Archive(ZipArchive<BufReader<File>>),
+}
+
+// Documents the journal has as converted are skipped with --resume; `earlier`
+// are the entries of the journal, empty without it
+fn run(args: &Args, earlier: &[JournalEntry]) -> Vec<FileResult> {
+    let input_name = args.input().display().to_string();
+    let input_checksum = input_checksum(args);
+    if let Some(checksum) = &input_checksum
+        && let Some(entry) =
+            journal::converted(earlier, &input_name, checksum, &args.options_fingerprint())
+    {
+        let files = vec![FileResult {
+            input: input_name,
+            input_checksum,
+            result: Ok(resumed(entry)),
+        }];
+        add_to_journal(args, &files);
+        return files;
+    }
+    let files = match open_input(args) {
+        Ok(Input::Document(input)) => {
+            let mut outputs = Outputs::new(args);
+            let result = input_path(args)
+                .and_then(|input_path| outputs.start(&input_path))
+                .and_then(|output| convert(args, input, &input_name, &mut outputs, output));
+            vec![FileResult {
+                input: input_name,
+                input_checksum,
+                result,
+            }]
+        }
+        // Members are added to the journal as they are converted
+        Ok(Input::Archive(archive)) => return convert_archive(args, archive, earlier),
+        Err(e) => vec![FileResult {
+            input: input_name,
+            input_checksum,
+            result: Err(e),
+        }],
+    };
+    add_to_journal(args, &files);
+    files
+}
+
+// SHA-256 of the input file for the manifest and journal. Input from a URL
+// is not kept, so it has none.
+fn input_checksum(args: &Args) -> Option<String> {
+    #[cfg(feature = "remote")]
+    if remote_url(args).is_some() {
+        return None;
+    }
+    if args.manifest.is_none() && args.journal.is_none() {
+        return None;
+    }
+    file_checksum(args.input(), ChecksumAlgorithm::Sha256).ok()
+}
+
+// Skipped document that the journal has as converted by an earlier run
+fn resumed(entry: &JournalEntry) -> Converted {
+    info!(
+        "Skipping {}, converted on {}",
+        entry.input,
+        entry.time.format("%Y-%m-%d %H:%M:%S")
+    );
+    let outputs: Vec<PathBuf> = entry.outputs.iter().map(PathBuf::from).collect();
+    let datetime = |datetime: Option<DateTime<FixedOffset>>| datetime.map(|d| d.to_rfc3339());
+    Converted {
+        output_path: outputs.first().cloned().unwrap_or_default(),
+        stats: ConversionStats::default(),
+        warnings: Vec::new(),
+        dropped: BTreeMap::new(),
+        skipped: true,
+        pages: if outputs.len() > 1 {
+            outputs
+        } else {
+            Vec::new()
+        },
+        pending_output: None,
+        code_totals: CodeTotals::new(),
+        invoices: InvoiceReport::new(),
+        exchanges: ExchangeReport::new(),
+        statement: Value::object()
+            .with("iban", entry.account.as_str())
+            .with("id", entry.statement.as_str())
+            .with("from", datetime(entry.from))
+            .with("to", datetime(entry.to)),
+    }
+}
+
+// Path the outputs of the input are named after. Downloads are converted
+// into the current folder.
+fn input_path(args: &Args) -> Result<PathBuf> {
+    #[cfg(feature = "remote")]
+    if let Some(url) = remote_url(args) {
+        return remote::file_name(url)
+            .map(PathBuf::from)
+            .with_context(|| format!("URL {} does not name a file", url));
+    }
+    Ok(args.input().to_path_buf())
+}
+
+#[cfg(feature = "remote")]
+fn remote_url(args: &Args) -> Option<&str> {
+    args.input()
+        .to_str()
+        .filter(|input| remote::is_remote(input))
+}
+
+fn open_input(args: &Args) -> Result<Input> {
+    #[cfg(feature = "remote")]
+    if let Some(url) = remote_url(args) {
+        if args.verify_checksum {
+            anyhow::bail!("Cannot verify the checksum of input from a URL");
+        }
+        debug!("Downloading {}", url);
+        let mut input = BufReader::new(remote::open(url)?);
+        let prefix = input.fill_buf()?;
+        // Archives need to be read out of order, and gpg reads files
+        if is_zip(prefix) || is_encrypted(prefix) {
+            anyhow::bail!(
+                "Zip archives and encrypted documents cannot be read from a URL, download {} first",
+                url
+            );
+        }
+        return Ok(Input::Document(Box::new(input)));
+    }
+
+    let path = args.input();
+    // Validate input file exists
+    if !path.exists() {
+        anyhow::bail!("Input file does not exist: {}", path.display());
+    }
+    if args.verify_checksum {
+        verify_sidecar(path, args.checksum.unwrap_or_default())?;
+        debug!("Checksum of {} verified", path.display());
+    }
+
+    let mut input = BufReader::new(File::open(path)?);
+    let prefix = input.fill_buf()?;
+    if is_zip(prefix) {
+        Ok(Input::Archive(ZipArchive::new(input)?))
+    } else if is_encrypted(prefix) {
+        debug!("Decrypting {} with gpg", path.display());
+        let mut file = input.into_inner();
+        file.rewind()?;
+        let plaintext = decrypt(file, &args.decrypt_options())?;
+        Ok(Input::Document(Box::new(BufReader::new(plaintext))))
+    } else {
+        Ok(Input::Document(Box::new(input)))
+    }
+}
+
+// Read a document and verify its signature. A compressed document is
+// returned as it is, its signature is verified on the decompressed document.
+fn read_verified(input: &mut impl BufRead, options: &VerifyOptions) -> Result<Vec<u8>> {
+    let mut document = Vec::new();
+    input.read_to_end(&mut document)?;
+    if is_gzip(&document) {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(document.as_slice()).read_to_end(&mut decompressed)?;
+        dsig::verify(&decompressed, options)?;
+    } else {
+        dsig::verify(&document, options)?;
+    }
+    Ok(document)
+}
+
+// Convert each XML document of a zip archive, naming the outputs after the
+// archive members
+fn convert_archive(
+    args: &Args,
+    mut archive: ZipArchive<BufReader<File>>,
+    earlier: &[JournalEntry],
+) -> Vec<FileResult> {
+    // Members converted by an earlier run are skipped if they did not change
+    let output_dir = args.input().parent().unwrap_or(Path::new(""));
+    let mut state = match ConversionState::load(output_dir) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!(
+                "Cannot read conversion state, converting all documents: {}",
+                e
+            );
+            None
+        }
+    };
+    let fingerprint = args.options_fingerprint();
+
+    let archive_name = args
+        .input()
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let mut files = Vec::new();
+    let mut outputs = Outputs::new(args);
+    for index in 0..archive.entries().len() {
+        let entry = &archive.entries()[index];
+        let Some(file_name) = entry.document_name() else {
+            continue;
+        };
+        let input_name = format!("{}:{}", args.input().display(), entry.name);
+        let state_name = format!("{}:{}", archive_name, entry.name);
+        // Only the file name is used, so members cannot be written outside
+        // the folder of the archive
+        let member_path = args.input().with_file_name(file_name);
+
+        let member_checksum = archive
+            .open(index)
+            .and_then(|member| reader_checksum(member, ChecksumAlgorithm::Sha256));
+        let result = outputs
+            .start(&member_path)
+            .and_then(|output| {
+                let input_checksum = member_checksum
+                    .as_ref()
+                    .map_err(|e| io::Error::new(e.kind(), e.to_string()))?;
+                if let Some(entry) =
+                    journal::converted(earlier, &input_name, input_checksum, &fingerprint)
+                {
+                    let converted = resumed(entry);
+                    if output.path.is_none() {
+                        outputs.claim(converted.output_path.clone())?;
                     }
-                    _ => {}
+                    return Ok(converted);
+                }
+                // Totals and reports need every document to be read
+                if let Some(state) = &state
+                    && !args.rerun
+                    && args.code_totals.is_none()
+                    && args.qr_report.is_none()
+                    && args.fx_report.is_none()
+                    && let Some(output_path) =
+                        state.unchanged_output(&state_name, input_checksum, &fingerprint)
+                {
+                    info!(
+                        "Skipping {}, unchanged since {} was written",
+                        input_name,
+                        output_path.display()
+                    );
+                    return Ok(Converted {
+                        output_path: outputs.claim(output_path)?,
+                        stats: ConversionStats::default(),
+                        warnings: Vec::new(),
+                        dropped: BTreeMap::new(),
+                        skipped: true,
+                        pages: Vec::new(),
+                        pending_output: None,
+                        code_totals: CodeTotals::new(),
+                        invoices: InvoiceReport::new(),
+                        exchanges: ExchangeReport::new(),
+                        statement: Value::Null,
+                    });
                 }
 
-                current_path.pop();
-            }
-            Ok(Event::Eof) => break,
-            _ => {}
-        }
+                let converted = convert(
+                    args,
+                    archive.open(index)?,
+                    &input_name,
+                    &mut outputs,
+                    output,
+                )?;
+                if let Some(state) = &mut state {
+                    state.record(
+                        &state_name,
+                        &converted.output_path,
+                        input_checksum,
+                        &fingerprint,
+                    );
+                }
+                Ok(converted)
+            })
+            .with_context(|| format!("Failed to convert {}", input_name));
+        let file = FileResult {
+            input: input_name,
+            input_checksum: member_checksum.ok(),
+            result,
+        };
+        add_to_journal(args, std::slice::from_ref(&file));
+        files.push(file);
+    }
 
-        buf.clear();
+    if let Some(state) = &state
+        && let Err(e) = state.save()
+    {
+        warn!("Cannot save conversion state: {}", e);
     }
 
-    Ok(statement)
+    if files.is_empty() {
+        files.push(FileResult {
+            input: args.input().display().to_string(),
+            input_checksum: input_checksum(args),
+            result: Err(anyhow::anyhow!(
+                "No XML documents found in archive {}",
+                args.input().display()
+            )),
+        });
+        add_to_journal(args, &files);
+    }
+    files
 }
 
-fn write_camt_08(path: &Path, statement: &Statement) -> Result<()> {
-    let file = File::create(path)?;
-    let file = BufWriter::new(file);
+fn convert(
+    args: &Args,
+    input: impl BufRead,
+    input_name: &str,
+    outputs: &mut Outputs,
+    mut output: Output,
+) -> Result<Converted> {
+    match &output.path {
+        Some(output_path) => info!("Converting {} to {}", input_name, output_path.display()),
+        None => info!("Converting {}", input_name),
+    }
 
-    let mut writer = Writer::new_with_indent(file, b' ', 4);
+    let converting = Instant::now();
+    // A signed document is verified as a whole before any of it is converted
+    let mut input = input;
+    let document;
+    let mut verified: &[u8];
+    let input: &mut dyn BufRead = if args.verify_options().is_enabled() {
+        document = read_verified(&mut input, &args.verify_options())?;
+        debug!("Signature of {} verified", input_name);
+        verified = &document;
+        &mut verified
+    } else {
+        &mut input
+    };
+    let mut input = CountingReader::new(input);
+    let parse_options = args.parse_options();
+    let mut write_options = args.write_options();
+    let no_hooks = Hooks::default();
+    let hooks = args.hooks.as_ref().unwrap_or(&no_hooks);
+    let no_categories = Categories::default();
+    let categories = args.categories.as_ref().unwrap_or(&no_categories);
+    let mut seen = match &args.seen_entries {
+        Some(path) => Some(
+            SeenEntries::load(path)
+                .with_context(|| format!("Cannot read seen entries from {}", path.display()))?,
+        ),
+        None => None,
+    };
+    let mut duplicates = seen
+        .as_ref()
+        .map(|seen| DuplicateCheck::new(seen, args.duplicates, write_options.reference));
+
+    // A statement under review is held in memory until it is confirmed
+    let mut converted = if args.stream && !args.reviewing() {
+        convert_streaming(
+            &mut input,
+            args,
+            outputs,
+            output,
+            write_options,
+            duplicates.as_mut(),
+        )?
+    } else {
+        // Parse the input file
+        let started = Instant::now();
+        let parsed = parse_camt_10(&mut input, &parse_options)?;
+        let mut warnings: Vec<String> = parsed.warnings.iter().map(ToString::to_string).collect();
+        let mut statement = parsed.statement;
+        hooks.apply_statement(&mut statement);
+        for transaction in &mut statement.transactions {
+            hooks.apply_transaction(transaction);
+            categories.apply(transaction, args.category_tags);
+        }
+        debug!(
+            "Read {} balances and {} entries in {:.1?}",
+            statement.balances.len(),
+            statement.transactions.len(),
+            started.elapsed()
+        );
+        log_bank(&statement);
+
+        // Opening balance plus entries should add up to the closing balance
+        warnings.extend(statement.check_balances());
+        if let Some(duplicates) = &mut duplicates {
+            let mut transactions = std::mem::take(&mut statement.transactions);
+            transactions.retain(|transaction| duplicates.keep(&statement, transaction));
+            statement.transactions = transactions;
+            warnings.append(&mut duplicates.warnings);
+        }
+        let pending = match args.pending {
+            PendingAction::Include => Vec::new(),
+            PendingAction::Drop | PendingAction::SeparateFile => take_pending(&mut statement),
+        };
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
 
-    // Write XML declaration
-    writer.write_event(Event::Decl(quick_xml::events::BytesDecl::new(
-        "1.0",
-        Some("UTF-8"),
-        None,
-    )))?;
+        if args.prompt {
+            prompt_missing(&statement, &mut write_options)?;
+        }
+        for placeholder in check_placeholders(&statement, &write_options)? {
+            warn!("{}", placeholder);
+            warnings.push(placeholder.to_string());
+        }
+        for truncation in check_lengths(&statement, &write_options)? {
+            warn!("{}", truncation);
+            warnings.push(truncation.to_string());
+        }
+
+        // Write the converted output
+        let started = Instant::now();
+        let mut output_path = outputs.resolve(&mut output, &statement)?;
+        if args.reviewing() {
+            review(
+                &statement,
+                &warnings,
+                &output_path,
+                args.color(io::stdout().is_terminal()),
+            )?;
+        }
+        let page_limits = args.page_limits();
+        let mut pages = Vec::new();
+        if args.format == OutputFormat::Arrow {
+            let file = BufWriter::new(File::create(&output_path)?);
+            arrow::write_entries(file, &args.exported(&statement), write_options.reference)?;
+        } else if page_limits.is_unlimited() {
+            let file = BufWriter::new(File::create(&output_path)?);
+            write_statement(file, &statement, &write_options)?;
+        } else {
+            let documents = write_pages(&statement, &write_options, &page_limits)?;
+            if documents.len() > 1 {
+                pages = (1..=documents.len())
+                    .map(|number| outputs.claim(page_path(&output_path, number)))
+                    .collect::<io::Result<_>>()?;
+                info!(
+                    "Split {} into {} pages",
+                    output_path.display(),
+                    documents.len()
+                );
+                output_path = pages[0].clone();
+            }
+            let paths = if pages.is_empty() {
+                vec![output_path.clone()]
+            } else {
+                pages.clone()
+            };
+            for (path, document) in paths.iter().zip(documents) {
+                std::fs::write(path, document)?;
+            }
+        }
+        debug!(
+            "Wrote {} in {:.1?}",
+            output_path.display(),
+            started.elapsed()
+        );
+        let pending_output = write_pending(
+            outputs,
+            &output_path,
+            &statement,
+            pending,
+            args.pending,
+            &write_options,
+        )?;
+        if let Some(database) = &args.duckdb {
+            duckdb::write_entries(
+                database,
+                &args.exported(&statement),
+                write_options.reference,
+            )
+            .with_context(|| format!("Cannot write entries to {}", database.display()))?;
+            debug!("Wrote entries to {}", database.display());
+        }
 
-    // Start Document element with namespace
-    let mut doc_elem = BytesStart::new("Document");
-    doc_elem.push_attribute(("xmlns", "urn:iso:std:iso:20022:tech:xsd:camt.053.001.08"));
-    doc_elem.push_attribute(("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"));
-    writer.write_event(Event::Start(doc_elem))?;
+        let mut code_totals = CodeTotals::new();
+        let mut invoices = InvoiceReport::new();
+        let mut exchanges = ExchangeReport::new();
+        for transaction in &statement.transactions {
+            code_totals.add(transaction)?;
+            invoices.add(&statement, transaction);
+            exchanges.add(&statement, transaction);
+        }
+
+        Converted {
+            output_path,
+            stats: ConversionStats {
+                balances: statement.balances.len(),
+                entries: statement.transactions.len(),
+                ..ConversionStats::default()
+            },
+            warnings,
+            dropped: parsed.dropped,
+            skipped: false,
+            pages,
+            pending_output,
+            code_totals,
+            invoices,
+            exchanges,
+            statement: statement_value(&statement),
+        }
+    };
+
+    for rules in &args.rules {
+        check_output(&mut converted, *rules, args.strict)?;
+    }
+    if args.canonicalize {
+        for output_path in converted.outputs().iter().chain(&converted.pending_output) {
+            let document = std::fs::read(output_path)?;
+            let mut canonical = Vec::with_capacity(document.len());
+            canonicalize(&document, &mut canonical)?;
+            std::fs::write(output_path, canonical)?;
+        }
+    }
+    if let Some(options) = args.sign_options() {
+        sign_outputs(&converted, &options)?;
+    }
 
-    // BkToCstmrStmt
-    writer.write_event(Event::Start(BytesStart::new("BkToCstmrStmt")))?;
+    // Entries are recorded once their output passed all checks
+    let keys = duplicates.map(DuplicateCheck::into_keys);
+    if let (Some(seen), Some(keys), Some(path)) = (&mut seen, keys, &args.seen_entries) {
+        for (iban, key) in keys {
+            seen.insert(&iban, &key, input_name);
+        }
+        if let Err(e) = seen.save() {
+            warn!("Cannot save seen entries to {}: {}", path.display(), e);
+        }
+    }
 
-    // Write Group Header
-    write_group_header(&mut writer, statement)?;
+    if let Some(algorithm) = args.checksum {
+        for output_path in converted.outputs().iter().chain(&converted.pending_output) {
+            let sidecar = write_sidecar(output_path, algorithm)?;
+            debug!("Wrote checksum to {}", sidecar.display());
+        }
+    }
+    if let Some(hook) = &args.post_hook {
+        run_post_hook(hook, &converted, input_name, args.target_version)?;
+    }
 
-    // Write Statement
-    write_statement(&mut writer, statement)?;
+    let bytes_written = converted
+        .outputs()
+        .iter()
+        .chain(&converted.pending_output)
+        .map(|path| Ok(std::fs::metadata(path)?.len()))
+        .sum::<io::Result<u64>>()?;
+    let stats = &mut converted.stats;
+    stats.totals = converted.code_totals.by_currency()?;
+    stats.warnings = converted.warnings.len();
+    stats.bytes_read = input.count();
+    stats.bytes_written = bytes_written;
+    stats.duration = converting.elapsed();
+    info!("{} {}", log::paint("Converted", Style::Green), stats);
+
+    Ok(converted)
+}
 
-    // Close BkToCstmrStmt
-    writer.write_event(Event::End(BytesEnd::new("BkToCstmrStmt")))?;
+// Run the post-hook for each output of a document, once all of them are
+// written
+fn run_post_hook(
+    hook: &PostHook,
+    converted: &Converted,
+    input_name: &str,
+    version: OutputVersion,
+) -> Result<()> {
+    let statement = &converted.statement;
+    let text = |key: &str| {
+        statement
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    // Dates of the period as written, YYYY-MM-DD
+    let date = |key: &str| text(key).chars().take(10).collect::<String>();
+    for output_path in converted.outputs() {
+        let values = [
+            ("output", output_path.display().to_string()),
+            ("input", input_name.to_string()),
+            ("iban", text("iban")),
+            ("currency", text("currency")),
+            ("id", text("id")),
+            ("from_date", date("from")),
+            ("to_date", date("to")),
+            ("version", version.number().to_string()),
+        ];
+        debug!("Running post-hook for {}", output_path.display());
+        hook.run(&values)?;
+    }
+    Ok(())
+}
 
-    // Close Document
-    writer.write_event(Event::End(BytesEnd::new("Document")))?;
+// Sign the outputs of a document. If one cannot be signed, all are removed,
+// so that no unsigned output is delivered.
+fn sign_outputs(converted: &Converted, options: &SignOptions) -> Result<()> {
+    let outputs: Vec<&PathBuf> = converted
+        .outputs()
+        .iter()
+        .chain(&converted.pending_output)
+        .collect();
+    for output_path in &outputs {
+        match dsig::sign_file(output_path, options) {
+            Ok(Some(signature)) => info!("Wrote signature to {}", signature.display()),
+            Ok(None) => debug!("Signed {}", output_path.display()),
+            Err(e) => {
+                for output_path in &outputs {
+                    let _ = std::fs::remove_file(output_path);
+                    if options.mode == SignatureMode::Detached {
+                        let _ = std::fs::remove_file(dsig::signature_path(output_path));
+                    }
+                }
+                return Err(e.into());
+            }
+        }
+    }
+    Ok(())
+}
 
+// Check a written output against business rules. Violations are warnings,
+// or with `strict` remove the output and fail the conversion.
+fn check_output(converted: &mut Converted, rules: RuleSet, strict: bool) -> Result<()> {
+    let mut violations = Vec::new();
+    for output_path in converted.outputs() {
+        let document = BufReader::new(File::open(output_path)?);
+        violations.extend(check_rules(document, rules)?);
+    }
+    if strict && let Some(violation) = violations.first().cloned() {
+        for output_path in converted.outputs() {
+            std::fs::remove_file(output_path)?;
+        }
+        return Err(ConvertError::FailedCheck {
+            path: violation.path,
+            message: format!("Rule {} failed: {}", violation.rule, violation.message),
+        }
+        .into());
+    }
+    for violation in violations {
+        warn!("{}", violation);
+        converted.warnings.push(violation.to_string());
+    }
     Ok(())
 }
 
-fn write_group_header<W: std::io::Write>(
-    writer: &mut Writer<W>,
+// Write the pending entries taken from a statement to an interim statement
+// next to its output, unless they are dropped
+fn write_pending(
+    outputs: &mut Outputs,
+    output_path: &Path,
+    statement: &Statement,
+    pending: Vec<Transaction>,
+    action: PendingAction,
+    options: &WriteOptions,
+) -> Result<Option<PathBuf>> {
+    if pending.is_empty() {
+        return Ok(None);
+    }
+    if action == PendingAction::Drop {
+        info!("Left out {} pending entries", pending.len());
+        return Ok(None);
+    }
+    let path = outputs.claim(pending_path(output_path))?;
+    info!(
+        "Writing {} pending entries to {}",
+        pending.len(),
+        path.display()
+    );
+    let file = BufWriter::new(File::create(&path)?);
+    write_statement(file, &interim_statement(statement, pending), options)?;
+    Ok(Some(path))
+}
+
+// Write entries to the output while the input is read, keeping only their total
+// for the balance check
+// Show a statement and ask whether its output should be written
+fn review(
     statement: &Statement,
+    warnings: &[String],
+    output_path: &Path,
+    color: bool,
 ) -> Result<()> {
-    writer.write_event(Event::Start(BytesStart::new("GrpHdr")))?;
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("Review needs a terminal to ask for confirmation");
+    }
+    write_review(io::stdout().lock(), statement, warnings, color)?;
+    let question = format!("\nWrite {}?", output_path.display());
+    if !confirm(io::stdin().lock(), io::stdout().lock(), &question)? {
+        anyhow::bail!("{} not written, declined in review", output_path.display());
+    }
+    Ok(())
+}
 
-    // MsgId - use statement ID or generate one
-    write_element(writer, "MsgId", &statement.id)?;
+// Ask for the BICs that would otherwise be written as placeholders
+fn prompt_missing(statement: &Statement, options: &mut WriteOptions) -> io::Result<()> {
+    if options.recipient_bic.is_none() && statement.message_recipient.is_none() {
+        options.recipient_bic =
+            prompt_bic(&format!("Recipient BIC of statement {}", statement.id))?;
+    }
+    if options.servicer_bic.is_none() && statement.servicer_bic.is_empty() {
+        options.servicer_bic = prompt_bic(&format!("Servicer BIC of account {}", statement.iban))?;
+    }
+    Ok(())
+}
 
-    // CreDtTm
-    write_element(
-        writer,
-        "CreDtTm",
-        &convert_datetime(&statement.creation_datetime)?,
+// Ask until a valid BIC is given, or none to keep the placeholder
+fn prompt_bic(question: &str) -> io::Result<Option<String>> {
+    let question = format!("{} (empty for {}):", question, PLACEHOLDER_BIC);
+    loop {
+        let answer = ask(io::stdin().lock(), io::stderr(), &question)?.to_ascii_uppercase();
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        match validate_bic(&answer) {
+            Ok(()) => return Ok(Some(answer)),
+            Err(e) => eprintln!("Invalid BIC {}: {}", answer, e),
+        }
+    }
+}
+
+fn convert_streaming(
+    input: impl BufRead,
+    args: &Args,
+    outputs: &mut Outputs,
+    output: Output,
+    write_options: WriteOptions,
+    duplicates: Option<&mut DuplicateCheck>,
+) -> Result<Converted> {
+    let started = Instant::now();
+    let no_hooks = Hooks::default();
+    let hooks = args.hooks.as_ref().unwrap_or(&no_hooks);
+    let no_categories = Categories::default();
+    let categories = args.categories.as_ref().unwrap_or(&no_categories);
+    let handler = EntryTotal {
+        inner: OutputFile {
+            outputs,
+            output,
+            options: write_options,
+            prompt: args.prompt,
+            writer: None,
+            truncations: Vec::new(),
+        },
+        duplicates,
+        pending: args.pending,
+        pending_entries: Vec::new(),
+        count: 0,
+        total: Decimal::default(),
+        code_totals: CodeTotals::new(),
+        invoices: InvoiceReport::new(),
+        exchanges: ExchangeReport::new(),
+    };
+    // Hooks come first, so that the totals are of the changed entries, and
+    // categories see the changes of the hooks
+    let handler = Categorized::new(categories, args.category_tags, handler);
+    let mut hooked = Hooked::new(hooks, handler);
+
+    let parsed = match stream_camt_10(input, &args.parse_options(), &mut hooked) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            // Don't leave a truncated document behind
+            let output_path = hooked.inner.inner.inner.output.path.take();
+            drop(hooked);
+            if let Some(output_path) = output_path {
+                let _ = std::fs::remove_file(output_path);
+            }
+            return Err(e.into());
+        }
+    };
+    let mut handler = hooked.inner.inner;
+    let mut statement = parsed.statement;
+    hooks.apply_statement(&mut statement);
+    let mut warnings: Vec<String> = parsed.warnings.iter().map(ToString::to_string).collect();
+    debug!(
+        "Converted {} balances and {} entries in {:.1?}",
+        statement.balances.len(),
+        handler.count,
+        started.elapsed()
+    );
+    log_bank(&statement);
+    // Entries left out as duplicates or pending still count towards the
+    // balances
+    let mut total = handler.total;
+    if let Some(duplicates) = &mut handler.duplicates {
+        total = duplicates
+            .dropped_total
+            .and_then(|dropped| total.checked_add(dropped))
+            .ok_or_else(|| ConvertError::Overflow("total of the entries".to_string()))?;
+        warnings.append(&mut duplicates.warnings);
+    }
+    warnings.extend(statement.check_balances_with_total(total));
+    // With --strict the writer has failed on the first placeholder or value
+    // that is too long
+    let placeholders = check_placeholders(&statement, &handler.inner.options)?;
+    warnings.extend(placeholders.iter().map(ToString::to_string));
+    let truncations = statement_truncations(&statement, &handler.inner.options);
+    warnings.extend(
+        truncations
+            .iter()
+            .chain(&handler.inner.truncations)
+            .map(ToString::to_string),
+    );
+    for warning in &warnings {
+        warn!("{}", warning);
+    }
+    let output_path = handler
+        .inner
+        .output
+        .path
+        .expect("the output is created by begin");
+    let pending_output = write_pending(
+        handler.inner.outputs,
+        &output_path,
+        &statement,
+        handler.pending_entries,
+        handler.pending,
+        &handler.inner.options,
     )?;
 
-    // MsgRcpt (required in v08)
-    writer.write_event(Event::Start(BytesStart::new("MsgRcpt")))?;
-    writer.write_event(Event::Start(BytesStart::new("Id")))?;
-    writer.write_event(Event::Start(BytesStart::new("OrgId")))?;
-    write_element(writer, "AnyBIC", "XXXXXXXX")?; // Generic placeholder
-    writer.write_event(Event::End(BytesEnd::new("OrgId")))?;
-    writer.write_event(Event::End(BytesEnd::new("Id")))?;
-    writer.write_event(Event::End(BytesEnd::new("MsgRcpt")))?;
+    Ok(Converted {
+        output_path,
+        stats: ConversionStats {
+            balances: statement.balances.len(),
+            entries: handler.count,
+            ..ConversionStats::default()
+        },
+        warnings,
+        dropped: parsed.dropped,
+        skipped: false,
+        pages: Vec::new(),
+        pending_output,
+        code_totals: handler.code_totals,
+        invoices: handler.invoices,
+        exchanges: handler.exchanges,
+        statement: statement_value(&statement),
+    })
+}
 
-    // MsgPgntn
-    writer.write_event(Event::Start(BytesStart::new("MsgPgntn")))?;
-    write_element(writer, "PgNb", "1")?;
-    write_element(writer, "LastPgInd", "true")?;
-    writer.write_event(Event::End(BytesEnd::new("MsgPgntn")))?;
+// Move the input to the archive once all its documents are converted
+fn archive_input(args: &Args, dir: &Path, files: &[FileResult]) -> Result<()> {
+    if !files.iter().all(|file| file.result.is_ok()) {
+        info!(
+            "Not archiving {}, not all documents converted",
+            args.input().display()
+        );
+        return Ok(());
+    }
+    #[cfg(feature = "remote")]
+    if remote_url(args).is_some() {
+        anyhow::bail!("URL input cannot be archived");
+    }
+    let timestamp = args.archive_timestamp.then(|| Local::now().naive_local());
+    let archived = archive_file(args.input(), dir, timestamp)?;
+    info!(
+        "Archived {} as {}",
+        args.input().display(),
+        archived.display()
+    );
+    Ok(())
+}
 
-    // AddtlInf
-    write_element(writer, "AddtlInf", "SPS/2.1")?;
+// Move an input with documents that cannot be read or are invalid to the
+// quarantine folder, returning whether it was moved. Inputs that failed for
+// other reasons, such as an output that cannot be written, stay where they
+// are, as trying again may convert them.
+fn quarantine_input(args: &Args, dir: &Path, files: &[FileResult]) -> Result<bool> {
+    let failed: Vec<_> = files
+        .iter()
+        .filter_map(|file| Some((&file.input, file.result.as_ref().err()?)))
+        .collect();
+    let invalid = failed
+        .iter()
+        .any(|(_, e)| matches!(Exit::of(e), Exit::Parse | Exit::Validation));
+    if !invalid {
+        return Ok(false);
+    }
+    #[cfg(feature = "remote")]
+    if remote_url(args).is_some() {
+        anyhow::bail!("URL input cannot be quarantined");
+    }
+    let mut errors = String::new();
+    for (input, e) in failed {
+        errors.push_str(&format!("{}: {:#}\n", input, e));
+    }
+    let quarantined = quarantine_file(args.input(), dir, Local::now().naive_local(), &errors)?;
+    warn!(
+        "Moved {} to quarantine as {}",
+        args.input().display(),
+        quarantined.display()
+    );
+    Ok(true)
+}
 
-    writer.write_event(Event::End(BytesEnd::new("GrpHdr")))?;
+// Totals of the documents that were converted
+fn write_code_totals(path: &Path, files: &[FileResult]) -> std::io::Result<()> {
+    let mut code_totals = CodeTotals::new();
+    for converted in files.iter().filter_map(|file| file.result.as_ref().ok()) {
+        code_totals
+            .merge(&converted.code_totals)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    }
+    if path == Path::new("-") {
+        code_totals.write_csv(std::io::stdout().lock())
+    } else {
+        code_totals.write_csv(BufWriter::new(File::create(path)?))?;
+        info!("Wrote code totals to {}", path.display());
+        Ok(())
+    }
+}
 
-    Ok(())
+// Invoice references of the documents that were converted
+fn write_qr_report(path: &Path, files: &[FileResult]) -> std::io::Result<()> {
+    let mut invoices = InvoiceReport::new();
+    for converted in files.iter().filter_map(|file| file.result.as_ref().ok()) {
+        invoices.merge(&converted.invoices);
+    }
+    if path == Path::new("-") {
+        invoices.write_csv(std::io::stdout().lock())
+    } else {
+        invoices.write_csv(BufWriter::new(File::create(path)?))?;
+        info!(
+            "Wrote {} invoice references to {}",
+            invoices.len(),
+            path.display()
+        );
+        Ok(())
+    }
 }
 
-fn write_statement<W: std::io::Write>(writer: &mut Writer<W>, statement: &Statement) -> Result<()> {
-    writer.write_event(Event::Start(BytesStart::new("Stmt")))?;
+// Currency exchanges of the documents that were converted
+fn write_fx_report(path: &Path, files: &[FileResult]) -> std::io::Result<()> {
+    let mut exchanges = ExchangeReport::new();
+    for converted in files.iter().filter_map(|file| file.result.as_ref().ok()) {
+        exchanges.merge(&converted.exchanges);
+    }
+    if path == Path::new("-") {
+        exchanges.write_csv(std::io::stdout().lock())
+    } else {
+        exchanges.write_csv(BufWriter::new(File::create(path)?))?;
+        info!(
+            "Wrote {} currency exchanges to {}",
+            exchanges.len(),
+            path.display()
+        );
+        Ok(())
+    }
+}
 
-    // Statement ID
-    write_element(writer, "Id", &statement.id)?;
+// Account, period and balances of a statement
+// Report the bank a statement comes from, if it is known
+fn log_bank(statement: &Statement) {
+    match bank::detect(statement) {
+        Some(detection) => info!(
+            "Detected {} from {}",
+            detection.bank.name, detection.evidence
+        ),
+        None => debug!("Bank of statement {} not detected", statement.id),
+    }
+}
 
-    // Electronic Sequence Number
-    write_element(writer, "ElctrncSeqNb", "1")?;
+fn statement_value(statement: &Statement) -> Value {
+    let balance = |balance: Option<&Balance>| {
+        balance.map(|balance| {
+            Value::object()
+                .with("amount", balance.signed_amount())
+                .with("currency", balance.amount.currency.as_str())
+        })
+    };
+    let opening = statement
+        .balance(BalanceType::OpeningBooked)
+        .or_else(|| statement.balance(BalanceType::PreviouslyClosedBooked));
+    Value::object()
+        .with("id", statement.id.as_str())
+        .with("iban", statement.iban.as_str())
+        .with("currency", statement.currency.as_str())
+        .with("owner", statement.owner_name.as_str())
+        .with(
+            "bank",
+            bank::detect(statement).map(|detection| detection.bank.id),
+        )
+        .with(
+            "from",
+            statement.from_datetime.map(|from| from.to_rfc3339()),
+        )
+        .with("to", statement.to_datetime.map(|to| to.to_rfc3339()))
+        .with("opening_balance", balance(opening))
+        .with(
+            "closing_balance",
+            balance(statement.balance(BalanceType::ClosingBooked)),
+        )
+}
 
-    // Creation DateTime
-    write_element(
-        writer,
-        "CreDtTm",
-        &convert_datetime(&statement.creation_datetime)?,
-    )?;
+// Webhook payload of a converted or failed document. Documents skipped as
+// unchanged are not notified again.
+fn notification(file: &FileResult) -> Option<Value> {
+    let value = Value::object().with("input", file.input.as_str());
+    match &file.result {
+        Ok(converted) if converted.skipped => None,
+        Ok(converted) => Some(
+            value
+                .with("status", "ok")
+                .with("output", converted.output_path.display().to_string())
+                .with("statement", converted.statement.clone())
+                .with("entries", converted.stats.entries)
+                .with("totals", converted.stats.totals_json())
+                .with("warnings", converted.warnings.clone()),
+        ),
+        Err(e) => Some(
+            value
+                .with("status", "error")
+                .with("error", format!("{:#}", e)),
+        ),
+    }
+}
 
-    // From/To Date
-    writer.write_event(Event::Start(BytesStart::new("FrToDt")))?;
-    write_element(
-        writer,
-        "FrDtTm",
-        &convert_datetime(&statement.from_datetime)?,
-    )?;
-    write_element(writer, "ToDtTm", &convert_datetime(&statement.to_datetime)?)?;
-    writer.write_event(Event::End(BytesEnd::new("FrToDt")))?;
+// The output of a document, with its pages if it was split and the interim
+// statement of its pending entries
+fn with_pages(value: Value, converted: &Converted) -> Value {
+    let mut value = value.with("output", converted.output_path.display().to_string());
+    if let Some(path) = &converted.pending_output {
+        value = value.with("pending_output", path.display().to_string());
+    }
+    if converted.pages.is_empty() {
+        return value;
+    }
+    let pages: Vec<Value> = converted
+        .pages
+        .iter()
+        .map(|page| page.display().to_string().into())
+        .collect();
+    value.with("pages", pages)
+}
 
-    // Account
-    writer.write_event(Event::Start(BytesStart::new("Acct")))?;
-    writer.write_event(Event::Start(BytesStart::new("Id")))?;
-    write_element(writer, "IBAN", &statement.iban)?;
-    writer.write_event(Event::End(BytesEnd::new("Id")))?;
-    write_element(writer, "Ccy", &statement.currency)?;
-    writer.write_event(Event::Start(BytesStart::new("Ownr")))?;
-    write_element(writer, "Nm", &statement.owner_name)?;
-    writer.write_event(Event::End(BytesEnd::new("Ownr")))?;
+// Whether all, some or none of the documents were converted
+fn status(files: &[FileResult]) -> &'static str {
+    match files.iter().filter(|file| file.result.is_ok()).count() {
+        0 => "error",
+        n if n == files.len() => "ok",
+        _ => "partial",
+    }
+}
 
-    // Servicer (required in v08, but using generic values)
-    writer.write_event(Event::Start(BytesStart::new("Svcr")))?;
-    writer.write_event(Event::Start(BytesStart::new("FinInstnId")))?;
-    write_element(writer, "BICFI", "XXXXXXXX")?; // Generic placeholder
-    write_element(writer, "Nm", "Bank")?; // Generic bank name
-    writer.write_event(Event::Start(BytesStart::new("Othr")))?;
-    write_element(writer, "Id", "XXX-000.000.000")?;
-    write_element(writer, "Issr", "ID")?;
-    writer.write_event(Event::End(BytesEnd::new("Othr")))?;
-    writer.write_event(Event::End(BytesEnd::new("FinInstnId")))?;
-    writer.write_event(Event::End(BytesEnd::new("Svcr")))?;
+// Result summary printed on standard output with --log-format json
+fn summary(files: &[FileResult]) -> Value {
+    let converted = files.iter().filter(|file| file.result.is_ok()).count();
+    let status = status(files);
+    let files: Vec<Value> = files
+        .iter()
+        .map(|file| {
+            let value = Value::object().with("input", file.input.as_str());
+            match &file.result {
+                Ok(converted) if converted.skipped => value
+                    .with("output", converted.output_path.display().to_string())
+                    .with("skipped", true),
+                Ok(converted) => with_pages(value, converted)
+                    .with("balances", converted.stats.balances)
+                    .with("entries", converted.stats.entries)
+                    .with("warnings", converted.warnings.clone())
+                    .with("stats", converted.stats.to_json()),
+                Err(e) => value.with("error", format!("{:#}", e)),
+            }
+        })
+        .collect();
 
-    writer.write_event(Event::End(BytesEnd::new("Acct")))?;
+    Value::object()
+        .with("status", status)
+        .with("files_processed", converted)
+        .with("files", files)
+}
 
-    // Balances
-    for balance in &statement.balances {
-        write_balance(writer, balance)?;
+// Table of the documents of a batch with their outcome, and the totals
+fn summary_table(files: &[FileResult]) {
+    // Padded before painting, as escape codes take no room on the terminal
+    let cell = |text: &str, width: usize, style: Option<Style>| {
+        let text = format!("{:<width$}", text);
+        style.map_or_else(|| text.clone(), |style| log::paint(&text, style))
+    };
+    let input_width = files
+        .iter()
+        .map(|file| file.input.chars().count())
+        .chain(["Input".len()])
+        .max()
+        .unwrap_or_default();
+    info!(
+        "{}",
+        log::paint(
+            &format!(
+                "{:<7}  {:<input_width$}  {:>7}  {:>8}  Output",
+                "Status", "Input", "Entries", "Warnings"
+            ),
+            Style::Bold
+        )
+    );
+    let (mut converted, mut skipped, mut failed, mut warnings) = (0, 0, 0, 0);
+    for file in files {
+        let (status, entries, file_warnings, output) = match &file.result {
+            Ok(done) if done.skipped => {
+                skipped += 1;
+                let status = cell("skipped", 7, Some(Style::Dim));
+                let output = done.output_path.display().to_string();
+                (status, String::new(), cell("", 8, None), output)
+            }
+            Ok(done) => {
+                converted += 1;
+                warnings += done.warnings.len();
+                let status = cell("ok", 7, Some(Style::Green));
+                let count = format!("{:>8}", done.warnings.len());
+                let file_warnings = cell(
+                    &count,
+                    8,
+                    (!done.warnings.is_empty()).then_some(Style::Yellow),
+                );
+                let mut output = done.output_path.display().to_string();
+                if done.pages.len() > 1 {
+                    output.push_str(&format!(" ({} pages)", done.pages.len()));
+                }
+                (
+                    status,
+                    done.stats.entries.to_string(),
+                    file_warnings,
+                    output,
+                )
+            }
+            Err(e) => {
+                failed += 1;
+                let status = cell("failed", 7, Some(Style::Red));
+                let error = log::paint(&e.root_cause().to_string(), Style::Red);
+                (status, String::new(), cell("", 8, None), error)
+            }
+        };
+        info!(
+            "{}  {:<input_width$}  {:>7}  {}  {}",
+            status, file.input, entries, file_warnings, output
+        );
     }
+    info!(
+        "{} documents: {} converted, {} skipped, {} failed, {} warnings",
+        files.len(),
+        converted,
+        skipped,
+        failed,
+        warnings
+    );
+}
 
-    // Entries (Transactions)
-    for transaction in &statement.transactions {
-        write_transaction(writer, transaction)?;
+// Counts and sums up the entry amounts in the account currency passed on to
+// another handler, leaving out duplicates the check drops and pending entries
+// unless they are included
+struct EntryTotal<'a, 'b, H> {
+    inner: H,
+    duplicates: Option<&'a mut DuplicateCheck<'b>>,
+    pending: PendingAction,
+    // Pending entries kept for a separate file, with their amounts in total
+    pending_entries: Vec<Transaction>,
+    count: usize,
+    total: Decimal,
+    code_totals: CodeTotals,
+    invoices: InvoiceReport,
+    exchanges: ExchangeReport,
+}
+
+impl<H: StatementHandler> StatementHandler for EntryTotal<'_, '_, H> {
+    fn begin(&mut self, statement: &Statement) -> camtconvert::Result<()> {
+        self.inner.begin(statement)
     }
 
-    writer.write_event(Event::End(BytesEnd::new("Stmt")))?;
+    fn entry(
+        &mut self,
+        statement: &Statement,
+        transaction: Transaction,
+    ) -> camtconvert::Result<()> {
+        if let Some(duplicates) = &mut self.duplicates
+            && !duplicates.keep(statement, &transaction)
+        {
+            return Ok(());
+        }
+        let amount = transaction.account_amount(statement.account_currency());
+        self.total = self
+            .total
+            .checked_add(amount)
+            .ok_or_else(|| ConvertError::Overflow("total of the entries".to_string()))?;
+        if self.pending != PendingAction::Include && is_pending(&transaction) {
+            if self.pending == PendingAction::SeparateFile {
+                self.pending_entries.push(transaction);
+            }
+            return Ok(());
+        }
+        self.count += 1;
+        self.code_totals.add(&transaction)?;
+        self.invoices.add(statement, &transaction);
+        self.exchanges.add(statement, &transaction);
+        self.inner.entry(statement, transaction)
+    }
 
-    Ok(())
+    fn end(&mut self, statement: &Statement) -> camtconvert::Result<()> {
+        self.inner.end(statement)
+    }
 }
 
-fn write_balance<W: std::io::Write>(writer: &mut Writer<W>, balance: &Balance) -> Result<()> {
-    writer.write_event(Event::Start(BytesStart::new("Bal")))?;
+// Creates the output file once the statement header has been read, so that
+// its name can be taken from the statement
+struct OutputFile<'a, 'b> {
+    outputs: &'a mut Outputs<'b>,
+    output: Output,
+    options: WriteOptions,
+    // Ask for missing values before the header is written
+    prompt: bool,
+    writer: Option<StreamWriter<BufWriter<File>>>,
+    // Values of the entries written that were too long
+    truncations: Vec<Truncation>,
+}
 
-    // Type
-    writer.write_event(Event::Start(BytesStart::new("Tp")))?;
-    writer.write_event(Event::Start(BytesStart::new("CdOrPrtry")))?;
-    write_element(writer, "Cd", &balance.balance_type)?;
-    writer.write_event(Event::End(BytesEnd::new("CdOrPrtry")))?;
-    writer.write_event(Event::End(BytesEnd::new("Tp")))?;
+impl OutputFile<'_, '_> {
+    fn writer(&mut self) -> &mut StreamWriter<BufWriter<File>> {
+        self.writer
+            .as_mut()
+            .expect("entries are passed after begin")
+    }
+}
 
-    // Amount with currency
-    let mut amt_elem = BytesStart::new("Amt");
-    amt_elem.push_attribute(("Ccy", balance.currency.as_str()));
-    writer.write_event(Event::Start(amt_elem))?;
-    writer.write_event(Event::Text(BytesText::new(&balance.amount)))?;
-    writer.write_event(Event::End(BytesEnd::new("Amt")))?;
+impl StatementHandler for OutputFile<'_, '_> {
+    fn begin(&mut self, statement: &Statement) -> camtconvert::Result<()> {
+        if self.prompt {
+            prompt_missing(statement, &mut self.options)?;
+        }
+        let output_path = self.outputs.resolve(&mut self.output, statement)?;
+        let file = BufWriter::new(File::create(output_path)?);
+        self.writer
+            .insert(StreamWriter::new(file, self.options.clone()))
+            .begin(statement)
+    }
 
-    // Credit/Debit Indicator
-    write_element(writer, "CdtDbtInd", &balance.credit_debit_ind)?;
+    fn entry(
+        &mut self,
+        statement: &Statement,
+        transaction: Transaction,
+    ) -> camtconvert::Result<()> {
+        let truncations = entry_truncations(&transaction, &self.options);
+        self.truncations.extend(truncations);
+        self.writer().entry(statement, transaction)
+    }
 
-    // Date
-    writer.write_event(Event::Start(BytesStart::new("Dt")))?;
-    write_element(writer, "Dt", &convert_datetime_to_date(&balance.date)?)?;
-    writer.write_event(Event::End(BytesEnd::new("Dt")))?;
+    fn end(&mut self, statement: &Statement) -> camtconvert::Result<()> {
+        self.writer().end(statement)
+    }
+}
 
-    writer.write_event(Event::End(BytesEnd::new("Bal")))?;
+// Output file names of a run, kept apart so that no document overwrites the
+// output of another
+struct Outputs<'a> {
+    template: Option<&'a NameTemplate>,
+    layout: OutputLayout,
+    format: OutputFormat,
+    version: OutputVersion,
+    written: Vec<PathBuf>,
+}
 
-    Ok(())
+// Output of one input document. The path is only known once the statement
+// has been read if it is named by a template or filed by a layout.
+struct Output {
+    input_path: PathBuf,
+    stem: String,
+    path: Option<PathBuf>,
 }
 
-fn write_transaction<W: std::io::Write>(
-    writer: &mut Writer<W>,
-    transaction: &Transaction,
-) -> Result<()> {
-    writer.write_event(Event::Start(BytesStart::new("Ntry")))?;
-
-    // Amount with currency
-    let mut amt_elem = BytesStart::new("Amt");
-    amt_elem.push_attribute(("Ccy", transaction.currency.as_str()));
-    writer.write_event(Event::Start(amt_elem))?;
-    writer.write_event(Event::Text(BytesText::new(&transaction.amount)))?;
-    writer.write_event(Event::End(BytesEnd::new("Amt")))?;
-
-    // Credit/Debit Indicator
-    write_element(writer, "CdtDbtInd", &transaction.credit_debit_ind)?;
-
-    // Status
-    writer.write_event(Event::Start(BytesStart::new("Sts")))?;
-    write_element(writer, "Cd", "BOOK")?;
-    writer.write_event(Event::End(BytesEnd::new("Sts")))?;
-
-    // Booking Date
-    writer.write_event(Event::Start(BytesStart::new("BookgDt")))?;
-    write_element(
-        writer,
-        "Dt",
-        &convert_datetime_to_date(&transaction.booking_date)?,
-    )?;
-    writer.write_event(Event::End(BytesEnd::new("BookgDt")))?;
-
-    // Value Date (same as booking date)
-    writer.write_event(Event::Start(BytesStart::new("ValDt")))?;
-    write_element(
-        writer,
-        "Dt",
-        &convert_datetime_to_date(&transaction.booking_date)?,
-    )?;
-    writer.write_event(Event::End(BytesEnd::new("ValDt")))?;
-
-    // Account Servicer Reference - generate deterministic ID
-    let ref_id = generate_transaction_reference(transaction);
-    write_element(writer, "AcctSvcrRef", &ref_id)?;
-
-    // Bank Transaction Code
-    writer.write_event(Event::Start(BytesStart::new("BkTxCd")))?;
-    writer.write_event(Event::Start(BytesStart::new("Domn")))?;
-    write_element(writer, "Cd", "PMNT")?;
-    writer.write_event(Event::Start(BytesStart::new("Fmly")))?;
-
-    // Determine transaction family based on transaction type
-    if transaction.bank_tx_code.starts_with("CARD") {
-        write_element(writer, "Cd", "CCRD")?;
-        write_element(writer, "SubFmlyCd", "POSD")?;
-    } else {
-        write_element(writer, "Cd", "ICDT")?;
-        write_element(writer, "SubFmlyCd", "ESCT")?;
+impl<'a> Outputs<'a> {
+    fn new(args: &'a Args) -> Self {
+        Outputs {
+            template: args.name_template.as_ref(),
+            layout: args.layout,
+            format: args.format,
+            version: args.target_version,
+            written: Vec::new(),
+        }
     }
 
-    writer.write_event(Event::End(BytesEnd::new("Fmly")))?;
-    writer.write_event(Event::End(BytesEnd::new("Domn")))?;
+    fn start(&mut self, input_path: &Path) -> Result<Output> {
+        let stem = input_stem(input_path)?;
+        let path = match (self.template, self.layout) {
+            (Some(_), _) | (None, OutputLayout::ByDate | OutputLayout::ByAccount) => None,
+            (None, OutputLayout::Flat) => {
+                let file_name = self.file_name(&stem);
+                Some(self.claim(input_path.with_file_name(file_name))?)
+            }
+        };
+        Ok(Output {
+            input_path: input_path.to_path_buf(),
+            stem,
+            path,
+        })
+    }
 
-    // Proprietary code
-    writer.write_event(Event::Start(BytesStart::new("Prtry")))?;
-    write_element(writer, "Cd", &transaction.bank_tx_code)?;
-    writer.write_event(Event::End(BytesEnd::new("Prtry")))?;
+    // File name of an output without name template
+    fn file_name(&self, stem: &str) -> String {
+        match self.format {
+            OutputFormat::Xml => format!("{}_{}.xml", stem, self.version.number()),
+            OutputFormat::Arrow => format!("{}.arrow", stem),
+        }
+    }
 
-    writer.write_event(Event::End(BytesEnd::new("BkTxCd")))?;
+    // Output path of a document, taken from its statement if needed
+    fn resolve(&mut self, output: &mut Output, statement: &Statement) -> io::Result<PathBuf> {
+        if let Some(path) = &output.path {
+            return Ok(path.clone());
+        }
+        let file_name = match self.template {
+            Some(template) => template.render(statement, &output.stem, self.version),
+            None => self.file_name(&output.stem),
+        };
+        let folder = output
+            .input_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(self.layout.folder(statement));
+        if self.layout != OutputLayout::Flat {
+            std::fs::create_dir_all(&folder)?;
+        }
+        let path = self.claim(folder.join(file_name))?;
+        info!("Writing {}", path.display());
+        Ok(output.path.insert(path).clone())
+    }
 
-    // Entry Details
-    if !transaction.additional_info.is_empty() {
-        writer.write_event(Event::Start(BytesStart::new("NtryDtls")))?;
-        writer.write_event(Event::Start(BytesStart::new("TxDtls")))?;
+    fn claim(&mut self, path: PathBuf) -> io::Result<PathBuf> {
+        if self.written.contains(&path) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "Output {} was already written for another document",
+                    path.display()
+                ),
+            ));
+        }
+        self.written.push(path.clone());
+        Ok(path)
+    }
+}
 
-        // References
-        writer.write_event(Event::Start(BytesStart::new("Refs")))?;
-        write_element(writer, "AcctSvcrRef", &ref_id)?;
-        writer.write_event(Event::End(BytesEnd::new("Refs")))?;
+fn input_stem(input_path: &Path) -> Result<String> {
+    // The output of statement.xml.gz or statement.xml.pgp is
+    // statement_08.xml, neither compressed nor encrypted
+    let mut name_path = input_path.to_path_buf();
+    while name_path.extension().is_some_and(|extension| {
+        ["gz", "pgp", "gpg", "asc"]
+            .iter()
+            .any(|e| extension.eq_ignore_ascii_case(e))
+    }) {
+        name_path.set_extension("");
+    }
+    let file_stem = name_path
+        .file_stem()
+        .context("Invalid input filename")?
+        .to_string_lossy();
+    Ok(file_stem.into_owned())
+}
 
-        // Amount
-        let mut amt_elem = BytesStart::new("Amt");
-        amt_elem.push_attribute(("Ccy", transaction.currency.as_str()));
-        writer.write_event(Event::Start(amt_elem))?;
-        writer.write_event(Event::Text(BytesText::new(&transaction.amount)))?;
-        writer.write_event(Event::End(BytesEnd::new("Amt")))?;
+// Path of a page of an output, statement_08_p2.xml for page 2 of
+// statement_08.xml
+fn page_path(output_path: &Path, number: usize) -> PathBuf {
+    suffixed_path(output_path, &format!("_p{}", number))
+}
 
-        // Credit/Debit Indicator
-        write_element(writer, "CdtDbtInd", &transaction.credit_debit_ind)?;
+// Path of the interim statement of pending entries
+fn pending_path(output_path: &Path) -> PathBuf {
+    suffixed_path(output_path, "_pending")
+}
 
-        // Remittance Information
-        writer.write_event(Event::Start(BytesStart::new("RmtInf")))?;
-        writer.write_event(Event::Start(BytesStart::new("Ustrd")))?;
-        writer.write_event(Event::Text(BytesText::new(&transaction.additional_info)))?;
-        writer.write_event(Event::End(BytesEnd::new("Ustrd")))?;
-        writer.write_event(Event::End(BytesEnd::new("RmtInf")))?;
+fn suffixed_path(output_path: &Path, suffix: &str) -> PathBuf {
+    let stem = output_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let mut file_name = format!("{}{}", stem, suffix);
+    if let Some(extension) = output_path.extension() {
+        file_name.push('.');
+        file_name.push_str(&extension.to_string_lossy());
+    }
+    output_path.with_file_name(file_name)
+}
 
-        writer.write_event(Event::End(BytesEnd::new("TxDtls")))?;
-        writer.write_event(Event::End(BytesEnd::new("NtryDtls")))?;
+fn parse_notify_url(url: &str) -> Result<String, String> {
+    if notify::is_webhook_url(url) {
+        Ok(url.to_string())
+    } else {
+        Err("expected an http:// or https:// URL".to_string())
     }
+}
 
-    // Additional Entry Info
-    write_element(writer, "AddtlNtryInf", &transaction.additional_info)?;
+fn read_merchant_patterns(path: &str) -> Result<MerchantCleanup, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read merchant patterns file {}: {}", path, e))?;
+    text.parse().map_err(|e: MerchantError| e.to_string())
+}
 
-    writer.write_event(Event::End(BytesEnd::new("Ntry")))?;
+fn read_gl_accounts(path: &str) -> Result<GlAccounts, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read GL accounts file {}: {}", path, e))?;
+    text.parse().map_err(|e: GlAccountError| e.to_string())
+}
 
-    Ok(())
+fn read_aliases(path: &str) -> Result<Aliases, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read aliases file {}: {}", path, e))?;
+    text.parse().map_err(|e: AliasError| e.to_string())
 }
 
-fn write_element<W: std::io::Write>(writer: &mut Writer<W>, name: &str, value: &str) -> Result<()> {
-    writer.write_event(Event::Start(BytesStart::new(name)))?;
-    writer.write_event(Event::Text(BytesText::new(value)))?;
-    writer.write_event(Event::End(BytesEnd::new(name)))?;
-    Ok(())
+fn read_categories(path: &str) -> Result<Categories, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read categories file {}: {}", path, e))?;
+    text.parse().map_err(|e: CategoryError| e.to_string())
 }
 
-fn convert_datetime(datetime_str: &str) -> Result<String> {
-    // Input format: 2025-06-22T17:33:43.291656435Z or 2025-06-20T00:00:00+02:00
-    // Output format: 2025-06-20T18:43:45+02:00
+fn read_hooks(path: &str) -> Result<Hooks, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read hooks file {}: {}", path, e))?;
+    text.parse().map_err(|e: HookError| e.to_string())
+}
 
-    // Try to parse as ISO 8601
-    if let Ok(dt) = DateTime::parse_from_rfc3339(datetime_str) {
-        return Ok(dt.format("%Y-%m-%dT%H:%M:%S%:z").to_string());
+fn parse_currency(code: &str) -> Result<String, String> {
+    match minor_units(code) {
+        Some(_) => Ok(code.to_string()),
+        None => Err(format!("{} is not an ISO 4217 currency code", code)),
     }
+}
 
-    // If that fails, try without timezone and add default
-    if let Ok(dt) = datetime_str.parse::<DateTime<Utc>>() {
-        return Ok(dt.format("%Y-%m-%dT%H:%M:%S+02:00").to_string());
+fn parse_page_size(text: &str) -> Result<usize, String> {
+    match text.parse() {
+        Ok(0) => Err("a page needs at least one entry".to_string()),
+        Ok(size) => Ok(size),
+        Err(e) => Err(format!("{}", e)),
     }
+}
 
-    // Fallback: return as-is
-    Ok(datetime_str.to_string())
+fn parse_bic(text: &str) -> Result<String, BicError> {
+    validate_bic(text).map(|()| text.to_string())
 }
 
-fn convert_datetime_to_date(datetime_str: &str) -> Result<String> {
-    // Extract just the date part (YYYY-MM-DD)
-    if datetime_str.len() >= 10 {
-        Ok(datetime_str[..10].to_string())
-    } else {
-        Ok(datetime_str.to_string())
+// Manifest of a run written with --manifest: what was converted with which
+// options into which files, with the SHA-256 of each
+fn manifest(args: &Args, files: &[FileResult]) -> io::Result<Value> {
+    let arguments: Vec<String> = std::env::args_os()
+        .skip(1)
+        .map(|argument| argument.to_string_lossy().into_owned())
+        .collect();
+    let mut documents = Vec::new();
+    for file in files {
+        let value = Value::object()
+            .with("input", file.input.as_str())
+            .with("input_sha256", file.input_checksum.clone());
+        documents.push(match &file.result {
+            Ok(converted) => {
+                let mut outputs = Vec::new();
+                for path in converted.outputs().iter().chain(&converted.pending_output) {
+                    outputs.push(
+                        Value::object()
+                            .with("path", path.display().to_string())
+                            .with("sha256", file_checksum(path, ChecksumAlgorithm::Sha256)?),
+                    );
+                }
+                value
+                    .with("status", if converted.skipped { "skipped" } else { "ok" })
+                    .with("outputs", outputs)
+            }
+            Err(e) => value
+                .with("status", "error")
+                .with("error", format!("{:#}", e)),
+        });
     }
+
+    Ok(Value::object()
+        .with("camtconvert", env!("CARGO_PKG_VERSION"))
+        .with("arguments", arguments)
+        .with("options_fingerprint", args.options_fingerprint())
+        .with("created", Local::now().to_rfc3339())
+        .with("files", documents))
 }
 
-fn generate_transaction_reference(transaction: &Transaction) -> String {
-    // Generate a deterministic reference based on transaction content
-    let mut hasher = DefaultHasher::new();
+// Journal lines of the documents of a run, for --journal
+// Set when an entry could not be added to the journal, which fails the run
+// with exit code 5 once all inputs are done
+static JOURNAL_FAILED: AtomicBool = AtomicBool::new(false);
+
+// Add documents to the journal as soon as they are done, so that an
+// interrupted run can be resumed
+fn add_to_journal(args: &Args, files: &[FileResult]) {
+    let Some(path) = &args.journal else {
+        return;
+    };
+    if let Err(e) = journal::append(path, &journal_entries(args, files)) {
+        error!("Cannot add to journal {}: {}", path.display(), e);
+        JOURNAL_FAILED.store(true, Ordering::Relaxed);
+    }
+}
 
-    // Hash the key transaction fields
-    transaction.amount.hash(&mut hasher);
-    transaction.currency.hash(&mut hasher);
-    transaction.credit_debit_ind.hash(&mut hasher);
-    transaction.booking_date.hash(&mut hasher);
-    transaction.bank_tx_code.hash(&mut hasher);
+fn journal_entries(args: &Args, files: &[FileResult]) -> Vec<JournalEntry> {
+    let time = Local::now().fixed_offset();
+    let fingerprint = args.options_fingerprint();
+    files
+        .iter()
+        .map(|file| {
+            let entry = JournalEntry {
+                time,
+                input: file.input.clone(),
+                input_sha256: file.input_checksum.clone(),
+                options_fingerprint: Some(fingerprint.clone()),
+                status: String::new(),
+                outputs: Vec::new(),
+                account: String::new(),
+                statement: String::new(),
+                from: None,
+                to: None,
+                error: None,
+            };
+            match &file.result {
+                Ok(converted) => {
+                    let statement = &converted.statement;
+                    let text = |key: &str| statement.get(key).and_then(Value::as_str);
+                    let datetime = |key: &str| {
+                        text(key).and_then(|text| DateTime::parse_from_rfc3339(text).ok())
+                    };
+                    JournalEntry {
+                        status: if converted.skipped { "skipped" } else { "ok" }.to_string(),
+                        outputs: converted
+                            .outputs()
+                            .iter()
+                            .chain(&converted.pending_output)
+                            .map(|path| path.display().to_string())
+                            .collect(),
+                        account: text("iban").unwrap_or_default().to_string(),
+                        statement: text("id").unwrap_or_default().to_string(),
+                        from: datetime("from"),
+                        to: datetime("to"),
+                        ..entry
+                    }
+                }
+                Err(e) => JournalEntry {
+                    status: "error".to_string(),
+                    error: Some(format!("{:#}", e)),
+                    ..entry
+                },
+            }
+        })
+        .collect()
+}
 
-    // Normalize additional_info before hashing to handle formatting differences
-    let normalized_info = transaction
-        .additional_info
-        .split_whitespace() // Split by any whitespace (spaces, tabs, newlines)
-        .collect::<Vec<_>>()
-        .join(" "); // Join back with single spaces
-    normalized_info.hash(&mut hasher);
+// List the conversions of a journal that match the options
+fn history(options: &History) -> Exit {
+    let (entries, unreadable) = match journal::read(&options.journal) {
+        Ok(read) => read,
+        Err(e) => {
+            error!("Cannot read journal {}: {}", options.journal.display(), e);
+            return Exit::Io;
+        }
+    };
+    if unreadable > 0 {
+        warn!(
+            "Skipped lines of {} that are not journal entries: {}",
+            options.journal.display(),
+            unreadable
+        );
+    }
+    let filter = HistoryFilter {
+        account: options.account.clone(),
+        month: options.month,
+        status: options.status.clone(),
+    };
+    let mut stdout = io::stdout().lock();
+    for entry in entries.iter().filter(|entry| filter.matches(entry)) {
+        let line = if options.json {
+            entry.to_json().to_string()
+        } else {
+            history_line(entry)
+        };
+        if writeln!(stdout, "{}", line).is_err() {
+            return Exit::Io;
+        }
+    }
+    Exit::Success
+}
 
-    let hash = hasher.finish();
+// A conversion as a line of text: time, outcome, account, period, input and
+// outputs or error
+fn history_line(entry: &JournalEntry) -> String {
+    let date = |datetime: Option<DateTime<FixedOffset>>| {
+        datetime.map_or("?".to_string(), |datetime| {
+            datetime.date_naive().to_string()
+        })
+    };
+    let period = match (entry.from, entry.to) {
+        (None, None) => "-".to_string(),
+        (from, to) => format!("{}..{}", date(from), date(to)),
+    };
+    let result = match &entry.error {
+        Some(error) => error.clone(),
+        None => format!("-> {}", entry.outputs.join(", ")),
+    };
+    format!(
+        "{}  {:<7}  {:<22}  {:<22}  {} {}",
+        entry.time.format("%Y-%m-%d %H:%M:%S%:z"),
+        entry.status,
+        if entry.account.is_empty() {
+            "-"
+        } else {
+            &entry.account
+        },
+        period,
+        entry.input,
+        result
+    )
+}
 
-    // Convert to a shorter alphanumeric string (base36)
-    // Take last 10 digits to keep it reasonable length
-    let short_hash = hash % 10_000_000_000;
-    format!("TX{:010}", short_hash)
+// Report of a run written with --report
+fn report(files: &[FileResult], exit: Exit, started: DateTime<Local>) -> Value {
+    let versions = Value::object()
+        .with("camtconvert", env!("CARGO_PKG_VERSION"))
+        .with("input", "camt.053.001.10")
+        .with("output", "camt.053.001.08");
+    let documents: Vec<Value> = files
+        .iter()
+        .map(|file| {
+            let value = Value::object().with("input", file.input.as_str());
+            match &file.result {
+                Ok(converted) => {
+                    let dropped = converted
+                        .dropped
+                        .iter()
+                        .fold(Value::object(), |dropped, (path, count)| {
+                            dropped.with(path, *count)
+                        });
+                    with_pages(value, converted)
+                        .with("status", if converted.skipped { "skipped" } else { "ok" })
+                        .with("statement", converted.statement.clone())
+                        .with("stats", converted.stats.to_json())
+                        .with("dropped", dropped)
+                        .with("warnings", converted.warnings.clone())
+                }
+                Err(e) => value
+                    .with("status", "error")
+                    .with("error", format!("{:#}", e)),
+            }
+        })
+        .collect();
+
+    Value::object()
+        .with("versions", versions)
+        .with("started", started.to_rfc3339())
+        .with("finished", Local::now().to_rfc3339())
+        .with("status", status(files))
+        .with("exit_code", exit as u64)
+        .with("files", documents)
 }
@@ -0,0 +1,79 @@
+//! The camt versions that can be read and written.
+//!
+//! Readers turn a document into the version independent
+//! [`Statement`](crate::model::Statement) and writers turn a statement into a
+//! document, so every registered reader can be combined with every registered
+//! writer. Supporting another version means adding one reader or writer here.
+
+use crate::error::Result;
+use crate::model::Statement;
+use crate::reader::{ParseOptions, Parsed, StatementHandler, parse_camt_10, stream_camt_10};
+use crate::writer::{WriteOptions, write_camt_04, write_camt_08};
+use std::io::{BufRead, Write};
+
+/// Reads one version into the statement model
+pub struct VersionReader {
+    /// Full name such as `camt.053.001.10`
+    pub version: &'static str,
+    pub read: fn(&mut dyn BufRead, &ParseOptions) -> Result<Parsed>,
+    /// Read passing each entry to a handler instead of keeping it, see
+    /// [`stream_camt_10`]
+    pub stream: fn(&mut dyn BufRead, &ParseOptions, &mut dyn StatementHandler) -> Result<Parsed>,
+}
+
+/// Writes the statement model as one version
+pub struct VersionWriter {
+    /// Full name such as `camt.053.001.08`
+    pub version: &'static str,
+    pub write: fn(&mut dyn Write, &Statement, &WriteOptions) -> Result<()>,
+}
+
+/// Input versions, the default first
+pub const READERS: &[VersionReader] = &[VersionReader {
+    version: "camt.053.001.10",
+    read: |input, options| parse_camt_10(input, options),
+    stream: |input, options, handler| stream_camt_10(input, options, handler),
+}];
+
+/// Output versions, the default first
+pub const WRITERS: &[VersionWriter] = &[
+    VersionWriter {
+        version: "camt.053.001.08",
+        write: |output, statement, options| write_camt_08(output, statement, options),
+    },
+    VersionWriter {
+        version: "camt.053.001.04",
+        write: |output, statement, options| write_camt_04(output, statement, options),
+    },
+];
+
+/// The reader of a version given in full, without the `camt.` prefix or as
+/// its last number: `camt.053.001.10`, `053.001.10` or `10`
+pub fn find_reader(version: &str) -> Option<&'static VersionReader> {
+    READERS
+        .iter()
+        .find(|reader| names_version(reader.version, version))
+}
+
+/// The writer of a version, named as for [`find_reader`]
+pub fn find_writer(version: &str) -> Option<&'static VersionWriter> {
+    WRITERS
+        .iter()
+        .find(|writer| names_version(writer.version, version))
+}
+
+/// Every input and output version that can be converted, as pairs of full
+/// names
+pub fn pairs() -> impl Iterator<Item = (&'static str, &'static str)> {
+    READERS.iter().flat_map(|reader| {
+        WRITERS
+            .iter()
+            .map(move |writer| (reader.version, writer.version))
+    })
+}
+
+pub(crate) fn names_version(version: &str, name: &str) -> bool {
+    name == version
+        || version.strip_prefix("camt.") == Some(name)
+        || version.rsplit('.').next() == Some(name)
+}
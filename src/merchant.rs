@@ -0,0 +1,127 @@
+//! Readable payee names for card entries, from merchant texts such as
+//! `COOP-4567 ZUERICH CHE TID 00123456 XXXX1234` that carry terminal ids,
+//! card numbers and country codes along with the name.
+//!
+//! A card entry is one whose bank transaction code is a card code, see
+//! [`Transaction::is_card`]. Its merchant text is the counterparty name, or
+//! the additional information where it has none. The cleaned text is the
+//! payee of exports, see [`Transaction::payee`]. Each match of the
+//! [`BUILTIN_PATTERNS`] and of the configured patterns is removed, in that
+//! order, and whitespace and separators left over are tidied up.
+
+use crate::model::{Statement, Transaction};
+use crate::regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+
+/// Patterns always removed from merchant texts: card transaction
+/// phrases, terminal ids, masked card numbers, dates, times, references
+/// after `*`, numbers of four or more digits and country codes at the end
+pub const BUILTIN_PATTERNS: &[&str] = &[
+    r"(?i)^card (transaction|payment|purchase) of [0-9.,']+ [a-z]{3} (issued by|at) ",
+    r"(?i)^(pos|eftpos|visa|mastercard|maestro|debit card|card)( purchase| payment)?( |:|-)+",
+    r"(?i)(^| )(tid|term|terminal|trm)( |:|#|-)*[0-9a-z]*[0-9][0-9a-z]*",
+    r"(?i)(x|\*){4,}[0-9]{4}",
+    r"[0-9]{2}[./][0-9]{2}[./][0-9]{2,4}",
+    r"[0-9]{2}:[0-9]{2}(:[0-9]{2})?",
+    r"\*[0-9A-Za-z]+",
+    r"(-|#|/)?[0-9]{4,}",
+    r" (CHE|CH|DEU|AUT|FRA|ITA|LIE|LUX|NLD|BEL|ESP|GBR|IRL|USA)$",
+];
+
+/// Patterns of a merchant patterns file and the built-in ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerchantCleanup {
+    patterns: Vec<Regex>,
+}
+
+/// A merchant patterns file that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerchantError(String);
+
+impl fmt::Display for MerchantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for MerchantError {}
+
+impl Default for MerchantCleanup {
+    fn default() -> Self {
+        MerchantCleanup {
+            patterns: BUILTIN_PATTERNS
+                .iter()
+                .map(|pattern| pattern.parse().expect("built-in patterns are valid"))
+                .collect(),
+        }
+    }
+}
+
+/// The built-in patterns followed by those of a patterns file, one regular
+/// expression per line
+impl FromStr for MerchantCleanup {
+    type Err = MerchantError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut cleanup = MerchantCleanup::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let pattern = line.parse().map_err(|message| {
+                MerchantError(format!(
+                    "Line {} of merchant patterns: {}",
+                    index + 1,
+                    message
+                ))
+            })?;
+            cleanup.patterns.push(pattern);
+        }
+        Ok(cleanup)
+    }
+}
+
+impl MerchantCleanup {
+    /// A merchant text without the matches of the patterns
+    pub fn clean(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, " ");
+        }
+        let words: Vec<&str> = text
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .collect();
+        words
+            .join(" ")
+            .trim_matches(|c: char| matches!(c, '-' | '*' | ',' | ':' | '/' | '#'))
+            .trim()
+            .to_string()
+    }
+
+    /// Set the payee of a card entry to its cleaned merchant text, keeping
+    /// the names of the bank. Other entries and texts with nothing left get
+    /// no payee.
+    pub fn apply_transaction(&self, transaction: &mut Transaction) {
+        if !transaction.is_card() {
+            return;
+        }
+        let text = match transaction.payee() {
+            "" => &transaction.additional_info,
+            name => name,
+        };
+        let cleaned = self.clean(text);
+        if !cleaned.is_empty() {
+            transaction.payee = cleaned;
+        }
+    }
+
+    /// Clean the merchant texts of the card entries of a statement
+    pub fn apply(&self, statement: &mut Statement) {
+        for transaction in &mut statement.transactions {
+            self.apply_transaction(transaction);
+        }
+    }
+}
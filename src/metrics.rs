@@ -0,0 +1,124 @@
+//! Counters of the conversions of a server, exposed at `GET /metrics` in the
+//! Prometheus text format so that the converter can be monitored like any
+//! other service.
+//!
+//! Conversions of `POST /convert` and of jobs are counted alike. Failures
+//! are labelled `invalid` for documents whose data is invalid or incomplete
+//! and `unreadable` for anything else about the document.
+
+use crate::convert::Converted;
+use crate::error::ConvertError;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds of the buckets of the duration histogram, in seconds
+pub const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Default)]
+struct Counts {
+    conversions: u64,
+    invalid: u64,
+    unreadable: u64,
+    entries: u64,
+    warnings: u64,
+    bytes_read: u64,
+    // Conversions per bucket of DURATION_BUCKETS and above the last, not
+    // cumulative, and the sum of all durations
+    durations: [u64; DURATION_BUCKETS.len() + 1],
+    duration_sum: Duration,
+}
+
+/// Metrics of a server, shared by its connections and workers
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counts: Mutex<Counts>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Count a conversion that took `duration`
+    pub fn record(&self, result: Result<&Converted, &ConvertError>, duration: Duration) {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        counts.conversions += 1;
+        match result {
+            Ok(converted) => {
+                counts.entries += converted.stats.entries as u64;
+                counts.warnings += converted.warnings.len() as u64;
+                counts.bytes_read += converted.stats.bytes_read;
+            }
+            Err(e) if e.is_recoverable() => counts.invalid += 1,
+            Err(_) => counts.unreadable += 1,
+        }
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        counts.durations[bucket] += 1;
+        counts.duration_sum += duration;
+    }
+
+    /// The metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        let mut text = String::new();
+        let mut counter = |name: &str, help: &str, values: &[(&str, u64)]| {
+            let _ = writeln!(text, "# HELP {} {}", name, help);
+            let _ = writeln!(text, "# TYPE {} counter", name);
+            for (labels, value) in values {
+                let _ = writeln!(text, "{}{} {}", name, labels, value);
+            }
+        };
+        counter(
+            "camtconvert_conversions_total",
+            "Documents converted or failed.",
+            &[("", counts.conversions)],
+        );
+        counter(
+            "camtconvert_conversion_failures_total",
+            "Documents that could not be converted.",
+            &[
+                ("{reason=\"invalid\"}", counts.invalid),
+                ("{reason=\"unreadable\"}", counts.unreadable),
+            ],
+        );
+        counter(
+            "camtconvert_entries_total",
+            "Entries of converted documents.",
+            &[("", counts.entries)],
+        );
+        counter(
+            "camtconvert_warnings_total",
+            "Warnings of converted documents.",
+            &[("", counts.warnings)],
+        );
+        counter(
+            "camtconvert_input_bytes_total",
+            "Bytes of converted documents as received.",
+            &[("", counts.bytes_read)],
+        );
+
+        let name = "camtconvert_conversion_duration_seconds";
+        let _ = writeln!(text, "# HELP {} Time taken by conversions.", name);
+        let _ = writeln!(text, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (index, bound) in DURATION_BUCKETS.iter().enumerate() {
+            cumulative += counts.durations[index];
+            let _ = writeln!(text, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+        }
+        let _ = writeln!(
+            text,
+            "{}_bucket{{le=\"+Inf\"}} {}",
+            name, counts.conversions
+        );
+        let _ = writeln!(text, "{}_sum {}", name, counts.duration_sum.as_secs_f64());
+        let _ = writeln!(text, "{}_count {}", name, counts.conversions);
+        text
+    }
+}
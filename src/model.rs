@@ -0,0 +1,536 @@
+use crate::currency::minor_units;
+use crate::decimal::Decimal;
+use chrono::{DateTime, FixedOffset, NaiveDate, SecondsFormat};
+use std::fmt;
+
+/// Monetary amount with its currency (the Ccy attribute of Amt elements)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Amount {
+    pub value: Decimal,
+    pub currency: String,
+}
+
+impl Amount {
+    /// The value with the decimal places of its currency, e.g. `10.5` CHF as
+    /// `10.50` and `100.00` JPY as `100`. Values in unknown currencies or with
+    /// more decimal places than their currency has are kept as they are.
+    pub fn normalized(&self) -> Decimal {
+        minor_units(&self.currency)
+            .and_then(|units| self.value.with_scale(units))
+            .unwrap_or(self.value)
+    }
+
+    /// Decimal places of the currency, if the value has more than that
+    pub fn excess_precision(&self) -> Option<u32> {
+        let units = minor_units(&self.currency)?;
+        self.value.with_scale(units).is_none().then_some(units)
+    }
+}
+
+/// ISO 20022 date choice: either a plain date (Dt) or a date with time (DtTm)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrDateTime {
+    Date(NaiveDate),
+    DateTime(DateTime<FixedOffset>),
+}
+
+impl DateOrDateTime {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            DateOrDateTime::Date(date) => *date,
+            DateOrDateTime::DateTime(datetime) => datetime.date_naive(),
+        }
+    }
+}
+
+impl fmt::Display for DateOrDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateOrDateTime::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            // Canonical RFC 3339 form, which matches the input for typical exports
+            DateOrDateTime::DateTime(datetime) => write!(
+                f,
+                "{}",
+                datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            ),
+        }
+    }
+}
+
+/// Credit/debit indicator (CdtDbtInd)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreditDebit {
+    Credit,
+    Debit,
+}
+
+impl CreditDebit {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "CRDT" => Some(CreditDebit::Credit),
+            "DBIT" => Some(CreditDebit::Debit),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CreditDebit::Credit => "CRDT",
+            CreditDebit::Debit => "DBIT",
+        }
+    }
+}
+
+/// Balance type code (Bal/Tp/CdOrPrtry/Cd), ISO 20022 external code list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceType {
+    ClosingAvailable,
+    ClosingBooked,
+    ForwardAvailable,
+    Information,
+    InterimAvailable,
+    InterimBooked,
+    OpeningAvailable,
+    OpeningBooked,
+    PreviouslyClosedBooked,
+    Expected,
+}
+
+impl BalanceType {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "CLAV" => Some(BalanceType::ClosingAvailable),
+            "CLBD" => Some(BalanceType::ClosingBooked),
+            "FWAV" => Some(BalanceType::ForwardAvailable),
+            "INFO" => Some(BalanceType::Information),
+            "ITAV" => Some(BalanceType::InterimAvailable),
+            "ITBD" => Some(BalanceType::InterimBooked),
+            "OPAV" => Some(BalanceType::OpeningAvailable),
+            "OPBD" => Some(BalanceType::OpeningBooked),
+            "PRCD" => Some(BalanceType::PreviouslyClosedBooked),
+            "XPCD" => Some(BalanceType::Expected),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BalanceType::ClosingAvailable => "CLAV",
+            BalanceType::ClosingBooked => "CLBD",
+            BalanceType::ForwardAvailable => "FWAV",
+            BalanceType::Information => "INFO",
+            BalanceType::InterimAvailable => "ITAV",
+            BalanceType::InterimBooked => "ITBD",
+            BalanceType::OpeningAvailable => "OPAV",
+            BalanceType::OpeningBooked => "OPBD",
+            BalanceType::PreviouslyClosedBooked => "PRCD",
+            BalanceType::Expected => "XPCD",
+        }
+    }
+}
+
+/// Entry status (Ntry/Sts/Cd)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntryStatus {
+    #[default]
+    Booked,
+    Pending,
+    Information,
+    Future,
+}
+
+impl EntryStatus {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "BOOK" => Some(EntryStatus::Booked),
+            "PDNG" => Some(EntryStatus::Pending),
+            "INFO" => Some(EntryStatus::Information),
+            "FUTR" => Some(EntryStatus::Future),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryStatus::Booked => "BOOK",
+            EntryStatus::Pending => "PDNG",
+            EntryStatus::Information => "INFO",
+            EntryStatus::Future => "FUTR",
+        }
+    }
+}
+
+/// A creditor reference of structured remittance information
+/// (RmtInf/Strd/CdtrRefInf)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CreditorReference {
+    /// Code or proprietary type such as `SCOR` or `QRR`, empty if not given
+    pub kind: String,
+    pub reference: String,
+}
+
+impl CreditorReference {
+    /// Whether the type is an ISO document type code, written as `Cd` rather
+    /// than `Prtry`
+    pub fn is_iso_kind(&self) -> bool {
+        matches!(
+            self.kind.as_str(),
+            "RADM" | "RPIN" | "FXDR" | "DISP" | "PUOR" | "SCOR"
+        )
+    }
+}
+
+/// Currency exchange of an amount of the amount details (CcyXchg)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CurrencyExchange {
+    pub source_currency: String,
+    /// Empty if not given
+    pub target_currency: String,
+    /// Currency the rate is quoted for one unit of, empty if not given
+    pub unit_currency: String,
+    pub rate: Decimal,
+}
+
+/// An amount of the amount details with the exchange it went through, if any
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AmountDetail {
+    pub amount: Amount,
+    pub exchange: Option<CurrencyExchange>,
+}
+
+/// Dates of the transaction details of an entry (TxDtls/RltdDts)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RelatedDates {
+    /// Date and time the payment was accepted (AccptncDtTm)
+    pub acceptance: Option<DateTime<FixedOffset>>,
+    /// Date the payment was settled between the banks (IntrBkSttlmDt)
+    pub interbank_settlement: Option<NaiveDate>,
+}
+
+impl RelatedDates {
+    pub fn is_empty(&self) -> bool {
+        self.acceptance.is_none() && self.interbank_settlement.is_none()
+    }
+}
+
+/// Amounts of an entry in other currencies than it was booked in (AmtDtls)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AmountDetails {
+    /// Amount as instructed by the originator (InstdAmt)
+    pub instructed: Option<AmountDetail>,
+    /// Amount moved between the parties (TxAmt)
+    pub transaction: Option<AmountDetail>,
+    /// Amount converted by the account servicer (CntrValAmt)
+    pub counter_value: Option<AmountDetail>,
+}
+
+impl AmountDetails {
+    pub fn is_empty(&self) -> bool {
+        self.details().next().is_none()
+    }
+
+    /// The instructed, transaction and counter value amounts given, in
+    /// schema order with their element names
+    pub fn details(&self) -> impl Iterator<Item = (&'static str, &AmountDetail)> {
+        [
+            ("InstdAmt", &self.instructed),
+            ("TxAmt", &self.transaction),
+            ("CntrValAmt", &self.counter_value),
+        ]
+        .into_iter()
+        .filter_map(|(name, detail)| detail.as_ref().map(|detail| (name, detail)))
+    }
+
+    /// The amount in a currency, preferring the counter value
+    pub fn in_currency(&self, currency: &str) -> Option<&Amount> {
+        [&self.counter_value, &self.transaction, &self.instructed]
+            .into_iter()
+            .flatten()
+            .map(|detail| &detail.amount)
+            .find(|amount| amount.currency == currency)
+    }
+}
+
+/// A statement entry (Ntry)
+#[derive(Debug, Default, Clone)]
+pub struct Transaction {
+    pub amount: Amount,
+    pub credit_debit_ind: Option<CreditDebit>,
+    pub status: EntryStatus,
+    pub booking_date: Option<DateOrDateTime>,
+    /// Value date (ValDt), `None` if the input has none
+    pub value_date: Option<DateOrDateTime>,
+    pub bank_tx_code: String,
+    pub additional_info: String,
+    /// Reference of the bank for the entry (Ntry/AcctSvcrRef), empty if the
+    /// input has none. Outputs have generated references instead.
+    pub account_servicer_ref: String,
+    /// EndToEndId of the first transaction details of the entry, if any
+    pub end_to_end_id: String,
+    /// Cheque number of the first transaction details that have one
+    /// (TxDtls/Refs/ChqNb)
+    pub cheque_number: String,
+    /// Unstructured remittance information (Ustrd) of the transaction details
+    pub remittance_info: Vec<String>,
+    /// Creditor reference of the first transaction details that has one
+    pub creditor_reference: Option<CreditorReference>,
+    /// Name of the debtor of the first transaction details that has one
+    pub debtor_name: String,
+    /// Name of the creditor of the first transaction details that has one
+    pub creditor_name: String,
+    /// Name of the counterparty for exports, set by
+    /// [`MerchantCleanup`](crate::merchant::MerchantCleanup) and
+    /// [`Aliases`](crate::alias::Aliases); empty for the name of the bank,
+    /// see [`Transaction::payee`]
+    pub payee: String,
+    /// Additional information of the first transaction details that have it
+    /// (TxDtls/AddtlTxInf), often the booking text
+    pub transaction_info: String,
+    /// Dates of the transaction details, each from the first that has it
+    pub related_dates: RelatedDates,
+    /// Supplementary data of the transaction details (TxDtls/SplmtryData),
+    /// kept with [`SupplementaryData::Keep`](crate::reader::SupplementaryData)
+    pub supplementary_data: Vec<XmlElement>,
+    pub charges: Option<Amount>,
+    /// Amount details of the entry, or else of its first transaction details
+    /// that have them
+    pub amount_details: AmountDetails,
+    /// Paths of the child and grandchild elements present in the input, e.g.
+    /// `BkTxCd/Prtry`, in input order
+    pub layout: Vec<String>,
+    /// Child elements not modelled above, kept for `--keep-unknown`
+    pub extra_elements: Vec<XmlElement>,
+    /// Category assigned by [`Categories`](crate::category::Categories),
+    /// empty if none
+    pub category: String,
+    /// General-ledger account assigned by
+    /// [`GlAccounts`](crate::ledger::GlAccounts) for exports, empty if none
+    pub gl_account: String,
+}
+
+impl Transaction {
+    /// Amount with debits negated
+    pub fn signed_amount(&self) -> Decimal {
+        signed(&self.amount, self.credit_debit_ind)
+    }
+
+    /// Name of the other party of the account owner: the creditor of a
+    /// debit and the debtor of a credit
+    pub fn counterparty(&self) -> &str {
+        match self.credit_debit_ind {
+            Some(CreditDebit::Debit) => &self.creditor_name,
+            _ => &self.debtor_name,
+        }
+    }
+
+    /// Name of the counterparty as exported: the cleaned or canonical name
+    /// where one was set, or else the counterparty name of the bank
+    pub fn payee(&self) -> &str {
+        match self.payee.as_str() {
+            "" => self.counterparty(),
+            payee => payee,
+        }
+    }
+
+    /// Signed amount in the account currency. Entries booked in another
+    /// currency are counted at their amount in the account currency from the
+    /// amount details, or at their own amount if there is none.
+    pub fn account_amount(&self, account_currency: &str) -> Decimal {
+        match self.amount_details.in_currency(account_currency) {
+            Some(amount) if self.amount.currency != account_currency => {
+                signed(amount, self.credit_debit_ind)
+            }
+            _ => self.signed_amount(),
+        }
+    }
+
+    /// Whether the entry is a card payment: its proprietary code starts with
+    /// `CARD`, or its code has the family `CCRD`
+    pub fn is_card(&self) -> bool {
+        self.bank_tx_code.starts_with("CARD") || self.bank_tx_code.contains("-CCRD-")
+    }
+
+    /// Domain, family and sub-family of the bank transaction code written
+    /// for the entry, derived from its proprietary code
+    pub fn bank_tx_family(&self) -> (&'static str, &'static str, &'static str) {
+        if self.bank_tx_code.starts_with("CARD") {
+            ("PMNT", "CCRD", "POSD")
+        } else {
+            ("PMNT", "ICDT", "ESCT")
+        }
+    }
+}
+
+/// A statement balance (Bal)
+#[derive(Debug, Default, Clone)]
+pub struct Balance {
+    pub balance_type: Option<BalanceType>,
+    pub amount: Amount,
+    pub credit_debit_ind: Option<CreditDebit>,
+    pub date: Option<DateOrDateTime>,
+    /// Paths of the child and grandchild elements present in the input, e.g.
+    /// `BkTxCd/Prtry`, in input order
+    pub layout: Vec<String>,
+    /// Child elements not modelled above, kept for `--keep-unknown`
+    pub extra_elements: Vec<XmlElement>,
+}
+
+impl Balance {
+    /// Amount with debit balances negated
+    pub fn signed_amount(&self) -> Decimal {
+        signed(&self.amount, self.credit_debit_ind)
+    }
+}
+
+/// A bank-to-customer statement (Stmt) with its account
+#[derive(Debug, Default, Clone)]
+pub struct Statement {
+    pub id: String,
+    /// Message identification of the group header (GrpHdr/MsgId), empty if
+    /// not given
+    pub message_id: String,
+    /// Electronic sequence number (ElctrncSeqNb), empty if not given
+    pub sequence_number: String,
+    pub creation_datetime: Option<DateTime<FixedOffset>>,
+    pub from_datetime: Option<DateTime<FixedOffset>>,
+    pub to_datetime: Option<DateTime<FixedOffset>>,
+    pub iban: String,
+    pub currency: String,
+    pub owner_name: String,
+    /// BIC of the account servicer (Acct/Svcr/FinInstnId/BICFI)
+    pub servicer_bic: String,
+    pub balances: Vec<Balance>,
+    pub transactions: Vec<Transaction>,
+    /// Paths of the child and grandchild elements present in the input, e.g.
+    /// `BkTxCd/Prtry`, in input order
+    pub layout: Vec<String>,
+    /// Child elements not modelled above, kept for `--keep-unknown`
+    pub extra_elements: Vec<XmlElement>,
+    /// Siblings of the statement in BkToCstmrStmt, such as `SplmtryData`
+    pub message_extra_elements: Vec<XmlElement>,
+    /// Message recipient of the group header (GrpHdr/MsgRcpt), as in the
+    /// input
+    pub message_recipient: Option<XmlElement>,
+    /// Additional information of the group header (GrpHdr/AddtlInf), empty
+    /// if not given
+    pub message_info: String,
+}
+
+impl Statement {
+    /// Currency of the account (Acct/Ccy), or else of its opening balance
+    pub fn account_currency(&self) -> &str {
+        if !self.currency.is_empty() {
+            return &self.currency;
+        }
+        self.balance(BalanceType::OpeningBooked)
+            .or_else(|| self.balance(BalanceType::PreviouslyClosedBooked))
+            .map_or("", |balance| balance.amount.currency.as_str())
+    }
+
+    pub fn balance(&self, balance_type: BalanceType) -> Option<&Balance> {
+        self.balances
+            .iter()
+            .find(|b| b.balance_type == Some(balance_type))
+    }
+
+    /// Check that the opening balance plus all entries gives the closing balance.
+    ///
+    /// Returns a description of the mismatch, or `None` if the statement adds up
+    /// or lacks the balances needed for the check.
+    pub fn check_balances(&self) -> Option<String> {
+        let currency = self.account_currency();
+        let Some(entries_total) = self
+            .transactions
+            .iter()
+            .try_fold(Decimal::default(), |sum, tx| {
+                sum.checked_add(tx.account_amount(currency))
+            })
+        else {
+            return Some("Entries do not add up: their total is too large to compute".to_string());
+        };
+        self.check_balances_with_total(entries_total)
+    }
+
+    /// Like [`check_balances`](Self::check_balances), for a statement whose
+    /// entries were not kept. `entries_total` is the sum of their
+    /// [account amounts](Transaction::account_amount).
+    pub fn check_balances_with_total(&self, entries_total: Decimal) -> Option<String> {
+        let opening = self
+            .balance(BalanceType::OpeningBooked)
+            .or_else(|| self.balance(BalanceType::PreviouslyClosedBooked))?
+            .signed_amount();
+        let closing = self.balance(BalanceType::ClosingBooked)?.signed_amount();
+
+        let Some(expected) = opening.checked_add(entries_total) else {
+            return Some(
+                "Entries do not add up: opening balance plus entries is too large to compute"
+                    .to_string(),
+            );
+        };
+
+        if expected == closing {
+            None
+        } else {
+            Some(format!(
+                "Entries do not add up: opening balance plus entries is {} but closing balance is {}",
+                expected, closing
+            ))
+        }
+    }
+}
+
+/// An element copied from the input as-is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlElement {
+    pub name: String,
+    /// Namespace of the element, `None` for the namespace of the statement
+    pub namespace: Option<String>,
+    /// Name and value of each attribute, namespaced ones named `{namespace}name`
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    /// Child elements with a name
+    pub fn children_named<'a, 'b>(
+        &'a self,
+        name: &'b str,
+    ) -> impl Iterator<Item = &'a XmlElement> + use<'a, 'b> {
+        self.children.iter().filter_map(move |node| match node {
+            XmlNode::Element(element) if element.name == name => Some(element),
+            _ => None,
+        })
+    }
+
+    /// The first element at a path of child names such as `Tp/CdOrPrtry/Cd`
+    pub fn find(&self, path: &str) -> Option<&XmlElement> {
+        path.split('/')
+            .try_fold(self, |element, name| element.children_named(name).next())
+    }
+
+    /// Text content of the element, without that of its children
+    pub fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|node| match node {
+                XmlNode::Text(text) => Some(text.as_str()),
+                XmlNode::Element(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Content of an [`XmlElement`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+fn signed(amount: &Amount, credit_debit_ind: Option<CreditDebit>) -> Decimal {
+    if credit_debit_ind == Some(CreditDebit::Debit) {
+        -amount.value
+    } else {
+        amount.value
+    }
+}
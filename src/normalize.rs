@@ -0,0 +1,169 @@
+//! Normalization of camt.053.001.10 documents within their version, so that
+//! archived statements can be deduplicated and diffed.
+//!
+//! The document is first read as a statement, so that only documents that
+//! convert are normalized, and then written back element by element. The
+//! children of the statement containers (`BkToCstmrStmt`, `Stmt`, `Bal` and
+//! `Ntry`) are put in schema order, which is the same in versions 08 and 10,
+//! keeping the order of repeated elements. Text is trimmed and escaped the
+//! same way everywhere, CDATA sections become text, comments, processing
+//! instructions and whitespace between elements are left out, attributes are
+//! sorted by name and namespaces are declared as default namespaces where they
+//! change. Nothing else is changed, so the normalized document holds the same
+//! information as the input.
+
+use crate::encoding::{InputEncoding, Transcoder, detect_encoding};
+use crate::error::{ConvertError, Result, Warning};
+use crate::gzip::{GzDecoder, is_gzip};
+use crate::model::{XmlElement, XmlNode};
+use crate::passthrough::{schema_position, write_element_tree};
+use crate::reader::{ParseOptions, copy_attributes, parse_camt_10};
+use crate::writer::Indent;
+use quick_xml::events::{BytesDecl, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::reader::NsReader;
+use quick_xml::writer::Writer;
+use std::io::{BufRead, Read, Write};
+
+/// Write a camt.053.001.10 document in normalized form. Fails if the document
+/// cannot be read as a statement, and returns the warnings of reading it and
+/// of the balance check.
+pub fn normalize_camt_10<R: BufRead, W: Write>(
+    mut input: R,
+    output: W,
+    options: &ParseOptions,
+    indent: Indent,
+) -> Result<Vec<Warning>> {
+    let mut document = Vec::new();
+    if is_gzip(input.fill_buf()?) {
+        GzDecoder::new(input).read_to_end(&mut document)?;
+    } else {
+        input.read_to_end(&mut document)?;
+    }
+    let encoding = match options.encoding {
+        Some(encoding) => encoding,
+        None => detect_encoding(&document)?,
+    };
+    if encoding != InputEncoding::Utf8 {
+        let mut transcoded = Vec::with_capacity(document.len());
+        Transcoder::new(document.as_slice(), encoding).read_to_end(&mut transcoded)?;
+        document = transcoded;
+    }
+
+    let options = ParseOptions {
+        encoding: Some(InputEncoding::Utf8),
+        ..options.clone()
+    };
+    let parsed = parse_camt_10(document.as_slice(), &options)?;
+    let mut warnings = parsed.warnings;
+    if let Some(message) = parsed.statement.check_balances() {
+        warnings.push(Warning {
+            message,
+            location: None,
+        });
+    }
+
+    let root = read_tree(document.as_slice())?;
+    let mut writer = match indent {
+        Indent::Spaces(width) => Writer::new_with_indent(output, b' ', width),
+        Indent::Compact => Writer::new(output),
+    };
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    write_element_tree(&mut writer, &root, "")?;
+    writer.into_inner().flush()?;
+    Ok(warnings)
+}
+
+// The element tree of a document, normalized
+fn read_tree(document: &[u8]) -> Result<XmlElement> {
+    let mut reader = NsReader::from_reader(document);
+    reader.config_mut().trim_text(false);
+    let mut buf = Vec::new();
+    // Open elements, innermost last, and the text of the innermost
+    let mut open: Vec<XmlElement> = Vec::new();
+    let mut text = String::new();
+    loop {
+        let (resolved, event) = reader.read_resolved_event_into(&mut buf)?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                flush_text(&mut open, &mut text);
+                let namespace = match resolved {
+                    ResolveResult::Bound(namespace) => {
+                        std::str::from_utf8(namespace.as_ref())?.to_string()
+                    }
+                    _ => String::new(),
+                };
+                let mut attributes = copy_attributes(&reader, e)?;
+                attributes.sort();
+                open.push(XmlElement {
+                    name: std::str::from_utf8(e.local_name().as_ref())?.to_string(),
+                    namespace: Some(namespace),
+                    attributes,
+                    children: Vec::new(),
+                });
+                if matches!(event, Event::Empty(_))
+                    && let Some(root) = close(&mut open)
+                {
+                    return Ok(root);
+                }
+            }
+            Event::Text(ref e) => text.push_str(&e.unescape()?),
+            Event::CData(ref e) => text.push_str(std::str::from_utf8(e)?),
+            Event::End(_) => {
+                flush_text(&mut open, &mut text);
+                if let Some(root) = close(&mut open) {
+                    return Ok(root);
+                }
+            }
+            Event::Eof => {
+                return Err(ConvertError::XmlSyntax(
+                    "Document ends before its root element".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+// Add the text read so far to the innermost element, trimmed, unless it is
+// only whitespace
+fn flush_text(open: &mut [XmlElement], text: &mut String) {
+    let trimmed = text.trim();
+    if let Some(element) = open.last_mut()
+        && !trimmed.is_empty()
+    {
+        element.children.push(XmlNode::Text(trimmed.to_string()));
+    }
+    text.clear();
+}
+
+// Finish the innermost element, returning it if it is the root
+fn close(open: &mut Vec<XmlElement>) -> Option<XmlElement> {
+    let mut element = open.pop()?;
+    sort_children(&mut element);
+    match open.last_mut() {
+        Some(parent) => {
+            parent.children.push(XmlNode::Element(element));
+            None
+        }
+        None => Some(element),
+    }
+}
+
+// Children of statement containers in schema order, those unknown to the
+// schema last
+fn sort_children(element: &mut XmlElement) {
+    let XmlElement {
+        name,
+        namespace,
+        children,
+        ..
+    } = element;
+    children.sort_by_key(|node| match node {
+        XmlNode::Element(child) if child.namespace == *namespace => {
+            schema_position(name, &child.name).unwrap_or(usize::MAX)
+        }
+        _ => usize::MAX,
+    });
+}
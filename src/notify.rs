@@ -0,0 +1,55 @@
+//! Notifications of conversions, posted as JSON to a webhook with `curl`.
+
+use crate::json::Value;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Time a webhook may take to answer
+const TIMEOUT_SECONDS: &str = "30";
+
+/// Whether a webhook URL can be posted to
+pub fn is_webhook_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// POST `payload` to `url`, failing if the webhook does not answer with a
+/// success status
+pub fn post_json(url: &str, payload: &Value) -> io::Result<()> {
+    let mut child = Command::new("curl")
+        .args([
+            "--fail",
+            "--silent",
+            "--show-error",
+            "--proto",
+            "=http,https",
+            "--max-time",
+            TIMEOUT_SECONDS,
+            "--header",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+            "--output",
+            "/dev/null",
+            "--",
+        ])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not run curl: {}", e)))?;
+    // Closed at the end of the block, so the input ends
+    {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(payload.to_string().as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "Could not notify {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
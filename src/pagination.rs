@@ -0,0 +1,254 @@
+//! Outputs split into pages, for receiving channels that limit the number of
+//! entries or the size of a file.
+//!
+//! Each page is a complete document with some of the entries of the
+//! statement, numbered in the message pagination of its group header
+//! (`MsgPgntn`). The opening balances of the input are written to the first
+//! page and its other balances to the last. Every page before the last ends
+//! with an interim booked balance (`ITBD`) of the entries up to and including
+//! it, and every page after the first opens with the interim balance of the
+//! page before it, so that the entries of each page add up.
+
+use crate::decimal::Decimal;
+use crate::error::{ConvertError, Result};
+use crate::model::{Amount, Balance, BalanceType, CreditDebit, Statement};
+use crate::writer::{Page, WriteOptions, write_statement};
+use std::ops::Range;
+
+/// Limits of the pages of an output, none by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageLimits {
+    /// Most entries on one page
+    pub max_entries: Option<usize>,
+    /// Largest size of one page in bytes
+    pub max_bytes: Option<u64>,
+}
+
+impl PageLimits {
+    /// Whether no limit is set, so that the output is a single document
+    pub fn is_unlimited(&self) -> bool {
+        self.max_entries.is_none() && self.max_bytes.is_none()
+    }
+}
+
+/// Write a statement as pages within the limits, each a complete document.
+///
+/// A statement within the limits is written as a single page, the same as by
+/// [`write_statement`]. Fails if a page without entries or with a single
+/// entry is larger than `max_bytes`.
+pub fn write_pages(
+    statement: &Statement,
+    options: &WriteOptions,
+    limits: &PageLimits,
+) -> Result<Vec<Vec<u8>>> {
+    let pager = Pager::new(statement, options)?;
+    let count = statement.transactions.len();
+    let sizes = match limits.max_bytes {
+        Some(_) => pager.entry_sizes()?,
+        None => Vec::new(),
+    };
+
+    let mut pages = Vec::new();
+    let mut start = 0;
+    loop {
+        let number = pages.len() + 1;
+        let mut end = match limits.max_entries {
+            Some(max_entries) => count.min(start + max_entries.max(1)),
+            None => count,
+        };
+        if let Some(max_bytes) = limits.max_bytes {
+            // Estimated from the sizes of the entries, then checked below.
+            // The last page has other balances than the pages before it.
+            let entries_size: u64 = sizes[start..end].iter().sum();
+            let last_size = pager.write(start..start, number, true)?.len() as u64;
+            if end < count || last_size + entries_size > max_bytes {
+                let mut size = pager.write(start..start, number, false)?.len() as u64;
+                let mut fitting = start;
+                while fitting < end && size + sizes[fitting] <= max_bytes {
+                    size += sizes[fitting];
+                    fitting += 1;
+                }
+                end = fitting.max((start + 1).min(end));
+            }
+        }
+
+        let page = loop {
+            let page = pager.write(start..end, number, end == count)?;
+            let Some(max_bytes) = limits.max_bytes.filter(|&max| page.len() as u64 > max) else {
+                break page;
+            };
+            if end <= start + 1 {
+                let reason = if end > start {
+                    format!("entry {} needs a page of {} bytes", end, page.len())
+                } else {
+                    format!("a page without entries has {} bytes", page.len())
+                };
+                return Err(ConvertError::PageLimit(format!(
+                    "{}, more than the {} bytes allowed",
+                    reason, max_bytes
+                )));
+            }
+            end -= 1;
+        };
+        pages.push(page);
+        if end == count {
+            return Ok(pages);
+        }
+        start = end;
+    }
+}
+
+// Writes pages of a statement
+struct Pager<'a> {
+    statement: &'a Statement,
+    // The statement without entries, to which those of a page are added
+    header: Statement,
+    options: &'a WriteOptions,
+    // The balance before the first entry, if the input has an opening or
+    // closing booked balance
+    opening: Option<Balance>,
+    // Sums of the amounts of the first entries in the account currency, by
+    // their count
+    totals: Vec<Decimal>,
+}
+
+impl<'a> Pager<'a> {
+    fn new(statement: &'a Statement, options: &'a WriteOptions) -> Result<Self> {
+        let currency = statement.account_currency();
+        let mut totals = vec![Decimal::default()];
+        for transaction in &statement.transactions {
+            let total = totals[totals.len() - 1]
+                .checked_add(transaction.account_amount(currency))
+                .ok_or_else(overflow)?;
+            totals.push(total);
+        }
+        let opening = statement
+            .balance(BalanceType::OpeningBooked)
+            .or_else(|| statement.balance(BalanceType::PreviouslyClosedBooked))
+            .map(|balance| (balance, Some(balance.signed_amount())))
+            .or_else(|| {
+                let closing = statement.balance(BalanceType::ClosingBooked)?;
+                let amount = closing
+                    .signed_amount()
+                    .checked_sub(totals[totals.len() - 1]);
+                Some((closing, amount))
+            });
+        let opening = match opening {
+            Some((balance, Some(amount))) => Some(signed_balance(balance, amount)),
+            Some((_, None)) => return Err(overflow()),
+            None => None,
+        };
+        Ok(Pager {
+            statement,
+            header: Statement {
+                transactions: Vec::new(),
+                ..statement.clone()
+            },
+            options,
+            opening,
+            totals,
+        })
+    }
+
+    // Size each entry adds to a page, as written on a page of its own
+    fn entry_sizes(&self) -> Result<Vec<u64>> {
+        let empty = self.write(0..0, 1, false)?.len() as u64;
+        (0..self.statement.transactions.len())
+            .map(|index| {
+                let size = self.write(index..index + 1, 1, false)?.len() as u64;
+                Ok(size.saturating_sub(empty))
+            })
+            .collect()
+    }
+
+    fn write(&self, entries: Range<usize>, number: usize, last: bool) -> Result<Vec<u8>> {
+        let first = number == 1;
+        let mut balances: Vec<Balance> = self
+            .statement
+            .balances
+            .iter()
+            .filter(|balance| match (first, last) {
+                (true, true) => true,
+                (true, false) => is_opening(balance),
+                (false, true) => !is_opening(balance),
+                (false, false) => false,
+            })
+            .cloned()
+            .collect();
+        // Each page opens with the interim balance the page before it closes
+        // with
+        if let Some(opening) = &self.opening {
+            if !first {
+                balances.insert(0, self.interim(opening, entries.start)?);
+            }
+            if !last {
+                balances.push(self.interim(opening, entries.end)?);
+            }
+        }
+        let page = Statement {
+            balances,
+            transactions: self.statement.transactions[entries].to_vec(),
+            ..self.header.clone()
+        };
+        let options = WriteOptions {
+            page: Page { number, last },
+            ..self.options.clone()
+        };
+        let mut output = Vec::new();
+        write_statement(&mut output, &page, &options)?;
+        Ok(output)
+    }
+
+    // Interim booked balance after the first `count` entries, dated as the
+    // last of them that is booked
+    fn interim(&self, opening: &Balance, count: usize) -> Result<Balance> {
+        let amount = opening
+            .signed_amount()
+            .checked_add(self.totals[count])
+            .ok_or_else(overflow)?;
+        let mut interim = signed_balance(opening, amount);
+        interim.balance_type = Some(BalanceType::InterimBooked);
+        interim.date = self.statement.transactions[..count]
+            .iter()
+            .rev()
+            .find_map(|transaction| transaction.booking_date)
+            .or(interim.date);
+        Ok(interim)
+    }
+}
+
+fn is_opening(balance: &Balance) -> bool {
+    matches!(
+        balance.balance_type,
+        Some(
+            BalanceType::OpeningBooked
+                | BalanceType::OpeningAvailable
+                | BalanceType::PreviouslyClosedBooked
+        )
+    )
+}
+
+// A balance like another with a signed amount, without the input elements
+// that only belong to the other
+fn signed_balance(balance: &Balance, amount: Decimal) -> Balance {
+    let debit = amount < Decimal::default();
+    Balance {
+        balance_type: balance.balance_type,
+        amount: Amount {
+            value: if debit { -amount } else { amount },
+            currency: balance.amount.currency.clone(),
+        },
+        credit_debit_ind: Some(if debit {
+            CreditDebit::Debit
+        } else {
+            CreditDebit::Credit
+        }),
+        date: balance.date,
+        layout: Vec::new(),
+        extra_elements: Vec::new(),
+    }
+}
+
+fn overflow() -> ConvertError {
+    ConvertError::Overflow("running balance of the pages".to_string())
+}
@@ -0,0 +1,207 @@
+// Passthrough of input elements the converter does not model (--keep-unknown).
+//
+// Unknown children of the statement containers are copied as element trees by
+// the reader and written back by the writer at their place in the
+// camt.053.001.08 schema, so nothing is lost when archiving converted files.
+
+use crate::error::Result;
+use crate::model::{XmlElement, XmlNode};
+use crate::writer::CAMT_08_NAMESPACE;
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::writer::Writer;
+use std::io::Write;
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+struct Container {
+    name: &'static str,
+    // Child elements in schema order
+    order: &'static [&'static str],
+    // Children the writer generates itself
+    written: &'static [&'static str],
+}
+
+const CONTAINERS: &[Container] = &[
+    Container {
+        name: "BkToCstmrStmt",
+        order: &["GrpHdr", "Stmt", "SplmtryData"],
+        written: &["GrpHdr", "Stmt"],
+    },
+    Container {
+        name: "Stmt",
+        order: &[
+            "Id",
+            "StmtPgntn",
+            "ElctrncSeqNb",
+            "RptgSeq",
+            "LglSeqNb",
+            "CreDtTm",
+            "FrToDt",
+            "CpyDplctInd",
+            "RptgSrc",
+            "Acct",
+            "RltdAcct",
+            "Intrst",
+            "Bal",
+            "TxsSummry",
+            "Ntry",
+            "AddtlStmtInf",
+        ],
+        written: &[
+            "Id",
+            "ElctrncSeqNb",
+            "CreDtTm",
+            "FrToDt",
+            "Acct",
+            "Bal",
+            "Ntry",
+        ],
+    },
+    Container {
+        name: "Bal",
+        order: &["Tp", "CdtLine", "Amt", "CdtDbtInd", "Dt", "Avlbty"],
+        written: &["Tp", "Amt", "CdtDbtInd", "Dt"],
+    },
+    Container {
+        name: "Ntry",
+        order: &[
+            "NtryRef",
+            "Amt",
+            "CdtDbtInd",
+            "RvslInd",
+            "Sts",
+            "BookgDt",
+            "ValDt",
+            "AcctSvcrRef",
+            "Avlbty",
+            "BkTxCd",
+            "ComssnWvrInd",
+            "AddtlInfInd",
+            "AmtDtls",
+            "Chrgs",
+            "TechInptChanl",
+            "Intrst",
+            "CardTx",
+            "NtryDtls",
+            "AddtlNtryInf",
+        ],
+        written: &[
+            "Amt",
+            "CdtDbtInd",
+            "Sts",
+            "BookgDt",
+            "ValDt",
+            "AcctSvcrRef",
+            "BkTxCd",
+            "AmtDtls",
+            "NtryDtls",
+            "AddtlNtryInf",
+        ],
+    },
+];
+
+fn container(name: &str) -> Option<&'static Container> {
+    CONTAINERS.iter().find(|c| c.name == name)
+}
+
+/// Whether a child element of `container` is to be copied rather than
+/// converted. Elements from foreign namespaces are always copied.
+pub(crate) fn is_passed_through(container_name: &str, name: &str) -> bool {
+    container(container_name).is_some_and(|c| !c.written.contains(&name))
+}
+
+/// Position of a child element in the schema order of `container_name`, if
+/// both are known
+pub(crate) fn schema_position(container_name: &str, name: &str) -> Option<usize> {
+    container(container_name)?
+        .order
+        .iter()
+        .position(|n| *n == name)
+}
+
+/// Write the copied children of `container_name` that belong between the
+/// schema elements `after` and `before`. Elements unknown to the schema go
+/// last, in input order.
+pub(crate) fn write_between<W: Write>(
+    writer: &mut Writer<W>,
+    container_name: &str,
+    elements: &[XmlElement],
+    after: Option<&str>,
+    before: Option<&str>,
+) -> Result<()> {
+    let Some(container) = container(container_name) else {
+        return Ok(());
+    };
+    let position = |name: &str| {
+        container
+            .order
+            .iter()
+            .position(|n| *n == name)
+            .unwrap_or(container.order.len())
+    };
+
+    for element in elements {
+        let index = match element.namespace {
+            None => position(&element.name),
+            Some(_) => container.order.len(),
+        };
+        let after_start = after.is_none_or(|name| index > position(name));
+        let before_end = before.is_none_or(|name| index < position(name));
+        if after_start && before_end {
+            write_element_tree(writer, element, CAMT_08_NAMESPACE)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Write an element and its content. A default namespace declaration is added
+// wherever the namespace differs from that of the parent element.
+pub(crate) fn write_element_tree<W: Write>(
+    writer: &mut Writer<W>,
+    element: &XmlElement,
+    parent_namespace: &str,
+) -> Result<()> {
+    let mut start = BytesStart::new(element.name.as_str());
+    let namespace = element.namespace.as_deref().unwrap_or(CAMT_08_NAMESPACE);
+    if namespace != parent_namespace {
+        start.push_attribute(("xmlns", namespace));
+    }
+    let mut prefixes: Vec<&str> = Vec::new();
+    for (name, value) in &element.attributes {
+        let name = match name.strip_prefix('{').and_then(|n| n.split_once('}')) {
+            Some((XML_NAMESPACE, local_name)) => format!("xml:{}", local_name),
+            Some((namespace, local_name)) => {
+                // Declare a prefix for each namespace used by the attributes
+                let index = match prefixes.iter().position(|p| *p == namespace) {
+                    Some(index) => index,
+                    None => {
+                        prefixes.push(namespace);
+                        let prefix = format!("xmlns:ns{}", prefixes.len());
+                        start.push_attribute((prefix.as_str(), namespace));
+                        prefixes.len() - 1
+                    }
+                };
+                format!("ns{}:{}", index + 1, local_name)
+            }
+            None => name.clone(),
+        };
+        start.push_attribute((name.as_str(), value.as_str()));
+    }
+
+    if element.children.is_empty() {
+        writer.write_event(Event::Empty(start))?;
+        return Ok(());
+    }
+
+    writer.write_event(Event::Start(start.borrow()))?;
+    for child in &element.children {
+        match child {
+            XmlNode::Element(child) => write_element_tree(writer, child, namespace)?,
+            XmlNode::Text(text) => writer.write_event(Event::Text(BytesText::new(text)))?,
+        }
+    }
+    writer.write_event(Event::End(start.to_end()))?;
+
+    Ok(())
+}
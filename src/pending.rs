@@ -0,0 +1,53 @@
+//! Pending entries (`Ntry/Sts/Cd` PDNG), which a bank reports before they are
+//! booked and which are not part of the booked balances.
+//!
+//! They can be kept in the statement, left out of it, or moved to an interim
+//! statement of their own. The interim statement has the header of the booked
+//! one and its closing booked balance as interim booked balance (ITBD), the
+//! balance the pending entries will be booked against.
+
+use crate::model::{BalanceType, EntryStatus, Statement, Transaction};
+
+/// What happens to pending entries
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PendingAction {
+    /// Keep them in the statement
+    #[default]
+    Include,
+    /// Leave them out of the output
+    Drop,
+    /// Write them to a separate interim statement
+    SeparateFile,
+}
+
+/// Whether an entry is pending
+pub fn is_pending(transaction: &Transaction) -> bool {
+    transaction.status == EntryStatus::Pending
+}
+
+/// Remove the pending entries from a statement, returning them in statement
+/// order
+pub fn take_pending(statement: &mut Statement) -> Vec<Transaction> {
+    let (pending, booked) = std::mem::take(&mut statement.transactions)
+        .into_iter()
+        .partition(is_pending);
+    statement.transactions = booked;
+    pending
+}
+
+/// Interim statement of pending entries taken from a statement
+pub fn interim_statement(statement: &Statement, pending: Vec<Transaction>) -> Statement {
+    let balances = match statement.balance(BalanceType::ClosingBooked) {
+        Some(closing) => {
+            let mut interim = closing.clone();
+            interim.balance_type = Some(BalanceType::InterimBooked);
+            vec![interim]
+        }
+        None => statement.balances.clone(),
+    };
+    Statement {
+        balances,
+        transactions: pending,
+        ..statement.clone()
+    }
+}
@@ -0,0 +1,144 @@
+// Decryption of PGP-encrypted input with the gpg command line tool.
+//
+// Some bank channels deliver statements encrypted. gpg writes the plaintext
+// to a pipe that is read directly, so it never hits the disk.
+
+use crate::process::ChildOutput;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const ARMOR_HEADER: &[u8] = b"-----BEGIN PGP MESSAGE-----";
+
+/// How encrypted input is decrypted
+#[derive(Debug, Clone, Default)]
+pub struct DecryptOptions {
+    /// Secret key file to decrypt with. When not set, gpg uses the keys of
+    /// the current user.
+    pub key: Option<PathBuf>,
+    /// File holding the passphrase of the secret key
+    pub passphrase_file: Option<PathBuf>,
+}
+
+/// Whether a document starts like an OpenPGP message, either ASCII armored
+/// or as a binary packet with an encrypted session key
+pub fn is_encrypted(prefix: &[u8]) -> bool {
+    let tag = match prefix.first() {
+        // New packet format
+        Some(&b) if b & 0xC0 == 0xC0 => b & 0x3F,
+        // Old packet format
+        Some(&b) if b & 0x80 != 0 => (b >> 2) & 0x0F,
+        _ => 0,
+    };
+    // Public key or symmetric key encrypted session key
+    tag == 1 || tag == 3 || prefix.trim_ascii_start().starts_with(ARMOR_HEADER)
+}
+
+/// Start decrypting `input` with gpg.
+///
+/// Reading the returned reader yields the plaintext. If gpg fails, the error
+/// is returned once all output has been read.
+pub fn decrypt(input: fs::File, options: &DecryptOptions) -> io::Result<Decrypted> {
+    let home = match &options.key {
+        Some(key) => Some(TempHome::with_key(key)?),
+        None => None,
+    };
+
+    let mut command = gpg(home.as_ref());
+    command.arg("--decrypt");
+    if let Some(passphrase_file) = &options.passphrase_file {
+        command
+            .args(["--pinentry-mode", "loopback", "--passphrase-file"])
+            .arg(passphrase_file);
+    }
+    command.stdin(input);
+    let output =
+        ChildOutput::spawn(&mut command, "gpg could not decrypt the input").map_err(spawn_error)?;
+
+    Ok(Decrypted {
+        output,
+        _home: home,
+    })
+}
+
+/// Plaintext of an encrypted document, read from gpg
+pub struct Decrypted {
+    output: ChildOutput,
+    // Removed when decryption is done, after gpg was stopped
+    _home: Option<TempHome>,
+}
+
+impl Read for Decrypted {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.output.read(buf)
+    }
+}
+
+fn gpg(home: Option<&TempHome>) -> Command {
+    let mut command = Command::new("gpg");
+    command.args(["--batch", "--quiet", "--no-tty"]);
+    if let Some(home) = home {
+        command.arg("--homedir").arg(&home.path);
+    }
+    command
+}
+
+fn spawn_error(e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("Could not run gpg: {}", e))
+}
+
+// A keyring of its own for a key given on the command line, so the user's
+// keyring is left alone
+struct TempHome {
+    path: PathBuf,
+}
+
+impl TempHome {
+    fn with_key(key: &Path) -> io::Result<TempHome> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "camtconvert-gnupg-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        let builder = {
+            let mut builder = builder;
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            builder
+        };
+        builder.create(&path)?;
+        let home = TempHome { path };
+
+        let output = gpg(Some(&home))
+            .arg("--import")
+            .arg(key)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(spawn_error)?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "gpg could not import the key {}: {}",
+                key.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(home)
+    }
+}
+
+impl Drop for TempHome {
+    fn drop(&mut self) {
+        // Stop the agent gpg started for this keyring before removing it
+        let _ = Command::new("gpgconf")
+            .arg("--homedir")
+            .arg(&self.path)
+            .args(["--kill", "gpg-agent"])
+            .stderr(Stdio::null())
+            .status();
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
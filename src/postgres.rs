@@ -0,0 +1,224 @@
+//! Statements loaded into PostgreSQL with the `psql` client, so that they
+//! can be queried next to other business data.
+//!
+//! Statements, balances and entries go to the tables of [`SCHEMA`], created
+//! if they do not exist. A statement is identified by its account (IBAN) and
+//! electronic sequence number, or its id where it has none, and an entry by
+//! its statement and its position in it, as the references generated for
+//! entries are not unique. Loading a statement again updates the rows it
+//! loaded before instead of adding them twice, deletes those of entries it
+//! no longer has, and replaces its balances. Each statement is loaded in one
+//! transaction.
+
+use crate::model::{DateOrDateTime, Statement};
+use crate::reference::{ReferenceStyle, generate_transaction_reference};
+use crate::sql::{literal, optional};
+use chrono::{DateTime, FixedOffset};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Tables statements are loaded into
+pub const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS camt_statements (
+    account TEXT NOT NULL,
+    sequence_number TEXT NOT NULL,
+    id TEXT NOT NULL,
+    currency TEXT,
+    owner TEXT,
+    servicer_bic TEXT,
+    created_at TIMESTAMPTZ,
+    from_at TIMESTAMPTZ,
+    to_at TIMESTAMPTZ,
+    loaded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (account, sequence_number)
+);
+CREATE TABLE IF NOT EXISTS camt_balances (
+    account TEXT NOT NULL,
+    sequence_number TEXT NOT NULL,
+    balance_type TEXT,
+    balance_date DATE,
+    amount NUMERIC NOT NULL,
+    currency TEXT NOT NULL,
+    FOREIGN KEY (account, sequence_number)
+        REFERENCES camt_statements ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS camt_balances_statement
+    ON camt_balances (account, sequence_number);
+CREATE TABLE IF NOT EXISTS camt_entries (
+    account TEXT NOT NULL,
+    sequence_number TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    reference TEXT NOT NULL,
+    account_servicer_ref TEXT,
+    end_to_end_id TEXT,
+    amount NUMERIC NOT NULL,
+    currency TEXT NOT NULL,
+    status TEXT NOT NULL,
+    booking_date DATE,
+    value_date DATE,
+    bank_transaction_code TEXT,
+    creditor_reference TEXT,
+    debtor TEXT,
+    remittance_info TEXT,
+    additional_info TEXT,
+    category TEXT,
+    gl_account TEXT,
+    payee TEXT,
+    PRIMARY KEY (account, sequence_number, position),
+    FOREIGN KEY (account, sequence_number)
+        REFERENCES camt_statements ON DELETE CASCADE
+);";
+
+/// Sequence number a statement is loaded under: its electronic sequence
+/// number, or its id where it has none
+pub fn sequence_number(statement: &Statement) -> &str {
+    match statement.sequence_number.trim() {
+        "" => statement.id.trim(),
+        number => number,
+    }
+}
+
+/// SQL loading a statement in one transaction, creating the tables if needed
+pub fn load_sql(statement: &Statement, style: ReferenceStyle) -> String {
+    let account = literal(&statement.iban);
+    let sequence = literal(sequence_number(statement));
+    let timestamp = |datetime: Option<DateTime<FixedOffset>>| {
+        datetime.map_or("NULL".to_string(), |datetime| {
+            literal(&datetime.to_rfc3339())
+        })
+    };
+    let date = |date: Option<&DateOrDateTime>| {
+        date.map_or("NULL".to_string(), |date| literal(&date.date().to_string()))
+    };
+
+    let mut sql = format!("BEGIN;\n{}\n", SCHEMA);
+    let _ = writeln!(
+        sql,
+        "INSERT INTO camt_statements \
+         (account, sequence_number, id, currency, owner, servicer_bic, created_at, from_at, to_at) \
+         VALUES ({}, {}, {}, {}, {}, {}, {}, {}, {}) \
+         ON CONFLICT (account, sequence_number) DO UPDATE SET \
+         id = EXCLUDED.id, currency = EXCLUDED.currency, owner = EXCLUDED.owner, \
+         servicer_bic = EXCLUDED.servicer_bic, created_at = EXCLUDED.created_at, \
+         from_at = EXCLUDED.from_at, to_at = EXCLUDED.to_at, loaded_at = now();",
+        account,
+        sequence,
+        literal(&statement.id),
+        optional(&statement.currency),
+        optional(&statement.owner_name),
+        optional(&statement.servicer_bic),
+        timestamp(statement.creation_datetime),
+        timestamp(statement.from_datetime),
+        timestamp(statement.to_datetime),
+    );
+
+    let _ = writeln!(
+        sql,
+        "DELETE FROM camt_balances WHERE account = {} AND sequence_number = {};",
+        account, sequence
+    );
+    for balance in &statement.balances {
+        let _ = writeln!(
+            sql,
+            "INSERT INTO camt_balances VALUES ({}, {}, {}, {}, {}, {});",
+            account,
+            sequence,
+            balance
+                .balance_type
+                .map_or("NULL".to_string(), |balance_type| literal(
+                    balance_type.as_str()
+                )),
+            date(balance.date.as_ref()),
+            balance.signed_amount(),
+            literal(&balance.amount.currency),
+        );
+    }
+
+    for (index, transaction) in statement.transactions.iter().enumerate() {
+        let _ = writeln!(
+            sql,
+            "INSERT INTO camt_entries VALUES \
+             ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}) \
+             ON CONFLICT (account, sequence_number, position) DO UPDATE SET \
+             reference = EXCLUDED.reference, account_servicer_ref = EXCLUDED.account_servicer_ref, \
+             end_to_end_id = EXCLUDED.end_to_end_id, amount = EXCLUDED.amount, \
+             currency = EXCLUDED.currency, status = EXCLUDED.status, \
+             booking_date = EXCLUDED.booking_date, value_date = EXCLUDED.value_date, \
+             bank_transaction_code = EXCLUDED.bank_transaction_code, \
+             creditor_reference = EXCLUDED.creditor_reference, debtor = EXCLUDED.debtor, \
+             remittance_info = EXCLUDED.remittance_info, \
+             additional_info = EXCLUDED.additional_info, category = EXCLUDED.category, \
+             gl_account = EXCLUDED.gl_account, payee = EXCLUDED.payee;",
+            account,
+            sequence,
+            index + 1,
+            literal(&generate_transaction_reference(
+                statement,
+                transaction,
+                style
+            )),
+            optional(&transaction.account_servicer_ref),
+            optional(&transaction.end_to_end_id),
+            transaction.signed_amount(),
+            literal(&transaction.amount.currency),
+            literal(transaction.status.as_str()),
+            date(transaction.booking_date.as_ref()),
+            date(transaction.value_date.as_ref()),
+            optional(&transaction.bank_tx_code),
+            transaction
+                .creditor_reference
+                .as_ref()
+                .map_or("NULL".to_string(), |reference| literal(
+                    &reference.reference
+                )),
+            optional(&transaction.debtor_name),
+            optional(&transaction.remittance_info.join("\n")),
+            optional(&transaction.additional_info),
+            optional(&transaction.category),
+            optional(&transaction.gl_account),
+            optional(transaction.payee()),
+        );
+    }
+    // Entries of an earlier load the statement no longer has
+    let _ = writeln!(
+        sql,
+        "DELETE FROM camt_entries WHERE account = {} AND sequence_number = {} AND position > {};",
+        account,
+        sequence,
+        statement.transactions.len()
+    );
+    sql.push_str("COMMIT;\n");
+    sql
+}
+
+/// Load a statement into the database of a connection string or URI, as
+/// taken by `psql`
+pub fn load(connection: &str, statement: &Statement, style: ReferenceStyle) -> io::Result<()> {
+    let mut child = Command::new("psql")
+        .args([
+            "--no-psqlrc",
+            "--quiet",
+            "--set",
+            "ON_ERROR_STOP=1",
+            "--dbname",
+        ])
+        .arg(connection)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not run psql: {}", e)))?;
+    // Closed at the end of the block, so the input ends
+    {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(load_sql(statement, style).as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "psql failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
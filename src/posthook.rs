@@ -0,0 +1,187 @@
+//! Command run after each converted output, such as
+//! `import-statement --account {iban} {output}`, to hand outputs on to an
+//! importer or upload script.
+//!
+//! The command is split into words like a shell does, with single and double
+//! quotes, but it is not run by a shell: placeholders are replaced within
+//! their word, so a value with spaces or quotes stays one argument and
+//! cannot run other commands. Commands are checked when they are parsed, so
+//! that an unknown placeholder is reported before any input is read.
+
+use std::fmt;
+use std::io;
+use std::process::Command;
+use std::str::FromStr;
+
+/// Placeholders that can be used in a command
+pub const PLACEHOLDERS: &[&str] = &[
+    "output",
+    "input",
+    "iban",
+    "currency",
+    "id",
+    "from_date",
+    "to_date",
+    "version",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Placeholder(&'static str),
+}
+
+/// A command run after each converted output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostHook {
+    command: String,
+    words: Vec<Vec<Part>>,
+}
+
+impl FromStr for PostHook {
+    type Err = String;
+
+    fn from_str(command: &str) -> Result<Self, Self::Err> {
+        let words = split_words(command)?
+            .iter()
+            .map(|word| parse_word(word))
+            .collect::<Result<Vec<_>, _>>()?;
+        if words.is_empty() {
+            return Err("Post-hook command is empty".to_string());
+        }
+        Ok(PostHook {
+            command: command.to_string(),
+            words,
+        })
+    }
+}
+
+impl fmt::Display for PostHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.command)
+    }
+}
+
+impl PostHook {
+    /// Program and arguments with the placeholders replaced by `values`,
+    /// given by placeholder name. Placeholders without a value are replaced
+    /// by nothing.
+    pub fn arguments(&self, values: &[(&str, String)]) -> Vec<String> {
+        self.words
+            .iter()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        Part::Text(text) => text.as_str(),
+                        Part::Placeholder(name) => values
+                            .iter()
+                            .find(|(value_name, _)| value_name == name)
+                            .map_or("", |(_, value)| value.as_str()),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Run the command and wait for it, failing if it does not succeed. Its
+    /// output goes to the standard output and error of the converter.
+    pub fn run(&self, values: &[(&str, String)]) -> io::Result<()> {
+        let arguments = self.arguments(values);
+        let status = Command::new(&arguments[0])
+            .args(&arguments[1..])
+            .status()
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Could not run post-hook {}: {}", arguments[0], e),
+                )
+            })?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "Post-hook {} failed with {}",
+                arguments[0], status
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Words of a command: separated by whitespace, with text in single quotes
+// taken as it is and in double quotes with \" and \\ for quotes and
+// backslashes. Backslashes elsewhere are kept, for Windows paths.
+fn split_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(unclosed_quote(command)),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(unclosed_quote(command)),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(unclosed_quote(command)),
+                    }
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn unclosed_quote(command: &str) -> String {
+    format!("Unclosed quote in post-hook command: {}", command)
+}
+
+fn parse_word(word: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut rest = word;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(Part::Text(rest[..start].to_string()));
+        }
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in post-hook command: {}", word))?;
+        let name = &rest[start + 1..start + end];
+        let placeholder = PLACEHOLDERS
+            .iter()
+            .find(|placeholder| **placeholder == name)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown placeholder {{{}}} in post-hook command, expected one of: {}",
+                    name,
+                    PLACEHOLDERS.join(", ")
+                )
+            })?;
+        parts.push(Part::Placeholder(placeholder));
+        rest = &rest[start + end + 1..];
+    }
+    if !rest.is_empty() || parts.is_empty() {
+        parts.push(Part::Text(rest.to_string()));
+    }
+    Ok(parts)
+}
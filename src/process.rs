@@ -0,0 +1,74 @@
+//! Output of a command line tool, read while the tool runs.
+
+use std::io::{self, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::thread::JoinHandle;
+
+/// Standard output of a running command. If the command fails, reading
+/// fails once all output has been read.
+pub(crate) struct ChildOutput {
+    child: Child,
+    stdout: ChildStdout,
+    messages: Option<JoinHandle<String>>,
+    // What went wrong if the command fails, e.g. "gpg could not decrypt the input"
+    failure: String,
+}
+
+impl ChildOutput {
+    /// Start `command` with its standard output and error piped
+    pub(crate) fn spawn(command: &mut Command, failure: impl Into<String>) -> io::Result<Self> {
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        // Collect messages while the output is read, so a chatty command
+        // cannot block on a full pipe
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let messages = std::thread::spawn(move || {
+            let mut messages = String::new();
+            let _ = stderr.read_to_string(&mut messages);
+            messages
+        });
+
+        Ok(ChildOutput {
+            child,
+            stdout,
+            messages: Some(messages),
+            failure: failure.into(),
+        })
+    }
+}
+
+impl Read for ChildOutput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.stdout.read(buf)?;
+        // At the end of the output, check whether the command succeeded
+        if n == 0
+            && !buf.is_empty()
+            && let Some(messages) = self.messages.take()
+        {
+            let status = self.child.wait()?;
+            let messages = messages.join().unwrap_or_default();
+            if !status.success() {
+                return Err(io::Error::other(format!(
+                    "{}: {}",
+                    self.failure,
+                    messages.trim()
+                )));
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl Drop for ChildOutput {
+    fn drop(&mut self) {
+        // Stop the command if its output was not read to the end
+        if self.messages.is_some() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
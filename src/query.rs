@@ -0,0 +1,133 @@
+//! Conversion options given as URL query parameters, named like the command
+//! line options: `version=08&reference=uuid5&date-format=input&strict`.
+//!
+//! Flags are set when given without a value or as `true`, and cleared with
+//! `false`.
+
+use crate::bic::validate_bic;
+use crate::charset::Charset;
+use crate::matrix::WRITERS;
+use crate::reader::{ParseOptions, SupplementaryData};
+use crate::reference::ReferenceStyle;
+use crate::writer::{DateFormat, Indent, OutputVersion, WriteOptions};
+use clap::ValueEnum;
+
+/// Override `parse` and `write` options with the parameters of a query such
+/// as `version=08&reference=uuid5&strict`, without the leading `?`.
+///
+/// Fails with a message naming the first unknown or invalid parameter.
+pub fn apply_query(
+    query: &str,
+    parse: &mut ParseOptions,
+    write: &mut WriteOptions,
+) -> Result<(), String> {
+    let mut date_format = None;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let name = percent_decode(name)?;
+        let value = percent_decode(value)?;
+        let invalid = || format!("Invalid value '{}' of parameter {}", value, name);
+        match name.as_str() {
+            "version" => {
+                write.version = OutputVersion::from_str(&value, false).map_err(|_| {
+                    let versions: Vec<&str> = WRITERS.iter().map(|writer| writer.version).collect();
+                    format!(
+                        "Unsupported target version '{}', expected one of: {}",
+                        value,
+                        versions.join(", ")
+                    )
+                })?;
+            }
+            "reference" => {
+                write.reference = ReferenceStyle::from_str(&value, false).map_err(|_| invalid())?;
+            }
+            "date-format" => {
+                date_format = Some(DateFormat::from_str(&value, false).map_err(|_| invalid())?);
+            }
+            "timezone" => {
+                let timezone = value.parse().map_err(|_| invalid())?;
+                parse.timezone = timezone;
+                write.timezone = parse.timezone.clone();
+            }
+            "servicer-bic" => {
+                validate_bic(&value).map_err(|e| format!("{}: {}", invalid(), e))?;
+                write.servicer_bic = Some(value);
+            }
+            "recipient-bic" => {
+                validate_bic(&value).map_err(|e| format!("{}: {}", invalid(), e))?;
+                write.recipient_bic = Some(value);
+            }
+            "fidelity" => {
+                write.fidelity = flag(&value).ok_or_else(invalid)?;
+                // As on the command line, fidelity keeps the input form of
+                // dates unless a format is given
+                if write.fidelity {
+                    write.date_format = DateFormat::Input;
+                }
+            }
+            "lenient" => parse.lenient = flag(&value).ok_or_else(invalid)?,
+            "strict" => {
+                let strict = flag(&value).ok_or_else(invalid)?;
+                parse.strict = strict;
+                write.strict = strict;
+            }
+            "keep-unknown" => parse.keep_unknown = flag(&value).ok_or_else(invalid)?,
+            "supplementary-data" => {
+                let policy = SupplementaryData::from_str(&value, false).map_err(|_| invalid())?;
+                parse.supplementary_data = Some(policy);
+            }
+            "strip-control-chars" => {
+                parse.strip_control_chars = flag(&value).ok_or_else(invalid)?;
+            }
+            "esr-references" => write.esr_references = flag(&value).ok_or_else(invalid)?,
+            "charset" => {
+                write.charset = Charset::from_str(&value, false).map_err(|_| invalid())?;
+            }
+            "msg-id" => {
+                write.message_id = value.parse().map_err(|e| format!("{}: {}", invalid(), e))?
+            }
+            "compact" => {
+                if flag(&value).ok_or_else(invalid)? {
+                    write.indent = Indent::Compact;
+                }
+            }
+            "indent" => {
+                write.indent = Indent::Spaces(value.parse().map_err(|_| invalid())?);
+            }
+            _ => return Err(format!("Unknown parameter {}", name)),
+        }
+    }
+    if let Some(date_format) = date_format {
+        write.date_format = date_format;
+    }
+    Ok(())
+}
+
+// A parameter without value, such as `?strict`, is set
+fn flag(value: &str) -> Option<bool> {
+    match value {
+        "" | "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn percent_decode(text: &str) -> Result<String, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.bytes();
+    while let Some(byte) = rest.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [rest.next(), rest.next()];
+                let digit = |byte: Option<u8>| (byte? as char).to_digit(16);
+                match (digit(hex[0]), digit(hex[1])) {
+                    (Some(high), Some(low)) => bytes.push((high * 16 + low) as u8),
+                    _ => return Err(format!("Invalid percent encoding in query: {}", text)),
+                }
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("Query is not UTF-8: {}", text))
+}
@@ -0,0 +1,1108 @@
+use crate::bic::validate_bic;
+use crate::decimal::Decimal;
+use crate::encoding::{InputEncoding, Transcoder, detect_encoding};
+use crate::error::{ConvertError, Location, Result, Warning};
+use crate::gzip::{GzDecoder, is_gzip};
+use crate::iban::validate_iban;
+use crate::model::{
+    Amount, AmountDetail, AmountDetails, Balance, BalanceType, CreditDebit, DateOrDateTime,
+    EntryStatus, Statement, Transaction, XmlElement, XmlNode,
+};
+use crate::passthrough::is_passed_through;
+use crate::timezone::TimeZone;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::{LocalName, ResolveResult};
+use quick_xml::reader::NsReader;
+use std::collections::BTreeMap;
+use std::io::{BufRead, Read};
+
+const CAMT_10_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:camt.053.001.10";
+
+/// Options controlling how input documents are read
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Skip entries with invalid or missing data instead of failing, recording
+    /// a warning for each skipped entry
+    pub lenient: bool,
+    /// Character encoding of the input. When not set, the encoding named in
+    /// the XML declaration is used, defaulting to UTF-8.
+    pub encoding: Option<InputEncoding>,
+    /// Keep elements the converter does not model, such as `SplmtryData`,
+    /// so they can be copied to the output
+    pub keep_unknown: bool,
+    /// What happens to supplementary data (`SplmtryData`). When not set it
+    /// is treated like other elements not modelled.
+    pub supplementary_data: Option<SupplementaryData>,
+    /// Time zone of timestamps without a UTC offset
+    pub timezone: TimeZone,
+    /// Fail on values that fail a check, such as invalid IBANs, instead of
+    /// recording a warning
+    pub strict: bool,
+    /// Replace tabs, line breaks and other control characters in text by
+    /// spaces. Characters not allowed in XML are always removed.
+    pub strip_control_chars: bool,
+    pub limits: Limits,
+}
+
+/// What happens to supplementary data (`SplmtryData`), the blocks in which
+/// banks deliver proprietary content
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SupplementaryData {
+    /// Leave it out of the output
+    Drop,
+    /// Copy it verbatim to the output, that of the message and of the
+    /// transaction details
+    Keep,
+    /// Reject inputs that have any
+    Error,
+}
+
+/// Caps on the structure of input documents, which may come from external
+/// parties. Documents exceeding them are rejected.
+///
+/// DOCTYPE declarations are always rejected, so documents cannot define
+/// entities and no entity expansion takes place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Deepest nesting of elements
+    pub max_depth: usize,
+    /// Number of elements in the document
+    pub max_elements: u64,
+    /// Length of the text of one element, in bytes
+    pub max_text_length: usize,
+}
+
+impl Default for Limits {
+    // Far above what statements need, including statements with millions of
+    // entries and supplementary data
+    fn default() -> Self {
+        Limits {
+            max_depth: 64,
+            max_elements: 100_000_000,
+            max_text_length: 1024 * 1024,
+        }
+    }
+}
+
+/// A parsed statement together with the warnings raised while reading it
+#[derive(Debug)]
+pub struct Parsed {
+    pub statement: Statement,
+    pub warnings: Vec<Warning>,
+    /// Paths of elements whose text is not converted, with how often they
+    /// occur. Elements kept with `keep_unknown` are not included.
+    pub dropped: BTreeMap<String, usize>,
+}
+
+/// Receives a statement piece by piece while it is read, see [`stream_camt_10`]
+pub trait StatementHandler {
+    /// Called once the statement header, account and balances have been read,
+    /// right before the first entry or at the end of a statement without any
+    fn begin(&mut self, statement: &Statement) -> Result<()>;
+
+    /// Called for each entry, in input order
+    fn entry(&mut self, statement: &Statement, transaction: Transaction) -> Result<()>;
+
+    /// Called at the end of the document with everything but the entries
+    fn end(&mut self, statement: &Statement) -> Result<()>;
+}
+
+// Keeps all entries, for parse_camt_10
+struct CollectEntries(Vec<Transaction>);
+
+impl StatementHandler for CollectEntries {
+    fn begin(&mut self, _statement: &Statement) -> Result<()> {
+        Ok(())
+    }
+
+    fn entry(&mut self, _statement: &Statement, transaction: Transaction) -> Result<()> {
+        self.0.push(transaction);
+        Ok(())
+    }
+
+    fn end(&mut self, _statement: &Statement) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Parse a camt.053.001.10 document into a [`Statement`].
+///
+/// Errors found in the document carry the line, column and element path
+/// where they occurred, see [`ConvertError::location`].
+pub fn parse_camt_10<R: BufRead>(input: R, options: &ParseOptions) -> Result<Parsed> {
+    let mut entries = CollectEntries(Vec::new());
+    let mut parsed = stream_camt_10(input, options, &mut entries)?;
+    parsed.statement.transactions = entries.0;
+    Ok(parsed)
+}
+
+/// Parse a camt.053.001.10 document, passing each entry to `handler` as soon
+/// as it has been read instead of keeping it.
+///
+/// Memory use does not depend on the number of entries. The returned
+/// statement has no transactions.
+///
+/// Gzip-compressed input is recognised by its magic bytes and decompressed
+/// while it is read.
+pub fn stream_camt_10<R: BufRead>(
+    mut input: R,
+    options: &ParseOptions,
+    handler: &mut dyn StatementHandler,
+) -> Result<Parsed> {
+    if is_gzip(input.fill_buf()?) {
+        crate::debug!("Decompressing gzip input");
+        return read_document(GzDecoder::new(input), options, handler);
+    }
+    read_document(input, options, handler)
+}
+
+fn read_document<R: BufRead>(
+    mut input: R,
+    options: &ParseOptions,
+    handler: &mut dyn StatementHandler,
+) -> Result<Parsed> {
+    let encoding = match options.encoding {
+        Some(encoding) => encoding,
+        None => detect_encoding(input.fill_buf()?)?,
+    };
+    crate::debug!("Reading input as {}", encoding.as_str());
+
+    match encoding {
+        InputEncoding::Utf8 => parse_document(input, options, handler),
+        encoding => parse_document(Transcoder::new(input, encoding), options, handler),
+    }
+}
+
+fn parse_document<R: BufRead>(
+    input: R,
+    options: &ParseOptions,
+    handler: &mut dyn StatementHandler,
+) -> Result<Parsed> {
+    let mut reader = NsReader::from_reader(LineTracker::new(input));
+    // Text is trimmed per element rather than per event, so that whitespace
+    // next to CDATA sections and entity references is kept
+    reader.config_mut().trim_text(false);
+
+    let mut buf = Vec::new();
+    let mut state = ParseState {
+        keep_unknown: options.keep_unknown,
+        supplementary_data: options.supplementary_data,
+        timezone: options.timezone.clone(),
+        strict: options.strict,
+        strip_control_chars: options.strip_control_chars,
+        ..ParseState::default()
+    };
+    let mut warnings = Vec::new();
+    let mut begun = false;
+    let mut elements = 0;
+
+    loop {
+        let event_start = reader.buffer_position();
+        let (name, event) = match reader.read_resolved_event_into(&mut buf) {
+            Ok((resolved, event)) => {
+                let name = match &event {
+                    Event::Start(e) | Event::Empty(e) => state
+                        .element_name(resolved, e.local_name())
+                        .and_then(|name| {
+                            if options.keep_unknown
+                                || options.supplementary_data == Some(SupplementaryData::Keep)
+                            {
+                                state.element_attributes = copy_attributes(&reader, e)?;
+                            }
+                            Ok(name)
+                        }),
+                    Event::End(e) => state.element_name(resolved, e.local_name()),
+                    _ => Ok(String::new()),
+                };
+                (name, event)
+            }
+            Err(e) => {
+                let position = reader.error_position();
+                return Err(state.locate(e.into(), reader.get_ref(), position));
+            }
+        };
+
+        let event_start = match &event {
+            // Point at the '<' of the tag rather than the whitespace before it
+            Event::Start(e) => reader.buffer_position() - e.len() as u64 - 2,
+            Event::Empty(e) => reader.buffer_position() - e.len() as u64 - 3,
+            // Invalid values are reported where their text starts, missing
+            // fields at the start tag of their element
+            Event::End(_) if state.has_text() => state.text_start,
+            Event::End(_) => state.element_start,
+            _ => event_start,
+        };
+
+        if let Err(e) = check_limits(&event, &state, &mut elements, &options.limits) {
+            return Err(state.locate(e, reader.get_ref(), event_start));
+        }
+
+        let at_end_tag = matches!(event, Event::End(_));
+        let result = name.and_then(|name| state.handle_event(event, &name, event_start));
+        match result {
+            Ok(true) => {
+                if !begun {
+                    handler.begin(&state.statement)?;
+                }
+                handler.end(&state.statement)?;
+                break;
+            }
+            Ok(false) => {}
+            Err(e) if options.lenient && e.is_recoverable() && state.in_transaction => {
+                let e = state.locate(e, reader.get_ref(), event_start);
+                warnings.push(Warning {
+                    message: format!("Skipped entry: {}", e.kind()),
+                    location: e.location().cloned(),
+                });
+                state.skip_entry(at_end_tag);
+            }
+            Err(e) => return Err(state.locate(e, reader.get_ref(), event_start)),
+        }
+
+        for (message, offset, path) in state.warnings.drain(..) {
+            let (line, column) = reader.get_ref().line_and_column(offset);
+            warnings.push(Warning {
+                message,
+                location: Some(Location { line, column, path }),
+            });
+        }
+
+        // Errors are never reported before the current Stmt, Bal or Ntry
+        reader
+            .get_mut()
+            .forget_before(state.element_start.min(event_start));
+
+        // Pass on finished entries
+        if state.in_transaction && !begun {
+            handler.begin(&state.statement)?;
+            begun = true;
+        }
+        for transaction in std::mem::take(&mut state.statement.transactions) {
+            crate::trace!(
+                "Entry of {} {} booked {}",
+                transaction.amount.value,
+                transaction.amount.currency,
+                transaction
+                    .booking_date
+                    .map_or_else(|| "-".to_string(), |date| date.to_string())
+            );
+            handler.entry(&state.statement, transaction)?;
+        }
+
+        buf.clear();
+    }
+
+    Ok(Parsed {
+        statement: state.statement,
+        warnings,
+        dropped: state.dropped,
+    })
+}
+
+fn check_limits(
+    event: &Event,
+    state: &ParseState,
+    elements: &mut u64,
+    limits: &Limits,
+) -> Result<()> {
+    let rejected = |reason: String| Err(ConvertError::Rejected(reason));
+    match event {
+        Event::DocType(_) => rejected("DOCTYPE declarations are not allowed".to_string()),
+        Event::Start(_) | Event::Empty(_) => {
+            *elements += 1;
+            if *elements > limits.max_elements {
+                return rejected(format!("more than {} elements", limits.max_elements));
+            }
+            if state.current_path.len() >= limits.max_depth {
+                return rejected(format!(
+                    "elements nested more than {} deep",
+                    limits.max_depth
+                ));
+            }
+            Ok(())
+        }
+        Event::Text(text) if state.text.len() + text.len() > limits.max_text_length => {
+            rejected(format!("text longer than {} bytes", limits.max_text_length))
+        }
+        Event::CData(text) if state.text.len() + text.len() > limits.max_text_length => {
+            rejected(format!("text longer than {} bytes", limits.max_text_length))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Default)]
+struct ParseState {
+    statement: Statement,
+    current_balance: Balance,
+    current_transaction: Transaction,
+
+    // Namespace of the root element. Elements from other namespaces are
+    // recorded in the path as {namespace}name, so they never match a field.
+    namespace: String,
+    current_path: Vec<String>,
+    current_currency: String,
+    in_balance: bool,
+    in_transaction: bool,
+    in_charges: bool,
+    skipping_entry: bool,
+    // Time zone of timestamps without offset, and whether failed checks of
+    // values are errors
+    timezone: TimeZone,
+    strict: bool,
+    strip_control_chars: bool,
+
+    // Unmodelled elements being copied, innermost last, the container the
+    // outermost of them belongs to, and the attributes of the current element
+    keep_unknown: bool,
+    supplementary_data: Option<SupplementaryData>,
+    unknown_elements: Vec<XmlElement>,
+    unknown_container: String,
+    element_attributes: Vec<(String, String)>,
+
+    // Byte offset of the innermost open Stmt, Bal or Ntry element
+    element_start: u64,
+
+    // Text of the current element, which may be split over several text and
+    // CDATA events, and the byte offset where it starts
+    text: String,
+    text_start: u64,
+
+    // Warnings about values that were read, with the byte offset and path of
+    // the value, until they are located
+    warnings: Vec<(String, u64, String)>,
+
+    // Paths of text not read into a field, with their count
+    dropped: BTreeMap<String, usize>,
+}
+
+impl ParseState {
+    // Name of an element as used in paths: the local name for elements in the
+    // document namespace, independent of the prefix used in the input
+    fn element_name(&mut self, resolved: ResolveResult, local_name: LocalName) -> Result<String> {
+        let local_name = std::str::from_utf8(local_name.as_ref())?;
+        match resolved {
+            // Documents without any namespace are read as camt
+            ResolveResult::Unbound => Ok(local_name.to_string()),
+            ResolveResult::Bound(namespace) => {
+                let namespace = std::str::from_utf8(namespace.as_ref())?;
+                if self.current_path.is_empty() {
+                    check_namespace(namespace)?;
+                    self.namespace = namespace.to_string();
+                }
+                if namespace == self.namespace {
+                    Ok(local_name.to_string())
+                } else {
+                    Ok(format!("{{{}}}{}", namespace, local_name))
+                }
+            }
+            ResolveResult::Unknown(prefix) => Err(ConvertError::XmlSyntax(format!(
+                "Undeclared namespace prefix '{}'",
+                String::from_utf8_lossy(&prefix)
+            ))),
+        }
+    }
+
+    // Returns true at the end of the document
+    fn handle_event(&mut self, event: Event, name: &str, event_start: u64) -> Result<bool> {
+        if self.skipping_entry {
+            return self.handle_skipped_event(event, name);
+        }
+
+        match event {
+            Event::Start(ref e) => {
+                self.take_text()?;
+                self.open_unknown(name)?;
+                self.current_path.push(name.to_string());
+                self.record_layout();
+
+                // Amount elements carry their currency as an attribute
+                self.current_currency.clear();
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    if attr.key.local_name().as_ref() == b"Ccy" {
+                        self.current_currency = std::str::from_utf8(&attr.value)?.to_string();
+                    }
+                }
+
+                match name {
+                    "Stmt" => {
+                        self.element_start = event_start;
+                    }
+                    "Bal" => {
+                        self.in_balance = true;
+                        self.current_balance = Balance::default();
+                        self.element_start = event_start;
+                    }
+                    "Ntry" => {
+                        self.in_transaction = true;
+                        self.current_transaction = Transaction::default();
+                        self.element_start = event_start;
+                    }
+                    "Chrgs" => {
+                        self.in_charges = true;
+                    }
+                    _ => {}
+                }
+            }
+            Event::Empty(_) => {
+                self.take_text()?;
+                self.open_unknown(name)?;
+                self.close_unknown();
+            }
+            Event::Text(ref e) => {
+                self.append_text(&e.unescape()?, event_start);
+            }
+            Event::CData(ref e) => {
+                // CDATA content is taken literally, without unescaping
+                self.append_text(std::str::from_utf8(e)?, event_start);
+            }
+            Event::End(_) => {
+                let copied = !self.unknown_elements.is_empty();
+                let text = self.take_text()?;
+                self.close_unknown();
+                if !text.is_empty() && !self.handle_text(text)? && !copied {
+                    *self.dropped.entry(self.current_path.join("/")).or_default() += 1;
+                }
+
+                match name {
+                    "Stmt" if self.statement.creation_datetime.is_none() => {
+                        return Err(missing("CreDtTm"));
+                    }
+                    "Bal" => {
+                        let balance = &self.current_balance;
+                        if balance.balance_type.is_none() {
+                            return Err(missing("Tp"));
+                        } else if balance.credit_debit_ind.is_none() {
+                            return Err(missing("CdtDbtInd"));
+                        } else if balance.date.is_none() {
+                            return Err(missing("Dt"));
+                        }
+                        self.in_balance = false;
+                        self.statement.balances.push(self.current_balance.clone());
+                    }
+                    "Ntry" => {
+                        if self.current_transaction.credit_debit_ind.is_none() {
+                            return Err(missing("CdtDbtInd"));
+                        }
+                        self.in_transaction = false;
+                        self.in_charges = false;
+                        self.statement
+                            .transactions
+                            .push(self.current_transaction.clone());
+                    }
+                    "Chrgs" => {
+                        self.in_charges = false;
+                    }
+                    _ => {}
+                }
+
+                self.current_path.pop();
+            }
+            Event::Eof => return Ok(true),
+            _ => {}
+        }
+
+        Ok(false)
+    }
+
+    // Consume the remaining events of a skipped entry, keeping the path in sync
+    fn handle_skipped_event(&mut self, event: Event, name: &str) -> Result<bool> {
+        match event {
+            Event::Start(_) => {
+                self.current_path.push(name.to_string());
+            }
+            Event::End(_) => {
+                if name == "Ntry" {
+                    self.skip_entry(true);
+                } else {
+                    self.current_path.pop();
+                }
+            }
+            Event::Eof => return Ok(true),
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    // Drop the current entry. If the error was raised by the end tag of the
+    // entry it is finished right away, otherwise the rest of it is skipped
+    // first.
+    fn skip_entry(&mut self, at_end_tag: bool) {
+        self.unknown_elements.clear();
+        self.text.clear();
+
+        // An end tag that raised an error has not closed its element yet
+        let closed = if at_end_tag {
+            self.current_path.pop()
+        } else {
+            None
+        };
+        if closed.as_deref() == Some("Ntry") {
+            self.skipping_entry = false;
+            self.in_transaction = false;
+            self.in_charges = false;
+        } else {
+            self.skipping_entry = true;
+        }
+    }
+
+    fn has_text(&self) -> bool {
+        !self.text.trim().is_empty()
+    }
+
+    fn append_text(&mut self, text: &str, event_start: u64) {
+        if !self.has_text() {
+            self.text.clear();
+            self.text_start = event_start;
+        }
+        self.text.push_str(text);
+    }
+
+    // Text read since the last tag with surrounding whitespace and characters
+    // that cannot be written removed. It is also added to the element being
+    // copied, if any.
+    fn take_text(&mut self) -> Result<String> {
+        let text = std::mem::take(&mut self.text);
+        let (text, removed, replaced) = sanitize(text.trim(), self.strip_control_chars);
+        let messages = [
+            (removed, "Removed characters not allowed in XML"),
+            (replaced, "Replaced control characters by spaces"),
+        ];
+        for (characters, message) in messages {
+            if characters.is_empty() {
+                continue;
+            }
+            let path = self.current_path.join("/");
+            let message = format!("{}: {}", message, characters.join(", "));
+            if self.strict {
+                return Err(ConvertError::FailedCheck { path, message });
+            }
+            self.warnings.push((message, self.text_start, path));
+        }
+        if !text.is_empty()
+            && let Some(element) = self.unknown_elements.last_mut()
+        {
+            element.children.push(XmlNode::Text(text.clone()));
+        }
+        Ok(text)
+    }
+
+    // Note the path of a just opened element relative to its Stmt, Bal or Ntry
+    fn record_layout(&mut self) {
+        let len = self.current_path.len();
+        for depth in 1..=2.min(len - 1) {
+            let layout = match self.current_path[len - 1 - depth].as_str() {
+                "Stmt" => &mut self.statement.layout,
+                "Bal" if self.in_balance => &mut self.current_balance.layout,
+                "Ntry" if self.in_transaction => &mut self.current_transaction.layout,
+                _ => continue,
+            };
+            let path = self.current_path[len - depth..].join("/");
+            if !layout.contains(&path) {
+                layout.push(path);
+            }
+        }
+    }
+
+    // Start copying an element if it is not modelled by the converter, or is
+    // part of an element being copied
+    fn open_unknown(&mut self, name: &str) -> Result<()> {
+        if self.unknown_elements.is_empty() {
+            let container = self.current_path.last().map_or("", String::as_str);
+            // The message recipient is copied to the output unless an option
+            // replaces it
+            let recipient = container == "GrpHdr" && name == "MsgRcpt";
+            let supplementary = match self.supplementary_data {
+                Some(policy) if name == "SplmtryData" => Some(policy),
+                _ => None,
+            };
+            match supplementary {
+                Some(SupplementaryData::Error) => {
+                    return Err(ConvertError::Rejected(
+                        "supplementary data (SplmtryData) is not accepted".to_string(),
+                    ));
+                }
+                Some(SupplementaryData::Drop) => return Ok(()),
+                Some(SupplementaryData::Keep)
+                    if matches!(container, "BkToCstmrStmt" | "TxDtls") => {}
+                _ if !recipient && (!self.keep_unknown || !is_passed_through(container, name)) => {
+                    return Ok(());
+                }
+                _ => {}
+            }
+            self.unknown_container = container.to_string();
+        }
+
+        // Foreign elements are named {namespace}name, see element_name
+        let (namespace, name) = match name.strip_prefix('{').and_then(|n| n.split_once('}')) {
+            Some((namespace, name)) => (Some(namespace.to_string()), name.to_string()),
+            None => (None, name.to_string()),
+        };
+
+        self.unknown_elements.push(XmlElement {
+            name,
+            namespace,
+            attributes: std::mem::take(&mut self.element_attributes),
+            children: Vec::new(),
+        });
+        Ok(())
+    }
+
+    // Finish the innermost element being copied, if any
+    fn close_unknown(&mut self) {
+        let Some(element) = self.unknown_elements.pop() else {
+            return;
+        };
+        if let Some(parent) = self.unknown_elements.last_mut() {
+            parent.children.push(XmlNode::Element(element));
+            return;
+        }
+
+        match self.unknown_container.as_str() {
+            "GrpHdr" => self.statement.message_recipient = Some(element),
+            "BkToCstmrStmt" => self.statement.message_extra_elements.push(element),
+            "Stmt" => self.statement.extra_elements.push(element),
+            "Bal" => self.current_balance.extra_elements.push(element),
+            "Ntry" => self.current_transaction.extra_elements.push(element),
+            "TxDtls" => self.current_transaction.supplementary_data.push(element),
+            _ => {}
+        }
+    }
+
+    // Returns whether the text is read into a field
+    fn handle_text(&mut self, text: String) -> Result<bool> {
+        let path = self.current_path.join("/");
+        let statement = &mut self.statement;
+        // Problem found by checking a value, reported once it is read
+        let mut problem = None;
+        let mut read = false;
+        let mut field = |suffix: &str| {
+            let matches = path.ends_with(suffix);
+            read |= matches;
+            matches
+        };
+
+        // Parse statement header information
+        if field("Stmt/Id") {
+            statement.id = text.clone();
+        } else if field("GrpHdr/MsgId") {
+            statement.message_id = text.clone();
+        } else if field("GrpHdr/AddtlInf") {
+            statement.message_info = text.clone();
+        } else if field("Stmt/ElctrncSeqNb") {
+            statement.sequence_number = text.clone();
+        } else if field("Stmt/CreDtTm") {
+            statement.creation_datetime = Some(parse_datetime(&text, &path, &self.timezone)?);
+        } else if field("FrToDt/FrDtTm") {
+            statement.from_datetime = Some(parse_datetime(&text, &path, &self.timezone)?);
+        } else if field("FrToDt/ToDtTm") {
+            statement.to_datetime = Some(parse_datetime(&text, &path, &self.timezone)?);
+        } else if field("Acct/Id/IBAN") {
+            problem = validate_iban(&text)
+                .err()
+                .map(|e| format!("Invalid IBAN {}: {}", text, e));
+            statement.iban = text.clone();
+        } else if field("Acct/Ccy") {
+            statement.currency = text.clone();
+        } else if field("Acct/Ownr/Nm") {
+            statement.owner_name = text.clone();
+        } else if field("Acct/Svcr/FinInstnId/BICFI") {
+            problem = validate_bic(&text)
+                .err()
+                .map(|e| format!("Invalid BIC {}: {}", text, e));
+            statement.servicer_bic = text.clone();
+        }
+
+        // Parse balance information
+        if self.in_balance {
+            let balance = &mut self.current_balance;
+            if field("Bal/Tp/CdOrPrtry/Cd") {
+                balance.balance_type = Some(parse_code(BalanceType::from_code, &text, &path)?);
+            } else if field("Bal/Amt") {
+                balance.amount = parse_amount(&text, &self.current_currency, &path)?;
+                problem = minor_units_problem(&balance.amount);
+            } else if field("Bal/CdtDbtInd") {
+                balance.credit_debit_ind = Some(parse_code(CreditDebit::from_code, &text, &path)?);
+            } else if field("Bal/Dt/Dt") || field("Bal/Dt/DtTm") {
+                balance.date = Some(parse_date_choice(&text, &path, &self.timezone)?);
+            }
+        }
+
+        // Parse transaction information
+        if self.in_transaction {
+            let transaction = &mut self.current_transaction;
+            if field("Ntry/Amt") {
+                transaction.amount = parse_amount(&text, &self.current_currency, &path)?;
+                problem = minor_units_problem(&transaction.amount);
+            } else if field("Ntry/CdtDbtInd") {
+                transaction.credit_debit_ind =
+                    Some(parse_code(CreditDebit::from_code, &text, &path)?);
+            } else if field("Ntry/Sts/Cd") || field("Ntry/Sts") {
+                // Sts is a plain code up to v04 and wrapped in Cd since v08
+                transaction.status = parse_code(EntryStatus::from_code, &text, &path)?;
+            } else if field("Ntry/BookgDt/Dt") || field("Ntry/BookgDt/DtTm") {
+                transaction.booking_date = Some(parse_date_choice(&text, &path, &self.timezone)?);
+            } else if field("Ntry/ValDt/Dt") || field("Ntry/ValDt/DtTm") {
+                transaction.value_date = Some(parse_date_choice(&text, &path, &self.timezone)?);
+            } else if field("Ntry/BkTxCd/Prtry/Cd") {
+                transaction.bank_tx_code = text.clone();
+            } else if field("Ntry/AcctSvcrRef") {
+                transaction.account_servicer_ref = text.clone();
+            } else if field("Ntry/AddtlNtryInf") {
+                transaction.additional_info = text.clone();
+            } else if field("Ntry/NtryDtls/TxDtls/Refs/EndToEndId")
+                && transaction.end_to_end_id.is_empty()
+            {
+                transaction.end_to_end_id = text.clone();
+            } else if field("Ntry/NtryDtls/TxDtls/RmtInf/Ustrd") {
+                transaction.remittance_info.push(text.clone());
+            } else if field("RmtInf/Strd/CdtrRefInf/Tp/CdOrPrtry/Cd")
+                || field("RmtInf/Strd/CdtrRefInf/Tp/CdOrPrtry/Prtry")
+            {
+                let reference = transaction.creditor_reference.get_or_insert_default();
+                if reference.reference.is_empty() {
+                    reference.kind = text.clone();
+                }
+            } else if field("RmtInf/Strd/CdtrRefInf/Ref") {
+                let reference = transaction.creditor_reference.get_or_insert_default();
+                if reference.reference.is_empty() {
+                    reference.reference = text.clone();
+                }
+            } else if (field("TxDtls/RltdPties/Dbtr/Pty/Nm") || field("TxDtls/RltdPties/Dbtr/Nm"))
+                && transaction.debtor_name.is_empty()
+            {
+                transaction.debtor_name = text.clone();
+            } else if (field("TxDtls/RltdPties/Cdtr/Pty/Nm") || field("TxDtls/RltdPties/Cdtr/Nm"))
+                && transaction.creditor_name.is_empty()
+            {
+                transaction.creditor_name = text.clone();
+            } else if field("Ntry/NtryDtls/TxDtls/Refs/ChqNb")
+                && transaction.cheque_number.is_empty()
+            {
+                transaction.cheque_number = text.clone();
+            } else if field("Ntry/NtryDtls/TxDtls/RltdDts/AccptncDtTm") {
+                let dates = &mut transaction.related_dates;
+                if dates.acceptance.is_none() {
+                    dates.acceptance = Some(parse_datetime(&text, &path, &self.timezone)?);
+                }
+            } else if field("Ntry/NtryDtls/TxDtls/RltdDts/IntrBkSttlmDt") {
+                let dates = &mut transaction.related_dates;
+                if dates.interbank_settlement.is_none() {
+                    dates.interbank_settlement = Some(parse_date(&text, &path)?);
+                }
+            } else if field("Ntry/NtryDtls/TxDtls/AddtlTxInf")
+                && transaction.transaction_info.is_empty()
+            {
+                transaction.transaction_info = text.clone();
+            }
+
+            if self.in_charges && field("Chrgs/TtlChrgsAndTaxAmt") {
+                transaction.charges = Some(parse_amount(&text, &self.current_currency, &path)?);
+            }
+
+            // Amount details of the entry come before those of its
+            // transaction details, and the first value of each field is kept
+            if let Some((detail, name)) = amount_detail(&mut transaction.amount_details, &path) {
+                read = true;
+                if name == "Amt" {
+                    if detail.is_none() {
+                        let amount = parse_amount(&text, &self.current_currency, &path)?;
+                        problem = minor_units_problem(&amount);
+                        *detail = Some(AmountDetail {
+                            amount,
+                            exchange: None,
+                        });
+                    }
+                } else if let Some(detail) = detail {
+                    let exchange = detail.exchange.get_or_insert_default();
+                    match name {
+                        "SrcCcy" if exchange.source_currency.is_empty() => {
+                            exchange.source_currency = text.clone();
+                        }
+                        "TrgtCcy" if exchange.target_currency.is_empty() => {
+                            exchange.target_currency = text.clone();
+                        }
+                        "UnitCcy" if exchange.unit_currency.is_empty() => {
+                            exchange.unit_currency = text.clone();
+                        }
+                        "XchgRate" if exchange.rate == Decimal::default() => {
+                            exchange.rate =
+                                text.parse().map_err(|_| invalid_value(&text, &path))?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        match problem {
+            Some(message) if self.strict => Err(ConvertError::FailedCheck { path, message }),
+            Some(message) => {
+                self.warnings.push((message, self.text_start, path));
+                Ok(read)
+            }
+            None => Ok(read),
+        }
+    }
+
+    fn locate<R>(
+        &self,
+        error: ConvertError,
+        tracker: &LineTracker<R>,
+        offset: u64,
+    ) -> ConvertError {
+        let (line, column) = tracker.line_and_column(offset);
+        let location = Location {
+            line,
+            column,
+            path: self.current_path.join("/"),
+        };
+        ConvertError::Located(Box::new(error), location)
+    }
+}
+
+// Records where lines start while the XML reader consumes the input, so byte
+// offsets can be turned into line and column numbers for error messages.
+struct LineTracker<R> {
+    inner: R,
+    offset: u64,
+    line_starts: Vec<u64>,
+    // Number of the line starting at line_starts[0], counted from zero
+    first_line: u64,
+}
+
+impl<R> LineTracker<R> {
+    fn new(inner: R) -> Self {
+        LineTracker {
+            inner,
+            offset: 0,
+            line_starts: vec![0],
+            first_line: 0,
+        }
+    }
+
+    fn line_and_column(&self, offset: u64) -> (u64, u64) {
+        // Index of the last line starting at or before the offset
+        let line = self.line_index(offset);
+        let column = offset.saturating_sub(self.line_starts[line]) + 1;
+        (self.first_line + line as u64 + 1, column)
+    }
+
+    // Drop the starts of lines before the one containing the offset, so
+    // memory use does not grow with the size of the document
+    fn forget_before(&mut self, offset: u64) {
+        let line = self.line_index(offset);
+        // Draining in batches keeps this cheap
+        if line >= 4096 {
+            self.line_starts.drain(..line);
+            self.first_line += line as u64;
+        }
+    }
+
+    fn line_index(&self, offset: u64) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1)
+    }
+}
+
+impl<R: BufRead> Read for LineTracker<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        record_line_starts(&mut self.line_starts, self.offset, &buf[..n]);
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for LineTracker<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // The data to consume is still buffered, so this does not read
+        if let Ok(buf) = self.inner.fill_buf() {
+            let consumed = &buf[..amt.min(buf.len())];
+            record_line_starts(&mut self.line_starts, self.offset, consumed);
+        }
+        self.offset += amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+fn record_line_starts(line_starts: &mut Vec<u64>, offset: u64, bytes: &[u8]) {
+    for (i, b) in bytes.iter().enumerate() {
+        if *b == b'\n' {
+            line_starts.push(offset + i as u64 + 1);
+        }
+    }
+}
+
+// Attributes of an element to copy, with namespaced ones named {namespace}name
+pub(crate) fn copy_attributes<R>(
+    reader: &NsReader<R>,
+    e: &BytesStart,
+) -> Result<Vec<(String, String)>> {
+    let mut attributes = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        // Namespace declarations are written anew by the writer
+        if attr.key.as_namespace_binding().is_some() {
+            continue;
+        }
+        let local_name = std::str::from_utf8(attr.key.local_name().into_inner())?;
+        let name = match reader.resolve_attribute(attr.key).0 {
+            ResolveResult::Bound(namespace) => {
+                let namespace = std::str::from_utf8(namespace.as_ref())?;
+                format!("{{{}}}{}", namespace, local_name)
+            }
+            ResolveResult::Unbound => local_name.to_string(),
+            ResolveResult::Unknown(prefix) => {
+                return Err(ConvertError::XmlSyntax(format!(
+                    "Undeclared namespace prefix '{}'",
+                    String::from_utf8_lossy(&prefix)
+                )));
+            }
+        };
+        attributes.push((name, attr.unescape_value()?.to_string()));
+    }
+    Ok(attributes)
+}
+
+fn missing(field: &str) -> ConvertError {
+    ConvertError::MissingField(field.to_string())
+}
+
+fn check_namespace(namespace: &str) -> Result<()> {
+    // Only camt namespaces are checked, documents without one are read as-is
+    if namespace.starts_with("urn:iso:std:iso:20022:tech:xsd:camt.")
+        && namespace != CAMT_10_NAMESPACE
+    {
+        return Err(ConvertError::UnsupportedVersion(namespace.to_string()));
+    }
+    Ok(())
+}
+
+fn invalid_value(text: &str, path: &str) -> ConvertError {
+    ConvertError::InvalidValue {
+        path: path.to_string(),
+        value: text.to_string(),
+    }
+}
+
+fn parse_datetime(text: &str, path: &str, timezone: &TimeZone) -> Result<DateTime<FixedOffset>> {
+    // Input format: 2025-06-22T17:33:43.291656435Z or 2025-06-20T00:00:00+02:00
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Ok(dt);
+    }
+
+    // Timestamps without offset are local time of the configured zone
+    let naive = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+        .map_err(|_| invalid_value(text, path))?;
+    Ok(timezone.from_local(naive))
+}
+
+fn parse_date_choice(text: &str, path: &str, timezone: &TimeZone) -> Result<DateOrDateTime> {
+    if path.ends_with("/DtTm") {
+        Ok(DateOrDateTime::DateTime(parse_datetime(
+            text, path, timezone,
+        )?))
+    } else {
+        Ok(DateOrDateTime::Date(parse_date(text, path)?))
+    }
+}
+
+fn parse_date(text: &str, path: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").map_err(|_| invalid_value(text, path))
+}
+
+fn parse_code<T>(from_code: fn(&str) -> Option<T>, text: &str, path: &str) -> Result<T> {
+    from_code(text).ok_or_else(|| invalid_value(text, path))
+}
+
+// Amounts with more decimal places than their currency has are kept as they
+// are, but reported
+fn minor_units_problem(amount: &Amount) -> Option<String> {
+    let units = amount.excess_precision()?;
+    Some(format!(
+        "Amount {} {} has more than the {} decimal places of its currency",
+        amount.value, amount.currency, units
+    ))
+}
+
+// Text without the characters XML 1.0 does not allow, and with
+// `strip_control_chars` with other control characters replaced by spaces.
+// Also returns the code points of the distinct characters removed and
+// replaced.
+fn sanitize(text: &str, strip_control_chars: bool) -> (String, Vec<String>, Vec<String>) {
+    let note = |codes: &mut Vec<String>, c: char| {
+        let code = format!("U+{:04X}", c as u32);
+        if !codes.contains(&code) {
+            codes.push(code);
+        }
+    };
+    let mut removed = Vec::new();
+    let mut replaced = Vec::new();
+    let mut sanitized = String::with_capacity(text.len());
+    for c in text.chars() {
+        let allowed = matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}')
+            || c >= '\u{10000}';
+        if !allowed {
+            note(&mut removed, c);
+        } else if strip_control_chars && c.is_control() {
+            note(&mut replaced, c);
+            sanitized.push(' ');
+        } else {
+            sanitized.push(c);
+        }
+    }
+    let sanitized = if strip_control_chars {
+        sanitized.trim().to_string()
+    } else {
+        sanitized
+    };
+    (sanitized, removed, replaced)
+}
+
+// Amount detail at a path such as `AmtDtls/TxAmt/Amt` or
+// `AmtDtls/TxAmt/CcyXchg/XchgRate`, with the name of the element holding the
+// text
+fn amount_detail<'a>(
+    details: &'a mut AmountDetails,
+    path: &'a str,
+) -> Option<(&'a mut Option<AmountDetail>, &'a str)> {
+    let (_, rest) = path.rsplit_once("AmtDtls/")?;
+    let (kind, rest) = rest.split_once('/')?;
+    let name = match rest {
+        "Amt" => "Amt",
+        _ => rest.strip_prefix("CcyXchg/")?,
+    };
+    let detail = match kind {
+        "InstdAmt" => &mut details.instructed,
+        "TxAmt" => &mut details.transaction,
+        "CntrValAmt" => &mut details.counter_value,
+        _ => return None,
+    };
+    Some((detail, name))
+}
+
+// ISO 20022 amounts have at most 18 digits in total
+const MAX_AMOUNT_DIGITS: u32 = 18;
+
+fn parse_amount(text: &str, currency: &str, path: &str) -> Result<Amount> {
+    let value = text
+        .parse::<Decimal>()
+        .ok()
+        .filter(|value| value.total_digits() <= MAX_AMOUNT_DIGITS)
+        .ok_or_else(|| ConvertError::InvalidAmount {
+            path: path.to_string(),
+            value: text.to_string(),
+        })?;
+    Ok(Amount {
+        value,
+        currency: currency.to_string(),
+    })
+}
@@ -0,0 +1,307 @@
+//! Reconciliation of statement entries against a list of expected payments.
+//!
+//! The list is a CSV file with a header line naming its columns, of which
+//! `reference`, `amount` and `currency` are used and others ignored. Columns
+//! are separated by commas, or by semicolons as in spreadsheet exports where
+//! the header has no comma. An entry matches an expected payment with a
+//! reference if the reference is its QR or creditor reference, end-to-end
+//! reference or a line of its remittance information, ignoring spaces and
+//! case. Expected payments without a reference match entries by amount
+//! alone. Where an amount or currency is given, it has to be the same as
+//! well.
+
+use crate::decimal::Decimal;
+use crate::invoice::invoice_reference;
+use crate::model::{Amount, Statement, Transaction};
+use crate::totals::csv_field;
+use std::io::{self, BufRead, Write};
+
+/// A payment from the list of expected payments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedPayment {
+    /// Reference as given in the list, empty to match by amount
+    pub reference: String,
+    pub amount: Option<Decimal>,
+    /// Currency code, empty for any currency
+    pub currency: String,
+    /// Line of the payment in the list, counted from 1
+    pub line: usize,
+}
+
+impl ExpectedPayment {
+    fn matches(&self, transaction: &Transaction, references: &[String]) -> bool {
+        let reference = normalize(&self.reference);
+        (reference.is_empty() || references.contains(&reference))
+            && self
+                .amount
+                .is_none_or(|amount| amount == transaction.amount.value)
+            && (self.currency.is_empty()
+                || self
+                    .currency
+                    .eq_ignore_ascii_case(&transaction.amount.currency))
+    }
+}
+
+/// Read a list of expected payments. Fails naming the line of an amount
+/// that is not a number, or if the header has neither a `reference` nor an
+/// `amount` column.
+pub fn read_expected(input: impl BufRead) -> io::Result<Vec<ExpectedPayment>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut lines = input.lines();
+    let Some(header) = lines.next().transpose()? else {
+        return Ok(Vec::new());
+    };
+    let header = header.trim_start_matches('\u{feff}');
+    let delimiter = if !header.contains(',') && header.contains(';') {
+        ';'
+    } else {
+        ','
+    };
+    let columns: Vec<String> = split_csv_line(header, delimiter)
+        .iter()
+        .map(|column| column.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name: &str| columns.iter().position(|column| column == name);
+    let reference = column("reference");
+    let amount = column("amount");
+    let currency = column("currency");
+    if reference.is_none() && amount.is_none() {
+        return Err(invalid(
+            "Expected payments need a reference or amount column".to_string(),
+        ));
+    }
+
+    let mut payments = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let number = index + 2;
+        let fields = split_csv_line(&line, delimiter);
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| fields.get(column))
+                .map(|field| field.trim())
+                .unwrap_or_default()
+        };
+        let text = field(amount);
+        let amount = if text.is_empty() {
+            None
+        } else {
+            let invalid_amount = format!("Invalid amount '{}' on line {}", text, number);
+            Some(text.parse().map_err(|_| invalid(invalid_amount))?)
+        };
+        payments.push(ExpectedPayment {
+            reference: field(reference).to_string(),
+            amount,
+            currency: field(currency).to_string(),
+            line: number,
+        });
+    }
+    Ok(payments)
+}
+
+// Fields of a CSV line, with quotes removed
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// A reference without spaces, in upper case
+fn normalize(reference: &str) -> String {
+    reference
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+// References an entry can be matched by
+fn references(transaction: &Transaction) -> Vec<String> {
+    invoice_reference(transaction)
+        .map(|reference| reference.reference)
+        .into_iter()
+        .chain(
+            transaction
+                .creditor_reference
+                .iter()
+                .map(|reference| reference.reference.clone()),
+        )
+        .chain([transaction.end_to_end_id.clone()])
+        .chain(transaction.remittance_info.iter().cloned())
+        .map(|reference| normalize(&reference))
+        .filter(|reference| !reference.is_empty())
+        .collect()
+}
+
+/// Outcome of an expected payment or entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// An entry matched an expected payment
+    Matched,
+    /// No entry matched the expected payment
+    Missing,
+    /// The entry matched no expected payment
+    Unexpected,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Matched => "matched",
+            Status::Missing => "missing",
+            Status::Unexpected => "unexpected",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    reference: String,
+    amount: Amount,
+    credit_debit: &'static str,
+    booking_date: String,
+    account: String,
+    statement: String,
+}
+
+/// Expected payments with the entries that matched them, and the entries
+/// that matched none
+#[derive(Debug, Clone, Default)]
+pub struct Reconciliation {
+    expected: Vec<(ExpectedPayment, Option<Entry>)>,
+    unexpected: Vec<Entry>,
+}
+
+impl Reconciliation {
+    pub fn new(expected: Vec<ExpectedPayment>) -> Self {
+        Reconciliation {
+            expected: expected
+                .into_iter()
+                .map(|payment| (payment, None))
+                .collect(),
+            unexpected: Vec::new(),
+        }
+    }
+
+    /// Match the entries of a statement against the payments not matched
+    /// yet, those with a reference first
+    pub fn add(&mut self, statement: &Statement) {
+        for transaction in &statement.transactions {
+            let references = references(transaction);
+            let unmatched = |with_reference: bool| {
+                self.expected.iter().position(|(payment, entry)| {
+                    entry.is_none()
+                        && payment.reference.is_empty() != with_reference
+                        && payment.matches(transaction, &references)
+                })
+            };
+            let found = unmatched(true).or_else(|| unmatched(false));
+            let entry = Entry {
+                reference: match found {
+                    Some(index) if !self.expected[index].0.reference.is_empty() => {
+                        self.expected[index].0.reference.clone()
+                    }
+                    _ => references.first().cloned().unwrap_or_default(),
+                },
+                amount: transaction.amount.clone(),
+                credit_debit: transaction
+                    .credit_debit_ind
+                    .map(|ind| ind.as_str())
+                    .unwrap_or_default(),
+                booking_date: transaction
+                    .booking_date
+                    .map(|date| date.date().to_string())
+                    .unwrap_or_default(),
+                account: statement.iban.clone(),
+                statement: statement.id.clone(),
+            };
+            match found {
+                Some(index) => self.expected[index].1 = Some(entry),
+                None => self.unexpected.push(entry),
+            }
+        }
+    }
+
+    /// Number of expected payments with the status, or of unexpected entries
+    pub fn count(&self, status: Status) -> usize {
+        match status {
+            Status::Matched => self.expected.iter().filter(|(_, e)| e.is_some()).count(),
+            Status::Missing => self.expected.iter().filter(|(_, e)| e.is_none()).count(),
+            Status::Unexpected => self.unexpected.len(),
+        }
+    }
+
+    /// Write one line per expected payment in list order, then one per
+    /// unexpected entry. Lines of expected payments name their line in the
+    /// list.
+    pub fn write_csv(&self, mut output: impl Write) -> io::Result<()> {
+        writeln!(
+            output,
+            "status,reference,amount,currency,credit_debit,booking_date,account,statement,line"
+        )?;
+        for (payment, entry) in &self.expected {
+            match entry {
+                Some(entry) => write_entry(&mut output, Status::Matched, entry, payment.line)?,
+                None => writeln!(
+                    output,
+                    "{},{},{},{},,,,,{}",
+                    Status::Missing.as_str(),
+                    csv_field(&payment.reference),
+                    payment
+                        .amount
+                        .map(|amount| amount.to_string())
+                        .unwrap_or_default(),
+                    csv_field(&payment.currency),
+                    payment.line
+                )?,
+            }
+        }
+        for entry in &self.unexpected {
+            write_entry(&mut output, Status::Unexpected, entry, 0)?;
+        }
+        output.flush()
+    }
+}
+
+// A line of an entry, with the line of the expected payment unless it is 0
+fn write_entry(
+    output: &mut impl Write,
+    status: Status,
+    entry: &Entry,
+    line: usize,
+) -> io::Result<()> {
+    writeln!(
+        output,
+        "{},{},{},{},{},{},{},{},{}",
+        status.as_str(),
+        csv_field(&entry.reference),
+        entry.amount.normalized(),
+        csv_field(&entry.amount.currency),
+        entry.credit_debit,
+        entry.booking_date,
+        csv_field(&entry.account),
+        csv_field(&entry.statement),
+        if line > 0 {
+            line.to_string()
+        } else {
+            String::new()
+        }
+    )
+}
@@ -0,0 +1,137 @@
+use crate::model::{Statement, Transaction};
+use crate::{sha1, siphash};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// How AcctSvcrRef values are generated for entries
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReferenceStyle {
+    /// Short hash of the entry content (TX + 10 digits)
+    #[default]
+    Hash,
+    /// UUIDv5 namespaced on IBAN and statement id (32 hex digits)
+    Uuid5,
+}
+
+pub fn generate_transaction_reference(
+    statement: &Statement,
+    transaction: &Transaction,
+    style: ReferenceStyle,
+) -> String {
+    match style {
+        ReferenceStyle::Hash => generate_hash_reference(transaction),
+        ReferenceStyle::Uuid5 => generate_uuid5_reference(statement, transaction),
+    }
+}
+
+fn generate_hash_reference(transaction: &Transaction) -> String {
+    // Generate a deterministic reference based on transaction content
+    let amount = transaction.amount.value.to_string();
+    let booking_date = booking_date_text(transaction);
+    let fields = [
+        amount.as_str(),
+        transaction.amount.currency.as_str(),
+        credit_debit_text(transaction),
+        booking_date.as_str(),
+        transaction.bank_tx_code.as_str(),
+        // Normalize additional_info before hashing to handle formatting differences
+        &normalize_whitespace(&transaction.additional_info),
+    ];
+
+    // Each field ends in 0xff, as the references hashed with the standard
+    // library's `Hash` for strings did
+    let mut data = Vec::new();
+    for field in fields {
+        data.extend_from_slice(field.as_bytes());
+        data.push(0xff);
+    }
+    let hash = siphash::digest(&data);
+
+    // Convert to a shorter alphanumeric string (base36)
+    // Take last 10 digits to keep it reasonable length
+    let short_hash = hash % 10_000_000_000;
+    format!("TX{:010}", short_hash)
+}
+
+// Namespace for all camtconvert UUIDs: UUIDv5 of the project URL in the RFC 4122 URL namespace
+const URL_NAMESPACE: [u8; 16] = [
+    0x6b, 0xa7, 0xb8, 0x11, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8,
+];
+
+fn generate_uuid5_reference(statement: &Statement, transaction: &Transaction) -> String {
+    // Chain namespaces: tool -> account/statement -> entry content. Entries of
+    // different accounts or statements can never collide even if identical.
+    let tool_ns = uuid5(&URL_NAMESPACE, "https://github.com/samvdst/camtconvert");
+    let statement_ns = uuid5(&tool_ns, &format!("{}/{}", statement.iban, statement.id));
+
+    let amount = transaction.amount.value.to_string();
+    let booking_date = booking_date_text(transaction);
+    let name = [
+        amount.as_str(),
+        transaction.amount.currency.as_str(),
+        credit_debit_text(transaction),
+        booking_date.as_str(),
+        transaction.bank_tx_code.as_str(),
+        &normalize_whitespace(&transaction.additional_info),
+    ]
+    .join("\u{1f}");
+    let uuid = uuid5(&statement_ns, &name);
+
+    // AcctSvcrRef is Max35Text, so use the 32 digit form without hyphens
+    uuid.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A random UUID (version 4) as 32 hex digits, like the `uuid5` references
+pub fn random_uuid() -> String {
+    // The hashers of the standard library are seeded randomly; the time and
+    // a counter make the UUIDs of one process differ
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    // WebAssembly outside a browser or WASI host has no clock
+    #[cfg(not(target_family = "wasm"))]
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |now| now.as_nanos());
+    #[cfg(target_family = "wasm")]
+    let now = 0u128;
+    let state = RandomState::new();
+    let mut uuid = [0u8; 16];
+    for (half, chunk) in uuid.chunks_mut(8).enumerate() {
+        chunk.copy_from_slice(&state.hash_one((now, count, half)).to_be_bytes());
+    }
+    uuid[6] = (uuid[6] & 0x0f) | 0x40; // version 4
+    uuid[8] = (uuid[8] & 0x3f) | 0x80; // RFC 4122 variant
+    uuid.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn uuid5(namespace: &[u8; 16], name: &str) -> [u8; 16] {
+    let mut data = namespace.to_vec();
+    data.extend_from_slice(name.as_bytes());
+    let hash = sha1::digest(&data);
+
+    let mut uuid = [0u8; 16];
+    uuid.copy_from_slice(&hash[..16]);
+    uuid[6] = (uuid[6] & 0x0f) | 0x50; // version 5
+    uuid[8] = (uuid[8] & 0x3f) | 0x80; // RFC 4122 variant
+    uuid
+}
+
+fn credit_debit_text(transaction: &Transaction) -> &'static str {
+    transaction
+        .credit_debit_ind
+        .map(|ind| ind.as_str())
+        .unwrap_or_default()
+}
+
+fn booking_date_text(transaction: &Transaction) -> String {
+    // References hash the booking date as it appeared in the input
+    transaction
+        .booking_date
+        .map(|date| date.to_string())
+        .unwrap_or_default()
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    // Split by any whitespace (spaces, tabs, newlines), join back with single spaces
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,378 @@
+//! Regular expressions for rules matching text of entries, such as those of
+//! [`crate::category`].
+//!
+//! The common syntax is supported: literals, `.`, classes such as `[a-z]`
+//! and `[^0-9]`, `\d`, `\w` and `\s` and their negations `\D`, `\W` and
+//! `\S`, anchors `^` and `$`, groups with alternatives `(a|b)`, the
+//! quantifiers `*`, `+`, `?` and `{n,m}`, and `(?i)` at the start for
+//! matching regardless of case. A regular expression matches if it matches
+//! anywhere in the text, unless anchored.
+//!
+//! Matching backtracks, which is fast for the short texts of entries but can
+//! take long for nested quantifiers such as `(a+)+`.
+
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+
+/// A compiled regular expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regex {
+    pattern: String,
+    alternatives: Vec<Vec<Node>>,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<Item>, bool),
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat(Box<Node>, usize, Option<usize>),
+}
+
+// Member of a character class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl Item {
+    fn contains(self, c: char) -> bool {
+        match self {
+            Item::Range(first, last) => (first..=last).contains(&c),
+            Item::Digit(negated) => c.is_ascii_digit() != negated,
+            Item::Word(negated) => (c.is_alphanumeric() || c == '_') != negated,
+            Item::Space(negated) => c.is_whitespace() != negated,
+        }
+    }
+}
+
+impl FromStr for Regex {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let (ignore_case, rest) = match pattern.strip_prefix("(?i)") {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let mut parser = Parser {
+            chars: rest.chars().collect(),
+            position: 0,
+        };
+        let alternatives = parser.alternatives()?;
+        if parser.position < parser.chars.len() {
+            return Err(format!("unmatched ) in regular expression {}", pattern));
+        }
+        Ok(Regex {
+            pattern: pattern.to_string(),
+            alternatives,
+            ignore_case,
+        })
+    }
+}
+
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+impl Regex {
+    /// Whether the regular expression matches anywhere in `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    /// Byte range of the first match in `text`. Of the matches starting
+    /// there, repetitions take as much as they can.
+    pub fn find(&self, text: &str) -> Option<(usize, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        let (start, end) = self.find_chars(&chars, 0)?;
+        let offset = |index: usize| chars[..index].iter().map(|c| c.len_utf8()).sum();
+        Some((offset(start), offset(end)))
+    }
+
+    /// `text` with every match replaced by `with`
+    pub fn replace_all(&self, text: &str, with: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut replaced = String::new();
+        let mut position = 0;
+        while let Some((start, end)) = self.find_chars(&chars, position) {
+            replaced.extend(&chars[position..start]);
+            replaced.push_str(with);
+            // After an empty match, the next character is kept as it is
+            if end == start {
+                if let Some(c) = chars.get(end) {
+                    replaced.push(*c);
+                }
+                position = end + 1;
+            } else {
+                position = end;
+            }
+            if position > chars.len() {
+                return replaced;
+            }
+        }
+        replaced.extend(&chars[position..]);
+        replaced
+    }
+
+    // First match starting at `from` or later, as character indices
+    fn find_chars(&self, text: &[char], from: usize) -> Option<(usize, usize)> {
+        let matcher = Matcher {
+            text,
+            ignore_case: self.ignore_case,
+        };
+        let group = [Node::Group(self.alternatives.clone())];
+        let end = Cell::new(None);
+        (from..=text.len()).find_map(|start| {
+            matcher
+                .sequence(&group, start, &|position| {
+                    end.set(Some(position));
+                    true
+                })
+                .then(|| (start, end.get().unwrap_or(start)))
+        })
+    }
+}
+
+struct Matcher<'a> {
+    text: &'a [char],
+    ignore_case: bool,
+}
+
+impl Matcher<'_> {
+    // Whether `nodes` match from `position` and `next` matches after them
+    fn sequence(&self, nodes: &[Node], position: usize, next: &dyn Fn(usize) -> bool) -> bool {
+        let Some((node, rest)) = nodes.split_first() else {
+            return next(position);
+        };
+        let then = |position| self.sequence(rest, position, next);
+        match node {
+            Node::Start => position == 0 && then(position),
+            Node::End => position == self.text.len() && then(position),
+            Node::Group(alternatives) => alternatives
+                .iter()
+                .any(|alternative| self.sequence(alternative, position, &then)),
+            Node::Repeat(node, min, max) => self.repeat(node, *min, *max, 0, position, &then),
+            single => {
+                position < self.text.len()
+                    && self.single(single, self.text[position])
+                    && then(position + 1)
+            }
+        }
+    }
+
+    // Greedy repetition: as many times as possible, fewer if the rest does
+    // not match
+    fn repeat(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        count: usize,
+        position: usize,
+        next: &dyn Fn(usize) -> bool,
+    ) -> bool {
+        let more = max.is_none_or(|max| count < max)
+            && self.sequence(std::slice::from_ref(node), position, &|after| {
+                // An empty match beyond the minimum would repeat forever
+                (after != position || count < min)
+                    && self.repeat(node, min, max, count + 1, after, next)
+            });
+        more || (count >= min && next(position))
+    }
+
+    fn single(&self, node: &Node, c: char) -> bool {
+        match node {
+            Node::Any => c != '\n',
+            Node::Char(expected) => {
+                *expected == c || (self.ignore_case && same_letter(*expected, c))
+            }
+            Node::Class(items, negated) => {
+                let contains = |c| items.iter().any(|item| item.contains(c));
+                let found = contains(c)
+                    || (self.ignore_case
+                        && (c.to_lowercase().any(contains) || c.to_uppercase().any(contains)));
+                found != *negated
+            }
+            _ => false,
+        }
+    }
+}
+
+fn same_letter(a: char, b: char) -> bool {
+    a.to_lowercase().eq(b.to_lowercase())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.position += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek()
+            && c != '|'
+            && c != ')'
+        {
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.next().unwrap_or_default();
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                // Groups do not capture, so (?:...) is the same
+                if self.chars[self.position..].starts_with(&['?', ':']) {
+                    self.position += 2;
+                }
+                let alternatives = self.alternatives()?;
+                if self.next() != Some(')') {
+                    return Err("missing ) in regular expression".to_string());
+                }
+                Node::Group(alternatives)
+            }
+            '[' => self.class()?,
+            '\\' => match self.escape()? {
+                Item::Range(c, _) => Node::Char(c),
+                item => Node::Class(vec![item], false),
+            },
+            '*' | '+' | '?' | '{' => {
+                return Err(format!(
+                    "nothing to repeat before {} in regular expression",
+                    c
+                ));
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    // The character or class after a backslash
+    fn escape(&mut self) -> Result<Item, String> {
+        let c = self
+            .next()
+            .ok_or("regular expression ends with \\".to_string())?;
+        Ok(match c {
+            'd' | 'D' => Item::Digit(c == 'D'),
+            'w' | 'W' => Item::Word(c == 'W'),
+            's' | 'S' => Item::Space(c == 'S'),
+            'n' => Item::Range('\n', '\n'),
+            't' => Item::Range('\t', '\t'),
+            'r' => Item::Range('\r', '\r'),
+            c if c.is_ascii_alphanumeric() => {
+                return Err(format!("unknown escape \\{} in regular expression", c));
+            }
+            c => Item::Range(c, c),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.position += 1;
+        }
+        let mut items = Vec::new();
+        loop {
+            let item = match self.next() {
+                None => return Err("missing ] in regular expression".to_string()),
+                // A ] first in the class is a character
+                Some(']') if !items.is_empty() => break,
+                Some('\\') => self.escape()?,
+                Some(c) => Item::Range(c, c),
+            };
+            let range_end = self.chars.get(self.position + 1).filter(|&&c| c != ']');
+            if let (Item::Range(first, _), Some('-'), Some(&last)) = (item, self.peek(), range_end)
+            {
+                if last < first {
+                    return Err(format!(
+                        "invalid range {}-{} in regular expression",
+                        first, last
+                    ));
+                }
+                self.position += 2;
+                items.push(Item::Range(first, last));
+            } else {
+                items.push(item);
+            }
+        }
+        Ok(Node::Class(items, negated))
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => return self.counted(atom),
+            _ => return Ok(atom),
+        };
+        self.position += 1;
+        self.lazy();
+        Ok(Node::Repeat(Box::new(atom), min, max))
+    }
+
+    // A quantifier {n}, {n,} or {n,m}
+    fn counted(&mut self, atom: Node) -> Result<Node, String> {
+        let end = self.chars[self.position..]
+            .iter()
+            .position(|&c| c == '}')
+            .ok_or("missing } in regular expression".to_string())?;
+        let counts: String = self.chars[self.position + 1..self.position + end]
+            .iter()
+            .collect();
+        let invalid = || format!("invalid quantifier {{{}}} in regular expression", counts);
+        let number = |text: &str| text.trim().parse::<usize>().map_err(|_| invalid());
+        let (min, max) = match counts.split_once(',') {
+            None => (number(&counts)?, Some(number(&counts)?)),
+            Some((min, "")) => (number(min)?, None),
+            Some((min, max)) => (number(min)?, Some(number(max)?)),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(invalid());
+        }
+        self.position += end + 1;
+        self.lazy();
+        Ok(Node::Repeat(Box::new(atom), min, max))
+    }
+
+    // Lazy quantifiers match the same texts, so their ? is skipped
+    fn lazy(&mut self) {
+        if self.peek() == Some('?') {
+            self.position += 1;
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Input read from `https://` and `s3://` URLs.
+//!
+//! The download is streamed into the parser, so statements kept in an object
+//! store do not have to be copied to a local file first. HTTPS downloads are
+//! made with `curl`, S3 downloads with the AWS command line tool and its
+//! usual credentials and configuration.
+
+use crate::process::ChildOutput;
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+
+/// Schemes of URLs that can be read
+pub const SCHEMES: &[&str] = &["https://", "s3://"];
+
+/// Whether an input names a URL rather than a local file
+pub fn is_remote(input: &str) -> bool {
+    SCHEMES.iter().any(|scheme| input.starts_with(scheme))
+}
+
+/// Name of the file a URL points to, without query or fragment, e.g.
+/// `statement.xml` for `https://host/camt/statement.xml?token=x`
+pub fn file_name(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next()?;
+    let (_, rest) = path.split_once("://")?;
+    let (_, path) = rest.split_once('/')?;
+    path.rsplit('/').next().filter(|name| !name.is_empty())
+}
+
+/// Start downloading a URL.
+///
+/// Reading the returned reader yields the content. If the download fails,
+/// the error is returned once all content has been read.
+pub fn open(url: &str) -> io::Result<Download> {
+    let (mut command, program) = if url.starts_with("s3://") {
+        let mut command = Command::new("aws");
+        command
+            .args(["s3", "cp", "--only-show-errors", "--"])
+            .arg(url)
+            .arg("-");
+        (command, "aws")
+    } else if url.starts_with("https://") {
+        let mut command = Command::new("curl");
+        // Redirects must not lead away from HTTPS
+        command
+            .args([
+                "--fail",
+                "--silent",
+                "--show-error",
+                "--location",
+                "--proto",
+                "=https",
+                "--proto-redir",
+                "=https",
+                "--",
+            ])
+            .arg(url);
+        (command, "curl")
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unsupported URL {}, expected one of: {}",
+                url,
+                SCHEMES.join(", ")
+            ),
+        ));
+    };
+    command.stdin(Stdio::null());
+    let output = ChildOutput::spawn(&mut command, format!("Could not download {}", url))
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not run {}: {}", program, e)))?;
+    Ok(Download { output })
+}
+
+/// Content of a URL, read while it is downloaded
+pub struct Download {
+    output: ChildOutput,
+}
+
+impl Read for Download {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.output.read(buf)
+    }
+}
@@ -0,0 +1,180 @@
+//! Text view of a parsed statement, for a person to check before its output
+//! is written, and questions asked on the terminal. The same overview is
+//! available as JSON, for scripts.
+
+use crate::decimal::Decimal;
+use crate::json::Value;
+use crate::model::{EntryStatus, Statement, Transaction};
+use std::io::{self, BufRead, Write};
+
+// Longest additional information shown for an entry, in characters
+const INFO_WIDTH: usize = 48;
+
+const BOLD: &str = "\x1b[1m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Write the statement header, balances, entries and warnings. With `color`,
+/// headings are bold and warnings yellow.
+pub fn write_review(
+    mut output: impl Write,
+    statement: &Statement,
+    warnings: &[String],
+    color: bool,
+) -> io::Result<()> {
+    let style = |style: &'static str| if color { style } else { "" };
+    let (bold, yellow, reset) = (style(BOLD), style(YELLOW), style(RESET));
+
+    writeln!(
+        output,
+        "{}Statement {}{} of {} {}, {}",
+        bold, statement.id, reset, statement.iban, statement.currency, statement.owner_name
+    )?;
+    let datetime = |datetime: Option<chrono::DateTime<chrono::FixedOffset>>| {
+        datetime.map_or_else(
+            || "?".to_string(),
+            |dt| dt.format("%Y-%m-%d %H:%M").to_string(),
+        )
+    };
+    writeln!(
+        output,
+        "Period {} to {}, created {}",
+        datetime(statement.from_datetime),
+        datetime(statement.to_datetime),
+        datetime(statement.creation_datetime)
+    )?;
+
+    writeln!(output, "\n{}Balances{}", bold, reset)?;
+    for balance in &statement.balances {
+        writeln!(
+            output,
+            "  {:<4}  {:<10}  {:>16} {}",
+            balance
+                .balance_type
+                .map_or("?", |balance_type| balance_type.as_str()),
+            balance
+                .date
+                .map_or_else(String::new, |date| date.date().to_string()),
+            balance.signed_amount().to_string(),
+            balance.amount.currency
+        )?;
+    }
+
+    writeln!(
+        output,
+        "\n{}Entries ({}){}",
+        bold,
+        statement.transactions.len(),
+        reset
+    )?;
+    for (number, transaction) in statement.transactions.iter().enumerate() {
+        writeln!(output, "  {:>4}  {}", number + 1, entry_line(transaction))?;
+    }
+
+    if !warnings.is_empty() {
+        writeln!(output, "\n{}Warnings ({}){}", bold, warnings.len(), reset)?;
+        for warning in warnings {
+            writeln!(output, "  {}! {}{}", yellow, warning, reset)?;
+        }
+    }
+    Ok(())
+}
+
+/// The statement header, balances, entry counts and warnings as JSON.
+/// `entries_total` is the sum of the entries in the account currency, null if
+/// too large to compute, and
+/// `balance_mismatch` the result of [`Statement::check_balances`].
+pub fn summary(statement: &Statement, warnings: &[String]) -> Value {
+    let datetime = |datetime: Option<chrono::DateTime<chrono::FixedOffset>>| {
+        datetime.map(|datetime| datetime.to_rfc3339())
+    };
+    let balances: Vec<Value> = statement
+        .balances
+        .iter()
+        .map(|balance| {
+            Value::object()
+                .with(
+                    "type",
+                    balance
+                        .balance_type
+                        .map(|balance_type| balance_type.as_str()),
+                )
+                .with("date", balance.date.map(|date| date.to_string()))
+                .with("amount", balance.signed_amount())
+                .with("currency", balance.amount.currency.as_str())
+        })
+        .collect();
+    let currency = statement.account_currency();
+    let entries_total = statement
+        .transactions
+        .iter()
+        .try_fold(Decimal::default(), |sum, transaction| {
+            sum.checked_add(transaction.account_amount(currency))
+        });
+    let pending = statement
+        .transactions
+        .iter()
+        .filter(|transaction| transaction.status == EntryStatus::Pending)
+        .count();
+    Value::object()
+        .with("id", statement.id.as_str())
+        .with("iban", statement.iban.as_str())
+        .with("currency", currency)
+        .with("owner", statement.owner_name.as_str())
+        .with("created", datetime(statement.creation_datetime))
+        .with("from", datetime(statement.from_datetime))
+        .with("to", datetime(statement.to_datetime))
+        .with("balances", balances)
+        .with("entries", statement.transactions.len())
+        .with("pending_entries", pending)
+        .with("entries_total", entries_total)
+        .with("balance_mismatch", statement.check_balances())
+        .with("warnings", warnings.to_vec())
+}
+
+fn entry_line(transaction: &Transaction) -> String {
+    let mut info: String = transaction
+        .additional_info
+        .chars()
+        .take(INFO_WIDTH)
+        .collect();
+    if transaction.additional_info.chars().count() > INFO_WIDTH {
+        info.pop();
+        info.push('…');
+    }
+    let status = match transaction.status {
+        EntryStatus::Booked => String::new(),
+        status => format!(" [{}]", status.as_str()),
+    };
+    format!(
+        "{:<10}  {:>16} {}  {:<16}  {}{}",
+        transaction
+            .booking_date
+            .map_or_else(String::new, |date| date.date().to_string()),
+        transaction.signed_amount().to_string(),
+        transaction.amount.currency,
+        transaction.bank_tx_code,
+        info,
+        status
+    )
+}
+
+/// Ask for a value, returning the answer without surrounding whitespace.
+/// The answer is empty if none was given before the end of the input.
+pub fn ask(mut input: impl BufRead, mut output: impl Write, question: &str) -> io::Result<String> {
+    write!(output, "{} ", question)?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// Ask a yes or no question, taking anything but `y` or `yes` as no
+pub fn confirm(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    question: &str,
+) -> io::Result<bool> {
+    let answer = ask(&mut input, &mut output, &format!("{} [y/N]", question))?;
+    Ok(matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes"))
+}
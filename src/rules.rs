@@ -0,0 +1,424 @@
+//! Business rules checked on written documents beyond what the XML schema
+//! requires, so that outputs pass the acceptance checks of the receiving
+//! banks and software.
+//!
+//! Each rule applies to the elements at a path of the document and checks one
+//! of them at a time, like a Schematron assertion.
+
+use crate::error::Result;
+use crate::iban::is_qr_iban;
+use crate::model::{XmlElement, XmlNode};
+use crate::writer::PLACEHOLDER_BIC;
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use std::fmt;
+use std::io::BufRead;
+
+/// A set of rules to check outputs against
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleSet {
+    /// Swiss Payment Standards business rules for camt.053
+    Sps,
+    /// SEPA checks of end-to-end references, characters and BICs
+    Sepa,
+}
+
+/// An element that breaks a rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: &'static str,
+    /// Path of the element, with the position of repeated elements such as
+    /// `Document/BkToCstmrStmt/Stmt/Ntry[2]`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Rule {} failed at {}: {}",
+            self.rule, self.path, self.message
+        )
+    }
+}
+
+struct Rule {
+    id: &'static str,
+    // Path of the elements the rule applies to, below Document
+    context: &'static str,
+    check: fn(&XmlElement) -> Option<String>,
+}
+
+const SPS_RULES: &[Rule] = &[
+    Rule {
+        id: "sps/pagination",
+        context: "BkToCstmrStmt/GrpHdr",
+        check: |header| require(header, "MsgPgntn"),
+    },
+    Rule {
+        id: "sps/version",
+        context: "BkToCstmrStmt/GrpHdr/AddtlInf",
+        check: |info| {
+            let text = info.text();
+            (!is_sps_version(&text)).then(|| {
+                format!(
+                    "AddtlInf {} is not an SPS version such as SPS/2.1 or SPS/2.1/PROD",
+                    text
+                )
+            })
+        },
+    },
+    Rule {
+        id: "sps/sequence-number",
+        context: "BkToCstmrStmt/Stmt",
+        check: |statement| require(statement, "ElctrncSeqNb"),
+    },
+    Rule {
+        id: "sps/iban",
+        context: "BkToCstmrStmt/Stmt/Acct/Id",
+        check: |id| require(id, "IBAN").map(|_| "account is not identified by IBAN".to_string()),
+    },
+    Rule {
+        id: "sps/balances",
+        context: "BkToCstmrStmt/Stmt",
+        check: |statement| {
+            let codes: Vec<String> = statement
+                .children_named("Bal")
+                .filter_map(|balance| balance.find("Tp/CdOrPrtry/Cd"))
+                .map(XmlElement::text)
+                .collect();
+            let has = |code: &str| codes.iter().any(|c| c == code);
+            if !has("OPBD") && !has("PRCD") {
+                Some("no opening balance OPBD or PRCD".to_string())
+            } else if !has("CLBD") {
+                Some("no closing balance CLBD".to_string())
+            } else {
+                None
+            }
+        },
+    },
+    Rule {
+        id: "sps/entry-reference",
+        context: "BkToCstmrStmt/Stmt/Ntry",
+        check: |entry| require(entry, "AcctSvcrRef"),
+    },
+    Rule {
+        id: "sps/booking-date",
+        context: "BkToCstmrStmt/Stmt/Ntry",
+        check: |entry| require(entry, "BookgDt"),
+    },
+    Rule {
+        id: "sps/value-date",
+        context: "BkToCstmrStmt/Stmt/Ntry",
+        check: |entry| require(entry, "ValDt"),
+    },
+    Rule {
+        id: "sps/transaction-code",
+        context: "BkToCstmrStmt/Stmt/Ntry/BkTxCd",
+        check: |code| {
+            require(code, "Domn").map(|_| "no ISO bank transaction code Domn".to_string())
+        },
+    },
+    Rule {
+        id: "sps/qr-reference",
+        context: "BkToCstmrStmt/Stmt/Ntry/NtryDtls/TxDtls/RmtInf/Strd/CdtrRefInf",
+        check: |info| {
+            let reference = info.find("Ref").map(XmlElement::text).unwrap_or_default();
+            if info.find("Tp/CdOrPrtry/Prtry").map(XmlElement::text) == Some("QRR".into()) {
+                (!is_qr_reference(&reference))
+                    .then(|| format!("{} is not a QR reference of 27 digits", reference))
+            } else if info.find("Tp/CdOrPrtry/Cd").map(XmlElement::text) == Some("SCOR".into()) {
+                (!is_creditor_reference(&reference))
+                    .then(|| format!("{} is not an ISO 11649 creditor reference", reference))
+            } else {
+                None
+            }
+        },
+    },
+    Rule {
+        id: "sps/qr-iban",
+        context: "BkToCstmrStmt/Stmt",
+        // Payments to a QR-IBAN always carry a QR reference
+        check: |statement| {
+            let iban = statement.find("Acct/Id/IBAN")?.text();
+            if !is_qr_iban(&iban) {
+                return None;
+            }
+            let missing = statement
+                .children_named("Ntry")
+                .filter(|entry| {
+                    entry.find("CdtDbtInd").map(XmlElement::text) == Some("CRDT".into())
+                })
+                .filter(|entry| !has_qr_reference(entry))
+                .count();
+            (missing > 0).then(|| {
+                format!(
+                    "{} credit entries to QR-IBAN {} have no QR reference",
+                    missing, iban
+                )
+            })
+        },
+    },
+];
+
+const SEPA_RULES: &[Rule] = &[
+    Rule {
+        id: "sepa/end-to-end-id",
+        context: "BkToCstmrStmt/Stmt/Ntry/NtryDtls/TxDtls",
+        check: |transaction| {
+            transaction
+                .find("Refs/EndToEndId")
+                .is_none()
+                .then(|| "TxDtls has no Refs/EndToEndId".to_string())
+        },
+    },
+    Rule {
+        id: "sepa/character-set",
+        context: "BkToCstmrStmt/Stmt/Acct/Ownr/Nm",
+        check: check_characters,
+    },
+    Rule {
+        id: "sepa/character-set",
+        context: "BkToCstmrStmt/Stmt/Ntry",
+        check: check_characters,
+    },
+    Rule {
+        id: "sepa/iban-bic",
+        context: "BkToCstmrStmt/Stmt/Acct",
+        check: |account| {
+            let iban = account.find("Id/IBAN")?.text();
+            let bic = account
+                .find("Svcr/FinInstnId/BICFI")
+                .map(XmlElement::text)
+                .unwrap_or_default();
+            if bic.is_empty() || bic == PLACEHOLDER_BIC {
+                return Some(format!("no servicer BIC for IBAN {}", iban));
+            }
+            let iban_country = iban.get(..2).unwrap_or_default();
+            let bic_country = bic.get(4..6).unwrap_or_default();
+            (!same_sepa_country(iban_country, bic_country)).then(|| {
+                format!(
+                    "BIC {} of {} does not match IBAN {} of {}",
+                    bic, bic_country, iban, iban_country
+                )
+            })
+        },
+    },
+];
+
+impl RuleSet {
+    fn rules(self) -> &'static [Rule] {
+        match self {
+            RuleSet::Sps => SPS_RULES,
+            RuleSet::Sepa => SEPA_RULES,
+        }
+    }
+}
+
+/// Check a written document against a set of rules
+pub fn check_rules<R: BufRead>(document: R, rules: RuleSet) -> Result<Vec<Violation>> {
+    let root = read_tree(document)?;
+    let mut violations = Vec::new();
+    for rule in rules.rules() {
+        for (path, element) in select(&root, rule.context) {
+            if let Some(message) = (rule.check)(element) {
+                violations.push(Violation {
+                    rule: rule.id,
+                    path,
+                    message,
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+// Elements at a path below the root with their own paths, numbered where an
+// element has siblings of the same name
+fn select<'a>(root: &'a XmlElement, path: &str) -> Vec<(String, &'a XmlElement)> {
+    let mut selected = vec![(root.name.clone(), root)];
+    for name in path.split('/') {
+        selected = selected
+            .into_iter()
+            .flat_map(|(path, element)| {
+                let children: Vec<&XmlElement> = element.children_named(name).collect();
+                let numbered = children.len() > 1;
+                children
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(index, child)| {
+                        let path = if numbered {
+                            format!("{}/{}[{}]", path, name, index + 1)
+                        } else {
+                            format!("{}/{}", path, name)
+                        };
+                        (path, child)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+    selected
+}
+
+// The document as a tree of elements named without prefix
+fn read_tree<R: BufRead>(input: R) -> Result<XmlElement> {
+    let mut reader = Reader::from_reader(input);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut open: Vec<XmlElement> = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => open.push(element(e.local_name().as_ref())?),
+            Event::Empty(e) => {
+                let element = element(e.local_name().as_ref())?;
+                if let Some(parent) = open.last_mut() {
+                    parent.children.push(XmlNode::Element(element));
+                }
+            }
+            Event::Text(e) => {
+                if let Some(parent) = open.last_mut() {
+                    parent
+                        .children
+                        .push(XmlNode::Text(e.unescape()?.into_owned()));
+                }
+            }
+            Event::CData(e) => {
+                if let Some(parent) = open.last_mut() {
+                    let text = std::str::from_utf8(&e)?.to_string();
+                    parent.children.push(XmlNode::Text(text));
+                }
+            }
+            Event::End(_) => {
+                let element = open.pop().expect("the reader checks end tags");
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(XmlNode::Element(element)),
+                    None => return Ok(element),
+                }
+            }
+            Event::Eof => {
+                return Err(crate::error::ConvertError::XmlSyntax(
+                    "document has no root element".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+fn element(name: &[u8]) -> Result<XmlElement> {
+    Ok(XmlElement {
+        name: std::str::from_utf8(name)?.to_string(),
+        namespace: None,
+        attributes: Vec::new(),
+        children: Vec::new(),
+    })
+}
+
+fn require(element: &XmlElement, child: &str) -> Option<String> {
+    element
+        .children_named(child)
+        .next()
+        .is_none()
+        .then(|| format!("{} has no {}", element.name, child))
+}
+
+fn has_qr_reference(entry: &XmlElement) -> bool {
+    entry.children_named("NtryDtls").any(|details| {
+        details.children_named("TxDtls").any(|transaction| {
+            transaction
+                .find("RmtInf/Strd/CdtrRefInf/Tp/CdOrPrtry/Prtry")
+                .is_some_and(|code| code.text() == "QRR")
+        })
+    })
+}
+
+// The first text below an element with a character outside the SEPA basic
+// Latin character set, with the path of its element
+fn check_characters(element: &XmlElement) -> Option<String> {
+    fn find(element: &XmlElement, path: &str) -> Option<(String, char)> {
+        element.children.iter().find_map(|child| match child {
+            XmlNode::Text(text) => text
+                .chars()
+                .find(|c| !is_sepa_character(*c))
+                .map(|c| (path.to_string(), c)),
+            XmlNode::Element(child) => find(child, &format!("{}/{}", path, child.name)),
+        })
+    }
+    find(element, &element.name)
+        .map(|(path, c)| format!("{} has {:?}, outside the SEPA character set", path, c))
+}
+
+/// Whether a character is in the basic Latin character set that all SEPA
+/// banks must support: letters, digits, space and `/ - ? : ( ) . , ' +`
+pub fn is_sepa_character(c: char) -> bool {
+    c.is_ascii_alphanumeric() || " /-?:().,'+".contains(c)
+}
+
+// Whether a BIC of one country may serve an account with an IBAN of another,
+// as territories without their own SEPA banking system use the IBANs of the
+// country whose banks serve them
+fn same_sepa_country(iban: &str, bic: &str) -> bool {
+    const TERRITORIES: &[(&str, &[&str])] = &[
+        (
+            "FR",
+            &[
+                "GF", "GP", "MQ", "RE", "PM", "BL", "MF", "YT", "NC", "PF", "WF",
+            ],
+        ),
+        ("GB", &["GG", "IM", "JE"]),
+        ("FI", &["AX"]),
+    ];
+    iban == bic
+        || TERRITORIES
+            .iter()
+            .any(|(country, territories)| *country == iban && territories.contains(&bic))
+}
+
+// SPS/ followed by major and minor version, optionally with /PROD or /TEST
+fn is_sps_version(text: &str) -> bool {
+    let Some(version) = text.strip_prefix("SPS/") else {
+        return false;
+    };
+    let version = version
+        .strip_suffix("/PROD")
+        .or_else(|| version.strip_suffix("/TEST"))
+        .unwrap_or(version);
+    version.split_once('.').is_some_and(|(major, minor)| {
+        [major, minor]
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+/// Whether a reference is a QR reference: 26 digits and a check digit
+/// computed with the recursive modulo 10 method
+pub fn is_qr_reference(reference: &str) -> bool {
+    let bytes = reference.as_bytes();
+    bytes.len() == 27
+        && bytes.iter().all(u8::is_ascii_digit)
+        && check_digit_mod10(&bytes[..26]) == bytes[26] - b'0'
+}
+
+// Check digit of ASCII digits with the recursive modulo 10 method of QR and
+// ESR references
+pub(crate) fn check_digit_mod10(digits: &[u8]) -> u8 {
+    const TABLE: [u8; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+    let carry = digits
+        .iter()
+        .fold(0, |carry, b| TABLE[((carry + b - b'0') % 10) as usize]);
+    (10 - carry) % 10
+}
+
+/// Whether a reference is an ISO 11649 creditor reference such as
+/// `RF18539007547034`
+pub fn is_creditor_reference(reference: &str) -> bool {
+    let bytes = reference.as_bytes();
+    (5..=25).contains(&bytes.len())
+        && reference.starts_with("RF")
+        && bytes[2..4].iter().all(u8::is_ascii_digit)
+        && bytes[4..].iter().all(u8::is_ascii_alphanumeric)
+        && crate::iban::remainder_97(bytes[4..].iter().chain(&bytes[..4])) == 1
+}
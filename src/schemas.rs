@@ -0,0 +1,48 @@
+//! The XSD files of the camt versions that can be read and written, bundled
+//! with the `schemas` feature.
+//!
+//! Each version in [`matrix`](crate::matrix) has its schema here, so
+//! documents can be validated offline against the XSD of exactly the version
+//! they were read or written as, for example with
+//! `xmllint --noout --schema camt.053.001.08.xsd statement_08.xml`.
+
+use crate::matrix::names_version;
+
+/// The XSD of one version
+pub struct Schema {
+    /// Full name such as `camt.053.001.08`
+    pub version: &'static str,
+    pub xsd: &'static str,
+}
+
+impl Schema {
+    /// Name of the XSD file, such as `camt.053.001.08.xsd`, as written in
+    /// `xsi:schemaLocation` by `--schema-location`
+    pub fn file_name(&self) -> String {
+        format!("{}.xsd", self.version)
+    }
+}
+
+/// Schemas of all input and output versions, newest first
+pub const SCHEMAS: &[Schema] = &[
+    Schema {
+        version: "camt.053.001.10",
+        xsd: include_str!("../schemas/camt.053.001.10.xsd"),
+    },
+    Schema {
+        version: "camt.053.001.08",
+        xsd: include_str!("../schemas/camt.053.001.08.xsd"),
+    },
+    Schema {
+        version: "camt.053.001.04",
+        xsd: include_str!("../schemas/camt.053.001.04.xsd"),
+    },
+];
+
+/// The schema of a version, named as for
+/// [`find_reader`](crate::matrix::find_reader)
+pub fn find_schema(version: &str) -> Option<&'static Schema> {
+    SCHEMAS
+        .iter()
+        .find(|schema| names_version(schema.version, version))
+}
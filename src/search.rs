@@ -0,0 +1,76 @@
+//! Search for entries of statements by text and amount, to find a payment in
+//! archived statements without reading their XML.
+//!
+//! Text is looked for, ignoring case, in the references, names, codes and
+//! information of an entry. An amount matches entries of that amount in
+//! either direction, or only debits if it is negative.
+
+use crate::decimal::Decimal;
+use crate::invoice::invoice_reference;
+use crate::model::Transaction;
+
+/// Which entries are found. An empty filter matches every entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntryFilter {
+    /// Text contained in any text of the entry
+    pub text: Option<String>,
+    pub amount: Option<Decimal>,
+}
+
+impl EntryFilter {
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        if let Some(amount) = self.amount {
+            let matches = if amount < Decimal::default() {
+                transaction.signed_amount() == amount
+            } else {
+                transaction.amount.value == amount
+            };
+            if !matches {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            let text = text.to_lowercase();
+            return texts(transaction).any(|field| field.to_lowercase().contains(&text));
+        }
+        true
+    }
+}
+
+/// The reference an entry is best known by: its QR or creditor reference,
+/// or else its end-to-end reference, empty if it has none
+pub fn reference(transaction: &Transaction) -> String {
+    invoice_reference(transaction)
+        .map(|reference| reference.reference)
+        .or_else(|| {
+            transaction
+                .creditor_reference
+                .as_ref()
+                .map(|reference| reference.reference.clone())
+        })
+        .unwrap_or_else(|| match transaction.end_to_end_id.as_str() {
+            "NOTPROVIDED" => String::new(),
+            id => id.to_string(),
+        })
+}
+
+// Texts of an entry that are searched
+fn texts(transaction: &Transaction) -> impl Iterator<Item = &str> {
+    [
+        transaction.additional_info.as_str(),
+        transaction.transaction_info.as_str(),
+        transaction.debtor_name.as_str(),
+        transaction.creditor_name.as_str(),
+        transaction.end_to_end_id.as_str(),
+        transaction.cheque_number.as_str(),
+        transaction.bank_tx_code.as_str(),
+    ]
+    .into_iter()
+    .chain(
+        transaction
+            .creditor_reference
+            .iter()
+            .map(|reference| reference.reference.as_str()),
+    )
+    .chain(transaction.remittance_info.iter().map(String::as_str))
+}
@@ -0,0 +1,209 @@
+//! Index of entries converted by earlier runs, so that entries a bank
+//! delivers again in a later statement can be flagged or left out.
+//!
+//! Entries are identified by the IBAN of their account and their
+//! [`entry_key`]: the AcctSvcrRef of the bank, or a hash of the whole entry
+//! where the input has none. The index is a small text file with one line
+//! per entry holding the IBAN, the key and the input the entry was first
+//! converted from, separated by tabs. Entries are only compared with those of
+//! earlier inputs, so identical entries within one statement are kept.
+
+use crate::decimal::Decimal;
+use crate::model::{DateOrDateTime, Statement, Transaction};
+use crate::reference::{ReferenceStyle, generate_transaction_reference};
+use crate::sha1;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const HEADER: &str = "# camtconvert seen entries: IBAN, entry key and first input";
+
+/// What happens to entries already converted from an earlier input
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateAction {
+    /// Keep them, warning about each
+    #[default]
+    Flag,
+    /// Leave them out of the output, warning about each
+    Drop,
+}
+
+/// Entries converted by earlier runs
+#[derive(Debug)]
+pub struct SeenEntries {
+    path: PathBuf,
+    // Input of each IBAN and entry key
+    entries: BTreeMap<(String, String), String>,
+}
+
+impl SeenEntries {
+    /// Load the index from a file. A missing file is read as no entries.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        // Lines that cannot be read only lose their entry
+        let entries = content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let iban = fields.next()?.to_string();
+                let key = fields.next()?.to_string();
+                Some(((iban, key), fields.next()?.to_string()))
+            })
+            .collect();
+        Ok(SeenEntries {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Input the entry with a key was first converted from, if any
+    pub fn first_seen(&self, iban: &str, key: &str) -> Option<&str> {
+        self.entries
+            .get(&(iban.to_string(), key.to_string()))
+            .map(String::as_str)
+    }
+
+    /// Record an entry as converted from `input`, unless it already was
+    pub fn insert(&mut self, iban: &str, key: &str, input: &str) {
+        self.entries
+            .entry((iban.to_string(), key.to_string()))
+            .or_insert_with(|| input.to_string());
+    }
+
+    /// Number of entries in the index
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the index file
+    pub fn save(&self) -> io::Result<()> {
+        let mut content = format!("{}\n", HEADER);
+        for ((iban, key), input) in &self.entries {
+            content.push_str(&format!("{}\t{}\t{}\n", iban, key, input));
+        }
+        fs::write(&self.path, content)
+    }
+}
+
+/// Checks the entries of one input against the index
+#[derive(Debug)]
+pub struct DuplicateCheck<'a> {
+    seen: &'a SeenEntries,
+    action: DuplicateAction,
+    reference: ReferenceStyle,
+    /// One warning for each entry converted before
+    pub warnings: Vec<String>,
+    /// Sum of the amounts of the entries left out, in the account currency,
+    /// `None` if too large to compute
+    pub dropped_total: Option<Decimal>,
+    // IBAN and key of every entry checked
+    keys: Vec<(String, String)>,
+}
+
+impl<'a> DuplicateCheck<'a> {
+    /// Check entries, naming them in warnings by the AcctSvcrRef generated in
+    /// the `reference` style
+    pub fn new(seen: &'a SeenEntries, action: DuplicateAction, reference: ReferenceStyle) -> Self {
+        DuplicateCheck {
+            seen,
+            action,
+            reference,
+            warnings: Vec::new(),
+            dropped_total: Some(Decimal::default()),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Whether an entry is written to the output
+    pub fn keep(&mut self, statement: &Statement, transaction: &Transaction) -> bool {
+        let key = entry_key(transaction);
+        let seen = self.seen.first_seen(&statement.iban, &key);
+        if let Some(input) = seen {
+            let reference = generate_transaction_reference(statement, transaction, self.reference);
+            let dropped = match self.action {
+                DuplicateAction::Flag => "",
+                DuplicateAction::Drop => ", left out",
+            };
+            self.warnings.push(format!(
+                "Entry {} of {} was already converted from {}{}",
+                reference, statement.iban, input, dropped
+            ));
+        }
+        let keep = seen.is_none() || self.action == DuplicateAction::Flag;
+        if !keep {
+            let amount = transaction.account_amount(statement.account_currency());
+            self.dropped_total = self
+                .dropped_total
+                .and_then(|total| total.checked_add(amount));
+        }
+        self.keys.push((statement.iban.clone(), key));
+        keep
+    }
+
+    /// IBAN and key of the entries checked, to be recorded once the output
+    /// has been written
+    pub fn into_keys(self) -> Vec<(String, String)> {
+        self.keys
+    }
+}
+
+/// Key of an entry in the index: `ref:` and the AcctSvcrRef of the bank
+/// where the input has one, or else `sha1:` and a hash of the whole entry
+/// with its dates, references, parties and remittance information. The
+/// references generated for entries only hash the amount, booking date, code
+/// and entry information, so distinct entries can share one.
+pub fn entry_key(transaction: &Transaction) -> String {
+    if !transaction.account_servicer_ref.trim().is_empty() {
+        return format!("ref:{}", transaction.account_servicer_ref.trim());
+    }
+    let date = |date: Option<DateOrDateTime>| date.map_or(String::new(), |date| date.to_string());
+    let fields = [
+        transaction.amount.value.to_string(),
+        transaction.amount.currency.clone(),
+        transaction
+            .credit_debit_ind
+            .map(|ind| ind.as_str().to_string())
+            .unwrap_or_default(),
+        transaction.status.as_str().to_string(),
+        date(transaction.booking_date),
+        date(transaction.value_date),
+        transaction.bank_tx_code.clone(),
+        transaction.additional_info.clone(),
+        transaction.end_to_end_id.clone(),
+        transaction.cheque_number.clone(),
+        transaction
+            .creditor_reference
+            .as_ref()
+            .map(|reference| reference.reference.clone())
+            .unwrap_or_default(),
+        transaction.debtor_name.clone(),
+        transaction.creditor_name.clone(),
+        transaction.transaction_info.clone(),
+    ];
+    // Each field and remittance line ends in 0xff, with whitespace
+    // normalized as banks reformat entries they deliver again
+    let mut data = Vec::new();
+    for field in fields.iter().chain(&transaction.remittance_info) {
+        data.extend_from_slice(normalize_whitespace(field).as_bytes());
+        data.push(0xff);
+    }
+    let hash: String = sha1::digest(&data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha1:{}", hash)
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,828 @@
+//! HTTP interface to the converter, so that other services can convert
+//! documents without running the command line tool.
+//!
+//! `POST /convert` with a camt.053.001.10 document as request body answers
+//! with the converted document. Query parameters select the target
+//! version and override conversion options, see [`crate::query`]:
+//! `/convert?version=08&reference=uuid5&date-format=input`.
+//!
+//! Large documents can be converted without holding the connection open:
+//! `POST /jobs` with the same query parameters queues the document and
+//! answers with the job's URL, `GET /jobs/{id}` tells whether it is done and
+//! `GET /jobs/{id}/result` returns the converted document, see
+//! [`crate::jobs`]. `DELETE /jobs/{id}` removes the job and its result.
+//!
+//! `GET /metrics` answers with counts and durations of the conversions in
+//! the Prometheus text format, see [`crate::metrics`]. `GET /healthz`
+//! answers as long as the server does, and `GET /readyz` tells whether jobs
+//! can be taken, for the probes of orchestrators such as Kubernetes.
+//!
+//! With API keys in [`ServerOptions::api_keys`], every request other than
+//! the probes has to give one of them as `Authorization: Bearer {key}` or
+//! `X-API-Key: {key}` and is answered with `401 Unauthorized` otherwise. The
+//! name of the key is logged with the request.
+//!
+//! Tenants of [`ServerOptions::tenants`], such as subsidiaries with their own
+//! banks, have their own default options. A request is converted with those
+//! of the tenant named in its path, as in `/tenants/{name}/convert`, or else
+//! of the tenant with the name of its API key. A key named like a tenant can
+//! only be used for that tenant.
+//!
+//! Each connection is served by its own thread and closed after one request.
+//! While [`MAX_CONNECTIONS`] connections are served, further ones are
+//! answered with `503 Service Unavailable` right away.
+
+use crate::convert::{Converted, convert_bytes};
+use crate::error::ConvertError;
+use crate::jobs::{JobState, Jobs, MAX_JOBS, MAX_RESULTS};
+use crate::json::Value;
+use crate::matrix::{READERS, WRITERS};
+use crate::metrics::Metrics;
+use crate::query::apply_query;
+use crate::reader::ParseOptions;
+use crate::writer::WriteOptions;
+use crate::{debug, info, warn};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Largest request body accepted, in bytes
+pub const MAX_BODY_SIZE: usize = 64 * 1024 * 1024;
+
+/// Most connections served at a time, each with a thread that may be
+/// converting a document
+pub const MAX_CONNECTIONS: usize = 64;
+
+// Limits of the request line and headers, to bound memory use
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+// Time a client may take to send a request
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Options of requests that do not override them
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    pub parse: ParseOptions,
+    pub write: WriteOptions,
+    /// Keys a client has to give, none to serve anyone
+    pub api_keys: Vec<ApiKey>,
+    pub tenants: Vec<Tenant>,
+}
+
+/// Default options of the requests of one tenant
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    /// Name in paths and of its API key, letters, digits, `-` and `_`
+    pub name: String,
+    pub options: ServerOptions,
+}
+
+/// Read tenants given one per line as `name: query`, where the query sets
+/// options as those of a request and overrides `defaults`, e.g.
+/// `ubs: version=04&servicer-bic=UBSWCHZH80A`. Empty lines and lines
+/// starting with `#` are skipped.
+pub fn parse_tenants(text: &str, defaults: &ServerOptions) -> Result<Vec<Tenant>, String> {
+    let mut tenants: Vec<Tenant> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, query)) = line.split_once(':') else {
+            return Err(format!("Line {}: expected name: query", number + 1));
+        };
+        let name = name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Line {}: invalid tenant name '{}'",
+                number + 1,
+                name
+            ));
+        }
+        if tenants.iter().any(|tenant| tenant.name == name) {
+            return Err(format!(
+                "Line {}: tenant {} is given twice",
+                number + 1,
+                name
+            ));
+        }
+        let mut options = ServerOptions {
+            parse: defaults.parse.clone(),
+            write: defaults.write.clone(),
+            ..ServerOptions::default()
+        };
+        apply_query(query.trim(), &mut options.parse, &mut options.write)
+            .map_err(|e| format!("Line {}: {}", number + 1, e))?;
+        tenants.push(Tenant {
+            name: name.to_string(),
+            options,
+        });
+    }
+    Ok(tenants)
+}
+
+/// A key that lets a client use the server, with a name for the logs
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    pub name: String,
+    pub key: String,
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Read API keys given one per line as `name:key`. Empty lines and lines
+/// starting with `#` are skipped.
+pub fn parse_api_keys(text: &str) -> Result<Vec<ApiKey>, String> {
+    let mut keys: Vec<ApiKey> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, key)) = line.split_once(':') else {
+            return Err(format!("Line {}: expected name:key", number + 1));
+        };
+        let (name, key) = (name.trim(), key.trim());
+        if name.is_empty() || key.is_empty() {
+            return Err(format!(
+                "Line {}: name and key must not be empty",
+                number + 1
+            ));
+        }
+        if keys
+            .iter()
+            .any(|other| other.name == name || other.key == key)
+        {
+            return Err(format!(
+                "Line {}: name or key of {} is given twice",
+                number + 1,
+                name
+            ));
+        }
+        keys.push(ApiKey {
+            name: name.to_string(),
+            key: key.to_string(),
+        });
+    }
+    Ok(keys)
+}
+
+/// A request as received by [`respond`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Path and query, e.g. `/convert?version=08`
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of a header, matching its name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A response to be sent to the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: u16, content_type: &str, body: Vec<u8>) -> Response {
+        Response {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+        }
+    }
+
+    fn text(status: u16, message: impl Into<String>) -> Response {
+        let mut body = message.into().into_bytes();
+        body.push(b'\n');
+        Response::new(status, "text/plain; charset=utf-8", body)
+    }
+
+    fn with_header(mut self, name: &str, value: impl Into<String>) -> Response {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Value of a header, matching its name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn write_to(&self, mut output: impl Write) -> io::Result<()> {
+        write!(
+            output,
+            "HTTP/1.1 {} {}\r\n",
+            self.status,
+            reason(self.status)
+        )?;
+        for (name, value) in &self.headers {
+            write!(output, "{}: {}\r\n", name, value)?;
+        }
+        write!(
+            output,
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            self.body.len()
+        )?;
+        output.write_all(&self.body)?;
+        output.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        409 => "Conflict",
+        422 => "Unprocessable Content",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+/// The requests a server answers, with its queue of jobs
+pub struct Server {
+    options: ServerOptions,
+    jobs: Jobs,
+    metrics: Arc<Metrics>,
+}
+
+impl Server {
+    /// A server converting jobs with one worker thread per processor
+    pub fn new(options: ServerOptions) -> Server {
+        let workers = thread::available_parallelism().map_or(1, usize::from);
+        let metrics = Arc::new(Metrics::new());
+        Server {
+            options,
+            jobs: Jobs::new(workers, Arc::clone(&metrics)),
+            metrics,
+        }
+    }
+
+    /// Name of the API key given with a request, `None` if it gives none or
+    /// an unknown one
+    pub fn client(&self, request: &Request) -> Option<&str> {
+        let given = request
+            .header("Authorization")
+            .and_then(|value| {
+                let (scheme, key) = value.split_once(' ')?;
+                scheme.eq_ignore_ascii_case("Bearer").then_some(key.trim())
+            })
+            .or_else(|| request.header("X-API-Key"))?;
+        self.options
+            .api_keys
+            .iter()
+            .find(|key| same_key(key.key.as_bytes(), given.as_bytes()))
+            .map(|key| key.name.as_str())
+    }
+
+    /// Answer a request, including those about jobs
+    pub fn respond(&self, request: &Request) -> Response {
+        let (path, query) = split_target(&request.target);
+        // Probes are answered without a key, as orchestrators send none
+        let probe = path == "/healthz" || path == "/readyz";
+        let client = self.client(request);
+        if !self.options.api_keys.is_empty() && !probe && client.is_none() {
+            return Response::text(401, "Give an API key as Authorization: Bearer {key}")
+                .with_header("WWW-Authenticate", "Bearer");
+        }
+        let (tenant, path) = match self.tenant(client, path) {
+            Ok(found) => found,
+            Err(response) => return response,
+        };
+        let options = tenant.map_or(&self.options, |tenant| &tenant.options);
+        // Jobs are only shown to the API key, or without keys the tenant,
+        // that submitted them
+        let owner = client.or(tenant.map(|tenant| tenant.name.as_str()));
+        if path == "/jobs" {
+            return self.submit(request, options, query, owner);
+        }
+        if probe {
+            if request.method != "GET" {
+                return method_not_allowed("GET");
+            }
+            return if path == "/healthz" {
+                json_response(200, &Value::object().with("status", "ok"))
+            } else {
+                self.readiness()
+            };
+        }
+        if path == "/metrics" {
+            if request.method != "GET" {
+                return method_not_allowed("GET");
+            }
+            return Response::new(
+                200,
+                "text/plain; version=0.0.4; charset=utf-8",
+                self.metrics.render().into_bytes(),
+            );
+        }
+        let Some(job) = path.strip_prefix("/jobs/") else {
+            return convert_request(request, path, query, options, Some(&self.metrics));
+        };
+        match job.split_once('/') {
+            None => self.job(request, job, owner),
+            Some((id, "result")) if request.method == "GET" => self.result(id, owner),
+            Some((_, "result")) => method_not_allowed("GET"),
+            Some(_) => Response::text(404, format!("No such endpoint: {}", path)),
+        }
+    }
+
+    // The tenant a request is for, by the prefix of its path or else the
+    // name of its API key, and the path without the prefix
+    fn tenant<'a>(
+        &self,
+        client: Option<&str>,
+        path: &'a str,
+    ) -> Result<(Option<&Tenant>, &'a str), Response> {
+        let find = |name: &str| {
+            self.options
+                .tenants
+                .iter()
+                .find(|tenant| tenant.name == name)
+        };
+        let bound = client.and_then(find);
+        let Some(rest) = path.strip_prefix("/tenants/") else {
+            return Ok((bound, path));
+        };
+        let (name, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let Some(tenant) = find(name) else {
+            return Err(Response::text(404, format!("No such tenant: {}", name)));
+        };
+        if let Some(bound) = bound
+            && bound.name != tenant.name
+        {
+            return Err(Response::text(
+                403,
+                format!("The API key is for tenant {}, not {}", bound.name, name),
+            ));
+        }
+        Ok((Some(tenant), path))
+    }
+
+    fn submit(
+        &self,
+        request: &Request,
+        options: &ServerOptions,
+        query: &str,
+        owner: Option<&str>,
+    ) -> Response {
+        if request.method != "POST" {
+            return method_not_allowed("POST");
+        }
+        let mut options = options.clone();
+        if let Err(message) = apply_query(query, &mut options.parse, &mut options.write) {
+            return Response::text(400, message);
+        }
+        let Some(id) = self.jobs.submit(request.body.clone(), options, owner) else {
+            return Response::text(503, "Too many jobs, try again later")
+                .with_header("Retry-After", "60");
+        };
+        let body = Value::object()
+            .with("id", id.as_str())
+            .with("status", JobState::Queued.as_str())
+            .with("url", format!("/jobs/{}", id))
+            .with("result_url", format!("/jobs/{}/result", id));
+        json_response(202, &body).with_header("Location", format!("/jobs/{}", id))
+    }
+
+    // Ready while a worker runs and another job can be queued, with the
+    // status of the workers and the versions that can be converted
+    fn readiness(&self) -> Response {
+        let pool = self.jobs.status();
+        let body = Value::object()
+            .with(
+                "status",
+                if pool.is_ready() {
+                    "ready"
+                } else {
+                    "not ready"
+                },
+            )
+            .with("workers", pool.workers)
+            .with("workers_alive", pool.alive)
+            .with("jobs_queued", pool.queued)
+            .with("jobs_running", pool.running)
+            .with("jobs_done", pool.done)
+            .with("jobs", pool.jobs)
+            .with("max_jobs", MAX_JOBS)
+            .with("max_results", MAX_RESULTS)
+            .with(
+                "input_versions",
+                READERS
+                    .iter()
+                    .map(|reader| reader.version)
+                    .collect::<Vec<_>>(),
+            )
+            .with(
+                "output_versions",
+                WRITERS
+                    .iter()
+                    .map(|writer| writer.version)
+                    .collect::<Vec<_>>(),
+            );
+        json_response(if pool.is_ready() { 200 } else { 503 }, &body)
+    }
+
+    // Status of a job, or its deletion
+    fn job(&self, request: &Request, id: &str, owner: Option<&str>) -> Response {
+        match request.method.as_str() {
+            "GET" => self
+                .jobs
+                .inspect(id, owner, |state| {
+                    let mut status = Value::object()
+                        .with("id", id)
+                        .with("status", state.as_str());
+                    match state {
+                        JobState::Done(converted) => {
+                            status = status
+                                .with("result_url", format!("/jobs/{}/result", id))
+                                .with("warnings", converted.warnings.clone());
+                        }
+                        JobState::Failed(e) => status = status.with("error", e.to_string()),
+                        JobState::Queued | JobState::Running => {}
+                    }
+                    json_response(200, &status)
+                })
+                .unwrap_or_else(|| no_such_job(id)),
+            "DELETE" if self.jobs.remove(id, owner) => Response {
+                status: 204,
+                headers: Vec::new(),
+                body: Vec::new(),
+            },
+            "DELETE" => no_such_job(id),
+            _ => method_not_allowed("GET, DELETE"),
+        }
+    }
+
+    // The converted document of a job, or why it failed
+    fn result(&self, id: &str, owner: Option<&str>) -> Response {
+        self.jobs
+            .inspect(id, owner, |state| match state {
+                JobState::Done(converted) => converted_response(converted),
+                JobState::Failed(e) => error_response(e),
+                JobState::Queued | JobState::Running => Response::text(
+                    409,
+                    format!(
+                        "Job {} is {}, poll /jobs/{} until it is done",
+                        id,
+                        state.as_str(),
+                        id
+                    ),
+                ),
+            })
+            .unwrap_or_else(|| no_such_job(id))
+    }
+}
+
+// Compare keys in a time independent of where they differ, so that a key
+// cannot be guessed from response times
+fn same_key(expected: &[u8], given: &[u8]) -> bool {
+    expected.len() == given.len()
+        && expected
+            .iter()
+            .zip(given)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn json_response(status: u16, body: &Value) -> Response {
+    Response::new(
+        status,
+        "application/json",
+        format!("{}\n", body).into_bytes(),
+    )
+}
+
+fn no_such_job(id: &str) -> Response {
+    Response::text(404, format!("No such job: {}", id))
+}
+
+fn method_not_allowed(allowed: &str) -> Response {
+    Response::text(405, format!("Use {} for this endpoint", allowed)).with_header("Allow", allowed)
+}
+
+/// Serve requests on `listener` until accepting connections fails
+pub fn serve(listener: TcpListener, options: ServerOptions) -> io::Result<()> {
+    let server = Arc::new(Server::new(options));
+    let connections = Arc::new(AtomicUsize::new(0));
+    loop {
+        let (stream, peer) = listener.accept()?;
+        if connections.fetch_add(1, Ordering::AcqRel) >= MAX_CONNECTIONS {
+            connections.fetch_sub(1, Ordering::AcqRel);
+            warn!("Too many connections, turning away {}", peer);
+            let _ = Response::text(503, "Too many connections, try again later")
+                .with_header("Retry-After", "1")
+                .write_to(&stream);
+            continue;
+        }
+        let slot = Slot(Arc::clone(&connections));
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            let _slot = slot;
+            if let Err(e) = handle_connection(stream, &server) {
+                debug!("Connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+// A connection being served, counted until its thread ends
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn handle_connection(stream: TcpStream, server: &Server) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let peer = stream.peer_addr()?;
+    let mut reader = BufReader::new(&stream);
+    let response = match read_request(&mut reader, &stream) {
+        Ok(request) => {
+            let response = server.respond(&request);
+            match server.client(&request) {
+                Some(name) => info!(
+                    "{} {} from {} with key {}: {}",
+                    request.method, request.target, peer, name, response.status
+                ),
+                None => info!(
+                    "{} {} from {}: {}",
+                    request.method, request.target, peer, response.status
+                ),
+            }
+            response
+        }
+        Err(RequestError::Io(e)) => return Err(e),
+        Err(RequestError::Invalid(response)) => {
+            info!("Invalid request from {}: {}", peer, response.status);
+            response
+        }
+    };
+    response.write_to(&stream)
+}
+
+enum RequestError {
+    // The connection failed, nothing can be answered
+    Io(io::Error),
+    // The request cannot be read, answered with this response
+    Invalid(Response),
+}
+
+impl From<io::Error> for RequestError {
+    fn from(e: io::Error) -> Self {
+        RequestError::Io(e)
+    }
+}
+
+fn invalid(status: u16, message: &str) -> RequestError {
+    RequestError::Invalid(Response::text(status, message))
+}
+
+// Read an HTTP/1.1 request. A client waiting for `100 Continue` before
+// sending the body is told to go on through `interim`.
+fn read_request(
+    reader: &mut impl BufRead,
+    mut interim: impl Write,
+) -> Result<Request, RequestError> {
+    let request_line = read_line(reader)?;
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid(400, "Malformed request line"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(invalid(400, "Only HTTP/1.x is supported"));
+    }
+
+    let mut content_length = None;
+    let mut chunked = false;
+    let mut expect_continue = false;
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(invalid(431, "Too many header fields"));
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(invalid(400, "Malformed header field"));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            let length = value
+                .parse::<usize>()
+                .map_err(|_| invalid(400, "Invalid Content-Length"))?;
+            content_length = Some(length);
+        } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+            if !chunked {
+                return Err(invalid(400, "Only chunked transfer encoding is supported"));
+            }
+        } else if name.eq_ignore_ascii_case("Expect") {
+            expect_continue = value.eq_ignore_ascii_case("100-continue");
+        }
+        headers.push((name.to_string(), value.to_string()));
+    }
+
+    let body = if chunked {
+        continue_if_expected(&mut interim, expect_continue)?;
+        read_chunked(reader)?
+    } else if let Some(length) = content_length {
+        if length > MAX_BODY_SIZE {
+            return Err(body_too_large());
+        }
+        continue_if_expected(&mut interim, expect_continue)?;
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        body
+    } else if method == "POST" {
+        return Err(invalid(411, "Content-Length is required"));
+    } else {
+        Vec::new()
+    };
+
+    Ok(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        headers,
+        body,
+    })
+}
+
+fn body_too_large() -> RequestError {
+    invalid(
+        413,
+        &format!("Request body is larger than {} bytes", MAX_BODY_SIZE),
+    )
+}
+
+fn continue_if_expected(mut interim: impl Write, expected: bool) -> io::Result<()> {
+    if expected {
+        interim.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        interim.flush()?;
+    }
+    Ok(())
+}
+
+// A line without its CRLF ending
+fn read_line(reader: &mut impl BufRead) -> Result<String, RequestError> {
+    let mut line = Vec::new();
+    reader
+        .take(MAX_LINE_LENGTH + 2)
+        .read_until(b'\n', &mut line)?;
+    if line.last() != Some(&b'\n') {
+        return Err(if line.len() as u64 > MAX_LINE_LENGTH {
+            invalid(431, "Request line or header field too long")
+        } else {
+            RequestError::Io(io::ErrorKind::UnexpectedEof.into())
+        });
+    }
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line).map_err(|_| invalid(400, "Request line or header is not UTF-8"))
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> Result<Vec<u8>, RequestError> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        // Chunk extensions after ';' are ignored
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size =
+            usize::from_str_radix(size, 16).map_err(|_| invalid(400, "Invalid chunk size"))?;
+        if size == 0 {
+            break;
+        }
+        let start = body.len();
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= MAX_BODY_SIZE)
+            .ok_or_else(body_too_large)?;
+        body.resize(end, 0);
+        reader.read_exact(&mut body[start..])?;
+        if !read_line(reader)?.is_empty() {
+            return Err(invalid(400, "Chunk is longer than its size"));
+        }
+    }
+    // Trailer fields are ignored
+    while !read_line(reader)?.is_empty() {}
+    Ok(body)
+}
+
+/// Answer a request to convert a document. Requests about jobs and metrics
+/// are answered by [`Server::respond`].
+pub fn respond(request: &Request, options: &ServerOptions) -> Response {
+    let (path, query) = split_target(&request.target);
+    convert_request(request, path, query, options, None)
+}
+
+// Path and query of a request target
+fn split_target(target: &str) -> (&str, &str) {
+    target.split_once('?').unwrap_or((target, ""))
+}
+
+fn convert_request(
+    request: &Request,
+    path: &str,
+    query: &str,
+    options: &ServerOptions,
+    metrics: Option<&Metrics>,
+) -> Response {
+    if path != "/convert" {
+        return Response::text(404, format!("No such endpoint: {}", path));
+    }
+    if request.method != "POST" {
+        return Response::text(405, "Use POST to convert a document").with_header("Allow", "POST");
+    }
+    let mut options = options.clone();
+    if let Err(message) = apply_query(query, &mut options.parse, &mut options.write) {
+        return Response::text(400, message);
+    }
+    convert(&request.body, &options, metrics)
+}
+
+fn convert(body: &[u8], options: &ServerOptions, metrics: Option<&Metrics>) -> Response {
+    let started = Instant::now();
+    let result = convert_bytes(body, &options.parse, &options.write);
+    if let Some(metrics) = metrics {
+        metrics.record(result.as_ref(), started.elapsed());
+    }
+    let converted = match result {
+        Ok(converted) => converted,
+        Err(e) => return error_response(&e),
+    };
+    for warning in &converted.warnings {
+        warn!("{}", warning);
+    }
+    converted_response(&converted)
+}
+
+fn converted_response(converted: &Converted) -> Response {
+    Response::new(
+        200,
+        "application/xml; charset=utf-8",
+        converted.output.clone(),
+    )
+    .with_header("X-Warning-Count", converted.warnings.len().to_string())
+}
+
+// Documents with invalid data are unprocessable, anything else about the
+// body is a bad request
+fn error_response(error: &ConvertError) -> Response {
+    let status = match error.kind() {
+        ConvertError::MissingField(_)
+        | ConvertError::InvalidAmount { .. }
+        | ConvertError::InvalidValue { .. }
+        | ConvertError::FailedCheck { .. }
+        | ConvertError::Overflow(_) => 422,
+        ConvertError::Internal(_) => 500,
+        _ => 400,
+    };
+    Response::text(status, error.to_string())
+}
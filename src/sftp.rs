@@ -0,0 +1,277 @@
+//! Download of statement files from a bank's SFTP drop with the OpenSSH
+//! `sftp` client.
+//!
+//! Each operation runs `sftp` in batch mode, so authentication must work
+//! without a prompt, through an SSH agent, a key without passphrase or the
+//! user's SSH configuration. The host key must already be known.
+
+use chrono::{Datelike, NaiveDate};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+/// Extensions of files that can be converted: documents, archives,
+/// compressed and encrypted documents
+const EXTENSIONS: &[&str] = &["xml", "zip", "gz", "pgp", "gpg", "asc"];
+
+/// A remote folder, `sftp://[user@]host[:port]/path`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SftpUrl {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    /// Absolute path of the folder, `/~/` for paths in the home folder
+    pub path: String,
+}
+
+impl FromStr for SftpUrl {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("Invalid SFTP URL {}: {}", url, reason);
+        let rest = url
+            .strip_prefix("sftp://")
+            .ok_or_else(|| invalid("expected sftp://[user@]host[:port]/path"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, "/"),
+        };
+        let (user, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host),
+            None => (None, authority),
+        };
+        let (host, port) = match host.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse().map_err(|_| invalid("invalid port"))?;
+                (host, Some(port))
+            }
+            None => (host, None),
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        if user.as_deref() == Some("") {
+            return Err(invalid("empty user name"));
+        }
+        // sftp paths are relative to the home folder, so /~/ leads there
+        let path = match path.strip_prefix("/~") {
+            Some(home) => format!(".{}", home),
+            None => path.to_string(),
+        };
+        Ok(SftpUrl {
+            user,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+}
+
+impl fmt::Display for SftpUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("sftp://")?;
+        if let Some(user) = &self.user {
+            write!(f, "{}@", user)?;
+        }
+        f.write_str(&self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        match self.path.strip_prefix('.') {
+            Some(home) => write!(f, "/~{}", home),
+            None => f.write_str(&self.path),
+        }
+    }
+}
+
+/// A file in the remote folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteFile {
+    pub name: String,
+    pub size: u64,
+    /// Day of the last modification, in the time zone of the server
+    pub modified: NaiveDate,
+}
+
+/// Connection settings of the remote folder
+#[derive(Debug, Clone)]
+pub struct Sftp {
+    pub url: SftpUrl,
+    /// Private key to log in with, instead of those of the SSH configuration
+    pub identity: Option<PathBuf>,
+}
+
+impl Sftp {
+    /// Files in the remote folder that can be converted
+    pub fn list(&self, today: NaiveDate) -> io::Result<Vec<RemoteFile>> {
+        let listing = self.run(&format!("ls -l {}", quote(&self.url.path)))?;
+        Ok(parse_listing(&listing, today)
+            .into_iter()
+            .filter(|file| is_statement_file(&file.name))
+            .collect())
+    }
+
+    /// Download `file` into `dir`. The file is written under a temporary
+    /// name first, so an interrupted download leaves no partial file behind.
+    pub fn download(&self, file: &RemoteFile, dir: &Path) -> io::Result<PathBuf> {
+        let path = dir.join(&file.name);
+        let partial = dir.join(format!(".{}.part", file.name));
+        let result = self
+            .run(&format!(
+                "get {} {}",
+                quote(&self.remote_path(&file.name)),
+                quote(&partial.to_string_lossy())
+            ))
+            .and_then(|_| {
+                let size = fs::metadata(&partial)?.len();
+                if size != file.size {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "Downloaded {} bytes of {}, expected {}",
+                            size, file.name, file.size
+                        ),
+                    ));
+                }
+                fs::rename(&partial, &path)
+            });
+        if result.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+        result.map(|()| path)
+    }
+
+    /// Move a file to another remote folder
+    pub fn archive(&self, name: &str, folder: &str) -> io::Result<()> {
+        let target = format!("{}/{}", folder.trim_end_matches('/'), name);
+        self.run(&format!(
+            "rename {} {}",
+            quote(&self.remote_path(name)),
+            quote(&target)
+        ))
+        .map(drop)
+    }
+
+    /// Delete a file from the remote folder
+    pub fn delete(&self, name: &str) -> io::Result<()> {
+        self.run(&format!("rm {}", quote(&self.remote_path(name))))
+            .map(drop)
+    }
+
+    fn remote_path(&self, name: &str) -> String {
+        format!("{}/{}", self.url.path.trim_end_matches('/'), name)
+    }
+
+    // Run one batch command, returning its output
+    fn run(&self, batch: &str) -> io::Result<String> {
+        let mut command = Command::new("sftp");
+        command.args(["-q", "-b", "-", "-o", "BatchMode=yes"]);
+        if let Some(port) = self.url.port {
+            command.arg("-P").arg(port.to_string());
+        }
+        if let Some(identity) = &self.identity {
+            command.arg("-i").arg(identity);
+        }
+        let destination = match &self.url.user {
+            Some(user) => format!("{}@{}", user, self.url.host),
+            None => self.url.host.clone(),
+        };
+        // Keep a host name starting with - from being read as an option
+        command.arg("--").arg(destination);
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("Could not run sftp: {}", e)))?;
+        // Closed at the end of the block, so the input ends
+        {
+            let mut stdin = child.stdin.take().expect("stdin is piped");
+            io::Write::write_all(&mut stdin, format!("{}\n", batch).as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "sftp {} failed: {}",
+                batch,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Whether a file name has the extension of a file that can be converted.
+/// Hidden files, such as partial downloads, are left out.
+pub fn is_statement_file(name: &str) -> bool {
+    !name.starts_with('.')
+        && name.rsplit_once('.').is_some_and(|(_, extension)| {
+            EXTENSIONS
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
+
+/// Regular files of the output of `ls -l`, such as
+/// `-rw-r--r--  1 bank  bank  5311 Jun 20 10:00 statement.xml`.
+///
+/// Recent files show a time instead of the year; they are placed in the last
+/// year in which the day is not after `today`, give or take a day.
+pub fn parse_listing(listing: &str, today: NaiveDate) -> Vec<RemoteFile> {
+    listing
+        .lines()
+        .filter(|line| line.starts_with('-'))
+        .filter_map(|line| parse_listing_line(line, today))
+        .collect()
+}
+
+fn parse_listing_line(line: &str, today: NaiveDate) -> Option<RemoteFile> {
+    let mut rest = line;
+    let mut fields = Vec::new();
+    // Mode, links, owner, group, size, month, day and time or year come
+    // before the name, which may contain spaces
+    for _ in 0..8 {
+        let trimmed = rest.trim_start();
+        let end = trimmed.find(char::is_whitespace)?;
+        fields.push(&trimmed[..end]);
+        rest = &trimmed[end..];
+    }
+    // Some servers list the path of the folder along with the name
+    let name = rest.trim_start().rsplit('/').next()?;
+    if name.is_empty() {
+        return None;
+    }
+    let size = fields[4].parse().ok()?;
+    let month = MONTHS.iter().position(|month| *month == fields[5])? as u32 + 1;
+    let day = fields[6].parse().ok()?;
+    let modified = if fields[7].contains(':') {
+        // A day of slack for servers in time zones ahead of ours
+        let latest = today.succ_opt()?;
+        NaiveDate::from_ymd_opt(today.year(), month, day)
+            .filter(|date| *date <= latest)
+            .or_else(|| NaiveDate::from_ymd_opt(today.year() - 1, month, day))?
+    } else {
+        NaiveDate::from_ymd_opt(fields[7].parse().ok()?, month, day)?
+    };
+    Some(RemoteFile {
+        name: name.to_string(),
+        size,
+        modified,
+    })
+}
+
+const MONTHS: &[&str] = &[
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Quote an argument of a batch command
+fn quote(argument: &str) -> String {
+    format!(
+        "\"{}\"",
+        argument.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
@@ -0,0 +1,15 @@
+// Literals of SQL statements written for database clients
+
+// A string literal, with quotes doubled
+pub(crate) fn literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+// A string literal, or NULL for empty text
+pub(crate) fn optional(text: &str) -> String {
+    if text.is_empty() {
+        "NULL".to_string()
+    } else {
+        literal(text)
+    }
+}
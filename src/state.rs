@@ -0,0 +1,109 @@
+//! Record of previous conversions, so that documents that have not changed
+//! since they were last converted can be skipped.
+//!
+//! The state is kept in a small text file in the output folder. Each line
+//! holds the name of an input document, its checksum, a fingerprint of the
+//! options it was converted with and the name of the output file, separated
+//! by tabs.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the state file in the output folder
+pub const STATE_FILE_NAME: &str = ".camtconvert-state";
+
+const HEADER: &str = "# camtconvert conversion state, safe to delete";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Record {
+    input: String,
+    input_checksum: String,
+    options: String,
+    output: String,
+}
+
+/// Previous conversions into one output folder
+#[derive(Debug)]
+pub struct ConversionState {
+    path: PathBuf,
+    records: Vec<Record>,
+}
+
+impl ConversionState {
+    /// Load the state of an output folder. A missing state file is read as
+    /// no previous conversions.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(STATE_FILE_NAME);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        // Lines that cannot be read only lose their record
+        let records = content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.splitn(4, '\t');
+                Some(Record {
+                    input: fields.next()?.to_string(),
+                    input_checksum: fields.next()?.to_string(),
+                    options: fields.next()?.to_string(),
+                    output: fields.next()?.to_string(),
+                })
+            })
+            .collect();
+        Ok(ConversionState { path, records })
+    }
+
+    /// Output of `input` if it exists and was converted from an input with
+    /// the same checksum and with the same options
+    pub fn unchanged_output(
+        &self,
+        input: &str,
+        input_checksum: &str,
+        options: &str,
+    ) -> Option<PathBuf> {
+        let record = self.records.iter().find(|record| {
+            record.input == input
+                && record.input_checksum == input_checksum
+                && record.options == options
+        })?;
+        let output_path = self.path.with_file_name(&record.output);
+        output_path.exists().then_some(output_path)
+    }
+
+    /// Record a conversion of `input` to `output_path`, replacing earlier
+    /// ones of the same input or to the same output
+    pub fn record(&mut self, input: &str, output_path: &Path, input_checksum: &str, options: &str) {
+        let output = file_name(output_path);
+        self.records
+            .retain(|record| record.input != input && record.output != output);
+        self.records.push(Record {
+            input: input.to_string(),
+            input_checksum: input_checksum.to_string(),
+            options: options.to_string(),
+            output,
+        });
+    }
+
+    /// Write the state file
+    pub fn save(&self) -> io::Result<()> {
+        let mut content = format!("{}\n", HEADER);
+        for record in &self.records {
+            content.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                record.input, record.input_checksum, record.options, record.output
+            ));
+        }
+        fs::write(&self.path, content)
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
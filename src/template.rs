@@ -0,0 +1,241 @@
+//! Output file names taken from the statement, such as
+//! `{iban}_{to_date}_{version}.xml`, and folders outputs are filed in, such
+//! as `2025/06/`.
+//!
+//! Templates are checked when they are parsed, so that an unknown placeholder
+//! is reported before any input is read.
+
+use crate::model::Statement;
+use crate::writer::OutputVersion;
+use chrono::{DateTime, FixedOffset};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Placeholders that can be used in a template
+pub const PLACEHOLDERS: &[&str] = &[
+    "iban",
+    "currency",
+    "id",
+    "owner",
+    "created_date",
+    "from_date",
+    "to_date",
+    "version",
+    "input",
+];
+
+/// Written for placeholders without a value in the statement
+const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Iban,
+    Currency,
+    Id,
+    Owner,
+    CreatedDate,
+    FromDate,
+    ToDate,
+    Version,
+    Input,
+}
+
+impl Placeholder {
+    fn from_name(name: &str) -> Option<Placeholder> {
+        Some(match name {
+            "iban" => Placeholder::Iban,
+            "currency" => Placeholder::Currency,
+            "id" => Placeholder::Id,
+            "owner" => Placeholder::Owner,
+            "created_date" => Placeholder::CreatedDate,
+            "from_date" => Placeholder::FromDate,
+            "to_date" => Placeholder::ToDate,
+            "version" => Placeholder::Version,
+            "input" => Placeholder::Input,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// Template of output file names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+/// A template that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError(String);
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl FromStr for NameTemplate {
+    type Err = TemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        if template.contains(['/', '\\']) {
+            return Err(TemplateError(
+                "Name template must be a file name, without folders".to_string(),
+            ));
+        }
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..].find('}').ok_or_else(|| {
+                TemplateError(format!(
+                    "Unclosed placeholder in name template: {}",
+                    template
+                ))
+            })?;
+            let name = &rest[start + 1..start + end];
+            let placeholder = Placeholder::from_name(name).ok_or_else(|| {
+                TemplateError(format!(
+                    "Unknown placeholder {{{}}} in name template, expected one of: {}",
+                    name,
+                    PLACEHOLDERS.join(", ")
+                ))
+            })?;
+            parts.push(Part::Placeholder(placeholder));
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        if parts.is_empty() {
+            return Err(TemplateError("Name template is empty".to_string()));
+        }
+
+        Ok(NameTemplate {
+            template: template.to_string(),
+            parts,
+        })
+    }
+}
+
+impl fmt::Display for NameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl NameTemplate {
+    /// File name for a statement read from an input named `input_stem` and
+    /// written in `version`.
+    ///
+    /// Dates are written as `YYYY-MM-DD`; a statement without a period uses
+    /// its creation date instead. Placeholders without a value are written as
+    /// `unknown`, and characters that cannot be used in file names are
+    /// replaced by `_`.
+    pub fn render(
+        &self,
+        statement: &Statement,
+        input_stem: &str,
+        version: OutputVersion,
+    ) -> String {
+        let created = statement.creation_datetime;
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(placeholder) => {
+                    let value = match placeholder {
+                        Placeholder::Iban => statement.iban.clone(),
+                        Placeholder::Currency => statement.currency.clone(),
+                        Placeholder::Id => statement.id.clone(),
+                        Placeholder::Owner => statement.owner_name.clone(),
+                        Placeholder::CreatedDate => date(created),
+                        Placeholder::FromDate => date(statement.from_datetime.or(created)),
+                        Placeholder::ToDate => date(statement.to_datetime.or(created)),
+                        Placeholder::Version => version.number().to_string(),
+                        Placeholder::Input => input_stem.to_string(),
+                    };
+                    sanitize(value.trim())
+                }
+            })
+            .collect()
+    }
+}
+
+/// Folders outputs are filed in, below the folder of their input
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputLayout {
+    /// Next to the input
+    #[default]
+    Flat,
+    /// `YYYY/MM/` of the start of the statement period
+    ByDate,
+    /// `IBAN/YYYY/` of the account and the start of the statement period
+    ByAccount,
+}
+
+impl OutputLayout {
+    /// Folder of the output of a statement, relative to the folder of the
+    /// input. A statement without a period is filed by its creation date,
+    /// and values that are missing are written as `unknown`.
+    pub fn folder(self, statement: &Statement) -> PathBuf {
+        let start = statement
+            .from_datetime
+            .or(statement.to_datetime)
+            .or(statement.creation_datetime);
+        let part = |format: &str| {
+            start.map_or(UNKNOWN.to_string(), |start| {
+                start.format(format).to_string()
+            })
+        };
+        match self {
+            OutputLayout::Flat => PathBuf::new(),
+            OutputLayout::ByDate => [part("%Y"), part("%m")].iter().collect(),
+            OutputLayout::ByAccount => {
+                let iban: String = statement
+                    .iban
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                let iban = match sanitize(&iban) {
+                    iban if iban.is_empty() => UNKNOWN.to_string(),
+                    iban => iban,
+                };
+                [iban, part("%Y")].iter().collect()
+            }
+        }
+    }
+}
+
+fn date(datetime: Option<DateTime<FixedOffset>>) -> String {
+    datetime
+        .map(|datetime| datetime.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+// Keep values from adding folders or characters some file systems reject
+fn sanitize(value: &str) -> String {
+    if value.chars().all(|c| c == '.') {
+        return UNKNOWN.to_string();
+    }
+    value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
@@ -0,0 +1,508 @@
+//! Time zones for timestamps written without a UTC offset.
+//!
+//! Some banks leave the offset out of `CreDtTm` and similar timestamps. These
+//! are read as local time of a [`TimeZone`], Swiss time by default, with the
+//! daylight saving time of the date in question applied. Named zones are read
+//! from the system time zone database (`/usr/share/zoneinfo`, or the folder
+//! in `TZDIR`).
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+// Rule of Swiss time since 1981, so the default needs no time zone database
+const DEFAULT_NAME: &str = "Europe/Zurich";
+const DEFAULT_RULE: &str = "CET-1CEST,M3.5.0,M10.5.0/3";
+
+const SECONDS_PER_DAY: i64 = 24 * 3600;
+
+/// Time zone of local timestamps
+#[derive(Clone)]
+pub struct TimeZone {
+    name: String,
+    kind: Kind,
+}
+
+#[derive(Clone)]
+enum Kind {
+    Fixed(i32),
+    Rule(Rule),
+    Zone(Zone),
+}
+
+// A zone of the time zone database: offsets changing at the transitions
+// (UTC timestamps), and a rule for the times after the last one
+#[derive(Clone)]
+struct Zone {
+    initial: i32,
+    transitions: Vec<(i64, i32)>,
+    rule: Option<Rule>,
+}
+
+// POSIX TZ rule, such as CET-1CEST,M3.5.0,M10.5.0/3. Offsets are in seconds
+// east of UTC.
+#[derive(Clone)]
+struct Rule {
+    standard: i32,
+    daylight: Option<DaylightSaving>,
+}
+
+#[derive(Clone)]
+struct DaylightSaving {
+    offset: i32,
+    start: Change,
+    end: Change,
+}
+
+// Date and local time of the change to or from daylight saving time
+#[derive(Clone)]
+struct Change {
+    day: RuleDay,
+    time: i64,
+}
+
+#[derive(Clone)]
+enum RuleDay {
+    // Jn: day 1 to 365, February 29 is never counted
+    Julian(u32),
+    // n: day 0 to 365, February 29 is counted in leap years
+    DayOfYear(u32),
+    // Mm.w.d: day d (0 is Sunday) of week w (5 is the last) of month m
+    Month { month: u32, week: u32, weekday: u32 },
+}
+
+impl Default for TimeZone {
+    /// Swiss time, with daylight saving time from the last Sunday of March to
+    /// the last Sunday of October
+    fn default() -> Self {
+        TimeZone {
+            name: DEFAULT_NAME.to_string(),
+            kind: Kind::Rule(parse_rule(DEFAULT_RULE).expect("valid default rule")),
+        }
+    }
+}
+
+impl fmt::Debug for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TimeZone").field(&self.name).finish()
+    }
+}
+
+impl fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = io::Error;
+
+    /// Read a fixed offset such as `+01:00` or `Z`, or the name of a zone of
+    /// the time zone database such as `Europe/Zurich`
+    fn from_str(text: &str) -> io::Result<Self> {
+        match parse_fixed_offset(text) {
+            Some(offset) => Ok(TimeZone {
+                name: text.to_string(),
+                kind: Kind::Fixed(offset),
+            }),
+            None => TimeZone::named(text),
+        }
+    }
+}
+
+impl TimeZone {
+    /// A time zone that is always `offset` from UTC
+    pub fn fixed(offset: FixedOffset) -> Self {
+        TimeZone {
+            name: offset.to_string(),
+            kind: Kind::Fixed(offset.local_minus_utc()),
+        }
+    }
+
+    /// Load a zone of the time zone database, e.g. `Europe/Zurich`
+    pub fn named(name: &str) -> io::Result<Self> {
+        let is_valid = !name.is_empty()
+            && name
+                .split('/')
+                .all(|part| !part.is_empty() && part != "." && part != "..");
+        if !is_valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid time zone name: {}", name),
+            ));
+        }
+
+        // Without a database only the default zone is known, by its rule
+        #[cfg(not(feature = "fs"))]
+        if name == DEFAULT_NAME {
+            return Ok(TimeZone::default());
+        }
+        let data = read_zone_file(name)?;
+        let zone = parse_tzif(&data).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid time zone file for {}", name),
+            )
+        })?;
+        Ok(TimeZone {
+            name: name.to_string(),
+            kind: Kind::Zone(zone),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Offset from UTC at a point in time
+    pub fn offset_at(&self, utc: DateTime<FixedOffset>) -> FixedOffset {
+        offset(self.offset_seconds(utc.timestamp()))
+    }
+
+    /// Attach the offset of this zone to a local time.
+    ///
+    /// A time that occurs twice when clocks go back is read as the first,
+    /// daylight saving one. A time skipped when clocks go forward is read
+    /// with the offset before the change, as 02:30+01:00 for Swiss time,
+    /// which is 03:30 summer time.
+    pub fn from_local(&self, local: NaiveDateTime) -> DateTime<FixedOffset> {
+        let as_utc = local.and_utc().timestamp();
+        let before = self.offset_seconds(as_utc - SECONDS_PER_DAY);
+        let after = self.offset_seconds(as_utc + SECONDS_PER_DAY);
+
+        // An offset is right if the instant it gives has that offset
+        let valid = [before, after]
+            .into_iter()
+            .filter(|&offset| self.offset_seconds(as_utc - offset as i64) == offset)
+            .max();
+        let offset = offset(valid.unwrap_or(before));
+        local
+            .and_local_timezone(offset)
+            .single()
+            .expect("fixed offsets are unambiguous")
+    }
+
+    fn offset_seconds(&self, utc: i64) -> i32 {
+        match &self.kind {
+            Kind::Fixed(offset) => *offset,
+            Kind::Rule(rule) => rule.offset_at(utc),
+            Kind::Zone(zone) => zone.offset_at(utc),
+        }
+    }
+}
+
+fn offset(seconds: i32) -> FixedOffset {
+    FixedOffset::east_opt(seconds).unwrap_or_else(|| FixedOffset::east_opt(0).expect("UTC"))
+}
+
+impl Zone {
+    fn offset_at(&self, utc: i64) -> i32 {
+        let index = self.transitions.partition_point(|&(time, _)| time <= utc);
+        if index == self.transitions.len()
+            && let Some(rule) = &self.rule
+        {
+            return rule.offset_at(utc);
+        }
+        match index {
+            0 => self.initial,
+            index => self.transitions[index - 1].1,
+        }
+    }
+}
+
+impl Rule {
+    fn offset_at(&self, utc: i64) -> i32 {
+        let Some(daylight) = &self.daylight else {
+            return self.standard;
+        };
+        let year = DateTime::from_timestamp(utc + self.standard as i64, 0)
+            .map(|datetime| datetime.year())
+            .unwrap_or(1970);
+        // Start times are given in standard time, end times in daylight time
+        let start = daylight.start.timestamp(year, self.standard);
+        let end = daylight.end.timestamp(year, daylight.offset);
+        let is_daylight = if start < end {
+            start <= utc && utc < end
+        } else {
+            // Southern hemisphere, daylight saving time spans the new year
+            !(end <= utc && utc < start)
+        };
+        if is_daylight {
+            daylight.offset
+        } else {
+            self.standard
+        }
+    }
+}
+
+impl Change {
+    // UTC timestamp of the change in `year`
+    fn timestamp(&self, year: i32, offset: i32) -> i64 {
+        let date = match self.day {
+            RuleDay::Julian(day) => {
+                let date = NaiveDate::from_yo_opt(year, day.min(365));
+                match date {
+                    Some(date) if date.leap_year() && day >= 60 => date.succ_opt(),
+                    date => date,
+                }
+            }
+            RuleDay::DayOfYear(day) => NaiveDate::from_yo_opt(year, day + 1),
+            RuleDay::Month {
+                month,
+                week,
+                weekday,
+            } => month_day(year, month, week, weekday),
+        };
+        let days = date
+            .map(|date| date.signed_duration_since(NaiveDate::default()).num_days())
+            .unwrap_or_default();
+        days * SECONDS_PER_DAY + self.time - offset as i64
+    }
+}
+
+fn month_day(year: i32, month: u32, week: u32, weekday: u32) -> Option<NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let first_weekday = first.weekday().num_days_from_sunday();
+    let mut day = 1 + (weekday + 7 - first_weekday) % 7 + (week - 1) * 7;
+    // Week 5 is the last one, which may be the fourth
+    while NaiveDate::from_ymd_opt(year, month, day).is_none() {
+        day -= 7;
+    }
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+// +01:00, -0500, +01 or Z, in seconds east of UTC
+fn parse_fixed_offset(text: &str) -> Option<i32> {
+    if text.eq_ignore_ascii_case("Z") || text.eq_ignore_ascii_case("UTC") {
+        return Some(0);
+    }
+    let (sign, digits) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    let digits = digits.replace(':', "");
+    if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits.get(2..).map_or(Some(0), |m| m.parse().ok())?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(feature = "fs")]
+fn read_zone_file(name: &str) -> io::Result<Vec<u8>> {
+    let dir = std::env::var_os("TZDIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::path::PathBuf::from("/usr/share/zoneinfo"));
+    std::fs::read(dir.join(name))
+        .map_err(|e| io::Error::new(e.kind(), format!("Unknown time zone {}: {}", name, e)))
+}
+
+#[cfg(not(feature = "fs"))]
+fn read_zone_file(name: &str) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "Unknown time zone {}: no time zone database in this build, use an offset such as +01:00",
+            name
+        ),
+    ))
+}
+
+// Time zone information file (RFC 8536), as found in /usr/share/zoneinfo
+fn parse_tzif(data: &[u8]) -> Option<Zone> {
+    let header = Header::read(data)?;
+    // Version 2 and later repeat the data with 64-bit times after the
+    // version 1 data, followed by a POSIX TZ rule for later times
+    let (header, body, time_size) = if header.version >= b'2' {
+        let data = data.get(44 + header.data_len(4)..)?;
+        (Header::read(data)?, data.get(44..)?, 8)
+    } else {
+        (header, data.get(44..)?, 4)
+    };
+
+    let mut at = 0;
+    let mut take = |len: usize| {
+        let bytes = body.get(at..at + len);
+        at += len;
+        bytes
+    };
+    let times = take(header.time_count * time_size)?;
+    let indices = take(header.time_count)?;
+    let types = take(header.type_count * 6)?;
+    take(header.data_len(time_size) - header.time_count * (time_size + 1) - header.type_count * 6)?;
+    let footer = body.get(at..).unwrap_or_default();
+
+    let type_offset = |index: usize| {
+        let entry = types.get(index * 6..index * 6 + 4)?;
+        Some(i32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]))
+    };
+    let transitions = times
+        .chunks_exact(time_size)
+        .zip(indices)
+        .map(|(time, &index)| {
+            let time = match time_size {
+                8 => i64::from_be_bytes(time.try_into().ok()?),
+                _ => i32::from_be_bytes(time.try_into().ok()?) as i64,
+            };
+            Some((time, type_offset(index as usize)?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let rule = std::str::from_utf8(footer)
+        .ok()
+        .and_then(|footer| footer.trim().lines().next())
+        .and_then(parse_rule);
+    Some(Zone {
+        initial: type_offset(0)?,
+        transitions,
+        rule,
+    })
+}
+
+struct Header {
+    version: u8,
+    utc_count: usize,
+    standard_count: usize,
+    leap_count: usize,
+    time_count: usize,
+    type_count: usize,
+    char_count: usize,
+}
+
+impl Header {
+    fn read(data: &[u8]) -> Option<Header> {
+        if !data.starts_with(b"TZif") || data.len() < 44 {
+            return None;
+        }
+        let count = |index: usize| {
+            let at = 20 + index * 4;
+            u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]) as usize
+        };
+        Some(Header {
+            version: data[4],
+            utc_count: count(0),
+            standard_count: count(1),
+            leap_count: count(2),
+            time_count: count(3),
+            type_count: count(4),
+            char_count: count(5),
+        })
+    }
+
+    // Length of the data following the header
+    fn data_len(&self, time_size: usize) -> usize {
+        self.time_count * (time_size + 1)
+            + self.type_count * 6
+            + self.char_count
+            + self.leap_count * (time_size + 4)
+            + self.standard_count
+            + self.utc_count
+    }
+}
+
+fn parse_rule(text: &str) -> Option<Rule> {
+    let mut rest = text;
+    zone_abbreviation(&mut rest)?;
+    let standard = -rule_time(&mut rest)? as i32;
+    if rest.is_empty() {
+        return Some(Rule {
+            standard,
+            daylight: None,
+        });
+    }
+
+    zone_abbreviation(&mut rest)?;
+    let offset = if rest.starts_with(',') {
+        standard + 3600
+    } else {
+        -rule_time(&mut rest)? as i32
+    };
+    rest = rest.strip_prefix(',')?;
+    let start = rule_change(&mut rest)?;
+    rest = rest.strip_prefix(',')?;
+    let end = rule_change(&mut rest)?;
+    if !rest.is_empty() {
+        return None;
+    }
+    Some(Rule {
+        standard,
+        daylight: Some(DaylightSaving { offset, start, end }),
+    })
+}
+
+// CET, or <+03> for abbreviations that are not letters
+fn zone_abbreviation(rest: &mut &str) -> Option<()> {
+    let len = if rest.starts_with('<') {
+        rest.find('>')? + 1
+    } else {
+        rest.find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len())
+    };
+    if len < 3 {
+        return None;
+    }
+    *rest = &rest[len..];
+    Some(())
+}
+
+// [+-]hh[:mm[:ss]] in seconds
+fn rule_time(rest: &mut &str) -> Option<i64> {
+    let sign = match rest.as_bytes().first()? {
+        b'-' => -1,
+        _ => 1,
+    };
+    *rest = rest.trim_start_matches(['+', '-']);
+    let len = rest
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(rest.len());
+    let (time, remaining) = rest.split_at(len);
+    *rest = remaining;
+
+    let mut seconds = 0;
+    let mut parts = time.split(':');
+    for unit in [3600, 60, 1] {
+        if let Some(part) = parts.next() {
+            seconds += part.parse::<i64>().ok()? * unit;
+        }
+    }
+    Some(sign * seconds)
+}
+
+// Mm.w.d, Jn or n, optionally followed by /time
+fn rule_change(rest: &mut &str) -> Option<Change> {
+    let len = rest.find([',', '/']).unwrap_or(rest.len());
+    let (day, remaining) = rest.split_at(len);
+    *rest = remaining;
+
+    let day = if let Some(day) = day.strip_prefix('M') {
+        let mut fields = day.split('.').map(|field| field.parse::<u32>().ok());
+        let (month, week, weekday) = (fields.next()??, fields.next()??, fields.next()??);
+        if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+            return None;
+        }
+        RuleDay::Month {
+            month,
+            week,
+            weekday,
+        }
+    } else if let Some(day) = day.strip_prefix('J') {
+        RuleDay::Julian(day.parse().ok().filter(|day| (1..=365).contains(day))?)
+    } else {
+        RuleDay::DayOfYear(day.parse().ok().filter(|day| *day <= 365)?)
+    };
+
+    let time = match rest.strip_prefix('/') {
+        Some(time) => {
+            *rest = time;
+            rule_time(rest)?
+        }
+        None => 2 * 3600,
+    };
+    Some(Change { day, time })
+}
@@ -0,0 +1,151 @@
+//! Totals of entries by bank transaction code, for checking fee and interest
+//! postings at month end.
+//!
+//! Entries are grouped by the domain, family and sub-family of their bank
+//! transaction code, their proprietary code and their currency. Amounts of
+//! different currencies are never added up.
+
+use crate::decimal::Decimal;
+use crate::error::{ConvertError, Result};
+use crate::model::{Amount, CreditDebit, Transaction};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Key {
+    domain: &'static str,
+    family: &'static str,
+    sub_family: &'static str,
+    code: String,
+    currency: String,
+}
+
+/// Count and sums of the entries of one code and currency
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Totals {
+    pub entries: usize,
+    pub credits: Decimal,
+    pub debits: Decimal,
+}
+
+/// Totals of entries by bank transaction code and currency
+#[derive(Debug, Clone, Default)]
+pub struct CodeTotals {
+    totals: BTreeMap<Key, Totals>,
+}
+
+impl CodeTotals {
+    pub fn new() -> Self {
+        CodeTotals::default()
+    }
+
+    pub fn add(&mut self, transaction: &Transaction) -> Result<()> {
+        let (domain, family, sub_family) = transaction.bank_tx_family();
+        let key = Key {
+            domain,
+            family,
+            sub_family,
+            code: transaction.bank_tx_code.clone(),
+            currency: transaction.amount.currency.clone(),
+        };
+        let totals = self.totals.entry(key).or_default();
+        totals.entries += 1;
+        let sum = match transaction.credit_debit_ind {
+            Some(CreditDebit::Debit) => &mut totals.debits,
+            _ => &mut totals.credits,
+        };
+        *sum = sum
+            .checked_add(transaction.amount.value)
+            .ok_or_else(|| overflow(&transaction.amount.currency))?;
+        Ok(())
+    }
+
+    /// Add the totals of other documents
+    pub fn merge(&mut self, other: &CodeTotals) -> Result<()> {
+        for (key, other) in &other.totals {
+            let totals = self.totals.entry(key.clone()).or_default();
+            totals.entries += other.entries;
+            totals.credits = totals
+                .credits
+                .checked_add(other.credits)
+                .ok_or_else(|| overflow(&key.currency))?;
+            totals.debits = totals
+                .debits
+                .checked_add(other.debits)
+                .ok_or_else(|| overflow(&key.currency))?;
+        }
+        Ok(())
+    }
+
+    /// Totals of all codes by currency, with the decimal places of each
+    /// currency
+    pub fn by_currency(&self) -> Result<BTreeMap<String, Totals>> {
+        let mut by_currency: BTreeMap<String, Totals> = BTreeMap::new();
+        for (key, totals) in &self.totals {
+            let sum = by_currency.entry(key.currency.clone()).or_default();
+            let add = |sum: Decimal, value| {
+                sum.checked_add(value)
+                    .map(|sum| amount(sum, &key.currency))
+                    .ok_or_else(|| overflow(&key.currency))
+            };
+            sum.entries += totals.entries;
+            sum.credits = add(sum.credits, totals.credits)?;
+            sum.debits = add(sum.debits, totals.debits)?;
+        }
+        Ok(by_currency)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.totals.is_empty()
+    }
+
+    /// Write the totals as CSV, one line per code and currency with the net
+    /// amount of credits minus debits
+    pub fn write_csv(&self, mut output: impl Write) -> io::Result<()> {
+        writeln!(
+            output,
+            "domain,family,sub_family,code,currency,entries,credits,debits,net"
+        )?;
+        for (key, totals) in &self.totals {
+            let net = totals.credits.checked_sub(totals.debits).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, overflow(&key.currency))
+            })?;
+            writeln!(
+                output,
+                "{},{},{},{},{},{},{},{},{}",
+                key.domain,
+                key.family,
+                key.sub_family,
+                csv_field(&key.code),
+                csv_field(&key.currency),
+                totals.entries,
+                amount(totals.credits, &key.currency),
+                amount(totals.debits, &key.currency),
+                amount(net, &key.currency)
+            )?;
+        }
+        output.flush()
+    }
+}
+
+fn overflow(currency: &str) -> ConvertError {
+    ConvertError::Overflow(format!("total of the {} entries", currency))
+}
+
+// Sums with the decimal places of their currency
+fn amount(value: Decimal, currency: &str) -> Decimal {
+    Amount {
+        value,
+        currency: currency.to_string(),
+    }
+    .normalized()
+}
+
+// Quote fields that contain separators, quotes or line breaks
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
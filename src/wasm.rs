@@ -0,0 +1,163 @@
+//! Functions exported to JavaScript by the WebAssembly build, so statements
+//! can be converted in the browser without uploading them.
+//!
+//! The exports work on the module memory directly, so no bindings generator
+//! is needed. The caller copies the input into memory it allocated with
+//! `camt_alloc`, converts it with `camt_convert` and reads the result through
+//! the returned handle:
+//!
+//! ```js
+//! const bytes = new Uint8Array(await file.arrayBuffer());
+//! const input = camt.camt_alloc(bytes.length);
+//! new Uint8Array(camt.memory.buffer, input, bytes.length).set(bytes);
+//! const result = camt.camt_convert(input, bytes.length, 0, 0);
+//! camt.camt_free(input, bytes.length);
+//! const output = new Uint8Array(camt.memory.buffer,
+//!     camt.camt_result_ptr(result), camt.camt_result_len(result)).slice();
+//! const failed = camt.camt_result_is_error(result);
+//! camt.camt_result_free(result);
+//! ```
+//!
+//! Options are passed as a query string such as `reference=uuid5&strict`,
+//! see [`crate::query`], or with a length of 0 for the defaults.
+
+use crate::convert::convert_bytes;
+use crate::query::apply_query;
+use crate::reader::ParseOptions;
+use crate::writer::WriteOptions;
+use std::{ptr, slice};
+
+/// Outcome of `camt_convert`, owned by JavaScript until freed
+pub struct Conversion {
+    // The converted document, or the error message if the conversion failed
+    data: Vec<u8>,
+    // Warnings, one per line
+    warnings: Vec<u8>,
+    is_error: bool,
+}
+
+/// Allocate `len` bytes for an input, to be freed with `camt_free`
+#[unsafe(no_mangle)]
+pub extern "C" fn camt_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()).cast()
+}
+
+/// Free memory allocated with `camt_alloc`
+///
+/// # Safety
+///
+/// `ptr` and `len` must come from one call of `camt_alloc`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camt_free(ptr: *mut u8, len: usize) {
+    drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)) });
+}
+
+/// Convert the camt.053.001.10 document of `input_len` bytes at `input`,
+/// with the options of the query string at `options`
+///
+/// # Safety
+///
+/// Both ranges must be readable memory, such as allocated by `camt_alloc`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camt_convert(
+    input: *const u8,
+    input_len: usize,
+    options: *const u8,
+    options_len: usize,
+) -> *mut Conversion {
+    let input = unsafe { bytes(input, input_len) };
+    let options = unsafe { bytes(options, options_len) };
+    Box::into_raw(Box::new(convert(input, options)))
+}
+
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(ptr, len) }
+    }
+}
+
+fn convert(input: &[u8], options: &[u8]) -> Conversion {
+    let failed = |message: String| Conversion {
+        data: message.into_bytes(),
+        warnings: Vec::new(),
+        is_error: true,
+    };
+    let Ok(query) = std::str::from_utf8(options) else {
+        return failed("Options are not UTF-8".to_string());
+    };
+    let mut parse_options = ParseOptions::default();
+    let mut write_options = WriteOptions::default();
+    if let Err(message) = apply_query(query, &mut parse_options, &mut write_options) {
+        return failed(message);
+    }
+    match convert_bytes(input, &parse_options, &write_options) {
+        Ok(converted) => Conversion {
+            data: converted.output,
+            warnings: converted.warnings.join("\n").into_bytes(),
+            is_error: false,
+        },
+        Err(e) => failed(e.to_string()),
+    }
+}
+
+/// Start of the converted document, or of the error message
+///
+/// # Safety
+///
+/// `result` must be a handle returned by `camt_convert` and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camt_result_ptr(result: *const Conversion) -> *const u8 {
+    unsafe { (*result).data.as_ptr() }
+}
+
+/// Length of the converted document, or of the error message
+///
+/// # Safety
+///
+/// `result` must be a handle returned by `camt_convert` and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camt_result_len(result: *const Conversion) -> usize {
+    unsafe { (*result).data.len() }
+}
+
+/// Whether the conversion failed, 1 if so and 0 otherwise
+///
+/// # Safety
+///
+/// `result` must be a handle returned by `camt_convert` and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camt_result_is_error(result: *const Conversion) -> u32 {
+    unsafe { (*result).is_error as u32 }
+}
+
+/// Start of the warnings, one per line
+///
+/// # Safety
+///
+/// `result` must be a handle returned by `camt_convert` and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camt_result_warnings_ptr(result: *const Conversion) -> *const u8 {
+    unsafe { (*result).warnings.as_ptr() }
+}
+
+/// Length of the warnings
+///
+/// # Safety
+///
+/// `result` must be a handle returned by `camt_convert` and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camt_result_warnings_len(result: *const Conversion) -> usize {
+    unsafe { (*result).warnings.len() }
+}
+
+/// Free the result of `camt_convert`
+///
+/// # Safety
+///
+/// `result` must be a handle returned by `camt_convert` and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camt_result_free(result: *mut Conversion) {
+    drop(unsafe { Box::from_raw(result) });
+}
@@ -0,0 +1,1321 @@
+use crate::charset::Charset;
+use crate::error::{ConvertError, Result};
+use crate::invoice::{EsrDetails, find_esr};
+use crate::model::{
+    AmountDetails, Balance, CreditorReference, DateOrDateTime, EntryStatus, Statement, Transaction,
+};
+use crate::passthrough::{write_between, write_element_tree};
+use crate::reader::StatementHandler;
+use crate::reference::{ReferenceStyle, generate_transaction_reference, random_uuid};
+use crate::template::NameTemplate;
+use crate::timezone::TimeZone;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::writer::Writer;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+pub(crate) const CAMT_08_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:camt.053.001.08";
+const CAMT_04_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:camt.053.001.04";
+const CAMT_054_08_NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:camt.054.001.08";
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Written for BICs the input does not provide
+pub const PLACEHOLDER_BIC: &str = "XXXXXXXX";
+/// Name of the account servicer written with the placeholder BIC
+pub const PLACEHOLDER_BANK_NAME: &str = "Bank";
+/// Other identification of the account servicer written with the placeholder
+/// BIC
+pub const PLACEHOLDER_BANK_ID: &str = "XXX-000.000.000";
+/// Electronic sequence number written when the input has none
+pub const PLACEHOLDER_SEQUENCE_NUMBER: &str = "1";
+
+/// A generic value written where neither the input nor the options give one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    /// Path of the element below the document
+    pub path: &'static str,
+    /// What the value stands for, such as `recipient BIC`
+    pub name: &'static str,
+    pub value: &'static str,
+}
+
+/// `Placeholder XXXXXXXX written as recipient BIC at GrpHdr/MsgRcpt/Id/OrgId/AnyBIC`
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Placeholder {} written as {} at {}",
+            self.value, self.name, self.path
+        )
+    }
+}
+
+/// The placeholders a statement is written with, in document order
+pub fn placeholders(statement: &Statement, options: &WriteOptions) -> Vec<Placeholder> {
+    let mut placeholders = Vec::new();
+    if options.recipient_bic.is_none() && statement.message_recipient.is_none() {
+        placeholders.push(Placeholder {
+            path: "GrpHdr/MsgRcpt/Id/OrgId/AnyBIC",
+            name: "recipient BIC",
+            value: PLACEHOLDER_BIC,
+        });
+    }
+    if statement.sequence_number.is_empty() {
+        placeholders.push(Placeholder {
+            path: "Stmt/ElctrncSeqNb",
+            name: "electronic sequence number",
+            value: PLACEHOLDER_SEQUENCE_NUMBER,
+        });
+    }
+    if servicer_bic(statement, options).is_none() {
+        placeholders.extend([
+            Placeholder {
+                path: "Stmt/Acct/Svcr/FinInstnId/BICFI",
+                name: "servicer BIC",
+                value: PLACEHOLDER_BIC,
+            },
+            Placeholder {
+                path: "Stmt/Acct/Svcr/FinInstnId/Nm",
+                name: "servicer name",
+                value: PLACEHOLDER_BANK_NAME,
+            },
+            Placeholder {
+                path: "Stmt/Acct/Svcr/FinInstnId/Othr/Id",
+                name: "servicer identification",
+                value: PLACEHOLDER_BANK_ID,
+            },
+        ]);
+    }
+    placeholders
+}
+
+/// The placeholders a statement is written with, or with `strict` in the
+/// options an error for the first of them
+pub fn check_placeholders(
+    statement: &Statement,
+    options: &WriteOptions,
+) -> Result<Vec<Placeholder>> {
+    let placeholders = placeholders(statement, options);
+    match placeholders.first() {
+        Some(placeholder) if options.strict => Err(ConvertError::FailedCheck {
+            path: placeholder.path.to_string(),
+            message: format!(
+                "No {} to write instead of {}",
+                placeholder.name, placeholder.value
+            ),
+        }),
+        _ => Ok(placeholders),
+    }
+}
+
+/// Maximum length of identifications and references (Max35Text)
+const MAX_ID_LENGTH: usize = 35;
+/// Maximum length of names and unstructured remittance lines (Max140Text)
+const MAX_NAME_LENGTH: usize = 140;
+/// Maximum length of additional information (Max500Text)
+const MAX_INFO_LENGTH: usize = 500;
+
+/// A value longer than the schema allows, written cut to its maximum length
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncation {
+    pub path: &'static str,
+    /// Length of the value in characters
+    pub length: usize,
+    pub max_length: usize,
+}
+
+/// `Value of 612 characters cut to the 500 allowed at Ntry/AddtlNtryInf`
+impl fmt::Display for Truncation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Value of {} characters cut to the {} allowed at {}",
+            self.length, self.max_length, self.path
+        )
+    }
+}
+
+// The values with their path and maximum length that are too long
+fn too_long<'a>(
+    values: impl IntoIterator<Item = (&'static str, usize, Cow<'a, str>)>,
+) -> Vec<Truncation> {
+    values
+        .into_iter()
+        .map(|(path, max_length, value)| Truncation {
+            path,
+            length: value.chars().count(),
+            max_length,
+        })
+        .filter(|truncation| truncation.length > truncation.max_length)
+        .collect()
+}
+
+/// The values of the statement header that are cut to the schema length
+pub fn statement_truncations(statement: &Statement, options: &WriteOptions) -> Vec<Truncation> {
+    let mut values = vec![
+        (
+            "GrpHdr/AddtlInf",
+            MAX_INFO_LENGTH,
+            Cow::from(&statement.message_info),
+        ),
+        ("Stmt/Id", MAX_ID_LENGTH, Cow::from(&statement.id)),
+    ];
+    if include(options, &statement.layout, "Acct/Ownr") {
+        values.push((
+            "Stmt/Acct/Ownr/Nm",
+            MAX_NAME_LENGTH,
+            options.charset.convert(&statement.owner_name),
+        ));
+    }
+    too_long(values)
+}
+
+/// The values of an entry that are cut to the schema length
+pub fn entry_truncations(transaction: &Transaction, options: &WriteOptions) -> Vec<Truncation> {
+    let charset = options.charset;
+    let mut values = vec![(
+        "Ntry/BkTxCd/Prtry/Cd",
+        MAX_ID_LENGTH,
+        Cow::from(&transaction.bank_tx_code),
+    )];
+    if include(options, &transaction.layout, "NtryDtls") {
+        values.extend([
+            (
+                "Ntry/NtryDtls/TxDtls/Refs/EndToEndId",
+                MAX_ID_LENGTH,
+                Cow::from(&transaction.end_to_end_id),
+            ),
+            (
+                "Ntry/NtryDtls/TxDtls/Refs/ChqNb",
+                MAX_ID_LENGTH,
+                Cow::from(&transaction.cheque_number),
+            ),
+            (
+                "Ntry/NtryDtls/TxDtls/RltdPties/Dbtr/Nm",
+                MAX_NAME_LENGTH,
+                charset.convert(&transaction.debtor_name),
+            ),
+            (
+                "Ntry/NtryDtls/TxDtls/RltdPties/Cdtr/Nm",
+                MAX_NAME_LENGTH,
+                charset.convert(&transaction.creditor_name),
+            ),
+        ]);
+        // The entry information is written as remittance information where
+        // there is none
+        let lines = match transaction.remittance_info.as_slice() {
+            [] => std::slice::from_ref(&transaction.additional_info),
+            lines => lines,
+        };
+        values.extend(lines.iter().map(|line| {
+            (
+                "Ntry/NtryDtls/TxDtls/RmtInf/Ustrd",
+                MAX_NAME_LENGTH,
+                charset.convert(line),
+            )
+        }));
+        if let Some(reference) = &transaction.creditor_reference {
+            values.extend([
+                (
+                    "Ntry/NtryDtls/TxDtls/RmtInf/Strd/CdtrRefInf/Tp/CdOrPrtry/Prtry",
+                    MAX_ID_LENGTH,
+                    Cow::from(&reference.kind),
+                ),
+                (
+                    "Ntry/NtryDtls/TxDtls/RmtInf/Strd/CdtrRefInf/Ref",
+                    MAX_ID_LENGTH,
+                    Cow::from(&reference.reference),
+                ),
+            ]);
+        }
+        values.push((
+            "Ntry/NtryDtls/TxDtls/AddtlTxInf",
+            MAX_INFO_LENGTH,
+            charset.convert(&transaction.transaction_info),
+        ));
+    }
+    if include(options, &transaction.layout, "AddtlNtryInf") {
+        values.push((
+            "Ntry/AddtlNtryInf",
+            MAX_INFO_LENGTH,
+            charset.convert(&transaction.additional_info),
+        ));
+    }
+    too_long(values)
+}
+
+/// The values of a statement and its entries that are cut to the schema
+/// length, or with `strict` in the options an error for the first of them
+pub fn check_lengths(statement: &Statement, options: &WriteOptions) -> Result<Vec<Truncation>> {
+    let mut truncations = statement_truncations(statement, options);
+    for transaction in &statement.transactions {
+        truncations.extend(entry_truncations(transaction, options));
+    }
+    fail_on_truncation(truncations, options)
+}
+
+fn fail_on_truncation(
+    truncations: Vec<Truncation>,
+    options: &WriteOptions,
+) -> Result<Vec<Truncation>> {
+    match truncations.first() {
+        Some(truncation) if options.strict => Err(ConvertError::FailedCheck {
+            path: truncation.path.to_string(),
+            message: format!(
+                "Value of {} characters is longer than the {} allowed",
+                truncation.length, truncation.max_length
+            ),
+        }),
+        _ => Ok(truncations),
+    }
+}
+
+// The first characters of a value, up to a maximum length
+fn cut(value: &str, max_length: usize) -> &str {
+    match value.char_indices().nth(max_length) {
+        Some((end, _)) => &value[..end],
+        None => value,
+    }
+}
+
+// A name or remittance text in the character set of the options, cut to a
+// maximum length
+fn text<'a>(value: &'a str, max_length: usize, options: &WriteOptions) -> Cow<'a, str> {
+    match options.charset.convert(value) {
+        Cow::Borrowed(value) => Cow::Borrowed(cut(value, max_length)),
+        Cow::Owned(value) => Cow::Owned(cut(&value, max_length).to_string()),
+    }
+}
+
+// BIC of the account servicer from the options or else the input
+fn servicer_bic<'a>(statement: &'a Statement, options: &'a WriteOptions) -> Option<&'a str> {
+    options
+        .servicer_bic
+        .as_deref()
+        .or((!statement.servicer_bic.is_empty()).then_some(statement.servicer_bic.as_str()))
+}
+
+/// When the `xmlns:xsi` declaration is written on the Document element
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum XsiNamespace {
+    /// Always declare the namespace
+    #[default]
+    Always,
+    /// Only declare it when an xsi attribute is written
+    WhenUsed,
+}
+
+/// How booking and balance dates are written
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    /// As a plain date (Dt), leaving out the time of date-times
+    #[default]
+    Date,
+    /// As a date with time (DtTm), plain dates at midnight
+    DateTime,
+    /// In the form the input had them
+    Input,
+}
+
+/// Format of the output files
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// camt.053 documents
+    #[default]
+    Xml,
+    /// The entries as an Apache Arrow IPC stream
+    // Written by [`crate::arrow`], linked here as doc comments are --help
+    Arrow,
+}
+
+/// Version of the output document
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputVersion {
+    /// camt.053.001.04, which older ERPs are pinned to
+    #[value(name = "04", alias = "053.001.04", alias = "camt.053.001.04")]
+    V04,
+    /// camt.053.001.08 of the Swiss Payment Standards
+    #[default]
+    #[value(name = "08", alias = "053.001.08", alias = "camt.053.001.08")]
+    V08,
+}
+
+impl OutputVersion {
+    /// Last number of the version, such as `08`
+    pub fn number(self) -> &'static str {
+        match self {
+            OutputVersion::V04 => "04",
+            OutputVersion::V08 => "08",
+        }
+    }
+
+    fn namespace(self) -> &'static str {
+        match self {
+            OutputVersion::V04 => CAMT_04_NAMESPACE,
+            OutputVersion::V08 => CAMT_08_NAMESPACE,
+        }
+    }
+
+    /// Name of the XSD file as published by ISO 20022 and exported by the
+    /// `schemas` command, such as `camt.053.001.08.xsd`
+    pub fn schema_file(self) -> String {
+        format!("camt.053.001.{}.xsd", self.number())
+    }
+}
+
+/// Layout of the output document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// One element per line, nested by the given number of spaces
+    Spaces(usize),
+    /// The whole document on a single line
+    Compact,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Indent::Spaces(4)
+    }
+}
+
+/// What the output writes as message identification (GrpHdr/MsgId)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MessageId {
+    /// The statement Id
+    #[default]
+    Statement,
+    /// The MsgId of the input, or the statement Id if it has none
+    Input,
+    /// A random UUID, unique for every output
+    Uuid,
+    /// A template with the placeholders of output file names, such as
+    /// `{iban}-{created_date}`
+    Template(NameTemplate),
+}
+
+impl MessageId {
+    /// The MsgId to write for a statement, at most 35 characters long
+    pub fn render(&self, statement: &Statement, version: OutputVersion) -> String {
+        let id = match self {
+            MessageId::Statement => statement.id.clone(),
+            MessageId::Input if !statement.message_id.is_empty() => statement.message_id.clone(),
+            MessageId::Input => statement.id.clone(),
+            MessageId::Uuid => random_uuid(),
+            MessageId::Template(template) => template.render(statement, "", version),
+        };
+        id.chars().take(35).collect()
+    }
+}
+
+/// Parses `statement`, `input`, `uuid` or a template with placeholders
+impl FromStr for MessageId {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "statement" => Ok(MessageId::Statement),
+            "input" => Ok(MessageId::Input),
+            "uuid" => Ok(MessageId::Uuid),
+            template if template.contains('{') => template
+                .parse()
+                .map(MessageId::Template)
+                .map_err(|error: crate::template::TemplateError| error.to_string()),
+            _ => Err(format!(
+                "Unknown MsgId {}, expected statement, input, uuid or a template with placeholders",
+                value
+            )),
+        }
+    }
+}
+
+/// Position of a document in a message split into pages (GrpHdr/MsgPgntn)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    /// Number of the page, counted from 1
+    pub number: usize,
+    pub last: bool,
+}
+
+impl Default for Page {
+    /// The only page of a message
+    fn default() -> Self {
+        Page {
+            number: 1,
+            last: true,
+        }
+    }
+}
+
+/// Options controlling how output documents are written
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    pub version: OutputVersion,
+    pub reference: ReferenceStyle,
+    /// Location of the XSD to reference in `xsi:schemaLocation`, if any;
+    /// empty for [`OutputVersion::schema_file`]
+    pub schema_location: Option<String>,
+    pub xsi_namespace: XsiNamespace,
+    /// Write optional elements only if they were present in the input
+    pub fidelity: bool,
+    pub date_format: DateFormat,
+    /// Time zone of the midnight written for plain dates as date-times
+    pub timezone: TimeZone,
+    pub indent: Indent,
+    /// BIC of the account servicer, written instead of the one in the input
+    pub servicer_bic: Option<String>,
+    /// BIC of the message recipient, written instead of the placeholder
+    pub recipient_bic: Option<String>,
+    /// Fail instead of writing a placeholder where the input lacks a value
+    /// the output requires
+    pub strict: bool,
+    /// Write ESR references and participant numbers found in the text of
+    /// entries without creditor reference as structured information
+    pub esr_references: bool,
+    pub message_id: MessageId,
+    /// Character set of names and remittance text
+    pub charset: Charset,
+    /// Page of the document; the MsgId of pages of a longer message ends in
+    /// the page number, such as `-P2`
+    pub page: Page,
+}
+
+/// Write a statement as a camt.053.001.08 document.
+pub fn write_camt_08<W: Write>(
+    output: W,
+    statement: &Statement,
+    options: &WriteOptions,
+) -> Result<()> {
+    let options = WriteOptions {
+        version: OutputVersion::V08,
+        ..options.clone()
+    };
+    write_statement(output, statement, &options)
+}
+
+/// Write a statement as a camt.053.001.04 document.
+///
+/// Entry statuses are plain codes, which leaves out `FUTR`, and no SPS
+/// version is written to the group header.
+pub fn write_camt_04<W: Write>(
+    output: W,
+    statement: &Statement,
+    options: &WriteOptions,
+) -> Result<()> {
+    let options = WriteOptions {
+        version: OutputVersion::V04,
+        ..options.clone()
+    };
+    write_statement(output, statement, &options)
+}
+
+/// Write a statement in the version of the options
+pub fn write_statement<W: Write>(
+    output: W,
+    statement: &Statement,
+    options: &WriteOptions,
+) -> Result<()> {
+    let mut writer = StreamWriter::new(output, options.clone());
+    writer.begin(statement)?;
+    for transaction in &statement.transactions {
+        writer.write_entry(statement, transaction)?;
+    }
+    writer.end(statement)
+}
+
+/// Write one entry of a statement as a minimal camt.054.001.08 notification,
+/// with the account of the statement and the entry as in camt.053.001.08.
+///
+/// The entry keeps the reference of the bank where the input has one, so
+/// that the bank can find it; otherwise it gets the generated reference of
+/// the options, which also identifies the message and notification.
+pub fn write_notification<W: Write>(
+    output: W,
+    statement: &Statement,
+    transaction: &Transaction,
+    options: &WriteOptions,
+) -> Result<()> {
+    let options = WriteOptions {
+        version: OutputVersion::V08,
+        ..options.clone()
+    };
+    let mut writer = match options.indent {
+        Indent::Spaces(width) => Writer::new_with_indent(output, b' ', width),
+        Indent::Compact => Writer::new(output),
+    };
+    let reference = match transaction.account_servicer_ref.as_str() {
+        "" => generate_transaction_reference(statement, transaction, options.reference),
+        reference => reference.to_string(),
+    };
+    let creation_datetime = statement
+        .creation_datetime
+        .ok_or_else(|| missing("Stmt/CreDtTm"))?;
+
+    writer.write_event(Event::Decl(quick_xml::events::BytesDecl::new(
+        "1.0",
+        Some("UTF-8"),
+        None,
+    )))?;
+    let mut doc_elem = BytesStart::new("Document");
+    doc_elem.push_attribute(("xmlns", CAMT_054_08_NAMESPACE));
+    writer.write_event(Event::Start(doc_elem))?;
+    writer.write_event(Event::Start(BytesStart::new("BkToCstmrDbtCdtNtfctn")))?;
+
+    writer.write_event(Event::Start(BytesStart::new("GrpHdr")))?;
+    write_element(&mut writer, "MsgId", cut(&reference, MAX_ID_LENGTH))?;
+    write_element(&mut writer, "CreDtTm", &format_datetime(&creation_datetime))?;
+    writer.write_event(Event::End(BytesEnd::new("GrpHdr")))?;
+
+    writer.write_event(Event::Start(BytesStart::new("Ntfctn")))?;
+    write_element(&mut writer, "Id", cut(&reference, MAX_ID_LENGTH))?;
+    write_element(&mut writer, "CreDtTm", &format_datetime(&creation_datetime))?;
+    writer.write_event(Event::Start(BytesStart::new("Acct")))?;
+    writer.write_event(Event::Start(BytesStart::new("Id")))?;
+    write_element(&mut writer, "IBAN", &statement.iban)?;
+    writer.write_event(Event::End(BytesEnd::new("Id")))?;
+    if !statement.currency.is_empty() {
+        write_element(&mut writer, "Ccy", &statement.currency)?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("Acct")))?;
+    write_transaction(&mut writer, transaction, &reference, &options)?;
+    writer.write_event(Event::End(BytesEnd::new("Ntfctn")))?;
+
+    writer.write_event(Event::End(BytesEnd::new("BkToCstmrDbtCdtNtfctn")))?;
+    writer.write_event(Event::End(BytesEnd::new("Document")))?;
+    Ok(())
+}
+
+/// Writes a document while the input is still being read.
+///
+/// Entries are written as they arrive, so memory use does not grow with the
+/// number of entries. Use with [`stream_camt_10`](crate::reader::stream_camt_10).
+pub struct StreamWriter<W: Write> {
+    writer: Writer<W>,
+    options: WriteOptions,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(output: W, options: WriteOptions) -> Self {
+        let writer = match options.indent {
+            Indent::Spaces(width) => Writer::new_with_indent(output, b' ', width),
+            Indent::Compact => Writer::new(output),
+        };
+        StreamWriter { writer, options }
+    }
+
+    fn write_entry(&mut self, statement: &Statement, transaction: &Transaction) -> Result<()> {
+        let options = &self.options;
+        let ref_id = generate_transaction_reference(statement, transaction, options.reference);
+        write_transaction(&mut self.writer, transaction, &ref_id, options)
+    }
+}
+
+impl<W: Write> StatementHandler for StreamWriter<W> {
+    fn begin(&mut self, statement: &Statement) -> Result<()> {
+        write_document_start(&mut self.writer, statement, &self.options)
+    }
+
+    fn entry(&mut self, statement: &Statement, transaction: Transaction) -> Result<()> {
+        self.write_entry(statement, &transaction)
+    }
+
+    fn end(&mut self, statement: &Statement) -> Result<()> {
+        write_document_end(&mut self.writer, statement)
+    }
+}
+
+// Everything up to the first entry
+fn write_document_start<W: Write>(
+    writer: &mut Writer<W>,
+    statement: &Statement,
+    options: &WriteOptions,
+) -> Result<()> {
+    // Write XML declaration
+    writer.write_event(Event::Decl(quick_xml::events::BytesDecl::new(
+        "1.0",
+        Some("UTF-8"),
+        None,
+    )))?;
+
+    // Start Document element with namespace
+    let namespace = options.version.namespace();
+    let mut doc_elem = BytesStart::new("Document");
+    doc_elem.push_attribute(("xmlns", namespace));
+    if options.xsi_namespace == XsiNamespace::Always || options.schema_location.is_some() {
+        doc_elem.push_attribute(("xmlns:xsi", XSI_NAMESPACE));
+    }
+    if let Some(location) = &options.schema_location {
+        let schema_location = match location.as_str() {
+            "" => format!("{} {}", namespace, options.version.schema_file()),
+            location => format!("{} {}", namespace, location),
+        };
+        doc_elem.push_attribute(("xsi:schemaLocation", schema_location.as_str()));
+    }
+    writer.write_event(Event::Start(doc_elem))?;
+
+    check_placeholders(statement, options)?;
+    fail_on_truncation(statement_truncations(statement, options), options)?;
+
+    // BkToCstmrStmt
+    writer.write_event(Event::Start(BytesStart::new("BkToCstmrStmt")))?;
+
+    // Write Group Header
+    write_group_header(writer, statement, options)?;
+
+    // Write Statement up to its entries
+    write_statement_start(writer, statement, options)
+}
+
+// Everything after the last entry
+fn write_document_end<W: Write>(writer: &mut Writer<W>, statement: &Statement) -> Result<()> {
+    write_between(
+        writer,
+        "Stmt",
+        &statement.extra_elements,
+        Some("Ntry"),
+        None,
+    )?;
+    writer.write_event(Event::End(BytesEnd::new("Stmt")))?;
+
+    write_between(
+        writer,
+        "BkToCstmrStmt",
+        &statement.message_extra_elements,
+        Some("Stmt"),
+        None,
+    )?;
+
+    // Close BkToCstmrStmt
+    writer.write_event(Event::End(BytesEnd::new("BkToCstmrStmt")))?;
+
+    // Close Document
+    writer.write_event(Event::End(BytesEnd::new("Document")))?;
+
+    Ok(())
+}
+
+fn write_group_header<W: Write>(
+    writer: &mut Writer<W>,
+    statement: &Statement,
+    options: &WriteOptions,
+) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("GrpHdr")))?;
+
+    // MsgId, unique for every page
+    let mut message_id = options.message_id.render(statement, options.version);
+    if options.page != Page::default() {
+        let suffix = format!("-P{}", options.page.number);
+        message_id = message_id.chars().take(35 - suffix.len()).collect();
+        message_id.push_str(&suffix);
+    }
+    write_element(writer, "MsgId", &message_id)?;
+
+    // CreDtTm
+    let creation_datetime = statement
+        .creation_datetime
+        .ok_or_else(|| missing("Stmt/CreDtTm"))?;
+    write_element(writer, "CreDtTm", &format_datetime(&creation_datetime))?;
+
+    // MsgRcpt (required in v08), from the input unless an option gives the BIC
+    match (&options.recipient_bic, &statement.message_recipient) {
+        (None, Some(recipient)) => write_element_tree(writer, recipient, CAMT_08_NAMESPACE)?,
+        (recipient_bic, _) => {
+            writer.write_event(Event::Start(BytesStart::new("MsgRcpt")))?;
+            writer.write_event(Event::Start(BytesStart::new("Id")))?;
+            writer.write_event(Event::Start(BytesStart::new("OrgId")))?;
+            let recipient_bic = recipient_bic.as_deref().unwrap_or(PLACEHOLDER_BIC);
+            write_element(writer, "AnyBIC", recipient_bic)?;
+            writer.write_event(Event::End(BytesEnd::new("OrgId")))?;
+            writer.write_event(Event::End(BytesEnd::new("Id")))?;
+            writer.write_event(Event::End(BytesEnd::new("MsgRcpt")))?;
+        }
+    }
+
+    // MsgPgntn
+    writer.write_event(Event::Start(BytesStart::new("MsgPgntn")))?;
+    write_element(writer, "PgNb", &options.page.number.to_string())?;
+    write_element(writer, "LastPgInd", &options.page.last.to_string())?;
+    writer.write_event(Event::End(BytesEnd::new("MsgPgntn")))?;
+
+    // AddtlInf as in the input, else the SPS version of the version 08
+    // business rules
+    if !statement.message_info.is_empty() {
+        write_element(
+            writer,
+            "AddtlInf",
+            cut(&statement.message_info, MAX_INFO_LENGTH),
+        )?;
+    } else if options.version == OutputVersion::V08 {
+        write_element(writer, "AddtlInf", "SPS/2.1")?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("GrpHdr")))?;
+
+    Ok(())
+}
+
+fn write_statement_start<W: Write>(
+    writer: &mut Writer<W>,
+    statement: &Statement,
+    options: &WriteOptions,
+) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("Stmt")))?;
+
+    // Statement ID
+    write_element(writer, "Id", cut(&statement.id, MAX_ID_LENGTH))?;
+    write_between(
+        writer,
+        "Stmt",
+        &statement.extra_elements,
+        Some("Id"),
+        Some("ElctrncSeqNb"),
+    )?;
+
+    // Electronic Sequence Number
+    let sequence_number = match statement.sequence_number.as_str() {
+        "" => PLACEHOLDER_SEQUENCE_NUMBER,
+        number => number,
+    };
+    write_element(writer, "ElctrncSeqNb", sequence_number)?;
+    write_between(
+        writer,
+        "Stmt",
+        &statement.extra_elements,
+        Some("ElctrncSeqNb"),
+        Some("CreDtTm"),
+    )?;
+
+    // Creation DateTime
+    let creation_datetime = statement
+        .creation_datetime
+        .ok_or_else(|| missing("Stmt/CreDtTm"))?;
+    write_element(writer, "CreDtTm", &format_datetime(&creation_datetime))?;
+
+    // From/To Date (optional, but only meaningful as a pair)
+    if let (Some(from), Some(to)) = (&statement.from_datetime, &statement.to_datetime) {
+        writer.write_event(Event::Start(BytesStart::new("FrToDt")))?;
+        write_element(writer, "FrDtTm", &format_datetime(from))?;
+        write_element(writer, "ToDtTm", &format_datetime(to))?;
+        writer.write_event(Event::End(BytesEnd::new("FrToDt")))?;
+    }
+
+    write_between(
+        writer,
+        "Stmt",
+        &statement.extra_elements,
+        Some("FrToDt"),
+        Some("Acct"),
+    )?;
+
+    // Account
+    writer.write_event(Event::Start(BytesStart::new("Acct")))?;
+    writer.write_event(Event::Start(BytesStart::new("Id")))?;
+    write_element(writer, "IBAN", &statement.iban)?;
+    writer.write_event(Event::End(BytesEnd::new("Id")))?;
+    if include(options, &statement.layout, "Acct/Ccy") {
+        write_element(writer, "Ccy", &statement.currency)?;
+    }
+    if include(options, &statement.layout, "Acct/Ownr") {
+        writer.write_event(Event::Start(BytesStart::new("Ownr")))?;
+        let owner_name = text(&statement.owner_name, MAX_NAME_LENGTH, options);
+        write_element(writer, "Nm", &owner_name)?;
+        writer.write_event(Event::End(BytesEnd::new("Ownr")))?;
+    }
+
+    // Servicer (required in v08, generic values where the BIC is not known)
+    writer.write_event(Event::Start(BytesStart::new("Svcr")))?;
+    writer.write_event(Event::Start(BytesStart::new("FinInstnId")))?;
+    match servicer_bic(statement, options) {
+        Some(bic) => write_element(writer, "BICFI", bic)?,
+        None => {
+            write_element(writer, "BICFI", PLACEHOLDER_BIC)?;
+            write_element(writer, "Nm", PLACEHOLDER_BANK_NAME)?;
+            writer.write_event(Event::Start(BytesStart::new("Othr")))?;
+            write_element(writer, "Id", PLACEHOLDER_BANK_ID)?;
+            write_element(writer, "Issr", "ID")?;
+            writer.write_event(Event::End(BytesEnd::new("Othr")))?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new("FinInstnId")))?;
+    writer.write_event(Event::End(BytesEnd::new("Svcr")))?;
+
+    writer.write_event(Event::End(BytesEnd::new("Acct")))?;
+    write_between(
+        writer,
+        "Stmt",
+        &statement.extra_elements,
+        Some("Acct"),
+        Some("Bal"),
+    )?;
+
+    // Balances
+    for balance in &statement.balances {
+        write_balance(writer, balance, options)?;
+    }
+    write_between(
+        writer,
+        "Stmt",
+        &statement.extra_elements,
+        Some("Bal"),
+        Some("Ntry"),
+    )
+}
+
+fn write_balance<W: Write>(
+    writer: &mut Writer<W>,
+    balance: &Balance,
+    options: &WriteOptions,
+) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("Bal")))?;
+
+    // Type
+    writer.write_event(Event::Start(BytesStart::new("Tp")))?;
+    writer.write_event(Event::Start(BytesStart::new("CdOrPrtry")))?;
+    let balance_type = balance.balance_type.ok_or_else(|| missing("Bal/Tp"))?;
+    write_element(writer, "Cd", balance_type.as_str())?;
+    writer.write_event(Event::End(BytesEnd::new("CdOrPrtry")))?;
+    writer.write_event(Event::End(BytesEnd::new("Tp")))?;
+    write_between(
+        writer,
+        "Bal",
+        &balance.extra_elements,
+        Some("Tp"),
+        Some("Amt"),
+    )?;
+
+    // Amount with currency
+    let mut amt_elem = BytesStart::new("Amt");
+    amt_elem.push_attribute(("Ccy", balance.amount.currency.as_str()));
+    writer.write_event(Event::Start(amt_elem))?;
+    writer.write_event(Event::Text(BytesText::new(
+        &balance.amount.normalized().to_string(),
+    )))?;
+    writer.write_event(Event::End(BytesEnd::new("Amt")))?;
+
+    // Credit/Debit Indicator
+    let credit_debit_ind = balance.credit_debit_ind.ok_or_else(|| {
+        missing(&format!(
+            "Bal/CdtDbtInd of {} balance",
+            balance_type.as_str()
+        ))
+    })?;
+    write_element(writer, "CdtDbtInd", credit_debit_ind.as_str())?;
+
+    // Date
+    let date = balance
+        .date
+        .ok_or_else(|| missing(&format!("Bal/Dt of {} balance", balance_type.as_str())))?;
+    write_date_choice(writer, "Dt", &date, options)?;
+    write_between(writer, "Bal", &balance.extra_elements, Some("Dt"), None)?;
+
+    writer.write_event(Event::End(BytesEnd::new("Bal")))?;
+
+    Ok(())
+}
+
+fn write_transaction<W: Write>(
+    writer: &mut Writer<W>,
+    transaction: &Transaction,
+    ref_id: &str,
+    options: &WriteOptions,
+) -> Result<()> {
+    if options.strict {
+        fail_on_truncation(entry_truncations(transaction, options), options)?;
+    }
+    writer.write_event(Event::Start(BytesStart::new("Ntry")))?;
+    write_between(
+        writer,
+        "Ntry",
+        &transaction.extra_elements,
+        None,
+        Some("Amt"),
+    )?;
+
+    // Amount with currency
+    let mut amt_elem = BytesStart::new("Amt");
+    amt_elem.push_attribute(("Ccy", transaction.amount.currency.as_str()));
+    writer.write_event(Event::Start(amt_elem))?;
+    writer.write_event(Event::Text(BytesText::new(
+        &transaction.amount.normalized().to_string(),
+    )))?;
+    writer.write_event(Event::End(BytesEnd::new("Amt")))?;
+
+    // Credit/Debit Indicator
+    let credit_debit_ind = transaction
+        .credit_debit_ind
+        .ok_or_else(|| missing("Ntry/CdtDbtInd"))?;
+    write_element(writer, "CdtDbtInd", credit_debit_ind.as_str())?;
+    write_between(
+        writer,
+        "Ntry",
+        &transaction.extra_elements,
+        Some("CdtDbtInd"),
+        Some("Sts"),
+    )?;
+
+    // Status, wrapped in Cd since version 08
+    match options.version {
+        OutputVersion::V04 if transaction.status == EntryStatus::Future => {
+            return Err(ConvertError::FailedCheck {
+                path: "Ntry/Sts".to_string(),
+                message: "Status FUTR does not exist in camt.053.001.04".to_string(),
+            });
+        }
+        OutputVersion::V04 => write_element(writer, "Sts", transaction.status.as_str())?,
+        OutputVersion::V08 => {
+            writer.write_event(Event::Start(BytesStart::new("Sts")))?;
+            write_element(writer, "Cd", transaction.status.as_str())?;
+            writer.write_event(Event::End(BytesEnd::new("Sts")))?;
+        }
+    }
+
+    // Booking Date
+    if let Some(booking_date) = &transaction.booking_date {
+        write_date_choice(writer, "BookgDt", booking_date, options)?;
+    }
+
+    // Value Date, the booking date where the input has none
+    if let Some(value_date) = transaction.value_date.or(transaction.booking_date)
+        && include(options, &transaction.layout, "ValDt")
+    {
+        write_date_choice(writer, "ValDt", &value_date, options)?;
+    }
+
+    // Account Servicer Reference - deterministic ID
+    write_element(writer, "AcctSvcrRef", ref_id)?;
+    write_between(
+        writer,
+        "Ntry",
+        &transaction.extra_elements,
+        Some("AcctSvcrRef"),
+        Some("BkTxCd"),
+    )?;
+
+    // Bank Transaction Code
+    let (domain, family, sub_family) = transaction.bank_tx_family();
+    writer.write_event(Event::Start(BytesStart::new("BkTxCd")))?;
+    writer.write_event(Event::Start(BytesStart::new("Domn")))?;
+    write_element(writer, "Cd", domain)?;
+    writer.write_event(Event::Start(BytesStart::new("Fmly")))?;
+    write_element(writer, "Cd", family)?;
+    write_element(writer, "SubFmlyCd", sub_family)?;
+    writer.write_event(Event::End(BytesEnd::new("Fmly")))?;
+    writer.write_event(Event::End(BytesEnd::new("Domn")))?;
+
+    // Proprietary code
+    if include(options, &transaction.layout, "BkTxCd/Prtry") {
+        writer.write_event(Event::Start(BytesStart::new("Prtry")))?;
+        write_element(writer, "Cd", cut(&transaction.bank_tx_code, MAX_ID_LENGTH))?;
+        writer.write_event(Event::End(BytesEnd::new("Prtry")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("BkTxCd")))?;
+    write_between(
+        writer,
+        "Ntry",
+        &transaction.extra_elements,
+        Some("BkTxCd"),
+        Some("AmtDtls"),
+    )?;
+
+    // Amounts in other currencies, whether the input had them for the entry
+    // or its transaction details
+    if !transaction.amount_details.is_empty() {
+        write_amount_details(writer, &transaction.amount_details)?;
+    }
+    write_between(
+        writer,
+        "Ntry",
+        &transaction.extra_elements,
+        Some("AmtDtls"),
+        Some("NtryDtls"),
+    )?;
+
+    // ESR references found in the text are written as QR references, which
+    // have the same format
+    let esr = if options.esr_references {
+        find_esr(transaction)
+    } else {
+        EsrDetails::default()
+    };
+    let creditor_reference = transaction.creditor_reference.clone().or_else(|| {
+        esr.reference.map(|reference| CreditorReference {
+            kind: "QRR".to_string(),
+            reference,
+        })
+    });
+
+    // Entry Details
+    let has_details = !transaction.additional_info.is_empty()
+        || !transaction.remittance_info.is_empty()
+        || creditor_reference.is_some()
+        || !transaction.debtor_name.is_empty()
+        || !transaction.creditor_name.is_empty()
+        || !transaction.cheque_number.is_empty()
+        || !transaction.related_dates.is_empty()
+        || !transaction.transaction_info.is_empty()
+        || !transaction.supplementary_data.is_empty();
+    if has_details && include(options, &transaction.layout, "NtryDtls") {
+        writer.write_event(Event::Start(BytesStart::new("NtryDtls")))?;
+        writer.write_event(Event::Start(BytesStart::new("TxDtls")))?;
+
+        // References
+        writer.write_event(Event::Start(BytesStart::new("Refs")))?;
+        write_element(writer, "AcctSvcrRef", ref_id)?;
+        if !transaction.end_to_end_id.is_empty() {
+            write_element(
+                writer,
+                "EndToEndId",
+                cut(&transaction.end_to_end_id, MAX_ID_LENGTH),
+            )?;
+        }
+        if !transaction.cheque_number.is_empty() {
+            write_element(
+                writer,
+                "ChqNb",
+                cut(&transaction.cheque_number, MAX_ID_LENGTH),
+            )?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("Refs")))?;
+
+        // Amount
+        let mut amt_elem = BytesStart::new("Amt");
+        amt_elem.push_attribute(("Ccy", transaction.amount.currency.as_str()));
+        writer.write_event(Event::Start(amt_elem))?;
+        writer.write_event(Event::Text(BytesText::new(
+            &transaction.amount.normalized().to_string(),
+        )))?;
+        writer.write_event(Event::End(BytesEnd::new("Amt")))?;
+
+        // Credit/Debit Indicator
+        write_element(writer, "CdtDbtInd", credit_debit_ind.as_str())?;
+
+        // Related Parties, the debtor and creditor as party choices since
+        // version 08 and the ESR participant number as the creditor account
+        let has_parties = !transaction.debtor_name.is_empty()
+            || !transaction.creditor_name.is_empty()
+            || esr.participant.is_some();
+        if has_parties {
+            writer.write_event(Event::Start(BytesStart::new("RltdPties")))?;
+        }
+        write_party(writer, "Dbtr", &transaction.debtor_name, options)?;
+        write_party(writer, "Cdtr", &transaction.creditor_name, options)?;
+        if let Some(participant) = &esr.participant {
+            writer.write_event(Event::Start(BytesStart::new("CdtrAcct")))?;
+            writer.write_event(Event::Start(BytesStart::new("Id")))?;
+            writer.write_event(Event::Start(BytesStart::new("Othr")))?;
+            write_element(writer, "Id", participant)?;
+            writer.write_event(Event::End(BytesEnd::new("Othr")))?;
+            writer.write_event(Event::End(BytesEnd::new("Id")))?;
+            writer.write_event(Event::End(BytesEnd::new("CdtrAcct")))?;
+        }
+        if has_parties {
+            writer.write_event(Event::End(BytesEnd::new("RltdPties")))?;
+        }
+
+        // Remittance Information, the input lines or else the entry info
+        let lines: Vec<&str> = if transaction.remittance_info.is_empty() {
+            Some(transaction.additional_info.as_str())
+                .filter(|info| !info.is_empty())
+                .into_iter()
+                .collect()
+        } else {
+            transaction
+                .remittance_info
+                .iter()
+                .map(String::as_str)
+                .collect()
+        };
+        if !lines.is_empty() || creditor_reference.is_some() {
+            writer.write_event(Event::Start(BytesStart::new("RmtInf")))?;
+            for line in lines {
+                write_element(writer, "Ustrd", &text(line, MAX_NAME_LENGTH, options))?;
+            }
+            if let Some(reference) = &creditor_reference {
+                write_creditor_reference(writer, reference)?;
+            }
+            writer.write_event(Event::End(BytesEnd::new("RmtInf")))?;
+        }
+
+        // Related Dates
+        let dates = &transaction.related_dates;
+        if !dates.is_empty() {
+            writer.write_event(Event::Start(BytesStart::new("RltdDts")))?;
+            if let Some(acceptance) = &dates.acceptance {
+                write_element(writer, "AccptncDtTm", &format_datetime(acceptance))?;
+            }
+            if let Some(settlement) = &dates.interbank_settlement {
+                write_element(writer, "IntrBkSttlmDt", &format_date(settlement))?;
+            }
+            writer.write_event(Event::End(BytesEnd::new("RltdDts")))?;
+        }
+
+        // Additional Transaction Info
+        if !transaction.transaction_info.is_empty() {
+            write_element(
+                writer,
+                "AddtlTxInf",
+                &text(&transaction.transaction_info, MAX_INFO_LENGTH, options),
+            )?;
+        }
+        for element in &transaction.supplementary_data {
+            write_element_tree(writer, element, CAMT_08_NAMESPACE)?;
+        }
+
+        writer.write_event(Event::End(BytesEnd::new("TxDtls")))?;
+        writer.write_event(Event::End(BytesEnd::new("NtryDtls")))?;
+    }
+
+    // Additional Entry Info
+    if include(options, &transaction.layout, "AddtlNtryInf") {
+        write_element(
+            writer,
+            "AddtlNtryInf",
+            &text(&transaction.additional_info, MAX_INFO_LENGTH, options),
+        )?;
+    }
+    write_between(
+        writer,
+        "Ntry",
+        &transaction.extra_elements,
+        Some("AddtlNtryInf"),
+        None,
+    )?;
+
+    writer.write_event(Event::End(BytesEnd::new("Ntry")))?;
+
+    Ok(())
+}
+
+fn write_amount_details<W: Write>(writer: &mut Writer<W>, details: &AmountDetails) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("AmtDtls")))?;
+    for (name, detail) in details.details() {
+        writer.write_event(Event::Start(BytesStart::new(name)))?;
+        let mut amt_elem = BytesStart::new("Amt");
+        amt_elem.push_attribute(("Ccy", detail.amount.currency.as_str()));
+        writer.write_event(Event::Start(amt_elem))?;
+        writer.write_event(Event::Text(BytesText::new(
+            &detail.amount.normalized().to_string(),
+        )))?;
+        writer.write_event(Event::End(BytesEnd::new("Amt")))?;
+        if let Some(exchange) = &detail.exchange {
+            writer.write_event(Event::Start(BytesStart::new("CcyXchg")))?;
+            write_element(writer, "SrcCcy", &exchange.source_currency)?;
+            if !exchange.target_currency.is_empty() {
+                write_element(writer, "TrgtCcy", &exchange.target_currency)?;
+            }
+            if !exchange.unit_currency.is_empty() {
+                write_element(writer, "UnitCcy", &exchange.unit_currency)?;
+            }
+            write_element(writer, "XchgRate", &exchange.rate.to_string())?;
+            writer.write_event(Event::End(BytesEnd::new("CcyXchg")))?;
+        }
+        writer.write_event(Event::End(BytesEnd::new(name)))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("AmtDtls")))?;
+    Ok(())
+}
+
+// Whether to write an optional element. In fidelity mode only elements present
+// in the input are written.
+fn include(options: &WriteOptions, layout: &[String], path: &str) -> bool {
+    !options.fidelity || layout.iter().any(|p| p == path)
+}
+
+// Write a date choice element as Dt or DtTm, depending on the date format
+fn write_date_choice<W: Write>(
+    writer: &mut Writer<W>,
+    name: &str,
+    date: &DateOrDateTime,
+    options: &WriteOptions,
+) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    match (options.date_format, date) {
+        (DateFormat::Date, _) | (DateFormat::Input, DateOrDateTime::Date(_)) => {
+            write_element(writer, "Dt", &format_date(&date.date()))?;
+        }
+        (_, DateOrDateTime::DateTime(datetime)) => {
+            write_element(writer, "DtTm", &format_datetime(datetime))?;
+        }
+        (DateFormat::DateTime, DateOrDateTime::Date(date)) => {
+            let midnight = options.timezone.from_local(date.and_time(NaiveTime::MIN));
+            write_element(writer, "DtTm", &format_datetime(&midnight))?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+// Write a text-only element. The value is plain text as read from the input
+// and is escaped here, so it must not be escaped beforehand.
+// Structured remittance information with a creditor reference
+fn write_creditor_reference<W: Write>(
+    writer: &mut Writer<W>,
+    reference: &CreditorReference,
+) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("Strd")))?;
+    writer.write_event(Event::Start(BytesStart::new("CdtrRefInf")))?;
+    if !reference.kind.is_empty() {
+        writer.write_event(Event::Start(BytesStart::new("Tp")))?;
+        writer.write_event(Event::Start(BytesStart::new("CdOrPrtry")))?;
+        let code = if reference.is_iso_kind() {
+            "Cd"
+        } else {
+            "Prtry"
+        };
+        write_element(writer, code, cut(&reference.kind, MAX_ID_LENGTH))?;
+        writer.write_event(Event::End(BytesEnd::new("CdOrPrtry")))?;
+        writer.write_event(Event::End(BytesEnd::new("Tp")))?;
+    }
+    write_element(writer, "Ref", cut(&reference.reference, MAX_ID_LENGTH))?;
+    writer.write_event(Event::End(BytesEnd::new("CdtrRefInf")))?;
+    writer.write_event(Event::End(BytesEnd::new("Strd")))?;
+    Ok(())
+}
+
+// A related party by name, named directly up to version 04 and as a party
+// choice (Pty) since version 08; nothing if the name is empty
+fn write_party<W: Write>(
+    writer: &mut Writer<W>,
+    role: &str,
+    name: &str,
+    options: &WriteOptions,
+) -> Result<()> {
+    if name.is_empty() {
+        return Ok(());
+    }
+    let name = &text(name, MAX_NAME_LENGTH, options);
+    writer.write_event(Event::Start(BytesStart::new(role)))?;
+    match options.version {
+        OutputVersion::V04 => write_element(writer, "Nm", name)?,
+        OutputVersion::V08 => {
+            writer.write_event(Event::Start(BytesStart::new("Pty")))?;
+            write_element(writer, "Nm", name)?;
+            writer.write_event(Event::End(BytesEnd::new("Pty")))?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new(role)))?;
+    Ok(())
+}
+
+fn write_element<W: Write>(writer: &mut Writer<W>, name: &str, value: &str) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    writer.write_event(Event::Text(BytesText::new(value)))?;
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+fn missing(field: &str) -> ConvertError {
+    ConvertError::MissingField(field.to_string())
+}
+
+fn format_datetime(datetime: &DateTime<FixedOffset>) -> String {
+    // Output format: 2025-06-20T18:43:45+02:00
+    datetime.format("%Y-%m-%dT%H:%M:%S%:z").to_string()
+}
+
+fn format_date(date: &NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
@@ -0,0 +1,336 @@
+// Reading of zip archives (PKWARE APPNOTE), for the daily bundles some banks
+// deliver.
+//
+// Only what is needed to read the documents of an archive is supported:
+// stored and deflated members, read through the central directory. Encrypted
+// members and archives split over several files are rejected.
+
+use crate::inflate::{Inflater, crc32};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Take};
+use std::path::Path;
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+
+// End of central directory record without the comment
+const END_RECORD_SIZE: usize = 22;
+const MAX_COMMENT_SIZE: usize = 0xFFFF;
+
+const FLAG_ENCRYPTED: u16 = 0x0001;
+const FLAG_UTF8: u16 = 0x0800;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+const ZIP64_EXTRA: u16 = 0x0001;
+
+/// Whether a file starts like a zip archive
+pub fn is_zip(prefix: &[u8]) -> bool {
+    prefix.starts_with(&LOCAL_HEADER.to_le_bytes())
+        || prefix.starts_with(&END_OF_CENTRAL_DIRECTORY.to_le_bytes())
+}
+
+/// A file or directory in a zip archive
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    /// Path of the entry within the archive, with `/` separators
+    pub name: String,
+    /// Size of the uncompressed content in bytes
+    pub size: u64,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    header_offset: u64,
+}
+
+impl ZipEntry {
+    /// Whether the entry is a directory rather than a file
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    /// File name of an XML document, without its folders, or `None` for
+    /// directories, other files, macOS metadata and names with `..` parts.
+    /// Folders may also be separated by `\`, as in archives made on Windows.
+    pub fn document_name(&self) -> Option<&str> {
+        let parts = || self.name.split(['/', '\\']);
+        let file_name = parts().next_back()?;
+        let is_document = !self.is_dir()
+            && parts().all(|part| part != "..")
+            && parts().next() != Some("__MACOSX")
+            && !file_name.starts_with("._")
+            && Path::new(file_name)
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("xml"));
+        is_document.then_some(file_name)
+    }
+}
+
+/// A zip archive opened for reading its entries
+pub struct ZipArchive<R> {
+    inner: R,
+    entries: Vec<ZipEntry>,
+}
+
+impl<R: Read + Seek> ZipArchive<R> {
+    /// Read the central directory of an archive
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let (count, offset) = find_central_directory(&mut inner)?;
+        inner.seek(SeekFrom::Start(offset))?;
+        let mut directory = BufReader::new(&mut inner);
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            entries.push(read_central_header(&mut directory)?);
+        }
+        drop(directory);
+        Ok(ZipArchive { inner, entries })
+    }
+
+    /// The entries of the archive, in central directory order
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Open the entry at `index` of [`entries`](Self::entries) for reading.
+    ///
+    /// The content is decompressed while it is read, and an error is
+    /// returned at its end if its checksum or size do not match.
+    pub fn open(&mut self, index: usize) -> io::Result<ZipMember<'_, R>> {
+        let entry = self.entries[index].clone();
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            return Err(invalid(&format!("{} is encrypted", entry.name)));
+        }
+
+        self.inner.seek(SeekFrom::Start(entry.header_offset))?;
+        let mut header = [0u8; 30];
+        self.inner.read_exact(&mut header)?;
+        if u32_at(&header, 0) != LOCAL_HEADER {
+            return Err(invalid("invalid local file header"));
+        }
+        let name_length = u16_at(&header, 26) as i64;
+        let extra_length = u16_at(&header, 28) as i64;
+        self.inner
+            .seek(SeekFrom::Current(name_length + extra_length))?;
+
+        let data = BufReader::new((&mut self.inner).take(entry.compressed_size));
+        let data = match entry.method {
+            METHOD_STORED => Data::Stored {
+                input: data,
+                crc: 0,
+                size: 0,
+            },
+            METHOD_DEFLATED => Data::Deflated(Inflater::new(data)),
+            method => {
+                return Err(invalid(&format!(
+                    "{} uses unsupported compression method {}",
+                    entry.name, method
+                )));
+            }
+        };
+        Ok(ZipMember {
+            data,
+            entry,
+            checked: false,
+        })
+    }
+}
+
+/// Reader for the content of a zip archive entry
+pub struct ZipMember<'a, R> {
+    data: Data<'a, R>,
+    entry: ZipEntry,
+    checked: bool,
+}
+
+enum Data<'a, R> {
+    Stored {
+        input: BufReader<Take<&'a mut R>>,
+        crc: u32,
+        size: u64,
+    },
+    Deflated(Inflater<BufReader<Take<&'a mut R>>>),
+}
+
+impl<R: Read> ZipMember<'_, R> {
+    // Compare checksum and size with the central directory once all content
+    // has been read
+    fn check(&mut self) -> io::Result<()> {
+        let (crc, size) = match &self.data {
+            Data::Stored { crc, size, .. } => (*crc, *size),
+            Data::Deflated(inflater) => (inflater.crc(), inflater.size()),
+        };
+        self.checked = true;
+        if size != self.entry.size {
+            return Err(invalid(&format!(
+                "{} has the wrong length",
+                self.entry.name
+            )));
+        }
+        if crc != self.entry.crc {
+            return Err(invalid(&format!(
+                "checksum mismatch in {}",
+                self.entry.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ZipMember<'_, R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(out.len());
+        out[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for ZipMember<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let at_end = match &mut self.data {
+            Data::Stored { input, .. } => input.fill_buf()?.is_empty(),
+            Data::Deflated(inflater) => inflater.fill_buf()?.is_empty(),
+        };
+        if at_end && !self.checked {
+            self.check()?;
+        }
+        match &mut self.data {
+            Data::Stored { input, .. } => input.fill_buf(),
+            Data::Deflated(inflater) => inflater.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match &mut self.data {
+            Data::Stored { input, crc, size } => {
+                // The bytes are still buffered, so this does not read
+                if let Ok(buf) = input.fill_buf() {
+                    let amt = amt.min(buf.len());
+                    *crc = crc32(*crc, &buf[..amt]);
+                    *size += amt as u64;
+                }
+                input.consume(amt);
+            }
+            Data::Deflated(inflater) => inflater.consume(amt),
+        }
+    }
+}
+
+// Locate the end of central directory record, which is followed only by the
+// archive comment. Returns the number of entries and the offset of the
+// central directory.
+fn find_central_directory<R: Read + Seek>(inner: &mut R) -> io::Result<(usize, u64)> {
+    let length = inner.seek(SeekFrom::End(0))?;
+    let tail_length = length.min((END_RECORD_SIZE + MAX_COMMENT_SIZE) as u64);
+    inner.seek(SeekFrom::Start(length - tail_length))?;
+    let mut tail = vec![0u8; tail_length as usize];
+    inner.read_exact(&mut tail)?;
+
+    let signature = END_OF_CENTRAL_DIRECTORY.to_le_bytes();
+    let record = (0..=tail.len().saturating_sub(END_RECORD_SIZE))
+        .rev()
+        .find(|&i| {
+            tail.len() >= i + END_RECORD_SIZE
+                && tail[i..].starts_with(&signature)
+                && i + END_RECORD_SIZE + u16_at(&tail, i + 20) as usize == tail.len()
+        })
+        .ok_or_else(|| invalid("end of central directory not found"))?;
+    let record = &tail[record..];
+
+    if u16_at(record, 4) != 0 || u16_at(record, 6) != 0 {
+        return Err(invalid(
+            "archives split over several files are not supported",
+        ));
+    }
+    let count = u16_at(record, 10);
+    let offset = u32_at(record, 16);
+    if count == 0xFFFF || offset == 0xFFFF_FFFF {
+        return Err(invalid("zip64 archives are not supported"));
+    }
+    Ok((count as usize, offset as u64))
+}
+
+fn read_central_header<R: BufRead>(input: &mut R) -> io::Result<ZipEntry> {
+    let mut header = [0u8; 46];
+    input.read_exact(&mut header)?;
+    if u32_at(&header, 0) != CENTRAL_HEADER {
+        return Err(invalid("invalid central directory header"));
+    }
+    let flags = u16_at(&header, 8);
+    let mut name = vec![0u8; u16_at(&header, 28) as usize];
+    input.read_exact(&mut name)?;
+    let mut extra = vec![0u8; u16_at(&header, 30) as usize];
+    input.read_exact(&mut extra)?;
+    let mut comment = vec![0u8; u16_at(&header, 32) as usize];
+    input.read_exact(&mut comment)?;
+
+    // Names are UTF-8 when flagged, otherwise code page 437, which agrees
+    // with UTF-8 for the ASCII names used in practice
+    let name = if flags & FLAG_UTF8 != 0 {
+        String::from_utf8(name).map_err(|_| invalid("entry name is not valid UTF-8"))?
+    } else {
+        String::from_utf8_lossy(&name).into_owned()
+    };
+
+    let mut entry = ZipEntry {
+        name,
+        size: u32_at(&header, 24) as u64,
+        flags,
+        method: u16_at(&header, 10),
+        crc: u32_at(&header, 16),
+        compressed_size: u32_at(&header, 20) as u64,
+        header_offset: u32_at(&header, 42) as u64,
+    };
+    read_zip64_extra(&mut entry, &extra)?;
+    Ok(entry)
+}
+
+// Sizes and offsets that do not fit into 32 bits are stored in the zip64
+// extra field, in this order and only if their 32 bit field is saturated
+fn read_zip64_extra(entry: &mut ZipEntry, mut extra: &[u8]) -> io::Result<()> {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let length = (u16_at(extra, 2) as usize).min(extra.len() - 4);
+        let mut data = &extra[4..4 + length];
+        if id == ZIP64_EXTRA {
+            for field in [
+                &mut entry.size,
+                &mut entry.compressed_size,
+                &mut entry.header_offset,
+            ] {
+                if *field == 0xFFFF_FFFF {
+                    if data.len() < 8 {
+                        return Err(invalid("truncated zip64 extra field"));
+                    }
+                    *field = u64::from_le_bytes(data[..8].try_into().unwrap());
+                    data = &data[8..];
+                }
+            }
+        }
+        extra = &extra[4 + length..];
+    }
+    Ok(())
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid zip archive: {}", message),
+    )
+}
@@ -0,0 +1,104 @@
+use camtconvert::alias::Aliases;
+use camtconvert::model::{CreditDebit, Statement, Transaction};
+
+const ALIASES: &str = r#"
+    # Comments and blank lines are skipped
+    Amazon = "AMZN Mktp CH*", "amazon   eu sarl" # comment
+    Coop = matches "(?i)^coop(-| |$)"
+    Say "Hi" = "say \"hi\""
+    Amazon Web Services = "AWS*"
+"#;
+
+#[test]
+fn maps_variants_to_canonical_names() {
+    let aliases: Aliases = ALIASES.parse().unwrap();
+    assert_eq!(aliases.canonical("AMZN Mktp CH*2F4"), Some("Amazon"));
+    assert_eq!(aliases.canonical("amzn mktp ch"), Some("Amazon"));
+    assert_eq!(aliases.canonical(" AMAZON EU\tSARL "), Some("Amazon"));
+    assert_eq!(aliases.canonical("AMAZON EU SARL LUX"), None);
+    assert_eq!(aliases.canonical("Coop-1234 Zürich"), Some("Coop"));
+    assert_eq!(aliases.canonical("COOP"), Some("Coop"));
+    assert_eq!(aliases.canonical("Cooperative Bank"), None);
+    assert_eq!(aliases.canonical("Say \"HI\""), Some("Say \"Hi\""));
+    assert_eq!(
+        aliases.canonical("AWS EMEA SARL"),
+        Some("Amazon Web Services")
+    );
+    assert_eq!(aliases.canonical(""), None);
+}
+
+#[test]
+fn replaces_counterparty_names_of_entries() {
+    let aliases: Aliases = ALIASES.parse().unwrap();
+    let entry = |credit_debit: CreditDebit, debtor: &str, creditor: &str| Transaction {
+        credit_debit_ind: Some(credit_debit),
+        debtor_name: debtor.to_string(),
+        creditor_name: creditor.to_string(),
+        ..Transaction::default()
+    };
+    let mut statement = Statement {
+        transactions: vec![
+            entry(CreditDebit::Debit, "Müller AG", "AMZN Mktp CH*2F4"),
+            entry(CreditDebit::Credit, "AMAZON EU SARL", "Müller AG"),
+            entry(CreditDebit::Credit, "Muster AG", "Müller AG"),
+            entry(CreditDebit::Debit, "Müller AG", ""),
+        ],
+        ..Statement::default()
+    };
+    aliases.apply(&mut statement);
+    let payees: Vec<&str> = statement
+        .transactions
+        .iter()
+        .map(|transaction| transaction.payee())
+        .collect();
+    assert_eq!(payees, ["Amazon", "Amazon", "Muster AG", ""]);
+
+    // The names of the bank are kept
+    assert_eq!(statement.transactions[0].creditor_name, "AMZN Mktp CH*2F4");
+    assert_eq!(statement.transactions[0].debtor_name, "Müller AG");
+    assert_eq!(statement.transactions[2].payee, "");
+
+    // Cleaned merchant names are mapped too
+    let mut card = entry(CreditDebit::Debit, "Müller AG", "AMZN Mktp CH*2F4 LUX");
+    card.payee = "AMAZON EU SARL".to_string();
+    aliases.apply_transaction(&mut card);
+    assert_eq!(card.payee(), "Amazon");
+}
+
+#[test]
+fn rejects_invalid_mappings() {
+    for (aliases, message) in [
+        (
+            "Amazon \"AMZN*\"",
+            "Line 1 of aliases: expected a canonical name and = at the start",
+        ),
+        (
+            "\n = \"AMZN*\"",
+            "Line 2 of aliases: expected a canonical name before =",
+        ),
+        (
+            "Amazon = AMZN",
+            "Line 1 of aliases: expected a name in double quotes instead of 'A'",
+        ),
+        (
+            "Amazon = \"AMZN\",",
+            "Line 1 of aliases: expected a name in double quotes at the end",
+        ),
+        (
+            "Amazon = \"AMZN\" \"AMAZON\"",
+            "Line 1 of aliases: expected , instead of '\"'",
+        ),
+        ("Amazon = \"AMZN", "Line 1 of aliases: unclosed string"),
+        (
+            "Amazon = matches \"(AMZN\"",
+            "Line 1 of aliases: missing ) in regular expression",
+        ),
+    ] {
+        assert_eq!(
+            aliases.parse::<Aliases>().unwrap_err().to_string(),
+            message,
+            "{}",
+            aliases
+        );
+    }
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::archive::{archive_file, archive_name, error_path, quarantine_file};
+use chrono::NaiveDate;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn names_archived_files() {
+    let timestamp = NaiveDate::from_ymd_opt(2025, 6, 20)
+        .unwrap()
+        .and_hms_opt(10, 15, 0);
+    assert_eq!(
+        archive_name(Path::new("in/statement.xml"), timestamp).unwrap(),
+        "statement_20250620T101500.xml"
+    );
+    assert_eq!(
+        archive_name(Path::new("in/statement"), timestamp).unwrap(),
+        "statement_20250620T101500"
+    );
+    assert_eq!(
+        archive_name(Path::new("in/statement.xml"), None).unwrap(),
+        "statement.xml"
+    );
+}
+
+#[test]
+fn moves_files_without_overwriting() {
+    let dir = std::env::temp_dir().join(format!("camtconvert-archive-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("statement.xml");
+    let archive = dir.join("archive");
+
+    fs::write(&input, "first").unwrap();
+    let archived = archive_file(&input, &archive, None).unwrap();
+    assert_eq!(archived, archive.join("statement.xml"));
+    assert!(!input.exists());
+
+    fs::write(&input, "second").unwrap();
+    assert!(archive_file(&input, &archive, None).is_err());
+    assert_eq!(fs::read_to_string(&archived).unwrap(), "first");
+    assert!(input.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn quarantines_files_with_their_errors() {
+    let dir = std::env::temp_dir().join(format!("camtconvert-quarantine-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("statement.xml");
+    let quarantine = dir.join("quarantine");
+    let time = NaiveDate::from_ymd_opt(2025, 6, 20)
+        .unwrap()
+        .and_hms_opt(10, 15, 0)
+        .unwrap();
+
+    fs::write(&input, "<Document").unwrap();
+    let quarantined = quarantine_file(
+        &input,
+        &quarantine,
+        time,
+        "statement.xml: Error parsing XML\n",
+    )
+    .unwrap();
+    assert_eq!(
+        quarantined,
+        quarantine.join("statement_20250620T101500.xml")
+    );
+    assert!(!input.exists());
+    assert_eq!(
+        error_path(&quarantined),
+        quarantine.join("statement_20250620T101500.xml.error.txt")
+    );
+    assert_eq!(
+        fs::read_to_string(error_path(&quarantined)).unwrap(),
+        "statement.xml: Error parsing XML\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
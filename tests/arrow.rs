@@ -0,0 +1,273 @@
+use camtconvert::arrow::write_entries;
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+use camtconvert::reference::ReferenceStyle;
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id><Ccy>CHF</Ccy></Acct>
+      <Ntry>
+        <Amt Ccy="CHF">1234.5</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+        <AcctSvcrRef>BANK-REF-1</AcctSvcrRef>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">80.125</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <AddtlNtryInf>Refund</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[test]
+fn writes_entries_as_arrow_stream() {
+    let statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    let mut output = Vec::new();
+    write_entries(&mut output, &statement, ReferenceStyle::Hash).unwrap();
+
+    // Schema and record batch messages, each with 8 byte aligned metadata,
+    // and the end of stream marker
+    assert_eq!(output[..4], [0xFF; 4]);
+    let schema_length = i32::from_le_bytes(output[4..8].try_into().unwrap()) as usize;
+    assert_eq!(schema_length % 8, 0);
+    let batch = 8 + schema_length;
+    assert_eq!(output[batch..batch + 4], [0xFF; 4]);
+    assert!(output.ends_with(&[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0]));
+    assert_eq!(output.len() % 8, 0);
+
+    let schema = &output[8..batch];
+    for name in [
+        "iban",
+        "reference",
+        "amount",
+        "booking_date",
+        "additional_info",
+        "payee",
+    ] {
+        assert!(find(schema, name.as_bytes()).is_some(), "{}", name);
+    }
+
+    // Amounts at the largest scale of the statement, three places
+    let body = &output[batch..];
+    let debit = (-1_234_500i128).to_le_bytes();
+    let credit = 80_125i128.to_le_bytes();
+    let amounts = find(body, &debit).unwrap();
+    assert_eq!(body[amounts + 16..amounts + 32], credit);
+    // 2025-06-05 as days since 1970-01-01
+    assert!(find(body, &20_244i32.to_le_bytes()).is_some());
+    assert!(find(body, b"BANK-REF-1").is_some());
+    assert!(find(body, b"Refund").is_some());
+}
+
+// A table of the FlatBuffers holding IPC metadata, read without the writer's
+// code: the vtable before it gives the offset of each field
+struct Table<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Table<'a> {
+    fn root(buffer: &'a [u8]) -> Self {
+        Table {
+            buffer,
+            position: uoffset(buffer, 0),
+        }
+    }
+
+    fn field(&self, id: usize) -> Option<usize> {
+        let soffset = i32::from_le_bytes(self.bytes(self.position));
+        let vtable = (self.position as i64 - i64::from(soffset)) as usize;
+        let entry = 4 + 2 * id;
+        if entry >= usize::from(u16::from_le_bytes(self.bytes(vtable))) {
+            return None;
+        }
+        let offset = usize::from(u16::from_le_bytes(self.bytes(vtable + entry)));
+        (offset != 0).then_some(self.position + offset)
+    }
+
+    fn bytes<const N: usize>(&self, at: usize) -> [u8; N] {
+        self.buffer[at..at + N].try_into().unwrap()
+    }
+
+    fn u8(&self, id: usize) -> u8 {
+        self.field(id).map_or(0, |at| self.buffer[at])
+    }
+
+    fn i16(&self, id: usize) -> i16 {
+        self.field(id)
+            .map_or(0, |at| i16::from_le_bytes(self.bytes(at)))
+    }
+
+    fn i32(&self, id: usize) -> i32 {
+        self.field(id)
+            .map_or(0, |at| i32::from_le_bytes(self.bytes(at)))
+    }
+
+    fn i64(&self, id: usize) -> i64 {
+        self.field(id)
+            .map_or(0, |at| i64::from_le_bytes(self.bytes(at)))
+    }
+
+    fn table(&self, id: usize) -> Table<'a> {
+        let at = self.field(id).unwrap();
+        Table {
+            buffer: self.buffer,
+            position: at + uoffset(self.buffer, at),
+        }
+    }
+
+    // Start and length of a vector
+    fn vector(&self, id: usize) -> (usize, usize) {
+        let at = self.field(id).unwrap();
+        let vector = at + uoffset(self.buffer, at);
+        (vector + 4, uoffset(self.buffer, vector))
+    }
+
+    fn string(&self, id: usize) -> &'a str {
+        let (start, length) = self.vector(id);
+        std::str::from_utf8(&self.buffer[start..start + length]).unwrap()
+    }
+
+    fn tables(&self, id: usize) -> Vec<Table<'a>> {
+        let (start, length) = self.vector(id);
+        (0..length)
+            .map(|index| {
+                let at = start + 4 * index;
+                Table {
+                    buffer: self.buffer,
+                    position: at + uoffset(self.buffer, at),
+                }
+            })
+            .collect()
+    }
+
+    // Structs of two longs, as FieldNode and Buffer
+    fn pairs(&self, id: usize) -> Vec<[i64; 2]> {
+        let (start, length) = self.vector(id);
+        (0..length)
+            .map(|index| {
+                let at = start + 16 * index;
+                [
+                    i64::from_le_bytes(self.bytes(at)),
+                    i64::from_le_bytes(self.bytes(at + 8)),
+                ]
+            })
+            .collect()
+    }
+}
+
+fn uoffset(buffer: &[u8], at: usize) -> usize {
+    u32::from_le_bytes(buffer[at..at + 4].try_into().unwrap()) as usize
+}
+
+// The Message table of the encapsulated message at `at` and its body
+fn message(stream: &[u8], at: usize) -> (Table<'_>, &[u8]) {
+    assert_eq!(stream[at..at + 4], [0xFF; 4]);
+    let length = uoffset(stream, at + 4);
+    let metadata = &stream[at + 8..at + 8 + length];
+    let message = Table::root(metadata);
+    // MetadataVersion V5
+    assert_eq!(message.i16(0), 4);
+    let body = at + 8 + length;
+    let body = &stream[body..body + message.i64(3) as usize];
+    (message, body)
+}
+
+#[test]
+fn writes_schema_and_record_batch_as_arrow_reads_them() {
+    let statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    let mut output = Vec::new();
+    write_entries(&mut output, &statement, ReferenceStyle::Hash).unwrap();
+
+    let (schema, body) = message(&output, 0);
+    assert_eq!(schema.u8(1), 1);
+    assert!(body.is_empty());
+    let fields = schema.table(2).tables(1);
+    // Type union members: Utf8 5, Decimal 7, Date 8
+    let text = |name| (name, 5);
+    let expected = [
+        text("iban"),
+        text("statement"),
+        text("account_servicer_ref"),
+        text("reference"),
+        text("end_to_end_id"),
+        ("amount", 7),
+        text("currency"),
+        text("status"),
+        ("booking_date", 8),
+        ("value_date", 8),
+        text("bank_transaction_code"),
+        text("creditor_reference"),
+        text("debtor"),
+        text("remittance_info"),
+        text("additional_info"),
+        text("category"),
+        text("gl_account"),
+        text("payee"),
+    ];
+    let found: Vec<(&str, u8)> = fields
+        .iter()
+        .map(|field| (field.string(0), field.u8(2)))
+        .collect();
+    assert_eq!(found, expected);
+    for field in &fields {
+        assert_eq!(field.u8(1), 1, "{} is nullable", field.string(0));
+    }
+    // Decimal128 with precision 38 and three places, dates in days (unit 0)
+    let amount = fields[5].table(3);
+    assert_eq!((amount.i32(0), amount.i32(1), amount.i32(2)), (38, 3, 128));
+    assert_eq!(fields[8].table(3).i16(0), 0);
+
+    let batch_at = 8 + uoffset(&output, 4);
+    let (batch, body) = message(&output, batch_at);
+    assert_eq!(batch.u8(1), 3);
+    let batch = batch.table(2);
+    assert_eq!(batch.i64(0), 2);
+    let nodes = batch.pairs(1);
+    assert_eq!(nodes.len(), fields.len());
+    assert!(nodes.iter().all(|[rows, _]| *rows == 2));
+    // Only the first entry has a bank reference and a booking date, only the
+    // second an additional info
+    let nulls: Vec<i64> = nodes.iter().map(|[_, nulls]| *nulls).collect();
+    assert_eq!(nulls[..10], [0, 0, 1, 0, 2, 0, 0, 0, 1, 2]);
+    assert_eq!(nulls[14], 1);
+
+    // Validity, offsets and data for text, validity and values otherwise
+    let buffers = batch.pairs(2);
+    assert_eq!(buffers.len(), 3 * 15 + 2 * 2 + 2);
+    for [offset, length] in &buffers {
+        assert_eq!(offset % 8, 0);
+        assert!((offset + length) as usize <= body.len());
+    }
+    let buffer = |index: usize| {
+        let [offset, length] = buffers[index];
+        &body[offset as usize..(offset + length) as usize]
+    };
+    // The columns before the amount are texts of three buffers each
+    let amounts = buffer(3 * 5 + 1);
+    assert_eq!(amounts[..16], (-1_234_500i128).to_le_bytes());
+    assert_eq!(amounts[16..], 80_125i128.to_le_bytes());
+    let account_servicer_refs = (buffer(2 * 3 + 1), buffer(2 * 3 + 2));
+    assert_eq!(
+        account_servicer_refs.0,
+        [0, 0, 0, 0, 10, 0, 0, 0, 10, 0, 0, 0]
+    );
+    assert_eq!(account_servicer_refs.1, b"BANK-REF-1");
+}
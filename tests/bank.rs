@@ -0,0 +1,58 @@
+use camtconvert::bank::{Evidence, clearing_number, detect};
+use camtconvert::model::{Statement, Transaction};
+
+fn statement(servicer_bic: &str, iban: &str, codes: &[&str]) -> Statement {
+    Statement {
+        servicer_bic: servicer_bic.to_string(),
+        iban: iban.to_string(),
+        transactions: codes
+            .iter()
+            .map(|code| Transaction {
+                bank_tx_code: code.to_string(),
+                ..Transaction::default()
+            })
+            .collect(),
+        ..Statement::default()
+    }
+}
+
+#[test]
+fn detects_bank_from_bic_clearing_number_or_codes() {
+    let detection = detect(&statement("POFICHBEXXX", "", &[])).unwrap();
+    assert_eq!(detection.bank.id, "postfinance");
+    assert_eq!(
+        detection.evidence,
+        Evidence::ServicerBic("POFICHBEXXX".to_string())
+    );
+
+    let detection = detect(&statement("", "CH56 0900 0000 1234 5678 9", &[])).unwrap();
+    assert_eq!(detection.bank.id, "postfinance");
+    assert_eq!(detection.evidence, Evidence::ClearingNumber(9000));
+    let detection = detect(&statement("", "CH1080808001234567890", &[])).unwrap();
+    assert_eq!(detection.bank.id, "raiffeisen");
+
+    // The BIC wins over the clearing number
+    let detection = detect(&statement("UBSWCHZH80A", "CH5609000000123456789", &[])).unwrap();
+    assert_eq!(detection.bank.id, "ubs");
+
+    let wise = statement("", "BE12967000000000", &["TRANSFER-123", "CARD-456"]);
+    let detection = detect(&wise).unwrap();
+    assert_eq!(detection.bank.id, "wise");
+    assert_eq!(detection.evidence, Evidence::ProprietaryCodes);
+
+    // Codes only count if all entries have them
+    assert_eq!(detect(&statement("", "", &["TRANSFER-123", "NTRF"])), None);
+    assert_eq!(detect(&statement("", "", &[])), None);
+    assert_eq!(
+        detect(&statement("XXXXXXXX", "DE89370400440532013000", &[])),
+        None
+    );
+}
+
+#[test]
+fn reads_clearing_numbers_of_swiss_ibans() {
+    assert_eq!(clearing_number("CH9300762011623852957"), Some(762));
+    assert_eq!(clearing_number("li21 0881 0000 2324 013a a"), Some(8810));
+    assert_eq!(clearing_number("DE89370400440532013000"), None);
+    assert_eq!(clearing_number("CH93"), None);
+}
@@ -0,0 +1,19 @@
+use camtconvert::bic::{BicError, validate_bic};
+
+#[test]
+fn accepts_valid_bics() {
+    for bic in ["UBSWCHZH", "UBSWCHZH80A", "POFICHBEXXX", "DEUTDEFF500"] {
+        assert_eq!(validate_bic(bic), Ok(()), "{}", bic);
+    }
+}
+
+#[test]
+fn rejects_invalid_bics() {
+    assert_eq!(
+        validate_bic("XXXXXXXX"),
+        Err(BicError::Country("XX".to_string()))
+    );
+    assert_eq!(validate_bic("UBSWCHZH8"), Err(BicError::Length(9)));
+    assert_eq!(validate_bic("ubswchzh"), Err(BicError::Format));
+    assert_eq!(validate_bic("UBSW12ZH"), Err(BicError::Format));
+}
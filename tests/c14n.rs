@@ -0,0 +1,35 @@
+use camtconvert::c14n::canonicalize;
+
+fn canonical(document: &str) -> String {
+    let mut output = Vec::new();
+    canonicalize(document.as_bytes(), &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn writes_exclusive_canonical_form() {
+    let document = "<?xml version='1.0' encoding='UTF-8'?>\r\n\
+                    <!-- statement -->\r\n\
+                    <Document xmlns='urn:camt' xmlns:unused='urn:unused' xmlns:b='urn:bank'>\r\n\
+                    <Amt Ccy='CHF' b:z=\"1\" a='&apos;x&apos;'>10.00</Amt>\r\n\
+                    <Empty/><b:Info>A &amp; B &gt; \"C\"<![CDATA[<D>]]></b:Info>\r\n\
+                    </Document>\r\n";
+    assert_eq!(
+        canonical(document),
+        "<Document xmlns=\"urn:camt\">\n\
+         <Amt xmlns:b=\"urn:bank\" Ccy=\"CHF\" a=\"'x'\" b:z=\"1\">10.00</Amt>\n\
+         <Empty></Empty><b:Info xmlns:b=\"urn:bank\">A &amp; B &gt; \"C\"&lt;D&gt;</b:Info>\n\
+         </Document>"
+    );
+}
+
+#[test]
+fn declares_namespaces_once() {
+    let document =
+        r#"<a:Doc xmlns:a="urn:a"><a:Child><a:Leaf/></a:Child><Plain xmlns=""/></a:Doc>"#;
+    assert_eq!(
+        canonical(document),
+        r#"<a:Doc xmlns:a="urn:a"><a:Child><a:Leaf></a:Leaf></a:Child><Plain></Plain></a:Doc>"#
+    );
+    assert_eq!(canonical(&canonical(document)), canonical(document));
+}
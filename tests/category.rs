@@ -0,0 +1,164 @@
+use camtconvert::category::Categories;
+use camtconvert::model::{Amount, CreditDebit, Transaction};
+
+// An entry from or to a counterparty, the creditor of a debit and the
+// debtor of a credit
+fn transaction(amount: &str, credit_debit: CreditDebit, counterparty: &str) -> Transaction {
+    let mut transaction = Transaction {
+        amount: Amount {
+            value: amount.parse().unwrap(),
+            currency: "CHF".to_string(),
+        },
+        credit_debit_ind: Some(credit_debit),
+        ..Transaction::default()
+    };
+    match credit_debit {
+        CreditDebit::Debit => transaction.creditor_name = counterparty.to_string(),
+        CreditDebit::Credit => transaction.debtor_name = counterparty.to_string(),
+    }
+    transaction
+}
+
+const RULES: &str = r#"
+    # Comments and blank lines are skipped
+    groceries: counterparty matches "(?i)^(migros|coop)\s" and amount < 0
+    salary: remittance contains "SALARY" and amount > 0 # comment
+    bank-fees: code starts_with "PMNT-CCRD-CHRG"
+    rent: amount >= -2500 and amount <= -2000.00 and text matches "(?i)miete|rent"
+    quoted: info == "say \"hi\""
+    other:
+"#;
+
+#[test]
+fn assigns_the_category_of_the_first_matching_rule() {
+    let categories: Categories = RULES.parse().unwrap();
+
+    let groceries = transaction("45.20", CreditDebit::Debit, "COOP Pronto");
+    assert_eq!(categories.categorize(&groceries), Some("groceries"));
+    let refund = transaction("45.20", CreditDebit::Credit, "COOP Pronto");
+    assert_eq!(categories.categorize(&refund), Some("other"));
+
+    let mut salary = transaction("5000", CreditDebit::Credit, "Muster AG");
+    salary.remittance_info = vec!["SALARY".to_string(), "MARCH".to_string()];
+    assert_eq!(categories.categorize(&salary), Some("salary"));
+
+    let mut fee = transaction("2.00", CreditDebit::Debit, "");
+    fee.bank_tx_code = "PMNT-CCRD-CHRG-X".to_string();
+    assert_eq!(categories.categorize(&fee), Some("bank-fees"));
+
+    let mut rent = transaction("2100", CreditDebit::Debit, "Immo AG");
+    rent.transaction_info = "Miete April".to_string();
+    assert_eq!(categories.categorize(&rent), Some("rent"));
+    let mut large = rent.clone();
+    large.amount.value = "2500.01".parse().unwrap();
+    assert_eq!(categories.categorize(&large), Some("other"));
+
+    let mut quoted = transaction("1", CreditDebit::Debit, "");
+    quoted.additional_info = "say \"hi\"".to_string();
+    assert_eq!(categories.categorize(&quoted), Some("quoted"));
+
+    let without_fallback: Categories = "fees: code == \"FEE\"".parse().unwrap();
+    assert_eq!(without_fallback.categorize(&refund), None);
+}
+
+#[test]
+fn matches_the_creditor_of_debits_as_counterparty() {
+    let categories: Categories =
+        r#"groceries: counterparty matches "(?i)migros|coop|aldi" and amount < 0"#
+            .parse()
+            .unwrap();
+    let mut purchase = transaction("45.20", CreditDebit::Debit, "Migros Zürich");
+    purchase.debtor_name = "Müller AG".to_string();
+    assert_eq!(categories.categorize(&purchase), Some("groceries"));
+
+    // The account owner as debtor of a debit is not the counterparty
+    let mut other = transaction("45.20", CreditDebit::Debit, "Landi Bern");
+    other.debtor_name = "Coop Genossenschaft".to_string();
+    assert_eq!(categories.categorize(&other), None);
+}
+
+#[test]
+fn sets_and_tags_the_category() {
+    let categories: Categories = RULES.parse().unwrap();
+    let mut groceries = transaction("45.20", CreditDebit::Debit, "Migros Zürich");
+    categories.apply(&mut groceries, false);
+    assert_eq!(groceries.category, "groceries");
+    assert_eq!(groceries.additional_info, "");
+
+    categories.apply(&mut groceries, true);
+    assert_eq!(groceries.additional_info, "#groceries");
+    groceries.additional_info = "Card payment #groceries".to_string();
+    categories.apply(&mut groceries, true);
+    assert_eq!(groceries.additional_info, "Card payment #groceries");
+
+    let mut unmatched = transaction("1", CreditDebit::Debit, "");
+    "fees: code == \"FEE\""
+        .parse::<Categories>()
+        .unwrap()
+        .apply(&mut unmatched, true);
+    assert_eq!(unmatched.category, "");
+    assert_eq!(unmatched.additional_info, "");
+}
+
+#[test]
+fn rejects_invalid_rules() {
+    for (rules, message) in [
+        (
+            "groceries counterparty == \"Coop\"",
+            "Line 1 of categories: expected a category and : at the start",
+        ),
+        (
+            "big spend: amount < -1000",
+            "Line 1 of categories: invalid category \"big spend\"",
+        ),
+        (
+            "\nfees: payee == \"Bank\"",
+            "Line 2 of categories: unknown field payee, expected amount or one of: \
+             remittance, counterparty, info, code, currency, text",
+        ),
+        (
+            "fees: code contains \"FEE\" code == \"X\"",
+            "Line 1 of categories: expected and instead of code",
+        ),
+        (
+            "fees: code contains \"FEE\" and",
+            "Line 1 of categories: expected a condition after and",
+        ),
+        (
+            "fees: amount contains \"1\"",
+            "Line 1 of categories: expected ==, !=, <, <=, > or >= instead of contains",
+        ),
+        (
+            "fees: amount < \"1\"",
+            "Line 1 of categories: expected an amount instead of \"1\"",
+        ),
+        (
+            "fees: code < \"1\"",
+            "Line 1 of categories: expected matches, ==, !=, contains, starts_with or \
+             ends_with instead of <",
+        ),
+        (
+            "fees: code matches \"(FEE\"",
+            "Line 1 of categories: missing ) in regular expression",
+        ),
+        (
+            "fees: code == 1",
+            "Line 1 of categories: expected a string instead of 1",
+        ),
+        (
+            "fees: code ==",
+            "Line 1 of categories: expected a value after ==",
+        ),
+        (
+            "fees: code == \"FEE",
+            "Line 1 of categories: unclosed string",
+        ),
+    ] {
+        assert_eq!(
+            rules.parse::<Categories>().unwrap_err().to_string(),
+            message,
+            "{}",
+            rules
+        );
+    }
+}
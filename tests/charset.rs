@@ -0,0 +1,52 @@
+use camtconvert::charset::{Charset, to_swift_x};
+use camtconvert::model::{Amount, CreditDebit, Statement, Transaction};
+use camtconvert::writer::{Indent, WriteOptions, write_camt_08};
+use chrono::DateTime;
+
+#[test]
+fn transliterates_to_swift_x() {
+    assert_eq!(to_swift_x("Müller & Söhne AG"), "Mueller + Soehne AG");
+    assert_eq!(to_swift_x("Crédit Agricole, Łódź"), "Credit Agricole, Lodz");
+    assert_eq!(to_swift_x("„Rechnung“ Nr_17; 5 €"), "'Rechnung' Nr-17, 5 .");
+    assert_eq!(to_swift_x("Straße\tGroß"), "Strasse Gross");
+
+    assert_eq!(Charset::Unicode.convert("Müller"), "Müller");
+    assert_eq!(Charset::SwiftX.convert("Muster AG"), "Muster AG");
+}
+
+#[test]
+fn writes_names_and_text_in_charset() {
+    let transaction = Transaction {
+        amount: Amount {
+            value: "10.00".parse().unwrap(),
+            currency: "CHF".to_string(),
+        },
+        credit_debit_ind: Some(CreditDebit::Credit),
+        additional_info: "Überweisung für René".to_string(),
+        debtor_name: "Zoë Brändli".to_string(),
+        end_to_end_id: "E2E-ä".to_string(),
+        ..Transaction::default()
+    };
+    let statement = Statement {
+        id: "STMT-1".to_string(),
+        creation_datetime: DateTime::parse_from_rfc3339("2025-06-22T17:33:43Z").ok(),
+        iban: "CH9300762011623852957".to_string(),
+        owner_name: "Müller AG".to_string(),
+        transactions: vec![transaction],
+        ..Statement::default()
+    };
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        charset: Charset::SwiftX,
+        ..WriteOptions::default()
+    };
+    let mut output = Vec::new();
+    write_camt_08(&mut output, &statement, &options).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("<Ownr><Nm>Mueller AG</Nm></Ownr>"));
+    assert!(output.contains("<Nm>Zoe Braendli</Nm>"));
+    assert!(output.contains("<Ustrd>Ueberweisung fuer Rene</Ustrd>"));
+    assert!(output.contains("<AddtlNtryInf>Ueberweisung fuer Rene</AddtlNtryInf>"));
+    // References are kept as they are
+    assert!(output.contains("<EndToEndId>E2E-ä</EndToEndId>"));
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::checksum::{
+    ChecksumAlgorithm, file_checksum, sidecar_path, verify_sidecar, write_sidecar,
+};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+fn temp_file(name: &str, content: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("camtconvert-{}-{}", std::process::id(), name));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn computes_sha256_test_vectors() {
+    let vectors: [(&str, Vec<u8>, &str); 4] = [
+        (
+            "empty",
+            Vec::new(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        (
+            "abc",
+            b"abc".to_vec(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            "two-blocks",
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".to_vec(),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+        (
+            "million",
+            vec![b'a'; 1_000_000],
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+        ),
+    ];
+    for (name, content, expected) in vectors {
+        let path = temp_file(name, &content);
+        assert_eq!(
+            file_checksum(&path, ChecksumAlgorithm::Sha256).unwrap(),
+            expected,
+            "{}",
+            name
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn verifies_written_sidecar() {
+    let path = temp_file("statement_08.xml", b"<Document/>\n");
+    let sidecar = write_sidecar(&path, ChecksumAlgorithm::Sha256).unwrap();
+    assert_eq!(sidecar, sidecar_path(&path, ChecksumAlgorithm::Sha256));
+    assert!(
+        sidecar
+            .to_string_lossy()
+            .ends_with("statement_08.xml.sha256")
+    );
+    let content = fs::read_to_string(&sidecar).unwrap();
+    let checksum = file_checksum(&path, ChecksumAlgorithm::Sha256).unwrap();
+    let file_name = path.file_name().unwrap().to_string_lossy();
+    assert_eq!(content, format!("{}  {}\n", checksum, file_name));
+    verify_sidecar(&path, ChecksumAlgorithm::Sha256).unwrap();
+
+    fs::write(&path, b"<Document></Document>\n").unwrap();
+    let error = verify_sidecar(&path, ChecksumAlgorithm::Sha256).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+    fs::remove_file(&sidecar).unwrap();
+    let error = verify_sidecar(&path, ChecksumAlgorithm::Sha256).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::NotFound);
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,140 @@
+use camtconvert::convert::convert_bytes;
+use camtconvert::reader::ParseOptions;
+use camtconvert::reference::ReferenceStyle;
+use camtconvert::writer::{Indent, WriteOptions};
+
+const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <GrpHdr>
+      <MsgId>MSG-1</MsgId>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+    </GrpHdr>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct>
+        <Id><IBAN>CH9300762011623852957</IBAN></Id>
+        <Ccy>CHF</Ccy>
+        <Ownr><Nm>Müller AG</Nm></Ownr>
+      </Acct>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="CHF">100.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Dt><Dt>2025-06-01</Dt></Dt>
+      </Bal>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="CHF">107.50</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Dt><Dt>2025-06-30</Dt></Dt>
+      </Bal>
+      <Ntry>
+        <Amt Ccy="CHF">10.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+        <ValDt><Dt>2025-06-05</Dt></ValDt>
+        <AddtlNtryInf>Invoice 1234 &amp; 1235</AddtlNtryInf>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">2.50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><DtTm>2025-06-06T09:15:00+02:00</DtTm></BookgDt>
+        <ValDt><Dt>2025-06-06</Dt></ValDt>
+        <AddtlNtryInf>Card fee</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+fn output(options: &WriteOptions) -> String {
+    let converted = convert_bytes(DOCUMENT.as_bytes(), &ParseOptions::default(), options).unwrap();
+    String::from_utf8(converted.output).unwrap()
+}
+
+fn references(options: &WriteOptions) -> Vec<String> {
+    output(options)
+        .split("<Ntry>")
+        .skip(1)
+        .map(|entry| {
+            let start = entry.find("<AcctSvcrRef>").unwrap() + "<AcctSvcrRef>".len();
+            let end = entry.find("</AcctSvcrRef>").unwrap();
+            entry[start..end].to_string()
+        })
+        .collect()
+}
+
+// The archive deduplicates outputs by their hash, so the same input and
+// options must give the same bytes on every run, machine and Rust release
+#[test]
+fn writes_fixed_bytes() {
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    assert_eq!(
+        output(&options),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <Document xmlns=\"urn:iso:std:iso:20022:tech:xsd:camt.053.001.08\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\"><BkToCstmrStmt>\
+         <GrpHdr><MsgId>STMT-1</MsgId><CreDtTm>2025-06-22T17:33:43+00:00</CreDtTm>\
+         <MsgRcpt><Id><OrgId><AnyBIC>XXXXXXXX</AnyBIC></OrgId></Id></MsgRcpt>\
+         <MsgPgntn><PgNb>1</PgNb><LastPgInd>true</LastPgInd></MsgPgntn>\
+         <AddtlInf>SPS/2.1</AddtlInf></GrpHdr>\
+         <Stmt><Id>STMT-1</Id><ElctrncSeqNb>1</ElctrncSeqNb>\
+         <CreDtTm>2025-06-22T17:33:43+00:00</CreDtTm>\
+         <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id><Ccy>CHF</Ccy>\
+         <Ownr><Nm>Müller AG</Nm></Ownr><Svcr><FinInstnId><BICFI>XXXXXXXX</BICFI>\
+         <Nm>Bank</Nm><Othr><Id>XXX-000.000.000</Id><Issr>ID</Issr></Othr>\
+         </FinInstnId></Svcr></Acct>\
+         <Bal><Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp><Amt Ccy=\"CHF\">100.00</Amt>\
+         <CdtDbtInd>CRDT</CdtDbtInd><Dt><Dt>2025-06-01</Dt></Dt></Bal>\
+         <Bal><Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp><Amt Ccy=\"CHF\">107.50</Amt>\
+         <CdtDbtInd>CRDT</CdtDbtInd><Dt><Dt>2025-06-30</Dt></Dt></Bal>\
+         <Ntry><Amt Ccy=\"CHF\">10.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><Sts><Cd>BOOK</Cd></Sts>\
+         <BookgDt><Dt>2025-06-05</Dt></BookgDt><ValDt><Dt>2025-06-05</Dt></ValDt>\
+         <AcctSvcrRef>TX2340110468</AcctSvcrRef><BkTxCd><Domn><Cd>PMNT</Cd>\
+         <Fmly><Cd>ICDT</Cd><SubFmlyCd>ESCT</SubFmlyCd></Fmly></Domn>\
+         <Prtry><Cd></Cd></Prtry></BkTxCd><NtryDtls><TxDtls>\
+         <Refs><AcctSvcrRef>TX2340110468</AcctSvcrRef></Refs>\
+         <Amt Ccy=\"CHF\">10.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>\
+         <RmtInf><Ustrd>Invoice 1234 &amp; 1235</Ustrd></RmtInf></TxDtls></NtryDtls>\
+         <AddtlNtryInf>Invoice 1234 &amp; 1235</AddtlNtryInf></Ntry>\
+         <Ntry><Amt Ccy=\"CHF\">2.50</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts><Cd>BOOK</Cd></Sts>\
+         <BookgDt><Dt>2025-06-06</Dt></BookgDt><ValDt><Dt>2025-06-06</Dt></ValDt>\
+         <AcctSvcrRef>TX0462203402</AcctSvcrRef><BkTxCd><Domn><Cd>PMNT</Cd>\
+         <Fmly><Cd>ICDT</Cd><SubFmlyCd>ESCT</SubFmlyCd></Fmly></Domn>\
+         <Prtry><Cd></Cd></Prtry></BkTxCd><NtryDtls><TxDtls>\
+         <Refs><AcctSvcrRef>TX0462203402</AcctSvcrRef></Refs>\
+         <Amt Ccy=\"CHF\">2.50</Amt><CdtDbtInd>DBIT</CdtDbtInd>\
+         <RmtInf><Ustrd>Card fee</Ustrd></RmtInf></TxDtls></NtryDtls>\
+         <AddtlNtryInf>Card fee</AddtlNtryInf></Ntry></Stmt></BkToCstmrStmt></Document>"
+    );
+    assert_eq!(
+        output(&WriteOptions::default()),
+        output(&WriteOptions::default())
+    );
+}
+
+#[test]
+fn writes_fixed_references() {
+    assert_eq!(
+        references(&WriteOptions::default()),
+        ["TX2340110468", "TX0462203402"]
+    );
+    let options = WriteOptions {
+        reference: ReferenceStyle::Uuid5,
+        ..WriteOptions::default()
+    };
+    assert_eq!(
+        references(&options),
+        [
+            "94f01bc113ee5f92887ea99b7fd97e9c",
+            "b0320042146b5254bffb3399ee6dbd72"
+        ]
+    );
+}
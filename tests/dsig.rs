@@ -0,0 +1,211 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::dsig::{
+    SignOptions, SignatureMode, VerifyOptions, check_reference, envelop, is_signed, sign_file,
+    signature_path, signature_template, verify,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SIGNED: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt><Stmt><Id>STMT-1</Id></Stmt></BkToCstmrStmt>
+  <ds:Signature xmlns:ds="http://www.w3.org/2000/09/xmldsig#">
+    <ds:SignedInfo/>
+    <ds:SignatureValue>AAAA</ds:SignatureValue>
+  </ds:Signature>
+</Document>"#;
+
+#[test]
+fn detects_signatures() {
+    assert!(is_signed(SIGNED.as_bytes()));
+    let default_namespace = SIGNED.replace("ds:", "").replace("xmlns:ds=", "xmlns=");
+    assert!(is_signed(default_namespace.as_bytes()));
+
+    // Only signatures in the XML-DSig namespace count
+    let other_namespace = SIGNED.replace("xmldsig#", "other#");
+    assert!(!is_signed(other_namespace.as_bytes()));
+    assert!(!is_signed(b"<Document><Signature/></Document>"));
+    assert!(!is_signed(b""));
+}
+
+#[test]
+fn rejects_unsigned_input() {
+    let options = VerifyOptions {
+        certificate: Some(PathBuf::from("signer.pem")),
+        ..VerifyOptions::default()
+    };
+    assert!(options.is_enabled());
+    assert!(!VerifyOptions::default().is_enabled());
+
+    let unsigned = b"<Document><Stmt/></Document>";
+    let error = verify(unsigned, &options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Input rejected: no XML signature (ds:Signature)"
+    );
+}
+
+#[test]
+fn envelops_signature_template_after_last_statement() {
+    let template = signature_template("", true);
+    assert!(template.contains("<ds:Reference URI=\"\"><ds:Transforms>"));
+    assert!(template.contains("#enveloped-signature"));
+    assert!(template.contains("<ds:X509Data><ds:X509Certificate/></ds:X509Data>"));
+
+    let document = "<Document>\n    <BkToCstmrStmt>\n        <Stmt>\n            <Id>1</Id>\n        </Stmt>\n        \
+                    <Stmt>\n            <Id>2</Id>\n        </Stmt>\n    </BkToCstmrStmt>\n</Document>\n";
+    let enveloped =
+        String::from_utf8(envelop(document.as_bytes(), "<ds:Signature/>").unwrap()).unwrap();
+    assert!(enveloped.ends_with(
+        "<Id>2</Id>\n        </Stmt>\n        \
+         <SplmtryData><Envlp><ds:Signature/></Envlp></SplmtryData>\n    </BkToCstmrStmt>\n</Document>\n"
+    ));
+    assert!(is_signed(
+        envelop(document.as_bytes(), &template).unwrap().as_slice()
+    ));
+
+    let compact = "<Document><BkToCstmrStmt><Stmt><Id>1</Id></Stmt></BkToCstmrStmt></Document>";
+    let enveloped = envelop(compact.as_bytes(), "<ds:Signature/>").unwrap();
+    assert_eq!(
+        String::from_utf8(enveloped).unwrap(),
+        "<Document><BkToCstmrStmt><Stmt><Id>1</Id></Stmt>\
+         <SplmtryData><Envlp><ds:Signature/></Envlp></SplmtryData></BkToCstmrStmt></Document>"
+    );
+    assert!(envelop(b"<Document/>", "<ds:Signature/>").is_err());
+}
+
+#[test]
+fn references_detached_documents_by_name() {
+    let template = signature_template("statement%2008.xml", false);
+    assert!(template.contains("<ds:Reference URI=\"statement%2008.xml\"><ds:DigestMethod"));
+    assert!(!template.contains("Transforms"));
+    assert!(template.contains("<ds:KeyInfo><ds:KeyValue/></ds:KeyInfo>"));
+    assert_eq!(
+        signature_path(Path::new("out/statement_08.xml")),
+        Path::new("out/statement_08.xml.sig")
+    );
+}
+
+const DOCUMENT: &str = "<Document xmlns=\"urn:iso:std:iso:20022:tech:xsd:camt.053.001.08\">\
+                        <BkToCstmrStmt><Stmt><Id>STMT-1</Id></Stmt></BkToCstmrStmt></Document>";
+
+#[test]
+fn accepts_only_signatures_over_the_whole_document() {
+    let signed = |template: &str| envelop(DOCUMENT.as_bytes(), template).unwrap();
+    let template = signature_template("", true);
+    assert_eq!(check_reference(&signed(&template)), Ok(()));
+
+    for (template, message) in [
+        (
+            template.replace("URI=\"\"", "URI=\"#STMT-1\""),
+            "the signature does not cover the whole document, its reference URI is not empty",
+        ),
+        (
+            template.replace(" URI=\"\"", ""),
+            "the signature does not cover the whole document, its reference URI is not empty",
+        ),
+        (
+            template.replace("#enveloped-signature", "#base64"),
+            "the signature reference has the transform http://www.w3.org/2000/09/xmldsig#base64",
+        ),
+        (
+            template.replace(
+                "<ds:Transform Algorithm=\"http://www.w3.org/2000/09/xmldsig#enveloped-signature\"/>",
+                "",
+            ),
+            "the signature reference has no enveloped signature transform",
+        ),
+        (
+            template.replace(
+                "</ds:SignedInfo>",
+                "<ds:Reference URI=\"\"/></ds:SignedInfo>",
+            ),
+            "the signature has 2 references instead of one",
+        ),
+        (
+            format!("{}{}", template, template),
+            "2 XML signatures instead of one",
+        ),
+    ] {
+        assert_eq!(check_reference(&signed(&template)), Err(message.to_string()));
+    }
+    assert_eq!(
+        check_reference(template.as_bytes()),
+        Err("the signature is not in a document".to_string())
+    );
+}
+
+// Whether a command line tool can be run
+fn installed(tool: &str, arg: &str) -> bool {
+    Command::new(tool).arg(arg).output().is_ok()
+}
+
+// Private key and self-signed certificate, made with openssl
+fn key_pair(dir: &Path, name: &str) -> (PathBuf, PathBuf) {
+    let key = dir.join(format!("{}.key", name));
+    let certificate = dir.join(format!("{}.pem", name));
+    let status = Command::new("openssl")
+        .args([
+            "req", "-x509", "-newkey", "rsa:2048", "-nodes", "-days", "1",
+        ])
+        .arg("-subj")
+        .arg(format!("/CN={}", name))
+        .arg("-keyout")
+        .arg(&key)
+        .arg("-out")
+        .arg(&certificate)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+    (key, certificate)
+}
+
+// Signed documents are verified with xmlsec1; skipped where xmlsec1 or
+// openssl is not installed
+#[test]
+fn verifies_signatures_with_xmlsec1() {
+    if !installed("xmlsec1", "--version") || !installed("openssl", "version") {
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("camtconvert-dsig-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (key, certificate) = key_pair(&dir, "signer");
+    let (attacker_key, _) = key_pair(&dir, "attacker");
+    let path = dir.join("statement.xml");
+    let sign = |key: &Path, certificate: Option<&Path>| {
+        fs::write(&path, DOCUMENT).unwrap();
+        let options = SignOptions {
+            key: key.to_path_buf(),
+            certificate: certificate.map(Path::to_path_buf),
+            mode: SignatureMode::Enveloped,
+        };
+        sign_file(&path, &options).unwrap();
+        fs::read_to_string(&path).unwrap()
+    };
+    let by_certificate = VerifyOptions {
+        certificate: Some(certificate.clone()),
+        trusted: None,
+    };
+    let by_trust = VerifyOptions {
+        certificate: None,
+        trusted: Some(certificate.clone()),
+    };
+
+    let signed = sign(&key, Some(&certificate));
+    verify(signed.as_bytes(), &by_certificate).unwrap();
+    verify(signed.as_bytes(), &by_trust).unwrap();
+
+    let tampered = signed.replace("<Id>STMT-1</Id>", "<Id>STMT-2</Id>");
+    assert!(verify(tampered.as_bytes(), &by_certificate).is_err());
+    assert!(verify(tampered.as_bytes(), &by_trust).is_err());
+
+    // A signature carrying the signer's own public key proves nothing
+    let forged = sign(&attacker_key, None);
+    assert!(forged.contains("<ds:KeyValue>"));
+    assert!(verify(forged.as_bytes(), &by_certificate).is_err());
+    assert!(verify(forged.as_bytes(), &by_trust).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
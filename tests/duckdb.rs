@@ -0,0 +1,49 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::duckdb::entries_sql;
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+use camtconvert::reference::{ReferenceStyle, generate_transaction_reference};
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id><Ccy>CHF</Ccy></Acct>
+      <Ntry>
+        <Amt Ccy="CHF">1234.50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+        <AcctSvcrRef>BANK-REF-1</AcctSvcrRef>
+        <AddtlNtryInf>Payment to O'Brien</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn replaces_the_entries_of_a_statement() {
+    let statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    let sql = entries_sql(&statement, ReferenceStyle::Hash);
+    let reference = generate_transaction_reference(
+        &statement,
+        &statement.transactions[0],
+        ReferenceStyle::Hash,
+    );
+
+    assert!(sql.starts_with("BEGIN TRANSACTION;\nCREATE TABLE IF NOT EXISTS entries ("));
+    assert!(sql.contains(
+        "DELETE FROM entries WHERE iban = 'CH9300762011623852957' AND statement = 'STMT-1';"
+    ));
+    assert!(sql.contains(&format!(
+        "INSERT INTO entries VALUES ('CH9300762011623852957', 'STMT-1', 'BANK-REF-1', '{}', NULL, \
+         -1234.50, 'CHF', 'BOOK', '2025-06-05', NULL, NULL, NULL, NULL, NULL, \
+         'Payment to O''Brien', NULL, NULL, NULL);",
+        reference
+    )));
+    assert!(sql.ends_with("COMMIT;\n"));
+}
@@ -0,0 +1,69 @@
+use camtconvert::exchange::ExchangeReport;
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH4431999123000889012</IBAN></Id><Ccy>CHF</Ccy></Acct>
+      <Ntry>
+        <Amt Ccy="CHF">95.12</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+        <AmtDtls>
+          <InstdAmt>
+            <Amt Ccy="EUR">100.00</Amt>
+            <CcyXchg><SrcCcy>EUR</SrcCcy><TrgtCcy>CHF</TrgtCcy><XchgRate>0.9512</XchgRate></CcyXchg>
+          </InstdAmt>
+        </AmtDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-06</Dt></BookgDt>
+        <NtryDtls><TxDtls>
+          <AmtDtls><CntrValAmt>
+            <Amt Ccy="CHF">47.60</Amt>
+            <CcyXchg><SrcCcy>EUR</SrcCcy><UnitCcy>EUR</UnitCcy><XchgRate>0.952</XchgRate></CcyXchg>
+          </CntrValAmt></AmtDtls>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">5.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-07</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn reports_currency_exchanges_with_volume() {
+    let statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    let mut report = ExchangeReport::new();
+    for transaction in &statement.transactions {
+        report.add(&statement, transaction);
+    }
+    assert_eq!(report.len(), 2);
+
+    let volumes = report.volumes().unwrap();
+    let volume = &volumes[&("EUR".to_string(), "CHF".to_string())];
+    assert_eq!(volume.entries, 2);
+    assert_eq!(volume.original.to_string(), "150.00");
+    assert_eq!(volume.counter.to_string(), "142.72");
+
+    let mut output = Vec::new();
+    report.write_csv(&mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "line,entries,original_amount,original_currency,counter_amount,counter_currency,\
+         rate,unit_currency,credit_debit,booking_date,account,statement\n\
+         entry,1,100.00,EUR,95.12,CHF,0.9512,,CRDT,2025-06-05,CH4431999123000889012,STMT-1\n\
+         entry,1,50.00,EUR,47.60,CHF,0.952,EUR,DBIT,2025-06-06,CH4431999123000889012,STMT-1\n\
+         total,2,150.00,EUR,142.72,CHF,,,,,,\n"
+    );
+}
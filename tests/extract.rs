@@ -0,0 +1,107 @@
+use camtconvert::extract::{entry_value, find_entries};
+use camtconvert::json::Value;
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+use camtconvert::reference::{ReferenceStyle, generate_transaction_reference};
+use camtconvert::writer::{WriteOptions, write_notification};
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id><Ccy>CHF</Ccy></Acct>
+      <Ntry>
+        <Amt Ccy="CHF">1234.50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+        <AcctSvcrRef>BANK-REF-1</AcctSvcrRef>
+        <NtryDtls><TxDtls>
+          <Refs><EndToEndId>ORDER-7</EndToEndId></Refs>
+          <RmtInf><Ustrd>Invoice 77</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">80</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-06</Dt></BookgDt>
+        <AddtlNtryInf>Refund</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn finds_entries_by_any_reference() {
+    let statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    assert_eq!(statement.transactions[0].account_servicer_ref, "BANK-REF-1");
+    let generated = generate_transaction_reference(
+        &statement,
+        &statement.transactions[1],
+        ReferenceStyle::Hash,
+    );
+
+    // Amounts of the entries found
+    let found = |reference: &str| {
+        find_entries(&statement, reference, ReferenceStyle::Hash)
+            .iter()
+            .map(|transaction| transaction.amount.value.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(found("bank-ref-1"), ["1234.50"]);
+    assert_eq!(found(" ORDER-7 "), ["1234.50"]);
+    assert_eq!(found(&generated), ["80"]);
+    assert!(found("ORDER-8").is_empty());
+    assert!(found("").is_empty());
+}
+
+#[test]
+fn writes_an_entry_as_notification() {
+    let statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    let mut output = Vec::new();
+    write_notification(
+        &mut output,
+        &statement,
+        &statement.transactions[0],
+        &WriteOptions::default(),
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(
+        output.contains(r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.054.001.08">"#)
+    );
+    assert!(output.contains("<MsgId>BANK-REF-1</MsgId>"));
+    assert!(output.contains("<IBAN>CH9300762011623852957</IBAN>"));
+    assert!(output.contains("<AcctSvcrRef>BANK-REF-1</AcctSvcrRef>"));
+    assert!(output.contains("<EndToEndId>ORDER-7</EndToEndId>"));
+    assert_eq!(output.matches("<Ntry>").count(), 1);
+    assert!(!output.contains("Refund"));
+}
+
+#[test]
+fn writes_an_entry_as_json() {
+    let statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    let value = entry_value(&statement, &statement.transactions[0], ReferenceStyle::Hash);
+    let text = |key: &str| value.get(key).and_then(Value::as_str);
+
+    assert_eq!(text("iban"), Some("CH9300762011623852957"));
+    assert_eq!(text("statement"), Some("STMT-1"));
+    assert_eq!(text("account_servicer_ref"), Some("BANK-REF-1"));
+    assert_eq!(text("end_to_end_id"), Some("ORDER-7"));
+    assert_eq!(value.get("amount").unwrap().to_string(), "-1234.50");
+    assert_eq!(text("booking_date"), Some("2025-06-05"));
+    assert_eq!(
+        value.get("remittance_info").unwrap().to_string(),
+        r#"["Invoice 77"]"#
+    );
+    assert_eq!(value.get("additional_info").unwrap().to_string(), "null");
+    assert_eq!(value.get("payee").unwrap().to_string(), "null");
+}
@@ -0,0 +1,105 @@
+use camtconvert::gaps::{Delivery, Gap, find_gaps};
+use camtconvert::model::Statement;
+use chrono::NaiveDate;
+
+fn date(text: &str) -> Option<NaiveDate> {
+    Some(text.parse().unwrap())
+}
+
+fn delivery(source: &str, sequence_number: Option<u64>, from: &str, to: &str) -> Delivery {
+    Delivery {
+        source: source.to_string(),
+        iban: "CH93 0076 2011 6238 5295 7".to_string(),
+        sequence_number,
+        from: date(from),
+        to: date(to),
+    }
+}
+
+#[test]
+fn finds_missing_sequence_numbers_and_days() {
+    let deliveries = [
+        delivery("c.xml", Some(5), "2025-06-24", "2025-06-30"),
+        delivery("a.xml", Some(1), "2025-06-01", "2025-06-10"),
+        delivery("b.xml", Some(2), "2025-06-11", "2025-06-20"),
+    ];
+    let gaps = find_gaps(&deliveries);
+
+    assert_eq!(
+        gaps,
+        [
+            Gap::Sequence {
+                iban: "CH9300762011623852957".to_string(),
+                first: 3,
+                last: 4,
+                after: "b.xml".to_string(),
+                before: "c.xml".to_string(),
+            },
+            Gap::Dates {
+                iban: "CH9300762011623852957".to_string(),
+                from: date("2025-06-21").unwrap(),
+                to: date("2025-06-23").unwrap(),
+                after: "b.xml".to_string(),
+                before: "c.xml".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        gaps[0].to_string(),
+        "CH9300762011623852957: sequence numbers 3 to 4 are missing between b.xml and c.xml"
+    );
+    assert_eq!(
+        gaps[1].to_string(),
+        "CH9300762011623852957: no statement for 2025-06-21 to 2025-06-23 between b.xml and c.xml"
+    );
+}
+
+#[test]
+fn reports_duplicates_but_not_restarts_or_overlaps() {
+    let deliveries = [
+        delivery("a.xml", Some(250), "2024-12-01", "2024-12-31"),
+        delivery("b.xml", Some(1), "2025-01-01", "2025-01-31"),
+        delivery("c.xml", Some(1), "2025-01-15", "2025-01-31"),
+        delivery("d.xml", None, "2025-02-01", "2025-02-28"),
+    ];
+    let gaps = find_gaps(&deliveries);
+
+    assert_eq!(gaps.len(), 1);
+    assert_eq!(
+        gaps[0].to_string(),
+        "CH9300762011623852957: sequence number 1 is in both b.xml and c.xml"
+    );
+}
+
+#[test]
+fn checks_accounts_separately() {
+    let mut other = delivery("x.xml", Some(7), "2025-05-01", "2025-05-31");
+    other.iban = "CH5604835012345678009".to_string();
+    let deliveries = [
+        delivery("a.xml", Some(1), "2025-06-01", "2025-06-10"),
+        other,
+        delivery("b.xml", Some(2), "2025-06-11", "2025-06-20"),
+    ];
+    assert_eq!(find_gaps(&deliveries), []);
+}
+
+#[test]
+fn describes_a_statement() {
+    let statement = Statement {
+        iban: "CH9300762011623852957".to_string(),
+        sequence_number: " 17 ".to_string(),
+        from_datetime: Some("2025-06-01T00:00:00+02:00".parse().unwrap()),
+        to_datetime: Some("2025-06-20T23:59:59+02:00".parse().unwrap()),
+        ..Statement::default()
+    };
+    let delivery = Delivery::of("a.xml", &statement);
+    assert_eq!(delivery.sequence_number, Some(17));
+    assert_eq!(delivery.from, date("2025-06-01"));
+    assert_eq!(delivery.to, date("2025-06-20"));
+
+    let statement = Statement {
+        sequence_number: "X".to_string(),
+        ..Statement::default()
+    };
+    assert_eq!(Delivery::of("b.xml", &statement).sequence_number, None);
+}
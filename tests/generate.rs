@@ -0,0 +1,46 @@
+use camtconvert::generate::{GenerateOptions, generate_camt_10};
+use camtconvert::iban::validate_iban;
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+use chrono::NaiveDate;
+
+fn generate(entries: usize, currency: &str, seed: u64) -> Vec<u8> {
+    let options = GenerateOptions {
+        entries,
+        currency: currency.to_string(),
+        date: NaiveDate::from_ymd_opt(2025, 6, 20).unwrap(),
+        seed,
+    };
+    let mut output = Vec::new();
+    generate_camt_10(&mut output, &options).unwrap();
+    output
+}
+
+#[test]
+fn generates_balanced_statements() {
+    for currency in ["CHF", "EUR", "JPY", "KWD"] {
+        let document = generate(200, currency, 7);
+        let parsed = parse_camt_10(&document[..], &ParseOptions::default()).unwrap();
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        let statement = parsed.statement;
+        assert_eq!(statement.currency, currency);
+        assert_eq!(statement.transactions.len(), 200);
+        assert_eq!(validate_iban(&statement.iban), Ok(()));
+        assert_eq!(statement.check_balances(), None);
+
+        let period = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()
+            ..=NaiveDate::from_ymd_opt(2025, 6, 20).unwrap();
+        let dates: Vec<NaiveDate> = statement
+            .transactions
+            .iter()
+            .map(|transaction| transaction.booking_date.unwrap().date())
+            .collect();
+        assert!(dates.iter().all(|date| period.contains(date)));
+        assert!(dates.is_sorted());
+    }
+}
+
+#[test]
+fn repeats_statements_of_the_same_seed() {
+    assert_eq!(generate(20, "CHF", 1), generate(20, "CHF", 1));
+    assert_ne!(generate(20, "CHF", 1), generate(20, "CHF", 2));
+}
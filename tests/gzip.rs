@@ -0,0 +1,132 @@
+use camtconvert::gzip::{GzDecoder, is_gzip};
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+use std::io::{ErrorKind, Read};
+
+// "Ntry Ntry Ntry Ntry Ntry Ntry Ntry Ntry CRDT DBIT CRDT DBIT\n" compressed
+// with fixed Huffman codes
+const FIXED: &[u8] = &[
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xf3, 0x2b, 0x29, 0xaa, 0x54, 0xf0,
+    0x23, 0x4c, 0x38, 0x07, 0xb9, 0x84, 0x28, 0xb8, 0x38, 0x79, 0x86, 0x20, 0x58, 0x5c, 0x00, 0x84,
+    0xdf, 0x18, 0xbc, 0x3c, 0x00, 0x00, 0x00,
+];
+
+// The entries of `entries()` compressed with dynamic Huffman codes
+const DYNAMIC: &[u8] = &[
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xa5, 0x92, 0x3d, 0x0a, 0x02, 0x41,
+    0x0c, 0x85, 0x7b, 0x4f, 0x21, 0x7b, 0x80, 0x4c, 0x32, 0x99, 0x5f, 0x88, 0x03, 0x3a, 0x83, 0xb8,
+    0x8d, 0x85, 0x78, 0x02, 0xdd, 0x76, 0x2d, 0x64, 0x9a, 0xbd, 0xbd, 0x62, 0x63, 0xa1, 0xc5, 0x42,
+    0xca, 0x3c, 0xc8, 0xc7, 0xf7, 0xe0, 0xc9, 0xb9, 0x3f, 0x97, 0x22, 0xfb, 0xb9, 0x6f, 0xeb, 0x7d,
+    0xd9, 0x0d, 0xf5, 0x74, 0x1c, 0x0a, 0x02, 0xa2, 0x98, 0x77, 0x56, 0xa4, 0x4e, 0xbd, 0xdd, 0xfa,
+    0xf8, 0x98, 0x4a, 0x3b, 0x8c, 0x57, 0x31, 0xdf, 0x5b, 0xcc, 0xe7, 0x75, 0x23, 0xff, 0x08, 0x1c,
+    0x81, 0xf8, 0x07, 0x51, 0x2f, 0x6d, 0x3d, 0x22, 0x3a, 0xb0, 0x41, 0x87, 0x20, 0x22, 0xe0, 0xac,
+    0x6b, 0x42, 0x2e, 0x81, 0xb7, 0x4a, 0x8f, 0xe4, 0x21, 0x78, 0x1d, 0xc3, 0x5a, 0x0b, 0x31, 0xe9,
+    0xba, 0x58, 0x9f, 0x21, 0x93, 0xd2, 0x23, 0x07, 0x40, 0xa7, 0x63, 0x30, 0x33, 0x50, 0xd4, 0x2e,
+    0x0c, 0x81, 0x51, 0xe7, 0xe1, 0x30, 0x82, 0x5b, 0x3f, 0xd3, 0x17, 0x7e, 0xe1, 0x7a, 0xf3, 0x2c,
+    0x03, 0x00, 0x00,
+];
+
+const FIXED_TEXT: &str = "Ntry Ntry Ntry Ntry Ntry Ntry Ntry Ntry CRDT DBIT CRDT DBIT\n";
+
+fn entries() -> String {
+    (0..12)
+        .map(|i| {
+            format!(
+                "<Ntry><Amt Ccy=\"CHF\">{}.{:02}</Amt><CdtDbtInd>{}</CdtDbtInd></Ntry>\n",
+                i * 37 % 1000,
+                i * 13 % 100,
+                if i % 3 == 0 { "DBIT" } else { "CRDT" }
+            )
+        })
+        .collect()
+}
+
+fn gunzip(input: &[u8]) -> std::io::Result<String> {
+    let mut output = String::new();
+    GzDecoder::new(input).read_to_string(&mut output)?;
+    Ok(output)
+}
+
+// Gzip stream of uncompressed (stored) deflate blocks
+fn gzip_stored(data: &[u8]) -> Vec<u8> {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    let mut output = vec![0x1f, 0x8b, 0x08, 0, 0, 0, 0, 0, 0, 0xff];
+    let mut blocks = data.chunks(100).peekable();
+    while let Some(block) = blocks.next() {
+        output.push(u8::from(blocks.peek().is_none()));
+        output.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        output.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        output.extend_from_slice(block);
+    }
+    output.extend_from_slice(&(!crc).to_le_bytes());
+    output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    output
+}
+
+#[test]
+fn decompresses_fixed_dynamic_and_stored_blocks() {
+    assert_eq!(gunzip(FIXED).unwrap(), FIXED_TEXT);
+    assert_eq!(gunzip(DYNAMIC).unwrap(), entries());
+    assert_eq!(
+        gunzip(&gzip_stored(entries().as_bytes())).unwrap(),
+        entries()
+    );
+}
+
+#[test]
+fn reads_concatenated_members() {
+    let input = [FIXED, DYNAMIC].concat();
+    assert_eq!(
+        gunzip(&input).unwrap(),
+        format!("{}{}", FIXED_TEXT, entries())
+    );
+}
+
+#[test]
+fn rejects_corrupt_and_truncated_input() {
+    let mut corrupt = DYNAMIC.to_vec();
+    let crc_offset = corrupt.len() - 8;
+    corrupt[crc_offset] ^= 0xff;
+    let error = gunzip(&corrupt).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(error.to_string().contains("checksum"), "{}", error);
+
+    let error = gunzip(&DYNAMIC[..DYNAMIC.len() - 20]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn reader_detects_gzip_input() {
+    let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id></Acct>
+      <Ntry>
+        <Amt Ccy="CHF">10.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+    let input = gzip_stored(document.as_bytes());
+    assert!(is_gzip(&input));
+
+    let parsed = parse_camt_10(input.as_slice(), &ParseOptions::default()).unwrap();
+    assert_eq!(parsed.statement.id, "STMT-1");
+    assert_eq!(parsed.statement.transactions.len(), 1);
+}
@@ -0,0 +1,107 @@
+use camtconvert::hooks::Hooks;
+use camtconvert::model::{Amount, CreditDebit, Statement, Transaction};
+
+fn transaction(code: &str, info: &str) -> Transaction {
+    Transaction {
+        amount: Amount {
+            value: "50.00".parse().unwrap(),
+            currency: "CHF".to_string(),
+        },
+        credit_debit_ind: Some(CreditDebit::Debit),
+        bank_tx_code: code.to_string(),
+        additional_info: info.to_string(),
+        ..Transaction::default()
+    }
+}
+
+#[test]
+fn changes_fields_in_rule_order() {
+    let hooks: Hooks = r#"
+        # Comments and blank lines are skipped
+        transaction: bank_tx_code starts_with "CARD" => bank_tx_code = "CARD"
+        transaction: bank_tx_code == "CARD" => additional_info = trim(replace(additional_info, "Coop Zürich", "Coop")) + " " + amount + " " + currency
+        transaction: credit_debit != "DBIT" => additional_info = "credit"
+        statement: owner_name = upper(owner_name) + " \"" + lower(iban) + "\"" # comment
+        statement: servicer_bic = "UBSWCHZH80A"
+    "#
+    .parse()
+    .unwrap();
+
+    let mut card = transaction("CARD-456", " Card payment at Coop Zürich ");
+    hooks.apply_transaction(&mut card);
+    assert_eq!(card.bank_tx_code, "CARD");
+    assert_eq!(card.additional_info, "Card payment at Coop 50.00 CHF");
+
+    let mut transfer = transaction("TRANSFER-1", "Rent");
+    hooks.apply_transaction(&mut transfer);
+    assert_eq!(transfer.bank_tx_code, "TRANSFER-1");
+    assert_eq!(transfer.additional_info, "Rent");
+
+    let mut statement = Statement {
+        owner_name: "Muster AG".to_string(),
+        iban: "CH93X".to_string(),
+        ..Statement::default()
+    };
+    hooks.apply_statement(&mut statement);
+    assert_eq!(statement.owner_name, "MUSTER AG \"ch93x\"");
+    assert_eq!(statement.servicer_bic, "UBSWCHZH80A");
+}
+
+#[test]
+fn rewrites_remittance_information() {
+    let hooks: Hooks = r#"
+        transaction: remittance contains "INV-" => remittance = replace(remittance, "INV-", "Invoice ")
+        transaction: remittance == "" => remittance = additional_info
+    "#
+    .parse()
+    .unwrap();
+
+    let mut invoiced = transaction("TRANSFER-1", "Payment");
+    invoiced.remittance_info = vec!["INV-1042".to_string(), "INV-1043 partial".to_string()];
+    hooks.apply_transaction(&mut invoiced);
+    assert_eq!(
+        invoiced.remittance_info,
+        ["Invoice 1042", "Invoice 1043 partial"]
+    );
+
+    let mut unexplained = transaction("TRANSFER-2", "Rent May");
+    hooks.apply_transaction(&mut unexplained);
+    assert_eq!(unexplained.remittance_info, ["Rent May"]);
+}
+
+#[test]
+fn rejects_invalid_rules() {
+    for (rules, message) in [
+        (
+            "entry: bank_tx_code = \"X\"",
+            "Line 1 of hooks: expected statement: or transaction: at the start",
+        ),
+        (
+            "transaction: amount = \"1\"",
+            "Line 1 of hooks: amount cannot be changed",
+        ),
+        (
+            "\nstatement: additional_info = \"X\"",
+            "Line 2 of hooks: unknown statement field additional_info",
+        ),
+        (
+            "transaction: bank_tx_code = shout(bank_tx_code)",
+            "Line 1 of hooks: unknown function shout",
+        ),
+        (
+            "transaction: bank_tx_code = \"X",
+            "Line 1 of hooks: unclosed string",
+        ),
+        (
+            "transaction: bank_tx_code is \"X\" => bank_tx_code = \"Y\"",
+            "Line 1 of hooks: expected ==",
+        ),
+        (
+            "transaction: bank_tx_code = \"X\" \"Y\"",
+            "Line 1 of hooks: unexpected \"Y\" after the value",
+        ),
+    ] {
+        let error = rules.parse::<Hooks>().unwrap_err().to_string();
+        assert!(error.starts_with(message), "{}", error);
+    }
+}
@@ -0,0 +1,62 @@
+use camtconvert::iban::{IbanError, iban_from_bban, is_qr_iban, validate_iban};
+
+#[test]
+fn accepts_valid_ibans() {
+    for iban in [
+        "CH9300762011623852957",
+        "DE89370400440532013000",
+        "GB82WEST12345698765432",
+        "NO9386011117947",
+        "LC55HEMM000100010012001200023015",
+    ] {
+        assert_eq!(validate_iban(iban), Ok(()), "{}", iban);
+    }
+}
+
+#[test]
+fn rejects_invalid_ibans() {
+    assert_eq!(
+        validate_iban("CH9300762011623852958"),
+        Err(IbanError::Checksum)
+    );
+    // Swapped digits
+    assert_eq!(
+        validate_iban("CH9300762011623859257"),
+        Err(IbanError::Checksum)
+    );
+    assert_eq!(
+        validate_iban("CH930076201162385295"),
+        Err(IbanError::Length {
+            expected: 21,
+            actual: 20
+        })
+    );
+    for iban in [
+        "",
+        "CH93",
+        "ch9300762011623852957",
+        "CH93 0076 2011 6238 5295 7",
+    ] {
+        assert_eq!(validate_iban(iban), Err(IbanError::Format), "{}", iban);
+    }
+}
+
+#[test]
+fn computes_check_digits() {
+    assert_eq!(
+        iban_from_bban("CH", "00762011623852957"),
+        "CH9300762011623852957"
+    );
+    assert_eq!(
+        iban_from_bban("GB", "WEST12345698765432"),
+        "GB82WEST12345698765432"
+    );
+}
+
+#[test]
+fn recognizes_qr_ibans() {
+    assert!(is_qr_iban("CH4431999123000889012"));
+    assert!(is_qr_iban("CH44 3199 9123 0008 8901 2"));
+    assert!(!is_qr_iban("CH9300762011623852957"));
+    assert!(!is_qr_iban("DE4431999123000889012"));
+}
@@ -0,0 +1,103 @@
+use camtconvert::invoice::{
+    EsrDetails, InvoiceReport, ReferenceKind, find_esr, find_esr_participant, find_reference,
+    is_esr_participant,
+};
+use camtconvert::model::Transaction;
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH4431999123000889012</IBAN></Id></Acct>
+      <Ntry>
+        <Amt Ccy="CHF">120.50</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+        <NtryDtls><TxDtls>
+          <RltdPties><Dbtr><Pty><Nm>Meier, Hans</Nm></Pty></Dbtr></RltdPties>
+          <RmtInf><Strd><CdtrRefInf>
+            <Tp><CdOrPrtry><Prtry>QRR</Prtry></CdOrPrtry></Tp>
+            <Ref>210000000003139471430009017</Ref>
+          </CdtrRefInf></Strd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">80</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-06</Dt></BookgDt>
+        <NtryDtls><TxDtls>
+          <RmtInf><Ustrd>Invoice 17, ref rf18 5390 0754 7034</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">5.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-07</Dt></BookgDt>
+        <AddtlNtryInf>Account fee</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn finds_references_in_text() {
+    let reference = find_reference("Ref. 21 00000 00003 13947 14300 09017, thanks").unwrap();
+    assert_eq!(reference.kind, ReferenceKind::Qrr);
+    assert_eq!(reference.reference, "210000000003139471430009017");
+
+    let reference = find_reference("RF18 5390 0754 7034").unwrap();
+    assert_eq!(reference.kind, ReferenceKind::Scor);
+    assert_eq!(reference.reference, "RF18539007547034");
+
+    // Wrong check digits
+    assert_eq!(find_reference("21 00000 00003 13947 14300 09018"), None);
+    assert_eq!(find_reference("RF19 5390 0754 7034"), None);
+    assert_eq!(find_reference("Invoice 2025-17"), None);
+}
+
+#[test]
+fn reports_entries_with_references() {
+    let parsed = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default()).unwrap();
+    let statement = &parsed.statement;
+    let mut report = InvoiceReport::new();
+    for transaction in &statement.transactions {
+        report.add(statement, transaction);
+    }
+    assert_eq!(report.len(), 2);
+
+    let mut csv = Vec::new();
+    report.write_csv(&mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "reference,type,amount,currency,credit_debit,payer,booking_date,account,statement\n\
+         210000000003139471430009017,QRR,120.50,CHF,CRDT,\"Meier, Hans\",2025-06-05,CH4431999123000889012,STMT-1\n\
+         RF18539007547034,SCOR,80.00,CHF,CRDT,,2025-06-06,CH4431999123000889012,STMT-1\n"
+    );
+}
+
+#[test]
+fn finds_esr_details() {
+    assert!(is_esr_participant("010001628"));
+    assert!(!is_esr_participant("010001627"));
+    assert_eq!(
+        find_esr_participant("ESR 2025-06-05 01-162-8"),
+        Some("010001628".to_string())
+    );
+    assert_eq!(find_esr_participant("Tel. 01-162-7"), None);
+
+    let transaction = Transaction {
+        remittance_info: vec!["ESR 01-162-8".to_string()],
+        additional_info: "Ref 21 00000 00003 13947 14300 09017".to_string(),
+        ..Transaction::default()
+    };
+    assert_eq!(
+        find_esr(&transaction),
+        EsrDetails {
+            reference: Some("210000000003139471430009017".to_string()),
+            participant: Some("010001628".to_string()),
+        }
+    );
+}
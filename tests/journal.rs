@@ -0,0 +1,164 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::journal::{self, HistoryFilter, JournalEntry, parse_month};
+use chrono::DateTime;
+use std::fs;
+
+fn entry(input: &str, account: &str, from: &str, to: &str) -> JournalEntry {
+    JournalEntry {
+        time: DateTime::parse_from_rfc3339("2025-07-01T08:15:00+02:00").unwrap(),
+        input: input.to_string(),
+        input_sha256: Some("5d41402a".to_string()),
+        options_fingerprint: Some("8a1f0c2e".to_string()),
+        status: "ok".to_string(),
+        outputs: vec![input.replace(".xml", "_08.xml")],
+        account: account.to_string(),
+        statement: "STMT-1".to_string(),
+        from: DateTime::parse_from_rfc3339(from).ok(),
+        to: DateTime::parse_from_rfc3339(to).ok(),
+        error: None,
+    }
+}
+
+#[test]
+fn appends_and_reads_entries() {
+    let dir = std::env::temp_dir().join(format!("camtconvert-journal-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("journal.jsonl");
+    let _ = fs::remove_file(&path);
+
+    let june = entry(
+        "june.xml",
+        "CH9300762011623852957",
+        "2025-06-01T00:00:00+02:00",
+        "2025-06-30T23:59:59+02:00",
+    );
+    let failed = JournalEntry {
+        status: "error".to_string(),
+        outputs: Vec::new(),
+        account: String::new(),
+        error: Some("Missing required field Stmt/CreDtTm".to_string()),
+        ..entry("broken.xml", "", "", "")
+    };
+    journal::append(&path, std::slice::from_ref(&june)).unwrap();
+    journal::append(&path, std::slice::from_ref(&failed)).unwrap();
+    fs::write(
+        &path,
+        fs::read_to_string(&path).unwrap() + "not a journal entry\n",
+    )
+    .unwrap();
+
+    let (entries, unreadable) = journal::read(&path).unwrap();
+    assert_eq!(entries, [june, failed]);
+    assert_eq!(unreadable, 1);
+
+    let (entries, unreadable) = journal::read(&dir.join("missing.jsonl")).unwrap();
+    assert!(entries.is_empty());
+    assert_eq!(unreadable, 0);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn filters_by_account_month_and_status() {
+    let june = entry(
+        "june.xml",
+        "CH9300762011623852957",
+        "2025-06-01T00:00:00+02:00",
+        "2025-06-30T23:59:59+02:00",
+    );
+    let filter = HistoryFilter {
+        account: Some("ch93 0076 2011 6238 5295 7".to_string()),
+        month: Some(parse_month("2025-06").unwrap()),
+        status: Some("ok".to_string()),
+    };
+    assert!(filter.matches(&june));
+
+    let other_account = entry(
+        "other.xml",
+        "DE89370400440532013000",
+        "2025-06-01T00:00:00+02:00",
+        "2025-06-30T23:59:59+02:00",
+    );
+    assert!(!filter.matches(&other_account));
+
+    // Periods overlapping the month match, others do not
+    let month = |text| HistoryFilter {
+        month: Some(parse_month(text).unwrap()),
+        ..HistoryFilter::default()
+    };
+    let quarter = entry(
+        "q2.xml",
+        "CH9300762011623852957",
+        "2025-04-01T00:00:00+02:00",
+        "2025-06-30T23:59:59+02:00",
+    );
+    assert!(month("2025-05").matches(&quarter));
+    assert!(!month("2025-07").matches(&quarter));
+    assert!(!month("2025-03").matches(&quarter));
+
+    // Without a period, the day of the conversion counts
+    let undated = entry("undated.xml", "CH9300762011623852957", "", "");
+    assert!(month("2025-07").matches(&undated));
+    assert!(!month("2025-06").matches(&undated));
+
+    let failed = JournalEntry {
+        status: "error".to_string(),
+        ..june
+    };
+    assert!(!filter.matches(&failed));
+
+    assert!(parse_month("2025-13").is_err());
+    assert!(parse_month("June").is_err());
+}
+
+#[test]
+fn finds_earlier_conversions_to_resume() {
+    let dir = std::env::temp_dir().join(format!("camtconvert-resume-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let output = dir.join("june_08.xml");
+    fs::write(&output, "<Document/>").unwrap();
+
+    let converted = JournalEntry {
+        outputs: vec![output.display().to_string()],
+        ..entry("june.xml", "CH9300762011623852957", "", "")
+    };
+    let entries = [converted.clone()];
+    assert_eq!(
+        journal::converted(&entries, "june.xml", "5d41402a", "8a1f0c2e"),
+        Some(&converted)
+    );
+    // Another input, changed input or other options
+    assert_eq!(
+        journal::converted(&entries, "july.xml", "5d41402a", "8a1f0c2e"),
+        None
+    );
+    assert_eq!(
+        journal::converted(&entries, "june.xml", "7c211433", "8a1f0c2e"),
+        None
+    );
+    assert_eq!(
+        journal::converted(&entries, "june.xml", "5d41402a", "0b9e33d1"),
+        None
+    );
+
+    // Only the last conversion counts
+    let failed = JournalEntry {
+        status: "error".to_string(),
+        error: Some("Missing required field Stmt/CreDtTm".to_string()),
+        ..converted.clone()
+    };
+    let entries = [converted.clone(), failed];
+    assert_eq!(
+        journal::converted(&entries, "june.xml", "5d41402a", "8a1f0c2e"),
+        None
+    );
+
+    // Outputs removed since have to be written again
+    fs::remove_file(&output).unwrap();
+    let entries = [converted];
+    assert_eq!(
+        journal::converted(&entries, "june.xml", "5d41402a", "8a1f0c2e"),
+        None
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,65 @@
+use camtconvert::json::Value;
+
+#[test]
+fn serializes_nested_values_with_escaping() {
+    let value = Value::object()
+        .with("name", "ACME \"Co\"\n\\ Zürich\u{1}")
+        .with("count", 2usize)
+        .with(
+            "amount",
+            "-12.50".parse::<camtconvert::decimal::Decimal>().unwrap(),
+        )
+        .with("missing", None::<String>)
+        .with("tags", vec!["a", "b"]);
+    assert_eq!(
+        value.to_string(),
+        r#"{"name":"ACME \"Co\"\n\\ Zürich\u0001","count":2,"amount":-12.50,"missing":null,"tags":["a","b"]}"#
+    );
+}
+
+#[test]
+fn parses_what_it_writes() {
+    let value = Value::object()
+        .with("name", "ACME \"Co\"\n\\ Zürich\u{1}")
+        .with(
+            "amount",
+            "-12.50".parse::<camtconvert::decimal::Decimal>().unwrap(),
+        )
+        .with("missing", None::<String>)
+        .with("ok", true)
+        .with("tags", vec!["a", "b"])
+        .with(
+            "nested",
+            Value::object().with("empty", Vec::<String>::new()),
+        );
+    assert_eq!(value.to_string().parse::<Value>().unwrap(), value);
+
+    let parsed: Value = r#" { "a" : [1, -2.5e3, {"b": "\u00e9\ud83d\ude00\/"}] , "c":false } "#
+        .parse()
+        .unwrap();
+    let a = parsed.get("a").and_then(Value::as_array).unwrap();
+    assert_eq!(a[0], Value::Number("1".to_string()));
+    assert_eq!(a[1], Value::Number("-2.5e3".to_string()));
+    assert_eq!(a[2].get("b").and_then(Value::as_str), Some("é😀/"));
+    assert_eq!(parsed.get("c"), Some(&Value::Bool(false)));
+    assert_eq!(parsed.get("d"), None);
+}
+
+#[test]
+fn rejects_invalid_json() {
+    for text in [
+        "",
+        "{",
+        "{\"a\" 1}",
+        "[1,]",
+        "\"open",
+        "01x",
+        "-",
+        "tru",
+        "{} {}",
+        "\"\\ud83d\"",
+        "\"\u{1}\"",
+    ] {
+        assert!(text.parse::<Value>().is_err(), "{}", text);
+    }
+}
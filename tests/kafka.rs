@@ -0,0 +1,104 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::kafka::{Message, read_message};
+
+#[test]
+fn reads_consumed_messages() {
+    let output =
+        b"4 11 2 17 camt-in\nCH93<Document/>-1 9 0 18 camt-in\n<Stmt/>\n\n-1 -1 1 5 camt-in\n";
+    let mut reader = &output[..];
+    assert_eq!(
+        read_message(&mut reader).unwrap(),
+        Some(Message {
+            topic: "camt-in".to_string(),
+            partition: 2,
+            offset: 17,
+            key: Some(b"CH93".to_vec()),
+            payload: Some(b"<Document/>".to_vec()),
+        })
+    );
+    // Payloads may hold line breaks; messages without key or payload have
+    // none
+    let message = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(message.key, None);
+    assert_eq!(message.payload.as_deref(), Some(&b"<Stmt/>\n\n"[..]));
+    let message = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!((message.partition, message.offset), (1, 5));
+    assert_eq!(message.payload, None);
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+
+    assert!(read_message(&mut &b"% Fatal error\n"[..]).is_err());
+    assert!(read_message(&mut &b"4 100 0 0 camt-in\nCH93<Doc"[..]).is_err());
+}
+
+// Stands in for kcat: consumes one message, fails to publish, and logs its
+// arguments and what it was to publish
+#[cfg(unix)]
+const KCAT: &str = r#"#!/bin/sh
+log="$(dirname "$0")/log"
+printf '%s\n' "$*" >> "$log"
+case " $* " in
+*" -P "*)
+    for path; do :; done
+    ls -l "$path" | cut -c 1-10 >> "$log"
+    cat "$path" >> "$log"
+    echo "% ERROR: broker down" >&2
+    exit 1
+    ;;
+esac
+printf '4 11 0 7 camt-in\nCH93<Document/>'
+"#;
+
+#[cfg(unix)]
+#[test]
+fn consumes_at_most_once_and_publishes_privately() {
+    use camtconvert::kafka::Kafka;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("camtconvert-kcat-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let kcat = dir.join("kcat");
+    fs::write(&kcat, KCAT).unwrap();
+    fs::set_permissions(&kcat, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let mut paths = vec![dir.clone()];
+    paths.extend(std::env::split_paths(&path));
+    // No other test of this file runs programs
+    unsafe { std::env::set_var("PATH", std::env::join_paths(paths).unwrap()) };
+
+    let kafka = Kafka {
+        brokers: "kafka1:9092".to_string(),
+        properties: vec![],
+    };
+    let messages: Vec<Message> = kafka
+        .consume("camt-in", "converters", true)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].offset, 7);
+    let error = kafka
+        .publish("camt-out", Some(b"CH93"), &[], b"<Document/>\n<Next/>")
+        .unwrap_err();
+    assert!(error.to_string().contains("broker down"), "{}", error);
+
+    let log = fs::read_to_string(dir.join("log")).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    // The consumer group commits offsets as messages are read, before their
+    // results are published
+    assert!(lines[0].contains("-C -u"), "{}", lines[0]);
+    assert!(
+        lines[0].contains("-G converters -e camt-in"),
+        "{}",
+        lines[0]
+    );
+    // The payload is published from a file only the user can read, which is
+    // gone afterwards
+    let published = lines[1].split(' ').next_back().unwrap();
+    assert!(lines[1].contains("-P -t camt-out -k CH93"), "{}", lines[1]);
+    assert_eq!(&lines[2..], ["-rw-------", "<Document/>", "<Next/>"]);
+    assert!(!std::path::Path::new(published).exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
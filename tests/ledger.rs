@@ -0,0 +1,78 @@
+use camtconvert::ledger::GlAccounts;
+use camtconvert::model::Transaction;
+
+fn transaction(code: &str, category: &str) -> Transaction {
+    Transaction {
+        bank_tx_code: code.to_string(),
+        category: category.to_string(),
+        ..Transaction::default()
+    }
+}
+
+#[test]
+fn maps_categories_and_codes_to_accounts() {
+    let accounts: GlAccounts = "
+        # Comments and blank lines are skipped
+        category:groceries = 4400
+        code:CARD-* = 6570 # card payments without category
+        code:TRANSFER-123 = 1100
+        * = 2999
+    "
+    .parse()
+    .unwrap();
+    assert_eq!(
+        accounts.account(&transaction("CARD-1", "groceries")),
+        Some("4400")
+    );
+    assert_eq!(accounts.account(&transaction("CARD-1", "")), Some("6570"));
+    assert_eq!(
+        accounts.account(&transaction("TRANSFER-123", "")),
+        Some("1100")
+    );
+    assert_eq!(
+        accounts.account(&transaction("TRANSFER-1234", "")),
+        Some("2999")
+    );
+
+    let mut entry = transaction("CARD-1", "rent");
+    accounts.apply(&mut entry);
+    assert_eq!(entry.gl_account, "6570");
+
+    let without_default: GlAccounts = "category:rent = 6000".parse().unwrap();
+    let mut entry = transaction("CARD-1", "");
+    without_default.apply(&mut entry);
+    assert_eq!(entry.gl_account, "");
+}
+
+#[test]
+fn rejects_invalid_mappings() {
+    for (mappings, message) in [
+        (
+            "category:rent 6000",
+            "Line 1 of GL accounts: expected category:NAME, code:CODE or * and = before the account",
+        ),
+        (
+            "\ncategory:rent =",
+            "Line 2 of GL accounts: invalid account \"\"",
+        ),
+        (
+            "category:rent = 60 00",
+            "Line 1 of GL accounts: invalid account \"60 00\"",
+        ),
+        (
+            "payee:Coop = 4400",
+            "Line 1 of GL accounts: expected category:NAME, code:CODE or * instead of \"payee:Coop\"",
+        ),
+        (
+            "code: = 4400",
+            "Line 1 of GL accounts: expected category:NAME, code:CODE or * instead of \"code:\"",
+        ),
+    ] {
+        assert_eq!(
+            mappings.parse::<GlAccounts>().unwrap_err().to_string(),
+            message,
+            "{}",
+            mappings
+        );
+    }
+}
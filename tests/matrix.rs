@@ -0,0 +1,55 @@
+use camtconvert::convert::convert_versions;
+use camtconvert::matrix::{find_reader, find_writer, pairs};
+use camtconvert::reader::ParseOptions;
+use camtconvert::writer::WriteOptions;
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct>
+        <Id><IBAN>CH9300762011623852957</IBAN></Id>
+      </Acct>
+      <Ntry>
+        <Amt Ccy="CHF">10.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn finds_versions_by_any_name() {
+    for name in ["camt.053.001.10", "053.001.10", "10"] {
+        assert_eq!(find_reader(name).unwrap().version, "camt.053.001.10");
+    }
+    for name in ["camt.053.001.08", "053.001.08", "08"] {
+        assert_eq!(find_writer(name).unwrap().version, "camt.053.001.08");
+    }
+    assert!(find_reader("08").is_none());
+    assert!(find_writer("001.08").is_none());
+}
+
+#[test]
+fn converts_every_pair() {
+    for (from, to) in pairs() {
+        let converted = convert_versions(
+            DOCUMENT.as_bytes(),
+            find_reader(from).unwrap(),
+            find_writer(to).unwrap(),
+            &ParseOptions::default(),
+            &WriteOptions::default(),
+        )
+        .unwrap();
+        let output = String::from_utf8(converted.output).unwrap();
+        assert!(
+            output.contains(&format!("urn:iso:std:iso:20022:tech:xsd:{}", to)),
+            "{} to {}",
+            from,
+            to
+        );
+    }
+}
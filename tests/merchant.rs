@@ -0,0 +1,79 @@
+use camtconvert::merchant::MerchantCleanup;
+use camtconvert::model::{CreditDebit, Statement, Transaction};
+
+#[test]
+fn removes_noise_from_merchant_texts() {
+    let cleanup = MerchantCleanup::default();
+    for (text, cleaned) in [
+        ("COOP-4567 ZUERICH CHE", "COOP ZUERICH"),
+        ("AMZN Mktp CH*2F4", "AMZN Mktp CH"),
+        ("MIGROS M BASEL TID 00123456 XXXX1234", "MIGROS M BASEL"),
+        (
+            "POS 12.03.25 14:32 SBB CFF FFS 8021 Zürich",
+            "SBB CFF FFS Zürich",
+        ),
+        ("VISA PURCHASE - Starbucks ****5678", "Starbucks"),
+        (
+            "Card transaction of 50.00 CHF issued by Coop Zürich",
+            "Coop Zürich",
+        ),
+        ("Muster AG", "Muster AG"),
+        ("7-Eleven", "7-Eleven"),
+    ] {
+        assert_eq!(cleanup.clean(text), cleaned, "{}", text);
+    }
+}
+
+#[test]
+fn adds_patterns_of_a_file() {
+    let cleanup: MerchantCleanup = "# Store numbers of Coop\n(?i) filiale [0-9]+\n"
+        .parse()
+        .unwrap();
+    assert_eq!(cleanup.clean("COOP Filiale 12 Bern"), "COOP Bern");
+    assert_eq!(cleanup.clean("COOP-4567 ZUERICH CHE"), "COOP ZUERICH");
+
+    assert_eq!(
+        "\n[a-".parse::<MerchantCleanup>().unwrap_err().to_string(),
+        "Line 2 of merchant patterns: missing ] in regular expression"
+    );
+}
+
+#[test]
+fn cleans_card_entries_only() {
+    let entry = |code: &str, name: &str, info: &str| Transaction {
+        credit_debit_ind: Some(CreditDebit::Debit),
+        bank_tx_code: code.to_string(),
+        debtor_name: "Müller AG".to_string(),
+        creditor_name: name.to_string(),
+        additional_info: info.to_string(),
+        ..Transaction::default()
+    };
+    let mut statement = Statement {
+        transactions: vec![
+            entry("CARD-456", "COOP-4567 ZUERICH CHE", "Purchase"),
+            entry(
+                "PMNT-CCRD-POSD",
+                "",
+                "Card transaction of 50.00 CHF issued by Coop Zürich",
+            ),
+            entry("TRANSFER-123", "ACME 1234", ""),
+            entry("CARD-456", "", "12.03.25"),
+        ],
+        ..Statement::default()
+    };
+    MerchantCleanup::default().apply(&mut statement);
+    let payees: Vec<&str> = statement
+        .transactions
+        .iter()
+        .map(|transaction| transaction.payee())
+        .collect();
+    assert_eq!(payees, ["COOP ZUERICH", "Coop Zürich", "ACME 1234", ""]);
+
+    // The names of the bank are kept
+    assert_eq!(
+        statement.transactions[0].creditor_name,
+        "COOP-4567 ZUERICH CHE"
+    );
+    assert_eq!(statement.transactions[0].debtor_name, "Müller AG");
+    assert_eq!(statement.transactions[2].payee, "");
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::convert::convert_bytes;
+use camtconvert::error::ConvertError;
+use camtconvert::metrics::Metrics;
+use camtconvert::reader::ParseOptions;
+use camtconvert::writer::WriteOptions;
+use std::time::Duration;
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id></Acct>
+      <Ntry>
+        <Amt Ccy="CHF">10.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">5.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn counts_conversions_in_prometheus_format() {
+    let metrics = Metrics::new();
+    let converted = convert_bytes(
+        DOCUMENT.as_bytes(),
+        &ParseOptions::default(),
+        &WriteOptions::default(),
+    )
+    .unwrap();
+    metrics.record(Ok(&converted), Duration::from_millis(20));
+    metrics.record(
+        Err(&ConvertError::MissingField("Stmt/Id".to_string())),
+        Duration::from_millis(3),
+    );
+    metrics.record(
+        Err(&ConvertError::Io(std::io::Error::other("closed"))),
+        Duration::from_secs(60),
+    );
+    let text = metrics.render();
+
+    assert!(text.contains("# TYPE camtconvert_conversions_total counter\n"));
+    assert!(text.contains("\ncamtconvert_conversions_total 3\n"));
+    assert!(text.contains("\ncamtconvert_conversion_failures_total{reason=\"invalid\"} 1\n"));
+    assert!(text.contains("\ncamtconvert_conversion_failures_total{reason=\"unreadable\"} 1\n"));
+    assert!(text.contains("\ncamtconvert_entries_total 2\n"));
+    assert!(text.contains("# TYPE camtconvert_conversion_duration_seconds histogram\n"));
+    assert!(text.contains("\ncamtconvert_conversion_duration_seconds_bucket{le=\"0.005\"} 1\n"));
+    assert!(text.contains("\ncamtconvert_conversion_duration_seconds_bucket{le=\"0.025\"} 2\n"));
+    assert!(text.contains("\ncamtconvert_conversion_duration_seconds_bucket{le=\"10\"} 2\n"));
+    assert!(text.contains("\ncamtconvert_conversion_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+    assert!(text.contains("\ncamtconvert_conversion_duration_seconds_sum 60.023\n"));
+    assert!(text.ends_with("camtconvert_conversion_duration_seconds_count 3\n"));
+}
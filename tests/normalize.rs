@@ -0,0 +1,76 @@
+use camtconvert::normalize::normalize_camt_10;
+use camtconvert::reader::ParseOptions;
+use camtconvert::writer::Indent;
+
+const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- exported 2025-06-22 -->
+<c:Document xmlns:c="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <c:BkToCstmrStmt>
+    <c:Stmt>
+      <c:CreDtTm>2025-06-22T17:33:43Z</c:CreDtTm>
+      <c:Id>  STMT-1  </c:Id>
+      <c:Acct><c:Id><c:IBAN>CH9300762011623852957</c:IBAN></c:Id></c:Acct>
+      <c:Ntry>
+        <c:CdtDbtInd>CRDT</c:CdtDbtInd>
+        <c:Amt Ccy="CHF">10.00</c:Amt>
+        <c:AddtlNtryInf><![CDATA[Müller & Söhne]]></c:AddtlNtryInf>
+        <c:BookgDt><c:Dt>2025-06-05</c:Dt></c:BookgDt>
+      </c:Ntry>
+    </c:Stmt>
+  </c:BkToCstmrStmt>
+</c:Document>
+"#;
+
+#[test]
+fn writes_document_in_schema_order() {
+    let mut output = Vec::new();
+    let warnings = normalize_camt_10(
+        DOCUMENT.as_bytes(),
+        &mut output,
+        &ParseOptions::default(),
+        Indent::Compact,
+    )
+    .unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <Document xmlns=\"urn:iso:std:iso:20022:tech:xsd:camt.053.001.10\"><BkToCstmrStmt><Stmt>\
+         <Id>STMT-1</Id><CreDtTm>2025-06-22T17:33:43Z</CreDtTm>\
+         <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id></Acct>\
+         <Ntry><Amt Ccy=\"CHF\">10.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>\
+         <BookgDt><Dt>2025-06-05</Dt></BookgDt>\
+         <AddtlNtryInf>Müller &amp; Söhne</AddtlNtryInf></Ntry>\
+         </Stmt></BkToCstmrStmt></Document>"
+    );
+
+    // Normalizing again changes nothing
+    let normalized = output_of(DOCUMENT);
+    assert_eq!(output_of(&normalized), normalized);
+}
+
+#[test]
+fn fails_on_documents_that_do_not_convert() {
+    let input = DOCUMENT.replace("10.00", "ten");
+    let mut output = Vec::new();
+    let error = normalize_camt_10(
+        input.as_bytes(),
+        &mut output,
+        &ParseOptions::default(),
+        Indent::default(),
+    );
+    assert!(error.is_err());
+    assert!(output.is_empty());
+}
+
+fn output_of(document: &str) -> String {
+    let mut output = Vec::new();
+    normalize_camt_10(
+        document.as_bytes(),
+        &mut output,
+        &ParseOptions::default(),
+        Indent::default(),
+    )
+    .unwrap();
+    String::from_utf8(output).unwrap()
+}
@@ -0,0 +1,174 @@
+use camtconvert::ConvertError;
+use camtconvert::decimal::Decimal;
+use camtconvert::generate::{GenerateOptions, generate_camt_10};
+use camtconvert::model::{BalanceType, Statement};
+use camtconvert::pagination::{PageLimits, write_pages};
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+use camtconvert::writer::{Indent, WriteOptions, write_statement};
+use chrono::NaiveDate;
+
+fn statement(entries: usize) -> Statement {
+    let options = GenerateOptions {
+        entries,
+        currency: "CHF".to_string(),
+        date: NaiveDate::from_ymd_opt(2025, 6, 20).unwrap(),
+        seed: 3,
+    };
+    let mut document = Vec::new();
+    generate_camt_10(&mut document, &options).unwrap();
+    parse_camt_10(&document[..], &ParseOptions::default())
+        .unwrap()
+        .statement
+}
+
+fn options() -> WriteOptions {
+    WriteOptions {
+        indent: Indent::Compact,
+        recipient_bic: Some("UBSWCHZH80A".to_string()),
+        ..WriteOptions::default()
+    }
+}
+
+// Text of the first element with a name after a position
+fn text_after<'a>(page: &'a str, after: &str, name: &str) -> &'a str {
+    let rest = &page[page.find(after).unwrap()..];
+    let start = rest.find(&format!("<{}", name)).unwrap();
+    let rest = &rest[start..];
+    let start = rest.find('>').unwrap() + 1;
+    &rest[start..rest.find(&format!("</{}>", name)).unwrap()]
+}
+
+// Signed amount of the first `Amt` and `CdtDbtInd` in a text
+fn signed_amount(text: &str) -> Decimal {
+    let amount: Decimal = text_after(text, "", "Amt").parse().unwrap();
+    match text_after(text, "", "CdtDbtInd") {
+        "DBIT" => -amount,
+        _ => amount,
+    }
+}
+
+#[test]
+fn splits_entries_into_numbered_pages() {
+    let statement = statement(10);
+    let limits = PageLimits {
+        max_entries: Some(4),
+        ..PageLimits::default()
+    };
+    let pages: Vec<String> = write_pages(&statement, &options(), &limits)
+        .unwrap()
+        .into_iter()
+        .map(|page| String::from_utf8(page).unwrap())
+        .collect();
+    assert_eq!(pages.len(), 3);
+    for (index, page) in pages.iter().enumerate() {
+        let number = index + 1;
+        let last = number == pages.len();
+        assert!(page.contains(&format!(
+            "<MsgPgntn><PgNb>{}</PgNb><LastPgInd>{}</LastPgInd></MsgPgntn>",
+            number, last
+        )));
+        assert_eq!(
+            text_after(page, "<GrpHdr>", "MsgId"),
+            format!("{}-P{}", statement.id, number)
+        );
+        assert_eq!(page.matches("<Ntry>").count(), [4, 4, 2][index]);
+        assert_eq!(page.contains("<Cd>OPBD</Cd>"), number == 1);
+        assert_eq!(
+            page.matches("<Cd>ITBD</Cd>").count(),
+            usize::from(number > 1) + usize::from(!last)
+        );
+        assert_eq!(page.contains("<Cd>CLBD</Cd>"), last);
+    }
+
+    // The interim balance of a page is the balance after its entries
+    let opening = statement
+        .balance(BalanceType::OpeningBooked)
+        .unwrap()
+        .signed_amount();
+    let expected = statement.transactions[..8]
+        .iter()
+        .fold(opening, |sum, transaction| {
+            sum.checked_add(transaction.signed_amount()).unwrap()
+        });
+    let closing = &pages[1][pages[1].rfind("<Cd>ITBD</Cd>").unwrap()..];
+    assert_eq!(signed_amount(closing), expected);
+}
+
+#[test]
+fn balances_every_page() {
+    let statement = statement(20);
+    let limits = PageLimits {
+        max_entries: Some(7),
+        ..PageLimits::default()
+    };
+    let pages: Vec<String> = write_pages(&statement, &options(), &limits)
+        .unwrap()
+        .into_iter()
+        .map(|page| String::from_utf8(page).unwrap())
+        .collect();
+    assert_eq!(pages.len(), 3);
+    let mut previous_closing = None;
+    for (index, page) in pages.iter().enumerate() {
+        let balances: Vec<(&str, Decimal)> = page
+            .split("<Bal>")
+            .skip(1)
+            .map(|balance| {
+                let code = balance.split("<Cd>").nth(1).unwrap();
+                (&code[..code.find("</Cd>").unwrap()], signed_amount(balance))
+            })
+            .collect();
+        let last = index == pages.len() - 1;
+        let (opening_type, opening) = balances[0];
+        let (closing_type, closing) = balances[balances.len() - 1];
+        assert_eq!(opening_type, if index == 0 { "OPBD" } else { "ITBD" });
+        assert_eq!(closing_type, if last { "CLBD" } else { "ITBD" });
+        // A page opens where the page before it closed
+        if let Some(previous_closing) = previous_closing {
+            assert_eq!(opening, previous_closing);
+        }
+        let total = page
+            .split("<Ntry>")
+            .skip(1)
+            .map(signed_amount)
+            .fold(opening, |sum, amount| sum.checked_add(amount).unwrap());
+        assert_eq!(total, closing, "page {}", index + 1);
+        previous_closing = Some(closing);
+    }
+}
+
+#[test]
+fn splits_pages_within_size() {
+    let statement = statement(30);
+    let mut whole = Vec::new();
+    write_statement(&mut whole, &statement, &options()).unwrap();
+
+    // Within the limits the output is a single document
+    let limits = PageLimits {
+        max_bytes: Some(whole.len() as u64),
+        ..PageLimits::default()
+    };
+    assert_eq!(
+        write_pages(&statement, &options(), &limits).unwrap(),
+        [whole.clone()]
+    );
+
+    let limits = PageLimits {
+        max_bytes: Some(whole.len() as u64 / 3),
+        ..PageLimits::default()
+    };
+    let pages = write_pages(&statement, &options(), &limits).unwrap();
+    assert!(pages.len() >= 3);
+    assert!(pages.iter().all(|page| page.len() <= whole.len() / 3));
+    let entries: usize = pages
+        .iter()
+        .map(|page| String::from_utf8_lossy(page).matches("<Ntry>").count())
+        .sum();
+    assert_eq!(entries, 30);
+
+    let limits = PageLimits {
+        max_bytes: Some(500),
+        ..PageLimits::default()
+    };
+    let error = write_pages(&statement, &options(), &limits).unwrap_err();
+    assert!(matches!(error, ConvertError::PageLimit(_)), "{}", error);
+}
@@ -0,0 +1,63 @@
+use camtconvert::model::{
+    Amount, Balance, BalanceType, CreditDebit, EntryStatus, Statement, Transaction,
+};
+use camtconvert::pending::{interim_statement, take_pending};
+
+fn transaction(value: &str, status: EntryStatus) -> Transaction {
+    Transaction {
+        amount: Amount {
+            value: value.parse().unwrap(),
+            currency: "CHF".to_string(),
+        },
+        credit_debit_ind: Some(CreditDebit::Credit),
+        status,
+        ..Transaction::default()
+    }
+}
+
+fn balance(balance_type: BalanceType, value: &str) -> Balance {
+    Balance {
+        balance_type: Some(balance_type),
+        amount: Amount {
+            value: value.parse().unwrap(),
+            currency: "CHF".to_string(),
+        },
+        credit_debit_ind: Some(CreditDebit::Credit),
+        ..Balance::default()
+    }
+}
+
+#[test]
+fn moves_pending_entries_to_interim_statement() {
+    let mut statement = Statement {
+        id: "STMT-1".to_string(),
+        balances: vec![
+            balance(BalanceType::OpeningBooked, "100.00"),
+            balance(BalanceType::ClosingBooked, "110.00"),
+        ],
+        transactions: vec![
+            transaction("10.00", EntryStatus::Booked),
+            transaction("25.00", EntryStatus::Pending),
+            transaction("5.00", EntryStatus::Information),
+            transaction("7.50", EntryStatus::Pending),
+        ],
+        ..Statement::default()
+    };
+    let pending = take_pending(&mut statement);
+    let amounts: Vec<String> = pending
+        .iter()
+        .map(|tx| tx.amount.value.to_string())
+        .collect();
+    assert_eq!(amounts, ["25.00", "7.50"]);
+    assert_eq!(statement.transactions.len(), 2);
+
+    let interim = interim_statement(&statement, pending);
+    assert_eq!(interim.id, "STMT-1");
+    assert_eq!(interim.transactions.len(), 2);
+    assert_eq!(interim.balances.len(), 1);
+    assert_eq!(
+        interim.balances[0].balance_type,
+        Some(BalanceType::InterimBooked)
+    );
+    assert_eq!(interim.balances[0].amount.value.to_string(), "110.00");
+}
@@ -0,0 +1,19 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::pgp::is_encrypted;
+
+#[test]
+fn detects_encrypted_input() {
+    // Public key encrypted session key packet, new and old packet format
+    assert!(is_encrypted(&[0xC1, 0x0C, 0x03]));
+    assert!(is_encrypted(&[0x85, 0x01, 0x0C]));
+    // Symmetric key encrypted session key packet
+    assert!(is_encrypted(&[0x8C, 0x0D, 0x04]));
+    assert!(is_encrypted(b"-----BEGIN PGP MESSAGE-----\n\nhQEMA"));
+
+    assert!(!is_encrypted(b"<?xml version=\"1.0\"?>"));
+    assert!(!is_encrypted(b"\xEF\xBB\xBF<?xml version=\"1.0\"?>"));
+    assert!(!is_encrypted(b"-----BEGIN PGP SIGNED MESSAGE-----"));
+    assert!(!is_encrypted(&[0x1F, 0x8B, 0x08]));
+    assert!(!is_encrypted(b""));
+}
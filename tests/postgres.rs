@@ -0,0 +1,109 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::model::Statement;
+use camtconvert::postgres::{load_sql, sequence_number};
+use camtconvert::reader::{ParseOptions, parse_camt_10};
+use camtconvert::reference::{ReferenceStyle, generate_transaction_reference};
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <ElctrncSeqNb>17</ElctrncSeqNb>
+      <CreDtTm>2025-06-22T17:33:43+02:00</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id><Ccy>CHF</Ccy></Acct>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="CHF">20.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Dt><Dt>2025-06-20</Dt></Dt>
+      </Bal>
+      <Ntry>
+        <Amt Ccy="CHF">1234.50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+        <AddtlNtryInf>Payment to O'Brien</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn upserts_a_statement_with_its_balances_and_entries() {
+    let mut statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    statement.transactions[0].category = "rent".to_string();
+    statement.transactions[0].gl_account = "6000".to_string();
+    statement.transactions[0].payee = "O'Brien".to_string();
+    let sql = load_sql(&statement, ReferenceStyle::Hash);
+    let reference = generate_transaction_reference(
+        &statement,
+        &statement.transactions[0],
+        ReferenceStyle::Hash,
+    );
+
+    assert!(sql.starts_with("BEGIN;\nCREATE TABLE IF NOT EXISTS camt_statements ("));
+    assert!(sql.contains(
+        "VALUES ('CH9300762011623852957', '17', 'STMT-1', 'CHF', NULL, NULL, \
+         '2025-06-22T17:33:43+02:00', NULL, NULL) ON CONFLICT (account, sequence_number) DO UPDATE"
+    ));
+    assert!(sql.contains(
+        "DELETE FROM camt_balances WHERE account = 'CH9300762011623852957' AND sequence_number = '17';\n\
+         INSERT INTO camt_balances VALUES \
+         ('CH9300762011623852957', '17', 'CLBD', '2025-06-20', -20.00, 'CHF');"
+    ));
+    assert!(sql.contains(&format!(
+        "INSERT INTO camt_entries VALUES ('CH9300762011623852957', '17', 1, '{}', NULL, NULL, \
+         -1234.50, 'CHF', 'BOOK', '2025-06-05', NULL, NULL, NULL, NULL, NULL, \
+         'Payment to O''Brien', 'rent', '6000', 'O''Brien') ON CONFLICT (account, sequence_number, position) DO UPDATE",
+        reference
+    )));
+    assert!(sql.contains("gl_account = EXCLUDED.gl_account, payee = EXCLUDED.payee;"));
+    assert!(sql.contains(
+        "DELETE FROM camt_entries WHERE account = 'CH9300762011623852957' \
+         AND sequence_number = '17' AND position > 1;\nCOMMIT;"
+    ));
+    assert!(!sql.contains("ALTER TABLE"));
+    assert!(sql.ends_with("COMMIT;\n"));
+}
+
+#[test]
+fn keeps_entries_with_the_same_generated_reference_apart() {
+    let mut statement = parse_camt_10(DOCUMENT.as_bytes(), &ParseOptions::default())
+        .unwrap()
+        .statement;
+    let mut other = statement.transactions[0].clone();
+    other.account_servicer_ref = "BANK-REF-2".to_string();
+    other.debtor_name = "Bob".to_string();
+    statement.transactions.push(other);
+    let [first, second] = &statement.transactions[..] else {
+        unreachable!()
+    };
+    let reference = generate_transaction_reference(&statement, first, ReferenceStyle::Hash);
+    assert_eq!(
+        generate_transaction_reference(&statement, second, ReferenceStyle::Hash),
+        reference
+    );
+
+    let sql = load_sql(&statement, ReferenceStyle::Hash);
+    for position in [1, 2] {
+        assert!(sql.contains(&format!(
+            "INSERT INTO camt_entries VALUES ('CH9300762011623852957', '17', {}, '{}', ",
+            position, reference
+        )));
+    }
+    assert!(sql.contains("AND position > 2;"));
+}
+
+#[test]
+fn identifies_statements_without_sequence_number_by_id() {
+    let statement = Statement {
+        id: "STMT-2".to_string(),
+        sequence_number: " ".to_string(),
+        ..Statement::default()
+    };
+    assert_eq!(sequence_number(&statement), "STMT-2");
+}
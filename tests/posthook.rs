@@ -0,0 +1,48 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::posthook::PostHook;
+
+fn values() -> Vec<(&'static str, String)> {
+    vec![
+        ("output", "out/my statement_08.xml".to_string()),
+        ("iban", "CH9300762011623852957".to_string()),
+        ("from_date", "2025-06-01".to_string()),
+        ("to_date", "2025-06-30".to_string()),
+    ]
+}
+
+#[test]
+fn replaces_placeholders_within_words() {
+    let hook: PostHook =
+        r#"import --account={iban} --period "{from_date} {to_date}" '{output}' {id}"#
+            .parse()
+            .unwrap();
+    assert_eq!(
+        hook.arguments(&values()),
+        [
+            "import",
+            "--account=CH9300762011623852957",
+            "--period",
+            "2025-06-01 2025-06-30",
+            "out/my statement_08.xml",
+            "",
+        ]
+    );
+
+    // Quotes and backslashes
+    let hook: PostHook = r#"C:\tools\upload.exe "say \"hi\"" 'a"b'c"#.parse().unwrap();
+    assert_eq!(
+        hook.arguments(&[]),
+        [r"C:\tools\upload.exe", r#"say "hi""#, r#"a"bc"#]
+    );
+}
+
+#[test]
+fn rejects_invalid_commands() {
+    assert!("".parse::<PostHook>().is_err());
+    assert!("   ".parse::<PostHook>().is_err());
+    assert!("upload {account}".parse::<PostHook>().is_err());
+    assert!("upload {output".parse::<PostHook>().is_err());
+    assert!("upload 'out".parse::<PostHook>().is_err());
+    assert!(r#"upload "out"#.parse::<PostHook>().is_err());
+}
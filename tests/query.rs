@@ -0,0 +1,52 @@
+use camtconvert::query::apply_query;
+use camtconvert::reader::ParseOptions;
+use camtconvert::reference::ReferenceStyle;
+use camtconvert::writer::{DateFormat, Indent, MessageId, OutputVersion, WriteOptions};
+
+#[test]
+fn overrides_options_with_query_parameters() {
+    let mut parse = ParseOptions::default();
+    let mut write = WriteOptions::default();
+    apply_query(
+        "version=053.001.08&date-format=date-time&fidelity&reference=uuid5&strict=true&indent=2&servicer-bic=UBSWCHZH80A&recipient-bic=POFICHBEXXX&timezone=%2B01%3A00",
+        &mut parse,
+        &mut write,
+    )
+    .unwrap();
+    assert_eq!(write.version, OutputVersion::V08);
+    assert_eq!(write.reference, ReferenceStyle::Uuid5);
+    // An explicit date format wins over the one implied by fidelity
+    assert_eq!(write.date_format, DateFormat::DateTime);
+    assert!(write.fidelity);
+    assert!(parse.strict && write.strict);
+    assert_eq!(write.indent, Indent::Spaces(2));
+    assert_eq!(write.servicer_bic.as_deref(), Some("UBSWCHZH80A"));
+    assert_eq!(write.recipient_bic.as_deref(), Some("POFICHBEXXX"));
+    assert_eq!(parse.timezone.name(), "+01:00");
+
+    apply_query("version=04", &mut parse, &mut write).unwrap();
+    assert_eq!(write.version, OutputVersion::V04);
+
+    apply_query("esr-references", &mut parse, &mut write).unwrap();
+    assert!(write.esr_references);
+
+    apply_query("msg-id=uuid", &mut parse, &mut write).unwrap();
+    assert_eq!(write.message_id, MessageId::Uuid);
+
+    for query in [
+        "version=07",
+        "reference=sha1",
+        "strict=yes",
+        "servicer-bic=NOTABIC",
+        "recipient-bic=X",
+        "color=red",
+        "indent=%G0",
+        "msg-id=random",
+    ] {
+        assert!(
+            apply_query(query, &mut parse, &mut write).is_err(),
+            "{}",
+            query
+        );
+    }
+}
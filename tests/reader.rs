@@ -0,0 +1,494 @@
+use camtconvert::ConvertError;
+use camtconvert::model::XmlNode;
+use camtconvert::reader::{Limits, ParseOptions, Parsed, SupplementaryData, parse_camt_10};
+
+const DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct>
+        <Id><IBAN>CH9300762011623852957</IBAN></Id>
+        <Ownr><Nm>Müller AG</Nm></Ownr>
+      </Acct>
+      <Ntry>
+        <Amt Ccy="CHF">10.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+fn parse(input: &[u8]) -> Result<Parsed, ConvertError> {
+    parse_camt_10(input, &ParseOptions::default())
+}
+
+fn assert_parsed(input: &[u8]) {
+    let parsed = parse(input).expect("document should parse");
+    assert_eq!(parsed.statement.id, "STMT-1");
+    assert_eq!(parsed.statement.owner_name, "Müller AG");
+    assert_eq!(parsed.statement.transactions.len(), 1);
+}
+
+#[test]
+fn parses_plain_document() {
+    assert_parsed(format!("{}\n{}", DECLARATION, DOCUMENT).as_bytes());
+}
+
+#[test]
+fn ignores_utf8_bom() {
+    let input = format!("\u{feff}{}\n{}", DECLARATION, DOCUMENT);
+    assert_parsed(input.as_bytes());
+}
+
+#[test]
+fn accepts_missing_declaration() {
+    assert_parsed(DOCUMENT.as_bytes());
+    assert_parsed(format!("\u{feff}{}", DOCUMENT).as_bytes());
+}
+
+#[test]
+fn accepts_leading_whitespace() {
+    assert_parsed(format!("\r\n  {}\n{}", DECLARATION, DOCUMENT).as_bytes());
+    assert_parsed(format!("\u{feff}\r\n{}\n{}", DECLARATION, DOCUMENT).as_bytes());
+}
+
+#[test]
+fn reads_declared_encoding_after_leading_whitespace() {
+    let declaration = r#"<?xml version="1.0" encoding="ISO-8859-1"?>"#;
+    let input: Vec<u8> = format!("\n{}\n{}", declaration, DOCUMENT)
+        .chars()
+        .map(|c| u8::try_from(u32::from(c)).expect("Latin-1 character"))
+        .collect();
+    assert_parsed(&input);
+}
+
+#[test]
+fn rejects_utf16_bom() {
+    let error = parse(&[0xFF, 0xFE, b'<', 0]).unwrap_err();
+    assert!(matches!(error, ConvertError::UnsupportedEncoding(_)));
+}
+
+// Rewrite the document to use a `camt:` prefix instead of a default namespace
+fn prefixed(document: &str) -> String {
+    document
+        .replace("<Document xmlns=", "<Document xmlns:camt=")
+        .replace("</", "</camt:")
+        .replace('<', "<camt:")
+        .replace("<camt:/camt:", "</camt:")
+}
+
+#[test]
+fn parses_prefixed_elements() {
+    let input = prefixed(DOCUMENT);
+    assert!(input.contains("<camt:Stmt>"));
+    assert_parsed(input.as_bytes());
+}
+
+#[test]
+fn ignores_elements_from_other_namespaces() {
+    let input = DOCUMENT.replace(
+        "<Id>STMT-1</Id>",
+        r#"<Id>STMT-1</Id><Id xmlns="urn:example:other">OTHER</Id>"#,
+    );
+    assert_parsed(input.as_bytes());
+}
+
+#[test]
+fn checks_version_of_prefixed_document() {
+    let input = prefixed(DOCUMENT).replace("camt.053.001.10", "camt.053.001.04");
+    let error = parse(input.as_bytes()).unwrap_err();
+    assert!(matches!(error.kind(), ConvertError::UnsupportedVersion(_)));
+}
+
+#[test]
+fn keeps_unknown_elements() {
+    let input = DOCUMENT
+        .replace(
+            "<CdtDbtInd>CRDT</CdtDbtInd>",
+            "<CdtDbtInd>CRDT</CdtDbtInd><RvslInd>false</RvslInd>",
+        )
+        .replace(
+            "</Stmt>",
+            r#"</Stmt><SplmtryData><Envlp><b:Info xmlns:b="urn:example:bank" b:v="1"/></Envlp></SplmtryData>"#,
+        );
+
+    let parsed = parse(input.as_bytes()).unwrap();
+    assert!(parsed.statement.message_extra_elements.is_empty());
+    assert!(parsed.statement.transactions[0].extra_elements.is_empty());
+
+    let options = ParseOptions {
+        keep_unknown: true,
+        ..ParseOptions::default()
+    };
+    let statement = parse_camt_10(input.as_bytes(), &options).unwrap().statement;
+    let entry_extras = &statement.transactions[0].extra_elements;
+    assert_eq!(entry_extras.len(), 1);
+    assert_eq!(entry_extras[0].name, "RvslInd");
+
+    let supplementary = &statement.message_extra_elements[0];
+    assert_eq!(supplementary.name, "SplmtryData");
+    let XmlNode::Element(envelope) = &supplementary.children[0] else {
+        panic!("expected Envlp element");
+    };
+    let XmlNode::Element(info) = &envelope.children[0] else {
+        panic!("expected Info element");
+    };
+    assert_eq!(info.name, "Info");
+    assert_eq!(info.namespace.as_deref(), Some("urn:example:bank"));
+    assert_eq!(
+        info.attributes,
+        vec![("{urn:example:bank}v".to_string(), "1".to_string())]
+    );
+}
+
+#[test]
+fn counts_dropped_elements() {
+    let input = DOCUMENT.replace(
+        "<CdtDbtInd>CRDT</CdtDbtInd>",
+        r#"<CdtDbtInd>CRDT</CdtDbtInd><RvslInd>false</RvslInd><BkTxCd><Domn><Cd>PMNT</Cd></Domn></BkTxCd>"#,
+    );
+    let parsed = parse(input.as_bytes()).unwrap();
+    let dropped: Vec<(&str, usize)> = parsed
+        .dropped
+        .iter()
+        .map(|(path, count)| (path.as_str(), *count))
+        .collect();
+    assert_eq!(
+        dropped,
+        vec![
+            ("Document/BkToCstmrStmt/Stmt/Ntry/BkTxCd/Domn/Cd", 1),
+            ("Document/BkToCstmrStmt/Stmt/Ntry/RvslInd", 1),
+        ]
+    );
+
+    // Elements kept for the output are not dropped
+    let options = ParseOptions {
+        keep_unknown: true,
+        ..ParseOptions::default()
+    };
+    let parsed = parse_camt_10(input.as_bytes(), &options).unwrap();
+    assert_eq!(parsed.dropped.len(), 1);
+}
+
+#[test]
+fn reads_first_end_to_end_id() {
+    let input = DOCUMENT.replace(
+        "</BookgDt>",
+        "</BookgDt><NtryDtls><TxDtls><Refs><EndToEndId>E2E-1</EndToEndId></Refs></TxDtls>\
+         <TxDtls><Refs><EndToEndId>E2E-2</EndToEndId></Refs></TxDtls></NtryDtls>",
+    );
+    let parsed = parse(input.as_bytes()).unwrap();
+    assert_eq!(parsed.statement.transactions[0].end_to_end_id, "E2E-1");
+}
+
+#[test]
+fn reads_debtor_and_creditor_as_counterparty() {
+    let input = DOCUMENT
+        .replace("<CdtDbtInd>CRDT</CdtDbtInd>", "<CdtDbtInd>DBIT</CdtDbtInd>")
+        .replace(
+            "</BookgDt>",
+            "</BookgDt><NtryDtls><TxDtls><RltdPties><Dbtr><Pty><Nm>Müller AG</Nm></Pty></Dbtr>\
+         <Cdtr><Pty><Nm>Migros Zürich</Nm></Pty></Cdtr></RltdPties></TxDtls></NtryDtls>",
+        );
+    let parsed = parse(input.as_bytes()).unwrap();
+    let transaction = &parsed.statement.transactions[0];
+    assert_eq!(transaction.debtor_name, "Müller AG");
+    assert_eq!(transaction.creditor_name, "Migros Zürich");
+    assert_eq!(transaction.counterparty(), "Migros Zürich");
+
+    let input = DOCUMENT.replace(
+        "</BookgDt>",
+        "</BookgDt><NtryDtls><TxDtls><RltdPties><Dbtr><Pty><Nm>Hans Meier</Nm></Pty></Dbtr>\
+         <Cdtr><Pty><Nm>Müller AG</Nm></Pty></Cdtr></RltdPties></TxDtls></NtryDtls>",
+    );
+    let parsed = parse(input.as_bytes()).unwrap();
+    assert_eq!(
+        parsed.statement.transactions[0].counterparty(),
+        "Hans Meier"
+    );
+}
+
+#[test]
+fn reads_amount_details() {
+    let balance = |code: &str, amount: &str| {
+        format!(
+            r#"<Bal><Tp><CdOrPrtry><Cd>{}</Cd></CdOrPrtry></Tp><Amt Ccy="CHF">{}</Amt><CdtDbtInd>CRDT</CdtDbtInd><Dt><Dt>2025-06-05</Dt></Dt></Bal>"#,
+            code, amount
+        )
+    };
+    let input = DOCUMENT
+        .replace(
+            "<Ntry>",
+            &format!("{}{}<Ntry>", balance("OPBD", "100.00"), balance("CLBD", "109.51")),
+        )
+        .replace(r#"<Amt Ccy="CHF">10.00</Amt>"#, r#"<Amt Ccy="EUR">10.00</Amt>"#)
+        .replace(
+            "</BookgDt>",
+            r#"</BookgDt><AmtDtls><InstdAmt><Amt Ccy="EUR">10.00</Amt><CcyXchg><SrcCcy>EUR</SrcCcy><TrgtCcy>CHF</TrgtCcy><UnitCcy>EUR</UnitCcy><XchgRate>0.9512</XchgRate></CcyXchg></InstdAmt><CntrValAmt><Amt Ccy="CHF">9.51</Amt></CntrValAmt></AmtDtls><NtryDtls><TxDtls><AmtDtls><InstdAmt><Amt Ccy="USD">11.20</Amt></InstdAmt><TxAmt><Amt Ccy="EUR">10.00</Amt></TxAmt></AmtDtls></TxDtls></NtryDtls>"#,
+        );
+    let parsed = parse(input.as_bytes()).unwrap();
+    assert!(parsed.dropped.is_empty(), "{:?}", parsed.dropped);
+    let statement = &parsed.statement;
+    let details = &statement.transactions[0].amount_details;
+
+    // Amounts of the entry are kept over those of its transaction details
+    let instructed = details.instructed.as_ref().unwrap();
+    assert_eq!(instructed.amount.currency, "EUR");
+    let exchange = instructed.exchange.as_ref().unwrap();
+    assert_eq!(
+        (
+            exchange.source_currency.as_str(),
+            exchange.target_currency.as_str(),
+            exchange.unit_currency.as_str(),
+            exchange.rate.to_string().as_str()
+        ),
+        ("EUR", "CHF", "EUR", "0.9512")
+    );
+    let transaction = &details.transaction.as_ref().unwrap().amount;
+    assert_eq!(transaction.value.to_string(), "10.00");
+    let counter_value = details.in_currency("CHF").unwrap();
+    assert_eq!(counter_value.value.to_string(), "9.51");
+
+    // The entry counts at its counter value in the account currency
+    assert_eq!(statement.account_currency(), "CHF");
+    assert_eq!(statement.check_balances(), None);
+}
+
+#[test]
+fn reads_value_date() {
+    let parsed = parse(DOCUMENT.as_bytes()).unwrap();
+    assert_eq!(parsed.statement.transactions[0].value_date, None);
+
+    let input = DOCUMENT.replace(
+        "</BookgDt>",
+        "</BookgDt><ValDt><DtTm>2025-06-03T00:00:00+02:00</DtTm></ValDt>",
+    );
+    let parsed = parse(input.as_bytes()).unwrap();
+    let value_date = parsed.statement.transactions[0].value_date.unwrap();
+    assert_eq!(value_date.to_string(), "2025-06-03T00:00:00+02:00");
+}
+
+#[test]
+fn applies_supplementary_data_policy() {
+    let input = DOCUMENT
+        .replace(
+            "</BookgDt>",
+            "</BookgDt><NtryDtls><TxDtls>\
+             <SplmtryData><Envlp><Card>4711</Card></Envlp></SplmtryData>\
+             </TxDtls></NtryDtls>",
+        )
+        .replace(
+            "</Stmt>",
+            "</Stmt><SplmtryData><Envlp><Batch>7</Batch></Envlp></SplmtryData>",
+        );
+    let parse_with = |keep_unknown, policy| {
+        let options = ParseOptions {
+            keep_unknown,
+            supplementary_data: Some(policy),
+            ..ParseOptions::default()
+        };
+        parse_camt_10(input.as_bytes(), &options)
+    };
+
+    let parsed = parse_with(false, SupplementaryData::Keep).unwrap();
+    assert_eq!(parsed.statement.message_extra_elements[0].name, "SplmtryData");
+    let transaction = &parsed.statement.transactions[0];
+    assert_eq!(transaction.supplementary_data[0].name, "SplmtryData");
+    assert!(parsed.dropped.is_empty());
+
+    let parsed = parse_with(true, SupplementaryData::Drop).unwrap();
+    assert!(parsed.statement.message_extra_elements.is_empty());
+    assert!(parsed.statement.transactions[0].supplementary_data.is_empty());
+    assert_eq!(parsed.dropped.len(), 2);
+
+    let error = parse_with(true, SupplementaryData::Error).unwrap_err();
+    assert!(matches!(error.kind(), ConvertError::Rejected(_)));
+    let message = error.kind().to_string();
+    assert_eq!(
+        message,
+        "Input rejected: supplementary data (SplmtryData) is not accepted"
+    );
+}
+
+#[test]
+fn reads_cheque_number_and_related_dates() {
+    let input = DOCUMENT.replace(
+        "</BookgDt>",
+        "</BookgDt><NtryDtls><TxDtls>\
+         <Refs><ChqNb>000123</ChqNb></Refs>\
+         <RltdDts><AccptncDtTm>2025-06-04T09:30:00</AccptncDtTm>\
+         <IntrBkSttlmDt>2025-06-05</IntrBkSttlmDt></RltdDts>\
+         </TxDtls></NtryDtls>",
+    );
+    let parsed = parse(input.as_bytes()).unwrap();
+    let transaction = &parsed.statement.transactions[0];
+    assert_eq!(transaction.cheque_number, "000123");
+    let dates = transaction.related_dates;
+    let acceptance = dates.acceptance.unwrap().to_rfc3339();
+    assert_eq!(acceptance, "2025-06-04T09:30:00+02:00");
+    let settlement = dates.interbank_settlement.unwrap().to_string();
+    assert_eq!(settlement, "2025-06-05");
+    assert!(parsed.dropped.is_empty());
+}
+
+#[test]
+fn reads_additional_transaction_info() {
+    let input = DOCUMENT.replace(
+        "</BookgDt>",
+        "</BookgDt><NtryDtls>\
+         <TxDtls><AddtlTxInf>Gutschrift Lohn</AddtlTxInf></TxDtls>\
+         <TxDtls><AddtlTxInf>Bonus</AddtlTxInf></TxDtls>\
+         </NtryDtls>",
+    );
+    let parsed = parse(input.as_bytes()).unwrap();
+    let transaction = &parsed.statement.transactions[0];
+    assert_eq!(transaction.transaction_info, "Gutschrift Lohn");
+    assert!(parsed.dropped.is_empty());
+}
+
+#[test]
+fn unescapes_text_and_cdata() {
+    let input = DOCUMENT.replace(
+        "</BookgDt>",
+        "</BookgDt><AddtlNtryInf>ACME &amp; Co &lt;AG&gt; <![CDATA[Müller & <Söhne>]]> &#228;</AddtlNtryInf>",
+    );
+    let parsed = parse(input.as_bytes()).unwrap();
+    assert_eq!(
+        parsed.statement.transactions[0].additional_info,
+        "ACME & Co <AG> Müller & <Söhne> ä"
+    );
+}
+
+#[test]
+fn removes_characters_not_allowed_in_xml() {
+    let input = DOCUMENT.replace(
+        "</BookgDt>",
+        "</BookgDt><AddtlNtryInf>Rent&#x1B;\u{1}June\tand\r\nJuly&#1;</AddtlNtryInf>",
+    );
+    let parsed = parse(input.as_bytes()).unwrap();
+    assert_eq!(
+        parsed.statement.transactions[0].additional_info,
+        "RentJune\tand\r\nJuly"
+    );
+    assert_eq!(parsed.warnings.len(), 1);
+    assert_eq!(
+        parsed.warnings[0].to_string(),
+        "Removed characters not allowed in XML: U+001B, U+0001 at line 13, column 61"
+    );
+
+    // Other control characters are replaced when asked
+    let options = ParseOptions {
+        strip_control_chars: true,
+        ..ParseOptions::default()
+    };
+    let parsed = parse_camt_10(input.as_bytes(), &options).unwrap();
+    assert_eq!(
+        parsed.statement.transactions[0].additional_info,
+        "RentJune and  July"
+    );
+    assert_eq!(
+        parsed.warnings[1].message,
+        "Replaced control characters by spaces: U+0009, U+000D, U+000A"
+    );
+
+    let options = ParseOptions {
+        strict: true,
+        ..ParseOptions::default()
+    };
+    let error = parse_camt_10(input.as_bytes(), &options).unwrap_err();
+    assert!(matches!(error.kind(), ConvertError::FailedCheck { .. }), "{}", error);
+}
+
+#[test]
+fn warns_about_amounts_beyond_minor_units() {
+    let parsed = parse(DOCUMENT.as_bytes()).unwrap();
+    assert!(parsed.warnings.is_empty());
+
+    let input = DOCUMENT.replace(">10.00<", ">10.005<");
+    let parsed = parse(input.as_bytes()).unwrap();
+    assert_eq!(parsed.warnings.len(), 1);
+    assert_eq!(
+        parsed.warnings[0].to_string(),
+        "Amount 10.005 CHF has more than the 2 decimal places of its currency at line 11, column 24"
+    );
+}
+
+#[test]
+fn checks_iban() {
+    let input = DOCUMENT.replace("CH9300762011623852957", "CH9300762011623852958");
+    let parsed = parse(input.as_bytes()).unwrap();
+    assert_eq!(
+        parsed.warnings[0].message,
+        "Invalid IBAN CH9300762011623852958: check digits do not match"
+    );
+
+    let options = ParseOptions {
+        strict: true,
+        ..ParseOptions::default()
+    };
+    let error = parse_camt_10(input.as_bytes(), &options).unwrap_err();
+    assert!(matches!(error.kind(), ConvertError::FailedCheck { .. }));
+}
+
+#[test]
+fn rejects_doctype_declarations() {
+    let input = DOCUMENT.replacen(
+        "<Document",
+        "<!DOCTYPE lolz [<!ENTITY lol \"lol\"><!ENTITY lol2 \"&lol;&lol;\">]>\n<Document",
+        1,
+    );
+    let error = parse(input.as_bytes()).unwrap_err();
+    assert!(matches!(error.kind(), ConvertError::Rejected(_)));
+    assert_eq!(error.location().unwrap().line, 1);
+}
+
+#[test]
+fn rejects_documents_beyond_limits() {
+    let parse_with = |limits: Limits| {
+        let options = ParseOptions {
+            limits,
+            lenient: true,
+            ..ParseOptions::default()
+        };
+        parse_camt_10(DOCUMENT.as_bytes(), &options)
+    };
+    // The document has 15 elements nested 6 deep, with texts of up to 21 bytes
+    let fitting = Limits {
+        max_depth: 6,
+        max_elements: 15,
+        max_text_length: 21,
+    };
+    assert!(parse_with(fitting).is_ok());
+    for limits in [
+        Limits {
+            max_depth: 5,
+            ..fitting
+        },
+        Limits {
+            max_elements: 14,
+            ..fitting
+        },
+        Limits {
+            max_text_length: 20,
+            ..fitting
+        },
+    ] {
+        let error = parse_with(limits).unwrap_err();
+        assert!(matches!(error.kind(), ConvertError::Rejected(_)), "{}", error);
+    }
+}
+
+#[test]
+fn rejects_amounts_beyond_total_digits() {
+    let input = DOCUMENT.replace("10.00</Amt>", "1234567890123456.78</Amt>");
+    assert_parsed(input.as_bytes());
+
+    let input = DOCUMENT.replace("10.00</Amt>", "99999999999999999999999999999999999999</Amt>");
+    let error = parse(input.as_bytes()).unwrap_err();
+    assert!(matches!(error.kind(), ConvertError::InvalidAmount { .. }));
+}
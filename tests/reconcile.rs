@@ -0,0 +1,83 @@
+use camtconvert::model::{Amount, CreditDebit, CreditorReference, Statement, Transaction};
+use camtconvert::reconcile::{Reconciliation, Status, read_expected};
+
+fn transaction(value: &str, side: CreditDebit) -> Transaction {
+    Transaction {
+        amount: Amount {
+            value: value.parse().unwrap(),
+            currency: "CHF".to_string(),
+        },
+        credit_debit_ind: Some(side),
+        ..Transaction::default()
+    }
+}
+
+fn statement() -> Statement {
+    let mut by_reference = transaction("100.00", CreditDebit::Credit);
+    by_reference.creditor_reference = Some(CreditorReference {
+        kind: "SCOR".to_string(),
+        reference: "RF18539007547034".to_string(),
+    });
+    let mut by_end_to_end_id = transaction("42.00", CreditDebit::Credit);
+    by_end_to_end_id.end_to_end_id = "ORDER-7".to_string();
+    Statement {
+        id: "STMT-1".to_string(),
+        iban: "CH9300762011623852957".to_string(),
+        transactions: vec![
+            by_reference,
+            by_end_to_end_id,
+            transaction("250.50", CreditDebit::Credit),
+            transaction("250.50", CreditDebit::Credit),
+            transaction("5.00", CreditDebit::Debit),
+        ],
+        ..Statement::default()
+    }
+}
+
+#[test]
+fn reads_expected_payments() {
+    let csv = "\u{feff}Customer;Reference;Amount;Currency\n\
+               \"Muster; AG\";RF18 5390 0754 7034;100;chf\n\
+               \n\
+               Other;;250.50;\n";
+    let expected = read_expected(csv.as_bytes()).unwrap();
+    assert_eq!(expected.len(), 2);
+    assert_eq!(expected[0].reference, "RF18 5390 0754 7034");
+    assert_eq!(expected[0].amount.unwrap().to_string(), "100");
+    assert_eq!(expected[0].currency, "chf");
+    assert_eq!(expected[0].line, 2);
+    assert_eq!(expected[1].reference, "");
+    assert_eq!(expected[1].line, 4);
+
+    let error = read_expected("reference,amount\nA,ten\n".as_bytes()).unwrap_err();
+    assert_eq!(error.to_string(), "Invalid amount 'ten' on line 2");
+    assert!(read_expected("name,iban\n".as_bytes()).is_err());
+}
+
+#[test]
+fn matches_entries_by_reference_and_amount() {
+    let csv = "reference,amount,currency\n\
+               rf18 5390 0754 7034,100.00,CHF\n\
+               ORDER-7,41.00,\n\
+               ,250.50,CHF\n\
+               INV-404,,\n";
+    let mut reconciliation = Reconciliation::new(read_expected(csv.as_bytes()).unwrap());
+    reconciliation.add(&statement());
+    assert_eq!(reconciliation.count(Status::Matched), 2);
+    assert_eq!(reconciliation.count(Status::Missing), 2);
+    assert_eq!(reconciliation.count(Status::Unexpected), 3);
+
+    let mut report = Vec::new();
+    reconciliation.write_csv(&mut report).unwrap();
+    assert_eq!(
+        String::from_utf8(report).unwrap(),
+        "status,reference,amount,currency,credit_debit,booking_date,account,statement,line\n\
+         matched,rf18 5390 0754 7034,100.00,CHF,CRDT,,CH9300762011623852957,STMT-1,2\n\
+         missing,ORDER-7,41.00,,,,,,3\n\
+         matched,,250.50,CHF,CRDT,,CH9300762011623852957,STMT-1,4\n\
+         missing,INV-404,,,,,,,5\n\
+         unexpected,ORDER-7,42.00,CHF,CRDT,,CH9300762011623852957,STMT-1,\n\
+         unexpected,,250.50,CHF,CRDT,,CH9300762011623852957,STMT-1,\n\
+         unexpected,,5.00,CHF,DBIT,,CH9300762011623852957,STMT-1,\n"
+    );
+}
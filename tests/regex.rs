@@ -0,0 +1,83 @@
+use camtconvert::regex::Regex;
+
+fn matches(pattern: &str, text: &str) -> bool {
+    pattern.parse::<Regex>().unwrap().is_match(text)
+}
+
+#[test]
+fn matches_anywhere_unless_anchored() {
+    assert!(matches("coop", "Card payment Coop coop"));
+    assert!(!matches("^coop", "Card payment coop"));
+    assert!(matches("coop$", "Card payment coop"));
+    assert!(!matches("coop$", "coop Zürich"));
+    assert!(matches("", "anything"));
+    assert!(matches("^$", ""));
+}
+
+#[test]
+fn matches_classes_and_repetition() {
+    assert!(matches(r"^RF\d{2}[0-9A-Z]{1,21}$", "RF18539007547034"));
+    assert!(!matches(r"^RF\d{2}[0-9A-Z]{1,21}$", "RF18 539007547034"));
+    assert!(matches(r"^\w+\s+\S+$", "Zürich  HB"));
+    assert!(matches("^a.c$", "abc"));
+    assert!(!matches("^a.c$", "a\nc"));
+    assert!(matches("^[^0-9]+$", "no digits"));
+    assert!(!matches("^[^0-9]+$", "digit 1"));
+    assert!(matches("^[]a-]+$", "a-]"));
+    assert!(matches("^colou?r$", "color"));
+    assert!(matches("^(ab)*c$", "ababc"));
+    assert!(!matches("^(ab)+c$", "c"));
+    assert!(matches("^a{2,}$", "aaaa"));
+    assert!(!matches("^a{2,3}$", "aaaa"));
+    assert!(matches(r"^\d+?\.\d\d$", "12.50"));
+    // Backtracking gives back what a greedy repetition took
+    assert!(matches("^.*foo$", "xfoofoo"));
+    assert!(matches("^(a*)*b$", "aab"));
+}
+
+#[test]
+fn matches_alternatives_and_case() {
+    assert!(matches("migros|coop|aldi", "Coop Pronto aldi"));
+    assert!(!matches("migros|coop", "Coop Pronto"));
+    assert!(matches("(?i)migros|coop", "COOP PRONTO"));
+    assert!(matches("(?i)^zürich$", "ZÜRICH"));
+    assert!(matches("(?i)^[a-z]+$", "ABC"));
+    assert!(matches("^(?:card|pos) payment$", "pos payment"));
+    assert!(matches(r"^a\.b\(c\)$", "a.b(c)"));
+}
+
+#[test]
+fn rejects_invalid_patterns() {
+    for (pattern, message) in [
+        ("(coop", "missing ) in regular expression"),
+        ("coop)", "unmatched ) in regular expression coop)"),
+        ("[a-", "missing ] in regular expression"),
+        ("[z-a]", "invalid range z-a in regular expression"),
+        ("*coop", "nothing to repeat before * in regular expression"),
+        ("a{3,1}", "invalid quantifier {3,1} in regular expression"),
+        ("a{x}", "invalid quantifier {x} in regular expression"),
+        ("a{2", "missing } in regular expression"),
+        (r"\q", "unknown escape \\q in regular expression"),
+        ("a\\", "regular expression ends with \\"),
+    ] {
+        assert_eq!(
+            pattern.parse::<Regex>().unwrap_err(),
+            message,
+            "{}",
+            pattern
+        );
+    }
+}
+
+#[test]
+fn finds_and_replaces_matches() {
+    let regex: Regex = "[0-9]+".parse().unwrap();
+    assert_eq!(regex.find("Zürich 8001"), Some((8, 12)));
+    assert_eq!(regex.find("Zürich"), None);
+    assert_eq!(regex.replace_all("a1b22c333", "#"), "a#b#c#");
+
+    let empty: Regex = "x*".parse().unwrap();
+    assert_eq!(empty.replace_all("abc", "-"), "-a-b-c-");
+    let anchored: Regex = "^a".parse().unwrap();
+    assert_eq!(anchored.replace_all("aaa", ""), "aa");
+}
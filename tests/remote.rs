@@ -0,0 +1,22 @@
+#![cfg(feature = "remote")]
+
+use camtconvert::remote::{file_name, is_remote};
+
+#[test]
+fn names_outputs_after_url_path() {
+    assert!(is_remote("https://bank.example/camt/statement.xml"));
+    assert!(is_remote("s3://statements/2025/06/statement.xml"));
+    assert!(!is_remote("http://bank.example/statement.xml"));
+    assert!(!is_remote("statements/s3://x.xml"));
+
+    assert_eq!(
+        file_name("https://bank.example/camt/statement.xml?X-Amz-Signature=a/b#top"),
+        Some("statement.xml")
+    );
+    assert_eq!(
+        file_name("s3://statements/2025/06/statement.xml.gz"),
+        Some("statement.xml.gz")
+    );
+    assert_eq!(file_name("https://bank.example/camt/"), None);
+    assert_eq!(file_name("s3://statements"), None);
+}
@@ -0,0 +1,139 @@
+use camtconvert::json::Value;
+use camtconvert::model::{
+    Amount, Balance, BalanceType, CreditDebit, EntryStatus, Statement, Transaction,
+};
+use camtconvert::review::{ask, confirm, summary, write_review};
+
+fn amount(value: &str) -> Amount {
+    Amount {
+        value: value.parse().unwrap(),
+        currency: "CHF".to_string(),
+    }
+}
+
+#[test]
+fn lists_balances_entries_and_warnings() {
+    let statement = Statement {
+        id: "STMT-1".to_string(),
+        iban: "CH9300762011623852957".to_string(),
+        currency: "CHF".to_string(),
+        owner_name: "Muster AG".to_string(),
+        balances: vec![Balance {
+            balance_type: Some(BalanceType::ClosingBooked),
+            amount: amount("20.00"),
+            credit_debit_ind: Some(CreditDebit::Debit),
+            ..Balance::default()
+        }],
+        transactions: vec![Transaction {
+            amount: amount("20.00"),
+            credit_debit_ind: Some(CreditDebit::Debit),
+            status: EntryStatus::Pending,
+            bank_tx_code: "CARD-1".to_string(),
+            additional_info: "x".repeat(60),
+            ..Transaction::default()
+        }],
+        ..Statement::default()
+    };
+    let mut output = Vec::new();
+    write_review(
+        &mut output,
+        &statement,
+        &["Balances do not add up".to_string()],
+        false,
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.starts_with("Statement STMT-1 of CH9300762011623852957 CHF, Muster AG\n"));
+    assert!(output.contains(&format!("  CLBD{}-20.00 CHF\n", " ".repeat(24))));
+    assert!(output.contains("Entries (1)"));
+    assert!(output.contains(&format!("CARD-1            {}… [PDNG]", "x".repeat(47))));
+    assert!(output.ends_with("Warnings (1)\n  ! Balances do not add up\n"));
+    assert!(!output.contains('\x1b'));
+}
+
+#[test]
+fn summarizes_as_json() {
+    let statement = Statement {
+        id: "STMT-1".to_string(),
+        iban: "CH9300762011623852957".to_string(),
+        currency: "CHF".to_string(),
+        balances: vec![
+            Balance {
+                balance_type: Some(BalanceType::OpeningBooked),
+                amount: amount("100.00"),
+                credit_debit_ind: Some(CreditDebit::Credit),
+                ..Balance::default()
+            },
+            Balance {
+                balance_type: Some(BalanceType::ClosingBooked),
+                amount: amount("70.00"),
+                credit_debit_ind: Some(CreditDebit::Credit),
+                ..Balance::default()
+            },
+        ],
+        transactions: vec![
+            Transaction {
+                amount: amount("20.00"),
+                credit_debit_ind: Some(CreditDebit::Debit),
+                ..Transaction::default()
+            },
+            Transaction {
+                amount: amount("5.00"),
+                credit_debit_ind: Some(CreditDebit::Debit),
+                status: EntryStatus::Pending,
+                ..Transaction::default()
+            },
+        ],
+        ..Statement::default()
+    };
+    let summary = summary(&statement, &["Odd".to_string()]);
+
+    assert_eq!(summary.get("id").and_then(Value::as_str), Some("STMT-1"));
+    assert_eq!(summary.get("entries").unwrap().to_string(), "2");
+    assert_eq!(summary.get("pending_entries").unwrap().to_string(), "1");
+    assert_eq!(summary.get("entries_total").unwrap().to_string(), "-25.00");
+    let balances = summary.get("balances").and_then(Value::as_array).unwrap();
+    assert_eq!(balances.len(), 2);
+    assert_eq!(
+        balances[0].get("type").and_then(Value::as_str),
+        Some("OPBD")
+    );
+    assert_eq!(balances[1].get("amount").unwrap().to_string(), "70.00");
+    assert!(
+        summary
+            .get("balance_mismatch")
+            .and_then(Value::as_str)
+            .unwrap()
+            .starts_with("Entries do not add up")
+    );
+    assert_eq!(summary.get("warnings").unwrap().to_string(), r#"["Odd"]"#);
+    assert_eq!(summary.get("from").unwrap().to_string(), "null");
+}
+
+#[test]
+fn confirms_only_yes() {
+    for (answer, confirmed) in [
+        ("y\n", true),
+        ("Yes\n", true),
+        ("n\n", false),
+        ("\n", false),
+        ("", false),
+    ] {
+        let mut output = Vec::new();
+        assert_eq!(
+            confirm(answer.as_bytes(), &mut output, "Write out.xml?").unwrap(),
+            confirmed
+        );
+        assert_eq!(output, b"Write out.xml? [y/N] ");
+    }
+}
+
+#[test]
+fn asks_for_a_value() {
+    let mut output = Vec::new();
+    let answer = ask(&b"  UBSWCHZH80A \nnext\n"[..], &mut output, "BIC:").unwrap();
+    assert_eq!(answer, "UBSWCHZH80A");
+    assert_eq!(output, b"BIC: ");
+    assert_eq!(ask(&b""[..], &mut output, "BIC:").unwrap(), "");
+}
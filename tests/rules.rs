@@ -0,0 +1,161 @@
+use camtconvert::rules::{RuleSet, check_rules, is_creditor_reference, is_qr_reference};
+
+const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">
+  <BkToCstmrStmt>
+    <GrpHdr>
+      <MsgId>MSG-1</MsgId>
+      <MsgPgntn><PgNb>1</PgNb><LastPgInd>true</LastPgInd></MsgPgntn>
+      <AddtlInf>SPS/2.1</AddtlInf>
+    </GrpHdr>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <ElctrncSeqNb>1</ElctrncSeqNb>
+      <Acct><Id><IBAN>IBAN</IBAN></Id></Acct>
+      <Bal><Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp></Bal>
+      <Bal><Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp></Bal>
+      <Ntry>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+        <ValDt><Dt>2025-06-05</Dt></ValDt>
+        <AcctSvcrRef>TX1</AcctSvcrRef>
+        <BkTxCd><Domn><Cd>PMNT</Cd></Domn></BkTxCd>
+        <NtryDtls><TxDtls><RmtInf><Strd><CdtrRefInf>
+          <Tp><CdOrPrtry><Prtry>QRR</Prtry></CdOrPrtry></Tp>
+          <Ref>REFERENCE</Ref>
+        </CdtrRefInf></Strd></RmtInf></TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-06</Dt></BookgDt>
+        <AcctSvcrRef>TX2</AcctSvcrRef>
+        <BkTxCd><Prtry><Cd>FEE</Cd></Prtry></BkTxCd>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+fn violations(iban: &str, reference: &str) -> Vec<String> {
+    let document = DOCUMENT
+        .replace("<IBAN>IBAN</IBAN>", &format!("<IBAN>{}</IBAN>", iban))
+        .replace("REFERENCE", reference);
+    check_rules(document.as_bytes(), RuleSet::Sps)
+        .unwrap()
+        .iter()
+        .map(|violation| format!("{} {}", violation.rule, violation.path))
+        .collect()
+}
+
+#[test]
+fn checks_sps_rules() {
+    assert_eq!(
+        violations("CH9300762011623852957", "210000000003139471430009017"),
+        vec![
+            "sps/value-date Document/BkToCstmrStmt/Stmt/Ntry[2]",
+            "sps/transaction-code Document/BkToCstmrStmt/Stmt/Ntry[2]/BkTxCd",
+        ]
+    );
+}
+
+#[test]
+fn checks_qr_references() {
+    let violations = violations("CH4431999123000889012", "210000000003139471430009018");
+    assert!(violations.contains(
+        &"sps/qr-reference Document/BkToCstmrStmt/Stmt/Ntry[1]/NtryDtls/TxDtls/RmtInf/Strd/CdtrRefInf"
+            .to_string()
+    ));
+    assert!(!violations.iter().any(|v| v.starts_with("sps/qr-iban")));
+
+    // Credits to a QR-IBAN need a QR reference
+    let document = DOCUMENT
+        .replace("<IBAN>IBAN</IBAN>", "<IBAN>CH4431999123000889012</IBAN>")
+        .replace("QRR", "SCOR")
+        .replace("REFERENCE", "RF18539007547034");
+    let violations = check_rules(document.as_bytes(), RuleSet::Sps).unwrap();
+    let qr_iban = violations
+        .iter()
+        .find(|violation| violation.rule == "sps/qr-iban")
+        .expect("missing QR reference should be reported");
+    assert_eq!(
+        qr_iban.message,
+        "1 credit entries to QR-IBAN CH4431999123000889012 have no QR reference"
+    );
+}
+
+#[test]
+fn validates_references() {
+    assert!(is_qr_reference("210000000003139471430009017"));
+    assert!(!is_qr_reference("210000000003139471430009018"));
+    assert!(!is_qr_reference("21000000000313947143000901"));
+    assert!(is_creditor_reference("RF18539007547034"));
+    assert!(!is_creditor_reference("RF19539007547034"));
+    assert!(!is_creditor_reference("RF18"));
+}
+
+const SEPA_DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Acct>
+        <Id><IBAN>IBAN</IBAN></Id>
+        <Ownr><Nm>Muster GmbH</Nm></Ownr>
+        <Svcr><FinInstnId><BICFI>BIC</BICFI></FinInstnId></Svcr>
+      </Acct>
+      <Ntry>
+        <NtryDtls><TxDtls>
+          <Refs><AcctSvcrRef>TX1</AcctSvcrRef><EndToEndId>E2E-1</EndToEndId></Refs>
+          <RmtInf><Ustrd>Invoice 1</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+      <Ntry>
+        <NtryDtls><TxDtls>
+          <Refs><AcctSvcrRef>TX2</AcctSvcrRef></Refs>
+          <RmtInf><Ustrd>Bäckerei &amp; Co</Ustrd></RmtInf>
+        </TxDtls></NtryDtls>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+fn sepa_violations(iban: &str, bic: &str) -> Vec<String> {
+    let document = SEPA_DOCUMENT
+        .replace("<IBAN>IBAN</IBAN>", &format!("<IBAN>{}</IBAN>", iban))
+        .replace("<BICFI>BIC</BICFI>", &format!("<BICFI>{}</BICFI>", bic));
+    check_rules(document.as_bytes(), RuleSet::Sepa)
+        .unwrap()
+        .iter()
+        .map(|violation| {
+            format!(
+                "{} {}: {}",
+                violation.rule, violation.path, violation.message
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn checks_sepa_rules() {
+    assert_eq!(
+        sepa_violations("DE89370400440532013000", "COBADEFFXXX"),
+        vec![
+            "sepa/end-to-end-id Document/BkToCstmrStmt/Stmt/Ntry[2]/NtryDtls/TxDtls: TxDtls has no Refs/EndToEndId",
+            "sepa/character-set Document/BkToCstmrStmt/Stmt/Ntry[2]: Ntry/NtryDtls/TxDtls/RmtInf/Ustrd has 'ä', outside the SEPA character set",
+        ]
+    );
+}
+
+#[test]
+fn checks_bic_country_of_iban() {
+    let mismatch = |iban, bic| {
+        sepa_violations(iban, bic)
+            .iter()
+            .any(|violation| violation.starts_with("sepa/iban-bic"))
+    };
+    assert!(!mismatch("DE89370400440532013000", "COBADEFFXXX"));
+    assert!(mismatch("DE89370400440532013000", "BNPAFRPPXXX"));
+    assert!(mismatch("DE89370400440532013000", "XXXXXXXX"));
+    // Territories use the IBANs of the country serving them
+    assert!(!mismatch("FR1420041010050500013M02606", "BFCORERXXXX"));
+    assert!(mismatch("DE89370400440532013000", "BFCORERXXXX"));
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "schemas")]
+
+use camtconvert::convert::convert_versions;
+use camtconvert::generate::{GenerateOptions, generate_camt_10};
+use camtconvert::matrix::{READERS, WRITERS, find_reader, find_writer, pairs};
+use camtconvert::reader::ParseOptions;
+use camtconvert::schemas::{SCHEMAS, find_schema};
+use camtconvert::writer::WriteOptions;
+use chrono::NaiveDate;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn bundles_a_schema_of_every_version() {
+    let versions = READERS
+        .iter()
+        .map(|reader| reader.version)
+        .chain(WRITERS.iter().map(|writer| writer.version));
+    for version in versions {
+        let schema = find_schema(version).unwrap();
+        assert!(
+            schema.xsd.contains(&format!(
+                "targetNamespace=\"urn:iso:std:iso:20022:tech:xsd:{}\"",
+                version
+            )),
+            "{}",
+            version
+        );
+    }
+    assert_eq!(SCHEMAS.len(), READERS.len() + WRITERS.len());
+}
+
+#[test]
+fn finds_schemas_by_any_name() {
+    for name in ["camt.053.001.04", "053.001.04", "04"] {
+        let schema = find_schema(name).unwrap();
+        assert_eq!(schema.version, "camt.053.001.04");
+        assert_eq!(schema.file_name(), "camt.053.001.04.xsd");
+    }
+    assert!(find_schema("09").is_none());
+}
+
+// Inputs and the outputs written from them are valid against the bundled
+// schemas; skipped where xmllint is not installed
+#[test]
+fn validates_inputs_and_outputs() {
+    if Command::new("xmllint").arg("--version").output().is_err() {
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("camtconvert-schemas-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for schema in SCHEMAS {
+        std::fs::write(dir.join(schema.file_name()), schema.xsd).unwrap();
+    }
+    let mut input = Vec::new();
+    let options = GenerateOptions {
+        entries: 20,
+        currency: "CHF".to_string(),
+        date: NaiveDate::from_ymd_opt(2025, 6, 30).unwrap(),
+        seed: 7,
+    };
+    generate_camt_10(&mut input, &options).unwrap();
+
+    for (from, to) in pairs() {
+        let converted = convert_versions(
+            input.as_slice(),
+            find_reader(from).unwrap(),
+            find_writer(to).unwrap(),
+            &ParseOptions::default(),
+            &WriteOptions::default(),
+        )
+        .unwrap();
+        for (version, document) in [(from, &input), (to, &converted.output)] {
+            let path = dir.join(format!("{}.xml", version));
+            std::fs::write(&path, document).unwrap();
+            assert_valid(&dir.join(find_schema(version).unwrap().file_name()), &path);
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn assert_valid(xsd: &Path, document: &Path) {
+    let output = Command::new("xmllint")
+        .arg("--noout")
+        .arg("--schema")
+        .arg(xsd)
+        .arg(document)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
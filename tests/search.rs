@@ -0,0 +1,66 @@
+use camtconvert::model::{Amount, CreditDebit, CreditorReference, Transaction};
+use camtconvert::search::{EntryFilter, reference};
+
+fn transaction(value: &str, side: CreditDebit) -> Transaction {
+    Transaction {
+        amount: Amount {
+            value: value.parse().unwrap(),
+            currency: "CHF".to_string(),
+        },
+        credit_debit_ind: Some(side),
+        ..Transaction::default()
+    }
+}
+
+#[test]
+fn finds_entries_by_text_in_any_field() {
+    let mut entry = transaction("1234.50", CreditDebit::Credit);
+    entry.remittance_info = vec!["Invoice 77 ACME AG".to_string()];
+    let search = |text: &str| EntryFilter {
+        text: Some(text.to_string()),
+        amount: None,
+    };
+
+    assert!(search("acme").matches(&entry));
+    assert!(!search("muster").matches(&entry));
+    entry.debtor_name = "Muster GmbH".to_string();
+    assert!(search("MUSTER").matches(&entry));
+    assert!(EntryFilter::default().matches(&entry));
+}
+
+#[test]
+fn finds_entries_by_amount() {
+    let credit = transaction("1234.50", CreditDebit::Credit);
+    let debit = transaction("1234.50", CreditDebit::Debit);
+    let search = |amount: &str| EntryFilter {
+        text: None,
+        amount: Some(amount.parse().unwrap()),
+    };
+
+    assert!(search("1234.5").matches(&credit));
+    assert!(search("1234.50").matches(&debit));
+    assert!(!search("-1234.50").matches(&credit));
+    assert!(search("-1234.50").matches(&debit));
+    assert!(!search("1234.51").matches(&credit));
+
+    let both = EntryFilter {
+        text: Some("acme".to_string()),
+        amount: Some("1234.50".parse().unwrap()),
+    };
+    assert!(!both.matches(&credit));
+}
+
+#[test]
+fn references_prefer_creditor_references() {
+    let mut entry = transaction("10.00", CreditDebit::Credit);
+    assert_eq!(reference(&entry), "");
+    entry.end_to_end_id = "NOTPROVIDED".to_string();
+    assert_eq!(reference(&entry), "");
+    entry.end_to_end_id = "ORDER-7".to_string();
+    assert_eq!(reference(&entry), "ORDER-7");
+    entry.creditor_reference = Some(CreditorReference {
+        kind: "SCOR".to_string(),
+        reference: "RF18539007547034".to_string(),
+    });
+    assert_eq!(reference(&entry), "RF18539007547034");
+}
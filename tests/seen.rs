@@ -0,0 +1,158 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::model::{Amount, CreditDebit, Statement, Transaction};
+use camtconvert::reference::{ReferenceStyle, generate_transaction_reference};
+use camtconvert::seen::{DuplicateAction, DuplicateCheck, SeenEntries, entry_key};
+use std::fs;
+
+fn statement(values: &[&str]) -> Statement {
+    let transactions = values
+        .iter()
+        .map(|value| Transaction {
+            amount: Amount {
+                value: value.parse().unwrap(),
+                currency: "CHF".to_string(),
+            },
+            credit_debit_ind: Some(CreditDebit::Credit),
+            additional_info: format!("Payment of {}", value),
+            ..Transaction::default()
+        })
+        .collect();
+    Statement {
+        id: "STMT-1".to_string(),
+        iban: "CH9300762011623852957".to_string(),
+        transactions,
+        ..Statement::default()
+    }
+}
+
+#[test]
+fn remembers_seen_entries() {
+    let dir = std::env::temp_dir().join(format!("camtconvert-seen-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("seen");
+
+    let mut seen = SeenEntries::load(&path).unwrap();
+    assert!(seen.is_empty());
+    seen.insert("CH93", "TX1", "monday.xml");
+    seen.insert("CH93", "TX1", "tuesday.xml");
+    seen.save().unwrap();
+
+    let seen = SeenEntries::load(&path).unwrap();
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen.first_seen("CH93", "TX1"), Some("monday.xml"));
+    assert_eq!(seen.first_seen("CH93", "TX2"), None);
+    assert_eq!(seen.first_seen("DE89", "TX1"), None);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn flags_or_drops_entries_of_earlier_inputs() {
+    let dir = std::env::temp_dir().join(format!("camtconvert-dup-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut seen = SeenEntries::load(&dir.join("seen")).unwrap();
+    let first = statement(&["10.00", "20.00"]);
+    let reference =
+        generate_transaction_reference(&first, &first.transactions[1], ReferenceStyle::Hash);
+    seen.insert(
+        &first.iban,
+        &entry_key(&first.transactions[1]),
+        "monday.xml",
+    );
+
+    // The repeated entry is kept when flagged, with a warning
+    let second = statement(&["20.00", "30.00", "30.00"]);
+    let mut check = DuplicateCheck::new(&seen, DuplicateAction::Flag, ReferenceStyle::Hash);
+    let kept: Vec<bool> = second
+        .transactions
+        .iter()
+        .map(|transaction| check.keep(&second, transaction))
+        .collect();
+    assert_eq!(kept, [true, true, true]);
+    assert_eq!(
+        check.warnings,
+        [format!(
+            "Entry {} of CH9300762011623852957 was already converted from monday.xml",
+            reference
+        )]
+    );
+    assert_eq!(check.dropped_total.unwrap().to_string(), "0");
+
+    // Dropped otherwise, while identical entries of one input are kept
+    let mut check = DuplicateCheck::new(&seen, DuplicateAction::Drop, ReferenceStyle::Hash);
+    let kept: Vec<bool> = second
+        .transactions
+        .iter()
+        .map(|transaction| check.keep(&second, transaction))
+        .collect();
+    assert_eq!(kept, [false, true, true]);
+    assert!(check.warnings[0].ends_with(", left out"));
+    assert_eq!(check.dropped_total.unwrap().to_string(), "20.00");
+
+    for (iban, key) in check.into_keys() {
+        seen.insert(&iban, &key, "tuesday.xml");
+    }
+    assert_eq!(seen.len(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn tells_look_alike_entries_apart() {
+    let dir = std::env::temp_dir().join(format!("camtconvert-alike-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut seen = SeenEntries::load(&dir.join("seen")).unwrap();
+    let payment = |debtor: &str, bank_reference: &str| Transaction {
+        debtor_name: debtor.to_string(),
+        account_servicer_ref: bank_reference.to_string(),
+        ..statement(&["100.00"]).transactions[0].clone()
+    };
+
+    // Payments of the same amount on the same day share their generated
+    // reference, but not their key
+    let monday = Statement {
+        transactions: vec![payment("Alice", "BANKREF-A"), payment("Bob", "BANKREF-B")],
+        ..statement(&[])
+    };
+    let [alice, bob] = &monday.transactions[..] else {
+        unreachable!()
+    };
+    assert_eq!(
+        generate_transaction_reference(&monday, alice, ReferenceStyle::Hash),
+        generate_transaction_reference(&monday, bob, ReferenceStyle::Hash)
+    );
+    assert_eq!(entry_key(alice), "ref:BANKREF-A");
+    let mut check = DuplicateCheck::new(&seen, DuplicateAction::Drop, ReferenceStyle::Hash);
+    for transaction in &monday.transactions {
+        assert!(check.keep(&monday, transaction));
+    }
+    for (iban, key) in check.into_keys() {
+        seen.insert(&iban, &key, "monday.xml");
+    }
+
+    let tuesday = Statement {
+        transactions: vec![payment("Carol", "BANKREF-C"), payment("Bob", "BANKREF-B")],
+        ..statement(&[])
+    };
+    let mut check = DuplicateCheck::new(&seen, DuplicateAction::Drop, ReferenceStyle::Hash);
+    let kept: Vec<bool> = tuesday
+        .transactions
+        .iter()
+        .map(|transaction| check.keep(&tuesday, transaction))
+        .collect();
+    assert_eq!(kept, [true, false]);
+
+    // Without references of the bank, the whole entry is compared
+    let mut dave = payment("Dave", "");
+    let erin = payment("Erin", "");
+    assert_ne!(entry_key(&dave), entry_key(&erin));
+    assert!(entry_key(&dave).starts_with("sha1:"));
+    let key = entry_key(&dave);
+    dave.additional_info = format!("  {}  ", dave.additional_info);
+    assert_eq!(entry_key(&dave), key);
+    dave.remittance_info = vec!["Invoice 1".to_string()];
+    assert_ne!(entry_key(&dave), key);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,507 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::jobs::{MAX_JOBS, MAX_RESULTS, PoolStatus};
+use camtconvert::json::Value;
+use camtconvert::server::{
+    MAX_CONNECTIONS, Request, Response, Server, ServerOptions, parse_api_keys, parse_tenants,
+    respond, serve,
+};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct>
+        <Id><IBAN>CH9300762011623852957</IBAN></Id>
+      </Acct>
+      <Ntry>
+        <Amt Ccy="CHF">10.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><DtTm>2025-06-05T10:00:00+02:00</DtTm></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+fn post(target: &str, body: &str) -> Request {
+    Request {
+        method: "POST".to_string(),
+        target: target.to_string(),
+        headers: Vec::new(),
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+fn request(method: &str, target: &str) -> Request {
+    Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        headers: Vec::new(),
+        body: Vec::new(),
+    }
+}
+
+// Status of a job once it is no longer queued or running
+fn finished_status(server: &Server, id: &str) -> Value {
+    for _ in 0..500 {
+        let response = server.respond(&request("GET", &format!("/jobs/{}", id)));
+        assert_eq!(response.status, 200);
+        let status: Value = String::from_utf8(response.body).unwrap().parse().unwrap();
+        if !matches!(
+            status.get("status").and_then(Value::as_str),
+            Some("queued" | "running")
+        ) {
+            return status;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("Job {} did not finish", id);
+}
+
+#[test]
+fn converts_posted_document_with_query_options() {
+    let options = ServerOptions::default();
+    let response = respond(
+        &post("/convert?version=08&date-format=input", DOCUMENT),
+        &options,
+    );
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.header("content-type"),
+        Some("application/xml; charset=utf-8")
+    );
+    let output = String::from_utf8(response.body).unwrap();
+    assert!(output.contains("camt.053.001.08"));
+    assert!(output.contains("<DtTm>2025-06-05T10:00:00+02:00</DtTm>"));
+
+    let response = respond(&post("/convert?version=04", DOCUMENT), &options);
+    assert_eq!(response.status, 200);
+    assert!(
+        String::from_utf8(response.body)
+            .unwrap()
+            .contains("camt.053.001.04")
+    );
+    let response = respond(&post("/convert?version=07", DOCUMENT), &options);
+    assert_eq!(response.status, 400);
+    let response = respond(
+        &post(
+            "/convert",
+            r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.04"/>"#,
+        ),
+        &options,
+    );
+    assert_eq!(response.status, 400);
+    let response = respond(&post("/convert", &DOCUMENT.replace("CH93", "CH94")), &{
+        let mut options = ServerOptions::default();
+        options.parse.strict = true;
+        options
+    });
+    assert_eq!(response.status, 422);
+}
+
+#[test]
+fn answers_over_http() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener, ServerOptions::default()));
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "POST /convert HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue\r\nContent-Length: {}\r\n\r\n{}",
+        DOCUMENT.len(),
+        DOCUMENT
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("\r\nConnection: close\r\n"));
+    assert!(response.ends_with("</Document>\n") || response.ends_with("</Document>"));
+}
+
+#[test]
+fn rejects_chunks_beyond_the_body_size() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener, ServerOptions::default()));
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "POST /convert HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n\
+         4\r\n<Doc\r\nfffffffffffffff8\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+}
+
+#[test]
+fn turns_away_connections_beyond_the_limit() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener, ServerOptions::default()));
+
+    // Clients that do not send their request hold their connection
+    let idle: Vec<TcpStream> = (0..MAX_CONNECTIONS)
+        .map(|_| TcpStream::connect(address).unwrap())
+        .collect();
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    assert!(response.contains("\r\nRetry-After: 1\r\n"));
+
+    // Once they leave, others are served again
+    drop(idle);
+    let served = (0..100).any(|_| {
+        thread::sleep(Duration::from_millis(20));
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET /healthz HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response.starts_with("HTTP/1.1 200 OK\r\n")
+    });
+    assert!(served);
+}
+
+#[test]
+fn converts_submitted_jobs() {
+    let server = Server::new(ServerOptions::default());
+    let response = server.respond(&post("/jobs?version=04", DOCUMENT));
+    assert_eq!(response.status, 202);
+    let submitted: Value = String::from_utf8(response.body).unwrap().parse().unwrap();
+    let id = submitted
+        .get("id")
+        .and_then(Value::as_str)
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        response
+            .headers
+            .iter()
+            .find(|(name, _)| name == "Location")
+            .map(|(_, value)| value.clone()),
+        Some(format!("/jobs/{}", id))
+    );
+
+    let status = finished_status(&server, &id);
+    assert_eq!(status.get("status").and_then(Value::as_str), Some("done"));
+    let response = server.respond(&request("GET", &format!("/jobs/{}/result", id)));
+    assert_eq!(response.status, 200);
+    assert!(
+        String::from_utf8(response.body)
+            .unwrap()
+            .contains("camt.053.001.04")
+    );
+    assert!(
+        response
+            .headers
+            .iter()
+            .any(|(name, _)| name == "X-Warning-Count")
+    );
+
+    let response = server.respond(&request("DELETE", &format!("/jobs/{}", id)));
+    assert_eq!(response.status, 204);
+    let response = server.respond(&request("GET", &format!("/jobs/{}", id)));
+    assert_eq!(response.status, 404);
+
+    // Failed jobs answer for their result as the conversion would
+    let response = server.respond(&post("/jobs?strict", &DOCUMENT.replace("CH93", "CH94")));
+    let submitted: Value = String::from_utf8(response.body).unwrap().parse().unwrap();
+    let id = submitted
+        .get("id")
+        .and_then(Value::as_str)
+        .unwrap()
+        .to_string();
+    let status = finished_status(&server, &id);
+    assert_eq!(status.get("status").and_then(Value::as_str), Some("failed"));
+    assert!(status.get("error").is_some());
+    let response = server.respond(&request("GET", &format!("/jobs/{}/result", id)));
+    assert_eq!(response.status, 422);
+
+    assert_eq!(
+        server.respond(&post("/jobs?version=07", DOCUMENT)).status,
+        400
+    );
+    assert_eq!(server.respond(&request("GET", "/jobs")).status, 405);
+    assert_eq!(server.respond(&request("GET", "/jobs/unknown")).status, 404);
+    // Converting right away still works
+    assert_eq!(server.respond(&post("/convert", DOCUMENT)).status, 200);
+}
+
+#[test]
+fn keeps_accepting_jobs_whose_results_are_not_fetched() {
+    let server = Server::new(ServerOptions::default());
+    let mut ids = Vec::new();
+    for _ in 0..MAX_JOBS + MAX_RESULTS + 1 {
+        let response = server.respond(&post("/jobs", DOCUMENT));
+        assert_eq!(response.status, 202);
+        let submitted: Value = String::from_utf8(response.body).unwrap().parse().unwrap();
+        let id = submitted.get("id").and_then(Value::as_str).unwrap();
+        finished_status(&server, id);
+        ids.push(id.to_string());
+    }
+
+    // The oldest results made room for the newer ones
+    let response = server.respond(&request("GET", &format!("/jobs/{}", ids[0])));
+    assert_eq!(response.status, 404);
+    let response = server.respond(&request("GET", &format!("/jobs/{}", ids[ids.len() - 1])));
+    assert_eq!(response.status, 200);
+    let response = server.respond(&request("GET", "/readyz"));
+    let readiness: Value = String::from_utf8(response.body).unwrap().parse().unwrap();
+    assert_eq!(
+        readiness.get("jobs_done").unwrap().to_string(),
+        MAX_RESULTS.to_string()
+    );
+}
+
+#[test]
+fn exposes_metrics_of_conversions() {
+    let server = Server::new(ServerOptions::default());
+    assert_eq!(server.respond(&post("/convert", DOCUMENT)).status, 200);
+    let invalid = DOCUMENT.replace("<Amt Ccy=\"CHF\">10.00</Amt>", "<Amt Ccy=\"CHF\">ten</Amt>");
+    assert_eq!(server.respond(&post("/convert", &invalid)).status, 422);
+
+    let response = server.respond(&request("GET", "/metrics"));
+    assert_eq!(response.status, 200);
+    let metrics = String::from_utf8(response.body).unwrap();
+    assert!(metrics.contains("\ncamtconvert_conversions_total 2\n"));
+    assert!(metrics.contains("\ncamtconvert_conversion_failures_total{reason=\"invalid\"} 1\n"));
+    assert!(metrics.contains("\ncamtconvert_entries_total 1\n"));
+    assert!(metrics.contains("\ncamtconvert_conversion_duration_seconds_count 2\n"));
+    assert!(metrics.contains("camtconvert_conversion_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+
+    assert_eq!(server.respond(&post("/metrics", "")).status, 405);
+}
+
+#[test]
+fn answers_health_and_readiness_probes() {
+    let server = Server::new(ServerOptions::default());
+    let response = server.respond(&request("GET", "/healthz"));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"{\"status\":\"ok\"}\n");
+
+    let response = server.respond(&request("GET", "/readyz"));
+    assert_eq!(response.status, 200);
+    let readiness: Value = String::from_utf8(response.body).unwrap().parse().unwrap();
+    assert_eq!(
+        readiness.get("status").and_then(Value::as_str),
+        Some("ready")
+    );
+    assert_eq!(
+        readiness.get("workers").map(ToString::to_string),
+        readiness.get("workers_alive").map(ToString::to_string)
+    );
+    assert_eq!(readiness.get("jobs").unwrap().to_string(), "0");
+    assert!(
+        readiness
+            .get("input_versions")
+            .and_then(Value::as_array)
+            .unwrap()
+            .iter()
+            .any(|version| version.as_str() == Some("camt.053.001.10"))
+    );
+
+    assert_eq!(server.respond(&post("/readyz", "")).status, 405);
+
+    // Not ready without workers or room for another job
+    let pool = PoolStatus {
+        workers: 2,
+        alive: 2,
+        queued: 0,
+        running: 0,
+        done: 0,
+        jobs: 0,
+    };
+    assert!(pool.is_ready());
+    assert!(!PoolStatus { alive: 0, ..pool }.is_ready());
+    assert!(
+        !PoolStatus {
+            queued: MAX_JOBS - 1,
+            running: 1,
+            jobs: MAX_JOBS,
+            ..pool
+        }
+        .is_ready()
+    );
+    // Unfetched results do not take a pod out of rotation
+    assert!(
+        PoolStatus {
+            done: MAX_RESULTS,
+            jobs: MAX_RESULTS,
+            ..pool
+        }
+        .is_ready()
+    );
+}
+
+#[test]
+fn requires_an_api_key() {
+    let api_keys = parse_api_keys("# clients\nerp: s3cret\n\nreporting:0ther\n").unwrap();
+    assert_eq!(api_keys.len(), 2);
+    assert_eq!(api_keys[0].name, "erp");
+    assert_eq!(api_keys[0].key, "s3cret");
+    assert!(!format!("{:?}", api_keys[0]).contains("s3cret"));
+    let server = Server::new(ServerOptions {
+        api_keys,
+        ..ServerOptions::default()
+    });
+    let with = |name: &str, value: &str| {
+        let mut request = post("/convert", DOCUMENT);
+        request.headers.push((name.to_string(), value.to_string()));
+        request
+    };
+
+    let response = server.respond(&post("/convert", DOCUMENT));
+    assert_eq!(response.status, 401);
+    assert_eq!(response.header("WWW-Authenticate"), Some("Bearer"));
+    assert_eq!(
+        server
+            .respond(&with("Authorization", "Bearer s3cre"))
+            .status,
+        401
+    );
+    assert_eq!(
+        server
+            .respond(&with("Authorization", "Basic s3cret"))
+            .status,
+        401
+    );
+    assert_eq!(server.respond(&request("GET", "/metrics")).status, 401);
+
+    let given = with("authorization", "bearer s3cret");
+    assert_eq!(server.client(&given), Some("erp"));
+    assert_eq!(server.respond(&given).status, 200);
+    let given = with("X-API-Key", "0ther");
+    assert_eq!(server.client(&given), Some("reporting"));
+    assert_eq!(server.respond(&given).status, 200);
+
+    assert_eq!(server.respond(&request("GET", "/healthz")).status, 200);
+    assert_eq!(server.respond(&request("GET", "/readyz")).status, 200);
+}
+
+#[test]
+fn rejects_invalid_api_key_files() {
+    assert!(parse_api_keys("s3cret").is_err());
+    assert!(parse_api_keys("erp:").is_err());
+    assert!(parse_api_keys("erp:a\nerp:b").is_err());
+    assert!(parse_api_keys("erp:a\nreporting:a").is_err());
+    assert_eq!(parse_api_keys("\n# none\n"), Ok(Vec::new()));
+}
+
+#[test]
+fn converts_with_the_options_of_a_tenant() {
+    let mut options = ServerOptions {
+        api_keys: parse_api_keys("ubs:k1\nadmin:k2").unwrap(),
+        ..ServerOptions::default()
+    };
+    options.write.servicer_bic = Some("POFICHBEXXX".to_string());
+    options.tenants = parse_tenants(
+        "# subsidiaries\nubs: version=04&servicer-bic=UBSWCHZH80A\nother:\n",
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        options.tenants[0].options.write.servicer_bic.as_deref(),
+        Some("UBSWCHZH80A")
+    );
+    assert_eq!(
+        options.tenants[1].options.write.servicer_bic.as_deref(),
+        Some("POFICHBEXXX")
+    );
+    assert!(options.tenants[0].options.api_keys.is_empty());
+    let server = Server::new(options);
+    let with_key = |target: &str, key: &str| {
+        let mut request = post(target, DOCUMENT);
+        request
+            .headers
+            .push(("X-API-Key".to_string(), key.to_string()));
+        request
+    };
+    let body = |response: Response| String::from_utf8(response.body).unwrap();
+
+    // By the name of the key
+    let response = server.respond(&with_key("/convert", "k1"));
+    assert_eq!(response.status, 200);
+    let output = body(response);
+    assert!(output.contains("camt.053.001.04"));
+    assert!(output.contains("UBSWCHZH80A"));
+
+    // By the path, for keys not bound to a tenant
+    let output = body(server.respond(&with_key("/tenants/ubs/convert", "k2")));
+    assert!(output.contains("UBSWCHZH80A"));
+    let output = body(server.respond(&with_key("/tenants/other/convert?version=04", "k2")));
+    assert!(output.contains("camt.053.001.04"));
+    assert!(output.contains("POFICHBEXXX"));
+    let output = body(server.respond(&with_key("/convert", "k2")));
+    assert!(output.contains("camt.053.001.08"));
+
+    assert_eq!(
+        server
+            .respond(&with_key("/tenants/other/convert", "k1"))
+            .status,
+        403
+    );
+    assert_eq!(
+        server
+            .respond(&with_key("/tenants/none/convert", "k2"))
+            .status,
+        404
+    );
+    assert_eq!(
+        server.respond(&with_key("/tenants/ubs/jobs", "k1")).status,
+        202
+    );
+}
+
+#[test]
+fn shows_jobs_only_to_the_key_that_submitted_them() {
+    let server = Server::new(ServerOptions {
+        api_keys: parse_api_keys("ubs:k1\nother:k2").unwrap(),
+        ..ServerOptions::default()
+    });
+    let with_key = |method: &str, target: &str, key: &str| {
+        let mut request = request(method, target);
+        request.body = DOCUMENT.as_bytes().to_vec();
+        request
+            .headers
+            .push(("X-API-Key".to_string(), key.to_string()));
+        request
+    };
+    let response = server.respond(&with_key("POST", "/jobs", "k1"));
+    assert_eq!(response.status, 202);
+    let submitted: Value = String::from_utf8(response.body).unwrap().parse().unwrap();
+    let id = submitted.get("id").and_then(Value::as_str).unwrap();
+
+    for (method, target) in [
+        ("GET", format!("/jobs/{}", id)),
+        ("GET", format!("/jobs/{}/result", id)),
+        ("DELETE", format!("/jobs/{}", id)),
+    ] {
+        assert_eq!(server.respond(&with_key(method, &target, "k2")).status, 404);
+    }
+    let target = format!("/jobs/{}", id);
+    assert_eq!(server.respond(&with_key("GET", &target, "k1")).status, 200);
+    assert_eq!(
+        server.respond(&with_key("DELETE", &target, "k1")).status,
+        204
+    );
+}
+
+#[test]
+fn rejects_invalid_tenant_files() {
+    let defaults = ServerOptions::default();
+    assert!(parse_tenants("ubs version=04", &defaults).is_err());
+    assert!(parse_tenants("u b s: version=04", &defaults).is_err());
+    assert!(parse_tenants("ubs: version=99", &defaults).is_err());
+    assert!(parse_tenants("ubs:\nubs: strict", &defaults).is_err());
+}
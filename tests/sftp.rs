@@ -0,0 +1,63 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::sftp::{RemoteFile, SftpUrl, is_statement_file, parse_listing};
+use chrono::NaiveDate;
+
+#[test]
+fn parses_urls() {
+    let url: SftpUrl = "sftp://bank@sftp.example.com:2222/outgoing/camt"
+        .parse()
+        .unwrap();
+    assert_eq!(url.user.as_deref(), Some("bank"));
+    assert_eq!(url.host, "sftp.example.com");
+    assert_eq!(url.port, Some(2222));
+    assert_eq!(url.path, "/outgoing/camt");
+    assert_eq!(
+        url.to_string(),
+        "sftp://bank@sftp.example.com:2222/outgoing/camt"
+    );
+
+    let url: SftpUrl = "sftp://sftp.example.com/~/outgoing".parse().unwrap();
+    assert_eq!(url.user, None);
+    assert_eq!(url.path, "./outgoing");
+    assert_eq!(url.to_string(), "sftp://sftp.example.com/~/outgoing");
+
+    assert!("https://example.com/".parse::<SftpUrl>().is_err());
+    assert!("sftp://bank@:22/".parse::<SftpUrl>().is_err());
+    assert!("sftp://host:port/".parse::<SftpUrl>().is_err());
+}
+
+#[test]
+fn reads_regular_files_of_listing() {
+    let listing = "sftp> ls -l \"/outgoing\"\n\
+        drwxr-xr-x    2 bank     bank         4096 Jan  3 08:00 archive\n\
+        -rw-r--r--    1 bank     bank         5311 Jan  2 07:15 camt 053 2025-01-01.xml\n\
+        -rw-r--r--    1 bank     bank          812 Dec 31 23:59 /outgoing/statement.xml.gz\n\
+        -rw-r--r--    1 bank     bank          300 Mar  1  2023 old.zip\n";
+    let today = NaiveDate::from_ymd_opt(2025, 1, 3).unwrap();
+    assert_eq!(
+        parse_listing(listing, today),
+        vec![
+            RemoteFile {
+                name: "camt 053 2025-01-01.xml".to_string(),
+                size: 5311,
+                modified: NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+            },
+            RemoteFile {
+                name: "statement.xml.gz".to_string(),
+                size: 812,
+                modified: NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+            },
+            RemoteFile {
+                name: "old.zip".to_string(),
+                size: 300,
+                modified: NaiveDate::from_ymd_opt(2023, 3, 1).unwrap(),
+            },
+        ]
+    );
+
+    assert!(is_statement_file("statement.XML"));
+    assert!(is_statement_file("statement.xml.pgp"));
+    assert!(!is_statement_file("readme.txt"));
+    assert!(!is_statement_file(".statement.xml.part"));
+}
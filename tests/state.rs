@@ -0,0 +1,57 @@
+#![cfg(feature = "fs")]
+
+use camtconvert::state::{ConversionState, STATE_FILE_NAME};
+use std::fs;
+
+#[test]
+fn remembers_converted_documents() {
+    let dir = std::env::temp_dir().join(format!("camtconvert-state-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let output = dir.join("statement_08.xml");
+    fs::write(&output, "<Document/>").unwrap();
+
+    let mut state = ConversionState::load(&dir).unwrap();
+    assert_eq!(
+        state.unchanged_output("a.zip:s.xml", "abc", "options"),
+        None
+    );
+    state.record("a.zip:s.xml", &output, "abc", "options");
+    state.save().unwrap();
+    assert!(dir.join(STATE_FILE_NAME).exists());
+
+    let mut state = ConversionState::load(&dir).unwrap();
+    assert_eq!(
+        state.unchanged_output("a.zip:s.xml", "abc", "options"),
+        Some(output.clone())
+    );
+    assert_eq!(
+        state.unchanged_output("a.zip:s.xml", "abd", "options"),
+        None
+    );
+    assert_eq!(state.unchanged_output("a.zip:s.xml", "abc", "other"), None);
+    assert_eq!(
+        state.unchanged_output("b.zip:s.xml", "abc", "options"),
+        None
+    );
+
+    // A new conversion replaces the earlier record
+    state.record("a.zip:s.xml", &output, "abd", "options");
+    assert_eq!(
+        state.unchanged_output("a.zip:s.xml", "abc", "options"),
+        None
+    );
+    assert!(
+        state
+            .unchanged_output("a.zip:s.xml", "abd", "options")
+            .is_some()
+    );
+
+    // Deleted outputs are converted again
+    fs::remove_file(&output).unwrap();
+    assert_eq!(
+        state.unchanged_output("a.zip:s.xml", "abd", "options"),
+        None
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,95 @@
+use camtconvert::convert::convert_bytes;
+use camtconvert::reader::ParseOptions;
+use camtconvert::stats::{ConversionStats, CountingReader};
+use camtconvert::writer::WriteOptions;
+use std::io::{BufRead, Read};
+
+const DOCUMENT: &str = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <ElctrncSeqNb>7</ElctrncSeqNb>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct>
+        <Id><IBAN>CH9300762011623852957</IBAN></Id>
+        <Svcr><FinInstnId><BICFI>UBSWCHZH80A</BICFI></FinInstnId></Svcr>
+      </Acct>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="CHF">100.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Dt><Dt>2025-06-01</Dt></Dt>
+      </Bal>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="CHF">107.5</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Dt><Dt>2025-06-30</Dt></Dt>
+      </Bal>
+      <Ntry>
+        <Amt Ccy="CHF">10.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">2.5</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-06</Dt></BookgDt>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn counts_what_was_converted() {
+    let options = WriteOptions {
+        recipient_bic: Some("POFICHBEXXX".to_string()),
+        ..WriteOptions::default()
+    };
+    let converted = convert_bytes(DOCUMENT.as_bytes(), &ParseOptions::default(), &options).unwrap();
+    let stats = &converted.stats;
+    assert_eq!(stats.balances, 2);
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.warnings, converted.warnings.len());
+    assert_eq!(stats.bytes_read, DOCUMENT.len() as u64);
+    assert_eq!(stats.bytes_written, converted.output.len() as u64);
+    assert_eq!(stats.totals["CHF"].credits.to_string(), "10.00");
+    assert_eq!(stats.totals["CHF"].debits.to_string(), "2.50");
+
+    let json = stats.to_json().to_string();
+    assert!(json.contains(
+        r#""totals":[{"currency":"CHF","entries":2,"credits":10.00,"debits":2.50,"net":7.50}]"#
+    ));
+    assert!(
+        stats
+            .to_string()
+            .starts_with("2 entries (CHF credits 10.00, debits 2.50), 2 balances, 0 warnings")
+    );
+}
+
+#[test]
+fn counts_one_in_the_singular() {
+    let stats = ConversionStats {
+        balances: 1,
+        entries: 1,
+        warnings: 1,
+        ..ConversionStats::default()
+    };
+    assert!(
+        stats
+            .to_string()
+            .starts_with("1 entry, 1 balance, 1 warning, 0 bytes read")
+    );
+}
+
+#[test]
+fn counts_bytes_read() {
+    let mut reader = CountingReader::new("first line\nsecond".as_bytes());
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    assert_eq!(reader.count(), 11);
+    let mut rest = String::new();
+    reader.read_to_string(&mut rest).unwrap();
+    assert_eq!(reader.count(), 17);
+}
@@ -0,0 +1,93 @@
+use camtconvert::model::Statement;
+use camtconvert::template::{NameTemplate, OutputLayout};
+use camtconvert::writer::OutputVersion;
+use chrono::DateTime;
+use std::path::Path;
+
+fn statement() -> Statement {
+    Statement {
+        id: "STMT-2025-06".to_string(),
+        creation_datetime: DateTime::parse_from_rfc3339("2025-06-22T17:33:43+02:00").ok(),
+        from_datetime: DateTime::parse_from_rfc3339("2025-06-01T00:00:00+02:00").ok(),
+        to_datetime: DateTime::parse_from_rfc3339("2025-06-20T23:59:59+02:00").ok(),
+        iban: "CH9300762011623852957".to_string(),
+        currency: "CHF".to_string(),
+        owner_name: "Müller / Partner AG".to_string(),
+        ..Statement::default()
+    }
+}
+
+#[test]
+fn fills_placeholders_from_statement() {
+    let template: NameTemplate = "{iban}_{from_date}_{to_date}_{version}.xml"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        template.render(&statement(), "input", OutputVersion::V08),
+        "CH9300762011623852957_2025-06-01_2025-06-20_08.xml"
+    );
+    assert_eq!(
+        template.render(&statement(), "input", OutputVersion::V04),
+        "CH9300762011623852957_2025-06-01_2025-06-20_04.xml"
+    );
+
+    let template: NameTemplate = "{owner} {id} {currency} {input}.xml".parse().unwrap();
+    assert_eq!(
+        template.render(&statement(), "wise", OutputVersion::V08),
+        "Müller _ Partner AG STMT-2025-06 CHF wise.xml"
+    );
+
+    // Without a period the creation date is used
+    let statement = Statement {
+        to_datetime: None,
+        owner_name: String::new(),
+        ..statement()
+    };
+    let template: NameTemplate = "{owner}_{to_date}.xml".parse().unwrap();
+    assert_eq!(
+        template.render(&statement, "input", OutputVersion::V08),
+        "unknown_2025-06-22.xml"
+    );
+}
+
+#[test]
+fn rejects_invalid_templates() {
+    for template in ["{bic}.xml", "{iban.xml", "out/{iban}.xml", ""] {
+        assert!(template.parse::<NameTemplate>().is_err(), "{}", template);
+    }
+}
+
+#[test]
+fn files_outputs_by_date_or_account() {
+    let statement = statement();
+    assert_eq!(OutputLayout::Flat.folder(&statement), Path::new(""));
+    assert_eq!(
+        OutputLayout::ByDate.folder(&statement),
+        Path::new("2025/06")
+    );
+    assert_eq!(
+        OutputLayout::ByAccount.folder(&statement),
+        Path::new("CH9300762011623852957/2025")
+    );
+
+    // Without a period, by the creation date; without an IBAN, as unknown
+    let statement = Statement {
+        from_datetime: None,
+        to_datetime: None,
+        iban: " ".to_string(),
+        creation_datetime: DateTime::parse_from_rfc3339("2024-12-31T23:00:00+01:00").ok(),
+        ..statement
+    };
+    assert_eq!(
+        OutputLayout::ByDate.folder(&statement),
+        Path::new("2024/12")
+    );
+    assert_eq!(
+        OutputLayout::ByAccount.folder(&statement),
+        Path::new("unknown/2024")
+    );
+    assert_eq!(
+        OutputLayout::ByDate.folder(&Statement::default()),
+        Path::new("unknown/unknown")
+    );
+}
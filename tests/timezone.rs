@@ -0,0 +1,91 @@
+use camtconvert::timezone::TimeZone;
+use chrono::NaiveDateTime;
+
+fn local(text: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S").unwrap()
+}
+
+fn convert(timezone: &TimeZone, text: &str) -> String {
+    timezone.from_local(local(text)).to_rfc3339()
+}
+
+#[test]
+fn applies_swiss_daylight_saving_time_by_default() {
+    let timezone = TimeZone::default();
+    assert_eq!(timezone.name(), "Europe/Zurich");
+    assert_eq!(
+        convert(&timezone, "2025-01-15T10:00:00"),
+        "2025-01-15T10:00:00+01:00"
+    );
+    assert_eq!(
+        convert(&timezone, "2025-06-20T23:59:59"),
+        "2025-06-20T23:59:59+02:00"
+    );
+    // Clocks go forward at 02:00 on the last Sunday of March
+    assert_eq!(
+        convert(&timezone, "2025-03-30T01:59:59"),
+        "2025-03-30T01:59:59+01:00"
+    );
+    assert_eq!(
+        convert(&timezone, "2025-03-30T02:30:00"),
+        "2025-03-30T02:30:00+01:00"
+    );
+    assert_eq!(
+        convert(&timezone, "2025-03-30T03:00:00"),
+        "2025-03-30T03:00:00+02:00"
+    );
+    // and back at 03:00 on the last Sunday of October, 02:30 happens twice
+    assert_eq!(
+        convert(&timezone, "2025-10-26T02:30:00"),
+        "2025-10-26T02:30:00+02:00"
+    );
+    assert_eq!(
+        convert(&timezone, "2025-10-26T03:00:00"),
+        "2025-10-26T03:00:00+01:00"
+    );
+}
+
+#[test]
+fn reads_fixed_offsets() {
+    for (text, expected) in [
+        ("+05:30", "2025-01-15T10:00:00+05:30"),
+        ("-0400", "2025-01-15T10:00:00-04:00"),
+        ("Z", "2025-01-15T10:00:00+00:00"),
+    ] {
+        let timezone: TimeZone = text.parse().unwrap();
+        assert_eq!(convert(&timezone, "2025-01-15T10:00:00"), expected);
+    }
+    assert!("../etc/passwd".parse::<TimeZone>().is_err());
+}
+
+#[test]
+#[cfg(feature = "fs")]
+fn reads_zones_of_time_zone_database() {
+    if !std::path::Path::new("/usr/share/zoneinfo/America/New_York").exists() {
+        return;
+    }
+    let timezone: TimeZone = "America/New_York".parse().unwrap();
+    assert_eq!(
+        convert(&timezone, "2025-01-15T10:00:00"),
+        "2025-01-15T10:00:00-05:00"
+    );
+    assert_eq!(
+        convert(&timezone, "2025-07-15T10:00:00"),
+        "2025-07-15T10:00:00-04:00"
+    );
+    // Beyond the transitions listed in the file, its rule applies
+    assert_eq!(
+        convert(&timezone, "2090-07-15T10:00:00"),
+        "2090-07-15T10:00:00-04:00"
+    );
+
+    let timezone: TimeZone = "Australia/Sydney".parse().unwrap();
+    assert_eq!(
+        convert(&timezone, "2025-01-15T10:00:00"),
+        "2025-01-15T10:00:00+11:00"
+    );
+    assert_eq!(
+        convert(&timezone, "2025-07-15T10:00:00"),
+        "2025-07-15T10:00:00+10:00"
+    );
+}
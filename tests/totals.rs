@@ -0,0 +1,78 @@
+use camtconvert::model::{Amount, CreditDebit, Transaction};
+use camtconvert::totals::CodeTotals;
+
+fn transaction(code: &str, value: &str, currency: &str, side: CreditDebit) -> Transaction {
+    Transaction {
+        amount: Amount {
+            value: value.parse().unwrap(),
+            currency: currency.to_string(),
+        },
+        credit_debit_ind: Some(side),
+        bank_tx_code: code.to_string(),
+        ..Transaction::default()
+    }
+}
+
+#[test]
+fn sums_entries_by_code_and_currency() {
+    let mut first = CodeTotals::new();
+    first
+        .add(&transaction("CARD-1", "12.5", "CHF", CreditDebit::Debit))
+        .unwrap();
+    first
+        .add(&transaction("FEE", "3.00", "CHF", CreditDebit::Debit))
+        .unwrap();
+    first
+        .add(&transaction("FEE", "1.00", "CHF", CreditDebit::Credit))
+        .unwrap();
+
+    let mut second = CodeTotals::new();
+    second
+        .add(&transaction("FEE", "2.00", "CHF", CreditDebit::Debit))
+        .unwrap();
+    second
+        .add(&transaction("FEE", "100", "JPY", CreditDebit::Debit))
+        .unwrap();
+    second
+        .add(&transaction(
+            "INT,\"Q2\"",
+            "0.40",
+            "CHF",
+            CreditDebit::Credit,
+        ))
+        .unwrap();
+    first.merge(&second).unwrap();
+
+    let by_currency = first.by_currency().unwrap();
+    assert_eq!(by_currency["CHF"].entries, 5);
+    assert_eq!(by_currency["CHF"].credits.to_string(), "1.40");
+    assert_eq!(by_currency["CHF"].debits.to_string(), "17.50");
+    assert_eq!(by_currency["JPY"].debits.to_string(), "100");
+
+    let mut csv = Vec::new();
+    first.write_csv(&mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "domain,family,sub_family,code,currency,entries,credits,debits,net\n\
+         PMNT,CCRD,POSD,CARD-1,CHF,1,0.00,12.50,-12.50\n\
+         PMNT,ICDT,ESCT,FEE,CHF,3,1.00,5.00,-4.00\n\
+         PMNT,ICDT,ESCT,FEE,JPY,1,0,100,-100\n\
+         PMNT,ICDT,ESCT,\"INT,\"\"Q2\"\"\",CHF,1,0.40,0.00,0.40\n"
+    );
+}
+
+#[test]
+fn reports_totals_too_large_to_compute() {
+    let huge = "99999999999999999999999999999999999999";
+    let mut totals = CodeTotals::new();
+    totals
+        .add(&transaction("FEE", huge, "CHF", CreditDebit::Debit))
+        .unwrap();
+    let error = totals
+        .add(&transaction("FEE", huge, "CHF", CreditDebit::Debit))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The total of the CHF entries is too large to compute"
+    );
+}
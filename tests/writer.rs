@@ -0,0 +1,586 @@
+use camtconvert::model::{
+    Amount, AmountDetail, CreditDebit, CreditorReference, CurrencyExchange, DateOrDateTime,
+    EntryStatus, Statement, Transaction, XmlElement, XmlNode,
+};
+use camtconvert::reader::{ParseOptions, parse_camt_10, stream_camt_10};
+use camtconvert::writer::{
+    DateFormat, Indent, MessageId, StreamWriter, WriteOptions, check_lengths, check_placeholders,
+    write_camt_04, write_camt_08,
+};
+use chrono::{DateTime, NaiveDate};
+
+fn statement_with_info(additional_info: &str) -> Statement {
+    let transaction = Transaction {
+        amount: Amount {
+            value: "10.00".parse().unwrap(),
+            currency: "CHF".to_string(),
+        },
+        credit_debit_ind: Some(CreditDebit::Credit),
+        booking_date: NaiveDate::from_ymd_opt(2025, 6, 5).map(DateOrDateTime::Date),
+        additional_info: additional_info.to_string(),
+        ..Transaction::default()
+    };
+    Statement {
+        id: "STMT-1".to_string(),
+        creation_datetime: DateTime::parse_from_rfc3339("2025-06-22T17:33:43Z").ok(),
+        iban: "CH9300762011623852957".to_string(),
+        currency: "CHF".to_string(),
+        owner_name: "Müller AG".to_string(),
+        transactions: vec![transaction],
+        ..Statement::default()
+    }
+}
+
+fn write_with(statement: &Statement, options: &WriteOptions) -> String {
+    let mut output = Vec::new();
+    write_camt_08(&mut output, statement, options).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+fn write(statement: &Statement) -> String {
+    write_with(statement, &WriteOptions::default())
+}
+
+#[test]
+fn escapes_text_once() {
+    let output = write(&statement_with_info("ACME & Co <AG> Zürich"));
+    assert!(output.contains("<AddtlNtryInf>ACME &amp; Co &lt;AG&gt; Zürich</AddtlNtryInf>"));
+    assert!(output.contains("<Ustrd>ACME &amp; Co &lt;AG&gt; Zürich</Ustrd>"));
+    assert!(output.contains("<Nm>Müller AG</Nm>"));
+    assert!(!output.contains("&amp;amp;"));
+}
+
+#[test]
+fn writes_indent_and_compact_layouts() {
+    let statement = statement_with_info("Payment");
+    assert!(write(&statement).contains("\n    <BkToCstmrStmt>\n        <GrpHdr>"));
+
+    let options = WriteOptions {
+        indent: Indent::Spaces(2),
+        ..WriteOptions::default()
+    };
+    assert!(write_with(&statement, &options).contains("\n  <BkToCstmrStmt>\n    <GrpHdr>"));
+
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&statement, &options);
+    assert!(!output.contains('\n'));
+    assert!(output.contains("<BkToCstmrStmt><GrpHdr><MsgId>STMT-1</MsgId>"));
+}
+
+#[test]
+fn streaming_matches_buffered_output() {
+    let input = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id></Acct>
+      <Ntry>
+        <Amt Ccy="CHF">10.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <BookgDt><Dt>2025-06-05</Dt></BookgDt>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">2.50</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <AddtlNtryInf>Fee</AddtlNtryInf>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+    let options = ParseOptions::default();
+
+    let statement = parse_camt_10(input.as_bytes(), &options).unwrap().statement;
+    let buffered = write(&statement);
+
+    let mut streamed = Vec::new();
+    let mut writer = StreamWriter::new(&mut streamed, WriteOptions::default());
+    let parsed = stream_camt_10(input.as_bytes(), &options, &mut writer).unwrap();
+    assert!(parsed.statement.transactions.is_empty());
+
+    assert_eq!(String::from_utf8(streamed).unwrap(), buffered);
+}
+
+#[test]
+fn writes_dates_in_configured_format() {
+    let mut statement = statement_with_info("Payment");
+    let booked = DateTime::parse_from_rfc3339("2025-06-06T10:12:00+02:00").ok();
+    let mut booked_at = statement.transactions[0].clone();
+    booked_at.booking_date = booked.map(DateOrDateTime::DateTime);
+    statement.transactions.push(booked_at);
+
+    let output = write(&statement);
+    assert!(output.contains("<BookgDt>\n                    <Dt>2025-06-05</Dt>"));
+    assert!(output.contains("<BookgDt>\n                    <Dt>2025-06-06</Dt>"));
+
+    let options = WriteOptions {
+        date_format: DateFormat::Input,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&statement, &options);
+    assert!(output.contains("<Dt>2025-06-05</Dt>"));
+    assert!(output.contains("<DtTm>2025-06-06T10:12:00+02:00</DtTm>"));
+
+    let options = WriteOptions {
+        date_format: DateFormat::DateTime,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&statement, &options);
+    assert!(output.contains("<DtTm>2025-06-05T00:00:00+02:00</DtTm>"));
+    assert!(output.contains("<DtTm>2025-06-06T10:12:00+02:00</DtTm>"));
+}
+
+#[test]
+fn writes_amounts_with_minor_units_of_currency() {
+    let mut statement = statement_with_info("Payment");
+    let transaction = statement.transactions[0].clone();
+    statement.transactions.clear();
+    for (value, currency) in [
+        ("10.5", "CHF"),
+        ("1200.00", "JPY"),
+        ("7", "KWD"),
+        ("1.005", "EUR"),
+        ("2.5", "XAU"),
+    ] {
+        let mut transaction = transaction.clone();
+        transaction.amount = Amount {
+            value: value.parse().unwrap(),
+            currency: currency.to_string(),
+        };
+        statement.transactions.push(transaction);
+    }
+
+    let output = write(&statement);
+    for amount in [
+        r#"<Amt Ccy="CHF">10.50</Amt>"#,
+        r#"<Amt Ccy="JPY">1200</Amt>"#,
+        r#"<Amt Ccy="KWD">7.000</Amt>"#,
+        // Values that do not fit the currency are kept as they are
+        r#"<Amt Ccy="EUR">1.005</Amt>"#,
+        r#"<Amt Ccy="XAU">2.5</Amt>"#,
+    ] {
+        assert!(output.contains(amount), "{}", amount);
+    }
+}
+
+#[test]
+fn writes_value_date_of_input() {
+    let mut statement = statement_with_info("Interest");
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    // Entries without value date get their booking date
+    let output = write_with(&statement, &options);
+    assert!(output.contains("<ValDt><Dt>2025-06-05</Dt></ValDt>"));
+
+    statement.transactions[0].value_date =
+        NaiveDate::from_ymd_opt(2025, 6, 3).map(DateOrDateTime::Date);
+    let output = write_with(&statement, &options);
+    assert!(
+        output.contains("<BookgDt><Dt>2025-06-05</Dt></BookgDt><ValDt><Dt>2025-06-03</Dt></ValDt>")
+    );
+}
+
+#[test]
+fn writes_additional_transaction_info() {
+    let mut statement = statement_with_info("");
+    statement.transactions[0].transaction_info = "Gutschrift Lohn".to_string();
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&statement, &options);
+    assert!(output.contains("<AddtlTxInf>Gutschrift Lohn</AddtlTxInf></TxDtls>"));
+}
+
+#[test]
+fn writes_cheque_number_and_related_dates() {
+    let mut statement = statement_with_info("");
+    let transaction = &mut statement.transactions[0];
+    transaction.cheque_number = "000123".to_string();
+    transaction.related_dates.acceptance =
+        DateTime::parse_from_rfc3339("2025-06-04T09:30:00+02:00").ok();
+    transaction.related_dates.interbank_settlement = NaiveDate::from_ymd_opt(2025, 6, 5);
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&statement, &options);
+    assert!(output.contains("<ChqNb>000123</ChqNb></Refs>"));
+    assert!(output.contains(
+        "<RltdDts><AccptncDtTm>2025-06-04T09:30:00+02:00</AccptncDtTm>\
+         <IntrBkSttlmDt>2025-06-05</IntrBkSttlmDt></RltdDts></TxDtls>"
+    ));
+}
+
+#[test]
+fn writes_supplementary_data_of_transaction_details() {
+    let mut statement = statement_with_info("");
+    statement.transactions[0].supplementary_data = vec![XmlElement {
+        name: "SplmtryData".to_string(),
+        namespace: None,
+        attributes: Vec::new(),
+        children: vec![XmlNode::Text("4711".to_string())],
+    }];
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&statement, &options);
+    assert!(output.contains("<SplmtryData>4711</SplmtryData></TxDtls>"));
+}
+
+#[test]
+fn writes_servicer_bic() {
+    let mut statement = statement_with_info("Payment");
+    statement.sequence_number = "7".to_string();
+    let output = write(&statement);
+    assert!(output.contains("<BICFI>XXXXXXXX</BICFI>"));
+    assert!(output.contains("<Nm>Bank</Nm>"));
+
+    let options = WriteOptions {
+        strict: true,
+        recipient_bic: Some("UBSWCHZH80A".to_string()),
+        ..WriteOptions::default()
+    };
+    let mut output = Vec::new();
+    assert!(write_camt_08(&mut output, &statement, &options).is_err());
+
+    // The generic name and identification only go with the placeholder
+    statement.servicer_bic = "UBSWCHZH".to_string();
+    let output = write_with(&statement, &options);
+    assert!(output.contains("<BICFI>UBSWCHZH</BICFI>"));
+    assert!(!output.contains("<Nm>Bank</Nm>"));
+
+    let options = WriteOptions {
+        servicer_bic: Some("POFICHBEXXX".to_string()),
+        ..options
+    };
+    assert!(write_with(&statement, &options).contains("<BICFI>POFICHBEXXX</BICFI>"));
+}
+
+#[test]
+fn references_the_schema_of_the_output_version() {
+    let statement = statement_with_info("Payment");
+    let options = WriteOptions {
+        schema_location: Some(String::new()),
+        ..WriteOptions::default()
+    };
+    assert!(write_with(&statement, &options).contains(
+        r#"xsi:schemaLocation="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08 camt.053.001.08.xsd""#
+    ));
+    let mut output = Vec::new();
+    write_camt_04(&mut output, &statement, &options).unwrap();
+    assert!(String::from_utf8(output).unwrap().contains(
+        r#"xsi:schemaLocation="urn:iso:std:iso:20022:tech:xsd:camt.053.001.04 camt.053.001.04.xsd""#
+    ));
+
+    let options = WriteOptions {
+        schema_location: Some("https://example.com/camt.xsd".to_string()),
+        ..WriteOptions::default()
+    };
+    assert!(
+        write_with(&statement, &options).contains("camt.053.001.08 https://example.com/camt.xsd\"")
+    );
+}
+
+#[test]
+fn writes_recipient_bic() {
+    let statement = statement_with_info("Payment");
+    assert!(write(&statement).contains("<AnyBIC>XXXXXXXX</AnyBIC>"));
+
+    let options = WriteOptions {
+        recipient_bic: Some("UBSWCHZH80A".to_string()),
+        ..WriteOptions::default()
+    };
+    assert!(write_with(&statement, &options).contains("<AnyBIC>UBSWCHZH80A</AnyBIC>"));
+}
+
+#[test]
+fn writes_recipient_of_input() {
+    let input = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <GrpHdr>
+      <MsgId>MSG-1</MsgId>
+      <MsgRcpt><Nm>Muster AG</Nm><Id><OrgId><AnyBIC>MUSTCHZZ</AnyBIC></OrgId></Id></MsgRcpt>
+    </GrpHdr>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id></Acct>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+    let parsed = parse_camt_10(input.as_bytes(), &ParseOptions::default()).unwrap();
+    assert!(!parsed.dropped.keys().any(|path| path.contains("MsgRcpt")));
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&parsed.statement, &options);
+    assert!(output.contains(
+        "<MsgRcpt><Nm>Muster AG</Nm><Id><OrgId><AnyBIC>MUSTCHZZ</AnyBIC></OrgId></Id></MsgRcpt>"
+    ));
+    let placeholders = check_placeholders(&parsed.statement, &options).unwrap();
+    assert!(!placeholders.iter().any(|p| p.name == "recipient BIC"));
+
+    // An option replaces it
+    let options = WriteOptions {
+        recipient_bic: Some("UBSWCHZH80A".to_string()),
+        ..options
+    };
+    let output = write_with(&parsed.statement, &options);
+    assert!(
+        output.contains("<MsgRcpt><Id><OrgId><AnyBIC>UBSWCHZH80A</AnyBIC></OrgId></Id></MsgRcpt>")
+    );
+}
+
+#[test]
+fn writes_message_id_of_strategy() {
+    let input = r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.10">
+  <BkToCstmrStmt>
+    <GrpHdr><MsgId>MSG-1</MsgId><AddtlInf>SPS/2.1/PROD</AddtlInf></GrpHdr>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <CreDtTm>2025-06-22T17:33:43Z</CreDtTm>
+      <Acct><Id><IBAN>CH9300762011623852957</IBAN></Id></Acct>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>"#;
+    let parsed = parse_camt_10(input.as_bytes(), &ParseOptions::default()).unwrap();
+    assert_eq!(parsed.statement.message_info, "SPS/2.1/PROD");
+    let message_id = |message_id: &str| {
+        let options = WriteOptions {
+            indent: Indent::Compact,
+            message_id: message_id.parse().unwrap(),
+            ..WriteOptions::default()
+        };
+        let output = write_with(&parsed.statement, &options);
+        let start = output.find("<MsgId>").unwrap() + "<MsgId>".len();
+        let end = output.find("</MsgId>").unwrap();
+        output[start..end].to_string()
+    };
+    assert_eq!(message_id("statement"), "STMT-1");
+    assert_eq!(message_id("input"), "MSG-1");
+    assert_eq!(
+        message_id("{iban}-{created_date}"),
+        "CH9300762011623852957-2025-06-22"
+    );
+    let uuid = message_id("uuid");
+    assert_eq!(uuid.len(), 32);
+    assert!(uuid.bytes().all(|b| b.is_ascii_hexdigit()));
+    assert_eq!(&uuid[12..13], "4");
+    assert_ne!(uuid, message_id("uuid"));
+
+    // Without an input MsgId the statement Id is written, and long ids are
+    // cut to the 35 characters of the schema
+    let mut statement = parsed.statement.clone();
+    statement.message_id.clear();
+    statement.id = "S".repeat(40);
+    assert_eq!(
+        MessageId::Input.render(&statement, Default::default()),
+        "S".repeat(35)
+    );
+
+    assert!("random".parse::<MessageId>().is_err());
+    assert!("{nope}".parse::<MessageId>().is_err());
+}
+
+#[test]
+fn writes_additional_info_of_input() {
+    let mut statement = statement_with_info("Fee");
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    assert!(write_with(&statement, &options).contains("<AddtlInf>SPS/2.1</AddtlInf>"));
+
+    statement.message_info = "SPS/2.2/PROD".to_string();
+    assert!(write_with(&statement, &options).contains("<AddtlInf>SPS/2.2/PROD</AddtlInf>"));
+    let mut output = Vec::new();
+    write_camt_04(&mut output, &statement, &options).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("</MsgPgntn><AddtlInf>SPS/2.2/PROD</AddtlInf></GrpHdr>"));
+}
+
+#[test]
+fn writes_version_04() {
+    let mut statement = statement_with_info("Fee");
+    let mut output = Vec::new();
+    write_camt_04(&mut output, &statement, &WriteOptions::default()).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains(r#"xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.04""#));
+    assert!(output.contains("<Sts>BOOK</Sts>"));
+    assert!(!output.contains("<AddtlInf>"));
+
+    // Future entries have no status in version 04
+    statement.transactions[0].status = EntryStatus::Future;
+    let mut output = Vec::new();
+    assert!(write_camt_04(&mut output, &statement, &WriteOptions::default()).is_err());
+}
+
+#[test]
+fn writes_remittance_information_and_debtor() {
+    let mut statement = statement_with_info("Payment");
+    let transaction = &mut statement.transactions[0];
+    transaction.remittance_info = vec!["Invoice 17".to_string()];
+    transaction.creditor_reference = Some(CreditorReference {
+        kind: "QRR".to_string(),
+        reference: "210000000003139471430009017".to_string(),
+    });
+    transaction.debtor_name = "Hans Meier".to_string();
+    transaction.creditor_name = "Müller AG".to_string();
+
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&statement, &options);
+    assert!(output.contains(
+        "<RltdPties><Dbtr><Pty><Nm>Hans Meier</Nm></Pty></Dbtr>\
+         <Cdtr><Pty><Nm>Müller AG</Nm></Pty></Cdtr></RltdPties>"
+    ));
+    assert!(output.contains("<Ustrd>Invoice 17</Ustrd>"));
+    assert!(!output.contains("<Ustrd>Payment</Ustrd>"));
+    assert!(output.contains(
+        "<CdtrRefInf><Tp><CdOrPrtry><Prtry>QRR</Prtry></CdOrPrtry></Tp>\
+         <Ref>210000000003139471430009017</Ref></CdtrRefInf>"
+    ));
+
+    // Version 04 names the debtor and creditor directly
+    let mut output = Vec::new();
+    write_camt_04(&mut output, &statement, &options).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("<Dbtr><Nm>Hans Meier</Nm></Dbtr><Cdtr><Nm>Müller AG</Nm></Cdtr>"));
+}
+
+#[test]
+fn writes_amount_details() {
+    let amount = |value: &str, currency: &str| Amount {
+        value: value.parse().unwrap(),
+        currency: currency.to_string(),
+    };
+    let mut statement = statement_with_info("Payment");
+    let transaction = &mut statement.transactions[0];
+    transaction.amount = amount("1200", "JPY");
+    transaction.amount_details.instructed = Some(AmountDetail {
+        amount: amount("1200", "JPY"),
+        exchange: Some(CurrencyExchange {
+            source_currency: "JPY".to_string(),
+            target_currency: "CHF".to_string(),
+            unit_currency: String::new(),
+            rate: "0.00575".parse().unwrap(),
+        }),
+    });
+    transaction.amount_details.counter_value = Some(AmountDetail {
+        amount: amount("6.9", "CHF"),
+        exchange: None,
+    });
+
+    let options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    let output = write_with(&statement, &options);
+    assert!(output.contains(r#"<Ntry><Amt Ccy="JPY">1200</Amt>"#));
+    assert!(output.contains(
+        r#"</BkTxCd><AmtDtls><InstdAmt><Amt Ccy="JPY">1200</Amt><CcyXchg><SrcCcy>JPY</SrcCcy><TrgtCcy>CHF</TrgtCcy><XchgRate>0.00575</XchgRate></CcyXchg></InstdAmt><CntrValAmt><Amt Ccy="CHF">6.90</Amt></CntrValAmt></AmtDtls><NtryDtls>"#
+    ));
+    assert!(output.contains(r#"<TxDtls><Refs>"#));
+    assert!(output.contains(r#"</Refs><Amt Ccy="JPY">1200</Amt>"#));
+}
+
+#[test]
+fn cuts_values_to_schema_length() {
+    let mut statement = statement_with_info(&"Fee ".repeat(150));
+    statement.owner_name = "Müller".repeat(30);
+    statement.transactions[0].end_to_end_id = "E2E-2025-06-05-000000000000000000001".to_string();
+
+    let truncations = check_lengths(&statement, &WriteOptions::default()).unwrap();
+    let messages: Vec<String> = truncations.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "Value of 180 characters cut to the 140 allowed at Stmt/Acct/Ownr/Nm",
+            "Value of 36 characters cut to the 35 allowed at Ntry/NtryDtls/TxDtls/Refs/EndToEndId",
+            "Value of 600 characters cut to the 140 allowed at Ntry/NtryDtls/TxDtls/RmtInf/Ustrd",
+            "Value of 600 characters cut to the 500 allowed at Ntry/AddtlNtryInf",
+        ]
+    );
+
+    let output = write(&statement);
+    assert!(output.contains(&format!("<Nm>{}</Nm>", "Müller".repeat(23) + "Mü")));
+    assert!(output.contains("<EndToEndId>E2E-2025-06-05-00000000000000000000</EndToEndId>"));
+    assert!(output.contains(&format!("<Ustrd>{}</Ustrd>", "Fee ".repeat(35))));
+    assert!(output.contains(&format!(
+        "<AddtlNtryInf>{}</AddtlNtryInf>",
+        "Fee ".repeat(125)
+    )));
+
+    // Fails instead with --strict
+    let options = WriteOptions {
+        strict: true,
+        recipient_bic: Some("UBSWCHZH80A".to_string()),
+        servicer_bic: Some("UBSWCHZH80A".to_string()),
+        ..WriteOptions::default()
+    };
+    statement.sequence_number = "1".to_string();
+    let error = check_lengths(&statement, &options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Value of 180 characters is longer than the 140 allowed in Stmt/Acct/Ownr/Nm"
+    );
+    assert!(write_camt_08(&mut Vec::new(), &statement, &options).is_err());
+}
+
+#[test]
+fn writes_esr_references_when_asked() {
+    let statement = statement_with_info("ESR 01-162-8 21 00000 00003 13947 14300 09017");
+    let mut options = WriteOptions {
+        indent: Indent::Compact,
+        ..WriteOptions::default()
+    };
+    assert!(!write_with(&statement, &options).contains("<CdtrRefInf>"));
+
+    options.esr_references = true;
+    let output = write_with(&statement, &options);
+    assert!(output.contains(
+        "<RltdPties><CdtrAcct><Id><Othr><Id>010001628</Id></Othr></Id></CdtrAcct></RltdPties>"
+    ));
+    assert!(output.contains(
+        "<CdtrRefInf><Tp><CdOrPrtry><Prtry>QRR</Prtry></CdOrPrtry></Tp>\
+         <Ref>210000000003139471430009017</Ref></CdtrRefInf>"
+    ));
+}
+
+#[test]
+fn lists_placeholders() {
+    let mut statement = statement_with_info("Payment");
+    let mut options = WriteOptions::default();
+    let placeholders: Vec<String> = check_placeholders(&statement, &options)
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        placeholders,
+        vec![
+            "Placeholder XXXXXXXX written as recipient BIC at GrpHdr/MsgRcpt/Id/OrgId/AnyBIC",
+            "Placeholder 1 written as electronic sequence number at Stmt/ElctrncSeqNb",
+            "Placeholder XXXXXXXX written as servicer BIC at Stmt/Acct/Svcr/FinInstnId/BICFI",
+            "Placeholder Bank written as servicer name at Stmt/Acct/Svcr/FinInstnId/Nm",
+            "Placeholder XXX-000.000.000 written as servicer identification at Stmt/Acct/Svcr/FinInstnId/Othr/Id",
+        ]
+    );
+
+    statement.sequence_number = "7".to_string();
+    statement.servicer_bic = "UBSWCHZH80A".to_string();
+    options.recipient_bic = Some("POFICHBEXXX".to_string());
+    options.strict = true;
+    assert!(check_placeholders(&statement, &options).unwrap().is_empty());
+    assert!(write_with(&statement, &options).contains("<ElctrncSeqNb>7</ElctrncSeqNb>"));
+}
@@ -0,0 +1,136 @@
+use camtconvert::zip::{ZipArchive, is_zip};
+use std::io::{Cursor, ErrorKind, Read};
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Zip archive of stored (uncompressed) members
+fn zip_stored(members: &[(&str, &[u8], u32)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data, crc) in members {
+        let offset = archive.len() as u32;
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        fields.extend_from_slice(&crc.to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&[0, 0]);
+
+        archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        archive.extend_from_slice(&fields);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        directory.extend_from_slice(&[20, 0]);
+        directory.extend_from_slice(&fields);
+        // Comment length, disk number and attributes
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&(members.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(members.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&[0, 0]);
+    archive
+}
+
+#[test]
+fn reads_members_of_archive() {
+    let first = b"<Document>first</Document>".as_slice();
+    let second = b"<Document>second</Document>".as_slice();
+    let input = zip_stored(&[
+        ("2025/", b"", 0),
+        ("2025/first.xml", first, crc32(first)),
+        ("second.xml", second, crc32(second)),
+    ]);
+    assert!(is_zip(&input));
+
+    let mut archive = ZipArchive::new(Cursor::new(input)).unwrap();
+    let names: Vec<&str> = archive.entries().iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["2025/", "2025/first.xml", "second.xml"]);
+    assert!(archive.entries()[0].is_dir());
+
+    for (index, expected) in [(1, first), (2, second)] {
+        let mut content = Vec::new();
+        archive
+            .open(index)
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, expected);
+    }
+}
+
+#[test]
+fn rejects_member_with_wrong_checksum() {
+    let data = b"<Document/>".as_slice();
+    let input = zip_stored(&[("bad.xml", data, crc32(data) ^ 1)]);
+
+    let mut archive = ZipArchive::new(Cursor::new(input)).unwrap();
+    let mut content = Vec::new();
+    let error = archive
+        .open(0)
+        .unwrap()
+        .read_to_end(&mut content)
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(error.to_string().contains("checksum"), "{}", error);
+}
+
+#[test]
+fn names_documents_by_their_file_name() {
+    let names = [
+        "2025/",
+        "2025/june.xml",
+        "2025\\july.XML",
+        "readme.txt",
+        "__MACOSX/2025/._june.xml",
+        "._june.xml",
+        "../escape.xml",
+        "2025\\..\\..\\escape.xml",
+        "2025/..\\escape.xml",
+    ];
+    let members: Vec<(&str, &[u8], u32)> = names.iter().map(|name| (*name, &b""[..], 0)).collect();
+    let archive = ZipArchive::new(Cursor::new(zip_stored(&members))).unwrap();
+    let documents: Vec<Option<&str>> = archive
+        .entries()
+        .iter()
+        .map(|entry| entry.document_name())
+        .collect();
+    assert_eq!(
+        documents,
+        [
+            None,
+            Some("june.xml"),
+            Some("july.XML"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None
+        ]
+    );
+}